@@ -0,0 +1,44 @@
+// Captures the TFHE-rs version this crate is built against as `SAT_FHE_TFHE_VERSION`,
+// recorded in every artifact header (see `compat`). The dependency is unpinned, so the
+// version is read from the lock file of the build: the nearest `Cargo.lock` above the
+// output directory, which sits in the target directory of whatever workspace is
+// building us. Builds without one record "unknown".
+
+use std::path::{Path, PathBuf};
+
+fn lock_file() -> Option<PathBuf> {
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR")?);
+    let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR")?);
+    out_dir
+        .ancestors()
+        .chain(manifest_dir.ancestors())
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+fn tfhe_version(lock: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(lock).ok()?;
+    let mut lines = text.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line == "name = \"tfhe\"" {
+            let version = lines.next()?.strip_prefix("version = \"")?;
+            return Some(version.trim_end_matches('"').to_string());
+        }
+    }
+    None
+}
+
+fn main() {
+    let version = match lock_file() {
+        Some(lock) => {
+            println!("cargo:rerun-if-changed={}", lock.display());
+            tfhe_version(&lock)
+        }
+        None => None,
+    };
+    println!(
+        "cargo:rustc-env=SAT_FHE_TFHE_VERSION={}",
+        version.as_deref().unwrap_or("unknown")
+    );
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,174 @@
+// The canonical plaintext encoding, pinned down so independently written clients encode
+// positions bit for bit alike.
+//
+// A position is three components in meters, in the order x, y, z, expressed in the
+// session's frame. Each component is
+//
+//   1. scaled to steps of `units` (divided by `units.meters()`),
+//   2. rounded to the nearest integer, halves away from zero,
+//   3. offset by `offset` (2^31 canonically, so negative components fit), and
+//   4. stored as a `u32`; components that don't fit after the offset are an error.
+//
+// Serialized, a trajectory is its steps in order, each the three `u32`s x, y, z in
+// `byte_order` (little-endian canonically), with no header or padding: 12 bytes a step.
+// `decode` and `from_bytes` invert `encode` and `to_bytes`, up to the rounding.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::SatelliteData;
+use crate::frame::Frame;
+use crate::units::{BIAS, CANONICAL_UNITS, Units};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanonicalEncoding {
+    // Unit of one integer step.
+    pub units: Units,
+    // Added to the rounded step count of every component.
+    pub offset: i64,
+    pub byte_order: ByteOrder,
+}
+
+// The encoding `SatelliteData::encode` produces.
+pub const CANONICAL: CanonicalEncoding = CanonicalEncoding {
+    units: CANONICAL_UNITS,
+    offset: BIAS,
+    byte_order: ByteOrder::LittleEndian,
+};
+
+// Bytes of one serialized step.
+pub const STEP_BYTES: usize = 12;
+
+impl Default for CanonicalEncoding {
+    fn default() -> Self {
+        CANONICAL
+    }
+}
+
+impl CanonicalEncoding {
+    // Encodes one component given in meters.
+    pub fn encode_component(&self, meters: f64) -> Result<u32, Box<dyn Error>> {
+        // `libm::round` rounds halves away from zero.
+        let cell = libm::round(meters / self.units.meters()) + self.offset as f64;
+        if !(0.0..=u32::MAX as f64).contains(&cell) {
+            return Err(format!(
+                "{} m doesn't fit the encoding in {:?} with offset {}",
+                meters, self.units, self.offset
+            )
+            .into());
+        }
+        Ok(cell as u32)
+    }
+
+    // Inverse of `encode_component`, in meters.
+    pub fn decode_component(&self, cell: u32) -> f64 {
+        (cell as i64 - self.offset) as f64 * self.units.meters()
+    }
+
+    pub fn encode(&self, position: [f64; 3]) -> Result<[u32; 3], Box<dyn Error>> {
+        Ok([
+            self.encode_component(position[0])?,
+            self.encode_component(position[1])?,
+            self.encode_component(position[2])?,
+        ])
+    }
+
+    pub fn decode(&self, cells: [u32; 3]) -> [f64; 3] {
+        cells.map(|cell| self.decode_component(cell))
+    }
+
+    // Encodes positions in meters, one per step, expressed in `frame`.
+    pub fn encode_trajectory(
+        &self,
+        positions: &[[f64; 3]],
+        frame: Frame,
+    ) -> Result<SatelliteData, Box<dyn Error>> {
+        let mut data = SatelliteData {
+            x: Vec::with_capacity(positions.len()),
+            y: Vec::with_capacity(positions.len()),
+            z: Vec::with_capacity(positions.len()),
+            frame,
+            units: self.units,
+        };
+        for &position in positions {
+            let [x, y, z] = self.encode(position)?;
+            data.x.push(x);
+            data.y.push(y);
+            data.z.push(z);
+        }
+        Ok(data)
+    }
+
+    pub fn decode_trajectory(&self, data: &SatelliteData) -> Vec<[f64; 3]> {
+        (0..data.x.len())
+            .map(|i| self.decode([data.x[i], data.y[i], data.z[i]]))
+            .collect()
+    }
+
+    pub fn to_bytes(&self, data: &SatelliteData) -> Result<Vec<u8>, Box<dyn Error>> {
+        if data.units != self.units {
+            return Err(format!(
+                "trajectory is in {:?}, the encoding in {:?}",
+                data.units, self.units
+            )
+            .into());
+        }
+        if data.y.len() != data.x.len() || data.z.len() != data.x.len() {
+            return Err("trajectory axes have different lengths".into());
+        }
+        let mut out = Vec::with_capacity(data.x.len() * STEP_BYTES);
+        for i in 0..data.x.len() {
+            for cell in [data.x[i], data.y[i], data.z[i]] {
+                out.extend_from_slice(&match self.byte_order {
+                    ByteOrder::LittleEndian => cell.to_le_bytes(),
+                    ByteOrder::BigEndian => cell.to_be_bytes(),
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    // Reads what `to_bytes` wrote; the frame isn't part of the layout.
+    pub fn from_bytes(&self, bytes: &[u8], frame: Frame) -> Result<SatelliteData, Box<dyn Error>> {
+        if !bytes.len().is_multiple_of(STEP_BYTES) {
+            return Err(format!(
+                "{} bytes aren't a whole number of {}-byte steps",
+                bytes.len(),
+                STEP_BYTES
+            )
+            .into());
+        }
+        let steps = bytes.len() / STEP_BYTES;
+        let mut data = SatelliteData {
+            x: Vec::with_capacity(steps),
+            y: Vec::with_capacity(steps),
+            z: Vec::with_capacity(steps),
+            frame,
+            units: self.units,
+        };
+        for step in bytes.chunks_exact(STEP_BYTES) {
+            let mut cells = step.chunks_exact(4).map(|word| {
+                let word = [word[0], word[1], word[2], word[3]];
+                match self.byte_order {
+                    ByteOrder::LittleEndian => u32::from_le_bytes(word),
+                    ByteOrder::BigEndian => u32::from_be_bytes(word),
+                }
+            });
+            data.x.extend(cells.next());
+            data.y.extend(cells.next());
+            data.z.extend(cells.next());
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,61 @@
+// Encoded plaintext trajectories.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::frame::Frame;
+use crate::units::{self, CANONICAL_UNITS, Units};
+
+// Struct to group satellite trajectory data.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SatelliteData {
+    pub x: Vec<u32>,
+    pub y: Vec<u32>,
+    pub z: Vec<u32>,
+    // Reference frame the coordinates were expressed in before encoding.
+    pub frame: Frame,
+    // Unit of one integer step of the coordinates.
+    pub units: Units,
+}
+
+impl SatelliteData {
+    // Fixed-point encodes `positions` (given in `units`) into `CANONICAL_UNITS`.
+    pub fn encode(
+        positions: &[[f64; 3]],
+        units: Units,
+        frame: Frame,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut data = SatelliteData {
+            x: Vec::with_capacity(positions.len()),
+            y: Vec::with_capacity(positions.len()),
+            z: Vec::with_capacity(positions.len()),
+            frame,
+            units: CANONICAL_UNITS,
+        };
+        for &[x, y, z] in positions {
+            data.x.push(units::encode(x, units)?);
+            data.y.push(units::encode(y, units)?);
+            data.z.push(units::encode(z, units)?);
+        }
+        Ok(data)
+    }
+
+    // The same trajectory with its coordinates rescaled to `units`.
+    pub fn to_units(&self, units: Units) -> Result<Self, Box<dyn Error>> {
+        let rescale_axis = |axis: &[u32]| -> Result<Vec<u32>, Box<dyn Error>> {
+            axis.iter()
+                .map(|&v| units::rescale(v, self.units, units))
+                .collect()
+        };
+        Ok(SatelliteData {
+            x: rescale_axis(&self.x)?,
+            y: rescale_axis(&self.y)?,
+            z: rescale_axis(&self.z)?,
+            frame: self.frame,
+            units,
+        })
+    }
+}
@@ -0,0 +1,90 @@
+// Parameters a trajectory is quantized with: units, grid cell and the time grid of the
+// screening window. Both parties have to use the same ones (see the main crate's
+// `negotiation`).
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::SatelliteData;
+use crate::units::{Units, bias, unbias};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingParams {
+    // Unit of one integer step of an encoded coordinate.
+    pub units: Units,
+    // Grid cell edge in `units`: coordinates are multiples of it (counted from the
+    // encoding's zero). 1 means no grid beyond the units.
+    pub cell_size: u32,
+    // Seconds between consecutive steps.
+    pub time_step_s: u64,
+    // Screening window `[window_start, window_end)`, in epoch seconds.
+    pub window_start: u64,
+    pub window_end: u64,
+}
+
+impl EncodingParams {
+    // Number of steps in the window.
+    pub fn steps(&self) -> usize {
+        (self.window_end.saturating_sub(self.window_start) / self.time_step_s.max(1)) as usize
+    }
+
+    // Epoch of every step in the window.
+    pub fn epochs(&self) -> Vec<u64> {
+        (0..self.steps() as u64)
+            .map(|i| self.window_start + i * self.time_step_s)
+            .collect()
+    }
+
+    // `data` snapped to the nearest grid cell on every axis.
+    pub fn quantize(&self, data: &SatelliteData) -> SatelliteData {
+        let cell = self.cell_size.max(1) as i64;
+        let snap = |axis: &[u32]| {
+            axis.iter()
+                .map(|&v| {
+                    let snapped = libm::round(unbias(v) as f64 / cell as f64) as i64 * cell;
+                    bias(snapped.clamp(i32::MIN as i64, i32::MAX as i64)).unwrap_or(v)
+                })
+                .collect()
+        };
+        SatelliteData {
+            x: snap(&data.x),
+            y: snap(&data.y),
+            z: snap(&data.z),
+            frame: data.frame,
+            units: data.units,
+        }
+    }
+
+    // Checks that `data`, sampled at `epochs`, is encoded with these parameters.
+    pub fn check(&self, data: &SatelliteData, epochs: &[u64]) -> Result<(), Box<dyn Error>> {
+        if data.units != self.units {
+            return Err(format!(
+                "trajectory is in {:?}, the session encodes {:?}",
+                data.units, self.units
+            )
+            .into());
+        }
+        if data.x.len() != self.steps() || epochs != self.epochs() {
+            return Err(format!(
+                "trajectory of {} steps isn't sampled every {} s over [{}, {})",
+                data.x.len(),
+                self.time_step_s,
+                self.window_start,
+                self.window_end
+            )
+            .into());
+        }
+        let cell = self.cell_size.max(1) as i64;
+        let on_grid = |axis: &[u32]| axis.iter().all(|&v| unbias(v) % cell == 0);
+        if !(on_grid(&data.x) && on_grid(&data.y) && on_grid(&data.z)) {
+            return Err(
+                format!("trajectory isn't quantized to cells of {}", self.cell_size).into(),
+            );
+        }
+        Ok(())
+    }
+}
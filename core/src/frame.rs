@@ -0,0 +1,53 @@
+// Coordinate reference frames of trajectory data.
+//
+// An inertial (ECI) position and an Earth-fixed (ECEF) one for the same point differ by
+// the Earth's rotation angle at that instant, so the two can't be compared directly.
+// Conversion happens on plaintext before encoding/encryption; once encrypted, the
+// frame is just a label the protocol checks.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Frame {
+    // Earth-centred inertial (TEME/J2000-like; nutation/precession ignored).
+    #[default]
+    Eci,
+    // Earth-centred, Earth-fixed.
+    Ecef,
+}
+
+// Greenwich mean sidereal time in radians at `unix_s` (IAU 1982, truncated).
+pub fn gmst_rad(unix_s: f64) -> f64 {
+    let days_since_j2000 = unix_s / 86_400.0 + 2_440_587.5 - 2_451_545.0;
+    let degrees = libm::fmod(
+        280.460_618_37 + 360.985_647_366_29 * days_since_j2000,
+        360.0,
+    );
+    let degrees = if degrees < 0.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    };
+    degrees.to_radians()
+}
+
+pub fn eci_to_ecef(position: [f64; 3], unix_s: f64) -> [f64; 3] {
+    let (sin, cos) = libm::sincos(gmst_rad(unix_s));
+    let [x, y, z] = position;
+    [cos * x + sin * y, -sin * x + cos * y, z]
+}
+
+pub fn ecef_to_eci(position: [f64; 3], unix_s: f64) -> [f64; 3] {
+    let (sin, cos) = libm::sincos(gmst_rad(unix_s));
+    let [x, y, z] = position;
+    [cos * x - sin * y, sin * x + cos * y, z]
+}
+
+// Converts `position`, sampled at `unix_s`, from frame `from` to frame `to`.
+pub fn convert(position: [f64; 3], unix_s: f64, from: Frame, to: Frame) -> [f64; 3] {
+    match (from, to) {
+        (Frame::Eci, Frame::Ecef) => eci_to_ecef(position, unix_s),
+        (Frame::Ecef, Frame::Eci) => ecef_to_eci(position, unix_s),
+        _ => position,
+    }
+}
@@ -0,0 +1,18 @@
+// Voxel indices of the coarse 16-bit grid (see the main crate's `grid`).
+//
+// The grid spans `u16::MAX` voxels per axis around the encoding's zero; `OUTSIDE` is
+// reserved for positions beyond it.
+
+use crate::units::unbias;
+
+// Voxel index no owner position maps to.
+pub const OUTSIDE: u16 = u16::MAX;
+
+// Voxel index of the encoding's zero.
+const ORIGIN: i64 = 1 << 15;
+
+// Voxel of the encoded coordinate `value`, or `None` outside the grid.
+pub fn voxel(value: u32, cell_size: u32) -> Option<u16> {
+    let index = unbias(value).div_euclid(cell_size.max(1) as i64) + ORIGIN;
+    u16::try_from(index).ok().filter(|&v| v != OUTSIDE)
+}
@@ -0,0 +1,23 @@
+// Plaintext encoding of trajectories, shared by the ground segment and flight software.
+//
+// Exact matching only finds anything if the screened positions were encoded exactly as
+// the counterpart expects. This crate is the encoding alone (fixed-point units, frames,
+// grid cells and voxels, the time grid of a window) with nothing but `core` and `alloc`,
+// so software on board a satellite or another embedded target can prepare its
+// `SatelliteData` with the same code the ground segment encrypts it with. The main crate
+// re-exports it as `sat_trajectory_fhe::core`, and its `units`, `frame`, `negotiation`
+// and `grid` modules build on it.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod canonical;
+pub mod data;
+pub mod encoding;
+pub mod frame;
+pub mod grid;
+pub mod units;
+
+pub use canonical::{CANONICAL, CanonicalEncoding};
+pub use data::SatelliteData;
@@ -0,0 +1,82 @@
+// Length units of trajectory coordinates and the fixed-point encoding of positions.
+//
+// Encrypted comparisons only see integers, so a trajectory in meters compared with one
+// in kilometers would silently never match (or match the wrong things). Positions are
+// therefore encoded in one canonical unit, and data in another unit is rescaled on the
+// plaintext side before screening.
+
+use alloc::boxed::Box;
+use alloc::format;
+use core::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+// Unit of one integer step of an encoded coordinate.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    Meters,
+    Kilometers,
+}
+
+// Unit the fixed-point encoder produces.
+pub const CANONICAL_UNITS: Units = Units::Meters;
+
+// Encoded coordinates are offset by 2^31 so negative positions fit in a `u32`: the
+// signed step count `s` is stored as `s + BIAS`, on the plaintext side and in the
+// encrypted `FheUint32`s alike. Only differences of two coordinates are safe to take
+// directly (the bias cancels, which is what the encrypted comparisons and distances rely
+// on); anything else goes through `unbias`/`bias` first.
+pub const BIAS: i64 = 1 << 31;
+
+// Signed step count of an encoded coordinate.
+pub fn unbias(cell: u32) -> i64 {
+    cell as i64 - BIAS
+}
+
+// Encoded coordinate of a signed step count, or `None` outside `[-2^31, 2^31)`.
+pub fn bias(steps: i64) -> Option<u32> {
+    u32::try_from(steps.checked_add(BIAS)?).ok()
+}
+
+impl Units {
+    pub fn meters(self) -> f64 {
+        match self {
+            Units::Meters => 1.0,
+            Units::Kilometers => 1000.0,
+        }
+    }
+}
+
+// Encodes `value` (in `units`) as a biased integer in `CANONICAL_UNITS`, rounding to the
+// nearest step.
+pub fn encode(value: f64, units: Units) -> Result<u32, Box<dyn Error>> {
+    let steps = libm::round(value * units.meters() / CANONICAL_UNITS.meters());
+    to_cell(steps).ok_or_else(|| {
+        format!(
+            "{} {:?} doesn't fit the fixed-point range in {:?}",
+            value, units, CANONICAL_UNITS
+        )
+        .into()
+    })
+}
+
+// Inverse of `encode`, in `CANONICAL_UNITS`.
+pub fn decode(cell: u32) -> f64 {
+    unbias(cell) as f64
+}
+
+// Re-expresses an encoded coordinate in another unit. Going to a coarser unit rounds
+// to the nearest step.
+pub fn rescale(cell: u32, from: Units, to: Units) -> Result<u32, Box<dyn Error>> {
+    if from == to {
+        return Ok(cell);
+    }
+    let steps = libm::round(unbias(cell) as f64 * from.meters() / to.meters());
+    to_cell(steps)
+        .ok_or_else(|| format!("coordinate doesn't fit the fixed-point range in {:?}", to).into())
+}
+
+fn to_cell(steps: f64) -> Option<u32> {
+    let cell = steps + BIAS as f64;
+    (cell >= 0.0 && cell <= u32::MAX as f64).then_some(cell as u32)
+}
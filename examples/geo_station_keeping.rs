@@ -0,0 +1,176 @@
+// End-to-end screening of two neighbouring geostationary satellites:
+//
+//     cargo run --release --example geo_station_keeping
+//
+// SAT-A keeps station at 75.00°E. SAT-B, in the adjacent slot at 75.06°E, has lost its
+// east-west station keeping and drifts west through A's slot on a slightly lower orbit.
+// A's operator encrypts A's trajectory (checked by `sanity` first), B's operator screens
+// it against B's plaintext with a box kernel of ±25 km per axis, and A decrypts the
+// flags into conjunction events. Both parties run in one process here; see `party_a.rs`
+// and `party_b.rs` for the same exchange between two.
+
+use std::f64::consts::TAU;
+use std::time::Instant;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::core::canonical::{CANONICAL, CanonicalEncoding};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::sanity::SanityLimits;
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::units::Units;
+
+// Geostationary orbit radius.
+const GEO_RADIUS_M: f64 = 42_164_170.0;
+const SIDEREAL_DAY_S: f64 = 86_164.1;
+// 2024-06-01T00:00:00Z.
+const START_EPOCH: u64 = 1_717_200_000;
+const STEP_S: u64 = 1_200;
+const STEPS: usize = 36;
+// Box half-width, in kilometers.
+const HALF_WIDTH_KM: u32 = 25;
+
+// SAT-B drifts west at 0.01°/h, which takes an orbit about 18.7 km below GEO.
+const DRIFT_DEG_PER_H: f64 = 0.01;
+const DRIFT_RADIUS_OFFSET_M: f64 = -18_700.0;
+
+// Earth-fixed position of a near-geostationary satellite at `longitude_deg`, `t_s` into
+// the scenario.
+fn geo_position(longitude_deg: f64, radius_m: f64, inclination_deg: f64, t_s: f64) -> [f64; 3] {
+    let longitude = longitude_deg.to_radians();
+    let latitude = inclination_deg.to_radians() * (TAU * t_s / SIDEREAL_DAY_S).sin();
+    [
+        radius_m * latitude.cos() * longitude.cos(),
+        radius_m * latitude.cos() * longitude.sin(),
+        radius_m * latitude.sin(),
+    ]
+}
+
+fn sat_a(t_s: f64) -> [f64; 3] {
+    // A small daily east-west libration inside its control box.
+    let longitude = 75.0 + 0.003 * (TAU * t_s / SIDEREAL_DAY_S).sin();
+    geo_position(longitude, GEO_RADIUS_M, 0.01, t_s)
+}
+
+fn sat_b(t_s: f64) -> [f64; 3] {
+    let longitude = 75.06 - DRIFT_DEG_PER_H * t_s / 3_600.0;
+    geo_position(longitude, GEO_RADIUS_M + DRIFT_RADIUS_OFFSET_M, 0.02, t_s)
+}
+
+fn trajectory(positions: &[[f64; 3]]) -> Result<SatelliteData, Box<dyn std::error::Error>> {
+    CanonicalEncoding {
+        units: Units::Kilometers,
+        ..CANONICAL
+    }
+    .encode_trajectory(positions, Frame::Ecef)
+}
+
+fn elapsed(epoch: u64) -> String {
+    let s = epoch - START_EPOCH;
+    format!("T+{:02}:{:02}", s / 3_600, s / 60 % 60)
+}
+
+fn distance_km(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt() / 1_000.0
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let epochs: Vec<u64> = (0..STEPS as u64)
+        .map(|i| START_EPOCH + i * STEP_S)
+        .collect();
+    let t = |epoch: u64| (epoch - START_EPOCH) as f64;
+    let a_positions: Vec<[f64; 3]> = epochs.iter().map(|&e| sat_a(t(e))).collect();
+    let b_positions: Vec<[f64; 3]> = epochs.iter().map(|&e| sat_b(t(e))).collect();
+    let (a, b) = (trajectory(&a_positions)?, trajectory(&b_positions)?);
+
+    println!("GEO station-keeping screening");
+    println!("  SAT-A  75.00°E, station kept");
+    println!(
+        "  SAT-B  75.06°E, drifting west at {:.2}°/h",
+        DRIFT_DEG_PER_H
+    );
+    println!(
+        "  {} steps {} min apart, box of ±{} km per axis (Earth-fixed)",
+        STEPS,
+        STEP_S / 60,
+        HALF_WIDTH_KM
+    );
+    println!();
+
+    println!("[A] generating keys and encrypting SAT-A's trajectory...");
+    let owner = PartyBuilder::new(a.clone()).owner().build()?;
+    let (encrypted, report) = owner.encrypt_validated(&epochs, &SanityLimits::default())?;
+    println!("[A] sanity check: {}", report.to_string().trim_end());
+
+    println!("[B] screening it against SAT-B's plaintext trajectory...");
+    let evaluator = PartyBuilder::new(b.clone())
+        .screening(ScreeningConfig {
+            kernel: KernelChoice::BoxThreshold {
+                half_width: HALF_WIDTH_KM,
+            },
+            parallel_axes: true,
+            ..Default::default()
+        })
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let started = Instant::now();
+    let output = evaluator.evaluate(&encrypted)?;
+    println!(
+        "[B] {} steps in {:.1} s: {} comparisons, {} boolean operations, depth {}",
+        output.results.len(),
+        started.elapsed().as_secs_f64(),
+        output.ops.comparisons,
+        output.ops.boolean,
+        output.ops.depth
+    );
+
+    let events = owner.decrypt_events(&output.results, &encrypted.epochs, encrypted.first_index)?;
+    println!();
+    println!("Conjunction report for SAT-A");
+    if events.is_empty() {
+        println!("  no conjunctions");
+    }
+    for (n, event) in events.iter().enumerate() {
+        println!(
+            "  #{}  {} to {}  ({} steps inside the box)",
+            n + 1,
+            elapsed(event.start_epoch),
+            elapsed(event.end_epoch),
+            event.n_steps
+        );
+    }
+
+    // Neither operator could compute this in practice: it needs both plaintexts.
+    let (closest, distance) = a_positions
+        .iter()
+        .zip(&b_positions)
+        .map(|(&pa, &pb)| distance_km(pa, pb))
+        .enumerate()
+        .min_by(|x, y| x.1.total_cmp(&y.1))
+        .expect("at least one step");
+    println!();
+    println!(
+        "  ground truth: closest approach {:.1} km at {}",
+        distance,
+        elapsed(epochs[closest])
+    );
+    let half_width = HALF_WIDTH_KM as i64;
+    let expected: Vec<bool> = (0..STEPS)
+        .map(|i| {
+            [(a.x[i], b.x[i]), (a.y[i], b.y[i]), (a.z[i], b.z[i])]
+                .iter()
+                .all(|&(p, q)| (p as i64 - q as i64).abs() <= half_width)
+        })
+        .collect();
+    let flags = owner.decrypt_results(&output.results);
+    let agreeing = flags.iter().zip(&expected).filter(|(f, e)| f == e).count();
+    println!(
+        "  encrypted screening agrees with the plaintext check on {}/{} steps",
+        agreeing, STEPS
+    );
+    if agreeing != STEPS {
+        return Err("encrypted and plaintext screenings disagree".into());
+    }
+    Ok(())
+}
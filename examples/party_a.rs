@@ -0,0 +1,93 @@
+// Party A, the key owner, in a two-party screening over TCP:
+//
+//     cargo run --release --example party_b -- 127.0.0.1:7879
+//     cargo run --release --example party_a -- 127.0.0.1:7879
+//
+// Party A connects to party B, announces the session in a `Hello`, sends its server key
+// and encrypted trajectory, and decrypts the per-step flags B sends back. Every message
+// is a session `Envelope` in a length-prefixed frame (`service::write_frame`). A bincode
+// `SatelliteData` file can be given after the address in place of the demo trajectory.
+
+use tokio::net::TcpStream;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::protocol::{MessageKind, ProtocolError, SessionMetadata};
+use sat_trajectory_fhe::screening::results_from_bytes;
+use sat_trajectory_fhe::service::{ServiceError, read_frame, write_frame};
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::Units;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:7879";
+
+// Crosses party B's demo trajectory at step 2.
+fn demo_trajectory() -> SatelliteData {
+    SatelliteData {
+        x: vec![1000, 1100, 1200, 1300, 1400],
+        y: vec![5000, 5100, 5200, 5300, 5400],
+        z: vec![7000; 5],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let addr = args.next().unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let trajectory = match args.next() {
+        Some(path) => bincode::deserialize(&std::fs::read(path)?)?,
+        None => demo_trajectory(),
+    };
+    let (frame, units) = (trajectory.frame, trajectory.units);
+
+    println!("generating keys...");
+    let owner = PartyBuilder::new(trajectory).owner().build()?;
+    let mut stream = TcpStream::connect(&addr).await?;
+    println!("connected to {}", addr);
+
+    let mut session = Session::open()?;
+    let hello = session.hello(&SessionMetadata {
+        server_key_fingerprint: Some(owner.server_key_fingerprint().to_string()),
+        frame: Some(frame),
+        units: Some(units),
+        ..Default::default()
+    })?;
+    write_frame(&mut stream, &hello)
+        .await
+        .map_err(frame_error)?;
+    let server_key = session.send(MessageKind::ServerKey, owner.server_key_bytes()?)?;
+    write_frame(&mut stream, &server_key)
+        .await
+        .map_err(frame_error)?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let trajectory = session.send(MessageKind::EncryptedTrajectory, encrypted.to_bytes()?)?;
+    write_frame(&mut stream, &trajectory)
+        .await
+        .map_err(frame_error)?;
+    println!("sent server key and {} encrypted steps", encrypted.len());
+
+    let message: Vec<u8> = read_frame(&mut stream).await.map_err(frame_error)?;
+    let envelope = session.receive(&message)?;
+    if envelope.kind != MessageKind::Results {
+        return Err(ProtocolError::UnexpectedMessage {
+            expected: MessageKind::Results,
+            found: envelope.kind,
+        }
+        .into());
+    }
+    let flags = owner.decrypt_results(&results_from_bytes(&envelope.payload)?);
+    let hits: Vec<usize> = (0..flags.len()).filter(|&i| flags[i]).collect();
+    println!("conjunction steps: {:?}", hits);
+    println!("transcript: {}", hex(&session.transcript()));
+    Ok(())
+}
+
+fn frame_error(err: ServiceError) -> Box<dyn std::error::Error> {
+    err
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,107 @@
+// Party B, the evaluator, in a two-party screening over TCP; see `party_a.rs` for how to
+// run both.
+//
+// Party B waits for party A, checks the announced frame and server key fingerprint,
+// screens A's encrypted trajectory against its own plaintext one and sends the encrypted
+// flags back. It never holds a key that could decrypt them. A bincode `SatelliteData`
+// file can be given after the listen address in place of the demo trajectory.
+
+use tokio::net::{TcpListener, TcpStream};
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::{Frame, check_frames};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::protocol::{Envelope, MessageKind, ProtocolError};
+use sat_trajectory_fhe::screening::results_to_bytes;
+use sat_trajectory_fhe::service::{ServiceError, read_frame, write_frame};
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:7879";
+
+// Crosses party A's demo trajectory at step 2.
+fn demo_trajectory() -> SatelliteData {
+    SatelliteData {
+        x: vec![3000, 2100, 1200, 300, 9000],
+        y: vec![3000, 4100, 5200, 6300, 9000],
+        z: vec![7000; 5],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let addr = args.next().unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let trajectory = match args.next() {
+        Some(path) => bincode::deserialize(&std::fs::read(path)?)?,
+        None => demo_trajectory(),
+    };
+
+    let listener = TcpListener::bind(&addr).await?;
+    println!("waiting for party A on {}", listener.local_addr()?);
+    let (mut stream, peer) = listener.accept().await?;
+    println!("party A connected from {}", peer);
+
+    let hello: Vec<u8> = read_frame(&mut stream).await.map_err(frame_error)?;
+    let (mut session, metadata) = Session::accept(&hello)?;
+    if let Some(frame) = metadata.frame {
+        check_frames(frame, trajectory.frame)?;
+    }
+
+    let server_key = receive(&mut stream, &mut session, MessageKind::ServerKey).await?;
+    let evaluator = PartyBuilder::new(trajectory)
+        .evaluator(server_key.payload)
+        .build()?;
+    if let Some(announced) = &metadata.server_key_fingerprint
+        && announced != evaluator.server_key_fingerprint()
+    {
+        return Err(format!(
+            "server key {} doesn't match the announced {}",
+            evaluator.server_key_fingerprint(),
+            announced
+        )
+        .into());
+    }
+
+    let envelope = receive(&mut stream, &mut session, MessageKind::EncryptedTrajectory).await?;
+    let encrypted = EncryptedTrajectory::from_bytes(&envelope.payload)?;
+    println!("screening {} encrypted steps...", encrypted.len());
+    let output = evaluator.evaluate(&encrypted)?;
+    println!("done, {} homomorphic operations", output.ops.total());
+
+    let results = session.send(MessageKind::Results, results_to_bytes(&output.results)?)?;
+    write_frame(&mut stream, &results)
+        .await
+        .map_err(frame_error)?;
+    println!("transcript: {}", hex(&session.transcript()));
+    Ok(())
+}
+
+// Reads the next envelope from party A, which must be of `kind`.
+async fn receive(
+    stream: &mut TcpStream,
+    session: &mut Session,
+    kind: MessageKind,
+) -> Result<Envelope, Box<dyn std::error::Error>> {
+    let message: Vec<u8> = read_frame(stream).await.map_err(frame_error)?;
+    let envelope = session.receive(&message)?;
+    if envelope.kind != kind {
+        return Err(ProtocolError::UnexpectedMessage {
+            expected: kind,
+            found: envelope.kind,
+        }
+        .into());
+    }
+    Ok(envelope)
+}
+
+fn frame_error(err: ServiceError) -> Box<dyn std::error::Error> {
+    err
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,17 @@
+// `.eft` files: the header, the ciphertext ranges and every step they point to.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sat_trajectory_fhe::eft::EftReader;
+
+fuzz_target!(|data: &[u8]| {
+    // The reader memory-maps a path, so each input goes through a scratch file.
+    let path = std::env::temp_dir().join(format!("fuzz-eft-{}.eft", std::process::id()));
+    std::fs::write(&path, data).expect("write scratch file");
+    if let Ok(reader) = EftReader::open(&path) {
+        for i in 0..reader.len() {
+            let _ = reader.step(i);
+        }
+    }
+});
@@ -0,0 +1,14 @@
+// Individually serialized ciphertexts, as found inside trajectories, results and `.eft`
+// files.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sat_trajectory_fhe::common::safe_deserialize_item;
+use tfhe::{FheBool, FheUint32, FheUint64};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = safe_deserialize_item::<FheUint32>(data);
+    let _ = safe_deserialize_item::<FheBool>(data);
+    let _ = safe_deserialize_item::<FheUint64>(data);
+});
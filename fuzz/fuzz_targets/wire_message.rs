@@ -0,0 +1,27 @@
+// Everything a peer or a daemon client can send: service frames, envelopes, the `Hello`
+// and the artifacts carried in envelope payloads.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sat_trajectory_fhe::prescreen::CellFilter;
+use sat_trajectory_fhe::protocol::Envelope;
+use sat_trajectory_fhe::reveal::RevealedResult;
+use sat_trajectory_fhe::screening::results_from_bytes;
+use sat_trajectory_fhe::service::Request;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::stream::ResultBatch;
+use sat_trajectory_fhe::timing::EncryptedEpochs;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = bincode::deserialize::<Request>(data);
+    let _ = Envelope::from_bytes(data);
+    let _ = Session::accept(data);
+    let _ = EncryptedTrajectory::from_bytes(data);
+    let _ = results_from_bytes(data);
+    let _ = RevealedResult::from_bytes(data);
+    let _ = EncryptedEpochs::from_bytes(data);
+    let _ = ResultBatch::from_bytes(data);
+    let _ = CellFilter::from_bytes(data);
+});
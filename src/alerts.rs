@@ -0,0 +1,129 @@
+// Hooks fired when the owner decrypts a positive collision flag, for wiring screenings
+// into paging and ops tooling.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::events::ConjunctionEvent;
+
+// What an alert carries: the conjunction events of one decrypted screening, which always
+// contains at least one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlertReport {
+    // Steps screened in total.
+    pub steps: usize,
+    pub events: Vec<ConjunctionEvent>,
+}
+
+impl AlertReport {
+    pub fn to_json(&self, label: &str) -> String {
+        let events: Vec<String> = self
+            .events
+            .iter()
+            .map(|e| {
+                format!(
+                    "{{\"start_index\":{},\"start_epoch\":{},\"end_epoch\":{},\"n_steps\":{}}}",
+                    e.start_index, e.start_epoch, e.end_epoch, e.n_steps
+                )
+            })
+            .collect();
+        format!(
+            "{{\"label\":{},\"steps\":{},\"events\":[{}]}}",
+            json_string(label),
+            self.steps,
+            events.join(",")
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub trait AlertSink: std::fmt::Debug + Send + Sync {
+    fn notify(&self, report: &AlertReport) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+// POSTs the report as JSON to `url`. Like the S3 store it goes through the system `curl`
+// with its whole config, headers included, on stdin, so tokens never show up on the
+// command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookAlert {
+    pub url: String,
+    // Identifies the screening to whoever receives the alert, e.g. the satellite name.
+    pub label: String,
+    // Extra request headers, e.g. `Authorization: Bearer ...`.
+    pub headers: Vec<String>,
+}
+
+// Quoted string in curl's config syntax.
+fn curl_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl AlertSink for WebhookAlert {
+    fn notify(&self, report: &AlertReport) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = String::from(
+            "silent\nshow-error\nfail\nrequest = \"POST\"\nheader = \"Content-Type: application/json\"\n",
+        );
+        for header in &self.headers {
+            config.push_str(&format!("header = {}\n", curl_string(header)));
+        }
+        config.push_str(&format!(
+            "data-binary = {}\nurl = {}\n",
+            curl_string(&report.to_json(&self.label)),
+            curl_string(&self.url)
+        ));
+
+        let mut child = Command::new("curl")
+            .args(["-K", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .ok_or("curl stdin unavailable")?
+            .write_all(config.as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "webhook failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+// Appends the report as one JSON line to `path`, e.g. for a log shipper to pick up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonLinesAlert {
+    pub path: PathBuf,
+    pub label: String,
+}
+
+impl AlertSink for JsonLinesAlert {
+    fn notify(&self, report: &AlertReport) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", report.to_json(&self.label))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+// Screening against a protected asset with a published ephemeris.
+//
+// Some assets publish their trajectory: the ISS ephemeris is an OEM updated several times
+// a day. An evaluator protecting such an asset doesn't need a trajectory of its own: its
+// plaintext side is the published ephemeris, interpolated to the epochs of the owner's
+// encrypted trajectory, and the screening volume is the asset's safety volume.
+//
+// Safety volumes are boxes in the asset's radial, along-track and cross-track frame,
+// which rotates with the orbit. Screening runs on axis-aligned coordinates, so the box
+// kernel is sized to the half-diagonal of the safety volume: every orientation of the
+// volume fits inside it, at the price of a few more false alarms. Between samples the
+// ephemeris is interpolated with cubic Hermite polynomials over the published positions
+// and velocities, which is accurate to meters at the minutes-apart sampling of published
+// ephemerides; epochs outside the ephemeris are refused rather than extrapolated.
+
+use crate::alerts::{AlertReport, WebhookAlert};
+use crate::common::SatelliteData;
+use crate::core::canonical::{CANONICAL, CanonicalEncoding};
+use crate::export::{OemEphemeris, from_oem, iso8601};
+use crate::kernel::KernelChoice;
+use crate::party::{EvaluatorParty, PartyBuilder};
+use crate::screening::ScreeningConfig;
+use crate::units::Units;
+
+// Half-extents of a box around an asset, in meters, in its radial, along-track and
+// cross-track directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafetyVolume {
+    pub radial_m: f64,
+    pub along_track_m: f64,
+    pub cross_track_m: f64,
+}
+
+impl SafetyVolume {
+    // The ISS conjunction screening "pizza box": ±2 km radial, ±25 km along-track and
+    // cross-track.
+    pub const ISS: SafetyVolume = SafetyVolume {
+        radial_m: 2_000.0,
+        along_track_m: 25_000.0,
+        cross_track_m: 25_000.0,
+    };
+
+    // Half-width of the smallest axis-aligned box holding the volume in any orientation.
+    pub fn half_diagonal_m(&self) -> f64 {
+        (self.radial_m.powi(2) + self.along_track_m.powi(2) + self.cross_track_m.powi(2)).sqrt()
+    }
+}
+
+impl std::fmt::Display for SafetyVolume {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "±{:.1} km radial, ±{:.1} km along-track, ±{:.1} km cross-track",
+            self.radial_m / 1_000.0,
+            self.along_track_m / 1_000.0,
+            self.cross_track_m / 1_000.0
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtectedAsset {
+    pub ephemeris: OemEphemeris,
+    pub volume: SafetyVolume,
+}
+
+// Cubic Hermite interpolation of one coordinate over `[0, h]` at `t`.
+fn hermite(p0: f64, v0: f64, p1: f64, v1: f64, h: f64, t: f64) -> f64 {
+    let s = t / h;
+    let (s2, s3) = (s * s, s * s * s);
+    (2.0 * s3 - 3.0 * s2 + 1.0) * p0
+        + (s3 - 2.0 * s2 + s) * h * v0
+        + (-2.0 * s3 + 3.0 * s2) * p1
+        + (s3 - s2) * h * v1
+}
+
+impl ProtectedAsset {
+    pub fn from_oem(text: &str, volume: SafetyVolume) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            ephemeris: from_oem(text)?,
+            volume,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.ephemeris.object_name
+    }
+
+    // Position in meters at `epoch`, Unix seconds.
+    pub fn position_at(&self, epoch: u64) -> Result<[f64; 3], Box<dyn std::error::Error>> {
+        let ephemeris = &self.ephemeris;
+        let t = epoch as f64;
+        let first = ephemeris.epochs_s[0];
+        let last = ephemeris.epochs_s[ephemeris.epochs_s.len() - 1];
+        if t < first || t > last {
+            return Err(format!(
+                "epoch {} is outside the ephemeris of {} ({} to {})",
+                iso8601(epoch),
+                self.name(),
+                iso8601(first.ceil() as u64),
+                iso8601(last.floor() as u64)
+            )
+            .into());
+        }
+        let n = ephemeris.epochs_s.len();
+        if n == 1 {
+            return Ok(ephemeris.positions_m[0]);
+        }
+        // Start of the sample interval holding `t`.
+        let i = ephemeris
+            .epochs_s
+            .partition_point(|&e| e <= t)
+            .saturating_sub(1)
+            .min(n - 2);
+        let h = ephemeris.epochs_s[i + 1] - ephemeris.epochs_s[i];
+        let (p0, p1) = (ephemeris.positions_m[i], ephemeris.positions_m[i + 1]);
+        let (v0, v1) = (ephemeris.velocities_mps[i], ephemeris.velocities_mps[i + 1]);
+        let dt = t - ephemeris.epochs_s[i];
+        Ok([0, 1, 2].map(|axis| hermite(p0[axis], v0[axis], p1[axis], v1[axis], h, dt)))
+    }
+
+    // The asset's trajectory at `epochs`, encoded in `units` in the ephemeris frame.
+    pub fn trajectory_at(
+        &self,
+        epochs: &[u64],
+        units: Units,
+    ) -> Result<SatelliteData, Box<dyn std::error::Error>> {
+        let positions = epochs
+            .iter()
+            .map(|&epoch| self.position_at(epoch))
+            .collect::<Result<Vec<_>, _>>()?;
+        CanonicalEncoding { units, ..CANONICAL }.encode_trajectory(&positions, self.ephemeris.frame)
+    }
+
+    // Box kernel covering the safety volume in `units`.
+    pub fn screening(&self, units: Units) -> ScreeningConfig {
+        ScreeningConfig {
+            kernel: KernelChoice::BoxThreshold {
+                half_width: (self.volume.half_diagonal_m() / units.meters()).ceil() as u32,
+            },
+            ..Default::default()
+        }
+    }
+
+    // An evaluator screening uploads sampled at `epochs` in `units` against the asset.
+    pub fn evaluator(
+        &self,
+        server_key: Vec<u8>,
+        epochs: &[u64],
+        units: Units,
+    ) -> Result<EvaluatorParty, Box<dyn std::error::Error>> {
+        PartyBuilder::new(self.trajectory_at(epochs, units)?)
+            .screening(self.screening(units))
+            .evaluator(server_key)
+            .build()
+    }
+
+    // Label naming the asset and its safety volume, e.g. for `WebhookAlert`.
+    pub fn label(&self) -> String {
+        format!("{} safety volume ({})", self.name(), self.volume)
+    }
+
+    pub fn webhook(&self, url: &str, headers: Vec<String>) -> WebhookAlert {
+        WebhookAlert {
+            url: url.to_string(),
+            label: self.label(),
+            headers,
+        }
+    }
+
+    // One line per event of `report`, for operators of the asset.
+    pub fn alert_text(&self, report: &AlertReport) -> String {
+        let mut text = format!(
+            "{}: {} possible entr{} in {} screened steps\n",
+            self.label(),
+            report.events.len(),
+            if report.events.len() == 1 { "y" } else { "ies" },
+            report.steps
+        );
+        for event in &report.events {
+            text.push_str(&format!(
+                "  {} to {} UTC, {} step{}\n",
+                iso8601(event.start_epoch),
+                iso8601(event.end_epoch),
+                event.n_steps,
+                if event.n_steps == 1 { "" } else { "s" }
+            ));
+        }
+        text
+    }
+}
@@ -0,0 +1,324 @@
+// Standardized performance workloads, behind `sat-fhe bench`.
+//
+// How long a screening takes depends on the machine, the parameter preset and the kernel
+// far more than on anything the cost model in `depth` can see. `run_profile` times a
+// fixed grid of workloads (trajectory lengths of 100, 1k and 10k steps with the exact-
+// match, box and distance kernels), each with `pairs` screenings running concurrently
+// against the same key as a busy daemon would. The resulting `PerformanceProfile`
+// estimates the duration of a screening (`estimate`) and supplies the per-step time
+// `tuning` sizes result batches with before a daemon has screened anything itself
+// (`LinkBenchmark::step_time`). `to_json` is the machine-readable form the CLI prints, and
+// `from_json` reads it back. `profile_workload` (with the `profiling` feature) breaks one
+// workload down by TFHE operation instead.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::common::SatelliteData;
+use crate::context::FheContext;
+use crate::depth::OpCounter;
+use crate::frame::Frame;
+use crate::kernel::KernelChoice;
+use crate::planner::{Operand, screen_planned};
+use crate::preset::ParameterPreset;
+use crate::screening::ScreeningConfig;
+use crate::units::Units;
+
+pub const STANDARD_LENGTHS: [usize; 3] = [100, 1_000, 10_000];
+
+// Distance in units the box and distance workloads screen for.
+const WORKLOAD_THRESHOLD: u32 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchKernel {
+    Eq,
+    Box,
+    Distance,
+}
+
+impl BenchKernel {
+    pub const ALL: [BenchKernel; 3] = [BenchKernel::Eq, BenchKernel::Box, BenchKernel::Distance];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BenchKernel::Eq => "eq",
+            BenchKernel::Box => "box",
+            BenchKernel::Distance => "distance",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::ALL
+            .into_iter()
+            .find(|kernel| kernel.name() == name)
+            .ok_or_else(|| format!("unknown kernel {:?} (eq, box or distance)", name).into())
+    }
+
+    // The workload kernel measuring `kernel`, whatever its threshold.
+    pub fn of(kernel: KernelChoice) -> Option<Self> {
+        match kernel {
+            KernelChoice::ExactMatch => Some(BenchKernel::Eq),
+            KernelChoice::BoxThreshold { .. } => Some(BenchKernel::Box),
+            KernelChoice::SquaredDistanceThreshold { .. } => Some(BenchKernel::Distance),
+            KernelChoice::AltitudeBand { .. } => None,
+        }
+    }
+
+    pub fn choice(self) -> KernelChoice {
+        match self {
+            BenchKernel::Eq => KernelChoice::ExactMatch,
+            BenchKernel::Box => KernelChoice::BoxThreshold {
+                half_width: WORKLOAD_THRESHOLD,
+            },
+            BenchKernel::Distance => KernelChoice::SquaredDistanceThreshold {
+                threshold: WORKLOAD_THRESHOLD,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Workload {
+    pub kernel: BenchKernel,
+    pub steps: usize,
+    // Screenings of `steps` steps each running at the same time.
+    pub pairs: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub workload: Workload,
+    // Wall time until every pair was screened.
+    pub elapsed: Duration,
+    // Work of one pair.
+    pub ops: OpCounter,
+}
+
+impl Measurement {
+    // Wall time per step of all pairs together, i.e. the inverse of the throughput.
+    pub fn step_time(&self) -> Duration {
+        self.elapsed / (self.workload.steps * self.workload.pairs).max(1) as u32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerformanceProfile {
+    pub preset: ParameterPreset,
+    // Threads available to the evaluation.
+    pub threads: usize,
+    pub measurements: Vec<Measurement>,
+}
+
+impl PerformanceProfile {
+    // Step time of `kernel` in its longest measured workload, where fixed costs matter
+    // least.
+    pub fn step_time(&self, kernel: KernelChoice) -> Option<Duration> {
+        self.measurements
+            .iter()
+            .filter(|m| Some(m.workload.kernel) == BenchKernel::of(kernel))
+            .max_by_key(|m| m.workload.steps)
+            .map(Measurement::step_time)
+    }
+
+    // Estimated wall time of screening `steps` steps with `kernel`.
+    pub fn estimate(&self, kernel: KernelChoice, steps: usize) -> Option<Duration> {
+        self.step_time(kernel)
+            .map(|step_time| step_time * steps as u32)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{{\"preset\":\"{}\",\"threads\":{},\"measurements\":[",
+            self.preset.name(),
+            self.threads
+        );
+        for (i, m) in self.measurements.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "{{\"kernel\":\"{}\",\"steps\":{},\"pairs\":{},\"elapsed_ns\":{},\"step_ns\":{},\"comparisons\":{},\"arithmetic\":{},\"boolean\":{},\"depth\":{}}}",
+                m.workload.kernel.name(),
+                m.workload.steps,
+                m.workload.pairs,
+                m.elapsed.as_nanos(),
+                m.step_time().as_nanos(),
+                m.ops.comparisons,
+                m.ops.arithmetic,
+                m.ops.boolean,
+                m.ops.depth
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+
+    // Inverse of `to_json`, e.g. for a profile saved from `sat-fhe bench` to calibrate the
+    // ETAs of `sat-fhe screen`. Only reads what `to_json` writes.
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = json.trim();
+        let (head, measurements) = json
+            .split_once("\"measurements\":[")
+            .ok_or("performance profile has no measurements")?;
+        let measurements = measurements
+            .strip_suffix("]}")
+            .ok_or("performance profile is truncated")?;
+        let measurements = if measurements.is_empty() {
+            Vec::new()
+        } else {
+            measurements
+                .split("},{")
+                .map(|object| {
+                    let elapsed_ns: u64 = json_field(object, "elapsed_ns")?.parse()?;
+                    Ok(Measurement {
+                        workload: Workload {
+                            kernel: BenchKernel::parse(json_field(object, "kernel")?)?,
+                            steps: json_field(object, "steps")?.parse()?,
+                            pairs: json_field(object, "pairs")?.parse()?,
+                        },
+                        elapsed: Duration::from_nanos(elapsed_ns),
+                        ops: OpCounter {
+                            comparisons: json_field(object, "comparisons")?.parse()?,
+                            arithmetic: json_field(object, "arithmetic")?.parse()?,
+                            boolean: json_field(object, "boolean")?.parse()?,
+                            depth: json_field(object, "depth")?.parse()?,
+                            depth_warning: None,
+                        },
+                    })
+                })
+                .collect::<Result<_, Box<dyn std::error::Error>>>()?
+        };
+        Ok(Self {
+            preset: ParameterPreset::from_name(json_field(head, "preset")?)?,
+            threads: json_field(head, "threads")?.parse()?,
+            measurements,
+        })
+    }
+}
+
+// Value of `"name":` in a flat JSON object as `to_json` writes it, without quotes.
+fn json_field<'a>(object: &'a str, name: &str) -> Result<&'a str, Box<dyn std::error::Error>> {
+    let key = format!("\"{}\":", name);
+    let start = object
+        .find(&key)
+        .ok_or_else(|| format!("performance profile has no {:?}", name))?
+        + key.len();
+    let value = &object[start..];
+    let end = value.find([',', '}']).unwrap_or(value.len());
+    Ok(value[..end].trim_matches('"'))
+}
+
+// `kernels` × `lengths` with `pairs` concurrent screenings each.
+pub fn standard_workloads(
+    kernels: &[BenchKernel],
+    lengths: &[usize],
+    pairs: usize,
+) -> Vec<Workload> {
+    kernels
+        .iter()
+        .flat_map(|&kernel| {
+            lengths.iter().map(move |&steps| Workload {
+                kernel,
+                steps,
+                pairs: pairs.max(1),
+            })
+        })
+        .collect()
+}
+
+// A low-orbit-like trajectory of `steps` steps starting at `phase`, so pairs differ.
+fn synthetic(steps: usize, phase: u32) -> SatelliteData {
+    let axis = |scale: u32, offset: u32| -> Vec<u32> {
+        (0..steps as u32)
+            .map(|i| (1u32 << 31).wrapping_add((i + phase) * scale + offset))
+            .collect()
+    };
+    SatelliteData {
+        x: axis(7_500, 6_771_000),
+        y: axis(3_100, 0),
+        z: axis(900, 0),
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+// Times every workload under a fresh key of `preset`. Encryption isn't timed.
+pub fn run_profile(
+    preset: ParameterPreset,
+    workloads: &[Workload],
+) -> Result<PerformanceProfile, Box<dyn std::error::Error>> {
+    let context = FheContext::generate(preset.config())?;
+    let mut measurements = Vec::with_capacity(workloads.len());
+    for &workload in workloads {
+        let encrypted = context.encrypt(&synthetic(workload.steps, 0))?;
+        let counterparts: Vec<SatelliteData> = (0..workload.pairs)
+            .map(|pair| synthetic(workload.steps, pair as u32 + 1))
+            .collect();
+        let config = ScreeningConfig {
+            kernel: workload.kernel.choice(),
+            ..Default::default()
+        };
+        let started = Instant::now();
+        let outputs = std::thread::scope(|scope| {
+            let handles: Vec<_> = counterparts
+                .iter()
+                .map(|counterpart| {
+                    let (context, encrypted, config) = (&context, &encrypted, &config);
+                    scope.spawn(move || {
+                        context
+                            .evaluate_with(|| {
+                                screen_planned(encrypted, Operand::Clear(counterpart), config)
+                            })
+                            .map(|output| output.ops)
+                            .map_err(|e| e.to_string())
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .map_err(|_| "screening panicked".to_string())?
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })?;
+        measurements.push(Measurement {
+            workload,
+            elapsed: started.elapsed(),
+            ops: outputs.first().copied().unwrap_or_default(),
+        });
+    }
+    Ok(PerformanceProfile {
+        preset,
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        measurements,
+    })
+}
+
+// Where the time of screening one pair of `workload` goes, under a fresh key of `preset`
+// (see `profiling`). With `parallel_axes` the axes of each step run on the workers.
+#[cfg(feature = "profiling")]
+pub fn profile_workload(
+    preset: ParameterPreset,
+    workload: Workload,
+    parallel_axes: bool,
+) -> Result<crate::profiling::FoldedProfile, Box<dyn std::error::Error>> {
+    let context = FheContext::generate(preset.config())?;
+    let encrypted = context.encrypt(&synthetic(workload.steps, 0))?;
+    let counterpart = synthetic(workload.steps, 1);
+    let config = ScreeningConfig {
+        kernel: workload.kernel.choice(),
+        parallel_axes,
+        ..Default::default()
+    };
+    context.evaluate_with(|| {
+        let (output, profile) = crate::profiling::record("screen", || {
+            screen_planned(&encrypted, Operand::Clear(&counterpart), &config)
+        });
+        output.map(|_| profile)
+    })
+}
@@ -0,0 +1,126 @@
+// Metering of homomorphic work, for screening services that bill by usage.
+//
+// The screening daemon reports the operations it has evaluated to every `UsageMeter`
+// as it goes: one `UsageRecord` per streamed batch, or one per job for the reveal
+// policies and masks that screen in one piece. A job cancelled or failed halfway has
+// then only been metered for the steps actually screened. Counts are those of
+// `OpCounter`, i.e. planned per kernel step, not measured. `cost_report_csv` turns
+// records into a per-session bill.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use crate::depth::OpCounter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageRecord {
+    // The session the work was done for, e.g. the daemon's job ID.
+    pub session: String,
+    // Who it was done for, e.g. the client's IP address.
+    pub client: String,
+    pub steps: u64,
+    pub ops: OpCounter,
+    // When the work finished, in Unix seconds.
+    pub unix_s: u64,
+}
+
+pub trait UsageMeter: std::fmt::Debug + Send + Sync {
+    fn record(&self, usage: &UsageRecord) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+// Shared with whoever reads the records, e.g. an `Arc<UsageLog>`.
+impl<M: UsageMeter + ?Sized> UsageMeter for Arc<M> {
+    fn record(&self, usage: &UsageRecord) -> Result<(), Box<dyn std::error::Error>> {
+        (**self).record(usage)
+    }
+}
+
+// Keeps every record in memory, e.g. for a service that bills once a day.
+#[derive(Debug, Default)]
+pub struct UsageLog {
+    records: Mutex<Vec<UsageRecord>>,
+}
+
+impl UsageLog {
+    pub fn records(&self) -> Vec<UsageRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    // Removes and returns the records so far, e.g. once they are billed.
+    pub fn drain(&self) -> Vec<UsageRecord> {
+        std::mem::take(&mut *self.records.lock().unwrap())
+    }
+}
+
+impl UsageMeter for UsageLog {
+    fn record(&self, usage: &UsageRecord) -> Result<(), Box<dyn std::error::Error>> {
+        self.records.lock().unwrap().push(usage.clone());
+        Ok(())
+    }
+}
+
+// Price per operation, by type, in any currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PriceList {
+    pub comparison: f64,
+    pub arithmetic: f64,
+    pub boolean: f64,
+}
+
+impl PriceList {
+    pub fn cost(&self, ops: &OpCounter) -> f64 {
+        ops.comparisons as f64 * self.comparison
+            + ops.arithmetic as f64 * self.arithmetic
+            + ops.boolean as f64 * self.boolean
+    }
+}
+
+// Quoted if it has to be.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// One row per session and client, sorted, then a total:
+// `session,client,steps,comparisons,arithmetic,boolean,cost`.
+pub fn cost_report_csv(records: &[UsageRecord], prices: &PriceList) -> String {
+    let mut sessions: BTreeMap<(&str, &str), (u64, OpCounter)> = BTreeMap::new();
+    for record in records {
+        let (steps, ops) = sessions
+            .entry((record.session.as_str(), record.client.as_str()))
+            .or_default();
+        *steps += record.steps;
+        ops.add_steps(&record.ops, 1);
+    }
+    let mut out = String::from("session,client,steps,comparisons,arithmetic,boolean,cost\n");
+    let mut total = (0, OpCounter::default());
+    for ((session, client), (steps, ops)) in &sessions {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{:.6}",
+            csv_field(session),
+            csv_field(client),
+            steps,
+            ops.comparisons,
+            ops.arithmetic,
+            ops.boolean,
+            prices.cost(ops)
+        );
+        total.0 += steps;
+        total.1.add_steps(ops, 1);
+    }
+    let _ = writeln!(
+        out,
+        "total,,{},{},{},{},{:.6}",
+        total.0,
+        total.1.comparisons,
+        total.1.arithmetic,
+        total.1.boolean,
+        prices.cost(&total.1)
+    );
+    out
+}
@@ -0,0 +1,21 @@
+// Mock evaluator for contract-testing clients: `sat-fhe-mock <trajectory> [<listen>]`.
+// `<trajectory>` is a bincode-serialized `SatelliteData`, as for `sat-fhe-serve`; the
+// mock listens on 127.0.0.1:7878 unless told otherwise. Ciphertexts are clear values
+// (see `mock_server`), so keep real owners away from it.
+
+use sat_trajectory_fhe::mock_server::MockServer;
+use sat_trajectory_fhe::service::ServiceError;
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:7878";
+
+#[tokio::main]
+async fn main() -> Result<(), ServiceError> {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), listen) = (args.next(), args.next()) else {
+        return Err("usage: sat-fhe-mock <trajectory> [<listen>]".into());
+    };
+    let trajectory = bincode::deserialize(&std::fs::read(&path)?)?;
+    let server = MockServer::bind(listen.as_deref().unwrap_or(DEFAULT_LISTEN), trajectory).await?;
+    println!("sat-fhe-mock listening on {}", server.local_addr()?);
+    server.run().await
+}
@@ -0,0 +1,46 @@
+// Evaluator daemon: `sat-fhe-serve [--config <path> | --campaign <path>]`. With
+// `--campaign` the configuration is the `daemon` section of a campaign file. SIGTERM or
+// Ctrl-C shut it down gracefully (see `serve`).
+
+use sat_trajectory_fhe::campaign::CampaignConfig;
+use sat_trajectory_fhe::serve::{Daemon, ServeConfig};
+use sat_trajectory_fhe::service::ServiceError;
+
+const DEFAULT_CONFIG: &str = "/etc/sat-fhe/serve.toml";
+
+// Resolves on SIGTERM, as orchestrators send it, or on Ctrl-C.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    if let Ok(mut terminate) =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+    {
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+        return;
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[tokio::main]
+async fn main() -> Result<(), ServiceError> {
+    let mut args = std::env::args().skip(1);
+    let config = match (args.next().as_deref(), args.next()) {
+        (None, _) => ServeConfig::load(DEFAULT_CONFIG)?,
+        (Some("--config"), Some(path)) => ServeConfig::load(&path)?,
+        (Some("--campaign"), Some(path)) => CampaignConfig::load(&path)?
+            .daemon
+            .ok_or("the campaign has no daemon section")?,
+        _ => return Err("usage: sat-fhe-serve [--config <path> | --campaign <path>]".into()),
+    };
+    if config.tls.is_some() {
+        let daemon = Daemon::bind_tls(config).await?;
+        println!("sat-fhe-serve listening on {} (TLS)", daemon.local_addr()?);
+        daemon.run_until(shutdown_signal()).await
+    } else {
+        let daemon = Daemon::bind(config).await?;
+        println!("sat-fhe-serve listening on {}", daemon.local_addr()?);
+        daemon.run_until(shutdown_signal()).await
+    }
+}
@@ -0,0 +1,26 @@
+// Writes the golden wire-format vectors: `sat-fhe-testdata [<dir>]`, by default into
+// `tests/golden`. Only rerun it for an intentional format change.
+
+use std::path::PathBuf;
+
+use sat_trajectory_fhe::testdata;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = PathBuf::from(
+        std::env::args()
+            .nth(1)
+            .unwrap_or_else(|| "tests/golden".to_string()),
+    );
+    std::fs::create_dir_all(&dir)?;
+    for (name, bytes) in testdata::vectors()? {
+        std::fs::write(dir.join(name), bytes)?;
+        println!("wrote {}", dir.join(name).display());
+    }
+    #[cfg(feature = "mmap")]
+    {
+        let path = dir.join("trajectory.eft");
+        sat_trajectory_fhe::eft::write_eft(&path, &testdata::trajectory())?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
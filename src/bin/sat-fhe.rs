@@ -0,0 +1,223 @@
+// Operator tooling: `sat-fhe inspect <file>` prints what an artifact is and its public
+// metadata, without any keys; `sat-fhe bench` times the standard workloads on this
+// machine and prints the performance profile as JSON; `sat-fhe monitor <addr>` (with the
+// `monitor` feature) shows the jobs of a daemon on this host in the terminal;
+// `sat-fhe campaign <file>` checks a campaign file and summarizes what it screens;
+// `sat-fhe screen <owner> <evaluator>` (with the `progress` feature) encrypts, screens and
+// decrypts two bincode trajectories on this machine with a progress bar and ETA per phase,
+// calibrated by a profile saved from `sat-fhe bench`; `sat-fhe profile` (with the
+// `profiling` feature) prints where the time of one workload goes as folded stacks.
+
+use sat_trajectory_fhe::bench::{BenchKernel, STANDARD_LENGTHS, run_profile, standard_workloads};
+use sat_trajectory_fhe::inspect::inspect_file;
+use sat_trajectory_fhe::preset::ParameterPreset;
+
+const USAGE: &str = "usage: sat-fhe inspect <file>
+       sat-fhe bench [--preset <name>] [--lengths <n,...>] [--kernels <eq|box|distance,...>] [--pairs <n>]
+       sat-fhe monitor <addr> [--interval <ms>]
+       sat-fhe campaign <file>
+       sat-fhe profile [--preset <name>] [--kernel <eq|box|distance>] [--steps <n>] [--parallel]
+       sat-fhe screen <owner> <evaluator> [--preset <name>] [--kernel <eq|box|distance>] [--threshold <n>] [--batch <steps>] [--profile <file>]";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [command, path] if command == "inspect" => {
+            print!("{}", inspect_file(path)?);
+            Ok(())
+        }
+        [command, options @ ..] if command == "bench" => bench(options),
+        [command, addr, options @ ..] if command == "monitor" => monitor(addr, options),
+        [command, path] if command == "campaign" => campaign(path),
+        [command, options @ ..] if command == "profile" => profile(options),
+        [command, owner, evaluator, options @ ..] if command == "screen" => {
+            screen(owner, evaluator, options)
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn bench(options: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut preset = ParameterPreset::Default;
+    let mut lengths = STANDARD_LENGTHS.to_vec();
+    let mut kernels = BenchKernel::ALL.to_vec();
+    let mut pairs = 1;
+    for option in options.chunks(2) {
+        match option {
+            [flag, value] if flag == "--preset" => preset = ParameterPreset::from_name(value)?,
+            [flag, value] if flag == "--lengths" => {
+                lengths = value.split(',').map(str::parse).collect::<Result<_, _>>()?
+            }
+            [flag, value] if flag == "--kernels" => {
+                kernels = value
+                    .split(',')
+                    .map(BenchKernel::parse)
+                    .collect::<Result<_, _>>()?
+            }
+            [flag, value] if flag == "--pairs" => pairs = value.parse()?,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let profile = run_profile(preset, &standard_workloads(&kernels, &lengths, pairs))?;
+    println!("{}", profile.to_json());
+    Ok(())
+}
+
+#[cfg(feature = "profiling")]
+fn profile(options: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use sat_trajectory_fhe::bench::{Workload, profile_workload};
+
+    let mut preset = ParameterPreset::Default;
+    let mut kernel = BenchKernel::Eq;
+    let mut steps = 100;
+    let mut parallel_axes = false;
+    let mut options = options.iter();
+    while let Some(flag) = options.next() {
+        if flag == "--parallel" {
+            parallel_axes = true;
+            continue;
+        }
+        let value = options.next().ok_or(USAGE)?;
+        match flag.as_str() {
+            "--preset" => preset = ParameterPreset::from_name(value)?,
+            "--kernel" => kernel = BenchKernel::parse(value)?,
+            "--steps" => steps = value.parse()?,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let workload = Workload {
+        kernel,
+        steps,
+        pairs: 1,
+    };
+    print!(
+        "{}",
+        profile_workload(preset, workload, parallel_axes)?.to_folded()
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "profiling"))]
+fn profile(_options: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    Err("sat-fhe was built without the profiling feature".into())
+}
+
+#[cfg(feature = "monitor")]
+fn monitor(addr: &str, options: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let interval = match options {
+        [] => 1_000,
+        [flag, value] if flag == "--interval" => value.parse()?,
+        _ => return Err(USAGE.into()),
+    };
+    tokio::runtime::Runtime::new()?
+        .block_on(sat_trajectory_fhe::monitor::run_monitor(
+            addr,
+            std::time::Duration::from_millis(interval),
+        ))
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(not(feature = "monitor"))]
+fn monitor(_addr: &str, _options: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    Err("sat-fhe was built without the monitor feature".into())
+}
+
+#[cfg(feature = "serve")]
+fn campaign(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use sat_trajectory_fhe::campaign::{CampaignConfig, PartyRole};
+
+    let campaign = CampaignConfig::load(path).map_err(|e| e.to_string())?;
+    print!("{}", campaign.summary());
+    // Thresholds are only known in the owners' units once their trajectories are read,
+    // and those files are only on the owner's side.
+    for screening in &campaign.screenings {
+        let owner = campaign
+            .party(&screening.owner, PartyRole::Owner)
+            .map_err(|e| e.to_string())?;
+        if owner.trajectory.is_file() {
+            let data = owner.load_trajectory().map_err(|e| e.to_string())?;
+            let config = screening
+                .screening_config(data.units)
+                .map_err(|e| e.to_string())?;
+            println!(
+                "  {} -> {}: {:?} in {:?}",
+                screening.owner, screening.evaluator, config.kernel, data.units
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "serve"))]
+fn campaign(_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("sat-fhe was built without the serve feature".into())
+}
+
+#[cfg(feature = "progress")]
+fn screen(
+    owner: &str,
+    evaluator: &str,
+    options: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use sat_trajectory_fhe::bench::PerformanceProfile;
+    use sat_trajectory_fhe::common::SatelliteData;
+    use sat_trajectory_fhe::kernel::KernelChoice;
+    use sat_trajectory_fhe::progress::screen_local;
+    use sat_trajectory_fhe::screening::ScreeningConfig;
+
+    let mut preset = ParameterPreset::Default;
+    let mut kernel = BenchKernel::Eq;
+    let mut threshold = 0;
+    let mut batch_steps = 64;
+    let mut profile = None;
+    for option in options.chunks(2) {
+        match option {
+            [flag, value] if flag == "--preset" => preset = ParameterPreset::from_name(value)?,
+            [flag, value] if flag == "--kernel" => kernel = BenchKernel::parse(value)?,
+            [flag, value] if flag == "--threshold" => threshold = value.parse()?,
+            [flag, value] if flag == "--batch" => batch_steps = value.parse()?,
+            [flag, value] if flag == "--profile" => {
+                profile = Some(PerformanceProfile::from_json(&std::fs::read_to_string(
+                    value,
+                )?)?)
+            }
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let config = ScreeningConfig {
+        kernel: match kernel {
+            BenchKernel::Eq => KernelChoice::ExactMatch,
+            BenchKernel::Box => KernelChoice::BoxThreshold {
+                half_width: threshold,
+            },
+            BenchKernel::Distance => KernelChoice::SquaredDistanceThreshold { threshold },
+        },
+        ..Default::default()
+    };
+    let owner: SatelliteData = bincode::deserialize(&std::fs::read(owner)?)?;
+    let evaluator: SatelliteData = bincode::deserialize(&std::fs::read(evaluator)?)?;
+    let (flags, _) = screen_local(
+        &owner,
+        &evaluator,
+        preset,
+        &config,
+        batch_steps.max(1),
+        profile.as_ref(),
+    )?;
+    let flagged: Vec<usize> = (0..flags.len()).filter(|&i| flags[i]).collect();
+    println!("{} of {} steps flagged", flagged.len(), flags.len());
+    for step in flagged {
+        println!("  step {}", step);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "progress"))]
+fn screen(
+    _owner: &str,
+    _evaluator: &str,
+    _options: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("sat-fhe was built without the progress feature".into())
+}
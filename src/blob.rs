@@ -0,0 +1,326 @@
+// Storage for the bytes a daemon keeps per job.
+//
+// Uploaded server keys and trajectories, result batches, results and certificates are
+// opaque blobs addressed by a key such as `job-3/server_key.bin`. The daemon only talks
+// to a `BlobStore`, so where they live is a deployment choice (`BlobStoreConfig`): files
+// under `storage_dir` (`FsStore`), which survive a restart and keep memory use flat, or a
+// process-local map (`MemoryStore`) for tests and short-lived evaluators. Other backends
+// (an embedded database, a bucket) only need to implement the trait. `DedupStore` layers
+// content addressing over any of them, for daemons that see the same bytes under many
+// keys.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::transport::sha256_hex;
+
+pub trait BlobStore: Send + Sync {
+    // Stores `bytes` under `key`, replacing any blob there. Readers see the old or the
+    // new blob, never part of one.
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()>;
+    // `None` if there's no blob under `key`.
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+    fn contains(&self, key: &str) -> io::Result<bool>;
+    // Removing a missing blob isn't an error.
+    fn delete(&self, key: &str) -> io::Result<()>;
+
+    // Makes the blobs stored so far survive a crash, e.g. before the daemon exits.
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    // `get` for a blob that must be there.
+    fn read(&self, key: &str) -> io::Result<Vec<u8>> {
+        self.get(key)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no blob {:?}", key)))
+    }
+}
+
+// Backend section of the daemon configuration (`[blob_store]`).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum BlobStoreConfig {
+    // Files under the daemon's `storage_dir`.
+    #[default]
+    Filesystem,
+    // Lost when the daemon stops.
+    Memory,
+}
+
+impl BlobStoreConfig {
+    pub fn open(self, storage_dir: &Path) -> io::Result<Box<dyn BlobStore>> {
+        Ok(match self {
+            BlobStoreConfig::Filesystem => Box::new(FsStore::new(storage_dir)?),
+            BlobStoreConfig::Memory => Box::new(MemoryStore::default()),
+        })
+    }
+}
+
+// One file per blob, the key being its path relative to `dir`.
+#[derive(Debug, Clone)]
+pub struct FsStore {
+    dir: PathBuf,
+    // Files written since the last `flush`, which only the OS has seen.
+    unsynced: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+impl FsStore {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            unsynced: Arc::default(),
+        })
+    }
+
+    // Keys are relative paths that stay inside `dir`.
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(key);
+        let valid = !key.is_empty()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid blob key {:?}", key),
+            ));
+        }
+        Ok(self.dir.join(relative))
+    }
+}
+
+impl BlobStore for FsStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Renamed into place so a blob is never read half-written.
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".partial");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &path)?;
+        self.unsynced.lock().unwrap().insert(path);
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(key)?) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn contains(&self, key: &str) -> io::Result<bool> {
+        Ok(self.path(key)?.is_file())
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match std::fs::remove_file(self.path(key)?) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    // Syncs the files written since the last flush, then their directories, which hold
+    // the renames.
+    fn flush(&self) -> io::Result<()> {
+        let files = std::mem::take(&mut *self.unsynced.lock().unwrap());
+        let mut dirs = BTreeSet::new();
+        for file in files {
+            match std::fs::File::open(&file) {
+                Ok(handle) => handle.sync_all()?,
+                // Deleted since.
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+            dirs.extend(file.parent().map(Path::to_path_buf));
+        }
+        for dir in dirs {
+            #[cfg(unix)]
+            std::fs::File::open(&dir)?.sync_all()?;
+            #[cfg(not(unix))]
+            let _ = dir;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    blobs: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl BlobStore for MemoryStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        self.blobs
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.blobs.lock().unwrap().get(key).cloned())
+    }
+
+    fn contains(&self, key: &str) -> io::Result<bool> {
+        Ok(self.blobs.lock().unwrap().contains_key(key))
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        self.blobs.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+// Marks a blob that only refers to a `DedupStore` object, followed by the object's hex
+// SHA-256.
+const REFERENCE_MAGIC: &[u8] = b"sat-fhe blob ref\n";
+
+// Content-addressed layer over another store. Every distinct blob is kept once, under
+// `objects/<sha256>`; a key only holds a short reference to it, and
+// `objects/<sha256>.refs` counts the keys referring to it, so an object goes with its last
+// key. Catalog trajectories screened against many owners, server keys uploaded for every
+// job and identical result bundles then take their space once.
+//
+// Blobs put before the layer was added stay readable as they are. A crash between storing
+// an object and its reference leaves the object counted once too often, which only keeps
+// it around.
+pub struct DedupStore {
+    inner: Box<dyn BlobStore>,
+    // Serializes reference counting.
+    lock: Mutex<()>,
+}
+
+impl DedupStore {
+    pub fn new(inner: Box<dyn BlobStore>) -> Self {
+        Self {
+            inner,
+            lock: Mutex::new(()),
+        }
+    }
+
+    // The store holding the objects and references.
+    pub fn inner(&self) -> &dyn BlobStore {
+        self.inner.as_ref()
+    }
+
+    // Key of the object with hex SHA-256 `hash`.
+    pub fn object_key(hash: &str) -> String {
+        format!("objects/{}", hash)
+    }
+
+    fn refs_key(hash: &str) -> String {
+        format!("objects/{}.refs", hash)
+    }
+
+    // Hash of the object `key` refers to, or `None` for a missing or plain blob.
+    fn target(&self, key: &str) -> io::Result<Option<String>> {
+        Ok(self
+            .inner
+            .get(key)?
+            .and_then(|bytes| parse_reference(&bytes)))
+    }
+
+    fn refs(&self, hash: &str) -> io::Result<u64> {
+        Ok(match self.inner.get(&Self::refs_key(hash))? {
+            Some(bytes) => u64::from_le_bytes(bytes.try_into().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("corrupt reference count for object {}", hash),
+                )
+            })?),
+            None => 0,
+        })
+    }
+
+    // Drops one reference to `hash`, and the object with the last one.
+    fn release(&self, hash: &str) -> io::Result<()> {
+        match self.refs(hash)? {
+            0 | 1 => {
+                self.inner.delete(&Self::object_key(hash))?;
+                self.inner.delete(&Self::refs_key(hash))
+            }
+            refs => self
+                .inner
+                .put(&Self::refs_key(hash), &(refs - 1).to_le_bytes()),
+        }
+    }
+}
+
+fn parse_reference(bytes: &[u8]) -> Option<String> {
+    let hash = bytes.strip_prefix(REFERENCE_MAGIC)?;
+    (hash.len() == 64 && hash.iter().all(u8::is_ascii_hexdigit))
+        .then(|| String::from_utf8_lossy(hash).into_owned())
+}
+
+impl BlobStore for DedupStore {
+    // The object and its count are written before the reference, so a reader never
+    // follows a reference to nothing.
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let hash = sha256_hex(bytes);
+        let previous = self.target(key)?;
+        if previous.as_deref() == Some(hash.as_str()) {
+            return Ok(());
+        }
+        let object = Self::object_key(&hash);
+        if !self.inner.contains(&object)? {
+            self.inner.put(&object, bytes)?;
+        }
+        let refs = self.refs(&hash)? + 1;
+        self.inner
+            .put(&Self::refs_key(&hash), &refs.to_le_bytes())?;
+        let mut reference = REFERENCE_MAGIC.to_vec();
+        reference.extend(hash.as_bytes());
+        self.inner.put(key, &reference)?;
+        match previous {
+            Some(previous) => self.release(&previous),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(bytes) = self.inner.get(key)? else {
+            return Ok(None);
+        };
+        let Some(hash) = parse_reference(&bytes) else {
+            return Ok(Some(bytes));
+        };
+        self.inner
+            .get(&Self::object_key(&hash))?
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("blob {:?} refers to missing object {}", key, hash),
+                )
+            })
+            .map(Some)
+    }
+
+    fn contains(&self, key: &str) -> io::Result<bool> {
+        self.inner.contains(key)
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let target = self.target(key)?;
+        self.inner.delete(key)?;
+        match target {
+            Some(hash) => self.release(&hash),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,216 @@
+// Reproducibility archive of a finished screening.
+//
+// Months after a screening, an operator may have to show what was screened, with which
+// parameters and software, and what came out, e.g. to a regulator investigating a
+// conjunction. A `Bundle` keeps all of it in one tar file: every message of the session
+// in order (`messages/`), and a `manifest.toml` with the software version, parameter
+// preset, session nonce and transcript, the size and SHA-256 of each message, and the
+// decrypted report. `Bundle::read` checks the archive against its manifest and
+// recomputes the transcript from the messages, so a bundle that reads back is one whose
+// messages are exactly those the two parties agreed on (compare `transcript` with either
+// party's `Session::transcript`).
+//
+// The messages are the ones on the wire, so the server key and ciphertexts are in the
+// bundle, but no secret key is. The decrypted report is what the owner recorded; it can
+// be re-derived only with the owner's client key.
+
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::ConjunctionEvent;
+use crate::preset::ParameterPreset;
+use crate::protocol::{Envelope, MessageKind, SessionNonce};
+use crate::session::extend_transcript;
+use crate::transport::sha256_hex;
+
+const MANIFEST: &str = "manifest.toml";
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    software: String,
+    preset: ParameterPreset,
+    nonce: String,
+    transcript: String,
+    steps: usize,
+    events: Vec<ConjunctionEvent>,
+    messages: Vec<MessageEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MessageEntry {
+    path: String,
+    kind: MessageKind,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    // Name and version of the software that made the bundle.
+    pub software: String,
+    pub preset: ParameterPreset,
+    pub nonce: SessionNonce,
+    pub transcript: [u8; 32],
+    // Decrypted report: how many steps were screened and the events found.
+    pub steps: usize,
+    pub events: Vec<ConjunctionEvent>,
+    // Every message of the session, sent and received, in the order the session saw them.
+    pub messages: Vec<Vec<u8>>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex<const N: usize>(text: &str) -> Result<[u8; N], Box<dyn std::error::Error>> {
+    if text.len() != 2 * N || !text.is_ascii() {
+        return Err(format!("{:?} isn't {} hex bytes", text, N).into());
+    }
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[2 * i..2 * i + 2], 16)?;
+    }
+    Ok(bytes)
+}
+
+// Nonce and transcript of `messages`, which must all belong to one session.
+fn replay(messages: &[Vec<u8>]) -> Result<(SessionNonce, [u8; 32]), Box<dyn std::error::Error>> {
+    let first = messages
+        .first()
+        .ok_or("a bundle needs at least the Hello")?;
+    let nonce = Envelope::from_bytes(first)?.nonce;
+    let mut transcript = [0; 32];
+    for message in messages {
+        if Envelope::from_bytes(message)?.nonce != nonce {
+            return Err("bundled messages belong to different sessions".into());
+        }
+        transcript = extend_transcript(transcript, message);
+    }
+    Ok((nonce, transcript))
+}
+
+impl Bundle {
+    // A bundle of the session made of `messages`, decrypted into `events` over `steps`.
+    pub fn new(
+        preset: ParameterPreset,
+        messages: Vec<Vec<u8>>,
+        steps: usize,
+        events: Vec<ConjunctionEvent>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (nonce, transcript) = replay(&messages)?;
+        Ok(Self {
+            software: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            preset,
+            nonce,
+            transcript,
+            steps,
+            events,
+            messages,
+        })
+    }
+
+    pub fn write(&self, out: impl Write) -> Result<(), Box<dyn std::error::Error>> {
+        let mut entries = Vec::with_capacity(self.messages.len());
+        for (i, message) in self.messages.iter().enumerate() {
+            let kind = Envelope::from_bytes(message)?.kind;
+            entries.push(MessageEntry {
+                path: format!("messages/{:03}-{:?}.bin", i, kind).to_lowercase(),
+                kind,
+                size: message.len() as u64,
+                sha256: sha256_hex(message),
+            });
+        }
+        let manifest = Manifest {
+            software: self.software.clone(),
+            preset: self.preset,
+            nonce: hex(&self.nonce),
+            transcript: hex(&self.transcript),
+            steps: self.steps,
+            events: self.events.clone(),
+            messages: entries,
+        };
+
+        let mut archive = tar::Builder::new(out);
+        append(
+            &mut archive,
+            MANIFEST,
+            toml::to_string(&manifest)?.as_bytes(),
+        )?;
+        for (entry, message) in manifest.messages.iter().zip(&self.messages) {
+            append(&mut archive, &entry.path, message)?;
+        }
+        archive.into_inner()?.flush()?;
+        Ok(())
+    }
+
+    // Reads a bundle written by `write`, failing unless every message matches the
+    // manifest and the messages chain to the recorded transcript.
+    pub fn read(input: impl Read) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut manifest = None;
+        let mut files = Vec::new();
+        let mut archive = tar::Archive::new(input);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            if path == MANIFEST {
+                manifest = Some(toml::from_str::<Manifest>(std::str::from_utf8(&data)?)?);
+            } else {
+                files.push((path, data));
+            }
+        }
+        let manifest = manifest.ok_or("bundle has no manifest")?;
+        if files.len() != manifest.messages.len() {
+            return Err(format!(
+                "manifest lists {} messages, bundle has {} files",
+                manifest.messages.len(),
+                files.len()
+            )
+            .into());
+        }
+
+        let mut messages = Vec::with_capacity(files.len());
+        for entry in &manifest.messages {
+            let (_, data) = files
+                .iter()
+                .find(|(path, _)| *path == entry.path)
+                .ok_or_else(|| format!("bundle lacks {}", entry.path))?;
+            if data.len() as u64 != entry.size || sha256_hex(data) != entry.sha256 {
+                return Err(format!("{} doesn't match its manifest entry", entry.path).into());
+            }
+            if Envelope::from_bytes(data)?.kind != entry.kind {
+                return Err(format!("{} isn't a {:?} message", entry.path, entry.kind).into());
+            }
+            messages.push(data.clone());
+        }
+
+        let bundle = Self::new(manifest.preset, messages, manifest.steps, manifest.events)?;
+        if bundle.nonce != unhex(&manifest.nonce)? {
+            return Err("bundled messages belong to another session".into());
+        }
+        if bundle.transcript != unhex(&manifest.transcript)? {
+            return Err("bundled messages don't reproduce the transcript".into());
+        }
+        Ok(Self {
+            software: manifest.software,
+            ..bundle
+        })
+    }
+}
+
+fn append(
+    archive: &mut tar::Builder<impl Write>,
+    path: &str,
+    data: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, path, data)?;
+    Ok(())
+}
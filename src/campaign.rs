@@ -0,0 +1,339 @@
+// Whole screening campaigns declared in one TOML file.
+//
+// A campaign names its parties and the trajectory file each one holds, the pairs to
+// screen and with which kernel, the window the trajectories cover, how often to screen
+// again and where alerts go. Optionally it carries the daemon configuration of the
+// evaluator serving it, so `sat-fhe-serve --campaign` runs from the same file that
+// `sat-fhe campaign` checks and summarizes. Thresholds are given in meters and converted
+// to each owner's units when its trajectory is loaded, rounding up like keep-out zones
+// (see `keepout`), so one campaign works whatever units the trajectories are encoded in.
+//
+//     name = "geo-75e"
+//     cadence_s = 21600
+//
+//     [window]
+//     start = "2024-06-01T00:00:00Z"
+//     step_s = 1200
+//     steps = 36
+//
+//     [[party]]
+//     name = "SAT-A"
+//     role = "owner"
+//     trajectory = "sat-a.bin"
+//
+//     [[party]]
+//     name = "SAT-B"
+//     role = "evaluator"
+//     trajectory = "sat-b.bin"
+//     address = "screening.example.org:7878"
+//
+//     [[screening]]
+//     owner = "SAT-A"
+//     evaluator = "SAT-B"
+//     kernel = { type = "box", half_width_m = 25000.0 }
+//
+//     [[sink]]
+//     type = "webhook"
+//     url = "https://ops.example.org/conjunctions"
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::alerts::{AlertSink, JsonLinesAlert, WebhookAlert};
+use crate::common::SatelliteData;
+use crate::distance::DISTANCE_CAP;
+use crate::export::{iso8601, parse_epoch};
+use crate::kernel::KernelChoice;
+use crate::screening::ScreeningConfig;
+use crate::serve::ServeConfig;
+use crate::service::ServiceError;
+use crate::units::Units;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CampaignConfig {
+    pub name: String,
+    #[serde(rename = "party")]
+    pub parties: Vec<PartyConfig>,
+    #[serde(rename = "screening")]
+    pub screenings: Vec<CampaignScreening>,
+    // Window of every screening that doesn't set its own.
+    pub window: WindowConfig,
+    // Seconds between screenings of the same pair (see `recurring`); once if unset.
+    #[serde(default)]
+    pub cadence_s: Option<u64>,
+    #[serde(default, rename = "sink")]
+    pub sinks: Vec<SinkConfig>,
+    // Configuration of the daemon serving the campaign, for `sat-fhe-serve --campaign`.
+    #[serde(default)]
+    pub daemon: Option<ServeConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartyRole {
+    Owner,
+    Evaluator,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PartyConfig {
+    pub name: String,
+    pub role: PartyRole,
+    // Bincode-serialized `SatelliteData`, like `ServeConfig::trajectory`.
+    pub trajectory: PathBuf,
+    // Daemon an evaluator is reached at, e.g. "host:7878"; in-process if unset.
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+impl PartyConfig {
+    pub fn load_trajectory(&self) -> Result<SatelliteData, ServiceError> {
+        Ok(bincode::deserialize(&std::fs::read(&self.trajectory)?)?)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WindowConfig {
+    // ISO 8601 UTC, e.g. "2024-06-01T00:00:00Z".
+    #[serde(deserialize_with = "deserialize_epoch")]
+    pub start: u64,
+    pub step_s: u64,
+    pub steps: usize,
+}
+
+fn deserialize_epoch<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    let epoch = parse_epoch(&text).map_err(serde::de::Error::custom)?;
+    if epoch < 0.0 || epoch.fract() != 0.0 {
+        return Err(serde::de::Error::custom(format!(
+            "window start {:?} is not a whole second after 1970",
+            text
+        )));
+    }
+    Ok(epoch as u64)
+}
+
+impl WindowConfig {
+    pub fn epochs(&self) -> Vec<u64> {
+        (0..self.steps as u64)
+            .map(|i| self.start + i * self.step_s)
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), ServiceError> {
+        if self.steps == 0 || self.step_s == 0 {
+            return Err("a window needs at least one step and a non-zero step_s".into());
+        }
+        Ok(())
+    }
+}
+
+// Per-step criterion, with distances in meters.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum KernelConfig {
+    Exact,
+    Box { half_width_m: f64 },
+    Distance { threshold_m: f64 },
+}
+
+impl KernelConfig {
+    // The kernel for trajectories encoded in `units`; distances are rounded up.
+    pub fn choice(&self, units: Units) -> Result<KernelChoice, ServiceError> {
+        let cells = |meters: f64| -> Result<u32, ServiceError> {
+            let cells = (meters / units.meters()).ceil();
+            if !(0.0..DISTANCE_CAP as f64).contains(&cells) {
+                return Err(format!(
+                    "{} m is not between 0 and the distance cap in {:?}",
+                    meters, units
+                )
+                .into());
+            }
+            Ok(cells as u32)
+        };
+        Ok(match *self {
+            KernelConfig::Exact => KernelChoice::ExactMatch,
+            KernelConfig::Box { half_width_m } => KernelChoice::BoxThreshold {
+                half_width: cells(half_width_m)?,
+            },
+            KernelConfig::Distance { threshold_m } => KernelChoice::SquaredDistanceThreshold {
+                threshold: cells(threshold_m)?,
+            },
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CampaignScreening {
+    // Names of an owner and an evaluator party.
+    pub owner: String,
+    pub evaluator: String,
+    pub kernel: KernelConfig,
+    #[serde(default)]
+    pub parallel_axes: bool,
+    // Overrides the campaign's window.
+    #[serde(default)]
+    pub window: Option<WindowConfig>,
+}
+
+impl CampaignScreening {
+    pub fn screening_config(&self, units: Units) -> Result<ScreeningConfig, ServiceError> {
+        Ok(ScreeningConfig {
+            kernel: self.kernel.choice(units)?,
+            parallel_axes: self.parallel_axes,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SinkConfig {
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: Vec<String>,
+    },
+    JsonLines {
+        path: PathBuf,
+    },
+}
+
+impl SinkConfig {
+    // The sink, labelling alerts with `label`, e.g. the campaign name.
+    pub fn sink(&self, label: &str) -> Box<dyn AlertSink> {
+        match self {
+            SinkConfig::Webhook { url, headers } => Box::new(WebhookAlert {
+                url: url.clone(),
+                label: label.to_string(),
+                headers: headers.clone(),
+            }),
+            SinkConfig::JsonLines { path } => Box::new(JsonLinesAlert {
+                path: path.clone(),
+                label: label.to_string(),
+            }),
+        }
+    }
+}
+
+impl CampaignConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ServiceError> {
+        let config: CampaignConfig = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ServiceError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    fn validate(&self) -> Result<(), ServiceError> {
+        let mut names = HashSet::new();
+        for party in &self.parties {
+            if !names.insert(party.name.as_str()) {
+                return Err(format!("party {:?} is declared twice", party.name).into());
+            }
+        }
+        if self.screenings.is_empty() {
+            return Err("a campaign needs at least one screening".into());
+        }
+        self.window.validate()?;
+        for screening in &self.screenings {
+            self.party(&screening.owner, PartyRole::Owner)?;
+            self.party(&screening.evaluator, PartyRole::Evaluator)?;
+            if let Some(window) = &screening.window {
+                window.validate()?;
+            }
+            // Whether the thresholds fit at all; the owner's units are only known once
+            // its trajectory is loaded.
+            screening.kernel.choice(Units::Kilometers)?;
+        }
+        if self.cadence_s == Some(0) {
+            return Err("cadence_s must be at least 1".into());
+        }
+        if let Some(daemon) = &self.daemon {
+            daemon.validate()?;
+        }
+        Ok(())
+    }
+
+    // The party called `name`, which must have `role`.
+    pub fn party(&self, name: &str, role: PartyRole) -> Result<&PartyConfig, ServiceError> {
+        let party = self
+            .parties
+            .iter()
+            .find(|party| party.name == name)
+            .ok_or_else(|| format!("no party named {:?}", name))?;
+        if party.role != role {
+            let expected = match role {
+                PartyRole::Owner => "an owner",
+                PartyRole::Evaluator => "an evaluator",
+            };
+            return Err(format!("party {:?} is not {}", name, expected).into());
+        }
+        Ok(party)
+    }
+
+    pub fn window_of<'a>(&'a self, screening: &'a CampaignScreening) -> &'a WindowConfig {
+        screening.window.as_ref().unwrap_or(&self.window)
+    }
+
+    pub fn cadence(&self) -> Option<Duration> {
+        self.cadence_s.map(Duration::from_secs)
+    }
+
+    pub fn sinks(&self) -> Vec<Box<dyn AlertSink>> {
+        self.sinks
+            .iter()
+            .map(|sink| sink.sink(&self.name))
+            .collect()
+    }
+
+    // One line per screening, for operators checking a campaign before running it.
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "campaign {}: {} parties, {} screenings, {}\n",
+            self.name,
+            self.parties.len(),
+            self.screenings.len(),
+            match self.cadence_s {
+                Some(s) => format!("every {} s", s),
+                None => "once".to_string(),
+            }
+        );
+        for screening in &self.screenings {
+            let window = self.window_of(screening);
+            let evaluator = self
+                .party(&screening.evaluator, PartyRole::Evaluator)
+                .ok()
+                .and_then(|party| party.address.as_deref())
+                .unwrap_or("in-process");
+            text.push_str(&format!(
+                "  {} -> {} ({}): {:?}, {} steps of {} s from {}\n",
+                screening.owner,
+                screening.evaluator,
+                evaluator,
+                screening.kernel,
+                window.steps,
+                window.step_s,
+                iso8601(window.start)
+            ));
+        }
+        // Headers may hold tokens, so only where alerts go is shown.
+        for sink in &self.sinks {
+            let target = match sink {
+                SinkConfig::Webhook { url, .. } => format!("webhook {}", url),
+                SinkConfig::JsonLines { path } => format!("json lines {}", path.display()),
+            };
+            text.push_str(&format!("  alerts: {}\n", target));
+        }
+        text
+    }
+}
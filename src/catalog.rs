@@ -0,0 +1,253 @@
+// Public catalog pre-processing: turns two-line element sets (as published by Celestrak or
+// Space-Track) into the plaintext `SatelliteData` an evaluator screens against.
+//
+// Propagation is plain two-body Keplerian motion from the TLE mean elements. That is not
+// SGP4 and drifts by kilometres per day, which is acceptable for building a coarse
+// screening set but not for precise conjunction assessment.
+
+use std::f64::consts::PI;
+
+use crate::common::SatelliteData;
+use crate::frame::{Frame, convert};
+use crate::regime::AltitudeBand;
+use crate::units::Units;
+
+const MU_EARTH_KM3_S2: f64 = 398600.4418;
+pub const EARTH_RADIUS_KM: f64 = 6_378.137;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tle {
+    pub name: String,
+    pub norad_id: u32,
+    // Epoch as seconds since the Unix epoch (UTC).
+    pub epoch_unix_s: f64,
+    pub inclination_deg: f64,
+    pub raan_deg: f64,
+    pub eccentricity: f64,
+    pub arg_perigee_deg: f64,
+    pub mean_anomaly_deg: f64,
+    pub mean_motion_rev_per_day: f64,
+}
+
+impl Tle {
+    pub fn semi_major_axis_km(&self) -> f64 {
+        let n = self.mean_motion_rev_per_day * 2.0 * PI / 86_400.0;
+        (MU_EARTH_KM3_S2 / (n * n)).cbrt()
+    }
+
+    // Perigee and apogee altitudes above the equatorial radius.
+    pub fn perigee_apogee_km(&self) -> (f64, f64) {
+        let a = self.semi_major_axis_km();
+        (
+            a * (1.0 - self.eccentricity) - EARTH_RADIUS_KM,
+            a * (1.0 + self.eccentricity) - EARTH_RADIUS_KM,
+        )
+    }
+
+    pub fn altitude_band(&self) -> Result<AltitudeBand, Box<dyn std::error::Error>> {
+        let (perigee, apogee) = self.perigee_apogee_km();
+        AltitudeBand::of_orbit(perigee, apogee)
+    }
+
+    // Position (km, TEME-like inertial frame) at `unix_s`.
+    pub fn position_km(&self, unix_s: f64) -> [f64; 3] {
+        let n = self.mean_motion_rev_per_day * 2.0 * PI / 86_400.0;
+        let a = self.semi_major_axis_km();
+        let e = self.eccentricity;
+        let m = self.mean_anomaly_deg.to_radians() + n * (unix_s - self.epoch_unix_s);
+        let m = m.rem_euclid(2.0 * PI);
+
+        // Kepler's equation by Newton iteration.
+        let mut ecc_anomaly = if e < 0.8 { m } else { PI };
+        for _ in 0..50 {
+            let delta = (ecc_anomaly - e * ecc_anomaly.sin() - m) / (1.0 - e * ecc_anomaly.cos());
+            ecc_anomaly -= delta;
+            if delta.abs() < 1e-12 {
+                break;
+            }
+        }
+
+        let nu = 2.0
+            * ((1.0 + e).sqrt() * (ecc_anomaly / 2.0).sin())
+                .atan2((1.0 - e).sqrt() * (ecc_anomaly / 2.0).cos());
+        let r = a * (1.0 - e * ecc_anomaly.cos());
+
+        let (sin_raan, cos_raan) = self.raan_deg.to_radians().sin_cos();
+        let (sin_i, cos_i) = self.inclination_deg.to_radians().sin_cos();
+        let (sin_u, cos_u) = (self.arg_perigee_deg.to_radians() + nu).sin_cos();
+        [
+            r * (cos_raan * cos_u - sin_raan * sin_u * cos_i),
+            r * (sin_raan * cos_u + cos_raan * sin_u * cos_i),
+            r * (sin_u * sin_i),
+        ]
+    }
+}
+
+// Parses a TLE file in the three-line format (name line followed by lines 1 and 2).
+pub fn parse_tle(text: &str) -> Result<Vec<Tle>, Box<dyn std::error::Error>> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if !lines.len().is_multiple_of(3) {
+        return Err(format!("expected groups of three lines, got {} lines", lines.len()).into());
+    }
+    lines
+        .chunks(3)
+        .map(|group| parse_tle_group(group[0], group[1], group[2]))
+        .collect()
+}
+
+fn parse_tle_group(
+    name: &str,
+    line1: &str,
+    line2: &str,
+) -> Result<Tle, Box<dyn std::error::Error>> {
+    // The fields are fixed columns, so byte offsets only line up (and only land on char
+    // boundaries) when both data lines are ASCII.
+    if !line1.is_ascii()
+        || !line2.is_ascii()
+        || !line1.starts_with("1 ")
+        || !line2.starts_with("2 ")
+        || line1.len() < 32
+        || line2.len() < 63
+    {
+        return Err(format!("malformed TLE for {}", name.trim()).into());
+    }
+    let field = |line: &str, from: usize, to: usize| -> Result<f64, Box<dyn std::error::Error>> {
+        Ok(line[from - 1..to].trim().parse::<f64>()?)
+    };
+
+    let year = field(line1, 19, 20)? as i64;
+    let year = if year < 57 { 2000 + year } else { 1900 + year };
+    let day_of_year = field(line1, 21, 32)?;
+    let epoch_unix_s =
+        days_from_civil(year, 1, 1) as f64 * 86_400.0 + (day_of_year - 1.0) * 86_400.0;
+
+    Ok(Tle {
+        name: name.trim().trim_start_matches("0 ").to_string(),
+        norad_id: line1[2..7].trim().parse()?,
+        epoch_unix_s,
+        inclination_deg: field(line2, 9, 16)?,
+        raan_deg: field(line2, 18, 25)?,
+        eccentricity: format!("0.{}", line2[26..33].trim()).parse()?,
+        arg_perigee_deg: field(line2, 35, 42)?,
+        mean_anomaly_deg: field(line2, 44, 51)?,
+        mean_motion_rev_per_day: field(line2, 53, 63)?,
+    })
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Which catalog objects are worth screening against the user's satellite.
+#[derive(Debug, Clone, Copy)]
+pub struct CatalogFilter {
+    // Altitude band the user's satellite occupies; objects whose perigee..apogee range
+    // doesn't overlap it are dropped.
+    pub band: AltitudeBand,
+    // Keep only objects within `inclination_tolerance_deg` of `inclination_deg`, if set.
+    pub inclination_deg: Option<f64>,
+    pub inclination_tolerance_deg: f64,
+}
+
+impl CatalogFilter {
+    pub fn accepts(&self, tle: &Tle) -> bool {
+        // A TLE whose elements give no finite band (e.g. zero mean motion) can't be screened.
+        if !tle
+            .altitude_band()
+            .is_ok_and(|band| band.overlaps(&self.band))
+        {
+            return false;
+        }
+        match self.inclination_deg {
+            Some(inc) => (tle.inclination_deg - inc).abs() <= self.inclination_tolerance_deg,
+            None => true,
+        }
+    }
+}
+
+// Time grid shared by both parties: `steps` samples every `step_s` seconds from `start_unix_s`.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeGrid {
+    pub start_unix_s: f64,
+    pub step_s: f64,
+    pub steps: usize,
+}
+
+pub struct CatalogObject {
+    pub name: String,
+    pub norad_id: u32,
+    pub trajectory: SatelliteData,
+}
+
+// Samples `tle` on `grid`, expressed in `frame` and rounded to whole `units` (coarser
+// units make exact-match screening more tolerant).
+pub fn propagate(
+    tle: &Tle,
+    grid: &TimeGrid,
+    units: Units,
+    frame: Frame,
+) -> Result<SatelliteData, Box<dyn std::error::Error>> {
+    let positions: Vec<[f64; 3]> = (0..grid.steps)
+        .map(|i| {
+            let t = grid.start_unix_s + i as f64 * grid.step_s;
+            convert(tle.position_km(t), t, Frame::Eci, frame)
+        })
+        .collect();
+    SatelliteData::encode(&positions, Units::Kilometers, frame)?.to_units(units)
+}
+
+// Filter + propagate: produces the plaintext screening set from a parsed catalog.
+pub fn screening_set(
+    tles: &[Tle],
+    filter: &CatalogFilter,
+    grid: &TimeGrid,
+    units: Units,
+    frame: Frame,
+) -> Result<Vec<CatalogObject>, Box<dyn std::error::Error>> {
+    tles.iter()
+        .filter(|tle| filter.accepts(tle))
+        .map(|tle| {
+            Ok(CatalogObject {
+                name: tle.name.clone(),
+                norad_id: tle.norad_id,
+                trajectory: propagate(tle, grid, units, frame)?,
+            })
+        })
+        .collect()
+}
+
+pub fn celestrak_url(group: &str) -> String {
+    format!(
+        "https://celestrak.org/NORAD/elements/gp.php?GROUP={}&FORMAT=tle",
+        group
+    )
+}
+
+// Downloads a Celestrak GP group (e.g. "active", "stations") in TLE format. Runs the
+// system `curl`, which must be on `PATH`, so the crate doesn't pull in an HTTP client for
+// this one call. Behind the opt-in `celestrak` feature so that no default build spawns it.
+#[cfg(feature = "celestrak")]
+pub fn fetch_celestrak(group: &str) -> Result<Vec<Tle>, Box<dyn std::error::Error>> {
+    let output = std::process::Command::new("curl")
+        .args(["-sSfL", &celestrak_url(group)])
+        .output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "curl failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    parse_tle(&String::from_utf8(output.stdout)?)
+}
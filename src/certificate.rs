@@ -0,0 +1,231 @@
+// Evaluator work certificate: a hash chain over the ciphertexts a screening processed.
+//
+// The owner can't see what the evaluator computed, only the flags it got back. An
+// evaluator that skips part of the work (e.g. returns fresh encryptions of `false` for a
+// chunk it never screened, since it holds the public key material to make them), or hands
+// chunks back in the wrong order, goes unnoticed. Along with the results, the evaluator
+// therefore returns a `WorkCertificate`: for every chunk of steps it screened, in the
+// order it screened them, the SHA-256 of the exact input ciphertexts (the owner's
+// coordinates at those steps) and of the output flags, each chunk chained to the one
+// before. `verify` recomputes it from the owner's own ciphertexts and the flags received
+// and requires the chunks to cover the trajectory once, in step order.
+//
+// The certificate commits the evaluator to which inputs it claims to have used for which
+// outputs; it doesn't prove the outputs were computed from them. Combined with spot
+// checks (see `spotcheck`), skipping work undetected gets much harder.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tfhe::named::Named;
+use tfhe::{FheBool, Versionize};
+
+use crate::common::{SizeLimited, safe_serialize_item};
+use crate::migrate::{self, ArtifactKind};
+use crate::trajectory::EncryptedTrajectory;
+
+const DOMAIN: &[u8] = b"sat-fhe work certificate";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkCommitment {
+    // Absolute index of the chunk's first step.
+    pub first_index: usize,
+    pub steps: usize,
+    // SHA-256 of the chunk's input ciphertexts (x, y, z per step).
+    pub inputs: [u8; 32],
+    // SHA-256 of the chunk's output flags.
+    pub outputs: [u8; 32],
+    // Chain value after this chunk: SHA-256 of the previous one and the fields above.
+    pub link: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkCertificate {
+    pub chunks: Vec<ChunkCommitment>,
+}
+
+// Feeds `bytes`, a serialized item, to `hasher` with its length in front.
+fn hash_bytes(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
+
+fn hash_item<T>(hasher: &mut Sha256, item: &T) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Serialize + Versionize + Named + SizeLimited,
+{
+    hash_bytes(hasher, &safe_serialize_item(item)?);
+    Ok(())
+}
+
+fn commit(
+    previous: [u8; 32],
+    encrypted: &EncryptedTrajectory,
+    steps: Range<usize>,
+    flags: &[FheBool],
+) -> Result<ChunkCommitment, Box<dyn std::error::Error>> {
+    let mut inputs = Sha256::new();
+    for i in steps.clone() {
+        hash_item(&mut inputs, &encrypted.x[i])?;
+        hash_item(&mut inputs, &encrypted.y[i])?;
+        hash_item(&mut inputs, &encrypted.z[i])?;
+    }
+    let mut outputs = Sha256::new();
+    for flag in flags {
+        hash_item(&mut outputs, flag)?;
+    }
+    Ok(chain(
+        previous,
+        encrypted.absolute_index(steps.start),
+        steps.len(),
+        inputs,
+        outputs,
+    ))
+}
+
+// The chunk of `steps` steps from absolute step `first_index`, chained to `previous`.
+fn chain(
+    previous: [u8; 32],
+    first_index: usize,
+    steps: usize,
+    inputs: Sha256,
+    outputs: Sha256,
+) -> ChunkCommitment {
+    let inputs: [u8; 32] = inputs.finalize().into();
+    let outputs: [u8; 32] = outputs.finalize().into();
+    let mut link = Sha256::new();
+    link.update(DOMAIN);
+    link.update(previous);
+    link.update((first_index as u64).to_le_bytes());
+    link.update((steps as u64).to_le_bytes());
+    link.update(inputs);
+    link.update(outputs);
+    ChunkCommitment {
+        first_index,
+        steps,
+        inputs,
+        outputs,
+        link: link.finalize().into(),
+    }
+}
+
+impl WorkCertificate {
+    // Final chain value; all zeroes for no work.
+    pub fn head(&self) -> [u8; 32] {
+        self.chunks.last().map_or([0; 32], |chunk| chunk.link)
+    }
+
+    // Commits to having screened positions `steps` of `encrypted` into `flags`.
+    pub fn record(
+        &mut self,
+        encrypted: &EncryptedTrajectory,
+        steps: Range<usize>,
+        flags: &[FheBool],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if steps.len() != flags.len() || steps.end > encrypted.len() {
+            return Err(format!(
+                "{} flags for steps {}..{} of {}",
+                flags.len(),
+                steps.start,
+                steps.end,
+                encrypted.len()
+            )
+            .into());
+        }
+        let chunk = commit(self.head(), encrypted, steps, flags)?;
+        self.chunks.push(chunk);
+        Ok(())
+    }
+
+    // `record` for steps known only by their serialized ciphertexts: the x, y and z items
+    // of each step from absolute step `first_index` on, and one flag item per step.
+    #[cfg(feature = "mock-server")]
+    pub(crate) fn record_serialized(
+        &mut self,
+        first_index: usize,
+        inputs: &[[&[u8]; 3]],
+        flags: &[Vec<u8>],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if inputs.len() != flags.len() {
+            return Err(format!("{} flags for {} steps", flags.len(), inputs.len()).into());
+        }
+        let mut input_hash = Sha256::new();
+        for step in inputs {
+            for item in step {
+                hash_bytes(&mut input_hash, item);
+            }
+        }
+        let mut output_hash = Sha256::new();
+        for flag in flags {
+            hash_bytes(&mut output_hash, flag);
+        }
+        let chunk = chain(
+            self.head(),
+            first_index,
+            inputs.len(),
+            input_hash,
+            output_hash,
+        );
+        self.chunks.push(chunk);
+        Ok(())
+    }
+
+    // Checks the certificate against the owner's `encrypted` trajectory and the `flags`
+    // received for it: its chunks cover every step once, in order, and commit to exactly
+    // these inputs and outputs.
+    pub fn verify(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        flags: &[FheBool],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if flags.len() != encrypted.len() {
+            return Err(format!(
+                "{} flags for a trajectory of {} steps",
+                flags.len(),
+                encrypted.len()
+            )
+            .into());
+        }
+        let mut expected = WorkCertificate::default();
+        for (n, chunk) in self.chunks.iter().enumerate() {
+            let start = expected
+                .chunks
+                .last()
+                .map_or(0, |c| c.first_index + c.steps - encrypted.first_index);
+            if chunk.first_index != encrypted.absolute_index(start)
+                || chunk.steps == 0
+                || start + chunk.steps > encrypted.len()
+            {
+                return Err(format!("chunk {} skips, repeats or reorders steps", n).into());
+            }
+            let steps = start..start + chunk.steps;
+            expected.record(encrypted, steps.clone(), &flags[steps])?;
+            if expected.chunks.last() != Some(chunk) {
+                return Err(format!(
+                    "chunk {} doesn't commit to the ciphertexts screened and returned",
+                    n
+                )
+                .into());
+            }
+        }
+        let covered = expected.chunks.iter().map(|c| c.steps).sum::<usize>();
+        if covered != encrypted.len() {
+            return Err(format!(
+                "certificate covers {} of {} steps",
+                covered,
+                encrypted.len()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        migrate::encode(ArtifactKind::WorkCertificate, self)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        migrate::decode(ArtifactKind::WorkCertificate, data)
+    }
+}
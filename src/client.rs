@@ -0,0 +1,559 @@
+// Owner-side SDK for the `sat-fhe-serve` daemon: typed calls that hide the framing and
+// session envelopes of `service`.
+//
+// Uploads larger than the chunk size go in hashed pieces. A piece the daemon finds
+// corrupted or never got is sent again, and a dropped connection is reopened, each up to
+// `MAX_CHUNK_ATTEMPTS` times, so a flaky link doesn't restart a multi-GB upload.
+// `open_tuned_session` picks the piece size and the streaming batch size for the link
+// and daemon at hand (see `tuning`).
+
+use std::time::{Duration, Instant};
+
+use tfhe::FheBool;
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::certificate::WorkCertificate;
+use crate::net::{TcpTransport, Transport};
+use crate::preset::ParameterPreset;
+use crate::protocol::{MessageKind, ProtocolError, SessionMetadata};
+use crate::reveal::RevealedResult;
+use crate::service::{
+    CHUNK_BYTES, JobHandle, JobId, JobStatus, JobToken, JobsSnapshot, Request, Response,
+    ServiceError, read_frame, write_frame,
+};
+use crate::session::Session;
+use crate::stream::ResultBatch;
+use crate::tls::{TlsConfig, TlsTransport};
+use crate::trajectory::EncryptedTrajectory;
+use crate::transport::{ArtifactPointer, ArtifactRef, sha256_hex};
+use crate::tuning::{LinkBenchmark, SessionTuning, tune};
+
+// Tries per request of a chunked upload, and rounds of re-sending missing pieces.
+pub const MAX_CHUNK_ATTEMPTS: usize = 5;
+
+// Payload of the request `benchmark` measures throughput with.
+pub const PROBE_BYTES: usize = 1 << 20;
+
+// Session and screening errors are not `Send`; keep their message.
+fn local(err: Box<dyn std::error::Error>) -> ServiceError {
+    err.to_string().into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub preset: ParameterPreset,
+    pub max_jobs: usize,
+}
+
+// A job opened on the daemon, together with the owner's side of its session.
+#[derive(Debug)]
+pub struct RemoteJob {
+    pub id: JobId,
+    // Proves to the daemon that the job is ours.
+    token: JobToken,
+    session: Session,
+    // Streamed result batches fetched so far.
+    batches: usize,
+    // Runs whose results `next_run` returned.
+    runs: usize,
+}
+
+impl RemoteJob {
+    fn handle(&self) -> JobHandle {
+        JobHandle {
+            id: self.id,
+            token: self.token,
+        }
+    }
+}
+
+pub struct Client<T: Transport = TcpTransport> {
+    stream: T::Conn,
+    // Reconnects after a dropped connection.
+    transport: T,
+    chunk_bytes: usize,
+}
+
+impl Client {
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self, ServiceError> {
+        let stream = TcpStream::connect(addr).await?;
+        let transport = TcpTransport {
+            addr: stream.peer_addr()?,
+        };
+        Ok(Self::with_stream(transport, stream))
+    }
+}
+
+impl Client<TlsTransport> {
+    // Connects with mutual TLS, authenticating the daemon by its pinned certificate.
+    pub async fn connect_tls(
+        addr: impl ToSocketAddrs,
+        config: &TlsConfig,
+    ) -> Result<Self, ServiceError> {
+        let addr = tokio::net::lookup_host(addr)
+            .await?
+            .next()
+            .ok_or("address resolved to nothing")?;
+        Self::over(TlsTransport::new(TcpTransport { addr }, config)?).await
+    }
+}
+
+impl<T: Transport> Client<T> {
+    // Connects over `transport` (see `net`).
+    pub async fn over(transport: T) -> Result<Self, ServiceError> {
+        let stream = transport.connect().await?;
+        Ok(Self::with_stream(transport, stream))
+    }
+
+    fn with_stream(transport: T, stream: T::Conn) -> Self {
+        Self {
+            stream,
+            transport,
+            chunk_bytes: CHUNK_BYTES,
+        }
+    }
+
+    // Size of the pieces large uploads are split into; `service::CHUNK_BYTES` by default.
+    pub fn with_chunk_bytes(mut self, chunk_bytes: usize) -> Self {
+        self.chunk_bytes = chunk_bytes.max(1);
+        self
+    }
+
+    async fn call(&mut self, request: Request) -> Result<Response, ServiceError> {
+        write_frame(&mut self.stream, &request).await?;
+        match read_frame(&mut self.stream).await? {
+            Response::Error(message) => Err(message.into()),
+            Response::QuotaExceeded(err) => Err(err.into()),
+            response => Ok(response),
+        }
+    }
+
+    pub async fn info(&mut self) -> Result<ServerInfo, ServiceError> {
+        match self.call(Request::Info).await? {
+            Response::Info { preset, max_jobs } => Ok(ServerInfo { preset, max_jobs }),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    // Opens a new session with the daemon, announcing `metadata`.
+    pub async fn open_session(
+        &mut self,
+        metadata: &SessionMetadata,
+    ) -> Result<RemoteJob, ServiceError> {
+        let mut session = Session::open().map_err(local)?;
+        let hello = session.hello(metadata).map_err(local)?;
+        match self.call(Request::OpenSession { hello }).await? {
+            Response::SessionOpened { job } => Ok(RemoteJob {
+                id: job.id,
+                token: job.token,
+                session,
+                batches: 0,
+                runs: 0,
+            }),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    // Times an empty request and one carrying `PROBE_BYTES`, and asks the daemon for its
+    // step time.
+    pub async fn benchmark(&mut self) -> Result<LinkBenchmark, ServiceError> {
+        let mut timed = async |probe: Vec<u8>| {
+            let started = Instant::now();
+            match self.call(Request::Benchmark { probe }).await? {
+                Response::Benchmark { step_time } => Ok((started.elapsed(), step_time)),
+                other => Err(unexpected(other)),
+            }
+        };
+        let (rtt, _) = timed(Vec::new()).await?;
+        let (probe_time, step_time) = timed(vec![0; PROBE_BYTES]).await?;
+        let transfer = probe_time.saturating_sub(rtt).max(Duration::from_micros(1));
+        Ok(LinkBenchmark {
+            rtt,
+            bytes_per_second: PROBE_BYTES as f64 / transfer.as_secs_f64(),
+            step_time,
+        })
+    }
+
+    // `open_session`, then sizes upload pieces and result batches for this session from a
+    // `benchmark` of the link and the daemon.
+    pub async fn open_tuned_session(
+        &mut self,
+        metadata: &SessionMetadata,
+    ) -> Result<(RemoteJob, SessionTuning), ServiceError> {
+        let tuning = tune(&self.benchmark().await?);
+        let job = self.open_session(metadata).await?;
+        let request = Request::TuneSession {
+            job: job.handle(),
+            batch_steps: tuning.batch_steps,
+        };
+        match self.call(request).await? {
+            Response::Tuned => {}
+            other => return Err(unexpected(other)),
+        }
+        self.chunk_bytes = tuning.chunk_bytes;
+        Ok((job, tuning))
+    }
+
+    // `server_key` is the serialized server key, as from `OwnerParty::server_key_bytes`.
+    pub async fn upload_server_key(
+        &mut self,
+        job: &mut RemoteJob,
+        server_key: Vec<u8>,
+    ) -> Result<(), ServiceError> {
+        self.upload(job, MessageKind::ServerKey, server_key).await
+    }
+
+    pub async fn upload_trajectory(
+        &mut self,
+        job: &mut RemoteJob,
+        trajectory: &EncryptedTrajectory,
+    ) -> Result<(), ServiceError> {
+        let payload = trajectory.to_bytes().map_err(local)?;
+        self.upload(job, MessageKind::EncryptedTrajectory, payload)
+            .await
+    }
+
+    // Sends `artifact`, already placed in an object store the daemon can read (see
+    // `transport::publish`), in place of the artifact itself.
+    pub async fn upload_artifact_ref(
+        &mut self,
+        job: &mut RemoteJob,
+        kind: MessageKind,
+        artifact: ArtifactRef,
+    ) -> Result<(), ServiceError> {
+        let pointer = bincode::serialize(&ArtifactPointer { kind, artifact })?;
+        self.upload(job, MessageKind::ArtifactRef, pointer).await
+    }
+
+    async fn upload(
+        &mut self,
+        job: &mut RemoteJob,
+        kind: MessageKind,
+        payload: Vec<u8>,
+    ) -> Result<(), ServiceError> {
+        let envelope = job.session.send(kind, payload).map_err(local)?;
+        if envelope.len() > self.chunk_bytes {
+            return self.upload_chunked(job, envelope).await;
+        }
+        match self
+            .call(Request::Upload {
+                job: job.handle(),
+                envelope,
+            })
+            .await?
+        {
+            Response::Uploaded => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn upload_chunked(
+        &mut self,
+        job: &RemoteJob,
+        envelope: Vec<u8>,
+    ) -> Result<(), ServiceError> {
+        let chunks: Vec<&[u8]> = envelope.chunks(self.chunk_bytes).collect();
+        let begin = Request::BeginChunkedUpload {
+            job: job.handle(),
+            chunks: chunks.iter().map(|chunk| sha256_hex(chunk)).collect(),
+        };
+        let mut missing = match self.call_retrying(begin).await? {
+            Response::MissingChunks(missing) => missing,
+            other => return Err(unexpected(other)),
+        };
+        for _ in 0..MAX_CHUNK_ATTEMPTS {
+            for index in missing {
+                let chunk = chunks
+                    .get(index)
+                    .ok_or("the daemon asked for a chunk out of range")?;
+                let request = Request::UploadChunk {
+                    job: job.handle(),
+                    index,
+                    data: chunk.to_vec(),
+                };
+                // A corrupted piece is dropped by the daemon and reported missing below.
+                match self.call_retrying(request).await? {
+                    Response::ChunkReceived { .. } => {}
+                    other => return Err(unexpected(other)),
+                }
+            }
+            missing = match self
+                .call_retrying(Request::FinishChunkedUpload { job: job.handle() })
+                .await?
+            {
+                Response::Uploaded => return Ok(()),
+                Response::MissingChunks(missing) => missing,
+                other => return Err(unexpected(other)),
+            };
+        }
+        Err(format!(
+            "{} chunk(s) still missing after {} attempts",
+            missing.len(),
+            MAX_CHUNK_ATTEMPTS
+        )
+        .into())
+    }
+
+    // `call`, reconnecting and sending `request` again if the connection fails.
+    async fn call_retrying(&mut self, request: Request) -> Result<Response, ServiceError> {
+        let mut attempt = 1;
+        loop {
+            match self.call(request.clone()).await {
+                Err(err) if attempt < MAX_CHUNK_ATTEMPTS && is_io(&*err) => {
+                    attempt += 1;
+                    self.stream = self.transport.connect().await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Progress of every job on the daemon and its recent alerts; only answered when
+    // connected from the daemon's host.
+    pub async fn jobs(&mut self) -> Result<JobsSnapshot, ServiceError> {
+        match self.call(Request::Jobs).await? {
+            Response::Jobs(snapshot) => Ok(snapshot),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn status(&mut self, job: &RemoteJob) -> Result<JobStatus, ServiceError> {
+        match self.call(Request::Status { job: job.handle() }).await? {
+            Response::Status(status) => Ok(status),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    // Polls every `interval` until the job has finished; a failed job is an error.
+    pub async fn wait(&mut self, job: &RemoteJob, interval: Duration) -> Result<(), ServiceError> {
+        loop {
+            match self.status(job).await? {
+                JobStatus::Done => return Ok(()),
+                JobStatus::Failed(reason) => {
+                    return Err(format!("job {} failed: {}", job.id, reason).into());
+                }
+                JobStatus::Cancelled { steps } => {
+                    return Err(
+                        format!("job {} was cancelled after {} steps", job.id, steps).into(),
+                    );
+                }
+                _ => tokio::time::sleep(interval).await,
+            }
+        }
+    }
+
+    // Encrypted per-step flags of a finished job, for the owner to decrypt.
+    // Per-step flags; for sessions with another reveal policy use `revealed_results`.
+    pub async fn results(&mut self, job: &mut RemoteJob) -> Result<Vec<FheBool>, ServiceError> {
+        match self.revealed_results(job).await? {
+            RevealedResult::PerIndex(flags) => Ok(flags),
+            other => Err(format!(
+                "the daemon released a {:?} result, not per-step flags",
+                other.policy()
+            )
+            .into()),
+        }
+    }
+
+    // The evaluator's record of the ciphertexts it screened (see `certificate`).
+    pub async fn certificate(&mut self, job: &RemoteJob) -> Result<WorkCertificate, ServiceError> {
+        match self
+            .call(Request::Certificate { job: job.handle() })
+            .await?
+        {
+            Response::Certificate { certificate } => {
+                WorkCertificate::from_bytes(&certificate).map_err(local)
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    // `results`, checked against the job's work certificate for `trajectory`, the one
+    // uploaded.
+    pub async fn verified_results(
+        &mut self,
+        job: &mut RemoteJob,
+        trajectory: &EncryptedTrajectory,
+    ) -> Result<Vec<FheBool>, ServiceError> {
+        let flags = self.results(job).await?;
+        let certificate = self.certificate(job).await?;
+        certificate.verify(trajectory, &flags).map_err(local)?;
+        Ok(flags)
+    }
+
+    // Stops the job and polls every `interval` until it has, returning its final status:
+    // `Cancelled`, or `Done` if it finished first. The steps already screened can still be
+    // fetched with `partial_results`.
+    pub async fn cancel(
+        &mut self,
+        job: &RemoteJob,
+        interval: Duration,
+    ) -> Result<JobStatus, ServiceError> {
+        match self.call(Request::Cancel { job: job.handle() }).await? {
+            Response::Cancelling => {}
+            other => return Err(unexpected(other)),
+        }
+        loop {
+            match self.status(job).await? {
+                status if status.is_finished() => return Ok(status),
+                _ => tokio::time::sleep(interval).await,
+            }
+        }
+    }
+
+    // Flags of the steps a cancelled (or finished) job screened, from the first step of
+    // `trajectory`, the one uploaded, checked against the job's work certificate.
+    pub async fn partial_results(
+        &mut self,
+        job: &mut RemoteJob,
+        trajectory: &EncryptedTrajectory,
+    ) -> Result<ResultBatch, ServiceError> {
+        let envelope = match self
+            .call(Request::PartialResults { job: job.handle() })
+            .await?
+        {
+            Response::PartialResults { envelope } => envelope,
+            other => return Err(unexpected(other)),
+        };
+        let envelope = job.session.receive(&envelope).map_err(local)?;
+        if envelope.kind != MessageKind::ResultBatch {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::ResultBatch,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        let mut batch = ResultBatch::from_bytes(&envelope.payload).map_err(local)?;
+        if batch.is_empty() {
+            batch.first_index = trajectory.first_index;
+        }
+        if batch.first_index != trajectory.first_index {
+            return Err("partial results don't start at the trajectory's first step".into());
+        }
+        if batch.len() > trajectory.len() {
+            return Err(format!(
+                "{} flags for a trajectory of {} steps",
+                batch.len(),
+                trajectory.len()
+            )
+            .into());
+        }
+        let certificate = self.certificate(job).await?;
+        certificate
+            .verify(&trajectory.steps(0..batch.len()), &batch.flags)
+            .map_err(local)?;
+        Ok(batch)
+    }
+
+    // Makes the daemon screen the job again, at most once per `every`, whenever its
+    // counterpart ephemerides change (see `recurring`). `cancel` ends the recurrence.
+    pub async fn recur(&mut self, job: &RemoteJob, every: Duration) -> Result<(), ServiceError> {
+        match self
+            .call(Request::Recur {
+                job: job.handle(),
+                every,
+            })
+            .await?
+        {
+            Response::Recurring => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    // Verified per-step flags of the first run of the job `next_run` hasn't returned yet,
+    // polling every `interval` until one finishes. Fails once no other run will come.
+    pub async fn next_run(
+        &mut self,
+        job: &mut RemoteJob,
+        trajectory: &EncryptedTrajectory,
+        interval: Duration,
+    ) -> Result<Vec<FheBool>, ServiceError> {
+        loop {
+            let (completed, recurring) =
+                match self.call(Request::Runs { job: job.handle() }).await? {
+                    Response::Runs {
+                        completed,
+                        recurring,
+                    } => (completed, recurring),
+                    other => return Err(unexpected(other)),
+                };
+            let status = self.status(job).await?;
+            // A new run may have started since; its results come with the next one.
+            if completed > job.runs && status == JobStatus::Done {
+                let flags = self.verified_results(job, trajectory).await?;
+                job.runs = completed;
+                return Ok(flags);
+            }
+            if !recurring && status.is_finished() {
+                return Err(format!("job {} won't run again: {:?}", job.id, status).into());
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    // The result as released under the session's reveal policy.
+    pub async fn revealed_results(
+        &mut self,
+        job: &mut RemoteJob,
+    ) -> Result<RevealedResult, ServiceError> {
+        let envelope = match self.call(Request::Results { job: job.handle() }).await? {
+            Response::Results { envelope } => envelope,
+            other => return Err(unexpected(other)),
+        };
+        let envelope = job.session.receive(&envelope).map_err(local)?;
+        if envelope.kind != MessageKind::Results {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Results,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        RevealedResult::from_bytes(&envelope.payload).map_err(local)
+    }
+
+    // The next batch of per-step flags, fetched while the daemon is still screening (see
+    // `stream`), polling every `interval` until it is ready. `None` once the job is done
+    // and every batch has been fetched.
+    pub async fn next_batch(
+        &mut self,
+        job: &mut RemoteJob,
+        interval: Duration,
+    ) -> Result<Option<ResultBatch>, ServiceError> {
+        loop {
+            let request = Request::ResultBatch {
+                job: job.handle(),
+                batch: job.batches,
+            };
+            match self.call(request).await? {
+                Response::ResultBatch {
+                    envelope: Some(envelope),
+                    ..
+                } => {
+                    let envelope = job.session.receive(&envelope).map_err(local)?;
+                    if envelope.kind != MessageKind::ResultBatch {
+                        return Err(ProtocolError::UnexpectedMessage {
+                            expected: MessageKind::ResultBatch,
+                            found: envelope.kind,
+                        }
+                        .into());
+                    }
+                    job.batches += 1;
+                    return ResultBatch::from_bytes(&envelope.payload)
+                        .map(Some)
+                        .map_err(local);
+                }
+                Response::ResultBatch { finished: true, .. } => return Ok(None),
+                Response::ResultBatch { .. } => tokio::time::sleep(interval).await,
+                other => return Err(unexpected(other)),
+            }
+        }
+    }
+}
+
+fn is_io(err: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    err.downcast_ref::<std::io::Error>().is_some()
+}
+
+fn unexpected(response: Response) -> ServiceError {
+    format!("unexpected response from the daemon: {:?}", response).into()
+}
@@ -0,0 +1,129 @@
+// Commitments binding each party to its trajectory before it sees anything of the other's.
+//
+// In a two-way screening each party encrypts its trajectory for the other and screens the
+// other's ciphertexts against its own plaintext. Without a commitment, the party going
+// second could pick what it encrypts, or what it screens with, after seeing how the first
+// direction went. So right after the `Hello` (and `Encoding`), each side sends a
+// `Commitment` message (`Session::commit`) before accepting any of the peer's artifacts:
+// SHA-256 digests of its serialized encrypted trajectory and of its plaintext in the
+// canonical encoding (see `core::canonical`), each bound to the session nonce and
+// salted with a fresh secret.
+//
+// At the end, `Session::open_commitment` reveals the salts and `Session::accept_opening`
+// checks the ciphertexts received from the peer against its commitment. The plaintext
+// digest can only be checked by whoever gets to see the plaintext, e.g. an auditor
+// handed it together with the opening in a dispute (`verify_plaintext`); until then the
+// salt keeps a guessed trajectory from being confirmed against it.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::common::SatelliteData;
+use crate::core::canonical::{CANONICAL, CanonicalEncoding};
+use crate::protocol::{ProtocolError, SessionNonce};
+
+const CIPHERTEXT_DOMAIN: &[u8] = b"sat-fhe commitment: ciphertexts";
+const PLAINTEXT_DOMAIN: &[u8] = b"sat-fhe commitment: plaintext";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrajectoryCommitment {
+    // Digest of the sender's serialized `EncryptedTrajectory`.
+    pub ciphertexts: [u8; 32],
+    // Digest of the sender's plaintext trajectory.
+    pub plaintext: [u8; 32],
+}
+
+// The salts of a `TrajectoryCommitment`, kept secret until the exchange is over.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentOpening {
+    pub ciphertext_salt: [u8; 32],
+    pub plaintext_salt: [u8; 32],
+}
+
+fn digest(domain: &[u8], nonce: &SessionNonce, salt: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update(nonce);
+    hasher.update(salt);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+// The plaintext as committed to: frame and units, then the canonical bytes.
+fn plaintext_bytes(data: &SatelliteData) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut bytes = bincode::serialize(&(data.frame, data.units))?;
+    let encoding = CanonicalEncoding {
+        units: data.units,
+        ..CANONICAL
+    };
+    bytes.extend(encoding.to_bytes(data)?);
+    Ok(bytes)
+}
+
+impl TrajectoryCommitment {
+    // Commits to `ciphertexts` (`EncryptedTrajectory::to_bytes`) and `plaintext` within
+    // the session `nonce`.
+    pub fn commit(
+        nonce: &SessionNonce,
+        ciphertexts: &[u8],
+        plaintext: &SatelliteData,
+    ) -> Result<(Self, CommitmentOpening), Box<dyn std::error::Error>> {
+        let mut opening = CommitmentOpening {
+            ciphertext_salt: [0; 32],
+            plaintext_salt: [0; 32],
+        };
+        getrandom::getrandom(&mut opening.ciphertext_salt)?;
+        getrandom::getrandom(&mut opening.plaintext_salt)?;
+        let commitment = Self {
+            ciphertexts: digest(
+                CIPHERTEXT_DOMAIN,
+                nonce,
+                &opening.ciphertext_salt,
+                ciphertexts,
+            ),
+            plaintext: digest(
+                PLAINTEXT_DOMAIN,
+                nonce,
+                &opening.plaintext_salt,
+                &plaintext_bytes(plaintext)?,
+            ),
+        };
+        Ok((commitment, opening))
+    }
+
+    pub fn verify_ciphertexts(
+        &self,
+        nonce: &SessionNonce,
+        opening: &CommitmentOpening,
+        ciphertexts: &[u8],
+    ) -> Result<(), ProtocolError> {
+        if digest(
+            CIPHERTEXT_DOMAIN,
+            nonce,
+            &opening.ciphertext_salt,
+            ciphertexts,
+        ) != self.ciphertexts
+        {
+            return Err(ProtocolError::CommitmentMismatch {
+                artifact: "ciphertexts",
+            });
+        }
+        Ok(())
+    }
+
+    pub fn verify_plaintext(
+        &self,
+        nonce: &SessionNonce,
+        opening: &CommitmentOpening,
+        plaintext: &SatelliteData,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bytes = plaintext_bytes(plaintext)?;
+        if digest(PLAINTEXT_DOMAIN, nonce, &opening.plaintext_salt, &bytes) != self.plaintext {
+            return Err(ProtocolError::CommitmentMismatch {
+                artifact: "plaintext",
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
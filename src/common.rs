@@ -1,28 +1,213 @@
+use std::fmt;
 use std::io::Cursor;
+use std::sync::RwLock;
+
+use bincode::Options;
+use serde::Deserialize;
 use tfhe::named::Named;
 use tfhe::safe_serialization::{safe_deserialize, safe_serialize};
-use tfhe::{Unversionize, Versionize};
-// Struct to group satellite trajectory data.
-pub struct SatelliteData {
-    pub x: [u32; 3],
-    pub y: [u32; 3],
-    pub z: [u32; 3],
+use tfhe::{
+    CompactCiphertextList, CompressedCiphertextList, CompressedServerKey, FheBool, FheUint16,
+    FheUint32, FheUint64, Unversionize, Versionize,
+};
+
+pub use crate::core::SatelliteData;
+
+// Upper bounds, in bytes, on a serialized artifact of each type, checked when writing it
+// as well as when reading it, so a peer can't make us allocate without bound and we don't
+// write what the other side will refuse. Process-wide; see `set_serialization_limits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializationLimits {
+    // Per-step results.
+    pub fhe_bool: u64,
+    // Coordinates at grid resolution (see `grid`).
+    pub fhe_uint16: u64,
+    // Coordinates and counts.
+    pub fhe_uint32: u64,
+    // Epochs.
+    pub fhe_uint64: u64,
+    // `CompactCiphertextList` and `CompressedCiphertextList`.
+    pub packed_list: u64,
+    pub compressed_server_key: u64,
+    // A whole `result_bundle::ResultBundle`.
+    pub result_bundle: u64,
+}
+
+const DEFAULT_LIMITS: SerializationLimits = SerializationLimits {
+    fhe_bool: 1 << 20,
+    fhe_uint16: 1 << 21,
+    fhe_uint32: 1 << 22,
+    fhe_uint64: 1 << 23,
+    packed_list: 1 << 28,
+    compressed_server_key: 1 << 30,
+    result_bundle: 1 << 30,
+};
+
+impl Default for SerializationLimits {
+    fn default() -> Self {
+        DEFAULT_LIMITS
+    }
+}
+
+static LIMITS: RwLock<SerializationLimits> = RwLock::new(DEFAULT_LIMITS);
+
+pub fn serialization_limits() -> SerializationLimits {
+    *LIMITS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+// Replaces the limits for every later `safe_serialize_item` and `safe_deserialize_item`.
+pub fn set_serialization_limits(limits: SerializationLimits) {
+    *LIMITS.write().unwrap_or_else(|e| e.into_inner()) = limits;
+}
+
+// Which of the `SerializationLimits` applies to a type.
+pub trait SizeLimited {
+    fn size_limit(limits: &SerializationLimits) -> u64;
+}
+
+impl SizeLimited for FheBool {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.fhe_bool
+    }
+}
+
+impl SizeLimited for FheUint16 {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.fhe_uint16
+    }
+}
+
+impl SizeLimited for FheUint32 {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.fhe_uint32
+    }
+}
+
+impl SizeLimited for FheUint64 {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.fhe_uint64
+    }
+}
+
+impl SizeLimited for CompactCiphertextList {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.packed_list
+    }
+}
+
+impl SizeLimited for CompressedCiphertextList {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.packed_list
+    }
+}
+
+impl SizeLimited for CompressedServerKey {
+    fn size_limit(limits: &SerializationLimits) -> u64 {
+        limits.compressed_server_key
+    }
 }
 
 pub fn safe_serialize_item<T>(item: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>>
 where
-    T: serde::Serialize + Versionize + Named,
+    T: serde::Serialize + Versionize + Named + SizeLimited,
 {
     let mut buf = Vec::new();
-    safe_serialize(item, &mut buf, 1 << 20)?;
+    safe_serialize(item, &mut buf, T::size_limit(&serialization_limits()))?;
     Ok(buf)
 }
 
+// A serialized item of another type than the one asked for, e.g. a flag read back as a
+// coordinate. `index` is the item's position in the list it was read from, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemTypeMismatch {
+    pub expected: String,
+    pub found: String,
+    pub index: Option<usize>,
+}
+
+impl fmt::Display for ItemTypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)?;
+        if let Some(index) = self.index {
+            write!(f, " at index {}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ItemTypeMismatch {}
+
+// Leading fields of the header TFHE-rs writes before every safely serialized item; the
+// layout of `tfhe::safe_serialization`'s own (private) header.
+#[derive(Deserialize)]
+struct ItemHeader {
+    _header_version: String,
+    _versioning_mode: VersioningMode,
+    name: String,
+}
+
+#[derive(Deserialize)]
+enum VersioningMode {
+    Versioned { _versioning_version: String },
+    Unversioned { _crate_version: String },
+}
+
+// `Named` tags carry the module path, e.g. `high_level_api::FheBool`.
+fn short_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+// TFHE type name tagged in a safely serialized item, e.g. `FheBool`.
+pub fn item_type_name(data: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let header: ItemHeader = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(1 << 12)
+        .deserialize(data)
+        .map_err(|e| format!("not a serialized TFHE item: {}", e))?;
+    Ok(short_name(&header.name).to_string())
+}
+
 pub fn safe_deserialize_item<T>(data: &[u8]) -> Result<T, Box<dyn std::error::Error>>
 where
-    T: serde::de::DeserializeOwned + Unversionize + Named,
+    T: serde::de::DeserializeOwned + Unversionize + Named + SizeLimited,
 {
+    // Checked up front for an error naming both types; TFHE-rs checks the tag again.
+    let found = item_type_name(data)?;
+    let expected = short_name(T::NAME);
+    let aliases = T::BACKWARD_COMPATIBILITY_ALIASES
+        .iter()
+        .map(|a| short_name(a));
+    if found != expected && !aliases.clone().any(|alias| alias == found) {
+        return Err(ItemTypeMismatch {
+            expected: expected.to_string(),
+            found,
+            index: None,
+        }
+        .into());
+    }
     let cursor = Cursor::new(data);
-    let item = safe_deserialize(cursor, 1 << 20)?;
+    let item = safe_deserialize(cursor, T::size_limit(&serialization_limits()))?;
     Ok(item)
 }
+
+// Every item of a list, naming the index of the first one that fails.
+pub fn safe_deserialize_items<T>(items: &[Vec<u8>]) -> Result<Vec<T>, Box<dyn std::error::Error>>
+where
+    T: serde::de::DeserializeOwned + Unversionize + Named + SizeLimited,
+{
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, bytes)| {
+            safe_deserialize_item(bytes).map_err(|err| match err.downcast::<ItemTypeMismatch>() {
+                Ok(mismatch) => ItemTypeMismatch {
+                    index: Some(index),
+                    ..*mismatch
+                }
+                .into(),
+                Err(err) => format!("item {}: {}", index, err).into(),
+            })
+        })
+        .collect()
+}
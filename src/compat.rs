@@ -0,0 +1,267 @@
+// Why an artifact from another build can't be used here.
+//
+// Since format version 3 every tagged artifact (see `migrate`) records the TFHE-rs
+// version of the build that wrote it, captured at compile time from the lock file, and,
+// for server keys and the ciphertext artifacts that know them, a `ParameterDigest`. When
+// a payload fails to deserialize, `check` turns that record into the actual reason: a
+// format version this build can't read, or a TFHE-rs release newer than its own.
+// `check_parameters` additionally compares the parameters with a key's, which the
+// screening daemon does before evaluating an upload, since ciphertexts under other
+// parameters deserialize fine and only evaluate to garbage.
+//
+// The digest covers the parameters visible in a ciphertext: the dimension of the LWE key
+// it is encrypted under, the message and carry moduli and the ciphertext modulus. Bootstrapping
+// and key-switching parameters aren't part of a ciphertext, so parameter sets that only
+// differ in those share a digest. Older TFHE-rs releases are not flagged: TFHE-rs reads
+// data written by earlier versions. `.eft` files name their producer in their own header.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tfhe::integer::IntegerCiphertext;
+use tfhe::shortint::parameters::{EncryptionKeyChoice, PBSParameters};
+use tfhe::{FheBool, FheUint32};
+
+use crate::migrate::{self, ArtifactKind};
+
+// TFHE-rs version this crate was built against, or "unknown".
+pub const TFHE_VERSION: &str = env!("SAT_FHE_TFHE_VERSION");
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParameterDigest(pub [u8; 8]);
+
+impl ParameterDigest {
+    // `modulus` is the ciphertext modulus, 0 for the native one.
+    fn of_shape(lwe_dimension: usize, message: u64, carry: u64, modulus: u64) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(b"sat-fhe parameters");
+        hasher.update((lwe_dimension as u64).to_le_bytes());
+        hasher.update(message.to_le_bytes());
+        hasher.update(carry.to_le_bytes());
+        hasher.update(modulus.to_le_bytes());
+        let hash: [u8; 32] = hasher.finalize().into();
+        Self(hash[..8].try_into().expect("8 bytes"))
+    }
+
+    // Digest of the ciphertexts keys from `parameters` encrypt and evaluate.
+    pub fn of_parameters(parameters: PBSParameters) -> Self {
+        let lwe_dimension = match parameters.encryption_key_choice() {
+            EncryptionKeyChoice::Big => parameters
+                .glwe_dimension()
+                .to_equivalent_lwe_dimension(parameters.polynomial_size()),
+            EncryptionKeyChoice::Small => parameters.lwe_dimension(),
+        };
+        Self::of_shape(
+            lwe_dimension.0,
+            parameters.message_modulus().0,
+            parameters.carry_modulus().0,
+            parameters
+                .ciphertext_modulus()
+                .get_custom_modulus_as_optional_scalar()
+                .unwrap_or(0),
+        )
+    }
+
+    fn of_block(block: &tfhe::shortint::Ciphertext) -> Self {
+        Self::of_shape(
+            block.ct.lwe_size().to_lwe_dimension().0,
+            block.message_modulus.0,
+            block.carry_modulus.0,
+            block
+                .ct
+                .ciphertext_modulus()
+                .get_custom_modulus_as_optional_scalar()
+                .unwrap_or(0),
+        )
+    }
+
+    // Digest of the parameters `value` was encrypted with; `None` if it has no blocks.
+    pub fn of_uint(value: &FheUint32) -> Option<Self> {
+        let (radix, ..) = value.clone().into_raw_parts();
+        radix.blocks().first().map(Self::of_block)
+    }
+
+    pub fn of_bool(value: &FheBool) -> Self {
+        Self::of_block(&value.clone().into_raw_parts())
+    }
+}
+
+impl std::fmt::Display for ParameterDigest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+// Header written behind the tag of a version 3 artifact.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArtifactHeader {
+    pub(crate) tfhe_version: Option<String>,
+    pub(crate) parameters: Option<ParameterDigest>,
+}
+
+impl ArtifactHeader {
+    pub(crate) fn current(parameters: Option<ParameterDigest>) -> Self {
+        Self {
+            tfhe_version: Some(TFHE_VERSION.to_string()),
+            parameters,
+        }
+    }
+}
+
+// What an artifact records about how it was written (`migrate::meta_of`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactMeta {
+    pub kind: ArtifactKind,
+    pub format_version: u8,
+    // `None` for artifacts written before format version 3, and upgraded ones.
+    pub tfhe_version: Option<String>,
+    pub parameters: Option<ParameterDigest>,
+}
+
+impl ArtifactMeta {
+    // An untagged (version 1) artifact, which records nothing.
+    pub(crate) fn untagged(kind: ArtifactKind) -> Self {
+        Self {
+            kind,
+            format_version: 1,
+            tfhe_version: None,
+            parameters: None,
+        }
+    }
+}
+
+// One component of an artifact this build can't use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    Format {
+        kind: ArtifactKind,
+        found: u8,
+        supported: u8,
+    },
+    TfheVersion {
+        found: String,
+        current: String,
+    },
+    Parameters {
+        found: ParameterDigest,
+        expected: ParameterDigest,
+    },
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Format {
+                kind,
+                found,
+                supported,
+            } => write!(
+                f,
+                "{:?} format version {} is newer than this build supports ({})",
+                kind, found, supported
+            ),
+            Mismatch::TfheVersion { found, current } => write!(
+                f,
+                "written with TFHE-rs {}, newer than this build's {}",
+                found, current
+            ),
+            Mismatch::Parameters { found, expected } => write!(
+                f,
+                "encrypted under parameters {}, but the key is for parameters {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+// Every mismatching component of an artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incompatibility {
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl std::fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, mismatch) in self.mismatches.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Incompatibility {}
+
+// Major and minor version of a TFHE-rs release.
+fn release(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+fn mismatches(meta: &ArtifactMeta) -> Vec<Mismatch> {
+    let mut found = Vec::new();
+    if meta.format_version > meta.kind.current_version() {
+        found.push(Mismatch::Format {
+            kind: meta.kind,
+            found: meta.format_version,
+            supported: meta.kind.current_version(),
+        });
+    }
+    if let Some(version) = &meta.tfhe_version
+        && let (Some(written), Some(current)) = (release(version), release(TFHE_VERSION))
+        && written > current
+    {
+        found.push(Mismatch::TfheVersion {
+            found: version.clone(),
+            current: TFHE_VERSION.to_string(),
+        });
+    }
+    found
+}
+
+fn result(mismatches: Vec<Mismatch>) -> Result<(), Incompatibility> {
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Incompatibility { mismatches })
+    }
+}
+
+// Whether this build can read an artifact written as `meta` says.
+pub fn check(meta: &ArtifactMeta) -> Result<(), Incompatibility> {
+    result(mismatches(meta))
+}
+
+// Like `check`, and whether its ciphertexts can be evaluated with a key for `expected`.
+// Artifacts that don't record their parameters pass that part.
+pub fn check_parameters(
+    meta: &ArtifactMeta,
+    expected: ParameterDigest,
+) -> Result<(), Incompatibility> {
+    let mut found = mismatches(meta);
+    if let Some(parameters) = meta.parameters
+        && parameters != expected
+    {
+        found.push(Mismatch::Parameters {
+            found: parameters,
+            expected,
+        });
+    }
+    result(found)
+}
+
+// `error` from reading the artifact `data` of `kind`, with the reason `check` finds in its
+// header.
+pub(crate) fn explain(
+    kind: ArtifactKind,
+    data: &[u8],
+    error: Box<dyn std::error::Error>,
+) -> Box<dyn std::error::Error> {
+    match migrate::meta_of(kind, data).map(|meta| check(&meta)) {
+        Ok(Err(incompatibility)) => format!("{} ({})", incompatibility, error).into(),
+        _ => error,
+    }
+}
@@ -0,0 +1,213 @@
+// Key handling for one party, in one place.
+//
+// TFHE-rs evaluates against a thread-local server key installed with `set_server_key`.
+// `FheContext` owns the keys and only installs the server key for the duration of an
+// `evaluate_with` call, so callers never touch that global state directly and a key from
+// one screening can't leak into the next one on the same thread. Whatever was evaluating
+// before is put back when the call ends, also when it unwinds from a panic and when one
+// evaluation runs inside another.
+//
+// Kernels that fan out (see `join`) run on worker threads owned by the context, which
+// have its key installed for their whole lifetime. Contexts never share workers, so any
+// number of them can evaluate concurrently, e.g. one per job in the screening daemon.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use tfhe::prelude::*;
+use tfhe::{ClientKey, Config, ServerKey, generate_keys, set_server_key, unset_server_key};
+
+use crate::common::SatelliteData;
+use crate::profiling;
+use crate::redact::{EvaluationKey, SecretKey};
+use crate::trajectory::EncryptedTrajectory;
+
+#[derive(Debug)]
+pub struct FheContext {
+    // Absent on the evaluator side, which only ever receives the server key.
+    client_key: Option<SecretKey>,
+    server_key: EvaluationKey,
+    // Started on the first `evaluate_with`.
+    workers: OnceLock<Arc<ThreadPool>>,
+}
+
+// The context evaluating on a thread: its server key and its workers.
+struct Evaluating {
+    server_key: ServerKey,
+    workers: Arc<ThreadPool>,
+}
+
+thread_local! {
+    static EVALUATING: RefCell<Option<Evaluating>> = const { RefCell::new(None) };
+}
+
+// Puts back what was evaluating on this thread before an `evaluate_with`, when the call
+// returns or unwinds.
+struct Restore(Option<Evaluating>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        match &previous {
+            Some(outer) => set_server_key(outer.server_key.clone()),
+            None => unset_server_key(),
+        }
+        EVALUATING.with(|evaluating| *evaluating.borrow_mut() = previous);
+    }
+}
+
+// The evaluation workers of a context couldn't be started.
+#[derive(Debug)]
+pub struct WorkersUnavailable(ThreadPoolBuildError);
+
+impl fmt::Display for WorkersUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to start evaluation workers: {}", self.0)
+    }
+}
+
+impl std::error::Error for WorkersUnavailable {}
+
+// `rayon::join` on the workers of the evaluating context, so both halves run under its
+// server key. Outside `evaluate_with` (and on the workers themselves) this is plain
+// `rayon::join`. A screening being profiled records both halves under the caller's frames
+// (see `profiling`).
+pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    let (a, b) = (profiling::inherit(a), profiling::inherit(b));
+    let workers = EVALUATING.with(|evaluating| {
+        evaluating
+            .borrow()
+            .as_ref()
+            .map(|evaluating| evaluating.workers.clone())
+    });
+    match workers {
+        Some(pool) => pool.join(a, b),
+        None => rayon::join(a, b),
+    }
+}
+
+#[cfg(feature = "deterministic-tests")]
+fn with_seeded_engine<T>(seed: u128, f: impl FnOnce() -> T) -> T {
+    use tfhe::core_crypto::commons::generators::DeterministicSeeder;
+    use tfhe::core_crypto::prelude::DefaultRandomGenerator;
+    use tfhe::shortint::engine::ShortintEngine;
+
+    // Distinct from the client key's seed, so the two streams don't overlap.
+    let mut seeder = DeterministicSeeder::<DefaultRandomGenerator>::new(tfhe::Seed(!seed));
+    let seeded = ShortintEngine::new_from_seeder(&mut seeder);
+    let previous =
+        ShortintEngine::with_thread_local_mut(|engine| std::mem::replace(engine, seeded));
+    let result = f();
+    ShortintEngine::with_thread_local_mut(|engine| *engine = previous);
+    result
+}
+
+impl FheContext {
+    // Key owner: generates a fresh key pair.
+    pub fn generate(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let (client_key, server_key) = generate_keys(config);
+        Ok(Self {
+            client_key: Some(SecretKey::new(client_key)?),
+            server_key: EvaluationKey::new(server_key)?,
+            workers: OnceLock::new(),
+        })
+    }
+
+    // Key owner: generates the key pair determined by `seed`, for tests and golden files
+    // that must come out the same on every run. Never use this for real keys.
+    #[cfg(feature = "deterministic-tests")]
+    pub fn generate_seeded(config: Config, seed: u128) -> Result<Self, Box<dyn std::error::Error>> {
+        let client_key = ClientKey::generate_with_seed(config, tfhe::Seed(seed));
+        // The server key is drawn from the thread-local shortint engine, so swap in one
+        // seeded from `seed` for the duration and put the OS-seeded one back afterwards.
+        let server_key = with_seeded_engine(seed, || ServerKey::new(&client_key));
+        Ok(Self {
+            client_key: Some(SecretKey::new(client_key)?),
+            server_key: EvaluationKey::new(server_key)?,
+            workers: OnceLock::new(),
+        })
+    }
+
+    // Evaluator: evaluation only, from the owner's server key.
+    pub fn from_server_key(server_key: ServerKey) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            client_key: None,
+            server_key: EvaluationKey::new(server_key)?,
+            workers: OnceLock::new(),
+        })
+    }
+
+    // Evaluator: evaluation only, from a server key already wrapped, e.g. one loaded from
+    // a split key file.
+    pub fn from_evaluation_key(server_key: EvaluationKey) -> Self {
+        Self {
+            client_key: None,
+            server_key,
+            workers: OnceLock::new(),
+        }
+    }
+
+    pub fn server_key(&self) -> &EvaluationKey {
+        &self.server_key
+    }
+
+    pub fn client_key(&self) -> Result<&SecretKey, Box<dyn std::error::Error>> {
+        self.client_key
+            .as_ref()
+            .ok_or_else(|| "this context holds no client key".into())
+    }
+
+    pub fn encrypt(
+        &self,
+        data: &SatelliteData,
+    ) -> Result<EncryptedTrajectory, Box<dyn std::error::Error>> {
+        EncryptedTrajectory::encrypt(data, self.client_key()?)
+    }
+
+    // Two threads starting the workers at once may both build a pool; one is dropped.
+    fn workers(&self) -> Result<Arc<ThreadPool>, WorkersUnavailable> {
+        if let Some(workers) = self.workers.get() {
+            return Ok(workers.clone());
+        }
+        let key: ServerKey = (*self.server_key).clone();
+        let pool = ThreadPoolBuilder::new()
+            .thread_name(|i| format!("fhe-eval-{}", i))
+            .start_handler(move |_| set_server_key(key.clone()))
+            .build()
+            .map_err(WorkersUnavailable)?;
+        Ok(self.workers.get_or_init(|| Arc::new(pool)).clone())
+    }
+
+    // Runs `f` with this context's server key installed on the current thread, with
+    // `join` fanning out to this context's workers, and puts back whatever key and workers
+    // were there before afterwards. The global rayon pool is never touched. The key is
+    // reference counted, so installing it on every worker is cheap.
+    pub fn evaluate_with<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<WorkersUnavailable>,
+    {
+        let evaluating = Evaluating {
+            server_key: (*self.server_key).clone(),
+            workers: self.workers()?,
+        };
+        set_server_key(evaluating.server_key.clone());
+        let _restore = Restore(EVALUATING.with(|current| current.borrow_mut().replace(evaluating)));
+        f()
+    }
+
+    pub fn decrypt<C, T>(&self, values: &[C]) -> Result<Vec<T>, Box<dyn std::error::Error>>
+    where
+        C: FheDecrypt<T>,
+    {
+        let client_key: &ClientKey = self.client_key()?;
+        Ok(values.iter().map(|v| v.decrypt(client_key)).collect())
+    }
+}
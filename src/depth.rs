@@ -0,0 +1,88 @@
+use std::fmt;
+
+// Operation accounting for one screening evaluation.
+//
+// Every high-level TFHE integer/boolean operation ends in a programmable bootstrap, so
+// noise is refreshed after each op and the library exposes no separate "bootstrap now"
+// call. What still grows with the screening mode is the length of the longest chain of
+// dependent operations (latency) and the total op count (cost), which is what this
+// tracks. `DepthLimit` lets callers cap the chain before any work is done.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpCounter {
+    // Ciphertext comparisons (eq, lt, gt, ...), scalar or not.
+    pub comparisons: u64,
+    // Arithmetic on encrypted integers (sub, mul, add, ...).
+    pub arithmetic: u64,
+    // Boolean combinations of FheBool values (and, or, not).
+    pub boolean: u64,
+    // Longest chain of dependent operations in a single time step.
+    pub depth: u32,
+    // Set by `ScreeningConfig::check_depth` when `depth` is over a `DepthPolicy::Warn`
+    // limit; the screening ran anyway and the caller decides whether to tell anyone.
+    pub depth_warning: Option<DepthExceeded>,
+}
+
+impl OpCounter {
+    pub fn total(&self) -> u64 {
+        self.comparisons + self.arithmetic + self.boolean
+    }
+
+    // Adds the per-step cost of `step` repeated `steps` times. Steps are independent,
+    // so depth is the maximum over steps, not the sum.
+    pub fn add_steps(&mut self, step: &OpCounter, steps: u64) {
+        self.comparisons += step.comparisons * steps;
+        self.arithmetic += step.arithmetic * steps;
+        self.boolean += step.boolean * steps;
+        self.depth = self.depth.max(step.depth);
+        self.depth_warning = self.depth_warning.or(step.depth_warning);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthPolicy {
+    // Refuse to evaluate a mode whose per-step depth exceeds the limit.
+    Error,
+    // Evaluate anyway; the caller finds `OpCounter::depth_warning` set afterwards.
+    Warn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthLimit {
+    pub max_depth: u32,
+    pub policy: DepthPolicy,
+}
+
+impl DepthLimit {
+    // Checks the planned per-step cost against the limit. Returns whether the limit is
+    // exceeded (only possible with `DepthPolicy::Warn`).
+    pub fn check(&self, planned: &OpCounter) -> Result<bool, DepthExceeded> {
+        if planned.depth <= self.max_depth {
+            return Ok(false);
+        }
+        match self.policy {
+            DepthPolicy::Error => Err(DepthExceeded {
+                depth: planned.depth,
+                max_depth: self.max_depth,
+            }),
+            DepthPolicy::Warn => Ok(true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthExceeded {
+    pub depth: u32,
+    pub max_depth: u32,
+}
+
+impl fmt::Display for DepthExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "screening mode needs operation depth {} per step, limit is {}",
+            self.depth, self.max_depth
+        )
+    }
+}
+
+impl std::error::Error for DepthExceeded {}
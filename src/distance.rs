@@ -0,0 +1,314 @@
+// Distance-based screening: instead of asking "are both objects in the same cell", compute
+// the encrypted squared distance per time step once and compare it against any number of
+// screening distances (e.g. a 5 km warning and a 1 km alert). The distance is by far the
+// expensive part, so extra thresholds only cost one comparison each. `TieredThreshold` is
+// the common two-tier case as a kernel of its own: one distance per step, an alert and a
+// warning flag out of it.
+
+use serde::{Deserialize, Serialize};
+use tfhe::prelude::*;
+use tfhe::{FheBool, FheUint32, FheUint64};
+
+use crate::common::SatelliteData;
+use crate::context;
+use crate::depth::OpCounter;
+use crate::profiling::span;
+use crate::screening::{ClearCoord, ScreeningConfig, align_plaintext};
+use crate::trajectory::EncryptedTrajectory;
+
+// Per-axis differences are clamped to this many units before squaring, so the sum of
+// three squares fits in 64 bits. Thresholds must be below it.
+pub const DISTANCE_CAP: u32 = 1 << 21;
+
+// Per-step encrypted flags for each threshold, in the order the thresholds were given.
+pub struct ThresholdOutput {
+    pub thresholds: Vec<u32>,
+    pub results: Vec<Vec<FheBool>>,
+    pub ops: OpCounter,
+}
+
+// Cost of one squared-distance step: per axis max, min, subtract, clamp, square; then two
+// additions.
+pub fn squared_distance_cost() -> OpCounter {
+    OpCounter {
+        comparisons: 9,
+        arithmetic: 8,
+        boolean: 0,
+        depth: 6,
+        depth_warning: None,
+    }
+}
+
+// Squared-distance step plus one comparison per threshold.
+pub fn threshold_cost(thresholds: usize) -> OpCounter {
+    let distance = squared_distance_cost();
+    OpCounter {
+        comparisons: distance.comparisons + thresholds as u64,
+        depth: distance.depth + 1,
+        ..distance
+    }
+}
+
+// Both coordinates carry the encoding's bias (see `units::BIAS`), which cancels in the
+// difference, so negative positions need no correction here.
+pub(crate) fn axis_difference_squared(encrypted: &FheUint32, clear: ClearCoord) -> FheUint64 {
+    let (high, low) = (clear.max(encrypted), clear.min(encrypted));
+    let diff = span("sub", || high - low);
+    let diff = span("clamp", || diff.min(DISTANCE_CAP));
+    let diff: FheUint64 = span("cast", || diff.cast_into());
+    span("square", || &diff * &diff)
+}
+
+// Encrypted squared distance between one encrypted position and a clear one, axes in x, y,
+// z order. With `parallel`, the axes run concurrently (see `context::join`).
+pub(crate) fn squared_distance(
+    encrypted: [&FheUint32; 3],
+    clear: [ClearCoord; 3],
+    parallel: bool,
+) -> FheUint64 {
+    let [x, y, z] = encrypted;
+    let [px, py, pz] = clear;
+    let (dx, (dy, dz)) = if parallel {
+        context::join(
+            || axis_difference_squared(x, px),
+            || {
+                context::join(
+                    || axis_difference_squared(y, py),
+                    || axis_difference_squared(z, pz),
+                )
+            },
+        )
+    } else {
+        (
+            axis_difference_squared(x, px),
+            (
+                axis_difference_squared(y, py),
+                axis_difference_squared(z, pz),
+            ),
+        )
+    };
+    span("add", || dx + dy + dz)
+}
+
+// Encrypted squared distance between `encrypted` and `plaintext` at every step, in units
+// of the encrypted trajectory squared. `plaintext` is indexed by absolute step, and its
+// coordinates enter as `config` makes them operands (see `ClearCoord`).
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn squared_distances(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    config: &ScreeningConfig,
+) -> Result<Vec<FheUint64>, Box<dyn std::error::Error>> {
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let offset = encrypted.first_index;
+    Ok((0..encrypted.len())
+        .map(|i| {
+            let j = offset + i;
+            span("distance", || {
+                squared_distance(
+                    [&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]],
+                    [
+                        config.clear(plaintext.x[j]),
+                        config.clear(plaintext.y[j]),
+                        config.clear(plaintext.z[j]),
+                    ],
+                    config.parallel_axes,
+                )
+            })
+        })
+        .collect())
+}
+
+// Flags, for every threshold, the steps where the two objects are at most that many units
+// apart. The distances are computed once and shared by all thresholds.
+pub fn screen_thresholds(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    thresholds: &[u32],
+    config: &ScreeningConfig,
+) -> Result<ThresholdOutput, Box<dyn std::error::Error>> {
+    if let Some(&t) = thresholds.iter().find(|&&t| t >= DISTANCE_CAP) {
+        return Err(format!(
+            "threshold {} is not below the distance cap {}",
+            t, DISTANCE_CAP
+        )
+        .into());
+    }
+    let mut step = threshold_cost(thresholds.len());
+    config.check_depth(&mut step)?;
+
+    let distances = squared_distances(encrypted, plaintext, config)?;
+    let results = thresholds
+        .iter()
+        .map(|&t| {
+            let limit = t as u64 * t as u64;
+            distances
+                .iter()
+                .map(|d| span("le", || d.le(limit)))
+                .collect()
+        })
+        .collect();
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, encrypted.len() as u64);
+    Ok(ThresholdOutput {
+        thresholds: thresholds.to_vec(),
+        results,
+        ops,
+    })
+}
+
+// How close one step came under a `TieredThreshold`, in increasing severity.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Clear,
+    Warning,
+    Alert,
+}
+
+impl Tier {
+    // The tier of one step, from its decrypted flags.
+    pub fn from_flags(alert: bool, warning: bool) -> Self {
+        if alert {
+            Tier::Alert
+        } else if warning {
+            Tier::Warning
+        } else {
+            Tier::Clear
+        }
+    }
+
+    // Per-step tiers from the decrypted flags of a `TieredOutput`.
+    pub fn classify(alert: &[bool], warning: &[bool]) -> Vec<Tier> {
+        alert
+            .iter()
+            .zip(warning)
+            .map(|(&alert, &warning)| Tier::from_flags(alert, warning))
+            .collect()
+    }
+}
+
+// Two screening distances in one kernel: within `alert` units is an alert, within
+// `warning` units a warning. Both flags come out of the same encrypted distance, so the
+// second tier costs one comparison rather than a second screening.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TieredThreshold {
+    pub alert: u32,
+    pub warning: u32,
+}
+
+// One step's flags under a `TieredThreshold`; `alert` implies `warning`.
+pub struct TieredFlags {
+    pub alert: FheBool,
+    pub warning: FheBool,
+}
+
+// Per-step flags of both tiers.
+pub struct TieredOutput {
+    pub tiers: TieredThreshold,
+    pub alert: Vec<FheBool>,
+    pub warning: Vec<FheBool>,
+    pub ops: OpCounter,
+}
+
+impl TieredThreshold {
+    // The alert radius must not exceed the warning radius, and both must be below the
+    // distance cap.
+    pub fn new(alert: u32, warning: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        let tiers = TieredThreshold { alert, warning };
+        tiers.validate()?;
+        Ok(tiers)
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.warning >= DISTANCE_CAP {
+            return Err(format!(
+                "threshold {} is not below the distance cap {}",
+                self.warning, DISTANCE_CAP
+            )
+            .into());
+        }
+        if self.alert > self.warning {
+            return Err(format!(
+                "alert radius {} is beyond the warning radius {}",
+                self.alert, self.warning
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    // Squared-distance step plus two comparisons.
+    pub fn cost(&self) -> OpCounter {
+        threshold_cost(2)
+    }
+
+    // Both flags for one encrypted position and a clear one, axes in x, y, z order.
+    pub fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        parallel: bool,
+    ) -> TieredFlags {
+        let squared = squared_distance(encrypted, clear, parallel);
+        let limit = |t: u32| t as u64 * t as u64;
+        TieredFlags {
+            alert: span("le", || squared.le(limit(self.alert))),
+            warning: span("le", || squared.le(limit(self.warning))),
+        }
+    }
+
+    // The tier of two clear positions, as the encrypted comparison would give it.
+    pub fn tier_clear(&self, a: [u32; 3], b: [u32; 3]) -> Tier {
+        let squared: u64 = (0..3)
+            .map(|axis| (a[axis].abs_diff(b[axis]).min(DISTANCE_CAP) as u64).pow(2))
+            .sum();
+        let limit = |t: u32| t as u64 * t as u64;
+        Tier::from_flags(squared <= limit(self.alert), squared <= limit(self.warning))
+    }
+}
+
+// Flags every step of `encrypted` against both tiers of `tiers`. With
+// `config.parallel_axes`, the axes of each step's distance run concurrently.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_tiered(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    tiers: TieredThreshold,
+    config: &ScreeningConfig,
+) -> Result<TieredOutput, Box<dyn std::error::Error>> {
+    tiers.validate()?;
+    let mut step = tiers.cost();
+    config.check_depth(&mut step)?;
+
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let offset = encrypted.first_index;
+    let (alert, warning) = (0..encrypted.len())
+        .map(|i| {
+            let j = offset + i;
+            let flags = span("tiered", || {
+                tiers.compare(
+                    [&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]],
+                    [
+                        config.clear(plaintext.x[j]),
+                        config.clear(plaintext.y[j]),
+                        config.clear(plaintext.z[j]),
+                    ],
+                    config.parallel_axes,
+                )
+            });
+            (flags.alert, flags.warning)
+        })
+        .unzip();
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, encrypted.len() as u64);
+    Ok(TieredOutput {
+        tiers,
+        alert,
+        warning,
+        ops,
+    })
+}
@@ -0,0 +1,170 @@
+// Validation of a screening setup without any homomorphic work, see `Session::dry_run`.
+
+use std::fmt;
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::frame::{Frame, check_frames};
+use crate::protocol::SessionMetadata;
+use crate::screening::{ScreeningConfig, align_plaintext_to, exact_match_cost};
+use crate::time_system::{TimeSystem, check_time_systems};
+use crate::units::Units;
+
+// Everything the evaluator knows before it starts evaluating.
+pub struct DryRunInput<'a> {
+    // Metadata the owner declared in its `Hello`.
+    pub metadata: &'a SessionMetadata,
+    // Fingerprint of the server key actually received.
+    pub server_key_fingerprint: &'a str,
+    // Public header of the received encrypted trajectory.
+    pub first_index: usize,
+    pub epochs: &'a [u64],
+    pub frame: Frame,
+    pub units: Units,
+    // The evaluator's own plaintext trajectory and its epochs, indexed by absolute step.
+    pub plaintext: &'a SatelliteData,
+    pub plaintext_epochs: &'a [u64],
+    // Time system of `plaintext_epochs`, already converted to the owner's if it declared
+    // one (see `time_system::align_epochs`).
+    pub plaintext_time_system: TimeSystem,
+    pub config: &'a ScreeningConfig,
+}
+
+// What the screening would compute, and every problem found on the way.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunReport {
+    pub steps: usize,
+    pub first_index: usize,
+    pub planned_ops: OpCounter,
+    pub issues: Vec<String>,
+}
+
+impl DryRunReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "steps {}..{}: {} comparisons, {} boolean ops, depth {}",
+            self.first_index,
+            self.first_index + self.steps,
+            self.planned_ops.comparisons,
+            self.planned_ops.boolean,
+            self.planned_ops.depth
+        )?;
+        if self.is_ok() {
+            write!(f, "no issues found")
+        } else {
+            for issue in &self.issues {
+                writeln!(f, "issue: {}", issue)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn validate(input: &DryRunInput) -> DryRunReport {
+    let steps = input.epochs.len();
+    let kernel = input.config.kernel.kernel();
+    let mut step_cost = kernel
+        .as_ref()
+        .map_or_else(|_| exact_match_cost(), |kernel| kernel.cost());
+    let depth = input.config.check_depth(&mut step_cost);
+    let mut report = DryRunReport {
+        steps,
+        first_index: input.first_index,
+        ..Default::default()
+    };
+    report.planned_ops.add_steps(&step_cost, steps as u64);
+
+    // Dimensions.
+    let plain = input.plaintext;
+    if plain.y.len() != plain.x.len() || plain.z.len() != plain.x.len() {
+        report.issues.push(format!(
+            "plaintext axes have different lengths ({}, {}, {})",
+            plain.x.len(),
+            plain.y.len(),
+            plain.z.len()
+        ));
+    }
+    let end = input.first_index + steps;
+    if end > plain.x.len() {
+        report.issues.push(format!(
+            "encrypted trajectory covers steps {}..{} but plaintext has only {}",
+            input.first_index,
+            end,
+            plain.x.len()
+        ));
+    }
+
+    // Time alignment, only meaningful with both sides' epochs in the same time system.
+    if let Some(owner) = input.metadata.time_system
+        && let Err(err) = check_time_systems(owner, input.plaintext_time_system)
+    {
+        report.issues.push(err.to_string());
+    } else if input.plaintext_epochs.len() != plain.x.len() {
+        report.issues.push(format!(
+            "{} plaintext epochs for {} plaintext steps",
+            input.plaintext_epochs.len(),
+            plain.x.len()
+        ));
+    } else if end <= plain.x.len() {
+        let expected = &input.plaintext_epochs[input.first_index..end];
+        if let Some(i) = (0..steps).find(|&i| expected[i] != input.epochs[i]) {
+            report.issues.push(format!(
+                "epochs misaligned at step {}: encrypted {} vs plaintext {}",
+                input.first_index + i,
+                input.epochs[i],
+                expected[i]
+            ));
+        }
+    }
+
+    // Frame and units of the encrypted trajectory, as the screening aligns the plaintext
+    // to them; lengths were checked above.
+    if let Err(err) = align_plaintext_to(0..0, input.frame, input.units, plain) {
+        report.issues.push(err.to_string());
+    }
+    // Frames, if the owner declared one.
+    if let Some(owner) = input.metadata.frame
+        && let Err(err) = check_frames(owner, plain.frame)
+    {
+        report.issues.push(err.to_string());
+    }
+
+    // Keys.
+    match &input.metadata.server_key_fingerprint {
+        Some(announced) if announced != input.server_key_fingerprint => {
+            report.issues.push(format!(
+                "server key fingerprint {} doesn't match the announced {}",
+                input.server_key_fingerprint, announced
+            ));
+        }
+        Some(_) => {}
+        None => report
+            .issues
+            .push("owner didn't announce a server key fingerprint".to_string()),
+    }
+
+    // Parameters.
+    if let Err(err) = &kernel {
+        report.issues.push(err.to_string());
+    }
+    if let Some(declared) = input.metadata.kernel
+        && declared != input.config.kernel
+    {
+        report.issues.push(format!(
+            "owner asked for {:?} but the screening is configured for {:?}",
+            declared, input.config.kernel
+        ));
+    }
+    if let Err(err) = depth {
+        report.issues.push(err.to_string());
+    }
+
+    report
+}
@@ -0,0 +1,270 @@
+// `.eft` (encrypted flight trajectory) files: an encrypted trajectory laid out so that
+// single ciphertexts can be read without loading the rest.
+//
+// Layout: the magic `EFT` plus the format version digit, a little-endian `u64` header
+// length, the bincode header (trajectory metadata plus the byte range of every
+// ciphertext), then the ciphertexts back to back, each serialized with
+// `safe_serialize_item`. Version 1 files (`EFT1`) lack the header's `producer`; they are
+// still read, and `migrate::upgrade_eft` rewrites them. `EftReader` memory-maps the
+// file and deserializes a step only when asked for it, so a multi-GB screening runs in
+// roughly the memory of one step at a time.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use tfhe::FheUint32;
+
+use crate::common::{SatelliteData, safe_deserialize_item, safe_serialize_item};
+use crate::depth::OpCounter;
+use crate::frame::Frame;
+use crate::screening::{
+    ScreeningConfig, ScreeningOutput, align_plaintext_to, exact_match_cost, exact_match_step,
+};
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::Units;
+
+const MAGIC_PREFIX: &[u8; 3] = b"EFT";
+pub const EFT_VERSION: u8 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct EftHeader {
+    // Crate name and version that wrote the file.
+    producer: String,
+    frame: Frame,
+    units: Units,
+    first_index: usize,
+    epochs: Vec<u64>,
+    // (offset, length) of each ciphertext in the data section, step-major: x, y, z of
+    // step 0, then step 1, ...
+    ranges: Vec<(u64, u64)>,
+}
+
+// Header of version 1 files.
+#[derive(Serialize, Deserialize)]
+struct EftHeaderV1 {
+    frame: Frame,
+    units: Units,
+    first_index: usize,
+    epochs: Vec<u64>,
+    ranges: Vec<(u64, u64)>,
+}
+
+fn producer() -> String {
+    format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+// Writes a current-version file from its header and data section.
+fn write_file(
+    path: &Path,
+    header: &EftHeader,
+    blobs: &[&[u8]],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let header = bincode::serialize(header)?;
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC_PREFIX)?;
+    out.write_all(&[b'0' + EFT_VERSION])?;
+    out.write_all(&(header.len() as u64).to_le_bytes())?;
+    out.write_all(&header)?;
+    for blob in blobs {
+        out.write_all(blob)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+pub fn write_eft(
+    path: impl AsRef<Path>,
+    trajectory: &EncryptedTrajectory,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut blobs = Vec::with_capacity(3 * trajectory.len());
+    for i in 0..trajectory.len() {
+        for axis in [&trajectory.x, &trajectory.y, &trajectory.z] {
+            blobs.push(safe_serialize_item(&axis[i])?);
+        }
+    }
+    let mut offset = 0u64;
+    let ranges = blobs
+        .iter()
+        .map(|blob| {
+            let range = (offset, blob.len() as u64);
+            offset += blob.len() as u64;
+            range
+        })
+        .collect();
+    let header = EftHeader {
+        producer: producer(),
+        frame: trajectory.frame,
+        units: trajectory.units,
+        first_index: trajectory.first_index,
+        epochs: trajectory.epochs.clone(),
+        ranges,
+    };
+    let blobs: Vec<&[u8]> = blobs.iter().map(Vec::as_slice).collect();
+    write_file(path.as_ref(), &header, &blobs)
+}
+
+pub struct EftReader {
+    mmap: Mmap,
+    version: u8,
+    header: EftHeader,
+    data_start: usize,
+}
+
+impl EftReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        // Safety: the file is only read, and it must not be modified while mapped; a
+        // concurrent writer would at worst make a ciphertext fail to deserialize.
+        let mmap = unsafe { Mmap::map(&file)? };
+        if mmap.len() < 12 || &mmap[..3] != MAGIC_PREFIX {
+            return Err("not an .eft file".into());
+        }
+        let version = mmap[3].wrapping_sub(b'0');
+        if version == 0 || version > EFT_VERSION {
+            return Err(format!(
+                ".eft format version {} is newer than this build supports ({})",
+                mmap[3] as char, EFT_VERSION
+            )
+            .into());
+        }
+        let header_len = u64::from_le_bytes(mmap[4..12].try_into()?) as usize;
+        let data_start = 12usize
+            .checked_add(header_len)
+            .filter(|&end| end <= mmap.len())
+            .ok_or("truncated .eft header")?;
+        let header = if version == 1 {
+            let v1: EftHeaderV1 = bincode::deserialize(&mmap[12..data_start])?;
+            EftHeader {
+                producer: "unknown (.eft version 1)".to_string(),
+                frame: v1.frame,
+                units: v1.units,
+                first_index: v1.first_index,
+                epochs: v1.epochs,
+                ranges: v1.ranges,
+            }
+        } else {
+            bincode::deserialize(&mmap[12..data_start])?
+        };
+        if header.ranges.len() != 3 * header.epochs.len() {
+            return Err(".eft ciphertext count doesn't match its epochs".into());
+        }
+        let data_len = (mmap.len() - data_start) as u64;
+        if header
+            .ranges
+            .iter()
+            .any(|&(o, l)| o.checked_add(l).is_none_or(|end| end > data_len))
+        {
+            return Err(".eft ciphertext range outside the file".into());
+        }
+        Ok(Self {
+            mmap,
+            version,
+            header,
+            data_start,
+        })
+    }
+
+    // Format version the file was written in.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn producer(&self) -> &str {
+        &self.header.producer
+    }
+
+    // Writes this file to `path` in the current format, copying the ciphertexts as-is.
+    pub(crate) fn rewrite(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let header = EftHeader {
+            producer: producer(),
+            frame: self.header.frame,
+            units: self.header.units,
+            first_index: self.header.first_index,
+            epochs: self.header.epochs.clone(),
+            ranges: self.header.ranges.clone(),
+        };
+        write_file(path, &header, &[&self.mmap[self.data_start..]])
+    }
+
+    pub fn len(&self) -> usize {
+        self.header.epochs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.epochs.is_empty()
+    }
+
+    pub fn epochs(&self) -> &[u64] {
+        &self.header.epochs
+    }
+
+    pub fn first_index(&self) -> usize {
+        self.header.first_index
+    }
+
+    pub fn frame(&self) -> Frame {
+        self.header.frame
+    }
+
+    pub fn units(&self) -> Units {
+        self.header.units
+    }
+
+    // Total size of the ciphertexts, without the header.
+    pub fn ciphertext_bytes(&self) -> usize {
+        self.mmap.len() - self.data_start
+    }
+
+    // Deserializes the x, y, z ciphertexts of step `i` (relative to `first_index`).
+    pub fn step(&self, i: usize) -> Result<[FheUint32; 3], Box<dyn std::error::Error>> {
+        if i >= self.len() {
+            return Err(format!("step {} out of range for {} steps", i, self.len()).into());
+        }
+        let read = |k: usize| -> Result<FheUint32, Box<dyn std::error::Error>> {
+            let (offset, len) = self.header.ranges[3 * i + k];
+            let start = self.data_start + offset as usize;
+            safe_deserialize_item(&self.mmap[start..start + len as usize])
+        };
+        Ok([read(0)?, read(1)?, read(2)?])
+    }
+}
+
+// `screen_exact` over an `.eft` file, one step in memory at a time.
+//
+// Runs under the server key matching the file, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_exact_eft(
+    reader: &EftReader,
+    plaintext: &SatelliteData,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let offset = reader.first_index();
+    let plaintext = align_plaintext_to(
+        offset..offset + reader.len(),
+        reader.frame(),
+        reader.units(),
+        plaintext,
+    )?;
+    let mut step = exact_match_cost();
+    config.check_depth(&mut step)?;
+
+    let mut results = Vec::with_capacity(reader.len());
+    for i in 0..reader.len() {
+        let [x, y, z] = reader.step(i)?;
+        let p = offset + i;
+        results.push(exact_match_step(
+            [
+                (&x, config.clear(plaintext.x[p])),
+                (&y, config.clear(plaintext.y[p])),
+                (&z, config.clear(plaintext.z[p])),
+            ],
+            config.parallel_axes,
+        ));
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, reader.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
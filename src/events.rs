@@ -0,0 +1,46 @@
+// Post-decryption reporting: consecutive positive time steps are one conjunction event.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConjunctionEvent {
+    // Absolute index of the first positive step.
+    pub start_index: usize,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+    pub n_steps: usize,
+}
+
+// Clusters runs of adjacent `true` flags into events. `epochs[i]` is the epoch of
+// `flags[i]`; `first_index` is the absolute index of `flags[0]` (non-zero for windowed
+// trajectories).
+pub fn cluster(
+    flags: &[bool],
+    epochs: &[u64],
+    first_index: usize,
+) -> Result<Vec<ConjunctionEvent>, Box<dyn std::error::Error>> {
+    if flags.len() != epochs.len() {
+        return Err(format!("{} flags but {} epochs", flags.len(), epochs.len()).into());
+    }
+
+    let mut events: Vec<ConjunctionEvent> = Vec::new();
+    let mut previous_positive = false;
+    for (i, &flag) in flags.iter().enumerate() {
+        if flag {
+            match events.last_mut() {
+                Some(event) if previous_positive => {
+                    event.end_epoch = epochs[i];
+                    event.n_steps += 1;
+                }
+                _ => events.push(ConjunctionEvent {
+                    start_index: first_index + i,
+                    start_epoch: epochs[i],
+                    end_epoch: epochs[i],
+                    n_steps: 1,
+                }),
+            }
+        }
+        previous_positive = flag;
+    }
+    Ok(events)
+}
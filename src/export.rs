@@ -0,0 +1,332 @@
+// Plaintext trajectories written back out as CSV or CCSDS OEM.
+//
+// An owner who decrypts its own encrypted trajectory (`OwnerParty::decrypt_trajectory`)
+// can export it and compare it with the source it was encoded from: encoding rounds each
+// component to a whole step of the trajectory's units, and nothing else may change it,
+// so every decoded component is within `quantization_tolerance` of the original
+// (`round_trip_error`). Positions are decoded with the canonical encoding (see
+// `core::canonical`) in the trajectory's own units.
+//
+// CSV is one `epoch_s,x_m,y_m,z_m` row per step. OEM is the KVN form of a CCSDS Orbit
+// Ephemeris Message, version 2.0, with epochs taken as Unix seconds in UTC. OEM requires
+// a velocity per step, which a position-only trajectory doesn't have, so velocities are
+// finite differences of the positions. There is no TLE output: a TLE is a set of fitted
+// mean elements, not a sampled trajectory.
+//
+// `from_oem` reads OEMs published by others, e.g. the ISS ephemeris (see `asset`): UTC
+// epochs in calendar or day-of-year form, one or more segments in the same frame,
+// covariance blocks skipped.
+
+use std::fmt::Write;
+
+use crate::common::SatelliteData;
+use crate::core::canonical::{CANONICAL, CanonicalEncoding};
+use crate::frame::Frame;
+use crate::units::Units;
+
+// Epochs and positions in meters, as read back from CSV.
+pub type Ephemeris = (Vec<u64>, Vec<[f64; 3]>);
+
+// Largest error encoding can introduce per component, in meters.
+pub fn quantization_tolerance(units: Units) -> f64 {
+    units.meters() / 2.0
+}
+
+// Positions of `data` in meters, one per step.
+pub fn positions_m(data: &SatelliteData) -> Vec<[f64; 3]> {
+    CanonicalEncoding {
+        units: data.units,
+        ..CANONICAL
+    }
+    .decode_trajectory(data)
+}
+
+// Largest per-component difference in meters between `original` positions and the
+// decoded `data`.
+pub fn round_trip_error(
+    original: &[[f64; 3]],
+    data: &SatelliteData,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let decoded = positions_m(data);
+    if decoded.len() != original.len() {
+        return Err(format!(
+            "{} decoded steps for {} original ones",
+            decoded.len(),
+            original.len()
+        )
+        .into());
+    }
+    Ok(decoded
+        .iter()
+        .zip(original)
+        .flat_map(|(decoded, original)| {
+            (0..3).map(move |axis| (decoded[axis] - original[axis]).abs())
+        })
+        .fold(0.0, f64::max))
+}
+
+fn check_epochs(data: &SatelliteData, epochs: &[u64]) -> Result<(), Box<dyn std::error::Error>> {
+    if epochs.len() != data.x.len() {
+        return Err(format!("{} epochs for {} steps", epochs.len(), data.x.len()).into());
+    }
+    if epochs.windows(2).any(|w| w[0] >= w[1]) {
+        return Err("epochs must be strictly increasing".into());
+    }
+    Ok(())
+}
+
+pub fn to_csv(data: &SatelliteData, epochs: &[u64]) -> Result<String, Box<dyn std::error::Error>> {
+    check_epochs(data, epochs)?;
+    let mut out = String::from("epoch_s,x_m,y_m,z_m\n");
+    for (epoch, [x, y, z]) in epochs.iter().zip(positions_m(data)) {
+        let _ = writeln!(out, "{},{},{},{}", epoch, x, y, z);
+    }
+    Ok(out)
+}
+
+// Reads a CSV written by `to_csv`.
+pub fn from_csv(text: &str) -> Result<Ephemeris, Box<dyn std::error::Error>> {
+    let mut epochs = Vec::new();
+    let mut positions = Vec::new();
+    for (n, line) in text.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 4 {
+            return Err(
+                format!("line {}: expected 4 fields, found {}", n + 1, fields.len()).into(),
+            );
+        }
+        epochs.push(fields[0].parse()?);
+        positions.push([fields[1].parse()?, fields[2].parse()?, fields[3].parse()?]);
+    }
+    Ok((epochs, positions))
+}
+
+// `unix_s` as an ISO 8601 UTC timestamp, e.g. `2024-03-01T12:00:00.000`.
+pub(crate) fn iso8601(unix_s: u64) -> String {
+    let days = (unix_s / 86_400) as i64;
+    let secs = unix_s % 86_400;
+    // Days since 1970-01-01 to a civil date (Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+// Velocity in m/s at each step: central differences inside, one-sided at the ends.
+pub(crate) fn velocities(positions: &[[f64; 3]], epochs: &[u64]) -> Vec<[f64; 3]> {
+    let n = positions.len();
+    (0..n)
+        .map(|i| {
+            if n < 2 {
+                return [0.0; 3];
+            }
+            let (a, b) = (i.saturating_sub(1), (i + 1).min(n - 1));
+            let dt = (epochs[b] - epochs[a]) as f64;
+            [0, 1, 2].map(|axis| (positions[b][axis] - positions[a][axis]) / dt)
+        })
+        .collect()
+}
+
+// `data` as an OEM for `object_name`, epochs in Unix seconds.
+pub fn to_oem(
+    data: &SatelliteData,
+    epochs: &[u64],
+    object_name: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_epochs(data, epochs)?;
+    let (Some(&start), Some(&stop)) = (epochs.first(), epochs.last()) else {
+        return Err("an OEM needs at least one step".into());
+    };
+    let created = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let ref_frame = match data.frame {
+        Frame::Eci => "EME2000",
+        Frame::Ecef => "ITRF",
+    };
+    let mut out = String::new();
+    let _ = writeln!(out, "CCSDS_OEM_VERS = 2.0");
+    let _ = writeln!(out, "CREATION_DATE = {}", iso8601(created));
+    let _ = writeln!(out, "ORIGINATOR = sat-trajectory-fhe");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "META_START");
+    let _ = writeln!(out, "OBJECT_NAME = {}", object_name);
+    let _ = writeln!(out, "OBJECT_ID = {}", object_name);
+    let _ = writeln!(out, "CENTER_NAME = EARTH");
+    let _ = writeln!(out, "REF_FRAME = {}", ref_frame);
+    let _ = writeln!(out, "TIME_SYSTEM = UTC");
+    let _ = writeln!(out, "START_TIME = {}", iso8601(start));
+    let _ = writeln!(out, "STOP_TIME = {}", iso8601(stop));
+    let _ = writeln!(out, "META_STOP");
+    let _ = writeln!(out);
+    let positions = positions_m(data);
+    for ((epoch, position), velocity) in epochs
+        .iter()
+        .zip(&positions)
+        .zip(velocities(&positions, epochs))
+    {
+        // Kilometers and kilometers per second.
+        let _ = writeln!(
+            out,
+            "{} {:.3} {:.3} {:.3} {:.6} {:.6} {:.6}",
+            iso8601(*epoch),
+            position[0] / 1_000.0,
+            position[1] / 1_000.0,
+            position[2] / 1_000.0,
+            velocity[0] / 1_000.0,
+            velocity[1] / 1_000.0,
+            velocity[2] / 1_000.0
+        );
+    }
+    Ok(out)
+}
+
+// Unix seconds of midnight UTC starting `year`-`month`-`day` (Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Unix seconds of an OEM epoch, `YYYY-MM-DDThh:mm:ss[.f]` or `YYYY-DDDThh:mm:ss[.f]`, in
+// UTC with an optional trailing `Z`.
+pub(crate) fn parse_epoch(text: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    let invalid = || format!("invalid epoch {:?}", text);
+    let (date, time) = text
+        .trim_end_matches('Z')
+        .split_once('T')
+        .ok_or_else(invalid)?;
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let days = match date[..] {
+        [year, month, day] if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+            days_from_civil(year, month, day)
+        }
+        [year, doy] if (1..=366).contains(&doy) => days_from_civil(year, 1, 1) + doy - 1,
+        _ => return Err(invalid().into()),
+    };
+    let time: Vec<f64> = time
+        .split(':')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let [hours, minutes, seconds] = time[..] else {
+        return Err(invalid().into());
+    };
+    Ok(days as f64 * 86_400.0 + hours * 3_600.0 + minutes * 60.0 + seconds)
+}
+
+// The states of an OEM, in meters and meters per second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OemEphemeris {
+    pub object_name: String,
+    pub frame: Frame,
+    // Unix seconds, strictly increasing.
+    pub epochs_s: Vec<f64>,
+    pub positions_m: Vec<[f64; 3]>,
+    pub velocities_mps: Vec<[f64; 3]>,
+}
+
+fn oem_frame(name: &str) -> Result<Frame, Box<dyn std::error::Error>> {
+    match name {
+        "EME2000" | "J2000" | "GCRF" | "ICRF" => Ok(Frame::Eci),
+        name if name.starts_with("ITRF") => Ok(Frame::Ecef),
+        name => Err(format!("unsupported OEM reference frame {}", name).into()),
+    }
+}
+
+// Reads the KVN form of an OEM.
+pub fn from_oem(text: &str) -> Result<OemEphemeris, Box<dyn std::error::Error>> {
+    let mut ephemeris = OemEphemeris {
+        object_name: String::new(),
+        frame: Frame::Eci,
+        epochs_s: Vec::new(),
+        positions_m: Vec::new(),
+        velocities_mps: Vec::new(),
+    };
+    let mut frame = None;
+    let (mut in_meta, mut in_covariance) = (false, false);
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("COMMENT") {
+            continue;
+        }
+        match line {
+            "META_START" => in_meta = true,
+            "META_STOP" => in_meta = false,
+            "COVARIANCE_START" => in_covariance = true,
+            "COVARIANCE_STOP" => in_covariance = false,
+            _ if in_covariance => {}
+            _ if in_meta => {
+                let Some((key, value)) = line.split_once('=') else {
+                    return Err(format!("line {}: expected KEY = VALUE", n + 1).into());
+                };
+                match (key.trim(), value.trim()) {
+                    ("OBJECT_NAME", name) => ephemeris.object_name = name.to_string(),
+                    ("REF_FRAME", name) => {
+                        let segment = oem_frame(name)?;
+                        if frame.is_some_and(|frame| frame != segment) {
+                            return Err("OEM segments are in different frames".into());
+                        }
+                        frame = Some(segment);
+                    }
+                    ("TIME_SYSTEM", "UTC") => {}
+                    ("TIME_SYSTEM", other) => {
+                        return Err(format!("unsupported OEM time system {}", other).into());
+                    }
+                    _ => {}
+                }
+            }
+            // Header lines.
+            _ if line.contains('=') => {}
+            _ => {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                // Accelerations, if present, are ignored.
+                if fields.len() != 7 && fields.len() != 10 {
+                    return Err(format!("line {}: expected an epoch and a state", n + 1).into());
+                }
+                let epoch = parse_epoch(fields[0])?;
+                if ephemeris.epochs_s.last().is_some_and(|&last| last >= epoch) {
+                    return Err(format!("line {}: epochs must be increasing", n + 1).into());
+                }
+                let mut state = [0.0; 6];
+                for (value, field) in state.iter_mut().zip(&fields[1..7]) {
+                    // Kilometers and kilometers per second.
+                    *value = field.parse::<f64>()? * 1_000.0;
+                }
+                ephemeris.epochs_s.push(epoch);
+                ephemeris.positions_m.push([state[0], state[1], state[2]]);
+                ephemeris
+                    .velocities_mps
+                    .push([state[3], state[4], state[5]]);
+            }
+        }
+    }
+    ephemeris.frame = frame.ok_or("OEM has no REF_FRAME")?;
+    if ephemeris.epochs_s.is_empty() {
+        return Err("OEM has no states".into());
+    }
+    Ok(ephemeris)
+}
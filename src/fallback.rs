@@ -0,0 +1,169 @@
+// Recovering from a result artifact the owner can't read, without redoing the screening.
+//
+// A `ResultBundle` can arrive unreadable: larger than the owner's `result_bundle` limit
+// (see `common::SerializationLimits`), truncated or corrupted on the way, or with
+// compressed flags that fail to decompress, e.g. under a key loaded without its
+// decompression component (see `splitkey`). The flags took the evaluator hours to compute
+// and it still holds them, so instead of aborting, the owner answers with a `Resend`
+// message (`Session::request_resend`) naming the steps to send again and a `Fallback`
+// transfer for them: the flags uncompressed, or cut into plain bundles small enough for
+// any limit. Each request escalates from the last one (`Fallback::after`), chunking being
+// the last resort, after which the session gives up. The evaluator answers with
+// `rebundle` from the flags it kept, without evaluating anything again.
+
+use std::fmt;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use serde::{Deserialize, Serialize};
+use tfhe::FheBool;
+
+use crate::common::{SerializationLimits, serialization_limits};
+use crate::result_bundle::ResultBundle;
+
+// How the evaluator resends the flags of a `ResendRequest`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    // One plain bundle.
+    Uncompressed,
+    // Plain bundles of at most `chunk_steps` flags each.
+    Chunked { chunk_steps: usize },
+}
+
+impl Fallback {
+    // Plain bundles that fit the `result_bundle` limit even if every flag takes the most
+    // a serialized flag may.
+    pub fn chunked(limits: &SerializationLimits) -> Self {
+        Fallback::Chunked {
+            chunk_steps: (limits.result_bundle / limits.fhe_bool.max(1)).max(1) as usize,
+        }
+    }
+
+    // The transfer to ask for after `failure`, `previous` being the fallback already
+    // asked for, if any; `None` once chunking failed too.
+    pub fn after(
+        failure: &BundleFailure,
+        previous: Option<Fallback>,
+        limits: &SerializationLimits,
+    ) -> Option<Self> {
+        match (previous, failure) {
+            (None, BundleFailure::TooLarge { .. }) => Some(Self::chunked(limits)),
+            (None, _) => Some(Fallback::Uncompressed),
+            (Some(Fallback::Uncompressed), _) => Some(Self::chunked(limits)),
+            (Some(Fallback::Chunked { .. }), _) => None,
+        }
+    }
+}
+
+// Steps `first_index..first_index + steps` of a result artifact to send again.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResendRequest {
+    pub first_index: usize,
+    pub steps: usize,
+    pub fallback: Fallback,
+}
+
+impl ResendRequest {
+    // Bundles the evaluator answers with.
+    pub fn bundles(&self) -> usize {
+        match self.fallback {
+            Fallback::Uncompressed => 1,
+            Fallback::Chunked { chunk_steps } => self.steps.div_ceil(chunk_steps.max(1)),
+        }
+    }
+}
+
+// Why a result bundle couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleFailure {
+    TooLarge { size: u64, limit: u64 },
+    // Not a bundle, or a truncated one.
+    Malformed(String),
+    // Compressed flags that wouldn't decompress.
+    Decompression(String),
+}
+
+impl fmt::Display for BundleFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleFailure::TooLarge { size, limit } => write!(
+                f,
+                "result bundle of {} bytes exceeds the limit of {}",
+                size, limit
+            ),
+            BundleFailure::Malformed(reason) => write!(f, "malformed result bundle: {}", reason),
+            BundleFailure::Decompression(reason) => {
+                write!(f, "result bundle failed to decompress: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BundleFailure {}
+
+// The bundle serialized in `data`, its flags decompressed if they were compressed. Needs
+// the server key installed, e.g. inside `FheContext::evaluate_with`.
+pub fn open_bundle(data: &[u8]) -> Result<ResultBundle, BundleFailure> {
+    let limit = serialization_limits().result_bundle;
+    if data.len() as u64 > limit {
+        return Err(BundleFailure::TooLarge {
+            size: data.len() as u64,
+            limit,
+        });
+    }
+    let bundle =
+        ResultBundle::from_bytes(data).map_err(|e| BundleFailure::Malformed(e.to_string()))?;
+    if !bundle.is_compressed() {
+        return Ok(bundle);
+    }
+    let (first_index, failed) = (bundle.first_index, bundle.failed().to_vec());
+    let flags = catch_unwind(AssertUnwindSafe(|| bundle.into_flags())).map_err(|payload| {
+        BundleFailure::Decompression(
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "decompression panicked".to_string()),
+        )
+    })?;
+    Ok(ResultBundle::new(first_index, flags).with_failed(failed))
+}
+
+// The plain bundles answering `request`, from the `flags` of the original bundle, the
+// first of them at absolute step `first_index`, with the unverified steps `failed`.
+pub fn rebundle(
+    request: &ResendRequest,
+    first_index: usize,
+    flags: &[FheBool],
+    failed: &[usize],
+) -> Result<Vec<ResultBundle>, Box<dyn std::error::Error>> {
+    let start = request
+        .first_index
+        .checked_sub(first_index)
+        .filter(|&start| start + request.steps <= flags.len())
+        .ok_or_else(|| {
+            format!(
+                "resend of steps {}..{} asked for, but the results cover {}..{}",
+                request.first_index,
+                request.first_index + request.steps,
+                first_index,
+                first_index + flags.len()
+            )
+        })?;
+    let chunk_steps = match request.fallback {
+        Fallback::Uncompressed => request.steps.max(1),
+        Fallback::Chunked { chunk_steps } => chunk_steps.max(1),
+    };
+    Ok(flags[start..start + request.steps]
+        .chunks(chunk_steps)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let chunk_first = request.first_index + i * chunk_steps;
+            let chunk_failed = failed
+                .iter()
+                .copied()
+                .filter(|index| (chunk_first..chunk_first + chunk.len()).contains(index))
+                .collect();
+            ResultBundle::new(chunk_first, chunk.to_vec()).with_failed(chunk_failed)
+        })
+        .collect())
+}
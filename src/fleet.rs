@@ -0,0 +1,181 @@
+// Several satellites of one operator screened in one session.
+//
+// Operators own fleets, and screening them one session (and one server key) per
+// satellite repeats the key upload and the handshake for nothing. A `Fleet` holds
+// trajectories by `ObjectId`; the owner encrypts all of them under its one key into an
+// `EncryptedFleet`, sent as a single artifact. The evaluator screens every member
+// against each of its counterpart trajectories and returns `FleetResults`, keyed by
+// (member ID, counterpart index), so a hit can be attributed to both satellites. To keep
+// the members' identities from the evaluator, send `Fleet::blinded` instead.
+
+use std::collections::BTreeMap;
+
+use tfhe::FheBool;
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::migrate::{self, ArtifactKind};
+use crate::object_id::{Blinder, ObjectId};
+use crate::planner::{Operand, screen_planned};
+use crate::screening::{ScreeningConfig, results_from_bytes, results_to_bytes};
+use crate::trajectory::EncryptedTrajectory;
+
+// (ID of the owner's satellite, index of the evaluator's counterpart trajectory).
+pub type FleetKey = (ObjectId, usize);
+
+// The owner's plaintext trajectories, by ID, in the order they were added.
+#[derive(Clone, Default)]
+pub struct Fleet {
+    members: Vec<(ObjectId, SatelliteData)>,
+}
+
+impl Fleet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds a satellite; IDs must be unique within the fleet.
+    pub fn add(
+        &mut self,
+        id: impl Into<ObjectId>,
+        trajectory: SatelliteData,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let id = id.into();
+        if self.get(&id).is_some() {
+            return Err(format!("fleet already has a satellite {}", id).into());
+        }
+        self.members.push((id, trajectory));
+        Ok(())
+    }
+
+    pub fn get(&self, id: &ObjectId) -> Option<&SatelliteData> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == id)
+            .map(|(_, trajectory)| trajectory)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &ObjectId> {
+        self.members.iter().map(|(id, _)| id)
+    }
+
+    pub fn members(&self) -> &[(ObjectId, SatelliteData)] {
+        &self.members
+    }
+
+    // The same fleet with every ID blinded by `blinder`; results come back keyed by the
+    // blinded IDs, which `Blinder::resolve` maps back to `ids`.
+    pub fn blinded(&self, blinder: &Blinder) -> Self {
+        Self {
+            members: self
+                .members
+                .iter()
+                .map(|(id, trajectory)| (blinder.blind(id), trajectory.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+// Every member of a `Fleet`, encrypted under the owner's key.
+pub struct EncryptedFleet {
+    pub members: Vec<(ObjectId, EncryptedTrajectory)>,
+}
+
+impl EncryptedFleet {
+    pub fn get(&self, id: &ObjectId) -> Option<&EncryptedTrajectory> {
+        self.members
+            .iter()
+            .find(|(member, _)| member == id)
+            .map(|(_, trajectory)| trajectory)
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    // One artifact holding every member's `EncryptedTrajectory::to_bytes`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let members = self
+            .members
+            .iter()
+            .map(|(id, trajectory)| Ok((id.clone(), trajectory.to_bytes()?)))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        migrate::encode(ArtifactKind::Fleet, &members)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let members: Vec<(ObjectId, Vec<u8>)> = migrate::decode(ArtifactKind::Fleet, data)?;
+        Ok(Self {
+            members: members
+                .into_iter()
+                .map(|(id, bytes)| Ok((id, EncryptedTrajectory::from_bytes(&bytes)?)))
+                .collect::<Result<_, Box<dyn std::error::Error>>>()?,
+        })
+    }
+}
+
+// Per-step flags of every (member, counterpart) pair.
+#[derive(Default)]
+pub struct FleetResults {
+    pub flags: BTreeMap<FleetKey, Vec<FheBool>>,
+}
+
+impl FleetResults {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let pairs = self
+            .flags
+            .iter()
+            .map(|((id, counterpart), flags)| {
+                Ok((id.clone(), *counterpart, results_to_bytes(flags)?))
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        migrate::encode(ArtifactKind::FleetResults, &pairs)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let pairs: Vec<(ObjectId, usize, Vec<u8>)> =
+            migrate::decode(ArtifactKind::FleetResults, data)?;
+        let mut flags = BTreeMap::new();
+        for (id, counterpart, bytes) in pairs {
+            flags.insert((id, counterpart), results_from_bytes(&bytes)?);
+        }
+        Ok(Self { flags })
+    }
+}
+
+pub struct FleetOutput {
+    pub results: FleetResults,
+    pub ops: OpCounter,
+}
+
+// Screens every member of `fleet` against every trajectory of `counterparts`.
+//
+// Runs under the server key matching `fleet`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_fleet(
+    fleet: &EncryptedFleet,
+    counterparts: &[SatelliteData],
+    config: &ScreeningConfig,
+) -> Result<FleetOutput, Box<dyn std::error::Error>> {
+    let mut results = FleetResults::default();
+    let mut ops = OpCounter::default();
+    for (id, encrypted) in &fleet.members {
+        for (index, counterpart) in counterparts.iter().enumerate() {
+            let output = screen_planned(encrypted, Operand::Clear(counterpart), config)?;
+            ops.add_steps(&output.ops, 1);
+            results.flags.insert((id.clone(), index), output.results);
+        }
+    }
+    Ok(FleetOutput { results, ops })
+}
@@ -0,0 +1,13 @@
+// Coordinate reference frames and their conversions, see `core::frame`.
+
+pub use crate::core::frame::{Frame, convert, ecef_to_eci, eci_to_ecef, gmst_rad};
+use crate::protocol::ProtocolError;
+
+// Both parties' trajectories must be in the same frame; whoever differs has to convert
+// before encoding.
+pub fn check_frames(owner: Frame, evaluator: Frame) -> Result<(), ProtocolError> {
+    if owner != evaluator {
+        return Err(ProtocolError::FrameMismatch { owner, evaluator });
+    }
+    Ok(())
+}
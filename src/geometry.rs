@@ -0,0 +1,150 @@
+// Follow-up on conjunction candidates: where the owner's satellite is relative to the
+// evaluator's, in the evaluator's radial / in-track / cross-track (RIC) frame.
+//
+// A flag says two objects are close, not how to move apart; operators plan manoeuvres in
+// RIC components. Once the owner has decrypted the flags and agrees to ask, the evaluator
+// computes, at each requested step, the encrypted separation `A - B` and projects it on
+// the RIC basis of its own object, which it knows in the clear: radial along its
+// position, cross-track along its orbit normal (position x velocity, with the velocity
+// taken from its next step), in-track completing the frame. The projection is a dot
+// product with clear coefficients, so it costs only scalar multiplications. As with
+// `velocity`, the requested step indices are revealed to the evaluator.
+//
+// Everything runs in `FheUint64` with wrapping arithmetic: the separation and the
+// fixed-point coefficients are two's-complement values, and each component is a signed
+// number in units of the encrypted trajectory times `2^RIC_SCALE_BITS`. See `decode`.
+
+use tfhe::FheUint64;
+use tfhe::prelude::*;
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::screening::align_plaintext;
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::{Units, unbias};
+
+// Fractional bits of the fixed-point RIC coefficients.
+pub const RIC_SCALE_BITS: u32 = 16;
+
+pub struct RicOutput {
+    // Absolute step indices the components were computed at.
+    pub steps: Vec<usize>,
+    // Encrypted radial, in-track and cross-track components per requested step.
+    pub components: Vec<[FheUint64; 3]>,
+    pub ops: OpCounter,
+}
+
+// Cost of one step: three scalar subtractions for the separation, then per component
+// three scalar multiplications and two additions.
+pub fn ric_cost() -> OpCounter {
+    OpCounter {
+        comparisons: 0,
+        arithmetic: 3 + 3 * 5,
+        boolean: 0,
+        depth: 4,
+        depth_warning: None,
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn unit(v: [f64; 3]) -> Option<[f64; 3]> {
+    let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    (norm > 0.0).then(|| [v[0] / norm, v[1] / norm, v[2] / norm])
+}
+
+// Unit radial, in-track and cross-track vectors of `plaintext` at absolute step `k`,
+// with the velocity taken towards step `k + 1` (or from `k - 1` at the last step).
+pub fn ric_basis(
+    plaintext: &SatelliteData,
+    k: usize,
+) -> Result<[[f64; 3]; 3], Box<dyn std::error::Error>> {
+    let position = |i: usize| -> [f64; 3] {
+        let offset = |v: u32| unbias(v) as f64;
+        [
+            offset(plaintext.x[i]),
+            offset(plaintext.y[i]),
+            offset(plaintext.z[i]),
+        ]
+    };
+    let len = plaintext.x.len();
+    if k >= len || len < 2 {
+        return Err(format!("no velocity at step {} of a trajectory of {} steps", k, len).into());
+    }
+    let r = position(k);
+    let v = if k + 1 < len {
+        sub(position(k + 1), r)
+    } else {
+        sub(r, position(k - 1))
+    };
+    let degenerate = || format!("RIC frame is undefined at step {}", k);
+    let radial = unit(r).ok_or_else(degenerate)?;
+    let cross_track = unit(cross(r, v)).ok_or_else(degenerate)?;
+    let in_track = cross(cross_track, radial);
+    Ok([radial, in_track, cross_track])
+}
+
+// RIC components of the separation between the owner's encrypted trajectory and
+// `plaintext` at the absolute steps `steps`.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn ric_separation(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    steps: &[usize],
+) -> Result<RicOutput, Box<dyn std::error::Error>> {
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let offset = encrypted.first_index;
+    let scale = (1u64 << RIC_SCALE_BITS) as f64;
+
+    let mut components = Vec::with_capacity(steps.len());
+    for &k in steps {
+        if k < offset || k >= offset + encrypted.len() {
+            return Err(format!(
+                "step {} is outside steps {}..{}",
+                k,
+                offset,
+                offset + encrypted.len()
+            )
+            .into());
+        }
+        let i = k - offset;
+        let basis = ric_basis(&plaintext, k)?;
+        let separation = |a: &FheUint64, b: u32| a - b as u64;
+        let d = [
+            separation(&encrypted.x[i].clone().cast_into(), plaintext.x[k]),
+            separation(&encrypted.y[i].clone().cast_into(), plaintext.y[k]),
+            separation(&encrypted.z[i].clone().cast_into(), plaintext.z[k]),
+        ];
+        let project = |axis: [f64; 3]| {
+            let coefficient = |c: f64| (c * scale).round() as i64 as u64;
+            (&d[0] * coefficient(axis[0]))
+                + (&d[1] * coefficient(axis[1]))
+                + (&d[2] * coefficient(axis[2]))
+        };
+        components.push([project(basis[0]), project(basis[1]), project(basis[2])]);
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&ric_cost(), steps.len() as u64);
+    Ok(RicOutput {
+        steps: steps.to_vec(),
+        components,
+        ops,
+    })
+}
+
+// Owner side: a decrypted component in meters.
+pub fn decode(component: u64, units: Units) -> f64 {
+    component as i64 as f64 / (1u64 << RIC_SCALE_BITS) as f64 * units.meters()
+}
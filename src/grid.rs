@@ -0,0 +1,270 @@
+// Coarse 16-bit voxel-grid screening for evaluators short on CPU or memory.
+//
+// A full screening compares three 32-bit ciphertexts per step. An evaluator that can't
+// afford that, e.g. a small operator's single ground-station box, advertises its
+// resources and asks for a coarser encoding instead (`Session::request_resolution`):
+// every coordinate becomes the 16-bit index of the `cell_size` voxel it lies in, counted
+// from the encoding's zero, so the comparisons run on half as many blocks and the
+// ciphertexts are half the size. The owner re-encodes and re-encrypts its trajectory at
+// that resolution (`OwnerParty::encrypt_at`). Positions in the same voxel match; the
+// price is that close positions on either side of a voxel boundary don't.
+//
+// The grid spans `u16::MAX` voxels per axis around the zero. The owner's trajectory has
+// to fit; evaluator positions outside it can't match anything and are screened as the
+// reserved voxel `OUTSIDE`. Voxel indices are computed by `core::grid`.
+
+use serde::{Deserialize, Serialize};
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheBool, FheUint16};
+
+use crate::common::{SatelliteData, safe_deserialize_items, safe_serialize_item};
+pub use crate::core::grid::{OUTSIDE, voxel};
+use crate::depth::OpCounter;
+use crate::frame::Frame;
+use crate::migrate::{self, ArtifactKind};
+use crate::screening::{ScreeningOutput, align_plaintext_to};
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::{Units, unbias};
+
+// Below either, an evaluator is considered constrained and asks for the grid.
+pub const FULL_MIN_THREADS: usize = 4;
+pub const FULL_MIN_MEMORY_BYTES: u64 = 8 << 30;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluatorResources {
+    pub threads: usize,
+    // Physical memory; `None` where it can't be read.
+    pub memory_bytes: Option<u64>,
+}
+
+impl EvaluatorResources {
+    // The machine this runs on.
+    pub fn detect() -> Self {
+        Self {
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            memory_bytes: total_memory(),
+        }
+    }
+
+    pub fn constrained(&self) -> bool {
+        self.threads < FULL_MIN_THREADS
+            || self.memory_bytes.is_some_and(|m| m < FULL_MIN_MEMORY_BYTES)
+    }
+}
+
+// MemTotal from /proc/meminfo.
+fn total_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    // 32-bit coordinates, screened with the session's kernel.
+    Full,
+    // 16-bit voxel indices, voxels `cell_size` units wide, screened by exact match.
+    Grid16 { cell_size: u32 },
+}
+
+// The evaluator's `MessageKind::Resolution` message.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolutionRequest {
+    pub resources: EvaluatorResources,
+    pub resolution: Resolution,
+}
+
+impl ResolutionRequest {
+    // Full resolution, or the grid with voxels of `cell_size` if `resources` are
+    // constrained.
+    pub fn for_resources(resources: EvaluatorResources, cell_size: u32) -> Self {
+        let resolution = if resources.constrained() {
+            Resolution::Grid16 { cell_size }
+        } else {
+            Resolution::Full
+        };
+        Self {
+            resources,
+            resolution,
+        }
+    }
+}
+
+fn owner_voxels(axis: &[u32], cell_size: u32) -> Result<Vec<u16>, Box<dyn std::error::Error>> {
+    axis.iter()
+        .map(|&v| {
+            voxel(v, cell_size).ok_or_else(|| {
+                format!(
+                    "coordinate {} is outside the 16-bit grid of {}-unit voxels",
+                    unbias(v),
+                    cell_size
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedGrid {
+    frame: Frame,
+    units: Units,
+    first_index: usize,
+    cell_size: u32,
+    x: Vec<Vec<u8>>,
+    y: Vec<Vec<u8>>,
+    z: Vec<Vec<u8>>,
+}
+
+// An owner trajectory encrypted at `Resolution::Grid16`.
+pub struct GridTrajectory {
+    pub x: Vec<FheUint16>,
+    pub y: Vec<FheUint16>,
+    pub z: Vec<FheUint16>,
+    pub first_index: usize,
+    pub cell_size: u32,
+    pub frame: Frame,
+    pub units: Units,
+}
+
+impl GridTrajectory {
+    pub fn encrypt(
+        data: &SatelliteData,
+        cell_size: u32,
+        client_key: &ClientKey,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let encrypt_axis = |axis: &[u32]| -> Result<Vec<FheUint16>, Box<dyn std::error::Error>> {
+            owner_voxels(axis, cell_size)?
+                .into_iter()
+                .map(|v| Ok(FheUint16::try_encrypt(v, client_key)?))
+                .collect()
+        };
+        Ok(Self {
+            x: encrypt_axis(&data.x)?,
+            y: encrypt_axis(&data.y)?,
+            z: encrypt_axis(&data.z)?,
+            first_index: 0,
+            cell_size,
+            frame: data.frame,
+            units: data.units,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let serialize_axis =
+            |axis: &[FheUint16]| -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+                axis.iter().map(safe_serialize_item).collect()
+            };
+        let serialized = SerializedGrid {
+            frame: self.frame,
+            units: self.units,
+            first_index: self.first_index,
+            cell_size: self.cell_size,
+            x: serialize_axis(&self.x)?,
+            y: serialize_axis(&self.y)?,
+            z: serialize_axis(&self.z)?,
+        };
+        migrate::encode(ArtifactKind::GridTrajectory, &serialized)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let serialized: SerializedGrid = migrate::decode(ArtifactKind::GridTrajectory, data)?;
+        let len = serialized.x.len();
+        if serialized.y.len() != len || serialized.z.len() != len {
+            return Err("grid trajectory axes have different lengths".into());
+        }
+        let deserialize_axis =
+            |axis: &[Vec<u8>]| -> Result<Vec<FheUint16>, Box<dyn std::error::Error>> {
+                safe_deserialize_items(axis)
+            };
+        Ok(Self {
+            x: deserialize_axis(&serialized.x)?,
+            y: deserialize_axis(&serialized.y)?,
+            z: deserialize_axis(&serialized.z)?,
+            first_index: serialized.first_index,
+            cell_size: serialized.cell_size,
+            frame: serialized.frame,
+            units: serialized.units,
+        })
+    }
+}
+
+// What the owner sends at the resolution the evaluator asked for.
+pub enum EncryptedInput {
+    Full(EncryptedTrajectory),
+    Grid(GridTrajectory),
+}
+
+impl EncryptedInput {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match self {
+            EncryptedInput::Full(trajectory) => trajectory.to_bytes(),
+            EncryptedInput::Grid(grid) => grid.to_bytes(),
+        }
+    }
+
+    // Tells the two apart by their artifact tag.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        if migrate::kind_of(data) == Some(ArtifactKind::GridTrajectory) {
+            Ok(EncryptedInput::Grid(GridTrajectory::from_bytes(data)?))
+        } else {
+            Ok(EncryptedInput::Full(EncryptedTrajectory::from_bytes(data)?))
+        }
+    }
+}
+
+// Cost of one grid step: three 16-bit comparisons, then `(x & y) & z`.
+pub fn grid_match_cost() -> OpCounter {
+    OpCounter {
+        comparisons: 3,
+        arithmetic: 0,
+        boolean: 2,
+        depth: 3,
+        depth_warning: None,
+    }
+}
+
+// Exact match of voxels: one flag per step of `encrypted`, true where `plaintext` is in
+// the same voxel on every axis.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_grid(
+    encrypted: &GridTrajectory,
+    plaintext: &SatelliteData,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let plaintext = align_plaintext_to(
+        encrypted.first_index..encrypted.first_index + encrypted.len(),
+        encrypted.frame,
+        encrypted.units,
+        plaintext,
+    )?;
+    let cell_size = encrypted.cell_size;
+    let clear = |axis: &[u32], i: usize| voxel(axis[encrypted.first_index + i], cell_size);
+    let results: Vec<FheBool> = (0..encrypted.len())
+        .map(|i| {
+            match (
+                clear(&plaintext.x, i),
+                clear(&plaintext.y, i),
+                clear(&plaintext.z, i),
+            ) {
+                (Some(x), Some(y), Some(z)) => {
+                    encrypted.x[i].eq(x) & encrypted.y[i].eq(y) & encrypted.z[i].eq(z)
+                }
+                // The owner is never outside the grid.
+                _ => encrypted.x[i].eq(OUTSIDE),
+            }
+        })
+        .collect();
+    let mut ops = OpCounter::default();
+    ops.add_steps(&grid_match_cost(), results.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
@@ -0,0 +1,58 @@
+// Liveness and readiness probes for orchestrators, over plain HTTP.
+//
+// Load balancers and container schedulers don't speak the daemon's framed protocol, so
+// the daemon can answer `GET /healthz` and `GET /readyz` on a separate address
+// (`ServeConfig::health_listen`). `/healthz` succeeds while the process serves at all;
+// `/readyz` only once the daemon should be sent new jobs, i.e. its pre-warmed server keys
+// are decoded and its queue has room. Each connection gets one response and is closed.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// Bytes of a probe request the daemon looks at; the request line is all that matters.
+const MAX_REQUEST_BYTES: usize = 1024;
+
+// Status line and body answering the request line `request`.
+pub fn probe_response(request: &str, ready: bool) -> (&'static str, &'static str) {
+    let mut parts = request.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/healthz")) => ("200 OK", "ok\n"),
+        (Some("GET"), Some("/readyz")) if ready => ("200 OK", "ready\n"),
+        (Some("GET"), Some("/readyz")) => ("503 Service Unavailable", "not ready\n"),
+        (Some("GET"), Some(_)) => ("404 Not Found", "not found\n"),
+        _ => ("405 Method Not Allowed", "method not allowed\n"),
+    }
+}
+
+// Answers probes on `listener` until the process exits, asking `ready` for `/readyz`.
+pub async fn serve_probes(listener: TcpListener, ready: impl Fn() -> bool + Send + Sync + 'static) {
+    let ready = std::sync::Arc::new(ready);
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let ready = ready.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; MAX_REQUEST_BYTES];
+            let mut len = 0;
+            // Up to the end of the request line.
+            while len < buf.len() && !buf[..len].contains(&b'\n') {
+                match stream.read(&mut buf[len..]).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => len += n,
+                }
+            }
+            let request = String::from_utf8_lossy(&buf[..len]);
+            let line = request.lines().next().unwrap_or_default();
+            let (status, body) = probe_response(line, ready());
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
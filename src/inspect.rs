@@ -0,0 +1,349 @@
+// Human-readable summary of an artifact, for debugging exchanges.
+//
+// `inspect` reads only the framing and public metadata of an artifact: its kind, format
+// version, sizes, the trajectory's frame, units and epochs, envelope headers and the
+// session metadata of a `Hello`. No key is needed and no ciphertext is decrypted. Server
+// keys are fingerprinted like `redact::EvaluationKey` does and matched against the known
+// `ParameterPreset`s. The `sat-fhe inspect <file>` command prints the result.
+
+use std::fmt;
+use std::path::Path;
+
+use tfhe::ServerKey;
+
+use crate::certificate::WorkCertificate;
+use crate::grid::GridTrajectory;
+use crate::migrate::{self, ArtifactKind};
+use crate::object_id::ObjectId;
+use crate::preset::ParameterPreset;
+use crate::protocol::{Envelope, MessageKind, SessionMetadata};
+use crate::redact::fingerprint;
+use crate::result_bundle::ResultBundle;
+use crate::reveal::SerializedAggregate;
+use crate::trajectory::SerializedTrajectory;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactInfo {
+    // What the bytes are, e.g. "encrypted trajectory".
+    pub kind: &'static str,
+    // Format version; 1 for untagged data.
+    pub version: u8,
+    // Size of the whole artifact in bytes.
+    pub size: usize,
+    // Metadata as (name, value) pairs, in display order.
+    pub fields: Vec<(&'static str, String)>,
+    // The artifact carried by an envelope, if its payload is one.
+    pub payload: Option<Box<ArtifactInfo>>,
+}
+
+impl ArtifactInfo {
+    fn new(kind: &'static str, version: u8, size: usize) -> Self {
+        Self {
+            kind,
+            version,
+            size,
+            fields: Vec::new(),
+            payload: None,
+        }
+    }
+
+    fn field(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.fields.push((name, value.to_string()));
+        self
+    }
+
+    // Value of the field called `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Summarizes the artifact stored at `path`; `.eft` files are recognized by their magic.
+pub fn inspect_file(path: impl AsRef<Path>) -> Result<ArtifactInfo, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let data = std::fs::read(path)?;
+    #[cfg(feature = "mmap")]
+    if data.starts_with(b"EFT") {
+        return inspect_eft(path, data.len());
+    }
+    inspect(&data)
+}
+
+// Summarizes a serialized artifact. Untagged (version 1) bytes are tried as a trajectory,
+// an envelope and result flags, in that order.
+pub fn inspect(data: &[u8]) -> Result<ArtifactInfo, Box<dyn std::error::Error>> {
+    match migrate::kind_of(data) {
+        Some(kind) => {
+            let meta = migrate::meta_of(kind, data)?;
+            let mut info = inspect_kind(kind, meta.format_version, data)?;
+            if let Some(version) = meta.tfhe_version {
+                info = info.field("tfhe-rs", version);
+            }
+            if let Some(parameters) = meta.parameters {
+                info = info.field("parameters", parameters);
+            }
+            Ok(info)
+        }
+        None => [
+            ArtifactKind::Trajectory,
+            ArtifactKind::Envelope,
+            ArtifactKind::Results,
+        ]
+        .into_iter()
+        .find_map(|kind| inspect_kind(kind, 1, data).ok())
+        .ok_or_else(|| "not a recognized artifact".into()),
+    }
+}
+
+fn inspect_kind(
+    kind: ArtifactKind,
+    version: u8,
+    data: &[u8],
+) -> Result<ArtifactInfo, Box<dyn std::error::Error>> {
+    Ok(match kind {
+        ArtifactKind::Trajectory => {
+            let serialized = SerializedTrajectory::parse(data)?;
+            let ciphertexts: usize = [&serialized.x, &serialized.y, &serialized.z]
+                .iter()
+                .flat_map(|axis| axis.iter().map(Vec::len))
+                .sum();
+            ArtifactInfo::new("encrypted trajectory", version, data.len())
+                .field("steps", serialized.x.len())
+                .field("frame", format!("{:?}", serialized.frame))
+                .field("units", format!("{:?}", serialized.units))
+                .field("first index", serialized.first_index)
+                .field("epochs", epoch_range(&serialized.epochs))
+                .field("ciphertext bytes", ciphertexts)
+        }
+        ArtifactKind::Results => {
+            let items: Vec<Vec<u8>> = migrate::decode(kind, data)?;
+            ArtifactInfo::new("result flags", version, data.len())
+                .field("flags", items.len())
+                .field(
+                    "ciphertext bytes",
+                    items.iter().map(Vec::len).sum::<usize>(),
+                )
+        }
+        ArtifactKind::ServerKey => {
+            let key: ServerKey = migrate::decode(kind, data)?;
+            let preset = ParameterPreset::of_server_key(&key)
+                .map_or_else(|| "unknown".to_string(), |preset| format!("{:?}", preset));
+            // The fingerprint covers the key without the tag and header.
+            let payload = migrate::payload(kind, data)?;
+            ArtifactInfo::new("server key", version, data.len())
+                .field("fingerprint", fingerprint(payload))
+                .field("preset", preset)
+        }
+        ArtifactKind::Envelope => inspect_envelope(version, data)?,
+        ArtifactKind::Aggregate => {
+            let aggregate = match migrate::decode(kind, data)? {
+                SerializedAggregate::AnyFlag(_) => "any flag",
+                SerializedAggregate::Count(_) => "count",
+            };
+            ArtifactInfo::new("aggregate result", version, data.len()).field("policy", aggregate)
+        }
+        ArtifactKind::Epochs => {
+            let items: Vec<Vec<u8>> = migrate::decode(kind, data)?;
+            ArtifactInfo::new("encrypted epochs", version, data.len()).field("epochs", items.len())
+        }
+        ArtifactKind::GridTrajectory => {
+            let grid = GridTrajectory::from_bytes(data)?;
+            ArtifactInfo::new("grid trajectory", version, data.len())
+                .field("steps", grid.len())
+                .field("frame", format!("{:?}", grid.frame))
+                .field("units", format!("{:?}", grid.units))
+                .field("first index", grid.first_index)
+                .field("cell size", grid.cell_size)
+        }
+        ArtifactKind::Fleet => {
+            let members: Vec<(ObjectId, Vec<u8>)> = migrate::decode(kind, data)?;
+            let ids: Vec<String> = members.iter().map(|(id, _)| id.to_string()).collect();
+            ArtifactInfo::new("encrypted fleet", version, data.len())
+                .field("satellites", members.len())
+                .field("ids", ids.join(", "))
+        }
+        ArtifactKind::FleetResults => {
+            let pairs: Vec<(ObjectId, usize, Vec<u8>)> = migrate::decode(kind, data)?;
+            ArtifactInfo::new("fleet results", version, data.len()).field("pairs", pairs.len())
+        }
+        ArtifactKind::WorkCertificate => {
+            let certificate = WorkCertificate::from_bytes(data)?;
+            ArtifactInfo::new("work certificate", version, data.len())
+                .field("chunks", certificate.chunks.len())
+                .field(
+                    "steps",
+                    certificate.chunks.iter().map(|c| c.steps).sum::<usize>(),
+                )
+                .field("head", hex(&certificate.head()))
+        }
+        ArtifactKind::ResultBundle => {
+            let bundle = ResultBundle::from_bytes(data)?;
+            ArtifactInfo::new("result bundle", version, data.len())
+                .field("first index", bundle.first_index)
+                .field("flags", bundle.len())
+                .field("compressed", bundle.is_compressed())
+                .field("unverified", bundle.failed().len())
+        }
+        ArtifactKind::Batch => {
+            let (first_index, items): (usize, Vec<Vec<u8>>) = migrate::decode(kind, data)?;
+            ArtifactInfo::new("result batch", version, data.len())
+                .field("first index", first_index)
+                .field("flags", items.len())
+        }
+    })
+}
+
+fn inspect_envelope(version: u8, data: &[u8]) -> Result<ArtifactInfo, Box<dyn std::error::Error>> {
+    let envelope = Envelope::from_bytes(data)?;
+    let mut info = ArtifactInfo::new("envelope", version, data.len())
+        .field("nonce", hex(&envelope.nonce))
+        .field("seq", envelope.seq)
+        .field("message", format!("{:?}", envelope.kind))
+        .field("payload bytes", envelope.payload.len());
+    match envelope.kind {
+        MessageKind::Hello => {
+            let metadata: SessionMetadata = bincode::deserialize(&envelope.payload)?;
+            let optional = |value: Option<String>| value.unwrap_or_else(|| "unset".to_string());
+            info = info
+                .field(
+                    "altitude band",
+                    optional(metadata.altitude_band.map(|band| format!("{:?}", band))),
+                )
+                .field("key fingerprint", optional(metadata.server_key_fingerprint))
+                .field(
+                    "frame",
+                    optional(metadata.frame.map(|f| format!("{:?}", f))),
+                )
+                .field(
+                    "units",
+                    optional(metadata.units.map(|u| format!("{:?}", u))),
+                )
+                .field(
+                    "reveal",
+                    optional(metadata.reveal.map(|r| format!("{:?}", r))),
+                )
+                .field(
+                    "padded length",
+                    optional(metadata.padded_len.map(|n| n.to_string())),
+                )
+                .field(
+                    "kernel",
+                    optional(metadata.kernel.map(|k| format!("{:?}", k))),
+                )
+                .field(
+                    "masked steps",
+                    optional(metadata.mask.map(|m| format!("{:?}", m.ranges()))),
+                )
+                .field(
+                    "object",
+                    optional(metadata.object_id.map(|id| id.to_string())),
+                )
+                .field(
+                    "time system",
+                    optional(metadata.time_system.map(|t| format!("{:?}", t))),
+                );
+        }
+        MessageKind::Encoding => {
+            let params: crate::negotiation::EncodingParams =
+                bincode::deserialize(&envelope.payload)?;
+            info = info.field("encoding", format!("{:?}", params));
+        }
+        MessageKind::Resolution => {
+            let request: crate::grid::ResolutionRequest = bincode::deserialize(&envelope.payload)?;
+            info = info
+                .field("resources", format!("{:?}", request.resources))
+                .field("resolution", format!("{:?}", request.resolution));
+        }
+        MessageKind::Commitment => {
+            let commitment: crate::commitment::TrajectoryCommitment =
+                bincode::deserialize(&envelope.payload)?;
+            info = info
+                .field("ciphertexts digest", hex(&commitment.ciphertexts))
+                .field("plaintext digest", hex(&commitment.plaintext));
+        }
+        MessageKind::CellFilter => {
+            let filter = crate::prescreen::CellFilter::from_bytes(&envelope.payload)?;
+            info = info
+                .field("cell grid", format!("{:?}", filter.grid))
+                .field("units", format!("{:?}", filter.units));
+        }
+        MessageKind::ArtifactRef => {
+            let pointer: crate::transport::ArtifactPointer =
+                bincode::deserialize(&envelope.payload)?;
+            info = info
+                .field("artifact", format!("{:?}", pointer.kind))
+                .field("key", pointer.artifact.key)
+                .field("artifact bytes", pointer.artifact.size)
+                .field("sha256", pointer.artifact.sha256);
+        }
+        MessageKind::Resend => {
+            let request: crate::fallback::ResendRequest = bincode::deserialize(&envelope.payload)?;
+            info = info
+                .field(
+                    "steps",
+                    format!(
+                        "{}..{}",
+                        request.first_index,
+                        request.first_index + request.steps
+                    ),
+                )
+                .field("fallback", format!("{:?}", request.fallback));
+        }
+        // The remaining kinds carry an artifact of their own; payloads that don't parse
+        // are left at their size.
+        _ => info.payload = inspect(&envelope.payload).ok().map(Box::new),
+    }
+    Ok(info)
+}
+
+#[cfg(feature = "mmap")]
+fn inspect_eft(path: &Path, size: usize) -> Result<ArtifactInfo, Box<dyn std::error::Error>> {
+    let reader = crate::eft::EftReader::open(path)?;
+    Ok(ArtifactInfo::new(".eft trajectory", reader.version(), size)
+        .field("producer", reader.producer())
+        .field("steps", reader.len())
+        .field("frame", format!("{:?}", reader.frame()))
+        .field("units", format!("{:?}", reader.units()))
+        .field("first index", reader.first_index())
+        .field("epochs", epoch_range(reader.epochs()))
+        .field("ciphertext bytes", reader.ciphertext_bytes()))
+}
+
+fn epoch_range(epochs: &[u64]) -> String {
+    match epochs {
+        [] => "none".to_string(),
+        [first, .., last] => format!("{}..={}", first, last),
+        [only] => only.to_string(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl fmt::Display for ArtifactInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, "")
+    }
+}
+
+impl ArtifactInfo {
+    fn write_indented(&self, f: &mut fmt::Formatter<'_>, indent: &str) -> fmt::Result {
+        writeln!(
+            f,
+            "{}{} (format version {}, {} bytes)",
+            indent, self.kind, self.version, self.size
+        )?;
+        for (name, value) in &self.fields {
+            writeln!(f, "{}  {}: {}", indent, name, value)?;
+        }
+        if let Some(payload) = &self.payload {
+            writeln!(f, "{}  payload:", indent)?;
+            payload.write_indented(f, &format!("{}    ", indent))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+// Conversions to and from the time and state types of Rust mission-analysis pipelines.
+//
+// nyx-space keeps epochs as `hifitime::Epoch` and states as its `Orbit`, which is anise's
+// `CartesianState`: position in km and velocity in km/s, in an anise `Frame`. A pipeline
+// that propagates its satellite with nyx can turn the states into a
+// `TimestampedTrajectory` for screening (`from_states`), and decoded trajectories and
+// `ConjunctionEvent`s back into states and epochs (`to_states`, `event_epochs`), without
+// going through CSV or OEM files (see `export`).
+//
+// Epochs in this crate are whole Unix seconds in UTC, so an `Epoch` between two seconds is
+// refused rather than rounded: two parties rounding differently would be a step apart.
+// Of the anise frames, Earth J2000 is `Frame::Eci` and ITRF93 or IAU Earth is
+// `Frame::Ecef`; states around another body have no counterpart here. Velocities aren't
+// screened, so `to_states` fills them in by finite differences of the positions, like the
+// OEM export.
+
+use anise::constants::celestial_objects::EARTH;
+use anise::constants::frames::{EARTH_ITRF93, EARTH_J2000};
+use anise::constants::orientations::{IAU_EARTH, ITRF93, J2000};
+use anise::prelude::Orbit;
+use hifitime::{Duration, Epoch};
+
+use crate::common::SatelliteData;
+use crate::core::canonical::{CANONICAL, CanonicalEncoding};
+use crate::events::ConjunctionEvent;
+use crate::export::velocities;
+use crate::frame::Frame;
+use crate::units::Units;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+// An encoded trajectory with the epoch of each step, in Unix seconds.
+#[derive(Clone)]
+pub struct TimestampedTrajectory {
+    pub epochs: Vec<u64>,
+    pub data: SatelliteData,
+}
+
+pub fn epoch_from_unix(unix_s: u64) -> Epoch {
+    Epoch::from_unix_duration(Duration::from_total_nanoseconds(
+        unix_s as i128 * NANOS_PER_SECOND,
+    ))
+}
+
+// `epoch` in whole Unix seconds, UTC.
+pub fn epoch_to_unix(epoch: Epoch) -> Result<u64, Box<dyn std::error::Error>> {
+    let nanos = epoch.to_unix_duration().total_nanoseconds();
+    if nanos < 0 || nanos % NANOS_PER_SECOND != 0 {
+        return Err(format!("epoch {} is not a whole second after 1970 UTC", epoch).into());
+    }
+    Ok((nanos / NANOS_PER_SECOND) as u64)
+}
+
+pub fn frame_of(frame: anise::prelude::Frame) -> Result<Frame, Box<dyn std::error::Error>> {
+    match (frame.ephemeris_id, frame.orientation_id) {
+        (EARTH, J2000) => Ok(Frame::Eci),
+        (EARTH, ITRF93 | IAU_EARTH) => Ok(Frame::Ecef),
+        _ => Err(format!("no screening frame matches {}", frame).into()),
+    }
+}
+
+pub fn anise_frame(frame: Frame) -> anise::prelude::Frame {
+    match frame {
+        Frame::Eci => EARTH_J2000,
+        Frame::Ecef => EARTH_ITRF93,
+    }
+}
+
+// `states`, in time order and all in one frame, encoded in `units`.
+pub fn from_states(
+    states: &[Orbit],
+    units: Units,
+) -> Result<TimestampedTrajectory, Box<dyn std::error::Error>> {
+    let frame = match states.first() {
+        Some(state) => frame_of(state.frame)?,
+        None => Frame::default(),
+    };
+    let mut epochs = Vec::with_capacity(states.len());
+    let mut positions = Vec::with_capacity(states.len());
+    for state in states {
+        if frame_of(state.frame)? != frame {
+            return Err(format!(
+                "state at {} is in {}, the first one in {:?}",
+                state.epoch, state.frame, frame
+            )
+            .into());
+        }
+        epochs.push(epoch_to_unix(state.epoch)?);
+        positions.push([
+            state.radius_km.x * 1_000.0,
+            state.radius_km.y * 1_000.0,
+            state.radius_km.z * 1_000.0,
+        ]);
+    }
+    if epochs.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err("states are not in strictly increasing time order".into());
+    }
+    let encoding = CanonicalEncoding { units, ..CANONICAL };
+    Ok(TimestampedTrajectory {
+        epochs,
+        data: encoding.encode_trajectory(&positions, frame)?,
+    })
+}
+
+// The states of a decoded `trajectory`, velocities by finite differences.
+pub fn to_states(
+    trajectory: &TimestampedTrajectory,
+) -> Result<Vec<Orbit>, Box<dyn std::error::Error>> {
+    let data = &trajectory.data;
+    if trajectory.epochs.len() != data.x.len() {
+        return Err(format!(
+            "{} epochs for a trajectory of {} steps",
+            trajectory.epochs.len(),
+            data.x.len()
+        )
+        .into());
+    }
+    let encoding = CanonicalEncoding {
+        units: data.units,
+        ..CANONICAL
+    };
+    let positions = encoding.decode_trajectory(data);
+    let frame = anise_frame(data.frame);
+    Ok(positions
+        .iter()
+        .zip(velocities(&positions, &trajectory.epochs))
+        .zip(&trajectory.epochs)
+        .map(|((position, velocity), &epoch)| {
+            let [x, y, z] = position.map(|m| m / 1_000.0);
+            let [vx, vy, vz] = velocity.map(|m_s| m_s / 1_000.0);
+            Orbit::new(x, y, z, vx, vy, vz, epoch_from_unix(epoch), frame)
+        })
+        .collect())
+}
+
+// First and last flagged epochs of `event`.
+pub fn event_epochs(event: &ConjunctionEvent) -> (Epoch, Epoch) {
+    (
+        epoch_from_unix(event.start_epoch),
+        epoch_from_unix(event.end_epoch),
+    )
+}
+
+// The event of `n_steps` flagged steps from absolute step `start_index` at `start` to `end`.
+pub fn event_from_epochs(
+    start_index: usize,
+    n_steps: usize,
+    start: Epoch,
+    end: Epoch,
+) -> Result<ConjunctionEvent, Box<dyn std::error::Error>> {
+    let (start_epoch, end_epoch) = (epoch_to_unix(start)?, epoch_to_unix(end)?);
+    if end_epoch < start_epoch || n_steps == 0 {
+        return Err(format!(
+            "event of {} steps from {} to {} is empty",
+            n_steps, start, end
+        )
+        .into());
+    }
+    Ok(ConjunctionEvent {
+        start_index,
+        start_epoch,
+        end_epoch,
+        n_steps,
+    })
+}
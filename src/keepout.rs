@@ -0,0 +1,172 @@
+// Screening against static keep-out zones instead of a second trajectory.
+//
+// Some evaluators don't have a trajectory to offer, only volumes nobody may enter: a box
+// around a crewed station's parking slot, a sphere around a geostationary asset. Each
+// step of the owner's encrypted trajectory is then flagged if it lies inside any of the
+// zones. Zones are given in meters in a frame, like positions before encoding, and
+// converted to the units of the trajectory being screened: box bounds are rounded
+// outwards and sphere radii up, so rounding never shrinks a zone. Boxes reuse the
+// bounds check of `kernel::BoxThreshold`, spheres the squared distance of `distance`;
+// both go through `ClearCoord`, so the zones keep a constant shape when the session asks
+// for it.
+
+use serde::{Deserialize, Serialize};
+use tfhe::FheBool;
+use tfhe::prelude::*;
+
+use crate::depth::OpCounter;
+use crate::distance::{DISTANCE_CAP, axis_difference_squared, threshold_cost};
+use crate::frame::{Frame, check_frames};
+use crate::kernel::split_steps;
+use crate::screening::{ScreeningConfig, ScreeningOutput};
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::{Units, bias};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum KeepOutZone {
+    // Axis-aligned box between two corners, in meters.
+    Box { min_m: [f64; 3], max_m: [f64; 3] },
+    Sphere { center_m: [f64; 3], radius_m: f64 },
+}
+
+// A zone in the encoded coordinates of a trajectory.
+#[derive(Debug, Clone, Copy)]
+enum EncodedZone {
+    Box { min: [u32; 3], max: [u32; 3] },
+    Sphere { center: [u32; 3], radius: u32 },
+}
+
+fn cell(steps: f64) -> Result<u32, Box<dyn std::error::Error>> {
+    bias(steps as i64).ok_or_else(|| "keep-out zone outside the encodable range".into())
+}
+
+impl KeepOutZone {
+    fn encode(&self, units: Units) -> Result<EncodedZone, Box<dyn std::error::Error>> {
+        let unit = units.meters();
+        match *self {
+            KeepOutZone::Box { min_m, max_m } => {
+                if (0..3).any(|axis| min_m[axis] > max_m[axis]) {
+                    return Err("keep-out box has a minimum above its maximum".into());
+                }
+                Ok(EncodedZone::Box {
+                    min: [
+                        cell((min_m[0] / unit).floor())?,
+                        cell((min_m[1] / unit).floor())?,
+                        cell((min_m[2] / unit).floor())?,
+                    ],
+                    max: [
+                        cell((max_m[0] / unit).ceil())?,
+                        cell((max_m[1] / unit).ceil())?,
+                        cell((max_m[2] / unit).ceil())?,
+                    ],
+                })
+            }
+            KeepOutZone::Sphere { center_m, radius_m } => {
+                let radius = (radius_m / unit).ceil();
+                if !(0.0..DISTANCE_CAP as f64).contains(&radius) {
+                    return Err(format!(
+                        "keep-out radius of {} m is not below the distance cap in {:?}",
+                        radius_m, units
+                    )
+                    .into());
+                }
+                Ok(EncodedZone::Sphere {
+                    center: [
+                        cell((center_m[0] / unit).round())?,
+                        cell((center_m[1] / unit).round())?,
+                        cell((center_m[2] / unit).round())?,
+                    ],
+                    radius: radius as u32,
+                })
+            }
+        }
+    }
+}
+
+impl EncodedZone {
+    fn cost(&self) -> OpCounter {
+        match self {
+            // As `BoxThreshold`.
+            EncodedZone::Box { .. } => OpCounter {
+                comparisons: 6,
+                arithmetic: 0,
+                boolean: 5,
+                depth: 4,
+                depth_warning: None,
+            },
+            EncodedZone::Sphere { .. } => threshold_cost(1),
+        }
+    }
+
+    fn contains(&self, position: [&tfhe::FheUint32; 3], config: &ScreeningConfig) -> FheBool {
+        match *self {
+            EncodedZone::Box { min, max } => {
+                let axis = |i: usize| {
+                    config.clear(min[i]).encrypted_ge(position[i])
+                        & config.clear(max[i]).encrypted_le(position[i])
+                };
+                axis(0) & axis(1) & axis(2)
+            }
+            EncodedZone::Sphere { center, radius } => {
+                let squared =
+                    |i: usize| axis_difference_squared(position[i], config.clear(center[i]));
+                (squared(0) + squared(1) + squared(2)).le(radius as u64 * radius as u64)
+            }
+        }
+    }
+}
+
+// Work per step for `zones`: every zone's check, then an OR chain over them.
+fn keep_out_cost(zones: &[EncodedZone]) -> OpCounter {
+    let mut step = OpCounter::default();
+    for zone in zones {
+        let cost = zone.cost();
+        step.comparisons += cost.comparisons;
+        step.arithmetic += cost.arithmetic;
+        step.boolean += cost.boolean;
+        step.depth = step.depth.max(cost.depth);
+    }
+    let ors = zones.len().saturating_sub(1);
+    step.boolean += ors as u64;
+    step.depth += ors as u32;
+    step
+}
+
+// Flags the steps of `encrypted` inside any of `zones`, which are given in `frame`. With
+// `config.parallel_axes` whole steps run concurrently on the workers of the
+// `FheContext`.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_keep_out(
+    encrypted: &EncryptedTrajectory,
+    zones: &[KeepOutZone],
+    frame: Frame,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    check_frames(encrypted.frame, frame)?;
+    if zones.is_empty() {
+        return Err("no keep-out zones to screen against".into());
+    }
+    let zones = zones
+        .iter()
+        .map(|zone| zone.encode(encrypted.units))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut step = keep_out_cost(&zones);
+    config.check_depth(&mut step)?;
+    let compare = |i: usize| {
+        let position = [&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]];
+        zones
+            .iter()
+            .map(|zone| zone.contains(position, config))
+            .reduce(|inside, next| inside | next)
+            .expect("at least one zone")
+    };
+    let results = if config.parallel_axes {
+        split_steps(0..encrypted.len(), &compare)
+    } else {
+        (0..encrypted.len()).map(compare).collect()
+    };
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, encrypted.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
@@ -0,0 +1,340 @@
+// Per-step comparison kernels.
+//
+// A kernel decides, for one time step, whether the owner's encrypted position and the
+// evaluator's clear one count as a conjunction. Screening loops over steps and leaves the
+// math to the kernel, so a new criterion only needs a `ComparisonKernel` impl and a
+// `KernelChoice` variant; the session declares which one it wants in its `Hello`
+// (`SessionMetadata::kernel`) and the evaluator runs it via `ScreeningConfig::kernel`.
+// Evaluators experimenting with a criterion of their own can skip both and pass a closure
+// as a `CustomKernel` to `screen_custom`.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use tfhe::prelude::*;
+use tfhe::{FheBool, FheUint32};
+
+use crate::common::SatelliteData;
+use crate::context;
+use crate::depth::OpCounter;
+use crate::distance::{DISTANCE_CAP, squared_distance, threshold_cost};
+use crate::profiling::span;
+use crate::screening::{
+    ClearCoord, ScreeningConfig, ScreeningOutput, align_plaintext, exact_match_cost,
+    exact_match_step,
+};
+use crate::trajectory::EncryptedTrajectory;
+
+pub trait ComparisonKernel {
+    // Work spent on one step.
+    fn cost(&self) -> OpCounter;
+
+    // Frame of one step in a profile (see `profiling`).
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    // Compares one encrypted position with a clear one, axes in x, y, z order. With
+    // `parallel`, independent per-axis work may run concurrently (see `context::join`).
+    // Operations on `clear` go through `ClearCoord`, so they keep a constant shape when
+    // the session asks for it.
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        parallel: bool,
+    ) -> FheBool;
+}
+
+// Same cell on every axis.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactMatch;
+
+// Within `half_width` units of each other on every axis.
+#[derive(Debug, Clone, Copy)]
+pub struct BoxThreshold {
+    pub half_width: u32,
+}
+
+// At most `threshold` units apart in straight-line distance (see `distance`).
+#[derive(Debug, Clone, Copy)]
+pub struct SquaredDistanceThreshold {
+    pub threshold: u32,
+}
+
+impl ComparisonKernel for ExactMatch {
+    fn cost(&self) -> OpCounter {
+        exact_match_cost()
+    }
+
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        parallel: bool,
+    ) -> FheBool {
+        let [x, y, z] = encrypted;
+        let [px, py, pz] = clear;
+        exact_match_step([(x, px), (y, py), (z, pz)], parallel)
+    }
+}
+
+impl BoxThreshold {
+    fn axis(&self, encrypted: &FheUint32, clear: ClearCoord) -> FheBool {
+        let above = clear
+            .map(|c| c.saturating_sub(self.half_width))
+            .encrypted_ge(encrypted);
+        let below = clear
+            .map(|c| c.saturating_add(self.half_width))
+            .encrypted_le(encrypted);
+        span("and", || above & below)
+    }
+}
+
+// Two comparisons and an AND per axis, then `(x & y) & z`.
+impl ComparisonKernel for BoxThreshold {
+    fn cost(&self) -> OpCounter {
+        OpCounter {
+            comparisons: 6,
+            arithmetic: 0,
+            boolean: 5,
+            depth: 4,
+            depth_warning: None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "box"
+    }
+
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        parallel: bool,
+    ) -> FheBool {
+        let [x, y, z] = encrypted;
+        let [px, py, pz] = clear;
+        if parallel {
+            let (in_x, (in_y, in_z)) = context::join(
+                || self.axis(x, px),
+                || context::join(|| self.axis(y, py), || self.axis(z, pz)),
+            );
+            span("and", || in_x & in_y & in_z)
+        } else {
+            let (in_x, in_y, in_z) = (self.axis(x, px), self.axis(y, py), self.axis(z, pz));
+            span("and", || in_x & in_y & in_z)
+        }
+    }
+}
+
+// Altitude buckets (x axis of a `regime::radial_profile`) at most `tolerance` apart; the
+// other axes are ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct AltitudeBand {
+    pub tolerance: u32,
+}
+
+// Two comparisons and an AND on a single axis.
+impl ComparisonKernel for AltitudeBand {
+    fn cost(&self) -> OpCounter {
+        OpCounter {
+            comparisons: 2,
+            arithmetic: 0,
+            boolean: 1,
+            depth: 2,
+            depth_warning: None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "altitude_band"
+    }
+
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        _parallel: bool,
+    ) -> FheBool {
+        BoxThreshold {
+            half_width: self.tolerance,
+        }
+        .axis(encrypted[0], clear[0])
+    }
+}
+
+impl ComparisonKernel for SquaredDistanceThreshold {
+    fn cost(&self) -> OpCounter {
+        threshold_cost(1)
+    }
+
+    fn name(&self) -> &'static str {
+        "distance"
+    }
+
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        parallel: bool,
+    ) -> FheBool {
+        let squared = squared_distance(encrypted, clear, parallel);
+        span("le", || {
+            squared.le(self.threshold as u64 * self.threshold as u64)
+        })
+    }
+}
+
+// A kernel from a closure over one step's encrypted position and the evaluator's clear
+// one, axes in x, y, z order. `cost` is the closure's work per step, used for depth
+// checks and reports like a built-in kernel's. The closure gets plain `u32`s, so keeping
+// a constant shape (see `ClearCoord`) is up to it.
+pub struct CustomKernel<F> {
+    cost: OpCounter,
+    f: F,
+}
+
+impl<F> CustomKernel<F>
+where
+    F: Fn(&[FheUint32; 3], &[u32; 3]) -> FheBool + Sync,
+{
+    pub fn new(cost: OpCounter, f: F) -> Self {
+        Self { cost, f }
+    }
+}
+
+impl<F> ComparisonKernel for CustomKernel<F>
+where
+    F: Fn(&[FheUint32; 3], &[u32; 3]) -> FheBool + Sync,
+{
+    fn cost(&self) -> OpCounter {
+        self.cost
+    }
+
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        _parallel: bool,
+    ) -> FheBool {
+        (self.f)(&encrypted.map(FheUint32::clone), &clear.map(|c| c.value))
+    }
+}
+
+// Screens every step of `encrypted` with `kernel`. The closure is opaque, so with
+// `config.parallel_axes` whole steps run concurrently on the workers of the
+// `FheContext` instead of the axes of one step.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_custom<F>(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    kernel: &CustomKernel<F>,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>>
+where
+    F: Fn(&[FheUint32; 3], &[u32; 3]) -> FheBool + Sync,
+{
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let mut step = kernel.cost();
+    config.check_depth(&mut step)?;
+    let compare = |i: usize| {
+        let j = encrypted.first_index + i;
+        span(kernel.name(), || {
+            kernel.compare(
+                [&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]],
+                [
+                    config.clear(plaintext.x[j]),
+                    config.clear(plaintext.y[j]),
+                    config.clear(plaintext.z[j]),
+                ],
+                false,
+            )
+        })
+    };
+    let results = if config.parallel_axes {
+        split_steps(0..encrypted.len(), &compare)
+    } else {
+        (0..encrypted.len()).map(compare).collect()
+    };
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, encrypted.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
+
+// `compare` over `steps`, halving the range across the context's workers.
+pub(crate) fn split_steps(
+    steps: Range<usize>,
+    compare: &(impl Fn(usize) -> FheBool + Sync),
+) -> Vec<FheBool> {
+    if steps.len() <= 1 {
+        return steps.map(compare).collect();
+    }
+    let mid = steps.start + steps.len() / 2;
+    let (mut first, second) = context::join(
+        || split_steps(steps.start..mid, compare),
+        || split_steps(mid..steps.end, compare),
+    );
+    first.extend(second);
+    first
+}
+
+// Serializable kernel selection, as declared in the session `Hello`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KernelChoice {
+    #[default]
+    ExactMatch,
+    BoxThreshold {
+        half_width: u32,
+    },
+    SquaredDistanceThreshold {
+        threshold: u32,
+    },
+    // Quick prefilter on `regime::radial_profile` trajectories.
+    AltitudeBand {
+        tolerance: u32,
+    },
+}
+
+impl KernelChoice {
+    pub fn kernel(self) -> Result<Box<dyn ComparisonKernel>, Box<dyn std::error::Error>> {
+        match self {
+            KernelChoice::ExactMatch => Ok(Box::new(ExactMatch)),
+            KernelChoice::BoxThreshold { half_width } => Ok(Box::new(BoxThreshold { half_width })),
+            KernelChoice::SquaredDistanceThreshold { threshold } => {
+                if threshold >= DISTANCE_CAP {
+                    return Err(format!(
+                        "threshold {} is not below the distance cap {}",
+                        threshold, DISTANCE_CAP
+                    )
+                    .into());
+                }
+                Ok(Box::new(SquaredDistanceThreshold { threshold }))
+            }
+            KernelChoice::AltitudeBand { tolerance } => Ok(Box::new(AltitudeBand { tolerance })),
+        }
+    }
+
+    // The kernel's verdict on two clear positions, as the encrypted comparison would give
+    // it, e.g. for screening against objects whose orbits are public anyway.
+    pub fn matches_clear(self, a: [u32; 3], b: [u32; 3]) -> bool {
+        match self {
+            KernelChoice::ExactMatch => a == b,
+            KernelChoice::BoxThreshold { half_width } => {
+                (0..3).all(|axis| a[axis].abs_diff(b[axis]) <= half_width)
+            }
+            KernelChoice::SquaredDistanceThreshold { threshold } => {
+                let squared: u64 = (0..3)
+                    .map(|axis| (a[axis].abs_diff(b[axis]).min(DISTANCE_CAP) as u64).pow(2))
+                    .sum();
+                squared <= threshold as u64 * threshold as u64
+            }
+            KernelChoice::AltitudeBand { tolerance } => a[0].abs_diff(b[0]) <= tolerance,
+        }
+    }
+}
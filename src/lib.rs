@@ -1 +1,106 @@
+pub mod alerts;
+pub mod asset;
+pub mod bench;
+pub mod billing;
+pub mod blob;
+#[cfg(feature = "bundle")]
+pub mod bundle;
+#[cfg(feature = "serve")]
+pub mod campaign;
+#[cfg(feature = "catalog")]
+pub mod catalog;
+pub mod certificate;
+#[cfg(feature = "serve")]
+pub mod client;
+pub mod commitment;
 pub mod common;
+pub mod compat;
+pub mod context;
+pub mod depth;
+pub mod distance;
+pub mod dry_run;
+#[cfg(feature = "mmap")]
+pub mod eft;
+pub mod events;
+pub mod export;
+pub mod fallback;
+pub mod fleet;
+pub mod frame;
+pub mod geometry;
+pub mod grid;
+#[cfg(feature = "serve")]
+pub mod health;
+pub mod inspect;
+#[cfg(feature = "interop")]
+pub mod interop;
+pub mod keepout;
+pub mod kernel;
+pub mod mask;
+pub mod membership;
+pub mod migrate;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+#[cfg(feature = "monitor")]
+pub mod monitor;
+pub mod multires;
+pub mod negotiation;
+#[cfg(feature = "serve")]
+pub mod net;
+pub mod object_id;
+pub mod packing;
+pub mod padding;
+pub mod party;
+pub mod pipeline;
+pub mod planner;
+pub mod policy;
+pub mod pool;
+#[cfg(feature = "catalog")]
+pub mod precheck;
+pub mod prescreen;
+pub mod preset;
+pub mod profiling;
+#[cfg(feature = "progress")]
+pub mod progress;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod protocol;
+#[cfg(feature = "serve")]
+pub mod quota;
+pub mod recurring;
+pub mod redact;
+pub mod regime;
+pub mod report;
+pub mod result_bundle;
+pub mod reveal;
+pub mod sanity;
+pub mod schedule;
+pub mod screening;
+#[cfg(feature = "serve")]
+pub mod serve;
+#[cfg(feature = "serve")]
+pub mod service;
+pub mod session;
+#[cfg(feature = "serve")]
+pub mod shard;
+pub mod share;
+pub mod shuffle;
+#[cfg(feature = "mmap")]
+pub mod splitkey;
+pub mod spotcheck;
+#[cfg(feature = "storage")]
+pub mod storage;
+pub mod stream;
+pub mod testdata;
+pub mod time_system;
+pub mod timing;
+#[cfg(feature = "serve")]
+pub mod tls;
+pub mod trajectory;
+pub mod transport;
+#[cfg(feature = "serve")]
+pub mod tuning;
+pub mod units;
+pub mod velocity;
+
+// The std-free plaintext encoding (`units`, `frame`, `negotiation` and `grid` build on it).
+pub use sat_trajectory_fhe_core as core;
@@ -0,0 +1,100 @@
+// Steps left out of a screening, e.g. known maneuver windows.
+//
+// An operator who already knows its satellite maneuvers during some steps gains nothing
+// from screening them and may not trust the predicted positions there anyway. The owner
+// lists them in the session `Hello` (`SessionMetadata::mask`), so the mask is part of the
+// transcript both sides agreed on. The evaluator runs no comparison at masked steps and
+// sends one flag per remaining step only; the owner puts them back at their step indices
+// with `StepMask::unmask`, masked steps reading as no conjunction.
+//
+// Steps are absolute indices into the owner's trajectory, like `first_index`, so the same
+// mask applies to any window of it.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use tfhe::FheBool;
+
+use crate::common::SatelliteData;
+use crate::screening::{ScreeningConfig, ScreeningOutput, screen_kernel_each};
+use crate::trajectory::EncryptedTrajectory;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StepMask {
+    ignored: Vec<Range<usize>>,
+}
+
+impl StepMask {
+    // Masks every step in `ranges`; empty ranges are dropped.
+    pub fn new(ranges: impl IntoIterator<Item = Range<usize>>) -> Self {
+        Self {
+            ignored: ranges.into_iter().filter(|r| !r.is_empty()).collect(),
+        }
+    }
+
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ignored
+    }
+
+    pub fn is_masked(&self, step: usize) -> bool {
+        self.ignored.iter().any(|r| r.contains(&step))
+    }
+
+    // Steps of `window` that are screened, in order.
+    pub fn screened(&self, window: Range<usize>) -> Vec<usize> {
+        window.filter(|&step| !self.is_masked(step)).collect()
+    }
+
+    // Per-step flags of `window` from the flags of its screened steps; masked steps are
+    // false.
+    pub fn unmask(
+        &self,
+        flags: &[bool],
+        window: Range<usize>,
+    ) -> Result<Vec<bool>, Box<dyn std::error::Error>> {
+        let screened = self.screened(window.clone());
+        if flags.len() != screened.len() {
+            return Err(format!(
+                "{} flags for {} unmasked steps",
+                flags.len(),
+                screened.len()
+            )
+            .into());
+        }
+        let mut out = vec![false; window.len()];
+        for (step, &flag) in screened.into_iter().zip(flags) {
+            out[step - window.start] = flag;
+        }
+        Ok(out)
+    }
+}
+
+// `config.kernel` on the steps of `encrypted` not in `mask`, one flag per such step.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_masked(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    mask: &StepMask,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let window = encrypted.first_index..encrypted.first_index + encrypted.len();
+    let steps = mask
+        .screened(window)
+        .into_iter()
+        .map(|step| step - encrypted.first_index);
+    let kernel = config.kernel.kernel()?;
+    let mut results: Vec<FheBool> = Vec::new();
+    let ops = screen_kernel_each(
+        encrypted,
+        plaintext,
+        kernel.as_ref(),
+        config,
+        steps,
+        |flag| {
+            results.push(flag);
+            Ok(())
+        },
+    )?;
+    Ok(ScreeningOutput { results, ops })
+}
@@ -0,0 +1,103 @@
+// Screening one owned satellite against a whole catalog per step, on the voxel grid.
+//
+// Screening against each of K catalog objects separately costs K full screenings and K
+// result sets. On the grid (see `grid`) the question per step is just whether the owner's
+// voxel is one of the voxels the catalog occupies at that step, which the evaluator knows
+// in the clear. The owner's three encrypted voxel indices are packed into one 48-bit
+// voxel ID, compared against each of the step's catalog voxel IDs with a scalar
+// equality, and the hits are ORed in a balanced tree: K comparisons and ceil(log2 K)
+// levels of ORs per step, one flag per step. Catalog voxel IDs are sorted and
+// deduplicated, so objects sharing a voxel cost nothing extra and the evaluation order
+// says nothing about the catalog's order.
+
+use std::ops::Range;
+
+use tfhe::prelude::*;
+use tfhe::{FheBool, FheUint16, FheUint64};
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::grid::{GridTrajectory, OUTSIDE, voxel};
+use crate::reveal::{reduce_tree, tree_depth};
+use crate::screening::{ScreeningOutput, align_plaintext_to};
+
+// Packs per-axis voxel indices into one ID, x in the high bits.
+pub fn voxel_id(x: u16, y: u16, z: u16) -> u64 {
+    (x as u64) << 32 | (y as u64) << 16 | z as u64
+}
+
+// Sorted, deduplicated voxel IDs the `catalog` occupies at each step of `steps`, in voxels
+// of `cell_size`. Positions off the grid can't match any owner and are left out.
+pub fn catalog_cells(
+    catalog: &[&SatelliteData],
+    steps: Range<usize>,
+    cell_size: u32,
+) -> Vec<Vec<u64>> {
+    steps
+        .map(|step| {
+            let mut cells: Vec<u64> = catalog
+                .iter()
+                .filter_map(|object| {
+                    Some(voxel_id(
+                        voxel(object.x[step], cell_size)?,
+                        voxel(object.y[step], cell_size)?,
+                        voxel(object.z[step], cell_size)?,
+                    ))
+                })
+                .collect();
+            cells.sort_unstable();
+            cells.dedup();
+            cells
+        })
+        .collect()
+}
+
+// Cost of one step against `cells` catalog voxels: packing the ID (two shifts, two ORs),
+// one comparison per voxel and the OR tree over the hits.
+pub fn membership_cost(cells: usize) -> OpCounter {
+    OpCounter {
+        comparisons: cells.max(1) as u64,
+        arithmetic: 4,
+        boolean: cells.saturating_sub(1) as u64,
+        depth: 3 + tree_depth(cells),
+        depth_warning: None,
+    }
+}
+
+fn encrypted_id(x: &FheUint16, y: &FheUint16, z: &FheUint16) -> FheUint64 {
+    let x: FheUint64 = x.clone().cast_into();
+    let y: FheUint64 = y.clone().cast_into();
+    let z: FheUint64 = z.clone().cast_into();
+    (x << 32u64) | (y << 16u64) | z
+}
+
+// One flag per step of `encrypted`, true where the owner's voxel is occupied by any
+// object of `catalog`.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_membership(
+    encrypted: &GridTrajectory,
+    catalog: &[SatelliteData],
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let steps = encrypted.first_index..encrypted.first_index + encrypted.len();
+    let aligned = catalog
+        .iter()
+        .map(|object| align_plaintext_to(steps.clone(), encrypted.frame, encrypted.units, object))
+        .collect::<Result<Vec<_>, _>>()?;
+    let aligned: Vec<&SatelliteData> = aligned.iter().map(|object| object.as_ref()).collect();
+    let cells = catalog_cells(&aligned, steps, encrypted.cell_size);
+
+    let mut ops = OpCounter::default();
+    let results: Vec<FheBool> = cells
+        .iter()
+        .enumerate()
+        .map(|(i, cells)| {
+            ops.add_steps(&membership_cost(cells.len()), 1);
+            let id = encrypted_id(&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]);
+            let hits: Vec<FheBool> = cells.iter().map(|&cell| id.eq(cell)).collect();
+            // An empty step compares against a voxel no owner is in, which is false.
+            reduce_tree(hits, |a, b| a | b).unwrap_or_else(|| encrypted.x[i].eq(OUTSIDE))
+        })
+        .collect();
+    Ok(ScreeningOutput { results, ops })
+}
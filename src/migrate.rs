@@ -0,0 +1,316 @@
+// Format versions of everything the crate persists or exchanges, and upgrades of older
+// artifacts.
+//
+// Encrypted trajectories, result flags, server keys and envelopes start with a four-byte
+// tag: `SF`, a kind byte and the format version. Bytes without the tag are version 1, the
+// layout written before versions existed. From version 3 the tag is followed by a header
+// naming the TFHE-rs version and, where known, the parameters the artifact was written
+// with (see `compat`). Readers accept every version up to the current one, so data
+// written by an older release stays usable; `upgrade` and `upgrade_file` rewrite it in
+// the current format. `.eft` files carry their version in the magic instead, see
+// `upgrade_eft`.
+//
+// When an internal structure changes, bump its kind's version and convert from the old
+// layout in `decode`.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use bincode::Options;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::compat::{self, ArtifactHeader, ArtifactMeta, Mismatch, ParameterDigest};
+
+const TAG: &[u8; 2] = b"SF";
+// Upper bound on the header behind the tag, in bytes.
+const HEADER_LIMIT: u64 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    // `EncryptedTrajectory::to_bytes`.
+    Trajectory,
+    // `screening::results_to_bytes`.
+    Results,
+    // `OwnerParty::server_key_bytes`.
+    ServerKey,
+    // `Envelope::to_bytes`.
+    Envelope,
+    // Aggregated results, `reveal::RevealedResult::to_bytes`.
+    Aggregate,
+    // `timing::EncryptedEpochs::to_bytes`.
+    Epochs,
+    // `stream::ResultBatch::to_bytes`.
+    Batch,
+    // `grid::GridTrajectory::to_bytes`.
+    GridTrajectory,
+    // `fleet::EncryptedFleet::to_bytes`.
+    Fleet,
+    // `fleet::FleetResults::to_bytes`.
+    FleetResults,
+    // `certificate::WorkCertificate::to_bytes`.
+    WorkCertificate,
+    // `result_bundle::ResultBundle::to_bytes`.
+    ResultBundle,
+}
+
+impl ArtifactKind {
+    fn code(self) -> u8 {
+        match self {
+            ArtifactKind::Trajectory => b'T',
+            ArtifactKind::Results => b'R',
+            ArtifactKind::ServerKey => b'K',
+            ArtifactKind::Envelope => b'E',
+            ArtifactKind::Aggregate => b'A',
+            ArtifactKind::Epochs => b'N',
+            ArtifactKind::Batch => b'B',
+            ArtifactKind::GridTrajectory => b'G',
+            ArtifactKind::Fleet => b'F',
+            ArtifactKind::FleetResults => b'S',
+            ArtifactKind::WorkCertificate => b'W',
+            ArtifactKind::ResultBundle => b'U',
+        }
+    }
+
+    // Version written by this build. Version 2 only added the tag and version 3 the
+    // header; the payload layouts are those of version 1, except that envelopes gained
+    // their checksum in version 4. Kinds added since start at the version current when added.
+    pub fn current_version(self) -> u8 {
+        match self {
+            ArtifactKind::Envelope => 4,
+            _ => 3,
+        }
+    }
+}
+
+// Whether `data` is tagged as an artifact of `kind`.
+pub(crate) fn has_kind(kind: ArtifactKind, data: &[u8]) -> bool {
+    data.len() >= 4 && &data[..2] == TAG && data[2] == kind.code()
+}
+
+// Kind `data` is tagged as; `None` for untagged (version 1) data or an unknown kind.
+pub fn kind_of(data: &[u8]) -> Option<ArtifactKind> {
+    if data.len() < 4 || &data[..2] != TAG {
+        return None;
+    }
+    [
+        ArtifactKind::Trajectory,
+        ArtifactKind::Results,
+        ArtifactKind::ServerKey,
+        ArtifactKind::Envelope,
+        ArtifactKind::Aggregate,
+        ArtifactKind::Epochs,
+        ArtifactKind::Batch,
+        ArtifactKind::GridTrajectory,
+        ArtifactKind::Fleet,
+        ArtifactKind::FleetResults,
+        ArtifactKind::WorkCertificate,
+        ArtifactKind::ResultBundle,
+    ]
+    .into_iter()
+    .find(|kind| kind.code() == data[2])
+}
+
+// Format version of `data`, without parsing the payload.
+pub fn version_of(kind: ArtifactKind, data: &[u8]) -> Result<u8, Box<dyn std::error::Error>> {
+    Ok(split(kind, data)?.0.format_version)
+}
+
+// What `data` records about how it was written, without parsing the payload.
+pub fn meta_of(
+    kind: ArtifactKind,
+    data: &[u8],
+) -> Result<ArtifactMeta, Box<dyn std::error::Error>> {
+    Ok(split(kind, data)?.0)
+}
+
+// Payload of `data` behind its tag and header.
+pub(crate) fn payload(
+    kind: ArtifactKind,
+    data: &[u8],
+) -> Result<&[u8], Box<dyn std::error::Error>> {
+    Ok(split(kind, data)?.1)
+}
+
+fn split(
+    kind: ArtifactKind,
+    data: &[u8],
+) -> Result<(ArtifactMeta, &[u8]), Box<dyn std::error::Error>> {
+    if data.len() < 4 || &data[..2] != TAG {
+        return Ok((ArtifactMeta::untagged(kind), data));
+    }
+    if data[2] != kind.code() {
+        return Err(format!(
+            "expected a {:?} artifact, found kind {:?}",
+            kind, data[2] as char
+        )
+        .into());
+    }
+    let version = data[3];
+    let mut meta = ArtifactMeta::untagged(kind);
+    meta.format_version = version;
+    if version > kind.current_version() {
+        return Err(Mismatch::Format {
+            kind,
+            found: version,
+            supported: kind.current_version(),
+        }
+        .into());
+    }
+    let mut rest = &data[4..];
+    if version >= 3 {
+        // Bounded, so a corrupted length can't make it allocate.
+        let header: ArtifactHeader = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(HEADER_LIMIT)
+            .allow_trailing_bytes()
+            .deserialize_from(&mut rest)
+            .map_err(|e| format!("{:?} artifact header: {}", kind, e))?;
+        meta.tfhe_version = header.tfhe_version;
+        meta.parameters = header.parameters;
+    }
+    Ok((meta, rest))
+}
+
+fn write_tag(
+    out: &mut Vec<u8>,
+    kind: ArtifactKind,
+    header: &ArtifactHeader,
+) -> Result<(), Box<dyn std::error::Error>> {
+    out.extend_from_slice(TAG);
+    out.extend_from_slice(&[kind.code(), kind.current_version()]);
+    bincode::serialize_into(&mut *out, header)?;
+    Ok(())
+}
+
+// Serializes `value` in the current format of `kind`.
+pub(crate) fn encode<T: Serialize>(
+    kind: ArtifactKind,
+    value: &T,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    encode_with(kind, value, None)
+}
+
+// Like `encode`, recording the parameters of the ciphertexts or key in `value`.
+pub(crate) fn encode_with<T: Serialize>(
+    kind: ArtifactKind,
+    value: &T,
+    parameters: Option<ParameterDigest>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let header = ArtifactHeader::current(parameters);
+    let mut out = Vec::with_capacity(
+        4 + bincode::serialized_size(&header)? as usize + bincode::serialized_size(value)? as usize,
+    );
+    write_tag(&mut out, kind, &header)?;
+    bincode::serialize_into(&mut out, value)?;
+    Ok(out)
+}
+
+// Like `encode_with`, keeping the TFHE-rs version and parameters `meta` records, e.g. for
+// ciphertexts copied as-is out of the artifact `meta` was read from.
+pub(crate) fn encode_as<T: Serialize>(
+    kind: ArtifactKind,
+    value: &T,
+    meta: &ArtifactMeta,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut out = Vec::new();
+    write_tag(
+        &mut out,
+        kind,
+        &ArtifactHeader {
+            tfhe_version: meta.tfhe_version.clone(),
+            parameters: meta.parameters,
+        },
+    )?;
+    bincode::serialize_into(&mut out, value)?;
+    Ok(out)
+}
+
+// Deserializes an artifact of `kind` written in any supported version. A payload that
+// doesn't parse is explained by `compat::check` where the header shows why.
+pub(crate) fn decode<T: DeserializeOwned>(
+    kind: ArtifactKind,
+    data: &[u8],
+) -> Result<T, Box<dyn std::error::Error>> {
+    let (meta, payload) = split(kind, data)?;
+    let payload = current_payload(kind, meta.format_version, payload);
+    bincode::deserialize(&payload).map_err(|e| compat::explain(kind, data, e.into()))
+}
+
+// `payload`, written in `version`, in the current layout of `kind`. The checksum
+// envelopes gained in version 4 is their last field, so older ones read with a zero one.
+fn current_payload(kind: ArtifactKind, version: u8, payload: &[u8]) -> Cow<'_, [u8]> {
+    if kind == ArtifactKind::Envelope && version < 4 {
+        let mut current = payload.to_vec();
+        current.extend_from_slice(&[0; 32]);
+        return Cow::Owned(current);
+    }
+    Cow::Borrowed(payload)
+}
+
+// `data` in the current format of `kind`; `None` if it already is. The header of an
+// upgraded artifact leaves the TFHE-rs version and parameters unknown: nothing recorded
+// them when it was written.
+pub fn upgrade(
+    kind: ArtifactKind,
+    data: &[u8],
+) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let (meta, payload) = split(kind, data)?;
+    if meta.format_version == kind.current_version() {
+        return Ok(None);
+    }
+    let payload = current_payload(kind, meta.format_version, payload);
+    let mut out = Vec::with_capacity(4 + payload.len());
+    write_tag(
+        &mut out,
+        kind,
+        &ArtifactHeader {
+            tfhe_version: meta.tfhe_version,
+            parameters: meta.parameters,
+        },
+    )?;
+    out.extend_from_slice(&payload);
+    Ok(Some(out))
+}
+
+// Upgrades the artifact stored at `path` in place. Returns whether it was rewritten.
+pub fn upgrade_file(
+    kind: ArtifactKind,
+    path: impl AsRef<Path>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    match upgrade(kind, &std::fs::read(path)?)? {
+        Some(upgraded) => {
+            replace(path, |tmp| Ok(std::fs::write(tmp, &upgraded)?))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// Rewrites an `.eft` file of an older version in place, copying the ciphertexts as-is.
+// Returns whether it was rewritten.
+#[cfg(feature = "mmap")]
+pub fn upgrade_eft(path: impl AsRef<Path>) -> Result<bool, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let reader = crate::eft::EftReader::open(path)?;
+    if reader.version() == crate::eft::EFT_VERSION {
+        return Ok(false);
+    }
+    replace(path, |tmp| reader.rewrite(tmp))?;
+    Ok(true)
+}
+
+// Writes the new contents next to `path` and renames them over it, so an interrupted
+// upgrade leaves the old file intact.
+fn replace(
+    path: &Path,
+    write: impl FnOnce(&Path) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".upgrading");
+    let tmp = Path::new(&tmp);
+    write(tmp)?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
@@ -0,0 +1,665 @@
+// Stand-in for the evaluator daemon, for contract-testing clients written in other
+// languages.
+//
+// `MockServer` speaks the daemon's whole wire protocol (see `service` and `serve`): the
+// same frames and requests, session envelopes with their nonce and sequence checks,
+// tagged and versioned artifacts (see `migrate`), chunked uploads, streamed result
+// batches, aggregates and work certificates. Only the ciphertexts inside the artifacts are
+// fake, so a client can be checked byte for byte without a TFHE runtime: each ciphertext
+// item is its clear value, a coordinate or count as a little-endian `u32` and a flag as a
+// single byte, 0 or 1. The server key artifact only needs its tag and header; its body is
+// ignored.
+//
+// A job is screened on the clear values as soon as both uploads are in, with the kernel,
+// mask, padding and reveal policy its `Hello` declared, so it is `Done` by the next
+// `Status`. Its certificate chains the mock items like the daemon chains ciphertexts.
+// The evaluator's trajectory never changes, so recurring jobs never run again, and
+// artifacts sent by reference are refused, as by a daemon without an object store.
+//
+// Whatever a client sends the mock is in the clear: never point a real owner at it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use tokio::net::TcpListener;
+
+use crate::certificate::WorkCertificate;
+use crate::common::SatelliteData;
+use crate::frame::check_frames;
+use crate::migrate::{self, ArtifactKind};
+use crate::net::{Connection, Listener};
+use crate::padding::{EVALUATOR_SENTINEL, check_padded_len, pad};
+use crate::preset::ParameterPreset;
+use crate::protocol::{MessageKind, ProtocolError, SessionMetadata};
+use crate::reveal::{RevealPolicy, Revealed, SerializedAggregate};
+use crate::screening::align_plaintext_to;
+use crate::serve::{Fetch, STREAM_BATCH_STEPS};
+use crate::service::{
+    JobHandle, JobId, JobStatus, JobSummary, JobToken, JobsSnapshot, Request, Response,
+    ServiceError, read_frame, write_frame,
+};
+use crate::session::Session;
+use crate::trajectory::SerializedTrajectory;
+use crate::transport::sha256_hex;
+use crate::tuning::{MAX_BATCH_STEPS, MIN_BATCH_STEPS};
+
+// Mock ciphertext of a coordinate or count.
+pub fn mock_item(value: u32) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+// Mock ciphertext of a flag.
+pub fn mock_flag(flag: bool) -> Vec<u8> {
+    vec![flag as u8]
+}
+
+fn read_item(bytes: &[u8]) -> Result<u32, Box<dyn std::error::Error>> {
+    let bytes: [u8; 4] = bytes
+        .try_into()
+        .map_err(|_| format!("mock ciphertext of {} bytes, expected 4", bytes.len()))?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_flag(bytes: &[u8]) -> Result<bool, Box<dyn std::error::Error>> {
+    match bytes {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(format!("mock flag {:02x?} is neither [0] nor [1]", bytes).into()),
+    }
+}
+
+// The `EncryptedTrajectory` artifact an owner would upload for `data`, with mock
+// ciphertexts; what a client under test should produce.
+pub fn mock_trajectory(
+    data: &SatelliteData,
+    epochs: &[u64],
+    first_index: usize,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let axis = |axis: &[u32]| axis.iter().map(|&v| mock_item(v)).collect();
+    let serialized = SerializedTrajectory {
+        frame: data.frame,
+        units: data.units,
+        first_index,
+        epochs: epochs.to_vec(),
+        x: axis(&data.x),
+        y: axis(&data.y),
+        z: axis(&data.z),
+    };
+    serialized.validate()?;
+    migrate::encode(ArtifactKind::Trajectory, &serialized)
+}
+
+// A server key artifact the mock accepts: the tag and header, no key.
+pub fn mock_server_key() -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    migrate::encode(ArtifactKind::ServerKey, &Vec::<u8>::new())
+}
+
+// The clear results in the payload of a mock `Results` envelope.
+pub fn read_results(payload: &[u8]) -> Result<Revealed, Box<dyn std::error::Error>> {
+    if migrate::has_kind(ArtifactKind::Aggregate, payload) {
+        return Ok(match migrate::decode(ArtifactKind::Aggregate, payload)? {
+            SerializedAggregate::AnyFlag(bytes) => Revealed::AnyFlag(read_flag(&bytes)?),
+            SerializedAggregate::Count(bytes) => Revealed::Count(read_item(&bytes)?),
+        });
+    }
+    let items: Vec<Vec<u8>> = migrate::decode(ArtifactKind::Results, payload)?;
+    Ok(Revealed::PerIndex(
+        items
+            .iter()
+            .map(|item| read_flag(item))
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+// The absolute index of the first step and the clear flags in the payload of a mock
+// `ResultBatch` envelope.
+pub fn read_batch(payload: &[u8]) -> Result<(usize, Vec<bool>), Box<dyn std::error::Error>> {
+    let (first_index, items): (usize, Vec<Vec<u8>>) =
+        migrate::decode(ArtifactKind::Batch, payload)?;
+    let flags = items
+        .iter()
+        .map(|item| read_flag(item))
+        .collect::<Result<_, _>>()?;
+    Ok((first_index, flags))
+}
+
+fn results_payload(revealed: &Revealed) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match revealed {
+        Revealed::PerIndex(flags) => {
+            let items: Vec<Vec<u8>> = flags.iter().map(|&flag| mock_flag(flag)).collect();
+            migrate::encode(ArtifactKind::Results, &items)
+        }
+        Revealed::AnyFlag(flag) => migrate::encode(
+            ArtifactKind::Aggregate,
+            &SerializedAggregate::AnyFlag(mock_flag(*flag)),
+        ),
+        Revealed::Count(count) => migrate::encode(
+            ArtifactKind::Aggregate,
+            &SerializedAggregate::Count(mock_item(*count)),
+        ),
+    }
+}
+
+fn batch_payload(
+    first_index: usize,
+    flags: &[bool],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let items: Vec<Vec<u8>> = flags.iter().map(|&flag| mock_flag(flag)).collect();
+    migrate::encode(ArtifactKind::Batch, &(first_index, items))
+}
+
+// What the daemon would release for `trajectory` against `counterpart`, decrypted.
+fn screen(
+    metadata: &SessionMetadata,
+    trajectory: &SerializedTrajectory,
+    counterpart: &SatelliteData,
+) -> Result<Revealed, Box<dyn std::error::Error>> {
+    let padded = match metadata.padded_len {
+        Some(len) => Cow::Owned(pad(counterpart, len, EVALUATOR_SENTINEL)?),
+        None => Cow::Borrowed(counterpart),
+    };
+    let steps = trajectory.first_index..trajectory.first_index + trajectory.x.len();
+    let plaintext = align_plaintext_to(steps.clone(), trajectory.frame, trajectory.units, &padded)?;
+    let axis = |axis: &[Vec<u8>]| {
+        axis.iter()
+            .map(|item| read_item(item))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let (x, y, z) = (
+        axis(&trajectory.x)?,
+        axis(&trajectory.y)?,
+        axis(&trajectory.z)?,
+    );
+    let kernel = metadata.kernel.unwrap_or_default();
+    let flags: Vec<bool> = metadata
+        .mask
+        .clone()
+        .unwrap_or_default()
+        .screened(steps)
+        .into_iter()
+        .map(|step| {
+            let i = step - trajectory.first_index;
+            kernel.matches_clear(
+                [x[i], y[i], z[i]],
+                [plaintext.x[step], plaintext.y[step], plaintext.z[step]],
+            )
+        })
+        .collect();
+    Ok(match metadata.reveal.unwrap_or_default() {
+        RevealPolicy::PerIndex => Revealed::PerIndex(flags),
+        RevealPolicy::AnyFlag => Revealed::AnyFlag(flags.contains(&true)),
+        RevealPolicy::Count => Revealed::Count(flags.iter().filter(|&&flag| flag).count() as u32),
+    })
+}
+
+struct MockJob {
+    // Like the daemon, the mock only answers a job's requests carrying its token.
+    token: JobToken,
+    metadata: SessionMetadata,
+    session: Session,
+    status: JobStatus,
+    batch_steps: usize,
+    has_server_key: bool,
+    trajectory: Option<SerializedTrajectory>,
+    upload: Option<MockUpload>,
+    results: Option<Vec<u8>>,
+    // Streamed batches, as the first step of each and its flags.
+    batches: Vec<(usize, Vec<bool>)>,
+    certificate: Option<WorkCertificate>,
+    recurring: bool,
+    // Result envelopes sent so far, resent as they are when a fetch is retried.
+    sealed: HashMap<Fetch, Vec<u8>>,
+}
+
+// A chunked upload: the hashes of its announced pieces, and those received so far.
+struct MockUpload {
+    hashes: Vec<String>,
+    pieces: Vec<Option<Vec<u8>>>,
+}
+
+impl MockUpload {
+    fn missing(&self) -> Vec<usize> {
+        (0..self.pieces.len())
+            .filter(|&i| self.pieces[i].is_none())
+            .collect()
+    }
+}
+
+impl MockJob {
+    fn summary(&self, job: JobId) -> JobSummary {
+        let steps = self.trajectory.as_ref().map_or(0, |t| t.x.len());
+        let done = self.status == JobStatus::Done;
+        JobSummary {
+            job,
+            status: self.status.clone(),
+            steps,
+            screened: if done { steps } else { 0 },
+            batches: self.batches.len(),
+            batch_steps: self.batch_steps,
+            elapsed: Default::default(),
+        }
+    }
+
+    // The envelope answering `fetch`, sealed around `payload` the first time, like
+    // `serve::seal_once`.
+    fn seal_once(
+        &mut self,
+        fetch: Fetch,
+        payload: impl FnOnce(&Self) -> Result<Vec<u8>, ServiceError>,
+    ) -> Result<Vec<u8>, ServiceError> {
+        if let Some(envelope) = self.sealed.get(&fetch) {
+            return Ok(envelope.clone());
+        }
+        let payload = payload(self)?;
+        let envelope = self
+            .session
+            .send(fetch.kind(), payload)
+            .map_err(|e| e.to_string())?;
+        self.sealed.insert(fetch, envelope.clone());
+        Ok(envelope)
+    }
+
+    fn awaiting_uploads(&mut self) -> Result<&mut Self, ServiceError> {
+        if self.status != JobStatus::AwaitingUploads {
+            return Err("job is no longer accepting uploads".into());
+        }
+        Ok(self)
+    }
+
+    // Streams per-step results in batches, like `serve::streams`.
+    fn streams(&self) -> bool {
+        self.metadata.reveal.unwrap_or_default() == RevealPolicy::PerIndex
+            && self.metadata.mask.is_none()
+    }
+
+    // Screens the job, whose uploads are both in.
+    fn run(&mut self, counterpart: &SatelliteData) -> Result<(), Box<dyn std::error::Error>> {
+        let trajectory = self.trajectory.as_ref().ok_or("no trajectory uploaded")?;
+        let revealed = screen(&self.metadata, trajectory, counterpart)?;
+        if let (true, Revealed::PerIndex(flags)) = (self.streams(), &revealed) {
+            let mut certificate = WorkCertificate::default();
+            for (n, chunk) in flags.chunks(self.batch_steps).enumerate() {
+                let start = n * self.batch_steps;
+                let first_index = trajectory.first_index + start;
+                let inputs: Vec<[&[u8]; 3]> = (start..start + chunk.len())
+                    .map(|i| {
+                        [
+                            trajectory.x[i].as_slice(),
+                            trajectory.y[i].as_slice(),
+                            trajectory.z[i].as_slice(),
+                        ]
+                    })
+                    .collect();
+                let items: Vec<Vec<u8>> = chunk.iter().map(|&flag| mock_flag(flag)).collect();
+                certificate.record_serialized(first_index, &inputs, &items)?;
+                self.batches.push((first_index, chunk.to_vec()));
+            }
+            self.certificate = Some(certificate);
+        }
+        self.results = Some(results_payload(&revealed)?);
+        Ok(())
+    }
+}
+
+struct MockState {
+    trajectory: SatelliteData,
+    jobs: Mutex<HashMap<JobId, MockJob>>,
+    next_job: Mutex<JobId>,
+}
+
+// Speaks the daemon's protocol over the connections `listener` accepts, screening on
+// mock ciphertexts (see the module comment).
+pub struct MockServer<L = TcpListener> {
+    listener: L,
+    state: Arc<MockState>,
+}
+
+impl MockServer {
+    // Plain TCP on `addr`, screening against `trajectory`.
+    pub async fn bind(addr: &str, trajectory: SatelliteData) -> Result<Self, ServiceError> {
+        Ok(Self::with_listener(
+            TcpListener::bind(addr).await?,
+            trajectory,
+        ))
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ServiceError> {
+        Ok(self.listener.local_addr()?)
+    }
+}
+
+impl<L: Listener> MockServer<L> {
+    pub fn with_listener(listener: L, trajectory: SatelliteData) -> Self {
+        Self {
+            listener,
+            state: Arc::new(MockState {
+                trajectory,
+                jobs: Mutex::new(HashMap::new()),
+                next_job: Mutex::new(0),
+            }),
+        }
+    }
+
+    pub async fn run(mut self) -> Result<(), ServiceError> {
+        loop {
+            let (stream, peer) = self.listener.accept().await?;
+            let state = self.state.clone();
+            tokio::spawn(async move {
+                if let Err(err) = serve_connection(state, stream, peer).await {
+                    eprintln!("mock connection from {}: {}", peer, err);
+                }
+            });
+        }
+    }
+}
+
+async fn serve_connection(
+    state: Arc<MockState>,
+    mut stream: impl Connection,
+    client: IpAddr,
+) -> Result<(), ServiceError> {
+    loop {
+        let request: Request = match read_frame(&mut stream).await {
+            Ok(request) => request,
+            // Client closed the connection.
+            Err(err)
+                if err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == std::io::ErrorKind::UnexpectedEof) =>
+            {
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        let response =
+            handle(&state, client, request).unwrap_or_else(|err| Response::Error(err.to_string()));
+        write_frame(&mut stream, &response).await?;
+    }
+}
+
+fn handle(state: &MockState, client: IpAddr, request: Request) -> Result<Response, ServiceError> {
+    let mut jobs = state.jobs.lock().unwrap();
+    match request {
+        Request::Info => Ok(Response::Info {
+            preset: ParameterPreset::default(),
+            max_jobs: 1,
+        }),
+        Request::Benchmark { .. } => Ok(Response::Benchmark { step_time: None }),
+        Request::OpenSession { hello } => {
+            let (session, metadata) = Session::accept(&hello).map_err(|e| e.to_string())?;
+            if let Some(frame) = metadata.frame {
+                check_frames(frame, state.trajectory.frame)?;
+            }
+            if let Some(kernel) = metadata.kernel {
+                kernel.kernel().map_err(|e| e.to_string())?;
+            }
+            if let Some(len) = metadata.padded_len {
+                check_padded_len(state.trajectory.x.len(), len)?;
+            }
+            let job = {
+                let mut next = state.next_job.lock().unwrap();
+                *next += 1;
+                JobHandle::new(*next)?
+            };
+            jobs.insert(
+                job.id,
+                MockJob {
+                    token: job.token,
+                    metadata,
+                    session,
+                    status: JobStatus::AwaitingUploads,
+                    batch_steps: STREAM_BATCH_STEPS,
+                    has_server_key: false,
+                    trajectory: None,
+                    upload: None,
+                    results: None,
+                    batches: Vec::new(),
+                    certificate: None,
+                    recurring: false,
+                    sealed: HashMap::new(),
+                },
+            );
+            Ok(Response::SessionOpened { job })
+        }
+        Request::TuneSession { job, batch_steps } => {
+            if !(MIN_BATCH_STEPS..=MAX_BATCH_STEPS).contains(&batch_steps) {
+                return Err(format!(
+                    "batch size must be between {} and {} steps",
+                    MIN_BATCH_STEPS, MAX_BATCH_STEPS
+                )
+                .into());
+            }
+            job_mut(&mut jobs, job)?.awaiting_uploads()?.batch_steps = batch_steps;
+            Ok(Response::Tuned)
+        }
+        Request::Upload { job, envelope } => {
+            let entry = job_mut(&mut jobs, job)?.awaiting_uploads()?;
+            accept_upload(state, entry, &envelope)
+        }
+        Request::BeginChunkedUpload { job, chunks } => {
+            let entry = job_mut(&mut jobs, job)?.awaiting_uploads()?;
+            let resumed = entry
+                .upload
+                .as_ref()
+                .is_some_and(|upload| upload.hashes == chunks);
+            if !resumed {
+                entry.upload = Some(MockUpload {
+                    pieces: vec![None; chunks.len()],
+                    hashes: chunks,
+                });
+            }
+            Ok(Response::MissingChunks(missing_chunks(entry)))
+        }
+        Request::UploadChunk { job, index, data } => {
+            let entry = job_mut(&mut jobs, job)?.awaiting_uploads()?;
+            let upload = entry
+                .upload
+                .as_mut()
+                .ok_or("no chunked upload in progress")?;
+            let hash = upload.hashes.get(index).ok_or("chunk index out of range")?;
+            let valid = sha256_hex(&data) == *hash;
+            if valid {
+                upload.pieces[index] = Some(data);
+            }
+            Ok(Response::ChunkReceived { valid })
+        }
+        Request::FinishChunkedUpload { job } => {
+            let entry = job_mut(&mut jobs, job)?.awaiting_uploads()?;
+            if entry.upload.is_none() {
+                return Err("no chunked upload in progress".into());
+            }
+            let missing = missing_chunks(entry);
+            if !missing.is_empty() {
+                return Ok(Response::MissingChunks(missing));
+            }
+            let upload = entry.upload.take().ok_or("no chunked upload in progress")?;
+            let envelope = upload
+                .pieces
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect::<Vec<u8>>();
+            accept_upload(state, entry, &envelope)
+        }
+        Request::Jobs => {
+            if !client.is_loopback() {
+                return Err("jobs are only listed to clients on the daemon's host".into());
+            }
+            let mut summaries: Vec<JobSummary> = jobs
+                .iter()
+                .map(|(&job, entry)| entry.summary(job))
+                .collect();
+            summaries.sort_by_key(|summary| summary.job);
+            Ok(Response::Jobs(JobsSnapshot {
+                jobs: summaries,
+                alerts: Vec::new(),
+            }))
+        }
+        Request::Status { job } => Ok(Response::Status(job_mut(&mut jobs, job)?.status.clone())),
+        Request::Results { job } => {
+            let entry = job_mut(&mut jobs, job)?;
+            if entry.status != JobStatus::Done {
+                return Err(format!("job isn't done: {:?}", entry.status).into());
+            }
+            let envelope = entry.seal_once(Fetch::Results, |entry| {
+                Ok(entry.results.clone().ok_or("job has no results")?)
+            })?;
+            Ok(Response::Results { envelope })
+        }
+        Request::Certificate { job } => {
+            let entry = job_mut(&mut jobs, job)?;
+            if !matches!(entry.status, JobStatus::Done | JobStatus::Cancelled { .. }) {
+                return Err(format!("job isn't done: {:?}", entry.status).into());
+            }
+            let certificate = match (&entry.certificate, &entry.status) {
+                (Some(certificate), _) => certificate.clone(),
+                // Cancelled before screening anything.
+                (None, JobStatus::Cancelled { .. }) => WorkCertificate::default(),
+                (None, _) => return Err("only streamed per-step screenings are certified".into()),
+            };
+            Ok(Response::Certificate {
+                certificate: certificate.to_bytes().map_err(|e| e.to_string())?,
+            })
+        }
+        Request::ResultBatch { job, batch } => {
+            let entry = job_mut(&mut jobs, job)?;
+            if entry.metadata.reveal.unwrap_or_default() != RevealPolicy::PerIndex {
+                return Err("only per-step results are streamed".into());
+            }
+            if entry.metadata.mask.is_some() {
+                return Err("masked screenings are not streamed".into());
+            }
+            let finished = match &entry.status {
+                JobStatus::Done | JobStatus::Cancelled { .. } => true,
+                JobStatus::Failed(reason) => return Err(format!("job failed: {}", reason).into()),
+                _ => false,
+            };
+            if batch >= entry.batches.len() {
+                return Ok(Response::ResultBatch {
+                    envelope: None,
+                    finished,
+                });
+            }
+            let envelope = entry.seal_once(Fetch::Batch(batch), |entry| {
+                let (first_index, flags) = &entry.batches[batch];
+                Ok(batch_payload(*first_index, flags).map_err(|e| e.to_string())?)
+            })?;
+            Ok(Response::ResultBatch {
+                envelope: Some(envelope),
+                finished: false,
+            })
+        }
+        Request::Cancel { job } => {
+            let entry = job_mut(&mut jobs, job)?;
+            let recurred = std::mem::take(&mut entry.recurring);
+            match entry.status {
+                JobStatus::AwaitingUploads => {
+                    entry.upload = None;
+                    entry.status = JobStatus::Cancelled { steps: 0 };
+                }
+                _ if recurred => {}
+                _ => return Err(format!("job has already ended: {:?}", entry.status).into()),
+            }
+            Ok(Response::Cancelling)
+        }
+        Request::Recur { job, every } => {
+            if every.is_zero() {
+                return Err("a recurring job needs a cadence".into());
+            }
+            let entry = job_mut(&mut jobs, job)?;
+            if matches!(
+                entry.status,
+                JobStatus::AwaitingUploads | JobStatus::Cancelled { .. }
+            ) {
+                return Err(format!("job can't recur: {:?}", entry.status).into());
+            }
+            entry.recurring = true;
+            Ok(Response::Recurring)
+        }
+        Request::Runs { job } => {
+            let entry = job_mut(&mut jobs, job)?;
+            Ok(Response::Runs {
+                completed: usize::from(entry.status == JobStatus::Done),
+                recurring: entry.recurring,
+            })
+        }
+        Request::PartialResults { job } => {
+            let entry = job_mut(&mut jobs, job)?;
+            if !matches!(entry.status, JobStatus::Done | JobStatus::Cancelled { .. }) {
+                return Err(format!("job hasn't stopped: {:?}", entry.status).into());
+            }
+            if !entry.streams() {
+                return Err("only unmasked per-step results are kept in batches".into());
+            }
+            let envelope = entry.seal_once(Fetch::PartialResults, |entry| {
+                let first_index = entry.batches.first().map_or(0, |(first, _)| *first);
+                let flags: Vec<bool> = entry
+                    .batches
+                    .iter()
+                    .flat_map(|(_, flags)| flags.iter().copied())
+                    .collect();
+                Ok(batch_payload(first_index, &flags).map_err(|e| e.to_string())?)
+            })?;
+            Ok(Response::PartialResults { envelope })
+        }
+    }
+}
+
+fn job_mut(
+    jobs: &mut HashMap<JobId, MockJob>,
+    job: JobHandle,
+) -> Result<&mut MockJob, ServiceError> {
+    match jobs.get_mut(&job.id) {
+        Some(entry) if job.opens(&entry.token) => Ok(entry),
+        _ => Err("unknown job".into()),
+    }
+}
+
+fn missing_chunks(entry: &MockJob) -> Vec<usize> {
+    entry
+        .upload
+        .as_ref()
+        .map_or_else(Vec::new, MockUpload::missing)
+}
+
+// Checks an uploaded `ServerKey` or `EncryptedTrajectory` envelope and screens the job
+// once it has both.
+fn accept_upload(
+    state: &MockState,
+    entry: &mut MockJob,
+    envelope: &[u8],
+) -> Result<Response, ServiceError> {
+    let envelope = entry.session.receive(envelope).map_err(|e| e.to_string())?;
+    match envelope.kind {
+        MessageKind::ServerKey => {
+            migrate::meta_of(ArtifactKind::ServerKey, &envelope.payload)
+                .map_err(|e| e.to_string())?;
+            entry.has_server_key = true;
+        }
+        MessageKind::EncryptedTrajectory => {
+            let trajectory =
+                SerializedTrajectory::parse(&envelope.payload).map_err(|e| e.to_string())?;
+            entry.trajectory = Some(trajectory);
+        }
+        MessageKind::ArtifactRef => {
+            return Err("the mock server has no object store configured".into());
+        }
+        found => {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::EncryptedTrajectory,
+                found,
+            }
+            .to_string()
+            .into());
+        }
+    }
+    if entry.has_server_key && entry.trajectory.is_some() {
+        entry.status = match entry.run(&state.trajectory) {
+            Ok(()) => JobStatus::Done,
+            Err(err) => JobStatus::Failed(err.to_string()),
+        };
+    }
+    Ok(Response::Uploaded)
+}
@@ -0,0 +1,141 @@
+// Terminal view of a daemon's jobs, behind `sat-fhe monitor`.
+//
+// Polls `Request::Jobs` every `interval` and draws every job with a progress bar over its
+// result batches, the combined throughput of the running jobs and the daemon's recent
+// alerts (failed and cancelled jobs, refused sessions). The daemon only lists jobs to
+// clients on its own host, so the monitor runs next to it. `q` or Esc quits.
+
+use std::time::{Duration, Instant};
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Gauge, List, ListItem, Paragraph};
+
+use crate::client::Client;
+use crate::service::{JobStatus, JobSummary, JobsSnapshot, ServiceError};
+
+// Alerts shown, newest first.
+const SHOWN_ALERTS: usize = 6;
+
+fn status_name(status: &JobStatus) -> &'static str {
+    match status {
+        JobStatus::AwaitingUploads => "awaiting uploads",
+        JobStatus::Queued => "queued",
+        JobStatus::Running => "running",
+        JobStatus::Done => "done",
+        JobStatus::Failed(_) => "failed",
+        JobStatus::Cancelled { .. } => "cancelled",
+    }
+}
+
+// One-line description of `summary` drawn over its progress bar.
+pub fn job_label(summary: &JobSummary) -> String {
+    let mut label = format!(
+        "job {}  {}  {}/{} steps  batch {}/{}",
+        summary.job,
+        status_name(&summary.status),
+        summary.screened,
+        summary.steps,
+        summary.batches,
+        summary.total_batches()
+    );
+    if let Some(rate) = summary.steps_per_sec() {
+        label.push_str(&format!("  {:.1} steps/s", rate));
+    }
+    label
+}
+
+// Steps per second of all running jobs together.
+pub fn throughput(snapshot: &JobsSnapshot) -> f64 {
+    snapshot
+        .jobs
+        .iter()
+        .filter(|summary| summary.status == JobStatus::Running)
+        .filter_map(JobSummary::steps_per_sec)
+        .sum()
+}
+
+pub fn render(frame: &mut Frame, daemon: &str, snapshot: &JobsSnapshot) {
+    let [header, jobs, alerts] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(3),
+        Constraint::Length(SHOWN_ALERTS as u16 + 2),
+    ])
+    .areas(frame.area());
+
+    let active = snapshot
+        .jobs
+        .iter()
+        .filter(|summary| !summary.status.is_finished())
+        .count();
+    frame.render_widget(
+        Paragraph::new(format!(
+            "{} jobs, {} active, {:.1} steps/s    q to quit",
+            snapshot.jobs.len(),
+            active,
+            throughput(snapshot)
+        ))
+        .block(Block::bordered().title(format!(" sat-fhe monitor: {} ", daemon))),
+        header,
+    );
+
+    let block = Block::bordered().title(" jobs ");
+    let inner = block.inner(jobs);
+    frame.render_widget(block, jobs);
+    let rows = Layout::vertical(vec![Constraint::Length(1); snapshot.jobs.len()]).split(inner);
+    for (summary, row) in snapshot.jobs.iter().zip(rows.iter()) {
+        let color = match summary.status {
+            JobStatus::Failed(_) | JobStatus::Cancelled { .. } => Color::Red,
+            JobStatus::Done => Color::Green,
+            _ => Color::Cyan,
+        };
+        frame.render_widget(
+            Gauge::default()
+                .gauge_style(Style::default().fg(color))
+                .ratio(summary.progress())
+                .label(job_label(summary)),
+            *row,
+        );
+    }
+
+    let items: Vec<ListItem> = snapshot
+        .alerts
+        .iter()
+        .rev()
+        .take(SHOWN_ALERTS)
+        .map(|alert| ListItem::new(alert.as_str()))
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" recent alerts ")),
+        alerts,
+    );
+}
+
+// Runs the monitor against the daemon at `addr` until the user quits.
+pub async fn run_monitor(addr: &str, interval: Duration) -> Result<(), ServiceError> {
+    let mut client = Client::connect(addr).await?;
+    let mut terminal = ratatui::init();
+    let result = async {
+        loop {
+            let snapshot = client.jobs().await?;
+            terminal.draw(|frame| render(frame, addr, &snapshot))?;
+            let deadline = Instant::now() + interval;
+            while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                if !event::poll(left)? {
+                    break;
+                }
+                if let Event::Key(key) = event::read()?
+                    && key.kind == KeyEventKind::Press
+                    && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    .await;
+    ratatui::restore();
+    result
+}
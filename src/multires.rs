@@ -0,0 +1,142 @@
+// Coarse-to-fine screening.
+//
+// Most screening windows contain no conjunction at all, yet exact matching pays for every
+// step. Here the owner first sends every `factor`th step only, which the evaluator
+// screens with a box comparison (`BoxThreshold`) wide enough that two objects meeting
+// anywhere between two samples are still within it at the nearest sample. Only around
+// the samples that come back positive does the owner send the full-resolution steps for
+// regular screening, so a mostly clear window costs little more than `1 / factor` of the
+// full evaluation.
+//
+// The coarse flags are decrypted by the owner, who learns which parts of the window were
+// close at the coarse resolution; the evaluator learns which steps the owner asked to
+// refine, i.e. roughly where the positives were.
+
+use std::ops::Range;
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::kernel::{BoxThreshold, ComparisonKernel};
+use crate::screening::{ScreeningConfig, ScreeningOutput, align_plaintext_to};
+use crate::trajectory::EncryptedTrajectory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoarseToFine {
+    // Every `factor`th step goes into the coarse pass.
+    pub factor: usize,
+    // Box half-width of the coarse comparison, in the owner's units.
+    pub half_width: u32,
+}
+
+impl CoarseToFine {
+    // A plan whose coarse pass can't miss a collision, given that neither trajectory
+    // moves more than `max_step_owner` resp. `max_step_evaluator` units on any axis
+    // between consecutive steps: a collision is at most `factor / 2` steps (rounded up)
+    // from a sample, over which the two objects drift apart by at most the sum.
+    pub fn covering(factor: usize, max_step_owner: u32, max_step_evaluator: u32) -> Self {
+        let factor = factor.max(1);
+        let drift = max_step_owner.saturating_add(max_step_evaluator);
+        Self {
+            factor,
+            half_width: drift.saturating_mul(factor.div_ceil(2) as u32),
+        }
+    }
+
+    // Owner: the coarse trajectory, steps 0, `factor`, 2 * `factor`, ... of `encrypted`.
+    // Its `first_index` is that of `encrypted`; step `c` of it is absolute step
+    // `first_index + c * factor`.
+    pub fn coarse(&self, encrypted: &EncryptedTrajectory) -> EncryptedTrajectory {
+        let every = |axis: &[_]| axis.iter().step_by(self.factor).cloned().collect();
+        EncryptedTrajectory {
+            x: every(&encrypted.x),
+            y: every(&encrypted.y),
+            z: every(&encrypted.z),
+            epochs: encrypted
+                .epochs
+                .iter()
+                .step_by(self.factor)
+                .copied()
+                .collect(),
+            first_index: encrypted.first_index,
+            frame: encrypted.frame,
+            units: encrypted.units,
+        }
+    }
+
+    // Owner: positions in the full trajectory of `len` steps to screen at full
+    // resolution, given the decrypted coarse flags. Each positive sample pulls in the
+    // steps up to (not including) its neighbouring samples; overlapping ranges are merged.
+    pub fn refine(&self, coarse_flags: &[bool], len: usize) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (c, &flag) in coarse_flags.iter().enumerate() {
+            if !flag {
+                continue;
+            }
+            let sample = c * self.factor;
+            let range = sample.saturating_sub(self.factor - 1)..(sample + self.factor).min(len);
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        ranges
+    }
+}
+
+// Evaluator: the coarse pass. `coarse` comes from `CoarseToFine::coarse`, and `plaintext`
+// is the evaluator's full-resolution trajectory, indexed by absolute step.
+//
+// Runs under the server key matching `coarse`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_coarse(
+    coarse: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    plan: &CoarseToFine,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let last = coarse.first_index + coarse.len().saturating_sub(1) * plan.factor;
+    let plaintext = align_plaintext_to(
+        coarse.first_index..last + usize::from(!coarse.is_empty()),
+        coarse.frame,
+        coarse.units,
+        plaintext,
+    )?;
+    let kernel = BoxThreshold {
+        half_width: plan.half_width,
+    };
+    let mut step = kernel.cost();
+    config.check_depth(&mut step)?;
+
+    let mut results = Vec::with_capacity(coarse.len());
+    for c in 0..coarse.len() {
+        let j = coarse.first_index + c * plan.factor;
+        results.push(kernel.compare(
+            [&coarse.x[c], &coarse.y[c], &coarse.z[c]],
+            [
+                config.clear(plaintext.x[j]),
+                config.clear(plaintext.y[j]),
+                config.clear(plaintext.z[j]),
+            ],
+            config.parallel_axes,
+        ));
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, coarse.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
+
+// Owner: per-step flags of the full trajectory (`len` steps from `first_index`) out of
+// the decrypted fine passes, each given with the `first_index` of the steps it screened.
+// Steps outside every refined range are reported clear.
+pub fn assemble(
+    len: usize,
+    first_index: usize,
+    refined: impl IntoIterator<Item = (usize, Vec<bool>)>,
+) -> Vec<bool> {
+    let mut flags = vec![false; len];
+    for (start, part) in refined {
+        let from = start - first_index;
+        flags[from..from + part.len()].copy_from_slice(&part);
+    }
+    flags
+}
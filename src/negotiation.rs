@@ -0,0 +1,47 @@
+// Negotiation of how both parties quantize their trajectories.
+//
+// Exact matching only works if both sides turned positions and times into integers the
+// same way: the same units, the same grid cell, the same time step over the same window.
+// Each party states the parameters it encoded with in an `Encoding` message right after
+// the `Hello`; `Session::accept_encoding` compares the peer's with its own and refuses to
+// go on at the first difference, instead of screening incompatible encodings that would
+// silently never match. The parameters and the quantization they describe are
+// `core::encoding`.
+
+pub use crate::core::encoding::EncodingParams;
+use crate::protocol::ProtocolError;
+
+// The parameters both sides use, if `ours` and `theirs` agree.
+pub fn negotiate(
+    ours: &EncodingParams,
+    theirs: &EncodingParams,
+) -> Result<EncodingParams, ProtocolError> {
+    let mismatch = |parameter, ours: &dyn std::fmt::Debug, theirs: &dyn std::fmt::Debug| {
+        ProtocolError::EncodingMismatch {
+            parameter,
+            ours: format!("{:?}", ours),
+            theirs: format!("{:?}", theirs),
+        }
+    };
+    if ours.units != theirs.units {
+        return Err(mismatch("units", &ours.units, &theirs.units));
+    }
+    if ours.cell_size != theirs.cell_size {
+        return Err(mismatch("cell size", &ours.cell_size, &theirs.cell_size));
+    }
+    if ours.time_step_s != theirs.time_step_s {
+        return Err(mismatch(
+            "time step",
+            &ours.time_step_s,
+            &theirs.time_step_s,
+        ));
+    }
+    if (ours.window_start, ours.window_end) != (theirs.window_start, theirs.window_end) {
+        return Err(mismatch(
+            "window",
+            &(ours.window_start..ours.window_end),
+            &(theirs.window_start..theirs.window_end),
+        ));
+    }
+    Ok(*ours)
+}
@@ -0,0 +1,104 @@
+// Byte-stream transports between daemon clients and the daemon.
+//
+// `client::Client` and `serve::Daemon` only need a reliable, ordered byte stream to
+// exchange `service` frames over; how it is established is up to a `Transport` on the
+// client side and a `Listener` on the daemon side. Plain TCP is the default. `in_process`
+// connects the two ends over in-memory pipes, so the protocol logic can be exercised
+// without sockets; other transports, e.g. QUIC streams, plug in the same way.
+
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+// Buffer of each direction of an in-process connection.
+const IN_PROCESS_BUFFER: usize = 1 << 20;
+
+// An established connection.
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Connection for T {}
+
+// Opens connections to one daemon; called again to reconnect after a dropped connection.
+pub trait Transport: Send + Sync + 'static {
+    type Conn: Connection;
+
+    fn connect(&self) -> impl Future<Output = io::Result<Self::Conn>> + Send;
+}
+
+// Accepts connections for the daemon, with the address quotas are charged to.
+pub trait Listener: Send + 'static {
+    type Conn: Connection;
+
+    fn accept(&mut self) -> impl Future<Output = io::Result<(Self::Conn, IpAddr)>> + Send;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpTransport {
+    pub addr: SocketAddr,
+}
+
+impl Transport for TcpTransport {
+    type Conn = TcpStream;
+
+    async fn connect(&self) -> io::Result<TcpStream> {
+        TcpStream::connect(self.addr).await
+    }
+}
+
+impl Listener for TcpListener {
+    type Conn = TcpStream;
+
+    async fn accept(&mut self) -> io::Result<(TcpStream, IpAddr)> {
+        let (stream, peer) = TcpListener::accept(self).await?;
+        Ok((stream, peer.ip()))
+    }
+}
+
+// Connects to the `InProcessListener` it was created with.
+#[derive(Debug, Clone)]
+pub struct InProcessTransport {
+    incoming: mpsc::UnboundedSender<DuplexStream>,
+}
+
+#[derive(Debug)]
+pub struct InProcessListener {
+    incoming: mpsc::UnboundedReceiver<DuplexStream>,
+}
+
+// A connected transport and listener pair. Every connection appears to come from
+// 127.0.0.1.
+pub fn in_process() -> (InProcessTransport, InProcessListener) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (
+        InProcessTransport { incoming: sender },
+        InProcessListener { incoming: receiver },
+    )
+}
+
+impl Transport for InProcessTransport {
+    type Conn = DuplexStream;
+
+    async fn connect(&self) -> io::Result<DuplexStream> {
+        let (ours, theirs) = tokio::io::duplex(IN_PROCESS_BUFFER);
+        self.incoming
+            .send(theirs)
+            .map_err(|_| io::Error::new(io::ErrorKind::ConnectionRefused, "listener is gone"))?;
+        Ok(ours)
+    }
+}
+
+impl Listener for InProcessListener {
+    type Conn = DuplexStream;
+
+    async fn accept(&mut self) -> io::Result<(DuplexStream, IpAddr)> {
+        let stream =
+            self.incoming.recv().await.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "every transport is gone")
+            })?;
+        Ok((stream, IpAddr::V4(Ipv4Addr::LOCALHOST)))
+    }
+}
@@ -0,0 +1,133 @@
+// Identifiers of the objects being screened.
+//
+// With fleets and catalogs in one screening, a flag is only useful if it says which
+// objects it is about. An `ObjectId` names a satellite by NORAD catalog number or by an
+// operator-chosen label; the owner puts it in the `Hello` (`SessionMetadata::object_id`)
+// or keys its `fleet::Fleet` by it, and results and reports carry it back.
+//
+// An owner that doesn't want the evaluator to learn which of its assets is screened sends
+// blinded IDs instead: `Blinder` replaces each ID with a keyed SHA-256 of it under a
+// secret only the owner holds, so the evaluator sees stable but meaningless identifiers
+// and the owner maps them back with `Blinder::resolve`. A plain hash wouldn't do, as the
+// few tens of thousands of NORAD numbers are quickly tried. A fresh `Blinder` per session
+// also keeps sessions from being linked.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ObjectId {
+    // NORAD catalog number.
+    Norad(u32),
+    // Operator-chosen name.
+    Label(String),
+    // Output of `Blinder::blind`.
+    Blinded([u8; 32]),
+}
+
+impl ObjectId {
+    pub fn is_blinded(&self) -> bool {
+        matches!(self, ObjectId::Blinded(_))
+    }
+}
+
+impl From<&str> for ObjectId {
+    fn from(label: &str) -> Self {
+        ObjectId::Label(label.to_string())
+    }
+}
+
+impl From<String> for ObjectId {
+    fn from(label: String) -> Self {
+        ObjectId::Label(label)
+    }
+}
+
+// `norad:25544`, `label:<name>` or `blinded:<hex>`; parsed back by `FromStr`.
+impl fmt::Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectId::Norad(number) => write!(f, "norad:{}", number),
+            ObjectId::Label(label) => write!(f, "label:{}", label),
+            ObjectId::Blinded(bytes) => {
+                write!(f, "blinded:")?;
+                bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+        }
+    }
+}
+
+impl FromStr for ObjectId {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("object ID {:?} lacks a kind prefix", s))?;
+        match kind {
+            "norad" => Ok(ObjectId::Norad(value.parse()?)),
+            "label" => Ok(ObjectId::Label(value.to_string())),
+            "blinded" => {
+                if value.len() != 64 || !value.is_ascii() {
+                    return Err(format!("blinded object ID {:?} isn't 32 hex bytes", value).into());
+                }
+                let mut bytes = [0u8; 32];
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    *byte = u8::from_str_radix(&value[2 * i..2 * i + 2], 16)?;
+                }
+                Ok(ObjectId::Blinded(bytes))
+            }
+            _ => Err(format!("unknown object ID kind {:?}", kind).into()),
+        }
+    }
+}
+
+// The owner's secret for blinding IDs. Debug output doesn't show it.
+#[derive(Clone)]
+pub struct Blinder {
+    secret: [u8; 32],
+}
+
+impl fmt::Debug for Blinder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Blinder(..)")
+    }
+}
+
+impl Blinder {
+    pub fn generate() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut secret = [0u8; 32];
+        getrandom::getrandom(&mut secret)?;
+        Ok(Self { secret })
+    }
+
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        Self { secret }
+    }
+
+    // Already blinded IDs are returned as they are.
+    pub fn blind(&self, id: &ObjectId) -> ObjectId {
+        if id.is_blinded() {
+            return id.clone();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(b"sat-fhe object id");
+        hasher.update(self.secret);
+        hasher.update(id.to_string().as_bytes());
+        ObjectId::Blinded(hasher.finalize().into())
+    }
+
+    // Which of `candidates` (the owner's own IDs) `blinded` stands for.
+    pub fn resolve<'a>(
+        &self,
+        blinded: &ObjectId,
+        candidates: impl IntoIterator<Item = &'a ObjectId>,
+    ) -> Option<&'a ObjectId> {
+        candidates
+            .into_iter()
+            .find(|candidate| &self.blind(candidate) == blinded)
+    }
+}
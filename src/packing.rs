@@ -0,0 +1,138 @@
+// Packing of several catalog objects' grid cells into one 64-bit word per time step.
+//
+// Each object's position at a time step is reduced to a 16-bit grid-cell id, and four
+// objects share one `u64` (lane `k` holds object `4 * word + k`). The owner encrypts its
+// own cell replicated into all four lanes, so a single scalar XOR against a packed word
+// compares it with four objects at once; a lane is a hit iff it is zero afterwards.
+//
+// The XOR is the shared part. Extracting a lane is a block shift plus a truncating cast,
+// both of which are block moves without bootstrapping, leaving one 16-bit zero test per
+// object instead of three 32-bit equalities and two ANDs. The price is grid resolution:
+// positions must be quantized to 65536 cells.
+
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheBool, FheUint16, FheUint64};
+
+pub const LANES: usize = 4;
+const LANE_BITS: u32 = 16;
+
+// Packs up to four cell ids into one word, lane 0 in the low bits. Missing lanes are
+// filled with `fill`.
+pub fn pack(cells: &[u16], fill: u16) -> Result<u64, Box<dyn std::error::Error>> {
+    if cells.len() > LANES {
+        return Err(format!("at most {} cells per word, got {}", LANES, cells.len()).into());
+    }
+    Ok((0..LANES).fold(0u64, |word, k| {
+        let cell = cells.get(k).copied().unwrap_or(fill);
+        word | (u64::from(cell) << (LANE_BITS * k as u32))
+    }))
+}
+
+pub fn unpack(word: u64) -> [u16; LANES] {
+    let mut cells = [0u16; LANES];
+    for (k, cell) in cells.iter_mut().enumerate() {
+        *cell = (word >> (LANE_BITS * k as u32)) as u16;
+    }
+    cells
+}
+
+// `cell` copied into every lane.
+pub fn replicate(cell: u16) -> u64 {
+    u64::from(cell) * 0x0001_0001_0001_0001
+}
+
+// Evaluator-side catalog: for each time step, the packed cell ids of all objects.
+pub struct PackedCatalog {
+    objects: usize,
+    // words[step][word]
+    words: Vec<Vec<u64>>,
+}
+
+impl PackedCatalog {
+    // `cells[object][step]` is the grid cell of `object` at `step`; all objects must
+    // cover the same steps. Unused lanes of the last word are filled with `padding`.
+    pub fn new(cells: &[Vec<u16>], padding: u16) -> Result<Self, Box<dyn std::error::Error>> {
+        let steps = cells.first().map(|c| c.len()).unwrap_or(0);
+        if cells.iter().any(|c| c.len() != steps) {
+            return Err("catalog objects cover different numbers of time steps".into());
+        }
+        let words = (0..steps)
+            .map(|step| {
+                cells
+                    .chunks(LANES)
+                    .map(|chunk| {
+                        let lane_cells: Vec<u16> = chunk.iter().map(|c| c[step]).collect();
+                        pack(&lane_cells, padding)
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            objects: cells.len(),
+            words,
+        })
+    }
+
+    pub fn objects(&self) -> usize {
+        self.objects
+    }
+
+    pub fn steps(&self) -> usize {
+        self.words.len()
+    }
+
+    // Cell ids of all objects at `step`, in object order.
+    pub fn unpack_step(&self, step: usize) -> Vec<u16> {
+        self.words[step]
+            .iter()
+            .flat_map(|&w| unpack(w))
+            .take(self.objects)
+            .collect()
+    }
+
+    // Screens the owner's encrypted, lane-replicated cells (one per step) against every
+    // catalog object. Returns `flags[step][object]`.
+    //
+    // Runs under the server key matching `owner_cells`, e.g. inside `FheContext::evaluate_with`.
+    pub fn screen(
+        &self,
+        owner_cells: &[FheUint64],
+    ) -> Result<Vec<Vec<FheBool>>, Box<dyn std::error::Error>> {
+        if owner_cells.len() != self.steps() {
+            return Err(format!(
+                "trajectory length mismatch: owner has {} steps, catalog has {}",
+                owner_cells.len(),
+                self.steps()
+            )
+            .into());
+        }
+        let mut flags = Vec::with_capacity(self.steps());
+        for (step, owner) in owner_cells.iter().enumerate() {
+            let mut step_flags = Vec::with_capacity(self.objects);
+            for &word in &self.words[step] {
+                let diff = owner ^ word;
+                for k in 0..LANES {
+                    if step_flags.len() == self.objects {
+                        break;
+                    }
+                    let lane: FheUint16 = (&diff >> (LANE_BITS * k as u32) as u64).cast_into();
+                    step_flags.push(lane.eq(0u16));
+                }
+            }
+            flags.push(step_flags);
+        }
+        Ok(flags)
+    }
+}
+
+// Owner side: encrypts each step's cell replicated into all lanes.
+pub fn encrypt_replicated(
+    cells: &[u16],
+    client_key: &ClientKey,
+) -> Result<Vec<FheUint64>, Box<dyn std::error::Error>> {
+    let mut out = Vec::with_capacity(cells.len());
+    for &cell in cells {
+        out.push(FheUint64::try_encrypt(replicate(cell), client_key)?);
+    }
+    Ok(out)
+}
@@ -0,0 +1,76 @@
+// Decoy steps hiding the length of the owner's trajectory.
+//
+// The number of ciphertexts exchanged gives away how long (or how finely sampled) the
+// owner's trajectory is. Both parties can instead agree on a fixed number of steps in the
+// session `Hello` (`SessionMetadata::padded_len`); the owner appends encrypted decoy steps
+// up to it and the evaluator extends its plaintext to match. Decoys sit at
+// `OWNER_SENTINEL` on every axis and the evaluator's extension at `EVALUATOR_SENTINEL`:
+// both are over two million kilometers from the origin and differ from each other, so a
+// decoy never screens as a collision and aggregated results (see `reveal`) stay exact.
+// The owner, who knows its real length, drops decoys from per-step results with `strip`.
+
+use crate::common::SatelliteData;
+
+// Coordinate of every axis of the owner's decoy steps.
+pub const OWNER_SENTINEL: u32 = u32::MAX;
+
+// Coordinate of every axis of the steps the evaluator adds to reach the padded length.
+pub const EVALUATOR_SENTINEL: u32 = 0;
+
+// `data` extended to `len` steps at `sentinel`. A trajectory that already has `len` steps
+// or more is returned unchanged. `len` can come from the peer, so running out of memory
+// for it is an error rather than an abort.
+pub fn pad(
+    data: &SatelliteData,
+    len: usize,
+    sentinel: u32,
+) -> Result<SatelliteData, Box<dyn std::error::Error>> {
+    let pad_axis = |axis: &[u32]| -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let mut out = Vec::new();
+        out.try_reserve_exact(len.max(axis.len()))?;
+        out.extend_from_slice(axis);
+        out.resize(len.max(axis.len()), sentinel);
+        Ok(out)
+    };
+    Ok(SatelliteData {
+        x: pad_axis(&data.x)?,
+        y: pad_axis(&data.y)?,
+        z: pad_axis(&data.z)?,
+        frame: data.frame,
+        units: data.units,
+    })
+}
+
+// Checks that a trajectory of `steps` steps fits in `padded_len`; decoys can only be
+// added, never real steps dropped.
+pub fn check_padded_len(steps: usize, padded_len: usize) -> Result<(), String> {
+    if steps > padded_len {
+        return Err(format!(
+            "trajectory has {} steps, more than the padded length {}",
+            steps, padded_len
+        ));
+    }
+    Ok(())
+}
+
+// `epochs` extended to `len` steps, continuing at the interval of the last two, so the
+// public time metadata of decoys looks like that of real steps.
+pub fn pad_epochs(epochs: &[u64], len: usize) -> Vec<u64> {
+    let mut out = epochs.to_vec();
+    let interval = match epochs {
+        [.., a, b] => b - a,
+        _ => 1,
+    };
+    let mut next = epochs.last().map_or(0, |&t| t + interval);
+    while out.len() < len {
+        out.push(next);
+        next += interval;
+    }
+    out
+}
+
+// The flags of real steps only: those whose absolute index (counted from `first_index`,
+// see `EncryptedTrajectory::window`) is below `real_len`.
+pub fn strip(flags: &[bool], first_index: usize, real_len: usize) -> &[bool] {
+    &flags[..real_len.saturating_sub(first_index).min(flags.len())]
+}
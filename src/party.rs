@@ -0,0 +1,676 @@
+// Role-typed entry points for the two-party screening protocol.
+//
+// The key owner and the evaluator are distinct types, so protocol steps can only be
+// called by the party allowed to perform them: only `OwnerParty` encrypts its trajectory
+// and decrypts results, only `EvaluatorParty` evaluates. Both come out of a
+// `PartyBuilder`, which only offers `build` once its role has been fixed in its type.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use tfhe::{Config, ConfigBuilder, FheBool, FheUint32, ServerKey};
+
+use crate::alerts::{AlertReport, AlertSink};
+use crate::common::SatelliteData;
+use crate::compat::ParameterDigest;
+use crate::context::FheContext;
+use crate::depth::OpCounter;
+use crate::events::{ConjunctionEvent, cluster};
+use crate::fleet::{EncryptedFleet, Fleet, FleetKey, FleetOutput, FleetResults, screen_fleet};
+use crate::frame::Frame;
+use crate::grid::{EncryptedInput, GridTrajectory, Resolution, screen_grid};
+use crate::keepout::{KeepOutZone, screen_keep_out};
+use crate::kernel::{CustomKernel, screen_custom};
+use crate::mask::{StepMask, screen_masked};
+use crate::migrate::{self, ArtifactKind};
+use crate::multires::{CoarseToFine, screen_coarse};
+use crate::padding::{EVALUATOR_SENTINEL, OWNER_SENTINEL, check_padded_len, pad, strip};
+use crate::planner::{Operand, screen_planned};
+use crate::prescreen::{CellFilter, CellGrid, screen_prescreened};
+use crate::preset::ParameterPreset;
+use crate::redact::{EvaluationKey, PrivateTrajectory};
+use crate::reveal::{
+    Aggregation, RevealPolicy, Revealed, RevealedOutput, RevealedResult, reveal_cost_with,
+    reveal_with,
+};
+use crate::sanity::{SanityLimits, SanityReport, check_trajectory};
+use crate::schedule::StepOrder;
+use crate::screening::{ScreeningConfig, ScreeningOutput};
+use crate::shuffle::{Shuffle, screen_objects_shuffled};
+use crate::spotcheck::SpotChecks;
+use crate::stream::{ResultBatch, screen_streaming};
+use crate::timing::{EncryptedEpochs, screen_time_matched};
+use crate::trajectory::{EncryptedTrajectory, TrajectoryDigest};
+
+// Builder role markers.
+pub struct NoRole;
+pub struct OwnerRole {
+    alerts: Vec<Box<dyn AlertSink>>,
+    spot_checks: usize,
+    #[cfg(feature = "deterministic-tests")]
+    seed: Option<u128>,
+}
+pub struct EvaluatorRole {
+    server_key: ServerKeySource,
+}
+
+enum ServerKeySource {
+    Serialized(Vec<u8>),
+    Loaded(EvaluationKey),
+}
+
+pub struct PartyBuilder<R> {
+    trajectory: SatelliteData,
+    config: Config,
+    screening: ScreeningConfig,
+    padded_len: Option<usize>,
+    role: R,
+}
+
+impl PartyBuilder<NoRole> {
+    pub fn new(trajectory: SatelliteData) -> Self {
+        Self {
+            trajectory,
+            config: ConfigBuilder::default().build(),
+            screening: ScreeningConfig::default(),
+            padded_len: None,
+            role: NoRole,
+        }
+    }
+
+    // TFHE configuration used for key generation (owner only; the evaluator inherits
+    // parameters from the server key it receives).
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    // Screening options applied by the evaluator.
+    pub fn screening(mut self, screening: ScreeningConfig) -> Self {
+        self.screening = screening;
+        self
+    }
+
+    // Number of steps the owner's trajectory is padded to with decoys, as agreed in the
+    // session `Hello` (see `padding`). Both parties must use the same value.
+    pub fn pad_to(mut self, steps: usize) -> Self {
+        self.padded_len = Some(steps);
+        self
+    }
+
+    pub fn owner(self) -> PartyBuilder<OwnerRole> {
+        PartyBuilder {
+            trajectory: self.trajectory,
+            config: self.config,
+            screening: self.screening,
+            padded_len: self.padded_len,
+            role: OwnerRole {
+                alerts: Vec::new(),
+                spot_checks: 0,
+                #[cfg(feature = "deterministic-tests")]
+                seed: None,
+            },
+        }
+    }
+
+    // `server_key` is the serialized server key received from the owner.
+    pub fn evaluator(self, server_key: Vec<u8>) -> PartyBuilder<EvaluatorRole> {
+        PartyBuilder {
+            trajectory: self.trajectory,
+            config: self.config,
+            screening: self.screening,
+            padded_len: self.padded_len,
+            role: EvaluatorRole {
+                server_key: ServerKeySource::Serialized(server_key),
+            },
+        }
+    }
+
+    // Like `evaluator`, with the server key already loaded, e.g. by `SplitKey::load`.
+    pub fn evaluator_with_key(self, server_key: EvaluationKey) -> PartyBuilder<EvaluatorRole> {
+        PartyBuilder {
+            trajectory: self.trajectory,
+            config: self.config,
+            screening: self.screening,
+            padded_len: self.padded_len,
+            role: EvaluatorRole {
+                server_key: ServerKeySource::Loaded(server_key),
+            },
+        }
+    }
+}
+
+impl PartyBuilder<OwnerRole> {
+    // Notified whenever `decrypt_events` finds a conjunction; may be given several times.
+    pub fn alert(mut self, sink: impl AlertSink + 'static) -> Self {
+        self.role.alerts.push(Box::new(sink));
+        self
+    }
+
+    // Plants `count` known collisions among the decoy steps, which `decrypt_verified`
+    // then checks (see `spotcheck`). Needs `pad_to` with at least `count` decoy steps.
+    pub fn spot_checks(mut self, count: usize) -> Self {
+        self.role.spot_checks = count;
+        self
+    }
+
+    // Derives the key pair from `seed` instead of OS entropy, so tests get the same keys
+    // on every run.
+    #[cfg(feature = "deterministic-tests")]
+    pub fn seed(mut self, seed: u128) -> Self {
+        self.role.seed = Some(seed);
+        self
+    }
+
+    // Generates a fresh key pair for this party.
+    pub fn build(self) -> Result<OwnerParty, Box<dyn std::error::Error>> {
+        if let Some(len) = self.padded_len {
+            check_padded_len(self.trajectory.x.len(), len)?;
+        }
+        let spot_checks = match (self.role.spot_checks, self.padded_len) {
+            (0, _) => SpotChecks::default(),
+            (count, Some(len)) => SpotChecks::choose(self.trajectory.x.len(), len, count)?,
+            (_, None) => return Err("spot checks need a padded length".into()),
+        };
+        #[cfg(feature = "deterministic-tests")]
+        let context = match self.role.seed {
+            Some(seed) => FheContext::generate_seeded(self.config, seed)?,
+            None => FheContext::generate(self.config)?,
+        };
+        #[cfg(not(feature = "deterministic-tests"))]
+        let context = FheContext::generate(self.config)?;
+        Ok(OwnerParty {
+            context,
+            trajectory: PrivateTrajectory::new(self.trajectory),
+            padded_len: self.padded_len,
+            spot_checks,
+            alerts: self.role.alerts,
+        })
+    }
+}
+
+// Aggregates `output` under `policy` with `aggregation`. The aggregation runs after the
+// last step, so its depth adds to the steps'.
+fn revealing(
+    policy: RevealPolicy,
+    output: ScreeningOutput,
+    aggregation: Aggregation,
+) -> RevealedOutput {
+    let cost = reveal_cost_with(policy, output.results.len(), aggregation);
+    let mut ops = output.ops;
+    ops.add_steps(&cost, 1);
+    ops.depth = output.ops.depth + cost.depth;
+    RevealedOutput {
+        result: reveal_with(policy, output.results, aggregation),
+        ops,
+    }
+}
+
+impl PartyBuilder<EvaluatorRole> {
+    pub fn build(self) -> Result<EvaluatorParty, Box<dyn std::error::Error>> {
+        let context = match self.role.server_key {
+            ServerKeySource::Serialized(bytes) => {
+                let server_key: ServerKey = migrate::decode(ArtifactKind::ServerKey, &bytes)?;
+                FheContext::from_server_key(server_key)?
+            }
+            ServerKeySource::Loaded(server_key) => FheContext::from_evaluation_key(server_key),
+        };
+        let trajectory = match self.padded_len {
+            Some(len) => pad(&self.trajectory, len, EVALUATOR_SENTINEL)?,
+            None => self.trajectory,
+        };
+        Ok(EvaluatorParty {
+            context,
+            trajectory: PrivateTrajectory::new(trajectory),
+            screening: self.screening,
+        })
+    }
+}
+
+// Holds the secret key; encrypts its own trajectory and decrypts the results sent back.
+#[derive(Debug)]
+pub struct OwnerParty {
+    context: FheContext,
+    trajectory: PrivateTrajectory,
+    padded_len: Option<usize>,
+    spot_checks: SpotChecks,
+    alerts: Vec<Box<dyn AlertSink>>,
+}
+
+impl OwnerParty {
+    // Padded with decoy steps if a padded length was set, spot checks planted among them.
+    pub fn encrypt_trajectory(&self) -> Result<EncryptedTrajectory, Box<dyn std::error::Error>> {
+        match self.padded_len {
+            Some(len) => {
+                let mut padded = pad(&self.trajectory, len, OWNER_SENTINEL)?;
+                self.spot_checks.plant(&mut padded);
+                self.context.encrypt(&padded)
+            }
+            None => self.context.encrypt(&self.trajectory),
+        }
+    }
+
+    // An encrypted trajectory of this party's back in the clear, e.g. to check with
+    // `export` that encoding and encryption preserved it.
+    pub fn decrypt_trajectory(
+        &self,
+        encrypted: &EncryptedTrajectory,
+    ) -> Result<SatelliteData, Box<dyn std::error::Error>> {
+        Ok(encrypted.decrypt(self.context.client_key()?))
+    }
+
+    // The trajectory with `epochs` (seconds) attached, encrypted only if it passes the
+    // checks of `sanity`; warnings come back with it. A padded trajectory has no epochs
+    // for its decoy steps, so a padded owner refuses.
+    pub fn encrypt_validated(
+        &self,
+        epochs: &[u64],
+        limits: &SanityLimits,
+    ) -> Result<(EncryptedTrajectory, SanityReport), Box<dyn std::error::Error>> {
+        if self.padded_len.is_some() {
+            return Err("epochs can't be attached to a padded trajectory".into());
+        }
+        let report = check_trajectory(&self.trajectory, epochs, limits).into_result()?;
+        let encrypted = self.context.encrypt(&self.trajectory)?;
+        Ok((encrypted.with_epochs(epochs.to_vec())?, report))
+    }
+
+    // The trajectory encrypted at the resolution the evaluator asked for (see `grid`).
+    // Decoy padding can't be expressed on the grid, so a padded owner refuses it.
+    pub fn encrypt_at(
+        &self,
+        resolution: Resolution,
+    ) -> Result<EncryptedInput, Box<dyn std::error::Error>> {
+        match resolution {
+            Resolution::Full => Ok(EncryptedInput::Full(self.encrypt_trajectory()?)),
+            Resolution::Grid16 { .. } if self.padded_len.is_some() => {
+                Err("a padded trajectory can't be screened at grid resolution".into())
+            }
+            Resolution::Grid16 { cell_size } => Ok(EncryptedInput::Grid(GridTrajectory::encrypt(
+                &self.trajectory,
+                cell_size,
+                self.context.client_key()?,
+            )?)),
+        }
+    }
+
+    // Every satellite of `fleet` encrypted under this party's key. Decoy padding is not
+    // applied.
+    pub fn encrypt_fleet(
+        &self,
+        fleet: &Fleet,
+    ) -> Result<EncryptedFleet, Box<dyn std::error::Error>> {
+        Ok(EncryptedFleet {
+            members: fleet
+                .members()
+                .iter()
+                .map(|(id, trajectory)| Ok((id.clone(), self.context.encrypt(trajectory)?)))
+                .collect::<Result<_, Box<dyn std::error::Error>>>()?,
+        })
+    }
+
+    // Content hashes of this party's trajectory, to keep for `encrypt_changed` next time.
+    pub fn digest(&self) -> Result<TrajectoryDigest, Box<dyn std::error::Error>> {
+        TrajectoryDigest::of(&self.trajectory)
+    }
+
+    // Freshly encrypted steps that changed since the trajectory `old` was taken of, one
+    // trajectory per run of consecutive changed steps. Each keeps the absolute index of
+    // its first step, so `EvaluatorParty::evaluate` screens it against the right steps
+    // and its flags replace those of the same steps from the previous screening. Decoy
+    // padding is not applied.
+    pub fn encrypt_changed(
+        &self,
+        old: &TrajectoryDigest,
+    ) -> Result<Vec<EncryptedTrajectory>, Box<dyn std::error::Error>> {
+        let changed = EncryptedTrajectory::diff_indices(old, &self.digest()?);
+        let mut runs: Vec<Range<usize>> = Vec::new();
+        for i in changed {
+            match runs.last_mut() {
+                Some(run) if run.end == i => run.end += 1,
+                _ => runs.push(i..i + 1),
+            }
+        }
+        runs.into_iter()
+            .map(|run| {
+                let part = SatelliteData {
+                    x: self.trajectory.x[run.clone()].to_vec(),
+                    y: self.trajectory.y[run.clone()].to_vec(),
+                    z: self.trajectory.z[run.clone()].to_vec(),
+                    frame: self.trajectory.frame,
+                    units: self.trajectory.units,
+                };
+                let mut encrypted = self.context.encrypt(&part)?;
+                encrypted.first_index = run.start;
+                encrypted.epochs = (run.start as u64..run.end as u64).collect();
+                Ok(encrypted)
+            })
+            .collect()
+    }
+
+    // Nanosecond epochs of this party's trajectory, encrypted for time-matched screening.
+    pub fn encrypt_epochs(
+        &self,
+        epochs_ns: &[u64],
+    ) -> Result<EncryptedEpochs, Box<dyn std::error::Error>> {
+        EncryptedEpochs::encrypt(epochs_ns, self.context.client_key()?)
+    }
+
+    // Serialized server key to hand to the evaluator.
+    pub fn server_key_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let parameters = self.context.client_key()?.computation_parameters();
+        migrate::encode_with(
+            ArtifactKind::ServerKey,
+            &**self.context.server_key(),
+            Some(ParameterDigest::of_parameters(parameters)),
+        )
+    }
+
+    pub fn server_key_fingerprint(&self) -> &str {
+        self.context.server_key().fingerprint()
+    }
+
+    // Salted cells of this party's trajectory for the evaluator's pre-screen (see
+    // `prescreen`); `salt` is `Session::cell_salt`.
+    pub fn cell_filter(&self, grid: CellGrid, salt: &[u8; 32]) -> CellFilter {
+        CellFilter::build(&self.trajectory, grid, salt)
+    }
+
+    // Steps of this party's trajectory whose cell is in the evaluator's `filter`; if
+    // there are none, no step can match and the screening can be skipped.
+    pub fn prescreen_candidates(
+        &self,
+        filter: &CellFilter,
+        salt: &[u8; 32],
+    ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+        filter.candidates(&self.trajectory, salt)
+    }
+
+    pub fn decrypt_results(&self, results: &[FheBool]) -> Vec<bool> {
+        self.context
+            .decrypt(results)
+            .expect("owner context always holds the client key")
+    }
+
+    // `decrypt_results` for every pair of a fleet screening.
+    pub fn decrypt_fleet(&self, results: &FleetResults) -> BTreeMap<FleetKey, Vec<bool>> {
+        results
+            .flags
+            .iter()
+            .map(|(key, flags)| (key.clone(), self.decrypt_results(flags)))
+            .collect()
+    }
+
+    // `decrypt_results` without the flags of decoy steps; `first_index` is that of the
+    // encrypted trajectory that was screened.
+    pub fn decrypt_unpadded(&self, results: &[FheBool], first_index: usize) -> Vec<bool> {
+        strip(
+            &self.decrypt_results(results),
+            first_index,
+            self.trajectory.len(),
+        )
+        .to_vec()
+    }
+
+    // Per-step flags of a screening that skipped the steps in `mask`, decoys left out;
+    // `window` are the absolute indices of the encrypted trajectory that was screened.
+    pub fn decrypt_masked(
+        &self,
+        results: &[FheBool],
+        mask: &StepMask,
+        window: Range<usize>,
+    ) -> Result<Vec<bool>, Box<dyn std::error::Error>> {
+        let start = window.start;
+        let flags = mask.unmask(&self.decrypt_results(results), window)?;
+        Ok(strip(&flags, start, self.trajectory.len()).to_vec())
+    }
+
+    // `decrypt_unpadded`, after checking every decoy step screened as expected: planted
+    // spot checks positive, all others negative. A `SpotCheckFailed` means the evaluator
+    // didn't compute these flags honestly.
+    pub fn decrypt_verified(
+        &self,
+        results: &[FheBool],
+        first_index: usize,
+    ) -> Result<Vec<bool>, Box<dyn std::error::Error>> {
+        let flags = self.decrypt_results(results);
+        self.spot_checks
+            .verify(&flags, first_index, self.trajectory.len())?;
+        Ok(strip(&flags, first_index, self.trajectory.len()).to_vec())
+    }
+
+    pub fn decrypt_revealed(
+        &self,
+        result: &RevealedResult,
+    ) -> Result<Revealed, Box<dyn std::error::Error>> {
+        result.decrypt(&self.context)
+    }
+
+    // Decrypts the results and clusters positive steps into conjunction events; if there
+    // are any, every alert sink is notified before they are returned. `epochs` and
+    // `first_index` are those of the encrypted trajectory that was screened; decoy steps
+    // are left out, and checked first if spot checks were planted.
+    pub fn decrypt_events(
+        &self,
+        results: &[FheBool],
+        epochs: &[u64],
+        first_index: usize,
+    ) -> Result<Vec<ConjunctionEvent>, Box<dyn std::error::Error>> {
+        let flags = if self.spot_checks.is_empty() {
+            self.decrypt_unpadded(results, first_index)
+        } else {
+            self.decrypt_verified(results, first_index)?
+        };
+        let epochs = epochs.get(..flags.len()).unwrap_or(epochs);
+        let events = cluster(&flags, epochs, first_index)?;
+        if !events.is_empty() {
+            let report = AlertReport {
+                steps: flags.len(),
+                events: events.clone(),
+            };
+            for sink in &self.alerts {
+                sink.notify(&report)?;
+            }
+        }
+        Ok(events)
+    }
+}
+
+// Holds only the owner's server key and its own plaintext trajectory; can evaluate the
+// collision check but never decrypt anything.
+#[derive(Debug)]
+pub struct EvaluatorParty {
+    context: FheContext,
+    trajectory: PrivateTrajectory,
+    screening: ScreeningConfig,
+}
+
+impl EvaluatorParty {
+    // `revealing` with the aggregation the screening config picks for this key's
+    // parameters.
+    fn revealing(&self, policy: RevealPolicy, output: ScreeningOutput) -> RevealedOutput {
+        let preset = ParameterPreset::of_server_key(self.context.server_key()).unwrap_or_default();
+        let aggregation = self
+            .screening
+            .aggregation
+            .select(output.results.len(), preset);
+        revealing(policy, output, aggregation)
+    }
+
+    pub fn server_key_fingerprint(&self) -> &str {
+        self.context.server_key().fingerprint()
+    }
+
+    // Compares the owner's encrypted trajectory against this party's plaintext one and
+    // reports the homomorphic work spent.
+    pub fn evaluate(
+        &self,
+        encrypted: &EncryptedTrajectory,
+    ) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+        self.context.evaluate_with(|| {
+            screen_planned(encrypted, Operand::Clear(&self.trajectory), &self.screening)
+        })
+    }
+
+    // `evaluate` for whichever resolution the owner encrypted at (see `grid`).
+    pub fn evaluate_input(
+        &self,
+        encrypted: &EncryptedInput,
+    ) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+        match encrypted {
+            EncryptedInput::Full(trajectory) => self.evaluate(trajectory),
+            EncryptedInput::Grid(grid) => self
+                .context
+                .evaluate_with(|| screen_grid(grid, &self.trajectory)),
+        }
+    }
+
+    // Screens every satellite of the owner's `fleet` against each of `counterparts`,
+    // e.g. this party's own trajectory and those of others it screens on behalf of.
+    pub fn evaluate_fleet(
+        &self,
+        fleet: &EncryptedFleet,
+        counterparts: &[SatelliteData],
+    ) -> Result<FleetOutput, Box<dyn std::error::Error>> {
+        self.context
+            .evaluate_with(|| screen_fleet(fleet, counterparts, &self.screening))
+    }
+
+    // `evaluate`, passing the flags to `send` in batches of `batch_steps` as soon as they
+    // are computed, screening the batches in `order` (see `stream`).
+    pub fn evaluate_streaming(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        batch_steps: usize,
+        order: &StepOrder,
+        send: impl FnMut(ResultBatch) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<OpCounter, Box<dyn std::error::Error>> {
+        self.context.evaluate_with(|| {
+            screen_streaming(
+                encrypted,
+                &self.trajectory,
+                &self.screening,
+                batch_steps,
+                order,
+                send,
+            )
+        })
+    }
+
+    // The coarse pass of coarse-to-fine screening (see `multires`); the fine passes are
+    // regular `evaluate` calls on the steps the owner sends back.
+    pub fn evaluate_coarse(
+        &self,
+        coarse: &EncryptedTrajectory,
+        plan: &CoarseToFine,
+    ) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+        self.context
+            .evaluate_with(|| screen_coarse(coarse, &self.trajectory, plan, &self.screening))
+    }
+
+    // Salted cells of this party's trajectory, for the owner's `prescreen_candidates`.
+    pub fn cell_filter(&self, grid: CellGrid, salt: &[u8; 32]) -> CellFilter {
+        CellFilter::build(&self.trajectory, grid, salt)
+    }
+
+    // `evaluate`, running FHE only on the steps whose cell is in the owner's `filter`.
+    pub fn evaluate_prescreened(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        filter: &CellFilter,
+        salt: &[u8; 32],
+    ) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+        self.context.evaluate_with(|| {
+            screen_prescreened(encrypted, &self.trajectory, filter, salt, &self.screening)
+        })
+    }
+
+    // `evaluate`, releasing only what `policy` allows (see `reveal`).
+    pub fn evaluate_revealing(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        policy: RevealPolicy,
+    ) -> Result<RevealedOutput, Box<dyn std::error::Error>> {
+        self.context.evaluate_with(|| {
+            let output =
+                screen_planned(encrypted, Operand::Clear(&self.trajectory), &self.screening)?;
+            Ok(self.revealing(policy, output))
+        })
+    }
+
+    // Flags the steps of the owner's encrypted trajectory inside any of `zones`, given in
+    // `frame`, instead of comparing it with this party's trajectory (see `keepout`).
+    pub fn evaluate_keep_out(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        zones: &[KeepOutZone],
+        frame: Frame,
+    ) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+        self.context
+            .evaluate_with(|| screen_keep_out(encrypted, zones, frame, &self.screening))
+    }
+
+    // `evaluate_revealing` on the steps not in the owner's `mask` only (see `mask`).
+    pub fn evaluate_masked(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        mask: &StepMask,
+        policy: RevealPolicy,
+    ) -> Result<RevealedOutput, Box<dyn std::error::Error>> {
+        self.context.evaluate_with(|| {
+            let output = screen_masked(encrypted, &self.trajectory, mask, &self.screening)?;
+            Ok(self.revealing(policy, output))
+        })
+    }
+
+    // `evaluate_revealing` with a caller-supplied per-step kernel (see `CustomKernel`).
+    pub fn evaluate_custom<F>(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        kernel: &CustomKernel<F>,
+        policy: RevealPolicy,
+    ) -> Result<RevealedOutput, Box<dyn std::error::Error>>
+    where
+        F: Fn(&[FheUint32; 3], &[u32; 3]) -> FheBool + Sync,
+    {
+        self.context.evaluate_with(|| {
+            let output = screen_custom(encrypted, &self.trajectory, kernel, &self.screening)?;
+            Ok(self.revealing(policy, output))
+        })
+    }
+
+    // `evaluate`, pairing steps by encrypted time instead of by index: owner steps are
+    // matched against every step of this party's trajectory whose epoch (in
+    // `epochs_ns`) is within `delta_ns` of theirs (see `timing`).
+    pub fn evaluate_time_matched(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        epochs: &EncryptedEpochs,
+        epochs_ns: &[u64],
+        delta_ns: u64,
+    ) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+        self.context.evaluate_with(|| {
+            screen_time_matched(
+                encrypted,
+                epochs,
+                &self.trajectory,
+                epochs_ns,
+                delta_ns,
+                &self.screening,
+            )
+        })
+    }
+
+    // Screens the owner's trajectory against each of `objects` (a private catalog, in
+    // place of this party's own trajectory) and returns one "any step matched" flag per
+    // object, reordered by `shuffle`. Send `shuffle.commitment()` along with them.
+    pub fn evaluate_objects_shuffled(
+        &self,
+        encrypted: &EncryptedTrajectory,
+        objects: &[SatelliteData],
+        shuffle: &Shuffle,
+    ) -> Result<Vec<FheBool>, Box<dyn std::error::Error>> {
+        self.context
+            .evaluate_with(|| screen_objects_shuffled(encrypted, objects, &self.screening, shuffle))
+    }
+}
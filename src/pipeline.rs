@@ -0,0 +1,95 @@
+// Overlapped deserialization and evaluation of a serialized encrypted trajectory.
+//
+// Deserializing a ciphertext is far cheaper than comparing it, but for thousands of steps
+// doing all of it up front still delays the first comparison and holds every ciphertext
+// in memory at once. Here a producer thread deserializes step by step and hands the
+// ciphertexts to the evaluating thread through a bounded channel, so at most `capacity`
+// steps are waiting at any time.
+
+use std::sync::mpsc;
+use std::thread;
+
+use tfhe::FheUint32;
+
+use crate::common::{SatelliteData, safe_deserialize_item};
+use crate::depth::OpCounter;
+use crate::kernel::KernelChoice;
+use crate::screening::{
+    ScreeningConfig, ScreeningOutput, align_plaintext_to, exact_match_cost, exact_match_step,
+};
+use crate::trajectory::SerializedTrajectory;
+
+type StepCiphertexts = Result<[FheUint32; 3], String>;
+
+// `screen_exact` on a trajectory still in its `EncryptedTrajectory::to_bytes` form. Other
+// kernels aren't pipelined, so a `config` asking for one is refused.
+//
+// Runs under the server key matching the trajectory, e.g. inside
+// `FheContext::evaluate_with`; the producer thread needs no key.
+pub fn screen_exact_pipelined(
+    data: &[u8],
+    plaintext: &SatelliteData,
+    config: &ScreeningConfig,
+    capacity: usize,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    if config.kernel != KernelChoice::ExactMatch {
+        return Err(format!("{:?} is not available pipelined", config.kernel).into());
+    }
+    let serialized = SerializedTrajectory::parse(data)?;
+    let len = serialized.x.len();
+    let offset = serialized.first_index;
+    let plaintext = align_plaintext_to(
+        offset..offset + len,
+        serialized.frame,
+        serialized.units,
+        plaintext,
+    )?;
+    let mut step = exact_match_cost();
+    config.check_depth(&mut step)?;
+
+    let (sender, receiver) = mpsc::sync_channel::<StepCiphertexts>(capacity.max(1));
+    let results = thread::scope(|scope| {
+        let serialized = &serialized;
+        scope.spawn(move || {
+            for i in 0..len {
+                let deserialize = |bytes: &[u8]| {
+                    safe_deserialize_item::<FheUint32>(bytes)
+                        .map_err(|e| format!("step {}: {}", offset + i, e))
+                };
+                let step: StepCiphertexts = (|| {
+                    Ok([
+                        deserialize(&serialized.x[i])?,
+                        deserialize(&serialized.y[i])?,
+                        deserialize(&serialized.z[i])?,
+                    ])
+                })();
+                let failed = step.is_err();
+                // The evaluator hung up after an error of its own; nothing left to do.
+                if sender.send(step).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let mut results = Vec::with_capacity(len);
+        // Consuming the receiver means an early return drops it, which unblocks the
+        // producer before the scope joins it.
+        for (i, step) in receiver.into_iter().enumerate() {
+            let [x, y, z] = step?;
+            let p = offset + i;
+            results.push(exact_match_step(
+                [
+                    (&x, config.clear(plaintext.x[p])),
+                    (&y, config.clear(plaintext.y[p])),
+                    (&z, config.clear(plaintext.z[p])),
+                ],
+                config.parallel_axes,
+            ));
+        }
+        Ok::<_, Box<dyn std::error::Error>>(results)
+    })?;
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, len as u64);
+    Ok(ScreeningOutput { results, ops })
+}
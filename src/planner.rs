@@ -0,0 +1,115 @@
+// Chooses how to evaluate the per-step comparison depending on what the evaluator holds
+// for the other side.
+//
+// Comparing a ciphertext with a clear `u32` (scalar op) is much cheaper than comparing
+// two ciphertexts: the clear value is folded into the lookup tables instead of being
+// processed block by block. The normal two-party flow has one plaintext side, so it can
+// take the scalar path; ciphertext-vs-ciphertext is needed in symmetric mode, where both
+// trajectories are encrypted under the same key, and whenever
+// `ScreeningConfig::constant_shape` (the default) trivially encrypts the clear side to
+// hide its values from timing, see `ClearCoord`.
+
+use tfhe::FheBool;
+use tfhe::prelude::*;
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::frame::check_frames;
+use crate::kernel::KernelChoice;
+use crate::protocol::ProtocolError;
+use crate::screening::{ScreeningConfig, ScreeningOutput, exact_match_cost, screen_kernel};
+use crate::trajectory::EncryptedTrajectory;
+
+pub enum Operand<'a> {
+    Clear(&'a SatelliteData),
+    Encrypted(&'a EncryptedTrajectory),
+}
+
+impl Operand<'_> {
+    pub fn len(&self) -> usize {
+        match self {
+            Operand::Clear(data) => data.x.len(),
+            Operand::Encrypted(trajectory) => trajectory.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalPlan {
+    // Ciphertext vs clear value.
+    Scalar,
+    // Ciphertext vs ciphertext (symmetric mode).
+    Ciphertext,
+}
+
+pub fn plan(other: &Operand, config: &ScreeningConfig) -> EvalPlan {
+    match other {
+        Operand::Clear(_) if !config.constant_shape => EvalPlan::Scalar,
+        _ => EvalPlan::Ciphertext,
+    }
+}
+
+// Screening of `encrypted` against `other` with the configured kernel, using the cheapest
+// operation `plan` allows. Symmetric mode only supports exact match.
+pub fn screen_planned(
+    encrypted: &EncryptedTrajectory,
+    other: Operand,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    match other {
+        Operand::Clear(plaintext) => {
+            screen_kernel(encrypted, plaintext, &*config.kernel.kernel()?, config)
+        }
+        Operand::Encrypted(other) => {
+            if config.kernel != KernelChoice::ExactMatch {
+                return Err(
+                    format!("{:?} is not available in symmetric mode", config.kernel).into(),
+                );
+            }
+            screen_exact_symmetric(encrypted, other, config)
+        }
+    }
+}
+
+fn screen_exact_symmetric(
+    a: &EncryptedTrajectory,
+    b: &EncryptedTrajectory,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    if a.len() != b.len() || a.first_index != b.first_index {
+        return Err(format!(
+            "trajectory mismatch: steps {}..{} vs {}..{}",
+            a.first_index,
+            a.absolute_index(a.len()),
+            b.first_index,
+            b.absolute_index(b.len())
+        )
+        .into());
+    }
+    check_frames(a.frame, b.frame)?;
+    if a.units != b.units {
+        return Err(ProtocolError::UnitsMismatch {
+            owner: a.units,
+            evaluator: b.units,
+        }
+        .into());
+    }
+    let mut step = exact_match_cost();
+    config.check_depth(&mut step)?;
+
+    let mut results: Vec<FheBool> = Vec::with_capacity(a.len());
+    for i in 0..a.len() {
+        let eq_x = a.x[i].eq(&b.x[i]);
+        let eq_y = a.y[i].eq(&b.y[i]);
+        let eq_z = a.z[i].eq(&b.z[i]);
+        results.push(eq_x & eq_y & eq_z);
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, a.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
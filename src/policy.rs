@@ -0,0 +1,179 @@
+// Per-counterpart limits on what an owner's screenings may ask for.
+//
+// Every screening reveals something: a wide threshold says where the owner's satellite
+// roughly is, per-step flags say when, a fine resolution over a long horizon sharpens
+// both. An owner trusts some counterparts more than others, so it keeps one
+// `PolicyProfile` per counterpart organization in a `Policies` book: which kernels and
+// reveal policies are allowed, the widest tolerance, the finest resolution and time
+// step, and the longest window. A `Session` given a profile (`Session::with_policy`)
+// refuses to announce metadata or an encoding the profile forbids, and to accept an
+// encoding negotiated past it, before any trajectory artifact is exchanged.
+//
+// Unset limits allow anything. A counterpart without a profile gets the book's default,
+// or no screening at all if there is none.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::KernelChoice;
+use crate::negotiation::EncodingParams;
+use crate::protocol::{ProtocolError, SessionMetadata};
+use crate::reveal::RevealPolicy;
+use crate::units::CANONICAL_UNITS;
+
+// A kernel without its parameters.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelKind {
+    Exact,
+    Box,
+    Distance,
+    AltitudeBand,
+}
+
+impl KernelKind {
+    pub fn of(choice: KernelChoice) -> Self {
+        match choice {
+            KernelChoice::ExactMatch => KernelKind::Exact,
+            KernelChoice::BoxThreshold { .. } => KernelKind::Box,
+            KernelChoice::SquaredDistanceThreshold { .. } => KernelKind::Distance,
+            KernelChoice::AltitudeBand { .. } => KernelKind::AltitudeBand,
+        }
+    }
+}
+
+// Half-width, threshold or tolerance of `choice`, in its units; 0 for exact matching.
+fn tolerance(choice: KernelChoice) -> u32 {
+    match choice {
+        KernelChoice::ExactMatch => 0,
+        KernelChoice::BoxThreshold { half_width } => half_width,
+        KernelChoice::SquaredDistanceThreshold { threshold } => threshold,
+        KernelChoice::AltitudeBand { tolerance } => tolerance,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyProfile {
+    // Kernels the counterpart may run; any if empty.
+    #[serde(default)]
+    pub kernels: Vec<KernelKind>,
+    // Widest kernel tolerance, in meters.
+    #[serde(default)]
+    pub max_tolerance_m: Option<f64>,
+    // What the owner may learn from the results; any if empty.
+    #[serde(default)]
+    pub reveals: Vec<RevealPolicy>,
+    // Finest encoding resolution, one grid cell, in meters.
+    #[serde(default)]
+    pub min_resolution_m: Option<f64>,
+    #[serde(default)]
+    pub min_time_step_s: Option<u64>,
+    // Longest screening window, in seconds.
+    #[serde(default)]
+    pub max_horizon_s: Option<u64>,
+}
+
+fn violation(rule: &'static str, requested: String, allowed: String) -> ProtocolError {
+    ProtocolError::PolicyViolation {
+        rule,
+        requested,
+        allowed,
+    }
+}
+
+impl PolicyProfile {
+    // Whether the screening `metadata` announces is allowed.
+    pub fn check_metadata(&self, metadata: &SessionMetadata) -> Result<(), ProtocolError> {
+        let kernel = metadata.kernel.unwrap_or_default();
+        let kind = KernelKind::of(kernel);
+        if !self.kernels.is_empty() && !self.kernels.contains(&kind) {
+            return Err(violation(
+                "kernel",
+                format!("{:?}", kind),
+                format!("{:?}", self.kernels),
+            ));
+        }
+        if let Some(max) = self.max_tolerance_m {
+            let units = metadata.units.unwrap_or(CANONICAL_UNITS);
+            let tolerance_m = tolerance(kernel) as f64 * units.meters();
+            if tolerance_m > max {
+                return Err(violation(
+                    "tolerance",
+                    format!("{} m", tolerance_m),
+                    format!("at most {} m", max),
+                ));
+            }
+        }
+        let reveal = metadata.reveal.unwrap_or_default();
+        if !self.reveals.is_empty() && !self.reveals.contains(&reveal) {
+            return Err(violation(
+                "reveal policy",
+                format!("{:?}", reveal),
+                format!("{:?}", self.reveals),
+            ));
+        }
+        Ok(())
+    }
+
+    // Whether screening trajectories quantized with `params` is allowed.
+    pub fn check_encoding(&self, params: &EncodingParams) -> Result<(), ProtocolError> {
+        if let Some(min) = self.min_resolution_m {
+            let resolution_m = params.cell_size as f64 * params.units.meters();
+            if resolution_m < min {
+                return Err(violation(
+                    "resolution",
+                    format!("{} m", resolution_m),
+                    format!("at least {} m", min),
+                ));
+            }
+        }
+        if let Some(min) = self.min_time_step_s
+            && params.time_step_s < min
+        {
+            return Err(violation(
+                "time step",
+                format!("{} s", params.time_step_s),
+                format!("at least {} s", min),
+            ));
+        }
+        if let Some(max) = self.max_horizon_s {
+            let horizon = params.window_end.saturating_sub(params.window_start);
+            if horizon > max {
+                return Err(violation(
+                    "window",
+                    format!("{} s", horizon),
+                    format!("at most {} s", max),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The owner's profiles, by counterpart organization.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Policies {
+    #[serde(default)]
+    pub counterparts: BTreeMap<String, PolicyProfile>,
+    // Profile of every counterpart not listed; unlisted counterparts are refused if unset.
+    #[serde(default)]
+    pub default: Option<PolicyProfile>,
+}
+
+impl Policies {
+    pub fn profile(&self, counterpart: &str) -> Result<&PolicyProfile, ProtocolError> {
+        self.counterparts
+            .get(counterpart)
+            .or(self.default.as_ref())
+            .ok_or_else(|| {
+                violation(
+                    "counterpart",
+                    format!("{:?}", counterpart),
+                    "listed counterparts only".to_string(),
+                )
+            })
+    }
+}
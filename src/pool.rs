@@ -0,0 +1,138 @@
+// Dedicated OS threads for FHE evaluation, apart from the async runtime.
+//
+// A screening keeps a core busy for minutes. Run on tokio's blocking pool it competes with
+// the runtime's own threads and queues up without limit, so a burst of jobs makes the
+// daemon slow to answer even status polls. `EvalPool` runs work on a fixed number of
+// threads and holds at most `queue` tasks waiting for one; `submit` refuses anything
+// beyond that with `PoolFull`, which the daemon passes back to the client instead of
+// accepting work it can't start.
+//
+// Tasks bring their own keys: each evaluates inside `FheContext::evaluate_with`, which
+// installs the job's server key on the pool thread for the task's duration. The pool
+// removes any key a task left installed, so the next task on that thread never runs under
+// someone else's key.
+
+use std::fmt;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tokio::sync::oneshot;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+// `submit` was refused because `queue` tasks are already waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolFull {
+    pub queued: usize,
+}
+
+impl fmt::Display for PoolFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "evaluation queue is full ({} jobs waiting), try again later",
+            self.queued
+        )
+    }
+}
+
+impl std::error::Error for PoolFull {}
+
+pub struct EvalPool {
+    sender: Option<SyncSender<Task>>,
+    threads: Vec<JoinHandle<()>>,
+    // Submitted tasks no thread has picked up yet.
+    queued: Arc<AtomicUsize>,
+    queue: usize,
+}
+
+impl EvalPool {
+    // `threads` evaluation threads and room for `queue` waiting tasks, at least one each.
+    pub fn new(threads: usize, queue: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let queue = queue.max(1);
+        let (sender, receiver) = sync_channel::<Task>(queue);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+        let threads = (0..threads.max(1))
+            .map(|i| {
+                let receiver = receiver.clone();
+                let queued = queued.clone();
+                std::thread::Builder::new()
+                    .name(format!("fhe-pool-{}", i))
+                    .spawn(move || worker(&receiver, &queued))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            sender: Some(sender),
+            threads,
+            queued,
+            queue,
+        })
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads.len()
+    }
+
+    // Tasks waiting for a thread.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queued() >= self.queue
+    }
+
+    // Runs `work` on a pool thread and resolves to its result, or to an error if `work`
+    // panicked.
+    pub fn submit<T: Send + 'static>(
+        &self,
+        work: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<oneshot::Receiver<std::thread::Result<T>>, PoolFull> {
+        let (done, result) = oneshot::channel();
+        let task: Task = Box::new(move || {
+            let _ = done.send(catch_unwind(AssertUnwindSafe(work)));
+        });
+        // Counted before sending, so a thread picking the task up at once never takes the
+        // counter below zero.
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        let sender = self.sender.as_ref().expect("pool is running");
+        match sender.try_send(task) {
+            Ok(()) => Ok(result),
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                let queued = self.queued.fetch_sub(1, Ordering::SeqCst) - 1;
+                Err(PoolFull { queued })
+            }
+        }
+    }
+}
+
+fn worker(receiver: &Mutex<Receiver<Task>>, queued: &AtomicUsize) {
+    loop {
+        let task = match receiver.lock().unwrap().recv() {
+            Ok(task) => task,
+            // The pool was dropped.
+            Err(_) => return,
+        };
+        queued.fetch_sub(1, Ordering::SeqCst);
+        task();
+        tfhe::unset_server_key();
+    }
+}
+
+// Waits for the queued and running tasks to finish. A task may hold the last reference
+// to the pool's owner, in which case its own thread is left to exit by itself.
+impl Drop for EvalPool {
+    fn drop(&mut self) {
+        self.sender = None;
+        let current = std::thread::current().id();
+        for thread in self.threads.drain(..) {
+            if thread.thread().id() != current {
+                let _ = thread.join();
+            }
+        }
+    }
+}
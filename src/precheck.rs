@@ -0,0 +1,89 @@
+// Owner-side pre-check against the public catalog.
+//
+// Objects of the public catalog have published orbits, so screening the owner's
+// trajectory against them needs no encryption: the owner runs the same kernel in the
+// clear, locally, in milliseconds, and keeps FHE sessions for the operators whose
+// ephemerides are private. Each catalog object with at least one flagged step becomes a
+// `SessionOutcome` with `Source::Catalog`, peer "NORAD <id> <name>", to be aggregated
+// with the decrypted outcomes of the FHE sessions into one `GroupReport`.
+//
+// Verdicts are those of `KernelChoice::matches_clear`, which mirrors the encrypted
+// kernels step for step, so a conjunction is flagged the same whichever way it is
+// screened. The catalog must be propagated on the owner's epochs, in its frame and units.
+
+use rayon::prelude::*;
+
+use crate::catalog::CatalogObject;
+use crate::common::SatelliteData;
+use crate::events;
+use crate::frame::check_frames;
+use crate::kernel::KernelChoice;
+use crate::protocol::ProtocolError;
+use crate::report::{GroupReport, SessionOutcome, Source};
+
+// Flags of `kernel` between `owner` and `object`, step by step.
+pub fn flags(
+    owner: &SatelliteData,
+    object: &SatelliteData,
+    kernel: KernelChoice,
+) -> Result<Vec<bool>, Box<dyn std::error::Error>> {
+    check_frames(owner.frame, object.frame)?;
+    if owner.units != object.units {
+        return Err(ProtocolError::UnitsMismatch {
+            owner: owner.units,
+            evaluator: object.units,
+        }
+        .into());
+    }
+    if owner.x.len() != object.x.len() {
+        return Err(format!(
+            "trajectory of {} steps screened against one of {}",
+            owner.x.len(),
+            object.x.len()
+        )
+        .into());
+    }
+    Ok((0..owner.x.len())
+        .map(|i| {
+            kernel.matches_clear(
+                [owner.x[i], owner.y[i], owner.z[i]],
+                [object.x[i], object.y[i], object.z[i]],
+            )
+        })
+        .collect())
+}
+
+// Screens `satellite`'s plaintext `owner` trajectory, sampled at `epochs`, against every
+// object of `catalog`; one outcome per object with events, in catalog order.
+pub fn precheck_catalog(
+    satellite: &str,
+    owner: &SatelliteData,
+    epochs: &[u64],
+    catalog: &[CatalogObject],
+    kernel: KernelChoice,
+) -> Result<Vec<SessionOutcome>, Box<dyn std::error::Error>> {
+    let outcomes = catalog
+        .par_iter()
+        .map(|object| {
+            let events = flags(owner, &object.trajectory, kernel)
+                .and_then(|flags| events::cluster(&flags, epochs, 0))
+                .map_err(|e| format!("NORAD {}: {}", object.norad_id, e))?;
+            Ok(SessionOutcome {
+                satellite: satellite.to_string(),
+                peer: format!("NORAD {} {}", object.norad_id, object.name),
+                events,
+                source: Source::Catalog,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(outcomes
+        .into_iter()
+        .filter(|outcome| !outcome.events.is_empty())
+        .collect())
+}
+
+// One report over the pre-check outcomes and the decrypted outcomes of the FHE sessions.
+pub fn combined_report(catalog: &[SessionOutcome], sessions: &[SessionOutcome]) -> GroupReport {
+    let outcomes: Vec<SessionOutcome> = catalog.iter().chain(sessions).cloned().collect();
+    GroupReport::aggregate(&outcomes)
+}
@@ -0,0 +1,230 @@
+// Plaintext pre-screen over hashed space-time cells.
+//
+// Most steps of a screening window are nowhere near a conjunction, yet each one costs a
+// full FHE comparison. In this optional mode the owner first sends a Bloom filter of the
+// coarse cells its trajectory occupies: every (time bucket, voxel) pair hashed with a
+// per-session salt. The evaluator looks up its own cell at every step and only runs FHE on
+// steps whose cell is in the filter; the rest are known not to match and get a trivial
+// false flag. Likewise the owner can check its cells against the evaluator's filter, e.g.
+// to skip a screening no step of which could match.
+//
+// The filter reveals coarse occupancy: the peer can test any cell against it, and the
+// salt only keeps filters from different sessions unlinkable. Use cells no finer than
+// that leak is acceptable for. False positives cost an FHE step, never a missed match;
+// a threshold kernel needs the owner's filter built with `CellGrid::neighbors`, so a
+// match across a cell boundary is still found.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tfhe::FheBool;
+use tfhe::prelude::*;
+
+use crate::common::SatelliteData;
+use crate::protocol::SessionNonce;
+use crate::screening::{ScreeningConfig, ScreeningOutput, screen_kernel_each};
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::Units;
+
+// False positive rate filters are sized for.
+pub const FALSE_POSITIVE_RATE: f64 = 0.01;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellGrid {
+    // Voxel edge, in the trajectory's units.
+    pub cell_size: u32,
+    // Consecutive steps sharing a time bucket.
+    pub steps_per_bucket: usize,
+    // Also insert the 26 neighboring voxels of every occupied one, for kernels matching
+    // positions in different cells; `cell_size` must then be at least the kernel's
+    // tolerance.
+    pub neighbors: bool,
+}
+
+impl CellGrid {
+    fn cell(&self, data: &SatelliteData, step: usize) -> (u64, [u32; 3]) {
+        let size = self.cell_size.max(1);
+        (
+            (step / self.steps_per_bucket.max(1)) as u64,
+            [
+                data.x[step] / size,
+                data.y[step] / size,
+                data.z[step] / size,
+            ],
+        )
+    }
+}
+
+// Salt of the cell filters exchanged in the session with `nonce`.
+pub fn session_salt(nonce: &SessionNonce) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sat-fhe cell filter");
+    hasher.update(nonce);
+    hasher.finalize().into()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    len: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    // Sized for `items` insertions at `FALSE_POSITIVE_RATE`.
+    pub fn with_capacity(items: usize) -> Self {
+        let n = items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let len = (-n * FALSE_POSITIVE_RATE.ln() / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let hashes = ((len as f64 / n) * ln2).round().max(1.0) as u32;
+        Self {
+            bits: vec![0; len.div_ceil(64) as usize],
+            len,
+            hashes,
+        }
+    }
+
+    // Bit positions of `item` by double hashing its SHA-256.
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = u64> + '_ {
+        let digest = Sha256::digest(item);
+        let h1 = u64::from_le_bytes(digest[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap()) | 1;
+        (0..self.hashes as u64).map(move |k| h1.wrapping_add(k.wrapping_mul(h2)) % self.len)
+    }
+
+    pub fn insert(&mut self, item: &[u8]) {
+        let positions: Vec<u64> = self.positions(item).collect();
+        for bit in positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+}
+
+// The salted cells of one party's trajectory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CellFilter {
+    pub grid: CellGrid,
+    pub units: Units,
+    filter: BloomFilter,
+}
+
+fn cell_key(salt: &[u8; 32], bucket: u64, voxel: [u32; 3]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32 + 8 + 12);
+    key.extend_from_slice(salt);
+    key.extend_from_slice(&bucket.to_le_bytes());
+    for v in voxel {
+        key.extend_from_slice(&v.to_le_bytes());
+    }
+    key
+}
+
+impl CellFilter {
+    // Filter of the cells `data` occupies. Steps are indexed from the start of `data`,
+    // which must cover the screening window from its first step like the evaluator's
+    // plaintext does.
+    pub fn build(data: &SatelliteData, grid: CellGrid, salt: &[u8; 32]) -> Self {
+        let per_step = if grid.neighbors { 27 } else { 1 };
+        let mut filter = BloomFilter::with_capacity(data.x.len() * per_step);
+        for step in 0..data.x.len() {
+            let (bucket, [x, y, z]) = grid.cell(data, step);
+            if !grid.neighbors {
+                filter.insert(&cell_key(salt, bucket, [x, y, z]));
+                continue;
+            }
+            let around = |v: u32| v.saturating_sub(1)..=v.saturating_add(1);
+            for nx in around(x) {
+                for ny in around(y) {
+                    for nz in around(z) {
+                        filter.insert(&cell_key(salt, bucket, [nx, ny, nz]));
+                    }
+                }
+            }
+        }
+        Self {
+            grid,
+            units: data.units,
+            filter,
+        }
+    }
+
+    // Steps of `data` whose cell (on this filter's grid) is in the filter: the only ones
+    // that can match the trajectory the filter was built from.
+    pub fn candidates(
+        &self,
+        data: &SatelliteData,
+        salt: &[u8; 32],
+    ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+        let rescaled;
+        let data = if data.units == self.units {
+            data
+        } else {
+            rescaled = data.to_units(self.units)?;
+            &rescaled
+        };
+        Ok((0..data.x.len())
+            .filter(|&step| {
+                let (bucket, voxel) = self.grid.cell(data, step);
+                self.filter.contains(&cell_key(salt, bucket, voxel))
+            })
+            .collect())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let filter: CellFilter = bincode::deserialize(data)?;
+        let bloom = &filter.filter;
+        if bloom.len == 0
+            || bloom.bits.len() as u64 != bloom.len.div_ceil(64)
+            || !(1..=64).contains(&bloom.hashes)
+        {
+            return Err("malformed cell filter".into());
+        }
+        Ok(filter)
+    }
+}
+
+// `config.kernel` on the steps of `plaintext` whose cell is in the owner's `filter`;
+// every other step gets a trivial false flag. Results cover every step of `encrypted`.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_prescreened(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    filter: &CellFilter,
+    salt: &[u8; 32],
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let window = encrypted.first_index..encrypted.first_index + encrypted.len();
+    let steps: Vec<usize> = filter
+        .candidates(plaintext, salt)?
+        .into_iter()
+        .filter(|step| window.contains(step))
+        .map(|step| step - encrypted.first_index)
+        .collect();
+    let kernel = config.kernel.kernel()?;
+    let mut results: Vec<FheBool> = (0..encrypted.len())
+        .map(|_| FheBool::encrypt_trivial(false))
+        .collect();
+    let mut positions = steps.iter();
+    let ops = screen_kernel_each(
+        encrypted,
+        plaintext,
+        kernel.as_ref(),
+        config,
+        steps.iter().copied(),
+        |flag| {
+            results[*positions.next().expect("one flag per step")] = flag;
+            Ok(())
+        },
+    )?;
+    Ok(ScreeningOutput { results, ops })
+}
@@ -0,0 +1,78 @@
+// Named TFHE parameter sets. Both parties must generate and use keys from the same
+// preset; the evaluator daemon advertises the one it is configured for.
+
+use serde::{Deserialize, Serialize};
+use tfhe::conformance::ParameterSetConformant;
+use tfhe::shortint::parameters::{
+    ClassicPBSParameters, PARAM_MESSAGE_2_CARRY_2_KS_PBS_GAUSSIAN_2M128,
+    PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M64, PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M128,
+};
+use tfhe::{Config, ConfigBuilder, ServerKey};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParameterPreset {
+    // TFHE-rs defaults: 2-bit message blocks, TUniform noise, 2^-128 failure probability.
+    #[default]
+    Default,
+    // Same precision with Gaussian noise.
+    Gaussian2m128,
+    // Faster, with a 2^-64 failure probability.
+    Tuniform2m64,
+}
+
+impl ParameterPreset {
+    pub const ALL: [ParameterPreset; 3] = [
+        ParameterPreset::Default,
+        ParameterPreset::Gaussian2m128,
+        ParameterPreset::Tuniform2m64,
+    ];
+
+    // Name in configuration files and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            ParameterPreset::Default => "default",
+            ParameterPreset::Gaussian2m128 => "gaussian2m128",
+            ParameterPreset::Tuniform2m64 => "tuniform2m64",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| format!("unknown parameter preset {:?}", name).into())
+    }
+
+    pub fn config(self) -> Config {
+        match self {
+            ParameterPreset::Default => ConfigBuilder::default().build(),
+            ParameterPreset::Gaussian2m128 => {
+                ConfigBuilder::with_custom_parameters(PARAM_MESSAGE_2_CARRY_2_KS_PBS_GAUSSIAN_2M128)
+                    .build()
+            }
+            ParameterPreset::Tuniform2m64 => {
+                ConfigBuilder::with_custom_parameters(PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M64)
+                    .build()
+            }
+        }
+    }
+
+    // Block parameters of the preset. `Default` is TFHE-rs's default set.
+    pub fn parameters(self) -> ClassicPBSParameters {
+        match self {
+            ParameterPreset::Default => PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M128,
+            ParameterPreset::Gaussian2m128 => PARAM_MESSAGE_2_CARRY_2_KS_PBS_GAUSSIAN_2M128,
+            ParameterPreset::Tuniform2m64 => PARAM_MESSAGE_2_CARRY_2_KS_PBS_TUNIFORM_2M64,
+        }
+    }
+
+    // Preset `key` was generated from, recognized by checking the key's dimensions against
+    // each preset's config through a borrow, so the key isn't copied. `None` for keys from
+    // any other parameter set.
+    pub fn of_server_key(key: &ServerKey) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| key.is_conformant(&preset.config().into()))
+    }
+}
@@ -0,0 +1,190 @@
+// Where kernel time goes, for contributors optimizing the evaluation.
+//
+// With the `profiling` feature, the evaluation hot path wraps every TFHE operation of the
+// kernels (comparisons, min and max, arithmetic, casts, boolean combinations, trivial
+// encryptions) in a `span`, one frame of the profile, under a frame per step named after
+// its kernel (`ComparisonKernel::name`). `record` runs a screening and returns the time spent in each
+// stack of frames as a `FoldedProfile`, whose `to_folded` text is the folded-stack format
+// `inferno-flamegraph` and `flamegraph.pl` turn into a flame graph; `sat-fhe profile`
+// prints one for a standard workload (`bench::profile_workload`).
+//
+// A frame's time is its own, without its children's. Children on the context's workers
+// (see `context::join`) are recorded under the stack that forked them, and the time the
+// parent waits for them counts as its own, so with parallel axes the stacks add up to more
+// than the wall time. Without the feature `span` only runs its closure, and there is
+// nothing to record.
+
+#[cfg(feature = "profiling")]
+use std::cell::RefCell;
+#[cfg(feature = "profiling")]
+use std::collections::BTreeMap;
+#[cfg(feature = "profiling")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "profiling")]
+use std::time::{Duration, Instant};
+
+// Own time per stack, in nanoseconds, keyed by the frame names joined with ';'.
+#[cfg(feature = "profiling")]
+type Sink = Arc<Mutex<BTreeMap<String, u64>>>;
+
+#[cfg(feature = "profiling")]
+struct Recording {
+    sink: Sink,
+    stack: Vec<&'static str>,
+    // Time of the finished children of each frame of `stack`.
+    children: Vec<Duration>,
+}
+
+#[cfg(feature = "profiling")]
+thread_local! {
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+// Closes the innermost frame when dropped, so a panicking step (see
+// `screening::screen_kernel_checked`) doesn't leave it open.
+#[cfg(feature = "profiling")]
+struct Open {
+    started: Instant,
+}
+
+#[cfg(feature = "profiling")]
+impl Drop for Open {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        RECORDING.with(|recording| {
+            if let Some(recording) = recording.borrow_mut().as_mut() {
+                let stack = recording.stack.join(";");
+                recording.stack.pop();
+                let children = recording.children.pop().unwrap_or_default();
+                *recording.sink.lock().unwrap().entry(stack).or_default() +=
+                    elapsed.saturating_sub(children).as_nanos() as u64;
+                if let Some(parent) = recording.children.last_mut() {
+                    *parent += elapsed;
+                }
+            }
+        });
+    }
+}
+
+// Runs `f` as a frame named `name` of the screening being recorded on this thread, if any.
+pub fn span<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "profiling")]
+    {
+        let recording = RECORDING.with(|recording| match recording.borrow_mut().as_mut() {
+            Some(recording) => {
+                recording.stack.push(name);
+                recording.children.push(Duration::ZERO);
+                true
+            }
+            None => false,
+        });
+        if recording {
+            let _open = Open {
+                started: Instant::now(),
+            };
+            return f();
+        }
+    }
+    #[cfg(not(feature = "profiling"))]
+    let _ = name;
+    f()
+}
+
+// `f`, to be run on any thread, recording under the stack of the calling thread.
+pub(crate) fn inherit<T>(f: impl FnOnce() -> T + Send) -> impl FnOnce() -> T + Send {
+    #[cfg(feature = "profiling")]
+    {
+        let origin = std::thread::current().id();
+        let parent = RECORDING.with(|recording| {
+            recording
+                .borrow()
+                .as_ref()
+                .map(|recording| (recording.sink.clone(), recording.stack.clone()))
+        });
+        move || {
+            let Some((sink, stack)) = parent else {
+                return f();
+            };
+            let depth = stack.len();
+            let previous = RECORDING.with(|recording| {
+                recording.replace(Some(Recording {
+                    sink,
+                    stack,
+                    children: vec![Duration::ZERO; depth],
+                }))
+            });
+            let result = f();
+            let forked = RECORDING.with(|recording| recording.replace(previous));
+            // Run on the forking thread after all: the parent didn't wait, it did the work.
+            if std::thread::current().id() == origin {
+                let spent = forked
+                    .and_then(|forked| forked.children.last().copied())
+                    .unwrap_or_default();
+                RECORDING.with(|recording| {
+                    if let Some(parent) = recording
+                        .borrow_mut()
+                        .as_mut()
+                        .and_then(|recording| recording.children.last_mut())
+                    {
+                        *parent += spent;
+                    }
+                });
+            }
+            result
+        }
+    }
+    #[cfg(not(feature = "profiling"))]
+    f
+}
+
+// Own time of every stack of frames of one recorded screening.
+#[cfg(feature = "profiling")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FoldedProfile {
+    // Nanoseconds, by frame names from the root joined with ';'.
+    pub stacks: BTreeMap<String, u64>,
+}
+
+#[cfg(feature = "profiling")]
+impl FoldedProfile {
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.stacks.values().sum())
+    }
+
+    // Own time of the frames named `name`, wherever they are in the stacks.
+    pub fn time_in(&self, name: &str) -> Duration {
+        Duration::from_nanos(
+            self.stacks
+                .iter()
+                .filter(|(stack, _)| stack.rsplit(';').next() == Some(name))
+                .map(|(_, &nanos)| nanos)
+                .sum(),
+        )
+    }
+
+    // One "stack nanoseconds" line per stack, for flame graph tools.
+    pub fn to_folded(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|(stack, nanos)| format!("{} {}\n", stack, nanos))
+            .collect()
+    }
+}
+
+// Runs `f`, e.g. a screening inside `FheContext::evaluate_with`, as the root frame `root`
+// of a new recording on this thread.
+#[cfg(feature = "profiling")]
+pub fn record<T>(root: &'static str, f: impl FnOnce() -> T) -> (T, FoldedProfile) {
+    let sink = Sink::default();
+    let previous = RECORDING.with(|recording| {
+        recording.replace(Some(Recording {
+            sink: sink.clone(),
+            stack: Vec::new(),
+            children: Vec::new(),
+        }))
+    });
+    let result = span(root, f);
+    RECORDING.with(|recording| recording.replace(previous));
+    let stacks = std::mem::take(&mut *sink.lock().unwrap());
+    (result, FoldedProfile { stacks })
+}
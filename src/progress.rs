@@ -0,0 +1,184 @@
+// Progress bars for the long-running phases of `sat-fhe screen`.
+//
+// Encrypting, screening and decrypting a long trajectory each take minutes, and a command
+// that prints nothing for that long looks hung. `screen_local` runs the three phases of a
+// screening on this machine (the owner encrypting its trajectory, the evaluator screening
+// it in result batches, the owner decrypting the batches) and draws one bar per phase on
+// stderr with the steps done so far and the time left. Nothing is drawn when stderr isn't
+// a terminal.
+//
+// Before a phase has done anything, its time left is the calibrated estimate of a
+// `bench::PerformanceProfile`, if there is one: the profile times screenings, not
+// encryption, so only the screening phase has one. As steps complete, the rate measured so
+// far takes over in proportion to the share of steps done (`eta`).
+
+use std::time::{Duration, Instant};
+
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+
+use crate::bench::PerformanceProfile;
+use crate::common::SatelliteData;
+use crate::context::FheContext;
+use crate::depth::OpCounter;
+use crate::preset::ParameterPreset;
+use crate::schedule::StepOrder;
+use crate::screening::ScreeningConfig;
+use crate::stream::{ResultBatch, screen_streaming};
+use crate::trajectory::EncryptedTrajectory;
+
+// Steps encrypted between two updates of the encryption bar, at most.
+const ENCRYPT_CHUNK: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Encrypt,
+    Screen,
+    Decrypt,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Encrypt => "encrypt",
+            Phase::Screen => "screen",
+            Phase::Decrypt => "decrypt",
+        }
+    }
+}
+
+// Time left in a phase of `total` steps with `done` of them taking `elapsed`, blending
+// `estimate` (of the whole phase) with the measured rate by the share done. `None` while
+// there is neither an estimate nor a step done.
+pub fn eta(
+    estimate: Option<Duration>,
+    done: usize,
+    total: usize,
+    elapsed: Duration,
+) -> Option<Duration> {
+    if done >= total {
+        return Some(Duration::ZERO);
+    }
+    let share = done as f64 / total as f64;
+    let estimated = estimate.map(|estimate| estimate.as_secs_f64() * (1.0 - share));
+    let measured = (done > 0).then(|| elapsed.as_secs_f64() * (1.0 - share) / share);
+    let secs = match (estimated, measured) {
+        (Some(estimated), Some(measured)) => estimated * (1.0 - share) + measured * share,
+        (estimated, measured) => estimated.or(measured)?,
+    };
+    Some(Duration::from_secs_f64(secs))
+}
+
+// The bar of one phase.
+pub struct PhaseBar {
+    bar: ProgressBar,
+    started: Instant,
+    estimate: Option<Duration>,
+    total: usize,
+    done: usize,
+}
+
+impl PhaseBar {
+    pub fn new(phase: Phase, total: usize, estimate: Option<Duration>) -> Self {
+        let bar = ProgressBar::new(total as u64);
+        bar.set_style(
+            ProgressStyle::with_template("{prefix:>8} [{bar:40}] {pos}/{len} steps, {msg}")
+                .expect("template is valid")
+                .progress_chars("=> "),
+        );
+        bar.set_prefix(phase.name());
+        let mut phase = Self {
+            bar,
+            started: Instant::now(),
+            estimate,
+            total,
+            done: 0,
+        };
+        phase.advance(0);
+        phase
+    }
+
+    pub fn advance(&mut self, steps: usize) {
+        self.done = (self.done + steps).min(self.total);
+        self.bar.set_position(self.done as u64);
+        let message = match eta(self.estimate, self.done, self.total, self.started.elapsed()) {
+            Some(left) => format!("{} left", HumanDuration(left)),
+            None => "estimating time left".to_string(),
+        };
+        self.bar.set_message(message);
+    }
+
+    pub fn finish(self) {
+        self.bar
+            .finish_with_message(format!("done in {}", HumanDuration(self.started.elapsed())));
+    }
+}
+
+// Per-step flags of screening `owner` against `evaluator` under a fresh key of `preset`,
+// all three phases on this machine with a bar each, and the homomorphic work spent.
+// `profile` calibrates the screening ETA.
+pub fn screen_local(
+    owner: &SatelliteData,
+    evaluator: &SatelliteData,
+    preset: ParameterPreset,
+    config: &ScreeningConfig,
+    batch_steps: usize,
+    profile: Option<&PerformanceProfile>,
+) -> Result<(Vec<bool>, OpCounter), Box<dyn std::error::Error>> {
+    let steps = owner.x.len();
+    let context = FheContext::generate(preset.config())?;
+
+    let mut bar = PhaseBar::new(Phase::Encrypt, steps, None);
+    let mut encrypted = EncryptedTrajectory {
+        x: Vec::with_capacity(steps),
+        y: Vec::with_capacity(steps),
+        z: Vec::with_capacity(steps),
+        epochs: (0..steps as u64).collect(),
+        first_index: 0,
+        frame: owner.frame,
+        units: owner.units,
+    };
+    for start in (0..steps).step_by(ENCRYPT_CHUNK) {
+        let end = (start + ENCRYPT_CHUNK).min(steps);
+        let chunk = context.encrypt(&SatelliteData {
+            x: owner.x[start..end].to_vec(),
+            y: owner.y[start..end].to_vec(),
+            z: owner.z[start..end].to_vec(),
+            frame: owner.frame,
+            units: owner.units,
+        })?;
+        encrypted.x.extend(chunk.x);
+        encrypted.y.extend(chunk.y);
+        encrypted.z.extend(chunk.z);
+        bar.advance(end - start);
+    }
+    bar.finish();
+
+    let estimate = profile.and_then(|profile| profile.estimate(config.kernel, steps));
+    let mut bar = PhaseBar::new(Phase::Screen, steps, estimate);
+    let mut batches: Vec<ResultBatch> = Vec::new();
+    let ops = context.evaluate_with(|| {
+        screen_streaming(
+            &encrypted,
+            evaluator,
+            config,
+            batch_steps,
+            &StepOrder::Chronological,
+            |batch| {
+                bar.advance(batch.len());
+                batches.push(batch);
+                Ok(())
+            },
+        )
+    })?;
+    bar.finish();
+
+    let mut bar = PhaseBar::new(Phase::Decrypt, steps, None);
+    let mut flags = vec![false; steps];
+    for batch in &batches {
+        let decrypted: Vec<bool> = context.decrypt(&batch.flags)?;
+        flags[batch.first_index..batch.end_index()].copy_from_slice(&decrypted);
+        bar.advance(batch.len());
+    }
+    bar.finish();
+    Ok((flags, ops))
+}
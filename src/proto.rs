@@ -0,0 +1,499 @@
+// Protobuf types for the artifacts in `proto/sat_fhe.proto`, with conversions to and from
+// the internal structs. Written out with prost's derives rather than generated at build
+// time, so building the crate needs no `protoc`; field tags must match the schema.
+
+use tfhe::FheBool;
+
+use crate::common::{safe_deserialize_items, safe_serialize_item};
+use crate::kernel::KernelChoice;
+use crate::mask::StepMask;
+use crate::object_id::ObjectId;
+use crate::redact::{EvaluationKey, fingerprint};
+use crate::regime;
+use crate::reveal;
+use crate::time_system;
+use crate::trajectory::{EncryptedTrajectory, SerializedTrajectory};
+use crate::{frame, protocol, units};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Frame {
+    Eci = 0,
+    Ecef = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Units {
+    Meters = 0,
+    Kilometers = 1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum TimeSystem {
+    Utc = 0,
+    Tai = 1,
+    Gps = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum MessageKind {
+    Hello = 0,
+    ServerKey = 1,
+    EncryptedTrajectory = 2,
+    Results = 3,
+    ArtifactRef = 4,
+    ResultBatch = 5,
+    Encoding = 6,
+    CellFilter = 7,
+    Resolution = 8,
+    Commitment = 9,
+    Opening = 10,
+    Resend = 11,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum RevealPolicy {
+    PerIndex = 0,
+    AnyFlag = 1,
+    Count = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum KernelType {
+    ExactMatch = 0,
+    BoxThreshold = 1,
+    SquaredDistanceThreshold = 2,
+    AltitudeBand = 3,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Envelope {
+    #[prost(bytes = "vec", tag = "1")]
+    pub nonce: Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub seq: u64,
+    #[prost(enumeration = "MessageKind", tag = "3")]
+    pub kind: i32,
+    #[prost(bytes = "vec", tag = "4")]
+    pub payload: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub checksum: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct Kernel {
+    #[prost(enumeration = "KernelType", tag = "1")]
+    pub r#type: i32,
+    #[prost(uint32, tag = "2")]
+    pub parameter: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct AltitudeBand {
+    #[prost(double, tag = "1")]
+    pub min_km: f64,
+    #[prost(double, tag = "2")]
+    pub max_km: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct StepRange {
+    #[prost(uint64, tag = "1")]
+    pub start: u64,
+    #[prost(uint64, tag = "2")]
+    pub end: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ScreeningRequest {
+    #[prost(message, optional, tag = "1")]
+    pub altitude_band: Option<AltitudeBand>,
+    #[prost(string, optional, tag = "2")]
+    pub server_key_fingerprint: Option<String>,
+    #[prost(enumeration = "Frame", optional, tag = "3")]
+    pub frame: Option<i32>,
+    #[prost(enumeration = "Units", optional, tag = "4")]
+    pub units: Option<i32>,
+    #[prost(enumeration = "RevealPolicy", optional, tag = "5")]
+    pub reveal: Option<i32>,
+    #[prost(uint64, optional, tag = "6")]
+    pub padded_len: Option<u64>,
+    #[prost(message, optional, tag = "7")]
+    pub kernel: Option<Kernel>,
+    #[prost(message, repeated, tag = "8")]
+    pub mask: Vec<StepRange>,
+    #[prost(string, optional, tag = "9")]
+    pub object_id: Option<String>,
+    #[prost(enumeration = "TimeSystem", optional, tag = "10")]
+    pub time_system: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ServerKey {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: Vec<u8>,
+    #[prost(string, tag = "2")]
+    pub fingerprint: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TrajectoryBundle {
+    #[prost(enumeration = "Frame", tag = "1")]
+    pub frame: i32,
+    #[prost(enumeration = "Units", tag = "2")]
+    pub units: i32,
+    #[prost(uint64, tag = "3")]
+    pub first_index: u64,
+    #[prost(uint64, repeated, tag = "4")]
+    pub epochs: Vec<u64>,
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    pub x: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "6")]
+    pub y: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "7")]
+    pub z: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ScreeningResults {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub flags: Vec<Vec<u8>>,
+}
+
+impl From<frame::Frame> for Frame {
+    fn from(value: frame::Frame) -> Self {
+        match value {
+            frame::Frame::Eci => Frame::Eci,
+            frame::Frame::Ecef => Frame::Ecef,
+        }
+    }
+}
+
+impl From<Frame> for frame::Frame {
+    fn from(value: Frame) -> Self {
+        match value {
+            Frame::Eci => frame::Frame::Eci,
+            Frame::Ecef => frame::Frame::Ecef,
+        }
+    }
+}
+
+impl From<units::Units> for Units {
+    fn from(value: units::Units) -> Self {
+        match value {
+            units::Units::Meters => Units::Meters,
+            units::Units::Kilometers => Units::Kilometers,
+        }
+    }
+}
+
+impl From<Units> for units::Units {
+    fn from(value: Units) -> Self {
+        match value {
+            Units::Meters => units::Units::Meters,
+            Units::Kilometers => units::Units::Kilometers,
+        }
+    }
+}
+
+impl From<time_system::TimeSystem> for TimeSystem {
+    fn from(value: time_system::TimeSystem) -> Self {
+        match value {
+            time_system::TimeSystem::Utc => TimeSystem::Utc,
+            time_system::TimeSystem::Tai => TimeSystem::Tai,
+            time_system::TimeSystem::Gps => TimeSystem::Gps,
+        }
+    }
+}
+
+impl From<TimeSystem> for time_system::TimeSystem {
+    fn from(value: TimeSystem) -> Self {
+        match value {
+            TimeSystem::Utc => time_system::TimeSystem::Utc,
+            TimeSystem::Tai => time_system::TimeSystem::Tai,
+            TimeSystem::Gps => time_system::TimeSystem::Gps,
+        }
+    }
+}
+
+impl From<reveal::RevealPolicy> for RevealPolicy {
+    fn from(value: reveal::RevealPolicy) -> Self {
+        match value {
+            reveal::RevealPolicy::PerIndex => RevealPolicy::PerIndex,
+            reveal::RevealPolicy::AnyFlag => RevealPolicy::AnyFlag,
+            reveal::RevealPolicy::Count => RevealPolicy::Count,
+        }
+    }
+}
+
+impl From<RevealPolicy> for reveal::RevealPolicy {
+    fn from(value: RevealPolicy) -> Self {
+        match value {
+            RevealPolicy::PerIndex => reveal::RevealPolicy::PerIndex,
+            RevealPolicy::AnyFlag => reveal::RevealPolicy::AnyFlag,
+            RevealPolicy::Count => reveal::RevealPolicy::Count,
+        }
+    }
+}
+
+impl From<KernelChoice> for Kernel {
+    fn from(value: KernelChoice) -> Self {
+        let (kind, parameter) = match value {
+            KernelChoice::ExactMatch => (KernelType::ExactMatch, 0),
+            KernelChoice::BoxThreshold { half_width } => (KernelType::BoxThreshold, half_width),
+            KernelChoice::SquaredDistanceThreshold { threshold } => {
+                (KernelType::SquaredDistanceThreshold, threshold)
+            }
+            KernelChoice::AltitudeBand { tolerance } => (KernelType::AltitudeBand, tolerance),
+        };
+        Self {
+            r#type: kind as i32,
+            parameter,
+        }
+    }
+}
+
+impl TryFrom<Kernel> for KernelChoice {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: Kernel) -> Result<Self, Self::Error> {
+        let kind = KernelType::try_from(value.r#type)
+            .map_err(|_| format!("unknown kernel type {}", value.r#type))?;
+        Ok(match kind {
+            KernelType::ExactMatch => KernelChoice::ExactMatch,
+            KernelType::BoxThreshold => KernelChoice::BoxThreshold {
+                half_width: value.parameter,
+            },
+            KernelType::SquaredDistanceThreshold => KernelChoice::SquaredDistanceThreshold {
+                threshold: value.parameter,
+            },
+            KernelType::AltitudeBand => KernelChoice::AltitudeBand {
+                tolerance: value.parameter,
+            },
+        })
+    }
+}
+
+impl From<protocol::MessageKind> for MessageKind {
+    fn from(value: protocol::MessageKind) -> Self {
+        match value {
+            protocol::MessageKind::Hello => MessageKind::Hello,
+            protocol::MessageKind::ServerKey => MessageKind::ServerKey,
+            protocol::MessageKind::EncryptedTrajectory => MessageKind::EncryptedTrajectory,
+            protocol::MessageKind::Results => MessageKind::Results,
+            protocol::MessageKind::ArtifactRef => MessageKind::ArtifactRef,
+            protocol::MessageKind::ResultBatch => MessageKind::ResultBatch,
+            protocol::MessageKind::Encoding => MessageKind::Encoding,
+            protocol::MessageKind::CellFilter => MessageKind::CellFilter,
+            protocol::MessageKind::Resolution => MessageKind::Resolution,
+            protocol::MessageKind::Commitment => MessageKind::Commitment,
+            protocol::MessageKind::Opening => MessageKind::Opening,
+            protocol::MessageKind::Resend => MessageKind::Resend,
+        }
+    }
+}
+
+impl From<MessageKind> for protocol::MessageKind {
+    fn from(value: MessageKind) -> Self {
+        match value {
+            MessageKind::Hello => protocol::MessageKind::Hello,
+            MessageKind::ServerKey => protocol::MessageKind::ServerKey,
+            MessageKind::EncryptedTrajectory => protocol::MessageKind::EncryptedTrajectory,
+            MessageKind::Results => protocol::MessageKind::Results,
+            MessageKind::ArtifactRef => protocol::MessageKind::ArtifactRef,
+            MessageKind::ResultBatch => protocol::MessageKind::ResultBatch,
+            MessageKind::Encoding => protocol::MessageKind::Encoding,
+            MessageKind::CellFilter => protocol::MessageKind::CellFilter,
+            MessageKind::Resolution => protocol::MessageKind::Resolution,
+            MessageKind::Commitment => protocol::MessageKind::Commitment,
+            MessageKind::Opening => protocol::MessageKind::Opening,
+            MessageKind::Resend => protocol::MessageKind::Resend,
+        }
+    }
+}
+
+// Open enums arrive as plain integers; unknown values are errors.
+fn frame_from_i32(value: i32) -> Result<frame::Frame, Box<dyn std::error::Error>> {
+    Ok(Frame::try_from(value)
+        .map_err(|_| format!("unknown frame {}", value))?
+        .into())
+}
+
+fn units_from_i32(value: i32) -> Result<units::Units, Box<dyn std::error::Error>> {
+    Ok(Units::try_from(value)
+        .map_err(|_| format!("unknown units {}", value))?
+        .into())
+}
+
+fn time_system_from_i32(value: i32) -> Result<time_system::TimeSystem, Box<dyn std::error::Error>> {
+    Ok(TimeSystem::try_from(value)
+        .map_err(|_| format!("unknown time system {}", value))?
+        .into())
+}
+
+fn reveal_from_i32(value: i32) -> Result<reveal::RevealPolicy, Box<dyn std::error::Error>> {
+    Ok(RevealPolicy::try_from(value)
+        .map_err(|_| format!("unknown reveal policy {}", value))?
+        .into())
+}
+
+impl From<&protocol::Envelope> for Envelope {
+    fn from(value: &protocol::Envelope) -> Self {
+        Self {
+            nonce: value.nonce.to_vec(),
+            seq: value.seq,
+            kind: MessageKind::from(value.kind) as i32,
+            payload: value.payload.clone(),
+            checksum: value.checksum.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Envelope> for protocol::Envelope {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: Envelope) -> Result<Self, Self::Error> {
+        Ok(Self {
+            nonce: value
+                .nonce
+                .try_into()
+                .map_err(|_| "session nonce must be 16 bytes")?,
+            seq: value.seq,
+            kind: MessageKind::try_from(value.kind)
+                .map_err(|_| format!("unknown message kind {}", value.kind))?
+                .into(),
+            payload: value.payload,
+            checksum: value
+                .checksum
+                .try_into()
+                .map_err(|_| "envelope checksum must be 32 bytes")?,
+        })
+    }
+}
+
+impl From<&protocol::SessionMetadata> for ScreeningRequest {
+    fn from(value: &protocol::SessionMetadata) -> Self {
+        Self {
+            altitude_band: value.altitude_band.map(|band| AltitudeBand {
+                min_km: band.min_km(),
+                max_km: band.max_km(),
+            }),
+            server_key_fingerprint: value.server_key_fingerprint.clone(),
+            frame: value.frame.map(|f| Frame::from(f) as i32),
+            units: value.units.map(|u| Units::from(u) as i32),
+            reveal: value.reveal.map(|r| RevealPolicy::from(r) as i32),
+            padded_len: value.padded_len.map(|len| len as u64),
+            kernel: value.kernel.map(Kernel::from),
+            mask: value
+                .mask
+                .iter()
+                .flat_map(StepMask::ranges)
+                .map(|r| StepRange {
+                    start: r.start as u64,
+                    end: r.end as u64,
+                })
+                .collect(),
+            object_id: value.object_id.as_ref().map(ObjectId::to_string),
+            time_system: value.time_system.map(|t| TimeSystem::from(t) as i32),
+        }
+    }
+}
+
+impl TryFrom<ScreeningRequest> for protocol::SessionMetadata {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(value: ScreeningRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            altitude_band: value
+                .altitude_band
+                .map(|band| regime::AltitudeBand::new(band.min_km, band.max_km))
+                .transpose()?,
+            server_key_fingerprint: value.server_key_fingerprint,
+            frame: value.frame.map(frame_from_i32).transpose()?,
+            units: value.units.map(units_from_i32).transpose()?,
+            reveal: value.reveal.map(reveal_from_i32).transpose()?,
+            padded_len: value.padded_len.map(usize::try_from).transpose()?,
+            kernel: value.kernel.map(KernelChoice::try_from).transpose()?,
+            mask: if value.mask.is_empty() {
+                None
+            } else {
+                let ranges = value
+                    .mask
+                    .into_iter()
+                    .map(|r| Ok(usize::try_from(r.start)?..usize::try_from(r.end)?))
+                    .collect::<Result<Vec<_>, std::num::TryFromIntError>>()?;
+                Some(StepMask::new(ranges))
+            },
+            object_id: value.object_id.map(|id| id.parse()).transpose()?,
+            time_system: value.time_system.map(time_system_from_i32).transpose()?,
+        })
+    }
+}
+
+impl ServerKey {
+    pub fn from_key(key: &EvaluationKey) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            key: bincode::serialize(&**key)?,
+            fingerprint: key.fingerprint().to_string(),
+        })
+    }
+
+    // Checks the fingerprint against the key bytes before deserializing.
+    pub fn to_key(&self) -> Result<EvaluationKey, Box<dyn std::error::Error>> {
+        if fingerprint(&self.key) != self.fingerprint {
+            return Err("server key doesn't match its fingerprint".into());
+        }
+        EvaluationKey::new(bincode::deserialize(&self.key)?)
+    }
+}
+
+impl TrajectoryBundle {
+    pub fn from_trajectory(
+        trajectory: &EncryptedTrajectory,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let serialized = trajectory.to_serialized()?;
+        Ok(Self {
+            frame: Frame::from(serialized.frame) as i32,
+            units: Units::from(serialized.units) as i32,
+            first_index: serialized.first_index as u64,
+            epochs: serialized.epochs,
+            x: serialized.x,
+            y: serialized.y,
+            z: serialized.z,
+        })
+    }
+
+    pub fn to_trajectory(&self) -> Result<EncryptedTrajectory, Box<dyn std::error::Error>> {
+        let serialized = SerializedTrajectory {
+            frame: frame_from_i32(self.frame)?,
+            units: units_from_i32(self.units)?,
+            first_index: usize::try_from(self.first_index)?,
+            epochs: self.epochs.clone(),
+            x: self.x.clone(),
+            y: self.y.clone(),
+            z: self.z.clone(),
+        };
+        serialized.validate()?;
+        EncryptedTrajectory::from_serialized(serialized)
+    }
+}
+
+impl ScreeningResults {
+    pub fn from_results(results: &[FheBool]) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            flags: results
+                .iter()
+                .map(safe_serialize_item)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn to_results(&self) -> Result<Vec<FheBool>, Box<dyn std::error::Error>> {
+        safe_deserialize_items(&self.flags)
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::frame::Frame;
+use crate::kernel::KernelChoice;
+use crate::mask::StepMask;
+use crate::migrate::{self, ArtifactKind};
+use crate::object_id::ObjectId;
+use crate::regime::AltitudeBand;
+use crate::reveal::RevealPolicy;
+use crate::time_system::TimeSystem;
+use crate::units::Units;
+
+// Random per-session value chosen by the party opening the session. Every envelope
+// carries it, so messages captured from one screening can't be replayed into another.
+pub type SessionNonce = [u8; 16];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    // First message of a session, carrying the opener's `SessionMetadata`.
+    Hello,
+    ServerKey,
+    EncryptedTrajectory,
+    Results,
+    // Reference to an artifact in an object store (`transport::ArtifactPointer`).
+    ArtifactRef,
+    // Flags of a few steps sent while screening is still running (`stream::ResultBatch`).
+    ResultBatch,
+    // How the sender quantized its trajectory (`negotiation::EncodingParams`).
+    Encoding,
+    // Salted cells the sender's trajectory occupies (`prescreen::CellFilter`).
+    CellFilter,
+    // Resolution the evaluator can afford (`grid::ResolutionRequest`).
+    Resolution,
+    // What the sender's trajectory will be (`commitment::TrajectoryCommitment`).
+    Commitment,
+    // The salts of the sender's commitment (`commitment::CommitmentOpening`).
+    Opening,
+    // Results the sender couldn't read, to be sent again another way
+    // (`fallback::ResendRequest`).
+    Resend,
+}
+
+// Framing for every message exchanged between the two parties. `payload` holds the
+// serialized artifact (server key, encrypted trajectory, result ciphertexts).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub nonce: SessionNonce,
+    pub seq: u64,
+    pub kind: MessageKind,
+    pub payload: Vec<u8>,
+    // Integrity checksum of the fields above, chained to the session (see `Session::send`).
+    // Catches corruption and misplaced payloads, not tampering. All zeroes in envelopes
+    // written before format version 4.
+    pub checksum: [u8; 32],
+}
+
+impl Envelope {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        migrate::encode(ArtifactKind::Envelope, self)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        migrate::decode(ArtifactKind::Envelope, data)
+    }
+}
+
+// Parameters the session opener declares up front, sent in the `Hello` message.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SessionMetadata {
+    // Altitude band the owner's satellite occupies during the screening window. Lets the
+    // evaluator drop catalog objects that can never reach it (see `regime::prefilter`).
+    pub altitude_band: Option<AltitudeBand>,
+    // Fingerprint (`redact::fingerprint`) of the server key the owner is about to send,
+    // so the evaluator can check it received the right one.
+    pub server_key_fingerprint: Option<String>,
+    // Reference frame of the owner's trajectory.
+    pub frame: Option<Frame>,
+    // Units of the owner's encoded coordinates.
+    pub units: Option<Units>,
+    // What the owner may learn from the results; per-step flags if unset.
+    pub reveal: Option<RevealPolicy>,
+    // Steps the owner's trajectory is padded to with decoys (see `padding`); the evaluator
+    // extends its plaintext to the same length.
+    pub padded_len: Option<usize>,
+    // Per-step comparison the evaluator should run; exact match if unset.
+    pub kernel: Option<KernelChoice>,
+    // Steps the evaluator skips, e.g. maneuver windows; results then only cover the
+    // others (see `mask`).
+    pub mask: Option<StepMask>,
+    // The owner's object being screened, possibly blinded (see `object_id`).
+    pub object_id: Option<ObjectId>,
+    // Time system of the owner's epochs (see `time_system`).
+    pub time_system: Option<TimeSystem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    // Envelope belongs to a different session (or a replay from an earlier one).
+    SessionMismatch,
+    // The envelope's checksum doesn't match its contents: it was corrupted, or its payload
+    // was stamped with another message's nonce or sequence number.
+    ChecksumMismatch,
+    // Sequence number is not the next one expected: a replayed, dropped or reordered message.
+    UnexpectedSequence {
+        expected: u64,
+        found: u64,
+    },
+    // A message of another kind arrived where `expected` was required.
+    UnexpectedMessage {
+        expected: MessageKind,
+        found: MessageKind,
+    },
+    // The two trajectories are in different reference frames and neither was converted.
+    FrameMismatch {
+        owner: Frame,
+        evaluator: Frame,
+    },
+    // The two parties' epochs are in different time systems and neither was converted.
+    TimeSystemMismatch {
+        owner: TimeSystem,
+        evaluator: TimeSystem,
+    },
+    // Two encrypted trajectories use different units, which can't be rescaled.
+    UnitsMismatch {
+        owner: Units,
+        evaluator: Units,
+    },
+    // The parties quantized their trajectories differently (see `negotiation`).
+    EncodingMismatch {
+        parameter: &'static str,
+        ours: String,
+        theirs: String,
+    },
+    // A commitment was sent or accepted after artifacts of the other side had been
+    // exchanged, so it doesn't bind anything.
+    LateCommitment,
+    // The peer opened a commitment that doesn't match what it sent, or never committed.
+    CommitmentMismatch {
+        artifact: &'static str,
+    },
+    // The screening asks for more than the owner's profile for this counterpart allows
+    // (see `policy`).
+    PolicyViolation {
+        rule: &'static str,
+        requested: String,
+        allowed: String,
+    },
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::SessionMismatch => write!(f, "message belongs to a different session"),
+            ProtocolError::ChecksumMismatch => {
+                write!(f, "message checksum doesn't match its contents")
+            }
+            ProtocolError::UnexpectedSequence { expected, found } => write!(
+                f,
+                "unexpected sequence number: expected {}, found {}",
+                expected, found
+            ),
+            ProtocolError::UnexpectedMessage { expected, found } => {
+                write!(f, "expected a {:?} message, got {:?}", expected, found)
+            }
+            ProtocolError::FrameMismatch { owner, evaluator } => write!(
+                f,
+                "reference frame mismatch: owner uses {:?}, evaluator uses {:?}",
+                owner, evaluator
+            ),
+            ProtocolError::TimeSystemMismatch { owner, evaluator } => write!(
+                f,
+                "time system mismatch: owner's epochs are {:?}, evaluator's are {:?}",
+                owner, evaluator
+            ),
+            ProtocolError::UnitsMismatch { owner, evaluator } => write!(
+                f,
+                "units mismatch: owner uses {:?}, evaluator uses {:?}",
+                owner, evaluator
+            ),
+            ProtocolError::EncodingMismatch {
+                parameter,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "{} mismatch: we encode with {}, the peer with {}",
+                parameter, ours, theirs
+            ),
+            ProtocolError::LateCommitment => {
+                write!(
+                    f,
+                    "commitment came after trajectory artifacts were exchanged"
+                )
+            }
+            ProtocolError::CommitmentMismatch { artifact } => {
+                write!(f, "the peer's {} don't match its commitment", artifact)
+            }
+            ProtocolError::PolicyViolation {
+                rule,
+                requested,
+                allowed,
+            } => write!(
+                f,
+                "policy forbids {} {}; the profile allows {}",
+                rule, requested, allowed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
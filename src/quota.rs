@@ -0,0 +1,126 @@
+// Per-client limits for the screening daemon.
+//
+// FHE evaluation costs seconds per step, so a single client could otherwise occupy the
+// daemon for days. Clients are identified by their IP address; all limits are optional.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaConfig {
+    // Longest encrypted trajectory accepted, in steps.
+    pub max_trajectory_steps: Option<usize>,
+    // Jobs a client may have open (awaiting uploads, queued or running) at once.
+    pub max_concurrent_jobs: Option<usize>,
+    // Steps a client may submit per UTC day.
+    pub daily_step_budget: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum QuotaError {
+    TrajectoryTooLong {
+        steps: usize,
+        max: usize,
+    },
+    TooManyJobs {
+        active: usize,
+        max: usize,
+    },
+    DailyBudgetExceeded {
+        used: u64,
+        requested: u64,
+        budget: u64,
+    },
+}
+
+impl fmt::Display for QuotaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaError::TrajectoryTooLong { steps, max } => write!(
+                f,
+                "trajectory has {} steps, this server accepts at most {}",
+                steps, max
+            ),
+            QuotaError::TooManyJobs { active, max } => write!(
+                f,
+                "{} jobs already open, at most {} allowed per client",
+                active, max
+            ),
+            QuotaError::DailyBudgetExceeded {
+                used,
+                requested,
+                budget,
+            } => write!(
+                f,
+                "daily step budget exceeded: {} of {} used today, {} more requested",
+                used, budget, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaError {}
+
+pub struct QuotaTracker {
+    config: QuotaConfig,
+    // Per client: UTC day number and steps charged on it.
+    usage: Mutex<HashMap<IpAddr, (u64, u64)>>,
+}
+
+impl QuotaTracker {
+    pub fn new(config: QuotaConfig) -> Self {
+        Self {
+            config,
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // `active` is the number of jobs the client already has open.
+    pub fn check_jobs(&self, active: usize) -> Result<(), QuotaError> {
+        match self.config.max_concurrent_jobs {
+            Some(max) if active >= max => Err(QuotaError::TooManyJobs { active, max }),
+            _ => Ok(()),
+        }
+    }
+
+    // Checks a trajectory of `steps` steps against the length limit only.
+    pub fn check_steps(&self, steps: usize) -> Result<(), QuotaError> {
+        match self.config.max_trajectory_steps {
+            Some(max) if steps > max => Err(QuotaError::TrajectoryTooLong { steps, max }),
+            _ => Ok(()),
+        }
+    }
+
+    // Checks a trajectory of `steps` steps against the length limit and the client's
+    // budget for the day of `now_unix_s`, and charges it if both pass.
+    pub fn charge_steps(
+        &self,
+        client: IpAddr,
+        steps: usize,
+        now_unix_s: u64,
+    ) -> Result<(), QuotaError> {
+        self.check_steps(steps)?;
+        let today = now_unix_s / 86_400;
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage.entry(client).or_insert((today, 0));
+        if entry.0 != today {
+            *entry = (today, 0);
+        }
+        if let Some(budget) = self.config.daily_step_budget
+            && entry.1 + steps as u64 > budget
+        {
+            return Err(QuotaError::DailyBudgetExceeded {
+                used: entry.1,
+                requested: steps as u64,
+                budget,
+            });
+        }
+        entry.1 += steps as u64;
+        Ok(())
+    }
+}
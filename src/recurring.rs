@@ -0,0 +1,43 @@
+// Cadence of a recurring screening.
+//
+// Owners screening the same trajectory against a counterpart whose ephemerides are
+// refreshed every few hours don't want to re-upload a server key and trajectory each time.
+// A daemon job can instead be made recurring (`Request::Recur`): the daemon keeps its
+// artifacts and, at most once per `every`, screens them again if the counterpart
+// ephemerides changed since the last run (see `serve`). The owner fetches each run's
+// results like the first ones and decrypts them into events, which fires its alert sinks
+// (see `OwnerParty::decrypt_events`).
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub every: Duration,
+    // No run starts before this.
+    pub next_run: Instant,
+    // Identifies the counterpart ephemerides of the last run, e.g. their SHA-256.
+    pub screened: String,
+}
+
+impl Recurrence {
+    // A recurrence of a job that has just been screened against the ephemerides
+    // `screened`.
+    pub fn new(every: Duration, now: Instant, screened: String) -> Self {
+        Self {
+            every,
+            next_run: now + every,
+            screened,
+        }
+    }
+
+    // Whether a run should start at `now`, the counterpart ephemerides being `current`.
+    pub fn due(&self, now: Instant, current: &str) -> bool {
+        now >= self.next_run && self.screened != current
+    }
+
+    // Records a run started at `now` against `current`.
+    pub fn started(&mut self, now: Instant, current: &str) {
+        self.next_run = now + self.every;
+        self.screened = current.to_string();
+    }
+}
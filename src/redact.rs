@@ -0,0 +1,180 @@
+use std::fmt;
+use std::ops::Deref;
+
+use sha2::{Digest, Sha256};
+use tfhe::{ClientKey, ServerKey};
+
+use crate::common::SatelliteData;
+
+// Short, stable identifier for a blob of bytes: the first 8 bytes of its SHA-256, hex encoded.
+// Enough to tell two keys apart in a log line without revealing anything about them.
+pub fn fingerprint(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Secret (client) key. Debug/Display only show a fingerprint, never key material.
+pub struct SecretKey {
+    key: ClientKey,
+    fingerprint: String,
+}
+
+impl SecretKey {
+    pub fn new(key: ClientKey) -> Result<Self, Box<dyn std::error::Error>> {
+        let fingerprint = fingerprint(&bincode::serialize(&key)?);
+        Ok(Self { key, fingerprint })
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    pub fn into_inner(self) -> ClientKey {
+        self.key
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = ClientKey;
+
+    fn deref(&self) -> &ClientKey {
+        &self.key
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(fp={})", self.fingerprint)
+    }
+}
+
+impl fmt::Display for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+// Evaluation (server) key. Not secret, but hundreds of MB, so it is still only ever
+// printed as a fingerprint plus its serialized size. The fingerprint is computed once
+// here rather than on every log call.
+pub struct EvaluationKey {
+    key: ServerKey,
+    fingerprint: String,
+    size: usize,
+}
+
+impl EvaluationKey {
+    pub fn new(key: ServerKey) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = bincode::serialize(&key)?;
+        Ok(Self {
+            key,
+            fingerprint: fingerprint(&bytes),
+            size: bytes.len(),
+        })
+    }
+
+    // For keys whose fingerprint and size are already known, e.g. recorded in a split key
+    // file (see `splitkey`), which saves serializing the whole key again.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn with_fingerprint(key: ServerKey, fingerprint: String, size: usize) -> Self {
+        Self {
+            key,
+            fingerprint,
+            size,
+        }
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    // Serialized size in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn into_inner(self) -> ServerKey {
+        self.key
+    }
+}
+
+impl Deref for EvaluationKey {
+    type Target = ServerKey;
+
+    fn deref(&self) -> &ServerKey {
+        &self.key
+    }
+}
+
+impl fmt::Debug for EvaluationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "EvaluationKey(fp={}, {} bytes)",
+            self.fingerprint, self.size
+        )
+    }
+}
+
+impl fmt::Display for EvaluationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+// Plaintext trajectory held by its owner. Debug/Display print the number of time steps
+// and a fingerprint of the coordinates, never the coordinates themselves.
+pub struct PrivateTrajectory(SatelliteData);
+
+impl PrivateTrajectory {
+    pub fn new(data: SatelliteData) -> Self {
+        Self(data)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn fingerprint(&self) -> String {
+        let mut bytes = Vec::with_capacity(self.len() * 12);
+        for axis in [&self.0.x, &self.0.y, &self.0.z] {
+            for v in axis.iter() {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        fingerprint(&bytes)
+    }
+
+    pub fn into_inner(self) -> SatelliteData {
+        self.0
+    }
+}
+
+impl Deref for PrivateTrajectory {
+    type Target = SatelliteData;
+
+    fn deref(&self) -> &SatelliteData {
+        &self.0
+    }
+}
+
+impl fmt::Debug for PrivateTrajectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PrivateTrajectory({} steps, fp={})",
+            self.len(),
+            self.fingerprint()
+        )
+    }
+}
+
+impl fmt::Display for PrivateTrajectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
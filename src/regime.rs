@@ -0,0 +1,139 @@
+// Plaintext orbital-regime pre-filtering.
+//
+// Two objects can only collide if their altitude ranges overlap. The owner declares the
+// altitude band its satellite occupies during the screening window (sent as session
+// metadata, see `SessionMetadata::altitude_band`), and the evaluator drops every catalog
+// object whose perigee..apogee range can never reach it before doing any FHE work.
+//
+// The same idea also works per time step and encrypted: `radial_profile` turns a
+// trajectory into one altitude bucket per step, which the `AltitudeBand` kernel compares
+// far more cheaply than a full 3-D screening, as a first pass before it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::SatelliteData;
+use crate::units::unbias;
+
+// Always finite with `min_km <= max_km`: every constructor and deserialization checks it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "RawAltitudeBand")]
+pub struct AltitudeBand {
+    min_km: f64,
+    max_km: f64,
+}
+
+#[derive(Deserialize)]
+struct RawAltitudeBand {
+    min_km: f64,
+    max_km: f64,
+}
+
+impl TryFrom<RawAltitudeBand> for AltitudeBand {
+    type Error = String;
+
+    fn try_from(raw: RawAltitudeBand) -> Result<Self, Self::Error> {
+        AltitudeBand::new(raw.min_km, raw.max_km).map_err(|e| e.to_string())
+    }
+}
+
+impl AltitudeBand {
+    pub fn new(min_km: f64, max_km: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        if !(min_km.is_finite() && max_km.is_finite()) || min_km > max_km {
+            return Err(format!("invalid altitude band {}..{} km", min_km, max_km).into());
+        }
+        Ok(Self { min_km, max_km })
+    }
+
+    // Band swept by an orbit between perigee and apogee, in either order.
+    pub fn of_orbit(perigee_km: f64, apogee_km: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::new(perigee_km.min(apogee_km), perigee_km.max(apogee_km))
+    }
+
+    // Band grown by `margin_km` on both sides, to absorb propagation error.
+    pub fn widened(&self, margin_km: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::new(self.min_km - margin_km, self.max_km + margin_km)
+    }
+
+    pub fn min_km(&self) -> f64 {
+        self.min_km
+    }
+
+    pub fn max_km(&self) -> f64 {
+        self.max_km
+    }
+
+    pub fn overlaps(&self, other: &AltitudeBand) -> bool {
+        self.min_km <= other.max_km && other.min_km <= self.max_km
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitalRegime {
+    // Entirely below 2000 km.
+    Leo,
+    // Between LEO and the GEO belt.
+    Meo,
+    // Near-circular around 35786 km.
+    Geo,
+    // Everything crossing regimes (GTO, Molniya, ...).
+    Heo,
+}
+
+impl OrbitalRegime {
+    pub fn classify(band: &AltitudeBand) -> Self {
+        const LEO_MAX_KM: f64 = 2_000.0;
+        const GEO_KM: f64 = 35_786.0;
+        const GEO_TOLERANCE_KM: f64 = 500.0;
+
+        if band.max_km <= LEO_MAX_KM {
+            OrbitalRegime::Leo
+        } else if (band.min_km - GEO_KM).abs() <= GEO_TOLERANCE_KM
+            && (band.max_km - GEO_KM).abs() <= GEO_TOLERANCE_KM
+        {
+            OrbitalRegime::Geo
+        } else if band.min_km > LEO_MAX_KM && band.max_km < GEO_KM - GEO_TOLERANCE_KM {
+            OrbitalRegime::Meo
+        } else {
+            OrbitalRegime::Heo
+        }
+    }
+}
+
+// Objects whose orbit band (given by `band_of`) can reach `declared`, widened by
+// `margin_km`.
+pub fn prefilter<'a, T>(
+    declared: &AltitudeBand,
+    margin_km: f64,
+    objects: &'a [T],
+    band_of: impl Fn(&T) -> AltitudeBand,
+) -> Result<Vec<&'a T>, Box<dyn std::error::Error>> {
+    let band = declared.widened(margin_km)?;
+    Ok(objects
+        .iter()
+        .filter(|object| band_of(object).overlaps(&band))
+        .collect())
+}
+
+// Distance of every step of `data` from the frame origin, in buckets of `bucket` units of
+// `data.units`, carried on the x axis; y and z are zero. Both parties must use the same
+// bucket size. Objects at the same distance can still land in neighbouring buckets, so
+// compare with a tolerance of at least one bucket.
+pub fn radial_profile(data: &SatelliteData, bucket: u32) -> SatelliteData {
+    let bucket = bucket.max(1) as f64;
+    let offset = |v: u32| unbias(v) as f64;
+    let x = (0..data.x.len())
+        .map(|i| {
+            let r = offset(data.x[i])
+                .hypot(offset(data.y[i]))
+                .hypot(offset(data.z[i]));
+            (r / bucket) as u32
+        })
+        .collect();
+    SatelliteData {
+        x,
+        y: vec![0; data.x.len()],
+        z: vec![0; data.x.len()],
+        frame: data.frame,
+        units: data.units,
+    }
+}
@@ -0,0 +1,162 @@
+// Consolidated screening report across many pairwise sessions.
+//
+// An operator screening its fleet against several other operators runs one session per
+// (satellite, peer) pair and decrypts each into conjunction events. `GroupReport` merges
+// those outcomes into one list per owned satellite. Overlapping events against the same
+// peer (repeated or overlapping screening windows) are merged into one, and events are
+// ranked by their estimated time of closest approach (TCA), earliest first.
+//
+// Exact-match flags only say which steps were close, not when the objects were closest,
+// so the TCA is estimated as the middle of the event.
+//
+// Outcomes can also come from screening in the clear against the public catalog (see
+// `precheck`); their events are reported alongside, marked by `Source`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::events::ConjunctionEvent;
+
+// How an outcome was screened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    // An FHE session against another operator's private ephemeris.
+    #[default]
+    Encrypted,
+    // In the clear against a public catalog object.
+    Catalog,
+}
+
+// Decrypted events of one pairwise session.
+#[derive(Debug, Clone)]
+pub struct SessionOutcome {
+    // The owner's satellite that was screened.
+    pub satellite: String,
+    // The operator screened against.
+    pub peer: String,
+    pub events: Vec<ConjunctionEvent>,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportedEvent {
+    pub peer: String,
+    pub source: Source,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+    // Number of sessions that reported (part of) this event.
+    pub sessions: usize,
+}
+
+impl ReportedEvent {
+    pub fn tca_epoch(&self) -> u64 {
+        self.start_epoch + (self.end_epoch - self.start_epoch) / 2
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SatelliteReport {
+    pub satellite: String,
+    // Ranked by `tca_epoch`, earliest first.
+    pub events: Vec<ReportedEvent>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupReport {
+    // One entry per satellite with at least one event, in name order.
+    pub satellites: Vec<SatelliteReport>,
+}
+
+impl GroupReport {
+    pub fn aggregate(outcomes: &[SessionOutcome]) -> Self {
+        let mut by_pair: BTreeMap<(&str, &str, Source), Vec<ReportedEvent>> = BTreeMap::new();
+        for outcome in outcomes {
+            let events = by_pair
+                .entry((&outcome.satellite, &outcome.peer, outcome.source))
+                .or_default();
+            events.extend(outcome.events.iter().map(|event| ReportedEvent {
+                peer: outcome.peer.clone(),
+                source: outcome.source,
+                start_epoch: event.start_epoch,
+                end_epoch: event.end_epoch,
+                sessions: 1,
+            }));
+        }
+
+        let mut by_satellite: BTreeMap<&str, Vec<ReportedEvent>> = BTreeMap::new();
+        for ((satellite, _, _), events) in by_pair {
+            by_satellite
+                .entry(satellite)
+                .or_default()
+                .extend(merge_overlapping(events));
+        }
+
+        let satellites = by_satellite
+            .into_iter()
+            .map(|(satellite, mut events)| {
+                events.sort_by(|a, b| {
+                    (a.tca_epoch(), &a.peer, a.start_epoch).cmp(&(
+                        b.tca_epoch(),
+                        &b.peer,
+                        b.start_epoch,
+                    ))
+                });
+                SatelliteReport {
+                    satellite: satellite.to_string(),
+                    events,
+                }
+            })
+            .filter(|report| !report.events.is_empty())
+            .collect();
+        Self { satellites }
+    }
+
+    pub fn satellite(&self, name: &str) -> Option<&SatelliteReport> {
+        self.satellites
+            .iter()
+            .find(|report| report.satellite == name)
+    }
+}
+
+// Merges events of one (satellite, peer, source) triple whose epoch ranges overlap.
+fn merge_overlapping(mut events: Vec<ReportedEvent>) -> Vec<ReportedEvent> {
+    events.sort_by_key(|event| (event.start_epoch, event.end_epoch));
+    let mut merged: Vec<ReportedEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        match merged.last_mut() {
+            Some(last) if event.start_epoch <= last.end_epoch => {
+                last.end_epoch = last.end_epoch.max(event.end_epoch);
+                last.sessions += event.sessions;
+            }
+            _ => merged.push(event),
+        }
+    }
+    merged
+}
+
+impl fmt::Display for GroupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.satellites.is_empty() {
+            return write!(f, "no conjunctions");
+        }
+        for report in &self.satellites {
+            writeln!(f, "{}:", report.satellite)?;
+            for event in &report.events {
+                writeln!(
+                    f,
+                    "  TCA ~{} with {}{} (epochs {}..={}, {} session(s))",
+                    event.tca_epoch(),
+                    event.peer,
+                    match event.source {
+                        Source::Encrypted => "",
+                        Source::Catalog => " [public catalog]",
+                    },
+                    event.start_epoch,
+                    event.end_epoch,
+                    event.sessions
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
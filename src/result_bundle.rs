@@ -0,0 +1,127 @@
+// All per-step flags of a screening as one artifact.
+//
+// `screening::results_to_bytes` serializes every flag on its own, each with TFHE-rs's
+// header and length prefix, which for thousands of steps is a lot of framing and a lot
+// of `Vec<Vec<u8>>` handling for callers. A `ResultBundle` serializes the flags together
+// with the absolute index of the first one in one call, versioned by the artifact tag
+// (see `migrate`) instead of per item.
+//
+// Flags can also be bundled compressed (`ResultBundle::compressed`): modulus-switched
+// ciphertexts a fraction of the size. Compressing and decompressing both need the server
+// key installed, so the evaluator compresses inside `FheContext::evaluate_with`, and the
+// owner decompresses inside its own.
+//
+// Steps the evaluator couldn't evaluate (see `screening::screen_kernel_checked`) travel
+// with the flags as `failed`: their flags are placeholders, and the owner learns which
+// steps, and so which epochs, are unverified instead of reading them as clear.
+
+use serde::{Deserialize, Serialize};
+use tfhe::{CompressedFheBool, FheBool};
+
+use crate::common::serialization_limits;
+use crate::migrate::{self, ArtifactKind};
+
+#[derive(Serialize, Deserialize, Clone)]
+enum BundledFlags {
+    Plain(Vec<FheBool>),
+    Compressed(Vec<CompressedFheBool>),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ResultBundle {
+    // Absolute index of the step the first flag belongs to.
+    pub first_index: usize,
+    flags: BundledFlags,
+    // Absolute indices of steps whose flags are placeholders, ascending.
+    failed: Vec<usize>,
+}
+
+impl ResultBundle {
+    pub fn new(first_index: usize, flags: Vec<FheBool>) -> Self {
+        Self {
+            first_index,
+            flags: BundledFlags::Plain(flags),
+            failed: Vec::new(),
+        }
+    }
+
+    // Needs the server key installed.
+    pub fn compressed(first_index: usize, flags: &[FheBool]) -> Self {
+        Self {
+            first_index,
+            flags: BundledFlags::Compressed(flags.iter().map(FheBool::compress).collect()),
+            failed: Vec::new(),
+        }
+    }
+
+    // Marks the steps at absolute indices `failed` as unverified.
+    pub fn with_failed(mut self, mut failed: Vec<usize>) -> Self {
+        failed.sort_unstable();
+        failed.dedup();
+        self.failed = failed;
+        self
+    }
+
+    pub fn failed(&self) -> &[usize] {
+        &self.failed
+    }
+
+    pub fn is_verified(&self, index: usize) -> bool {
+        self.failed.binary_search(&index).is_err()
+    }
+
+    // Epochs of the unverified steps, where `epochs` holds one per flag of the bundle.
+    pub fn unverified_epochs(&self, epochs: &[u64]) -> Vec<u64> {
+        self.failed
+            .iter()
+            .filter_map(|&index| epochs.get(index.checked_sub(self.first_index)?).copied())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.flags {
+            BundledFlags::Plain(flags) => flags.len(),
+            BundledFlags::Compressed(flags) => flags.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(self.flags, BundledFlags::Compressed(_))
+    }
+
+    // The flags, decompressed if need be, which needs the server key installed.
+    pub fn into_flags(self) -> Vec<FheBool> {
+        match self.flags {
+            BundledFlags::Plain(flags) => flags,
+            BundledFlags::Compressed(flags) => flags.iter().map(|f| f.decompress()).collect(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let data = migrate::encode(ArtifactKind::ResultBundle, self)?;
+        check_size(&data)?;
+        Ok(data)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        check_size(data)?;
+        migrate::decode(ArtifactKind::ResultBundle, data)
+    }
+}
+
+fn check_size(data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let limit = serialization_limits().result_bundle;
+    if data.len() as u64 > limit {
+        return Err(format!(
+            "result bundle of {} bytes exceeds the limit of {}",
+            data.len(),
+            limit
+        )
+        .into());
+    }
+    Ok(())
+}
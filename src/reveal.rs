@@ -0,0 +1,266 @@
+// What the key owner learns from a screening, declared per session.
+//
+// With per-step flags the owner learns exactly when its satellite comes close to the
+// evaluator's. A session can instead declare that it only learns whether any step
+// matched, or how many did: the evaluator then aggregates the flags homomorphically and
+// sends back only the aggregate, so the per-step flags never leave it. The policy is part
+// of the `Hello` metadata and thereby of the session transcript (`Session::transcript`).
+//
+// "Did any step match" can be computed two ways (`Aggregation`): an OR tree costs one
+// bootstrap per flag, while casting the flags to integers and summing them lets TFHE
+// accumulate many flags in a block's carry space before propagating, and only then
+// compares the sum with zero. Summing wins on long screenings, ORs on short ones; by
+// default the cheaper one is picked from the flag count and the parameter set.
+
+use serde::{Deserialize, Serialize};
+use tfhe::prelude::*;
+use tfhe::{FheBool, FheUint16, FheUint32};
+
+use crate::common::{safe_deserialize_item, safe_serialize_item};
+use crate::context::FheContext;
+use crate::depth::OpCounter;
+use crate::migrate::{self, ArtifactKind};
+use crate::preset::ParameterPreset;
+use crate::screening::{results_from_bytes, results_to_bytes};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RevealPolicy {
+    // One flag per time step.
+    #[default]
+    PerIndex,
+    // A single flag: did any step match.
+    AnyFlag,
+    // The number of matching steps.
+    Count,
+}
+
+// How `RevealPolicy::AnyFlag` reduces the per-step flags.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregation {
+    // Whichever of the two `aggregation_bootstraps` estimates cheaper.
+    #[default]
+    Auto,
+    // Pairwise ORs.
+    OrTree,
+    // The flags summed as integers, then compared with zero.
+    Sum,
+}
+
+impl Aggregation {
+    // The strategy to reduce `count` flags with under `preset`; never `Auto`.
+    pub fn select(self, count: usize, preset: ParameterPreset) -> Aggregation {
+        match self {
+            Aggregation::Auto => {
+                let sum = aggregation_bootstraps(Aggregation::Sum, count, preset);
+                let or = aggregation_bootstraps(Aggregation::OrTree, count, preset);
+                if sum < or {
+                    Aggregation::Sum
+                } else {
+                    Aggregation::OrTree
+                }
+            }
+            strategy => strategy,
+        }
+    }
+}
+
+// Bits of the integer `count` flags are summed in.
+fn sum_bits(count: usize) -> u32 {
+    if count <= u16::MAX as usize { 16 } else { 32 }
+}
+
+// Rough number of programmable bootstraps reducing `count` flags with `strategy` costs.
+// An OR is one bootstrap. A sum adds up to `message * carry - 1` flags into a block for
+// free, then propagates carries through all blocks of the sum, and ends with one pass
+// over the blocks comparing with zero.
+pub fn aggregation_bootstraps(strategy: Aggregation, count: usize, preset: ParameterPreset) -> u64 {
+    match strategy.select(count, preset) {
+        Aggregation::Sum => {
+            let parameters = preset.parameters();
+            let message = parameters.message_modulus.0;
+            let capacity = (message * parameters.carry_modulus.0)
+                .saturating_sub(1)
+                .max(1);
+            let blocks = (sum_bits(count) / message.max(2).ilog2()) as u64;
+            (count as u64).div_ceil(capacity) * blocks + blocks
+        }
+        _ => count.saturating_sub(1) as u64,
+    }
+}
+
+// Encrypted screening result as released under a policy.
+pub enum RevealedResult {
+    PerIndex(Vec<FheBool>),
+    AnyFlag(FheBool),
+    Count(FheUint32),
+}
+
+// A released result plus the homomorphic work spent on screening and aggregation.
+pub struct RevealedOutput {
+    pub result: RevealedResult,
+    pub ops: OpCounter,
+}
+
+// Decrypted counterpart of `RevealedResult`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revealed {
+    PerIndex(Vec<bool>),
+    AnyFlag(bool),
+    Count(u32),
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) enum SerializedAggregate {
+    AnyFlag(Vec<u8>),
+    Count(Vec<u8>),
+}
+
+// Halves `items` pairwise until one is left, so the result is ceil(log2 n) ops deep.
+pub(crate) fn reduce_tree<T>(mut items: Vec<T>, op: impl Fn(&T, &T) -> T) -> Option<T> {
+    while items.len() > 1 {
+        let mut next = Vec::with_capacity(items.len().div_ceil(2));
+        let mut pairs = items.chunks_exact(2);
+        for pair in &mut pairs {
+            next.push(op(&pair[0], &pair[1]));
+        }
+        let odd = pairs.remainder().len() == 1;
+        if odd {
+            next.push(items.pop().expect("odd length"));
+        }
+        items = next;
+    }
+    items.pop()
+}
+
+pub(crate) fn tree_depth(steps: usize) -> u32 {
+    steps.max(1).next_power_of_two().trailing_zeros()
+}
+
+// Work the aggregation for `policy` adds on top of the per-step screening, with the
+// default `Aggregation` for the default parameters.
+pub fn reveal_cost(policy: RevealPolicy, steps: usize) -> OpCounter {
+    reveal_cost_with(
+        policy,
+        steps,
+        Aggregation::Auto.select(steps, ParameterPreset::Default),
+    )
+}
+
+// `reveal_cost` with `AnyFlag` reduced by `aggregation`.
+pub fn reveal_cost_with(policy: RevealPolicy, steps: usize, aggregation: Aggregation) -> OpCounter {
+    let merges = steps.saturating_sub(1) as u64;
+    match policy {
+        RevealPolicy::PerIndex => OpCounter::default(),
+        RevealPolicy::AnyFlag if aggregation == Aggregation::Sum => OpCounter {
+            comparisons: 1,
+            arithmetic: merges,
+            depth: tree_depth(steps) + 1,
+            ..Default::default()
+        },
+        RevealPolicy::AnyFlag => OpCounter {
+            boolean: merges,
+            depth: tree_depth(steps),
+            ..Default::default()
+        },
+        RevealPolicy::Count => OpCounter {
+            arithmetic: merges,
+            depth: tree_depth(steps),
+            ..Default::default()
+        },
+    }
+}
+
+// Reduces per-step flags to what `policy` reveals.
+//
+// Runs under the server key the flags were computed with, e.g. inside
+// `FheContext::evaluate_with`.
+pub fn reveal(policy: RevealPolicy, results: Vec<FheBool>) -> RevealedResult {
+    let aggregation = Aggregation::Auto.select(results.len(), ParameterPreset::Default);
+    reveal_with(policy, results, aggregation)
+}
+
+// `reveal` with `AnyFlag` reduced by `aggregation` (`Auto` as for the default parameters).
+pub fn reveal_with(
+    policy: RevealPolicy,
+    results: Vec<FheBool>,
+    aggregation: Aggregation,
+) -> RevealedResult {
+    let count = results.len();
+    match policy {
+        RevealPolicy::PerIndex => RevealedResult::PerIndex(results),
+        RevealPolicy::AnyFlag if results.is_empty() => {
+            RevealedResult::AnyFlag(FheBool::encrypt_trivial(false))
+        }
+        RevealPolicy::AnyFlag => {
+            RevealedResult::AnyFlag(match aggregation.select(count, ParameterPreset::Default) {
+                Aggregation::Sum if sum_bits(count) == 16 => results
+                    .into_iter()
+                    .map(|flag| -> FheUint16 { flag.cast_into() })
+                    .sum::<FheUint16>()
+                    .ne(0u16),
+                Aggregation::Sum => results
+                    .into_iter()
+                    .map(|flag| -> FheUint32 { flag.cast_into() })
+                    .sum::<FheUint32>()
+                    .ne(0u32),
+                _ => reduce_tree(results, |a, b| a | b).expect("non-empty flags"),
+            })
+        }
+        RevealPolicy::Count => {
+            let counts: Vec<FheUint32> = results.into_iter().map(|flag| flag.cast_into()).collect();
+            RevealedResult::Count(
+                reduce_tree(counts, |a, b| a + b)
+                    .unwrap_or_else(|| FheUint32::encrypt_trivial(0u32)),
+            )
+        }
+    }
+}
+
+impl RevealedResult {
+    pub fn policy(&self) -> RevealPolicy {
+        match self {
+            RevealedResult::PerIndex(_) => RevealPolicy::PerIndex,
+            RevealedResult::AnyFlag(_) => RevealPolicy::AnyFlag,
+            RevealedResult::Count(_) => RevealPolicy::Count,
+        }
+    }
+
+    // Per-step flags keep the `results_to_bytes` format; aggregates are their own
+    // artifact kind.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let aggregate = match self {
+            RevealedResult::PerIndex(flags) => return results_to_bytes(flags),
+            RevealedResult::AnyFlag(flag) => {
+                SerializedAggregate::AnyFlag(safe_serialize_item(flag)?)
+            }
+            RevealedResult::Count(count) => SerializedAggregate::Count(safe_serialize_item(count)?),
+        };
+        migrate::encode(ArtifactKind::Aggregate, &aggregate)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        if !migrate::has_kind(ArtifactKind::Aggregate, data) {
+            return Ok(RevealedResult::PerIndex(results_from_bytes(data)?));
+        }
+        Ok(match migrate::decode(ArtifactKind::Aggregate, data)? {
+            SerializedAggregate::AnyFlag(bytes) => {
+                RevealedResult::AnyFlag(safe_deserialize_item(&bytes)?)
+            }
+            SerializedAggregate::Count(bytes) => {
+                RevealedResult::Count(safe_deserialize_item(&bytes)?)
+            }
+        })
+    }
+
+    pub fn decrypt(&self, context: &FheContext) -> Result<Revealed, Box<dyn std::error::Error>> {
+        Ok(match self {
+            RevealedResult::PerIndex(flags) => Revealed::PerIndex(context.decrypt(flags)?),
+            RevealedResult::AnyFlag(flag) => {
+                Revealed::AnyFlag(context.decrypt(std::slice::from_ref(flag))?[0])
+            }
+            RevealedResult::Count(count) => {
+                Revealed::Count(context.decrypt(std::slice::from_ref(count))?[0])
+            }
+        })
+    }
+}
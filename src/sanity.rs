@@ -0,0 +1,218 @@
+// Plausibility checks on a plaintext trajectory before it is encrypted.
+//
+// Encrypting and screening cost orders of magnitude more than looking at the plaintext,
+// and nothing under encryption can tell a garbage trajectory from a real one. So the
+// owner can check first (`check_trajectory`, or `OwnerParty::encrypt_validated`) that
+//
+//   - there is one epoch per step and the epochs strictly increase (errors),
+//   - the steps are evenly spaced in time, within `step_tolerance` (a warning), and
+//   - the speed implied between consecutive steps is possible for something in Earth
+//     orbit (an error above `max_speed_mps`), and positions aren't inside the Earth (a
+//     warning, as test and relative-coordinate data often are).
+
+use std::fmt;
+
+use crate::common::SatelliteData;
+use crate::units::unbias;
+
+// Equatorial radius, as in `catalog`, which is behind a feature.
+const EARTH_RADIUS_M: f64 = 6_378_137.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SanityLimits {
+    // Fastest plausible speed, in m/s. Escape velocity at the surface is about 11.2 km/s.
+    pub max_speed_mps: f64,
+    // Largest tolerated deviation of a step's duration from the first one's, as a
+    // fraction of it.
+    pub step_tolerance: f64,
+    // Smallest plausible distance from the Earth's center, in meters.
+    pub min_radius_m: f64,
+}
+
+impl Default for SanityLimits {
+    fn default() -> Self {
+        Self {
+            max_speed_mps: 12_000.0,
+            step_tolerance: 0.01,
+            min_radius_m: EARTH_RADIUS_M,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    // Suspicious, but the trajectory can still be screened.
+    Warning,
+    // Screening the trajectory would be wasted work.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanityIssue {
+    pub severity: Severity,
+    // Step the issue was found at, if it concerns one.
+    pub step: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanityReport {
+    pub issues: Vec<SanityIssue>,
+}
+
+impl SanityReport {
+    // No errors; warnings don't count.
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &SanityIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &SanityIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    // The report itself if there are no errors, otherwise an error listing them.
+    pub fn into_result(self) -> Result<Self, Box<dyn std::error::Error>> {
+        if self.is_ok() {
+            return Ok(self);
+        }
+        let errors: Vec<String> = self.errors().map(ToString::to_string).collect();
+        Err(format!("implausible trajectory: {}", errors.join("; ")).into())
+    }
+
+    fn push(&mut self, severity: Severity, step: Option<usize>, message: String) {
+        self.issues.push(SanityIssue {
+            severity,
+            step,
+            message,
+        });
+    }
+}
+
+impl fmt::Display for SanityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.step {
+            Some(step) => write!(f, "step {}: {}", step, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl fmt::Display for SanityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "trajectory looks plausible");
+        }
+        for issue in &self.issues {
+            let severity = match issue.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            writeln!(f, "{}: {}", severity, issue)?;
+        }
+        Ok(())
+    }
+}
+
+// Position of step `i` in meters.
+fn position_m(data: &SatelliteData, i: usize) -> [f64; 3] {
+    let meters = data.units.meters();
+    [data.x[i], data.y[i], data.z[i]].map(|cell| unbias(cell) as f64 * meters)
+}
+
+// Checks `data`, whose step `i` is at `epochs[i]` (seconds), against `limits`.
+pub fn check_trajectory(
+    data: &SatelliteData,
+    epochs: &[u64],
+    limits: &SanityLimits,
+) -> SanityReport {
+    let mut report = SanityReport::default();
+    let steps = data.x.len();
+    if data.y.len() != steps || data.z.len() != steps {
+        report.push(
+            Severity::Error,
+            None,
+            "trajectory axes have different lengths".to_string(),
+        );
+        return report;
+    }
+    if epochs.len() != steps {
+        report.push(
+            Severity::Error,
+            None,
+            format!("{} epochs for {} steps", epochs.len(), steps),
+        );
+        return report;
+    }
+
+    // Time.
+    if let Some(i) = (1..steps).find(|&i| epochs[i] <= epochs[i - 1]) {
+        report.push(
+            Severity::Error,
+            Some(i),
+            format!(
+                "epoch {} doesn't follow the previous step's {}",
+                epochs[i],
+                epochs[i - 1]
+            ),
+        );
+        return report;
+    }
+    if steps > 2 {
+        let nominal = (epochs[1] - epochs[0]) as f64;
+        if let Some(i) = (2..steps).find(|&i| {
+            ((epochs[i] - epochs[i - 1]) as f64 - nominal).abs() > nominal * limits.step_tolerance
+        }) {
+            report.push(
+                Severity::Warning,
+                Some(i),
+                format!(
+                    "step of {} s where the trajectory starts with {} s steps",
+                    epochs[i] - epochs[i - 1],
+                    nominal
+                ),
+            );
+        }
+    }
+
+    // Space.
+    let positions: Vec<[f64; 3]> = (0..steps).map(|i| position_m(data, i)).collect();
+    if let Some(i) = (0..steps).find(|&i| {
+        let [x, y, z] = positions[i];
+        (x * x + y * y + z * z).sqrt() < limits.min_radius_m
+    }) {
+        report.push(
+            Severity::Warning,
+            Some(i),
+            format!(
+                "position is less than {} m from the Earth's center",
+                limits.min_radius_m
+            ),
+        );
+    }
+    for i in 1..steps {
+        let [a, b] = [positions[i - 1], positions[i]];
+        let distance =
+            ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2) + (b[2] - a[2]).powi(2)).sqrt();
+        let speed = distance / (epochs[i] - epochs[i - 1]) as f64;
+        if speed > limits.max_speed_mps {
+            report.push(
+                Severity::Error,
+                Some(i),
+                format!(
+                    "implied speed of {:.0} m/s exceeds {:.0} m/s",
+                    speed, limits.max_speed_mps
+                ),
+            );
+            break;
+        }
+    }
+    report
+}
@@ -0,0 +1,59 @@
+// Order in which streamed screening works through the time steps.
+//
+// With results streamed back in batches (see `stream`), the order the evaluator screens
+// them in decides which warnings arrive first. By default that is chronological, so the
+// next few hours are screened before the rest of the week; a session can instead start
+// from the steps nearest a given epoch, or follow its own per-step priorities (e.g. the
+// steps a coarse screening flagged). Steps are always scheduled a whole batch at a time,
+// so every batch still covers consecutive steps.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StepOrder {
+    // Earliest steps first.
+    #[default]
+    Chronological,
+    // Steps whose epoch is closest to this one first, e.g. the current time.
+    NearestTo(u64),
+    // One priority per step, lowest first; ties are screened chronologically.
+    Priority(Vec<u32>),
+}
+
+// The steps of a trajectory with `epochs`, cut into batches of `batch_steps` consecutive
+// steps (the last one may be shorter), in the order to screen them. A batch is ranked by
+// its most urgent step.
+pub fn batch_order(
+    order: &StepOrder,
+    epochs: &[u64],
+    batch_steps: usize,
+) -> Result<Vec<Range<usize>>, Box<dyn std::error::Error>> {
+    let batch_steps = batch_steps.max(1);
+    let mut batches: Vec<Range<usize>> = (0..epochs.len())
+        .step_by(batch_steps)
+        .map(|start| start..(start + batch_steps).min(epochs.len()))
+        .collect();
+    match order {
+        StepOrder::Chronological => {}
+        StepOrder::NearestTo(epoch) => {
+            batches.sort_by_key(|batch| {
+                epochs[batch.clone()]
+                    .iter()
+                    .map(|t| t.abs_diff(*epoch))
+                    .min()
+            });
+        }
+        StepOrder::Priority(priorities) => {
+            if priorities.len() != epochs.len() {
+                return Err(format!(
+                    "{} priorities for a trajectory of {} steps",
+                    priorities.len(),
+                    epochs.len()
+                )
+                .into());
+            }
+            batches.sort_by_key(|batch| priorities[batch.clone()].iter().min().copied());
+        }
+    }
+    Ok(batches)
+}
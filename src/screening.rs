@@ -0,0 +1,438 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheBool, FheUint32};
+
+use crate::common::{SatelliteData, safe_deserialize_items, safe_serialize_item};
+use crate::compat::{self, ParameterDigest};
+use crate::context;
+use crate::depth::{DepthExceeded, DepthLimit, OpCounter};
+use crate::frame::{Frame, check_frames};
+use crate::kernel::{ComparisonKernel, ExactMatch, KernelChoice};
+use crate::migrate::{self, ArtifactKind};
+use crate::profiling::span;
+use crate::reveal::Aggregation;
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::Units;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScreeningConfig {
+    // Maximum per-step operation depth allowed for the chosen mode; unlimited if `None`.
+    pub depth_limit: Option<DepthLimit>,
+    // Evaluate the three axis comparisons of a step concurrently on the workers of the
+    // `FheContext` running the screening.
+    pub parallel_axes: bool,
+    // Per-step comparison (see `kernel`); exact match unless the session chose another.
+    pub kernel: KernelChoice,
+    // Compare against the evaluator's clear coordinates as trivial ciphertexts (see
+    // `ClearCoord`). On by default; turning it off trades the timing guarantee for speed.
+    pub constant_shape: bool,
+    // How an `AnyFlag` reveal reduces the per-step flags (see `reveal`).
+    pub aggregation: Aggregation,
+}
+
+impl Default for ScreeningConfig {
+    fn default() -> Self {
+        Self {
+            depth_limit: None,
+            parallel_axes: false,
+            kernel: KernelChoice::default(),
+            constant_shape: true,
+            aggregation: Aggregation::default(),
+        }
+    }
+}
+
+impl ScreeningConfig {
+    pub(crate) fn clear(&self, value: u32) -> ClearCoord {
+        ClearCoord {
+            value,
+            constant_shape: self.constant_shape,
+        }
+    }
+
+    // Checks the per-step cost `step` against the depth limit. Over a `Warn` limit, the
+    // excess is recorded in `step.depth_warning` and carried into the screening's totals.
+    pub fn check_depth(&self, step: &mut OpCounter) -> Result<(), DepthExceeded> {
+        if let Some(limit) = &self.depth_limit
+            && limit.check(step)?
+        {
+            step.depth_warning = Some(DepthExceeded {
+                depth: step.depth,
+                max_depth: limit.max_depth,
+            });
+        }
+        Ok(())
+    }
+}
+
+// One of the evaluator's clear coordinates as an operand of the comparison kernels.
+//
+// TFHE's scalar operations look at the clear value to save work: comparisons against 0
+// are answered without any bootstrapping, and equality stops early on leading zero blocks
+// or values out of the ciphertext's range. Whoever can time the evaluator then learns
+// something about its plaintext, e.g. which steps are padding (see `padding`). With
+// `constant_shape` the value is trivially encrypted instead and compared ciphertext to
+// ciphertext, which runs the same lookup tables whatever it is, at about twice the cost of
+// the scalar path. Bounds derived from the value (as in `BoxThreshold`) go through the
+// same operands, so no branch depends on the evaluator's data either.
+#[derive(Debug, Clone, Copy)]
+pub struct ClearCoord {
+    pub value: u32,
+    pub constant_shape: bool,
+}
+
+impl ClearCoord {
+    // Scalar operand, for callers without a timing concern.
+    pub fn scalar(value: u32) -> Self {
+        Self {
+            value,
+            constant_shape: false,
+        }
+    }
+
+    pub fn map(self, f: impl FnOnce(u32) -> u32) -> Self {
+        Self {
+            value: f(self.value),
+            ..self
+        }
+    }
+
+    fn trivial(self) -> FheUint32 {
+        span("trivial", || FheUint32::encrypt_trivial(self.value))
+    }
+
+    pub fn eq(self, encrypted: &FheUint32) -> FheBool {
+        if self.constant_shape {
+            let value = self.trivial();
+            span("eq", || encrypted.eq(&value))
+        } else {
+            span("eq", || encrypted.eq(self.value))
+        }
+    }
+
+    // `encrypted >= value`.
+    pub fn encrypted_ge(self, encrypted: &FheUint32) -> FheBool {
+        if self.constant_shape {
+            let value = self.trivial();
+            span("ge", || encrypted.ge(&value))
+        } else {
+            span("ge", || encrypted.ge(self.value))
+        }
+    }
+
+    // `encrypted <= value`.
+    pub fn encrypted_le(self, encrypted: &FheUint32) -> FheBool {
+        if self.constant_shape {
+            let value = self.trivial();
+            span("le", || encrypted.le(&value))
+        } else {
+            span("le", || encrypted.le(self.value))
+        }
+    }
+
+    pub fn max(self, encrypted: &FheUint32) -> FheUint32 {
+        if self.constant_shape {
+            let value = self.trivial();
+            span("max", || encrypted.max(&value))
+        } else {
+            span("max", || encrypted.max(self.value))
+        }
+    }
+
+    pub fn min(self, encrypted: &FheUint32) -> FheUint32 {
+        if self.constant_shape {
+            let value = self.trivial();
+            span("min", || encrypted.min(&value))
+        } else {
+            span("min", || encrypted.min(self.value))
+        }
+    }
+}
+
+// Per-step encrypted collision flags plus the homomorphic work spent producing them.
+pub struct ScreeningOutput {
+    pub results: Vec<FheBool>,
+    pub ops: OpCounter,
+}
+
+// Decrypted per-step flags together with the summaries most callers want.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScreeningOutcome {
+    pub flags: Vec<bool>,
+    // Some step was flagged.
+    pub any: bool,
+    pub first_positive: Option<usize>,
+    // Flagged steps.
+    pub count: usize,
+}
+
+impl ScreeningOutcome {
+    pub fn from_flags(flags: Vec<bool>) -> Self {
+        let first_positive = flags.iter().position(|&flag| flag);
+        Self {
+            any: first_positive.is_some(),
+            first_positive,
+            count: flags.iter().filter(|&&flag| flag).count(),
+            flags,
+        }
+    }
+}
+
+pub fn decrypt_results(results: &[FheBool], client_key: &ClientKey) -> ScreeningOutcome {
+    ScreeningOutcome::from_flags(
+        results
+            .iter()
+            .map(|flag| flag.decrypt(client_key))
+            .collect(),
+    )
+}
+
+// Wire form of per-step result flags: each ciphertext serialized individually, packed
+// with bincode behind the format tag (see `migrate`).
+pub fn results_to_bytes(results: &[FheBool]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let items = results
+        .iter()
+        .map(safe_serialize_item)
+        .collect::<Result<Vec<_>, _>>()?;
+    migrate::encode_with(
+        ArtifactKind::Results,
+        &items,
+        results.first().map(ParameterDigest::of_bool),
+    )
+}
+
+pub fn results_from_bytes(data: &[u8]) -> Result<Vec<FheBool>, Box<dyn std::error::Error>> {
+    let items: Vec<Vec<u8>> = migrate::decode(ArtifactKind::Results, data)?;
+    safe_deserialize_items(&items).map_err(|e| compat::explain(ArtifactKind::Results, data, e))
+}
+
+// Cost of one exact-match step: three comparisons, then `(x & y) & z`.
+pub fn exact_match_cost() -> OpCounter {
+    OpCounter {
+        comparisons: 3,
+        arithmetic: 0,
+        boolean: 2,
+        depth: 3,
+        depth_warning: None,
+    }
+}
+
+// One exact-match step. The three comparisons are independent, so with `parallel` they run
+// concurrently and only the final AND waits on all of them.
+pub(crate) fn exact_match_step(axes: [(&FheUint32, ClearCoord); 3], parallel: bool) -> FheBool {
+    let [(x, px), (y, py), (z, pz)] = axes;
+    if parallel {
+        let (eq_x, (eq_y, eq_z)) =
+            context::join(|| px.eq(x), || context::join(|| py.eq(y), || pz.eq(z)));
+        span("and", || eq_x & eq_y & eq_z)
+    } else {
+        let (eq_x, eq_y, eq_z) = (px.eq(x), py.eq(y), pz.eq(z));
+        span("and", || eq_x & eq_y & eq_z)
+    }
+}
+
+// Checks that `plaintext` covers the steps of `encrypted` and is in the same frame, and
+// rescales it to the units of `encrypted` if they differ.
+pub(crate) fn align_plaintext<'a>(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &'a SatelliteData,
+) -> Result<Cow<'a, SatelliteData>, Box<dyn std::error::Error>> {
+    align_plaintext_to(
+        encrypted.first_index..encrypted.absolute_index(encrypted.len()),
+        encrypted.frame,
+        encrypted.units,
+        plaintext,
+    )
+}
+
+// `align_plaintext` for an encrypted trajectory known only by its metadata.
+pub(crate) fn align_plaintext_to<'a>(
+    steps: Range<usize>,
+    frame: Frame,
+    units: Units,
+    plaintext: &'a SatelliteData,
+) -> Result<Cow<'a, SatelliteData>, Box<dyn std::error::Error>> {
+    if steps.end > plaintext.x.len() {
+        return Err(format!(
+            "trajectory length mismatch: encrypted covers steps {}..{}, plaintext has {}",
+            steps.start,
+            steps.end,
+            plaintext.x.len()
+        )
+        .into());
+    }
+    check_frames(frame, plaintext.frame)?;
+    // The plaintext side can always be brought to the owner's units.
+    if plaintext.units == units {
+        Ok(Cow::Borrowed(plaintext))
+    } else {
+        Ok(Cow::Owned(plaintext.to_units(units)?))
+    }
+}
+
+// Exact-match collision check: for every time step, compare the encrypted position with
+// the plaintext one on all three axes (ciphertext vs plaintext) and AND the results.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_exact(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    screen_kernel(encrypted, plaintext, &ExactMatch, config)
+}
+
+// Collision check with `kernel` deciding each step.
+//
+// `plaintext` is rescaled to the units of `encrypted` if they differ, and is indexed by
+// absolute step, so a windowed trajectory (see `EncryptedTrajectory::window`) is compared
+// with the matching part of it.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_kernel(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    kernel: &dyn ComparisonKernel,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    let mut results = Vec::with_capacity(encrypted.len());
+    let ops = screen_kernel_each(
+        encrypted,
+        plaintext,
+        kernel,
+        config,
+        0..encrypted.len(),
+        |flag| {
+            results.push(flag);
+            Ok(())
+        },
+    )?;
+    Ok(ScreeningOutput { results, ops })
+}
+
+// A step whose evaluation panicked, by absolute index, with the panic message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepFailure {
+    pub index: usize,
+    pub reason: String,
+}
+
+// Output of a screening that carried on past failed steps. A failed step's flag is a
+// trivially encrypted `false` standing in for the missing result, so it says nothing
+// about that step; `failures` lists those steps so they are reported as unverified
+// rather than as clear.
+pub struct CheckedOutput {
+    pub output: ScreeningOutput,
+    pub failures: Vec<StepFailure>,
+}
+
+impl CheckedOutput {
+    // Absolute indices of the failed steps, ascending.
+    pub fn failed_indices(&self) -> Vec<usize> {
+        self.failures.iter().map(|failure| failure.index).collect()
+    }
+}
+
+// `screen_kernel`, except that a step whose evaluation panics doesn't end the screening:
+// the panic is caught, the step recorded in `CheckedOutput::failures` and the remaining
+// steps evaluated as usual. Errors before any step is evaluated (mismatched frames or
+// units, the depth limit) are still returned as errors.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_kernel_checked(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    kernel: &dyn ComparisonKernel,
+    config: &ScreeningConfig,
+) -> Result<CheckedOutput, Box<dyn std::error::Error>> {
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let offset = encrypted.first_index;
+    let mut step = kernel.cost();
+    config.check_depth(&mut step)?;
+
+    let mut results = Vec::with_capacity(encrypted.len());
+    let mut failures = Vec::new();
+    for i in 0..encrypted.len() {
+        let j = offset + i;
+        let flag = catch_unwind(AssertUnwindSafe(|| {
+            span(kernel.name(), || {
+                kernel.compare(
+                    [&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]],
+                    [
+                        config.clear(plaintext.x[j]),
+                        config.clear(plaintext.y[j]),
+                        config.clear(plaintext.z[j]),
+                    ],
+                    config.parallel_axes,
+                )
+            })
+        }));
+        match flag {
+            Ok(flag) => results.push(flag),
+            Err(payload) => {
+                failures.push(StepFailure {
+                    index: j,
+                    reason: panic_message(payload.as_ref()),
+                });
+                results.push(FheBool::encrypt_trivial(false));
+            }
+        }
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, (results.len() - failures.len()) as u64);
+    Ok(CheckedOutput {
+        output: ScreeningOutput { results, ops },
+        failures,
+    })
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "evaluation panicked".to_string()
+    }
+}
+
+// `screen_kernel` over the steps at positions `steps` of `encrypted`, in that order,
+// handing each step's flag to `on_step` as soon as it is computed.
+pub(crate) fn screen_kernel_each(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    kernel: &dyn ComparisonKernel,
+    config: &ScreeningConfig,
+    steps: impl IntoIterator<Item = usize>,
+    mut on_step: impl FnMut(FheBool) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<OpCounter, Box<dyn std::error::Error>> {
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let offset = encrypted.first_index;
+    let mut step = kernel.cost();
+    config.check_depth(&mut step)?;
+
+    let mut screened = 0;
+    for i in steps {
+        let j = offset + i;
+        on_step(span(kernel.name(), || {
+            kernel.compare(
+                [&encrypted.x[i], &encrypted.y[i], &encrypted.z[i]],
+                [
+                    config.clear(plaintext.x[j]),
+                    config.clear(plaintext.y[j]),
+                    config.clear(plaintext.z[j]),
+                ],
+                config.parallel_axes,
+            )
+        }))?;
+        screened += 1;
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, screened);
+    Ok(ops)
+}
@@ -0,0 +1,1487 @@
+// Long-running evaluator daemon behind the `sat-fhe-serve` binary.
+//
+// Owners open a job by sending their `Hello` envelope, upload their server key and
+// encrypted trajectory, poll the job status and download the result ciphertexts. The
+// daemon screens each job against its own plaintext trajectory on an `EvalPool` of
+// `max_jobs` threads; once `queue_depth` jobs wait for a thread, new sessions are refused
+// until the queue drains. Uploaded artifacts and results live in a `BlobStore` (by
+// default files in `storage_dir`, one directory per job), so memory use doesn't grow with
+// the number of queued jobs. Jobs returning per-step flags also store them in batches of
+// `STREAM_BATCH_STEPS` (or as many steps as the owner asked for, see `tuning`) as they are
+// computed, which owners can fetch before the job is done (see `stream`). Each result
+// envelope is sealed once and stored, so a fetch retried after a dropped reply gets the
+// same envelope instead of one with a sequence number the owner isn't expecting yet.
+// Large uploads arrive in hashed pieces (`Request::UploadChunk`) that are kept in the job
+// directory until the envelope is complete, so a dropped or corrupted piece is asked for
+// again instead of the whole artifact.
+//
+// Decoding a server key takes seconds, so the server keys of regular owners can be
+// listed in `prewarm_keys`: the daemon decodes them once at startup, and jobs uploading
+// the same key bytes start evaluating right away. `health_listen` serves the HTTP probes
+// of `health`, with `/readyz` failing until the pre-warm is done.
+//
+// Services billing by usage attach `billing::UsageMeter`s with `Daemon::with_meter`,
+// which hear about every streamed batch, or every job screened in one piece.
+//
+// `Daemon::run_until` shuts down gracefully, as `sat-fhe-serve` does on SIGTERM: the
+// daemon refuses new sessions and fails `/readyz` from then on, but keeps serving
+// connections, so owners can still fetch results, while queued and running jobs finish.
+// Jobs still running after `drain_timeout_s` are checkpointed: streamed ones stop after
+// the batch they are screening, like a cancelled job, keeping their batches and work
+// certificate, so only the steps after them need screening again; jobs screened in one
+// piece can't stop halfway and are abandoned. The blob store is then flushed and every
+// connection closed.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use tfhe::FheBool;
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+
+use crate::billing::{UsageMeter, UsageRecord};
+use crate::blob::{BlobStore, BlobStoreConfig, DedupStore};
+use crate::certificate::WorkCertificate;
+use crate::common::SatelliteData;
+use crate::compat;
+use crate::context::FheContext;
+use crate::depth::OpCounter;
+use crate::frame::check_frames;
+use crate::health::serve_probes;
+use crate::mask::screen_masked;
+use crate::migrate::{self, ArtifactKind};
+use crate::net::{Connection, Listener};
+use crate::padding::{EVALUATOR_SENTINEL, check_padded_len, pad};
+use crate::planner::{Operand, screen_planned};
+use crate::pool::{EvalPool, PoolFull};
+use crate::preset::ParameterPreset;
+use crate::protocol::{Envelope, MessageKind, ProtocolError, SessionMetadata};
+use crate::quota::{QuotaConfig, QuotaError, QuotaTracker};
+use crate::recurring::Recurrence;
+use crate::reveal::{RevealPolicy, RevealedResult, reveal_with};
+use crate::schedule::StepOrder;
+use crate::screening::ScreeningConfig;
+use crate::service::{
+    JobHandle, JobId, JobStatus, JobSummary, JobToken, JobsSnapshot, Request, Response,
+    ServiceError, read_frame, write_frame,
+};
+use crate::session::Session;
+use crate::stream::{ResultBatch, screen_streaming};
+use crate::tls::{TlsConfig, TlsListener};
+use crate::trajectory::{EncryptedTrajectory, SerializedTrajectory};
+use crate::transport::{
+    ArtifactPointer, ArtifactRef, ObjectStoreConfig, check_key, fetch_verified, sha256_hex,
+};
+use crate::tuning::{MAX_BATCH_STEPS, MIN_BATCH_STEPS};
+
+// Alerts kept for `Request::Jobs`.
+pub const RECENT_ALERTS: usize = 32;
+
+// Steps per streamed result batch.
+pub const STREAM_BATCH_STEPS: usize = 16;
+
+// How often the daemon checks its trajectory file for new ephemerides and starts the
+// recurring runs that are due.
+pub const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+// How often a draining daemon checks whether its jobs have finished.
+pub const DRAIN_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ServeConfig {
+    // Address to listen on, e.g. "0.0.0.0:7878".
+    pub listen: String,
+    // Directory for uploaded artifacts and results, with the filesystem blob store.
+    pub storage_dir: PathBuf,
+    // Evaluations running concurrently, each on its own thread; further jobs wait in the
+    // queue.
+    #[serde(default = "default_max_jobs")]
+    pub max_jobs: usize,
+    // Jobs that may wait for an evaluation thread before new sessions are refused.
+    #[serde(default = "default_queue_depth")]
+    pub queue_depth: usize,
+    // Parameters owners must generate their keys with.
+    #[serde(default)]
+    pub preset: ParameterPreset,
+    // The evaluator's own plaintext trajectory: a bincode-serialized `SatelliteData`.
+    pub trajectory: PathBuf,
+    // Where artifacts sent by reference (`MessageKind::ArtifactRef`) are fetched from.
+    #[serde(default)]
+    pub object_store: Option<ObjectStoreConfig>,
+    // Per-client limits; unlimited unless set.
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+    // Mutual TLS with pinned client certificates (see `tls`); plain TCP unless set.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    // Where job artifacts and results are kept; files in `storage_dir` unless set.
+    #[serde(default)]
+    pub blob_store: BlobStoreConfig,
+    // Keep identical blobs once, e.g. a catalog trajectory uploaded for every owner (see
+    // `blob::DedupStore`); off unless set.
+    #[serde(default)]
+    pub dedup_blobs: bool,
+    // Address for the `/healthz` and `/readyz` probes (see `health`); none unless set.
+    #[serde(default)]
+    pub health_listen: Option<String>,
+    // Server key artifacts, as owners upload them, decoded at startup so jobs with one of
+    // these keys don't wait for it.
+    #[serde(default)]
+    pub prewarm_keys: Vec<PathBuf>,
+    // Seconds a shutting-down daemon waits for its jobs before checkpointing them.
+    #[serde(default = "default_drain_timeout_s")]
+    pub drain_timeout_s: u64,
+}
+
+fn default_max_jobs() -> usize {
+    1
+}
+
+fn default_queue_depth() -> usize {
+    16
+}
+
+fn default_drain_timeout_s() -> u64 {
+    30
+}
+
+impl ServeConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ServiceError> {
+        let config: ServeConfig = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // What deserializing can't check; also run on the `daemon` section of a campaign.
+    pub(crate) fn validate(&self) -> Result<(), ServiceError> {
+        if self.max_jobs == 0 {
+            return Err("max_jobs must be at least 1".into());
+        }
+        if self.queue_depth == 0 {
+            return Err("queue_depth must be at least 1".into());
+        }
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ServiceError> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+}
+
+struct Job {
+    client: IpAddr,
+    token: JobToken,
+    metadata: SessionMetadata,
+    session: Session,
+    // Key prefix of the job's blobs.
+    prefix: String,
+    has_server_key: bool,
+    has_trajectory: bool,
+    status: JobStatus,
+    upload: Option<ChunkedUpload>,
+    // Steps per streamed result batch.
+    batch_steps: usize,
+    // Set by `Request::Cancel`; the evaluation stops after the batch it is screening.
+    cancel: Arc<AtomicBool>,
+    // Runs that finished, counting the first.
+    runs: usize,
+    recurrence: Option<Recurrence>,
+    progress: Progress,
+    // Result envelopes of the current run sent so far: held here until stored under
+    // `Fetch::file`, `None` from then on.
+    sealed: HashMap<Fetch, Option<Arc<Vec<u8>>>>,
+}
+
+impl Job {
+    fn awaiting_uploads(&mut self) -> Result<&mut Self, ServiceError> {
+        if self.status != JobStatus::AwaitingUploads {
+            return Err("job is no longer accepting uploads".into());
+        }
+        Ok(self)
+    }
+
+    fn summary(&self, job: JobId) -> JobSummary {
+        JobSummary {
+            job,
+            status: self.status.clone(),
+            steps: self.progress.steps,
+            screened: self.progress.screened,
+            batches: self.progress.batches,
+            batch_steps: self.batch_steps,
+            elapsed: self.progress.elapsed.unwrap_or_else(|| {
+                self.progress
+                    .started
+                    .map_or(Duration::ZERO, |started| started.elapsed())
+            }),
+        }
+    }
+}
+
+// A result envelope an owner can fetch, see `seal_once`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Fetch {
+    Results,
+    Batch(usize),
+    PartialResults,
+}
+
+impl Fetch {
+    pub(crate) fn kind(self) -> MessageKind {
+        match self {
+            Fetch::Results => MessageKind::Results,
+            Fetch::Batch(_) | Fetch::PartialResults => MessageKind::ResultBatch,
+        }
+    }
+
+    fn file(self) -> String {
+        match self {
+            Fetch::Results => "sealed-results.bin".to_string(),
+            Fetch::Batch(batch) => format!("sealed-{}", batch_file(batch)),
+            Fetch::PartialResults => "sealed-partial.bin".to_string(),
+        }
+    }
+}
+
+// How far the evaluation of a job's current run has got, for `Request::Jobs`.
+#[derive(Default)]
+struct Progress {
+    steps: usize,
+    screened: usize,
+    batches: usize,
+    started: Option<Instant>,
+    // Set once the run has finished.
+    elapsed: Option<Duration>,
+}
+
+// An evaluation stopped by `Request::Cancel` after screening `steps` steps.
+#[derive(Debug)]
+struct Cancelled {
+    steps: usize,
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled after {} steps", self.steps)
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+// An upload envelope arriving in pieces, stored as `chunk_file`s in the job directory.
+struct ChunkedUpload {
+    hashes: Vec<String>,
+    received: Vec<bool>,
+}
+
+impl ChunkedUpload {
+    fn missing(&self) -> Vec<usize> {
+        (0..self.received.len())
+            .filter(|&i| !self.received[i])
+            .collect()
+    }
+
+    fn remove(&self, blobs: &dyn BlobStore, prefix: &str) {
+        for i in 0..self.hashes.len() {
+            let _ = blobs.delete(&blob(prefix, &chunk_file(i)));
+        }
+    }
+}
+
+// The evaluator's plaintext trajectory, reloaded when its file changes.
+struct Ephemerides {
+    trajectory: Arc<SatelliteData>,
+    // Hex SHA-256 of the file it was read from.
+    sha256: String,
+}
+
+impl Ephemerides {
+    fn load(path: &Path) -> Result<Self, ServiceError> {
+        let bytes = std::fs::read(path)?;
+        Ok(Self {
+            trajectory: Arc::new(bincode::deserialize(&bytes)?),
+            sha256: sha256_hex(&bytes),
+        })
+    }
+}
+
+struct State {
+    config: ServeConfig,
+    ephemerides: RwLock<Ephemerides>,
+    blobs: Box<dyn BlobStore>,
+    jobs: Mutex<HashMap<JobId, Job>>,
+    next_job: Mutex<JobId>,
+    pool: EvalPool,
+    quotas: QuotaTracker,
+    // Time per step of the last finished screening, reported in `Request::Benchmark`.
+    step_time: Mutex<Option<Duration>>,
+    // Contexts of the `prewarm_keys`, by the hex SHA-256 of the key artifact.
+    warm_keys: RwLock<HashMap<String, Arc<FheContext>>>,
+    // Set once the `prewarm_keys` are decoded.
+    warmed: AtomicBool,
+    // The latest `RECENT_ALERTS` failures, cancellations and refusals, oldest first.
+    alerts: Mutex<VecDeque<String>>,
+    // Told about the work done for each job as it is done (see `billing`).
+    meters: Mutex<Vec<Box<dyn UsageMeter>>>,
+    // Set once `Daemon::run_until` starts draining.
+    shutting_down: AtomicBool,
+}
+
+impl State {
+    fn trajectory(&self) -> Arc<SatelliteData> {
+        self.ephemerides.read().unwrap().trajectory.clone()
+    }
+
+    fn alert(&self, message: String) {
+        let mut alerts = self.alerts.lock().unwrap();
+        if alerts.len() == RECENT_ALERTS {
+            alerts.pop_front();
+        }
+        alerts.push_back(message);
+    }
+
+    // Reports `steps` screened at a cost of `ops` for `job` to the usage meters. A meter
+    // failing doesn't fail the job, but raises an alert.
+    fn meter(&self, job: JobId, client: IpAddr, steps: usize, ops: OpCounter) {
+        let usage = UsageRecord {
+            session: job.to_string(),
+            client: client.to_string(),
+            steps: steps as u64,
+            ops,
+            unix_s: now_unix_s(),
+        };
+        for meter in self.meters.lock().unwrap().iter() {
+            if let Err(e) = meter.record(&usage) {
+                self.alert(format!("job {}: usage meter failed: {}", job, e));
+            }
+        }
+    }
+
+    // Whether the daemon should be sent new jobs.
+    fn is_ready(&self) -> bool {
+        self.warmed.load(Ordering::SeqCst)
+            && !self.pool.is_full()
+            && !self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+pub struct Daemon<L = TcpListener> {
+    listener: L,
+    // Bound up front so a `health_listen` port of 0 can be looked up.
+    health: Option<std::net::TcpListener>,
+    state: Arc<State>,
+}
+
+impl Daemon {
+    // Plain TCP; a config with `tls` set needs `bind_tls`.
+    pub async fn bind(config: ServeConfig) -> Result<Self, ServiceError> {
+        if config.tls.is_some() {
+            return Err("the config enables TLS; bind with Daemon::bind_tls".into());
+        }
+        let listener = TcpListener::bind(&config.listen).await?;
+        Self::with_listener(config, listener)
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ServiceError> {
+        Ok(self.listener.local_addr()?)
+    }
+}
+
+impl Daemon<TlsListener> {
+    pub async fn bind_tls(config: ServeConfig) -> Result<Self, ServiceError> {
+        let tls = config
+            .tls
+            .clone()
+            .ok_or("the config has no [tls] section")?;
+        let listener = TlsListener::new(TcpListener::bind(&config.listen).await?, &tls)?;
+        Self::with_listener(config, listener)
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ServiceError> {
+        Ok(self.listener.local_addr()?)
+    }
+}
+
+impl<L: Listener> Daemon<L> {
+    // Serves the connections `listener` accepts instead of listening on `config.listen`
+    // (see `net`).
+    pub fn with_listener(config: ServeConfig, listener: L) -> Result<Self, ServiceError> {
+        let ephemerides = Ephemerides::load(&config.trajectory)?;
+        let mut blobs = config.blob_store.open(&config.storage_dir)?;
+        if config.dedup_blobs {
+            blobs = Box::new(DedupStore::new(blobs));
+        }
+        let health = match &config.health_listen {
+            Some(addr) => {
+                let health = std::net::TcpListener::bind(addr)?;
+                health.set_nonblocking(true)?;
+                Some(health)
+            }
+            None => None,
+        };
+        Ok(Self {
+            listener,
+            health,
+            state: Arc::new(State {
+                pool: EvalPool::new(config.max_jobs, config.queue_depth)
+                    .map_err(|e| e.to_string())?,
+                quotas: QuotaTracker::new(config.quotas),
+                config,
+                ephemerides: RwLock::new(ephemerides),
+                blobs,
+                jobs: Mutex::new(HashMap::new()),
+                next_job: Mutex::new(0),
+                step_time: Mutex::new(None),
+                warm_keys: RwLock::new(HashMap::new()),
+                warmed: AtomicBool::new(false),
+                alerts: Mutex::new(VecDeque::new()),
+                meters: Mutex::new(Vec::new()),
+                shutting_down: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    // Reports the work of every job to `meter`; may be given several times.
+    pub fn with_meter(self, meter: impl UsageMeter + 'static) -> Self {
+        self.state.meters.lock().unwrap().push(Box::new(meter));
+        self
+    }
+
+    // Where the `/healthz` and `/readyz` probes are answered, if anywhere.
+    pub fn health_addr(&self) -> Result<Option<SocketAddr>, ServiceError> {
+        Ok(self.health.as_ref().map(|h| h.local_addr()).transpose()?)
+    }
+
+    pub async fn run(self) -> Result<(), ServiceError> {
+        self.run_until(std::future::pending()).await
+    }
+
+    // Serves until `shutdown` resolves, then drains and returns (see the module comment).
+    pub async fn run_until(
+        mut self,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), ServiceError> {
+        let probes = match self.health.take() {
+            Some(health) => {
+                let state = self.state.clone();
+                Some(tokio::spawn(serve_probes(
+                    TcpListener::from_std(health)?,
+                    move || state.is_ready(),
+                )))
+            }
+            None => None,
+        };
+        let state = self.state.clone();
+        tokio::task::spawn_blocking(move || prewarm_keys(&state));
+        let scheduler = tokio::spawn(run_scheduler(self.state.clone()));
+        let mut connections = JoinSet::new();
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => self.serve(&mut connections, accepted?),
+                () = &mut shutdown => break,
+            }
+        }
+
+        self.state.shutting_down.store(true, Ordering::SeqCst);
+        let drain = tokio::time::sleep(Duration::from_secs(self.state.config.drain_timeout_s));
+        tokio::pin!(drain);
+        let mut poll = tokio::time::interval(DRAIN_POLL);
+        let mut checkpointed = false;
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => self.serve(&mut connections, accepted?),
+                () = &mut drain, if !checkpointed => {
+                    checkpoint_jobs(&self.state);
+                    checkpointed = true;
+                }
+                _ = poll.tick() => {
+                    if !awaits_jobs(&self.state, checkpointed) {
+                        break;
+                    }
+                }
+            }
+        }
+        scheduler.abort();
+        if let Some(probes) = probes {
+            probes.abort();
+        }
+        let flushed = self.state.blobs.flush();
+        connections.shutdown().await;
+        Ok(flushed?)
+    }
+
+    fn serve(&self, connections: &mut JoinSet<()>, (stream, peer): (L::Conn, IpAddr)) {
+        // Forgets the connections that have closed.
+        while connections.try_join_next().is_some() {}
+        let state = self.state.clone();
+        connections.spawn(async move {
+            if let Err(err) = serve_connection(state, stream, peer).await {
+                eprintln!("connection from {}: {}", peer, err);
+            }
+        });
+    }
+}
+
+// Whether `metadata` asks for per-step flags, which are screened in batches.
+fn streams(metadata: &SessionMetadata) -> bool {
+    metadata.reveal.unwrap_or_default() == RevealPolicy::PerIndex && metadata.mask.is_none()
+}
+
+// Whether a draining daemon still waits for some job: any queued or running one, or once
+// they are `checkpointed`, the streamed jobs still screening their last batch.
+fn awaits_jobs(state: &State, checkpointed: bool) -> bool {
+    state
+        .jobs
+        .lock()
+        .unwrap()
+        .values()
+        .any(|job| match job.status {
+            JobStatus::Queued => !checkpointed,
+            JobStatus::Running => !checkpointed || streams(&job.metadata),
+            _ => false,
+        })
+}
+
+// Stops the queued and running jobs at the end of the drain timeout.
+fn checkpoint_jobs(state: &State) {
+    for (job, entry) in state.jobs.lock().unwrap().iter() {
+        if !matches!(entry.status, JobStatus::Queued | JobStatus::Running) {
+            continue;
+        }
+        entry.cancel.store(true, Ordering::SeqCst);
+        if entry.status == JobStatus::Running && !streams(&entry.metadata) {
+            state.alert(format!(
+                "job {} abandoned at shutdown: it is screened in one piece",
+                job
+            ));
+        }
+    }
+}
+
+async fn serve_connection(
+    state: Arc<State>,
+    mut stream: impl Connection,
+    client: IpAddr,
+) -> Result<(), ServiceError> {
+    loop {
+        let request: Request = match read_frame(&mut stream).await {
+            Ok(request) => request,
+            // Client closed the connection.
+            Err(err)
+                if err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == std::io::ErrorKind::UnexpectedEof) =>
+            {
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        // Requests read and write blobs, which may block.
+        let handled = {
+            let state = state.clone();
+            tokio::task::spawn_blocking(move || handle(&state, client, request)).await?
+        };
+        let response = handled.unwrap_or_else(|err| match err.downcast::<QuotaError>() {
+            Ok(quota) => Response::QuotaExceeded(*quota),
+            Err(err) => Response::Error(err.to_string()),
+        });
+        write_frame(&mut stream, &response).await?;
+    }
+}
+
+fn now_unix_s() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn handle(state: &Arc<State>, client: IpAddr, request: Request) -> Result<Response, ServiceError> {
+    match request {
+        Request::Info => Ok(Response::Info {
+            preset: state.config.preset,
+            max_jobs: state.config.max_jobs,
+        }),
+        Request::Benchmark { .. } => Ok(Response::Benchmark {
+            step_time: *state.step_time.lock().unwrap(),
+        }),
+        Request::OpenSession { hello } => {
+            if state.shutting_down.load(Ordering::SeqCst) {
+                return Err("daemon is shutting down, try another evaluator".into());
+            }
+            let (session, metadata) = Session::accept(&hello).map_err(|e| e.to_string())?;
+            // Refuse up front what would only fail after the uploads.
+            if let Some(frame) = metadata.frame {
+                check_frames(frame, state.trajectory().frame)?;
+            }
+            if let Some(kernel) = metadata.kernel {
+                kernel.kernel().map_err(|e| e.to_string())?;
+            }
+            // Decoy steps are screened like real ones, so the padded length is held to
+            // the same limit, and the daemon's own trajectory has to fit in it.
+            if let Some(len) = metadata.padded_len {
+                state.quotas.check_steps(len)?;
+                check_padded_len(state.trajectory().x.len(), len)?;
+            }
+            let active = state
+                .jobs
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|job| job.client == client && !job.status.is_finished())
+                .count();
+            state.quotas.check_jobs(active)?;
+            if state.pool.is_full() {
+                let full = PoolFull {
+                    queued: state.pool.queued(),
+                };
+                state.alert(format!("session from {} refused: {}", client, full));
+                return Err(full.into());
+            }
+            let job = {
+                let mut next = state.next_job.lock().unwrap();
+                *next += 1;
+                JobHandle::new(*next)?
+            };
+            state.jobs.lock().unwrap().insert(
+                job.id,
+                Job {
+                    client,
+                    token: job.token,
+                    metadata,
+                    session,
+                    prefix: format!("job-{}", job.id),
+                    has_server_key: false,
+                    has_trajectory: false,
+                    status: JobStatus::AwaitingUploads,
+                    upload: None,
+                    batch_steps: STREAM_BATCH_STEPS,
+                    cancel: Arc::default(),
+                    runs: 0,
+                    recurrence: None,
+                    progress: Progress::default(),
+                    sealed: HashMap::new(),
+                },
+            );
+            Ok(Response::SessionOpened { job })
+        }
+        Request::TuneSession { job, batch_steps } => {
+            if !(MIN_BATCH_STEPS..=MAX_BATCH_STEPS).contains(&batch_steps) {
+                return Err(format!(
+                    "batch size must be between {} and {} steps",
+                    MIN_BATCH_STEPS, MAX_BATCH_STEPS
+                )
+                .into());
+            }
+            with_job(state, job, |entry| {
+                entry.awaiting_uploads()?.batch_steps = batch_steps;
+                Ok(Response::Tuned)
+            })
+        }
+        Request::Upload { job, envelope } => accept_upload(state, client, job, envelope),
+        Request::BeginChunkedUpload { job, chunks } => {
+            let (prefix, replaced, missing) = with_job(state, job, |entry| {
+                let entry = entry.awaiting_uploads()?;
+                let resumed = entry
+                    .upload
+                    .as_ref()
+                    .is_some_and(|upload| upload.hashes == chunks);
+                let mut replaced = None;
+                if !resumed {
+                    replaced = entry.upload.take();
+                    entry.upload = Some(ChunkedUpload {
+                        received: vec![false; chunks.len()],
+                        hashes: chunks,
+                    });
+                }
+                let upload = entry.upload.as_ref().ok_or("no chunked upload")?;
+                Ok((entry.prefix.clone(), replaced, upload.missing()))
+            })?;
+            if let Some(replaced) = replaced {
+                replaced.remove(state.blobs.as_ref(), &prefix);
+            }
+            Ok(Response::MissingChunks(missing))
+        }
+        Request::UploadChunk { job, index, data } => {
+            let (prefix, hash) = with_job(state, job, |entry| {
+                let entry = entry.awaiting_uploads()?;
+                let upload = entry
+                    .upload
+                    .as_ref()
+                    .ok_or("no chunked upload in progress")?;
+                let hash = upload.hashes.get(index).ok_or("chunk index out of range")?;
+                Ok((entry.prefix.clone(), hash.clone()))
+            })?;
+            if sha256_hex(&data) != hash {
+                return Ok(Response::ChunkReceived { valid: false });
+            }
+            state.blobs.put(&blob(&prefix, &chunk_file(index)), &data)?;
+            with_job(state, job, |entry| {
+                // Only counts for the upload the piece was sent for.
+                if let Some(upload) = entry.awaiting_uploads()?.upload.as_mut()
+                    && upload.hashes.get(index) == Some(&hash)
+                {
+                    upload.received[index] = true;
+                }
+                Ok(Response::ChunkReceived { valid: true })
+            })
+        }
+        Request::FinishChunkedUpload { job } => {
+            let (prefix, upload) = with_job(state, job, |entry| {
+                let entry = entry.awaiting_uploads()?;
+                let upload = entry.upload.take().ok_or("no chunked upload in progress")?;
+                Ok((entry.prefix.clone(), upload))
+            })?;
+            let missing = upload.missing();
+            if !missing.is_empty() {
+                with_job(state, job, |entry| {
+                    entry.upload.get_or_insert(upload);
+                    Ok(())
+                })?;
+                return Ok(Response::MissingChunks(missing));
+            }
+            let mut envelope = Vec::new();
+            for i in 0..upload.hashes.len() {
+                envelope.extend(state.blobs.read(&blob(&prefix, &chunk_file(i)))?);
+            }
+            upload.remove(state.blobs.as_ref(), &prefix);
+            accept_upload(state, client, job, envelope)
+        }
+        Request::Jobs => {
+            if !client.is_loopback() {
+                return Err("jobs are only listed to clients on the daemon's host".into());
+            }
+            let mut jobs: Vec<JobSummary> = state
+                .jobs
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(&job, entry)| entry.summary(job))
+                .collect();
+            jobs.sort_by_key(|summary| summary.job);
+            let alerts = state.alerts.lock().unwrap().iter().cloned().collect();
+            Ok(Response::Jobs(JobsSnapshot { jobs, alerts }))
+        }
+        Request::Status { job } => with_job(state, job, |entry| {
+            Ok(Response::Status(entry.status.clone()))
+        }),
+        Request::Results { job } => {
+            with_job(state, job, |entry| {
+                if entry.status != JobStatus::Done {
+                    return Err(format!("job isn't done: {:?}", entry.status).into());
+                }
+                Ok(())
+            })?;
+            let envelope = seal_once(state, job, Fetch::Results, |prefix| {
+                Ok(Some(state.blobs.read(&blob(prefix, "results.bin"))?))
+            })?;
+            Ok(Response::Results {
+                envelope: envelope.ok_or("no results")?,
+            })
+        }
+        Request::Certificate { job } => {
+            let (prefix, status) = with_job(state, job, |entry| {
+                if !matches!(entry.status, JobStatus::Done | JobStatus::Cancelled { .. }) {
+                    return Err(format!("job isn't done: {:?}", entry.status).into());
+                }
+                Ok((entry.prefix.clone(), entry.status.clone()))
+            })?;
+            let certificate = match state.blobs.get(&blob(&prefix, "certificate.bin"))? {
+                Some(certificate) => certificate,
+                // Cancelled before screening anything.
+                None if status == (JobStatus::Cancelled { steps: 0 }) => WorkCertificate::default()
+                    .to_bytes()
+                    .map_err(|e| e.to_string())?,
+                None => return Err("only streamed per-step screenings are certified".into()),
+            };
+            Ok(Response::Certificate { certificate })
+        }
+        Request::ResultBatch { job, batch } => {
+            let finished = with_job(state, job, |entry| {
+                if entry.metadata.reveal.unwrap_or_default() != RevealPolicy::PerIndex {
+                    return Err("only per-step results are streamed".into());
+                }
+                if entry.metadata.mask.is_some() {
+                    return Err("masked screenings are not streamed".into());
+                }
+                // Batches are all written before the job is marked done.
+                let finished = match &entry.status {
+                    JobStatus::Done | JobStatus::Cancelled { .. } => true,
+                    JobStatus::Failed(reason) => {
+                        return Err(format!("job failed: {}", reason).into());
+                    }
+                    _ => false,
+                };
+                Ok(finished)
+            })?;
+            let envelope = seal_once(state, job, Fetch::Batch(batch), |prefix| {
+                Ok(state.blobs.get(&blob(prefix, &batch_file(batch)))?)
+            })?;
+            Ok(Response::ResultBatch {
+                finished: finished && envelope.is_none(),
+                envelope,
+            })
+        }
+        Request::Cancel { job } => {
+            let (prefix, upload) = with_job(state, job, |entry| {
+                // Cancelling a recurring job also ends the recurrence.
+                let recurred = entry.recurrence.take().is_some();
+                let mut upload = None;
+                match entry.status {
+                    JobStatus::AwaitingUploads => {
+                        upload = entry.upload.take();
+                        entry.status = JobStatus::Cancelled { steps: 0 };
+                    }
+                    JobStatus::Queued | JobStatus::Running => {
+                        entry.cancel.store(true, Ordering::SeqCst);
+                    }
+                    _ if recurred => {}
+                    _ => return Err(format!("job has already ended: {:?}", entry.status).into()),
+                }
+                Ok((entry.prefix.clone(), upload))
+            })?;
+            if let Some(upload) = upload {
+                upload.remove(state.blobs.as_ref(), &prefix);
+            }
+            Ok(Response::Cancelling)
+        }
+        Request::Recur { job, every } => {
+            if every.is_zero() {
+                return Err("a recurring job needs a cadence".into());
+            }
+            let current = state.ephemerides.read().unwrap().sha256.clone();
+            with_job(state, job, |entry| {
+                if matches!(
+                    entry.status,
+                    JobStatus::AwaitingUploads | JobStatus::Cancelled { .. }
+                ) {
+                    return Err(format!("job can't recur: {:?}", entry.status).into());
+                }
+                entry.recurrence = Some(Recurrence::new(every, Instant::now(), current));
+                Ok(Response::Recurring)
+            })
+        }
+        Request::Runs { job } => with_job(state, job, |entry| {
+            Ok(Response::Runs {
+                completed: entry.runs,
+                recurring: entry.recurrence.is_some(),
+            })
+        }),
+        Request::PartialResults { job } => {
+            with_job(state, job, |entry| {
+                if !matches!(entry.status, JobStatus::Done | JobStatus::Cancelled { .. }) {
+                    return Err(format!("job hasn't stopped: {:?}", entry.status).into());
+                }
+                if entry.metadata.reveal.unwrap_or_default() != RevealPolicy::PerIndex
+                    || entry.metadata.mask.is_some()
+                {
+                    return Err("only unmasked per-step results are kept in batches".into());
+                }
+                Ok(())
+            })?;
+            let envelope = seal_once(state, job, Fetch::PartialResults, |prefix| {
+                // The batches are written in step order, so they form a prefix of the steps.
+                let mut flags = Vec::new();
+                let mut first_index = None;
+                for batch in (0..).map(batch_file) {
+                    let Some(payload) = state.blobs.get(&blob(prefix, &batch))? else {
+                        break;
+                    };
+                    let batch = ResultBatch::from_bytes(&payload).map_err(|e| e.to_string())?;
+                    first_index.get_or_insert(batch.first_index);
+                    flags.extend(batch.flags);
+                }
+                let batch = ResultBatch {
+                    first_index: first_index.unwrap_or_default(),
+                    flags,
+                };
+                Ok(Some(batch.to_bytes().map_err(|e| e.to_string())?))
+            })?;
+            Ok(Response::PartialResults {
+                envelope: envelope.ok_or("no partial results")?,
+            })
+        }
+    }
+}
+
+// Runs `f` on the entry of `job` under the jobs lock. Blob I/O goes between such calls,
+// never inside one, so a large upload or download doesn't hold up other clients.
+//
+// A `job` whose token doesn't match is refused like one that was never opened.
+fn with_job<T>(
+    state: &State,
+    job: JobHandle,
+    f: impl FnOnce(&mut Job) -> Result<T, ServiceError>,
+) -> Result<T, ServiceError> {
+    let mut jobs = state.jobs.lock().unwrap();
+    match jobs.get_mut(&job.id) {
+        Some(entry) if job.opens(&entry.token) => f(entry),
+        _ => Err("unknown job".into()),
+    }
+}
+
+// Stores an uploaded `ServerKey`, `EncryptedTrajectory` or `ArtifactRef` envelope and
+// queues the job once it has both artifacts.
+//
+// Checking the envelope and parsing its payload can take a while for a large one, so
+// they run on a copy of the job's session outside the jobs lock. The copy replaces the
+// session only if no other upload to the job was accepted in the meantime.
+fn accept_upload(
+    state: &Arc<State>,
+    client: IpAddr,
+    job: JobHandle,
+    envelope: Vec<u8>,
+) -> Result<Response, ServiceError> {
+    let (mut session, metadata) = with_job(state, job, |entry| {
+        let entry = entry.awaiting_uploads()?;
+        Ok((entry.session.clone(), entry.metadata.clone()))
+    })?;
+    let transcript = session.transcript();
+    let envelope = session.receive(&envelope).map_err(|e| e.to_string())?;
+    let received = read_upload(state, envelope);
+    // The session has moved past this upload whether or not its payload is usable.
+    let (prefix, kind, file, contents) = with_job(state, job, |entry| {
+        let entry = entry.awaiting_uploads()?;
+        if entry.session.transcript() != transcript {
+            return Err("another upload to this job was accepted first, retry".into());
+        }
+        entry.session = session;
+        let (kind, file, contents, steps) = received?;
+        if let Some(steps) = steps {
+            // A rejected job can't continue, so it stops counting as open.
+            if let Err(err) = charge_trajectory(state, client, &metadata, steps) {
+                entry.status = JobStatus::Failed(err.to_string());
+                return Err(err);
+            }
+        }
+        Ok((entry.prefix.clone(), kind, file, contents))
+    })?;
+    state.blobs.put(&blob(&prefix, &file), &contents)?;
+    with_job(state, job, |entry| {
+        let entry = entry.awaiting_uploads()?;
+        match kind {
+            MessageKind::ServerKey => entry.has_server_key = true,
+            _ => entry.has_trajectory = true,
+        }
+        if entry.has_server_key && entry.has_trajectory {
+            // The artifacts are stored, so a job the pool refuses can't be retried.
+            if let Err(err) = start_job(
+                state,
+                job.id,
+                client,
+                entry.metadata.clone(),
+                entry.prefix.clone(),
+                entry.batch_steps,
+                entry.cancel.clone(),
+            ) {
+                entry.status = JobStatus::Failed(err.to_string());
+                return Err(err.into());
+            }
+            entry.status = JobStatus::Queued;
+        }
+        Ok(Response::Uploaded)
+    })
+}
+
+// The artifact kind, blob file and contents an upload is stored as and, for an inline
+// trajectory, the number of steps to charge for it. Referenced artifacts are only
+// recorded here; the job fetches and charges them.
+type Upload = (MessageKind, String, Vec<u8>, Option<usize>);
+
+fn read_upload(state: &State, envelope: Envelope) -> Result<Upload, ServiceError> {
+    if envelope.kind == MessageKind::ArtifactRef {
+        if state.config.object_store.is_none() {
+            return Err("this daemon has no object store configured".into());
+        }
+        let pointer: ArtifactPointer = bincode::deserialize(&envelope.payload)?;
+        check_key(&pointer.artifact.key)?;
+        let file = artifact_file(pointer.kind)?;
+        return Ok((
+            pointer.kind,
+            format!("{}.ref", file),
+            bincode::serialize(&pointer.artifact)?,
+            None,
+        ));
+    }
+    let file = artifact_file(envelope.kind)?;
+    let steps = if envelope.kind == MessageKind::EncryptedTrajectory {
+        let parsed = SerializedTrajectory::parse(&envelope.payload).map_err(|e| e.to_string())?;
+        Some(parsed.x.len())
+    } else {
+        None
+    };
+    Ok((envelope.kind, file.to_string(), envelope.payload, steps))
+}
+
+// The envelope answering `fetch`, sealed around what `payload` reads from the job's blobs
+// the first time and resent as is on every retry: sealing it again would take the next
+// sequence number, which the owner, still waiting for this one, would refuse. `None` if
+// there's nothing to seal yet.
+fn seal_once(
+    state: &State,
+    job: JobHandle,
+    fetch: Fetch,
+    payload: impl FnOnce(&str) -> Result<Option<Vec<u8>>, ServiceError>,
+) -> Result<Option<Vec<u8>>, ServiceError> {
+    let (prefix, sealed) = with_job(state, job, |entry| {
+        Ok((entry.prefix.clone(), entry.sealed.get(&fetch).cloned()))
+    })?;
+    let key = blob(&prefix, &fetch.file());
+    match sealed {
+        Some(Some(envelope)) => return Ok(Some(envelope.to_vec())),
+        Some(None) => return Ok(Some(state.blobs.read(&key)?)),
+        None => {}
+    }
+    let Some(payload) = payload(&prefix)? else {
+        return Ok(None);
+    };
+    let (envelope, fresh) = with_job(state, job, |entry| {
+        // Another fetch of the same envelope may have sealed it in the meantime.
+        if let Some(sealed) = entry.sealed.get(&fetch) {
+            return Ok((sealed.clone(), false));
+        }
+        let envelope = entry
+            .session
+            .send(fetch.kind(), payload)
+            .map_err(|e| e.to_string())?;
+        let envelope = Arc::new(envelope);
+        entry.sealed.insert(fetch, Some(envelope.clone()));
+        Ok((Some(envelope), true))
+    })?;
+    let Some(envelope) = envelope else {
+        return Ok(Some(state.blobs.read(&key)?));
+    };
+    if fresh {
+        state.blobs.put(&key, &envelope)?;
+        with_job(state, job, |entry| {
+            if let Some(sealed) = entry.sealed.get_mut(&fetch) {
+                *sealed = None;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(Some(envelope.to_vec()))
+}
+
+// Key of the job blob `name`.
+fn blob(prefix: &str, name: &str) -> String {
+    format!("{}/{}", prefix, name)
+}
+
+fn batch_file(batch: usize) -> String {
+    format!("batch-{}.bin", batch)
+}
+
+fn chunk_file(index: usize) -> String {
+    format!("chunk-{}.part", index)
+}
+
+fn artifact_file(kind: MessageKind) -> Result<&'static str, ServiceError> {
+    match kind {
+        MessageKind::ServerKey => Ok("server_key.bin"),
+        MessageKind::EncryptedTrajectory => Ok("trajectory.bin"),
+        found => Err(ProtocolError::UnexpectedMessage {
+            expected: MessageKind::EncryptedTrajectory,
+            found,
+        }
+        .to_string()
+        .into()),
+    }
+}
+
+// Reads an uploaded artifact, fetching and verifying it if only a reference was sent.
+fn read_artifact(
+    state: &State,
+    prefix: &str,
+    file: &str,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let Some(reference) = state.blobs.get(&blob(prefix, &format!("{}.ref", file)))? else {
+        return Ok(state.blobs.read(&blob(prefix, file))?);
+    };
+    let artifact: ArtifactRef = bincode::deserialize(&reference)?;
+    let store = state
+        .config
+        .object_store
+        .as_ref()
+        .ok_or("no object store configured")?;
+    fetch_verified(store.store(), &artifact)
+}
+
+// Decodes the configured `prewarm_keys` and starts their evaluation workers. A key that
+// can't be read is reported and left for the jobs to decode.
+fn prewarm_keys(state: &State) {
+    for path in &state.config.prewarm_keys {
+        let warmed = std::fs::read(path).map_err(|e| e.into()).and_then(|bytes| {
+            let key = migrate::decode(ArtifactKind::ServerKey, &bytes)?;
+            let context = FheContext::from_server_key(key)?;
+            context.evaluate_with(|| Ok::<_, Box<dyn std::error::Error>>(()))?;
+            Ok::<_, Box<dyn std::error::Error>>((sha256_hex(&bytes), context))
+        });
+        match warmed {
+            Ok((sha256, context)) => {
+                state
+                    .warm_keys
+                    .write()
+                    .unwrap()
+                    .insert(sha256, Arc::new(context));
+            }
+            Err(err) => eprintln!("pre-warming {}: {}", path.display(), err),
+        }
+    }
+    state.warmed.store(true, Ordering::SeqCst);
+}
+
+fn set_status(state: &State, job: JobId, status: JobStatus) {
+    if let Some(entry) = state.jobs.lock().unwrap().get_mut(&job) {
+        match &status {
+            JobStatus::Running => {
+                entry.progress = Progress {
+                    started: Some(Instant::now()),
+                    ..Default::default()
+                };
+            }
+            JobStatus::Done => {
+                entry.runs += 1;
+                entry.progress.screened = entry.progress.steps;
+            }
+            JobStatus::Failed(reason) => state.alert(format!("job {} failed: {}", job, reason)),
+            JobStatus::Cancelled { steps } => {
+                state.alert(format!("job {} cancelled after {} steps", job, steps))
+            }
+            JobStatus::AwaitingUploads | JobStatus::Queued => {}
+        }
+        if status.is_finished() {
+            entry.progress.elapsed = entry.progress.started.map(|started| started.elapsed());
+        }
+        entry.status = status;
+    }
+}
+
+fn update_progress(state: &State, job: JobId, update: impl FnOnce(&mut Progress)) {
+    if let Some(entry) = state.jobs.lock().unwrap().get_mut(&job) {
+        update(&mut entry.progress);
+    }
+}
+
+// Reloads the evaluator's trajectory when its file changes and starts the recurring runs
+// that are due, every `SCHEDULER_TICK`.
+async fn run_scheduler(state: Arc<State>) {
+    let mut tick = tokio::time::interval(SCHEDULER_TICK);
+    loop {
+        tick.tick().await;
+        let state = state.clone();
+        // Both read files.
+        let _ = tokio::task::spawn_blocking(move || {
+            if let Err(err) = reload_ephemerides(&state) {
+                eprintln!("reloading {}: {}", state.config.trajectory.display(), err);
+            }
+            start_due_runs(&state);
+        })
+        .await;
+    }
+}
+
+fn reload_ephemerides(state: &State) -> Result<(), ServiceError> {
+    let bytes = std::fs::read(&state.config.trajectory)?;
+    let sha256 = sha256_hex(&bytes);
+    if state.ephemerides.read().unwrap().sha256 == sha256 {
+        return Ok(());
+    }
+    let trajectory = Arc::new(bincode::deserialize(&bytes)?);
+    *state.ephemerides.write().unwrap() = Ephemerides { trajectory, sha256 };
+    Ok(())
+}
+
+fn start_due_runs(state: &Arc<State>) {
+    if state.shutting_down.load(Ordering::SeqCst) {
+        return;
+    }
+    let current = state.ephemerides.read().unwrap().sha256.clone();
+    let now = Instant::now();
+    // Marked queued under the lock, so no run starts twice; their blobs are cleared
+    // outside it.
+    let mut due = Vec::new();
+    for (&job, entry) in state.jobs.lock().unwrap().iter_mut() {
+        let is_due = matches!(entry.status, JobStatus::Done | JobStatus::Failed(_))
+            && entry
+                .recurrence
+                .as_ref()
+                .is_some_and(|recurrence| recurrence.due(now, &current));
+        // A full queue only delays the run to a later tick; the runs marked so far will
+        // join it.
+        if !is_due || state.pool.queued() + due.len() >= state.config.queue_depth {
+            continue;
+        }
+        if let Some(recurrence) = &mut entry.recurrence {
+            recurrence.started(now, &current);
+        }
+        entry.status = JobStatus::Queued;
+        // The next run's results are sealed afresh.
+        entry.sealed.clear();
+        due.push((
+            JobHandle {
+                id: job,
+                token: entry.token,
+            },
+            entry.client,
+            entry.metadata.clone(),
+            entry.prefix.clone(),
+        ));
+    }
+    for (job, client, metadata, prefix) in due {
+        let started = clear_run(state, client, &metadata, &prefix).and_then(|()| {
+            with_job(state, job, |entry| {
+                start_job(
+                    state,
+                    job.id,
+                    client,
+                    entry.metadata.clone(),
+                    prefix,
+                    entry.batch_steps,
+                    entry.cancel.clone(),
+                )?;
+                Ok(())
+            })
+        });
+        if let Err(err) = started {
+            state.alert(format!("job {} failed to rerun: {}", job.id, err));
+            let _ = with_job(state, job, |entry| {
+                entry.status = JobStatus::Failed(err.to_string());
+                Ok(())
+            });
+        }
+    }
+}
+
+// Charges a trajectory of `steps` steps to `client`. Decoys up to the padded length are
+// screened like real steps, so they are charged like them.
+fn charge_trajectory(
+    state: &State,
+    client: IpAddr,
+    metadata: &SessionMetadata,
+    steps: usize,
+) -> Result<(), ServiceError> {
+    if let Some(len) = metadata.padded_len {
+        check_padded_len(steps, len)?;
+    }
+    let charged = steps.max(metadata.padded_len.unwrap_or(0));
+    state.quotas.charge_steps(client, charged, now_unix_s())?;
+    Ok(())
+}
+
+// Clears the outputs of a job's last run, before it runs again.
+fn clear_run(
+    state: &State,
+    client: IpAddr,
+    metadata: &SessionMetadata,
+    prefix: &str,
+) -> Result<(), ServiceError> {
+    // Referenced trajectories are charged by every run, inline ones only on upload.
+    if !state.blobs.contains(&blob(prefix, "trajectory.bin.ref"))? {
+        let trajectory = state.blobs.read(&blob(prefix, "trajectory.bin"))?;
+        let steps = SerializedTrajectory::parse(&trajectory)
+            .map_err(|e| e.to_string())?
+            .x
+            .len();
+        charge_trajectory(state, client, metadata, steps)?;
+    }
+    state.blobs.delete(&blob(prefix, "results.bin"))?;
+    state.blobs.delete(&blob(prefix, "certificate.bin"))?;
+    state.blobs.delete(&blob(prefix, &Fetch::Results.file()))?;
+    state
+        .blobs
+        .delete(&blob(prefix, &Fetch::PartialResults.file()))?;
+    for batch in 0.. {
+        let key = blob(prefix, &batch_file(batch));
+        if !state.blobs.contains(&key)? {
+            break;
+        }
+        state.blobs.delete(&key)?;
+        state
+            .blobs
+            .delete(&blob(prefix, &Fetch::Batch(batch).file()))?;
+    }
+    Ok(())
+}
+
+// Queues the job on the evaluation pool and records its outcome when done.
+fn start_job(
+    state: &Arc<State>,
+    job: JobId,
+    client: IpAddr,
+    metadata: SessionMetadata,
+    prefix: String,
+    batch_steps: usize,
+    cancel: Arc<AtomicBool>,
+) -> Result<(), PoolFull> {
+    let worker = state.clone();
+    let outcome = state.pool.submit(move || {
+        set_status(&worker, job, JobStatus::Running);
+        match evaluate_job(
+            &worker,
+            job,
+            client,
+            &metadata,
+            &prefix,
+            batch_steps,
+            &cancel,
+        ) {
+            Ok(()) => JobStatus::Done,
+            Err(err) => match err.downcast_ref::<Cancelled>() {
+                Some(cancelled) => JobStatus::Cancelled {
+                    steps: cancelled.steps,
+                },
+                None => JobStatus::Failed(err.to_string()),
+            },
+        }
+    })?;
+    let state = state.clone();
+    tokio::spawn(async move {
+        let status = match outcome.await {
+            Ok(Ok(status)) => status,
+            Ok(Err(_)) => JobStatus::Failed("evaluation panicked".to_string()),
+            Err(_) => JobStatus::Failed("evaluation was dropped".to_string()),
+        };
+        set_status(&state, job, status);
+    });
+    Ok(())
+}
+
+// Aggregates `results` as the owner's policy allows, choosing the aggregation for the
+// daemon's parameters.
+fn revealed(
+    state: &State,
+    config: &ScreeningConfig,
+    policy: RevealPolicy,
+    results: Vec<FheBool>,
+) -> RevealedResult {
+    let aggregation = config
+        .aggregation
+        .select(results.len(), state.config.preset);
+    reveal_with(policy, results, aggregation)
+}
+
+fn evaluate_job(
+    state: &State,
+    job: JobId,
+    client: IpAddr,
+    metadata: &SessionMetadata,
+    prefix: &str,
+    batch_steps: usize,
+    cancel: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Cancelled while waiting for a thread.
+    if cancel.load(Ordering::SeqCst) {
+        return Err(Cancelled { steps: 0 }.into());
+    }
+    let server_key = read_artifact(state, prefix, "server_key.bin")?;
+    let warm = {
+        let warm_keys = state.warm_keys.read().unwrap();
+        // Hashing a key takes a while too, so only when there's a key to match.
+        if warm_keys.is_empty() {
+            None
+        } else {
+            warm_keys.get(&sha256_hex(&server_key)).cloned()
+        }
+    };
+    let trajectory = read_artifact(state, prefix, "trajectory.bin")?;
+    // Ciphertexts under other parameters than the key's would evaluate to garbage.
+    if let Some(parameters) = migrate::meta_of(ArtifactKind::ServerKey, &server_key)?.parameters {
+        compat::check_parameters(
+            &migrate::meta_of(ArtifactKind::Trajectory, &trajectory)?,
+            parameters,
+        )?;
+    }
+    if state.blobs.contains(&blob(prefix, "trajectory.bin.ref"))? {
+        let steps = SerializedTrajectory::parse(&trajectory)?.x.len();
+        charge_trajectory(state, client, metadata, steps).map_err(|e| e.to_string())?;
+    }
+    let encrypted = EncryptedTrajectory::from_bytes(&trajectory)?;
+    update_progress(state, job, |progress| progress.steps = encrypted.len());
+    let counterpart = state.trajectory();
+    let plaintext = match metadata.padded_len {
+        Some(len) => Cow::Owned(pad(&counterpart, len, EVALUATOR_SENTINEL)?),
+        None => Cow::Borrowed(counterpart.as_ref()),
+    };
+    let context = match warm {
+        Some(context) => context,
+        None => Arc::new(FheContext::from_server_key(migrate::decode(
+            ArtifactKind::ServerKey,
+            &server_key,
+        )?)?),
+    };
+    // Only the aggregate the owner's policy allows ever reaches the results file.
+    let reveal_policy = metadata.reveal.unwrap_or_default();
+    let config = ScreeningConfig {
+        kernel: metadata.kernel.unwrap_or_default(),
+        ..Default::default()
+    };
+    let started = Instant::now();
+    let result = context.evaluate_with(|| {
+        if let Some(mask) = &metadata.mask {
+            return screen_masked(&encrypted, &plaintext, mask, &config).map(|output| {
+                state.meter(job, client, output.results.len(), output.ops);
+                revealed(state, &config, reveal_policy, output.results)
+            });
+        }
+        if reveal_policy != RevealPolicy::PerIndex {
+            return screen_planned(&encrypted, Operand::Clear(&plaintext), &config).map(|output| {
+                state.meter(job, client, output.results.len(), output.ops);
+                revealed(state, &config, reveal_policy, output.results)
+            });
+        }
+        let step_cost = config.kernel.kernel()?.cost();
+        let mut flags = Vec::with_capacity(encrypted.len());
+        let mut certificate = WorkCertificate::default();
+        let mut batches = 0;
+        let streamed = screen_streaming(
+            &encrypted,
+            &plaintext,
+            &config,
+            batch_steps,
+            &StepOrder::Chronological,
+            |batch| {
+                // Blob stores never expose a half-written batch.
+                state
+                    .blobs
+                    .put(&blob(prefix, &batch_file(batches)), &batch.to_bytes()?)?;
+                batches += 1;
+                let start = batch.first_index - encrypted.first_index;
+                certificate.record(&encrypted, start..start + batch.flags.len(), &batch.flags)?;
+                let mut ops = OpCounter::default();
+                ops.add_steps(&step_cost, batch.len() as u64);
+                state.meter(job, client, batch.len(), ops);
+                flags.extend(batch.flags);
+                let screened = flags.len();
+                update_progress(state, job, |progress| {
+                    progress.screened = screened;
+                    progress.batches = batches;
+                });
+                if cancel.load(Ordering::SeqCst) && flags.len() < encrypted.len() {
+                    return Err(Cancelled { steps: flags.len() }.into());
+                }
+                Ok(())
+            },
+        );
+        // Certifies the batches already written even if the job was cancelled.
+        state
+            .blobs
+            .put(&blob(prefix, "certificate.bin"), &certificate.to_bytes()?)?;
+        streamed?;
+        Ok::<_, Box<dyn std::error::Error>>(RevealedResult::PerIndex(flags))
+    })?;
+    if !encrypted.is_empty() {
+        *state.step_time.lock().unwrap() = Some(started.elapsed() / encrypted.len() as u32);
+    }
+    state
+        .blobs
+        .put(&blob(prefix, "results.bin"), &result.to_bytes()?)?;
+    Ok(())
+}
@@ -0,0 +1,281 @@
+// Wire format between the evaluator daemon (`sat-fhe-serve`) and its clients (`client`).
+//
+// Every message is a `u32` big-endian length followed by a bincode `Request` or
+// `Response`. The screening artifacts themselves travel inside session `Envelope`s
+// (see `session`), so the daemon enforces the same nonce and ordering rules as a direct
+// two-party exchange.
+
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::preset::ParameterPreset;
+use crate::quota::QuotaError;
+
+pub type JobId = u64;
+
+pub type JobToken = [u8; 16];
+
+// A job as its owner addresses it. IDs are sequential, so the daemon also checks the
+// random `token` it drew when it opened the job: without it, a job is as unknown as one
+// that was never opened, whoever asks.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobHandle {
+    pub id: JobId,
+    pub token: JobToken,
+}
+
+impl JobHandle {
+    pub fn new(id: JobId) -> Result<Self, getrandom::Error> {
+        let mut token = JobToken::default();
+        getrandom::getrandom(&mut token)?;
+        Ok(Self { id, token })
+    }
+
+    // Compares the tokens in constant time, so response times don't reveal how much of a
+    // guess was right.
+    pub fn opens(&self, token: &JobToken) -> bool {
+        self.token
+            .iter()
+            .zip(token)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+pub type ServiceError = Box<dyn std::error::Error + Send + Sync>;
+
+// Largest frame accepted; server keys are a few hundred MB.
+pub const MAX_FRAME_BYTES: usize = 1 << 30;
+
+// Envelopes larger than this are uploaded in pieces of this size (see
+// `Request::BeginChunkedUpload`), so a broken link only costs the pieces it hit.
+pub const CHUNK_BYTES: usize = 8 << 20;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    // The session is open but the server key or the trajectory hasn't arrived yet.
+    AwaitingUploads,
+    // Waiting for a free evaluation slot.
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    // Stopped by `Request::Cancel` after screening the first `steps` steps, whose flags
+    // `Request::PartialResults` still returns.
+    Cancelled { steps: usize },
+}
+
+impl JobStatus {
+    // The job won't change status any more.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed(_) | JobStatus::Cancelled { .. }
+        )
+    }
+}
+
+// Progress of one job, as `Request::Jobs` reports it to operators.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JobSummary {
+    pub job: JobId,
+    pub status: JobStatus,
+    // Steps of the uploaded trajectory; 0 until its evaluation starts.
+    pub steps: usize,
+    // Steps screened and result batches stored so far in the current run.
+    pub screened: usize,
+    pub batches: usize,
+    pub batch_steps: usize,
+    // Time the current run has been evaluating, or took once it finished.
+    pub elapsed: Duration,
+}
+
+impl JobSummary {
+    // Screened share of the steps, between 0 and 1.
+    pub fn progress(&self) -> f64 {
+        if self.steps == 0 {
+            return 0.0;
+        }
+        (self.screened as f64 / self.steps as f64).min(1.0)
+    }
+
+    // Result batches the run will store in all.
+    pub fn total_batches(&self) -> usize {
+        self.steps.div_ceil(self.batch_steps.max(1))
+    }
+
+    pub fn steps_per_sec(&self) -> Option<f64> {
+        let secs = self.elapsed.as_secs_f64();
+        (self.screened > 0 && secs > 0.0).then(|| self.screened as f64 / secs)
+    }
+}
+
+// Every job the daemon knows, by ID, and its latest alerts, oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobsSnapshot {
+    pub jobs: Vec<JobSummary>,
+    pub alerts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Info,
+    // Answered right away with the daemon's step time, so the client can time the round
+    // trip; `probe` is ignored, its size measures throughput (see `tuning`).
+    Benchmark {
+        probe: Vec<u8>,
+    },
+    // Opens a job from the owner's `Hello` envelope.
+    OpenSession {
+        hello: Vec<u8>,
+    },
+    // Streams the job's per-step flags in batches of `batch_steps` instead of
+    // `serve::STREAM_BATCH_STEPS`; only before the uploads are complete.
+    TuneSession {
+        job: JobHandle,
+        batch_steps: usize,
+    },
+    // A `ServerKey` or `EncryptedTrajectory` envelope of the job's session.
+    Upload {
+        job: JobHandle,
+        envelope: Vec<u8>,
+    },
+    // Announces an upload envelope sent in pieces, by the hex SHA-256 of every piece in
+    // order. Announcing the same pieces again resumes the upload; the daemon answers with
+    // the pieces it still needs.
+    BeginChunkedUpload {
+        job: JobHandle,
+        chunks: Vec<String>,
+    },
+    UploadChunk {
+        job: JobHandle,
+        index: usize,
+        data: Vec<u8>,
+    },
+    // Handles the reassembled envelope like `Upload` once every piece has arrived intact.
+    FinishChunkedUpload {
+        job: JobHandle,
+    },
+    Status {
+        job: JobHandle,
+    },
+    // The `Results` envelope of a finished job.
+    Results {
+        job: JobHandle,
+    },
+    // The `ResultBatch` envelope with the `batch`th batch of per-step flags, available
+    // while the job is still running.
+    ResultBatch {
+        job: JobHandle,
+        batch: usize,
+    },
+    // The `certificate::WorkCertificate` of a finished job with per-step results; for a
+    // cancelled job it covers the steps screened before it stopped.
+    Certificate {
+        job: JobHandle,
+    },
+    // Stops the job, e.g. to free the daemon for a more urgent screening. A queued or
+    // running job stops after the batch being screened; poll `Status` for `Cancelled`.
+    Cancel {
+        job: JobHandle,
+    },
+    // Screens the job again, at most once per `every`, whenever the daemon's counterpart
+    // ephemerides have changed since its last run (see `recurring`).
+    Recur {
+        job: JobHandle,
+        every: Duration,
+    },
+    Runs {
+        job: JobHandle,
+    },
+    // One `ResultBatch` envelope with the flags of every step a cancelled or finished
+    // per-step job screened, from its first step.
+    PartialResults {
+        job: JobHandle,
+    },
+    // Progress of every job and the daemon's recent alerts, for `sat-fhe monitor`. Other
+    // clients' jobs are listed, so only clients on the daemon's own host are answered.
+    Jobs,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Info {
+        preset: ParameterPreset,
+        max_jobs: usize,
+    },
+    // Time per step of the daemon's last screening, if it ran one.
+    Benchmark {
+        step_time: Option<Duration>,
+    },
+    SessionOpened {
+        job: JobHandle,
+    },
+    Tuned,
+    Uploaded,
+    // `valid` is false if the piece didn't match its announced hash and was dropped.
+    ChunkReceived {
+        valid: bool,
+    },
+    // Indices of the pieces of a chunked upload not received intact yet.
+    MissingChunks(Vec<usize>),
+    Status(JobStatus),
+    Results {
+        envelope: Vec<u8>,
+    },
+    // `None` if the batch isn't there yet; `finished` once the job is done and there are
+    // no more batches.
+    ResultBatch {
+        envelope: Option<Vec<u8>>,
+        finished: bool,
+    },
+    Certificate {
+        certificate: Vec<u8>,
+    },
+    Cancelling,
+    Recurring,
+    // Runs of the job that finished, counting the first, and whether more will follow.
+    Runs {
+        completed: usize,
+        recurring: bool,
+    },
+    PartialResults {
+        envelope: Vec<u8>,
+    },
+    Jobs(JobsSnapshot),
+    // The request would exceed one of the client's quotas.
+    QuotaExceeded(QuotaError),
+    Error(String),
+}
+
+pub async fn write_frame<W, T>(writer: &mut W, message: &T) -> Result<(), ServiceError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let bytes = bincode::serialize(message)?;
+    if bytes.len() > MAX_FRAME_BYTES {
+        return Err(format!("frame of {} bytes is too large", bytes.len()).into());
+    }
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+pub async fn read_frame<R, T>(reader: &mut R) -> Result<T, ServiceError>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let len = reader.read_u32().await? as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(format!("frame of {} bytes is too large", len).into());
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    Ok(bincode::deserialize(&bytes)?)
+}
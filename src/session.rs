@@ -0,0 +1,435 @@
+use sha2::{Digest, Sha256};
+
+use crate::commitment::{CommitmentOpening, TrajectoryCommitment};
+use crate::common::serialization_limits;
+use crate::dry_run::{DryRunInput, DryRunReport, validate};
+use crate::fallback::{BundleFailure, Fallback, ResendRequest, open_bundle};
+use crate::grid::ResolutionRequest;
+use crate::negotiation::{EncodingParams, negotiate};
+use crate::policy::PolicyProfile;
+use crate::prescreen::{CellFilter, session_salt};
+use crate::protocol::{Envelope, MessageKind, ProtocolError, SessionMetadata, SessionNonce};
+use crate::result_bundle::ResultBundle;
+
+// One side of a screening session. Outgoing messages are stamped with the session nonce
+// and an increasing sequence number; incoming ones are only accepted if they carry the
+// same nonce and exactly the next sequence number from the peer.
+//
+// Each envelope also carries a checksum over its payload, nonce, sequence number and
+// kind, chained to the nonce and, once the `Hello` has been sent or accepted, to the
+// transcript up to it. It only catches accidental corruption, or a payload stamped into
+// the wrong slot by a buggy peer or proxy: everything it is computed from travels in
+// the clear, so anyone on the path can recompute it. It authenticates nothing; against
+// an attacker on the path, run the session over TLS (see `tls`).
+//
+// Every message sent or accepted is also folded into a running transcript hash, starting
+// with the `Hello` and the metadata (reveal policy included) it declares. Two sides that
+// exchanged the same messages end up with the same `transcript`.
+//
+// A session can also carry a commitment phase (see `commitment`): each side commits to
+// its trajectory before any trajectory artifact is exchanged, and opens the commitment
+// at the end.
+//
+// An owner can hold the session to its profile for the counterpart (see `policy`): the
+// `Hello` and `Encoding` it sends, and the encoding it accepts, are checked against it.
+//
+// Results the owner can't read are asked for again another way rather than ending the
+// session (see `fallback`), each request escalating from the last.
+#[derive(Debug, Clone)]
+pub struct Session {
+    nonce: SessionNonce,
+    next_send_seq: u64,
+    next_recv_seq: u64,
+    transcript: [u8; 32],
+    checksum_context: [u8; 32],
+    // An artifact other than session setup was sent or received.
+    exchanged: bool,
+    peer_commitment: Option<TrajectoryCommitment>,
+    policy: Option<PolicyProfile>,
+    // Fallback of the last resend this side asked for, and resends the peer asked for.
+    resend: Option<Fallback>,
+    resends_accepted: usize,
+}
+
+impl Session {
+    // Opens a new session with a fresh random nonce.
+    pub fn open() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut nonce = [0u8; 16];
+        getrandom::getrandom(&mut nonce)?;
+        Ok(Self::join(nonce))
+    }
+
+    // Joins a session opened by the peer, using the nonce it announced.
+    pub fn join(nonce: SessionNonce) -> Self {
+        Self {
+            nonce,
+            next_send_seq: 0,
+            next_recv_seq: 0,
+            transcript: [0; 32],
+            checksum_context: checksum_context(&nonce, &[0; 32]),
+            exchanged: false,
+            peer_commitment: None,
+            policy: None,
+            resend: None,
+            resends_accepted: 0,
+        }
+    }
+
+    // Refuses screenings `policy` forbids from here on.
+    pub fn with_policy(mut self, policy: PolicyProfile) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    // Joins the session announced by the peer's `Hello` message and returns the metadata
+    // it declared.
+    pub fn accept(hello: &[u8]) -> Result<(Self, SessionMetadata), Box<dyn std::error::Error>> {
+        let nonce = Envelope::from_bytes(hello)?.nonce;
+        let mut session = Self::join(nonce);
+        let envelope = session.receive(hello)?;
+        if envelope.kind != MessageKind::Hello {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Hello,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        let metadata = bincode::deserialize(&envelope.payload)?;
+        Ok((session, metadata))
+    }
+
+    // First message of the session, announcing `metadata` to the peer.
+    pub fn hello(
+        &mut self,
+        metadata: &SessionMetadata,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if let Some(policy) = &self.policy {
+            policy.check_metadata(metadata)?;
+        }
+        self.send(MessageKind::Hello, bincode::serialize(metadata)?)
+    }
+
+    // Announces how this side quantized its trajectory (see `negotiation`).
+    pub fn encoding(
+        &mut self,
+        params: &EncodingParams,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if let Some(policy) = &self.policy {
+            policy.check_encoding(params)?;
+        }
+        self.send(MessageKind::Encoding, bincode::serialize(params)?)
+    }
+
+    // Accepts the peer's `Encoding` message if it matches `ours`, which is returned.
+    pub fn accept_encoding(
+        &mut self,
+        ours: &EncodingParams,
+        message: &[u8],
+    ) -> Result<EncodingParams, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::Encoding {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Encoding,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        let theirs: EncodingParams = bincode::deserialize(&envelope.payload)?;
+        let agreed = negotiate(ours, &theirs)?;
+        if let Some(policy) = &self.policy {
+            policy.check_encoding(&agreed)?;
+        }
+        Ok(agreed)
+    }
+
+    // Tells the owner which resolution this evaluator can screen at (see `grid`).
+    pub fn request_resolution(
+        &mut self,
+        request: &ResolutionRequest,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.send(MessageKind::Resolution, bincode::serialize(request)?)
+    }
+
+    pub fn accept_resolution(
+        &mut self,
+        message: &[u8],
+    ) -> Result<ResolutionRequest, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::Resolution {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Resolution,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        Ok(bincode::deserialize(&envelope.payload)?)
+    }
+
+    // Salt both sides hash their cells with in this session (see `prescreen`).
+    pub fn cell_salt(&self) -> [u8; 32] {
+        session_salt(&self.nonce)
+    }
+
+    // Sends the salted cells this side's trajectory occupies.
+    pub fn cell_filter(
+        &mut self,
+        filter: &CellFilter,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.send(MessageKind::CellFilter, filter.to_bytes()?)
+    }
+
+    pub fn accept_cell_filter(
+        &mut self,
+        message: &[u8],
+    ) -> Result<CellFilter, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::CellFilter {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::CellFilter,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        CellFilter::from_bytes(&envelope.payload)
+    }
+
+    // Commits this side to its trajectory. Only possible before any trajectory artifact
+    // was sent or received.
+    pub fn commit(
+        &mut self,
+        commitment: &TrajectoryCommitment,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if self.exchanged {
+            return Err(ProtocolError::LateCommitment.into());
+        }
+        self.send(MessageKind::Commitment, bincode::serialize(commitment)?)
+    }
+
+    // Accepts the peer's commitment, which must arrive before this side sent any
+    // trajectory artifact.
+    pub fn accept_commitment(
+        &mut self,
+        message: &[u8],
+    ) -> Result<TrajectoryCommitment, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::Commitment {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Commitment,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        if self.exchanged {
+            return Err(ProtocolError::LateCommitment.into());
+        }
+        let commitment: TrajectoryCommitment = bincode::deserialize(&envelope.payload)?;
+        self.peer_commitment = Some(commitment);
+        Ok(commitment)
+    }
+
+    // Reveals this side's commitment salts once the exchange is over.
+    pub fn open_commitment(
+        &mut self,
+        opening: &CommitmentOpening,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.send(MessageKind::Opening, bincode::serialize(opening)?)
+    }
+
+    // Accepts the peer's opening if the `ciphertexts` it sent match its commitment. The
+    // opening is returned for checking the peer's plaintext later, if it is ever shown.
+    pub fn accept_opening(
+        &mut self,
+        message: &[u8],
+        ciphertexts: &[u8],
+    ) -> Result<CommitmentOpening, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::Opening {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Opening,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        let commitment = self
+            .peer_commitment
+            .ok_or(ProtocolError::CommitmentMismatch {
+                artifact: "ciphertexts",
+            })?;
+        let opening: CommitmentOpening = bincode::deserialize(&envelope.payload)?;
+        commitment.verify_ciphertexts(&self.nonce, &opening, ciphertexts)?;
+        Ok(opening)
+    }
+
+    // The result bundle of the peer's `Results` message, its flags decompressed, so under
+    // the server key. A bundle that can't be read is a `BundleFailure` to answer with
+    // `request_resend`; anything wrong with the message itself is an error.
+    pub fn accept_results(
+        &mut self,
+        message: &[u8],
+    ) -> Result<Result<ResultBundle, BundleFailure>, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::Results {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Results,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        Ok(open_bundle(&envelope.payload))
+    }
+
+    // Asks the peer to send the steps `first_index..first_index + steps` again after
+    // `failure`, in the next fallback (`Fallback::after`); an error once chunked bundles
+    // failed too.
+    pub fn request_resend(
+        &mut self,
+        failure: &BundleFailure,
+        first_index: usize,
+        steps: usize,
+    ) -> Result<(ResendRequest, Vec<u8>), Box<dyn std::error::Error>> {
+        let fallback = Fallback::after(failure, self.resend, &serialization_limits())
+            .ok_or_else(|| format!("giving up on results after {:?}: {}", self.resend, failure))?;
+        self.resend = Some(fallback);
+        let request = ResendRequest {
+            first_index,
+            steps,
+            fallback,
+        };
+        let message = self.send(MessageKind::Resend, bincode::serialize(&request)?)?;
+        Ok((request, message))
+    }
+
+    // The peer's `Resend` request, to answer with `fallback::rebundle`. A peer escalates
+    // at most twice (uncompressed, then chunked), so a third request is refused.
+    pub fn accept_resend(
+        &mut self,
+        message: &[u8],
+    ) -> Result<ResendRequest, Box<dyn std::error::Error>> {
+        let envelope = self.receive(message)?;
+        if envelope.kind != MessageKind::Resend {
+            return Err(ProtocolError::UnexpectedMessage {
+                expected: MessageKind::Resend,
+                found: envelope.kind,
+            }
+            .into());
+        }
+        if self.resends_accepted == MAX_RESENDS {
+            return Err(format!("peer asked for more than {} resends", MAX_RESENDS).into());
+        }
+        self.resends_accepted += 1;
+        Ok(bincode::deserialize(&envelope.payload)?)
+    }
+
+    pub fn nonce(&self) -> SessionNonce {
+        self.nonce
+    }
+
+    // SHA-256 chain over every message of the session so far, in order.
+    pub fn transcript(&self) -> [u8; 32] {
+        self.transcript
+    }
+
+    fn record(&mut self, kind: MessageKind, message: &[u8]) {
+        self.transcript = extend_transcript(self.transcript, message);
+        if kind == MessageKind::Hello {
+            self.checksum_context = checksum_context(&self.nonce, &self.transcript);
+        }
+    }
+
+    pub fn send(
+        &mut self,
+        kind: MessageKind,
+        payload: Vec<u8>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut envelope = Envelope {
+            nonce: self.nonce,
+            seq: self.next_send_seq,
+            kind,
+            payload,
+            checksum: [0; 32],
+        };
+        envelope.checksum = envelope_checksum(&self.checksum_context, &envelope);
+        self.next_send_seq += 1;
+        self.exchanged |= is_artifact(kind);
+        let bytes = envelope.to_bytes()?;
+        self.record(kind, &bytes);
+        Ok(bytes)
+    }
+
+    pub fn receive(&mut self, data: &[u8]) -> Result<Envelope, Box<dyn std::error::Error>> {
+        let envelope = Envelope::from_bytes(data)?;
+        if envelope.nonce != self.nonce {
+            return Err(ProtocolError::SessionMismatch.into());
+        }
+        if envelope.checksum != envelope_checksum(&self.checksum_context, &envelope) {
+            return Err(ProtocolError::ChecksumMismatch.into());
+        }
+        if envelope.seq != self.next_recv_seq {
+            return Err(ProtocolError::UnexpectedSequence {
+                expected: self.next_recv_seq,
+                found: envelope.seq,
+            }
+            .into());
+        }
+        self.next_recv_seq += 1;
+        self.exchanged |= is_artifact(envelope.kind);
+        self.record(envelope.kind, data);
+        Ok(envelope)
+    }
+
+    // Checks dimensions, time alignment, key fingerprints and screening parameters for
+    // the screening this session is about to run, and reports what it would compute,
+    // without doing any homomorphic work.
+    pub fn dry_run(&self, input: &DryRunInput) -> DryRunReport {
+        validate(input)
+    }
+}
+
+// Fallbacks a side can escalate through, see `Fallback::after`.
+const MAX_RESENDS: usize = 2;
+
+// Whether a message of `kind` carries a trajectory artifact or something computed from
+// one, rather than session setup.
+fn is_artifact(kind: MessageKind) -> bool {
+    !matches!(
+        kind,
+        MessageKind::Hello
+            | MessageKind::Encoding
+            | MessageKind::Resolution
+            | MessageKind::Commitment
+            | MessageKind::Opening
+            | MessageKind::Resend
+    )
+}
+
+// What a session's envelope checksums are chained to, given its transcript so far.
+fn checksum_context(nonce: &SessionNonce, transcript: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sat-fhe envelope checksum context");
+    hasher.update(nonce);
+    hasher.update(transcript);
+    hasher.finalize().into()
+}
+
+// SHA-256 over `context` and everything in `envelope` but its checksum. The payload's
+// length goes in first, so no two envelopes hash the same bytes.
+fn envelope_checksum(context: &[u8; 32], envelope: &Envelope) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sat-fhe envelope checksum");
+    hasher.update(context);
+    hasher.update(envelope.nonce);
+    hasher.update(envelope.seq.to_le_bytes());
+    hasher.update((envelope.kind as u32).to_le_bytes());
+    hasher.update((envelope.payload.len() as u64).to_le_bytes());
+    hasher.update(&envelope.payload);
+    hasher.finalize().into()
+}
+
+// The transcript after `message`, given the one before it; a session's transcript starts
+// at all zeroes.
+pub fn extend_transcript(transcript: [u8; 32], message: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(transcript);
+    hasher.update(message);
+    hasher.finalize().into()
+}
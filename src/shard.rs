@@ -0,0 +1,122 @@
+// One long screening split across several evaluator daemons.
+//
+// A screening's cost grows with the number of steps, and a window of weeks at a fine
+// time step keeps one daemon busy for days. The steps are independent, so `Sharded` cuts
+// the owner's trajectory into contiguous shards of steps (`shard_ranges`), opens one
+// session per shard on a different daemon, all holding the same counterpart trajectory,
+// and uploads the same server key to each. Shards keep their absolute step indices (see
+// `EncryptedTrajectory::steps`), so every daemon screens its part of the window against
+// the matching part of its trajectory; the per-step flags are checked against each
+// daemon's work certificate (see `certificate`) and concatenated back in step order.
+//
+// Only plain per-step results can be split this way: aggregates under another reveal
+// policy would be per shard, padding would pad every shard, and masked screenings aren't
+// certified.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use tfhe::FheBool;
+use tokio::task::JoinSet;
+
+use crate::client::Client;
+use crate::net::{TcpTransport, Transport};
+use crate::protocol::SessionMetadata;
+use crate::reveal::RevealPolicy;
+use crate::service::ServiceError;
+use crate::trajectory::EncryptedTrajectory;
+
+// How often each daemon is asked whether its shard is done.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// `len` steps cut into `shards` contiguous ranges whose lengths differ by at most one.
+// Fewer ranges if there are fewer steps than shards; none for no steps.
+pub fn shard_ranges(len: usize, shards: usize) -> Vec<Range<usize>> {
+    let shards = shards.clamp(1, len.max(1));
+    let (base, extra) = (len / shards, len % shards);
+    let mut start = 0;
+    (0..shards)
+        .map(|i| {
+            let end = start + base + usize::from(i < extra);
+            let range = start..end;
+            start = end;
+            range
+        })
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+pub struct Sharded<T: Transport = TcpTransport> {
+    evaluators: Vec<Client<T>>,
+    poll_interval: Duration,
+}
+
+impl<T: Transport> Sharded<T> {
+    // One shard per client; each must be connected to a different daemon serving the
+    // same counterpart trajectory with the same preset.
+    pub fn new(evaluators: Vec<Client<T>>) -> Self {
+        Self {
+            evaluators,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    // Screens `trajectory`, announcing `metadata` to every daemon, and returns the
+    // encrypted per-step flags of the whole trajectory. Fails if any shard fails.
+    pub async fn screen(
+        mut self,
+        metadata: &SessionMetadata,
+        server_key: Vec<u8>,
+        trajectory: &EncryptedTrajectory,
+    ) -> Result<Vec<FheBool>, ServiceError> {
+        if metadata.reveal.is_some_and(|r| r != RevealPolicy::PerIndex) {
+            return Err("sharded screenings return per-step flags only".into());
+        }
+        if metadata.padded_len.is_some() || metadata.mask.is_some() {
+            return Err("sharded screenings can't be padded or masked".into());
+        }
+        if self.evaluators.is_empty() {
+            return Err("no evaluator to shard the screening across".into());
+        }
+        let mut preset = None;
+        for client in &mut self.evaluators {
+            let info = client.info().await?.preset;
+            if preset.is_some_and(|p| p != info) {
+                return Err("the evaluators use different parameter presets".into());
+            }
+            preset = Some(info);
+        }
+
+        let ranges = shard_ranges(trajectory.len(), self.evaluators.len());
+        let mut shards = JoinSet::new();
+        for (index, (mut client, range)) in self.evaluators.into_iter().zip(ranges).enumerate() {
+            let metadata = metadata.clone();
+            let server_key = server_key.clone();
+            let shard = trajectory.steps(range);
+            let poll_interval = self.poll_interval;
+            shards.spawn(async move {
+                let mut job = client.open_session(&metadata).await?;
+                client.upload_server_key(&mut job, server_key).await?;
+                client.upload_trajectory(&mut job, &shard).await?;
+                client.wait(&job, poll_interval).await?;
+                let flags = client
+                    .verified_results(&mut job, &shard)
+                    .await
+                    .map_err(|err| format!("shard {}: {}", index, err))?;
+                Ok::<_, ServiceError>((index, flags))
+            });
+        }
+
+        let mut results: Vec<(usize, Vec<FheBool>)> = Vec::with_capacity(shards.len());
+        while let Some(done) = shards.join_next().await {
+            results.push(done??);
+        }
+        results.sort_by_key(|(index, _)| *index);
+        Ok(results.into_iter().flat_map(|(_, flags)| flags).collect())
+    }
+}
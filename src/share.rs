@@ -0,0 +1,111 @@
+// Reduced-scope copies of an encrypted trajectory, made without decrypting.
+//
+// An owner screening against several counterparts needn't show each of them the whole
+// trajectory: one only needs the hours around a planned manoeuvre, another a coarse pass.
+// These functions cut down an `EncryptedTrajectory::to_bytes` artifact by copying the
+// serialized ciphertexts of the steps kept as-is, so they need neither the client key
+// nor to deserialize a single ciphertext, and the copy keeps the TFHE-rs version and
+// parameters recorded in the original. Dropped steps are gone from the copy, not masked:
+// the counterpart can tell from the epochs which steps it wasn't given, but learns
+// nothing about them.
+//
+// Screening maps step `i` of a trajectory to absolute step `first_index + i`, so a copy
+// is a contiguous run of steps, and `redact` returns one copy per run left between the
+// dropped steps. `decimate` is the exception, like `multires::CoarseToFine::coarse`:
+// step `c` of it is absolute step `first_index + c * factor`, to be screened with
+// `multires::screen_coarse`.
+
+use crate::compat::ArtifactMeta;
+use crate::migrate::{self, ArtifactKind};
+use crate::trajectory::SerializedTrajectory;
+
+fn parse(data: &[u8]) -> Result<(ArtifactMeta, SerializedTrajectory), Box<dyn std::error::Error>> {
+    Ok((
+        migrate::meta_of(ArtifactKind::Trajectory, data)?,
+        SerializedTrajectory::parse(data)?,
+    ))
+}
+
+// The steps of `serialized` at `positions`, the first of which becomes absolute step
+// `first_index`.
+fn pick(
+    serialized: &SerializedTrajectory,
+    positions: &[usize],
+    first_index: usize,
+) -> SerializedTrajectory {
+    let axis = |axis: &[Vec<u8>]| positions.iter().map(|&i| axis[i].clone()).collect();
+    SerializedTrajectory {
+        frame: serialized.frame,
+        units: serialized.units,
+        first_index,
+        epochs: positions.iter().map(|&i| serialized.epochs[i]).collect(),
+        x: axis(&serialized.x),
+        y: axis(&serialized.y),
+        z: axis(&serialized.z),
+    }
+}
+
+// Steps whose epoch lies in `[start, end)`, like `EncryptedTrajectory::window`.
+pub fn trim(data: &[u8], start: u64, end: u64) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let (meta, serialized) = parse(data)?;
+    let from = serialized.epochs.partition_point(|&t| t < start);
+    let to = serialized.epochs.partition_point(|&t| t < end).max(from);
+    let positions: Vec<usize> = (from..to).collect();
+    migrate::encode_as(
+        ArtifactKind::Trajectory,
+        &pick(&serialized, &positions, serialized.first_index + from),
+        &meta,
+    )
+}
+
+// The trajectory without the absolute steps `drop`, as one copy per run of consecutive
+// steps left, in order.
+pub fn redact(data: &[u8], drop: &[usize]) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    let (meta, serialized) = parse(data)?;
+    let first = serialized.first_index;
+    let len = serialized.x.len();
+    let mut dropped = vec![false; len];
+    for &index in drop {
+        if !(first..first + len).contains(&index) {
+            return Err(format!(
+                "step {} to redact is outside the trajectory's steps {}..{}",
+                index,
+                first,
+                first + len
+            )
+            .into());
+        }
+        dropped[index - first] = true;
+    }
+    let mut runs: Vec<Vec<usize>> = Vec::new();
+    for i in (0..len).filter(|&i| !dropped[i]) {
+        match runs.last_mut() {
+            Some(run) if run.last().map(|&j| j + 1) == Some(i) => run.push(i),
+            _ => runs.push(vec![i]),
+        }
+    }
+    runs.iter()
+        .map(|run| {
+            migrate::encode_as(
+                ArtifactKind::Trajectory,
+                &pick(&serialized, run, first + run[0]),
+                &meta,
+            )
+        })
+        .collect()
+}
+
+// Every `factor`th step from the first, for `multires::screen_coarse` with the same
+// factor.
+pub fn decimate(data: &[u8], factor: usize) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if factor == 0 {
+        return Err("decimation factor must be at least 1".into());
+    }
+    let (meta, serialized) = parse(data)?;
+    let positions: Vec<usize> = (0..serialized.x.len()).step_by(factor).collect();
+    migrate::encode_as(
+        ArtifactKind::Trajectory,
+        &pick(&serialized, &positions, serialized.first_index),
+        &meta,
+    )
+}
@@ -0,0 +1,161 @@
+// Blinded per-object results for screening against a private catalog.
+//
+// When the evaluator screens the owner's trajectory against several catalog objects of its
+// own, one encrypted flag per object would tell the owner which object came close. The
+// evaluator can instead send the flags in an order only it knows: the owner decrypts "some
+// object matched" without learning which. Alongside the flags the evaluator sends a
+// commitment to the permutation (salted SHA-256), and only if both parties later agree to
+// reveal the mapping does it hand over the opening; the owner checks it against the
+// commitment, so the evaluator can't name a different object after the fact.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tfhe::FheBool;
+
+use crate::common::SatelliteData;
+use crate::planner::{Operand, screen_planned};
+use crate::reveal::{RevealPolicy, RevealedResult, reveal_with};
+use crate::screening::ScreeningConfig;
+use crate::trajectory::EncryptedTrajectory;
+
+// The evaluator's secret permutation: position `i` of the shuffled results holds the
+// result of catalog object `permutation[i]`.
+pub struct Shuffle {
+    permutation: Vec<usize>,
+    salt: [u8; 32],
+}
+
+// Published with the shuffled results; binds the evaluator to its permutation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuffleCommitment(pub [u8; 32]);
+
+// Reveals the permutation once both parties agree to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShuffleOpening {
+    pub permutation: Vec<usize>,
+    pub salt: [u8; 32],
+}
+
+impl Shuffle {
+    // A uniformly random permutation of `len` objects (Fisher-Yates).
+    pub fn random(len: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut permutation: Vec<usize> = (0..len).collect();
+        for i in (1..len).rev() {
+            let mut bytes = [0u8; 8];
+            getrandom::getrandom(&mut bytes)?;
+            // Multiply-shift maps a random u64 onto 0..=i without modulo bias to speak of.
+            let j = ((u64::from_le_bytes(bytes) as u128 * (i as u128 + 1)) >> 64) as usize;
+            permutation.swap(i, j);
+        }
+        let mut salt = [0u8; 32];
+        getrandom::getrandom(&mut salt)?;
+        Ok(Self { permutation, salt })
+    }
+
+    pub fn len(&self) -> usize {
+        self.permutation.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.permutation.is_empty()
+    }
+
+    // Reorders per-object `items` into shuffled order.
+    pub fn apply<T>(&self, items: Vec<T>) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+        if items.len() != self.len() {
+            return Err(format!(
+                "{} results for a shuffle of {} objects",
+                items.len(),
+                self.len()
+            )
+            .into());
+        }
+        let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+        Ok(self
+            .permutation
+            .iter()
+            .map(|&i| items[i].take().expect("permutation repeats an index"))
+            .collect())
+    }
+
+    pub fn commitment(&self) -> ShuffleCommitment {
+        commit(&self.permutation, &self.salt)
+    }
+
+    pub fn opening(&self) -> ShuffleOpening {
+        ShuffleOpening {
+            permutation: self.permutation.clone(),
+            salt: self.salt,
+        }
+    }
+}
+
+fn commit(permutation: &[usize], salt: &[u8; 32]) -> ShuffleCommitment {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    for &i in permutation {
+        hasher.update((i as u64).to_le_bytes());
+    }
+    ShuffleCommitment(hasher.finalize().into())
+}
+
+impl ShuffleCommitment {
+    // Checks `opening` against this commitment and returns the permutation it reveals.
+    pub fn verify(
+        &self,
+        opening: &ShuffleOpening,
+    ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+        if commit(&opening.permutation, &opening.salt) != *self {
+            return Err("shuffle opening doesn't match the commitment".into());
+        }
+        let mut seen = vec![false; opening.permutation.len()];
+        for &i in &opening.permutation {
+            if i >= seen.len() || std::mem::replace(&mut seen[i], true) {
+                return Err("shuffle opening is not a permutation".into());
+            }
+        }
+        Ok(opening.permutation.clone())
+    }
+}
+
+// Maps decrypted shuffled flags back to catalog order with a verified permutation.
+pub fn unshuffle<T: Clone>(
+    shuffled: &[T],
+    permutation: &[usize],
+) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    if shuffled.len() != permutation.len() {
+        return Err(format!(
+            "{} results for a permutation of {} objects",
+            shuffled.len(),
+            permutation.len()
+        )
+        .into());
+    }
+    let mut out: Vec<Option<T>> = vec![None; shuffled.len()];
+    for (item, &i) in shuffled.iter().zip(permutation) {
+        out[i] = Some(item.clone());
+    }
+    out.into_iter()
+        .map(|item| item.ok_or_else(|| "permutation misses an object".into()))
+        .collect()
+}
+
+// One encrypted "any step matched" flag per catalog object, in shuffled order.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_objects_shuffled(
+    encrypted: &EncryptedTrajectory,
+    objects: &[SatelliteData],
+    config: &ScreeningConfig,
+    shuffle: &Shuffle,
+) -> Result<Vec<FheBool>, Box<dyn std::error::Error>> {
+    let mut flags = Vec::with_capacity(objects.len());
+    for object in objects {
+        let output = screen_planned(encrypted, Operand::Clear(object), config)?;
+        match reveal_with(RevealPolicy::AnyFlag, output.results, config.aggregation) {
+            RevealedResult::AnyFlag(flag) => flags.push(flag),
+            _ => unreachable!("AnyFlag policy reduces to a single flag"),
+        }
+    }
+    shuffle.apply(flags)
+}
@@ -0,0 +1,394 @@
+// Server keys stored as separate components on disk, for evaluators short on memory.
+//
+// A server key is hundreds of MB. Loading one from an artifact holds it two or three
+// times over: the serialized bytes, the deserialized key, and another serialization
+// while `EvaluationKey` fingerprints it. A split key file (`.skf`) is written once from
+// the artifact, on any machine, and stores the key's components one after the other:
+// its shape (moduli, degree and noise bounds, PBS order, tag), keyswitching key,
+// bootstrapping key, and whichever optional keys it has. `SplitKey` memory-maps the
+// file and deserializes each component straight out of the mapping. The mapped pages
+// belong to the page cache, which the kernel drops once they have been read, so loading
+// peaks at about the size of the key itself. The fingerprint and size recorded at
+// writing time stand in for the ones `EvaluationKey` would compute.
+//
+// TFHE-rs can't evaluate with the keyswitching or bootstrapping key left on disk. It
+// evaluates from an owned key whose bootstrapping key lives in aligned heap buffers in
+// the Fourier domain. Every bootstrap also reads the whole bootstrapping key, and every
+// keyswitch the whole keyswitching key, so paging them in on demand would page all of
+// them in at the first operation. What can stay on disk are the components screening
+// never uses: the compression, decompression, noise-squashing and public-key
+// keyswitching keys. `load` skips them and `load_full` reads them too.
+//
+// Layout: the magic `SKF` plus the format version digit, a little-endian `u64` header
+// length, the bincode header, then the bincode components back to back.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tfhe::core_crypto::prelude::LweKeyswitchKeyOwned;
+use tfhe::integer::compression_keys::{CompressionKey, DecompressionKey};
+use tfhe::integer::key_switching_key::KeySwitchingKeyMaterial;
+use tfhe::integer::noise_squashing::NoiseSquashingKey;
+use tfhe::shortint::PBSOrder;
+use tfhe::shortint::ciphertext::{MaxDegree, MaxNoiseLevel};
+use tfhe::shortint::parameters::{CarryModulus, CiphertextModulus, MessageModulus};
+use tfhe::shortint::server_key::ShortintBootstrappingKey;
+use tfhe::{ServerKey, Tag};
+
+use crate::compat::{self, ArtifactMeta, ParameterDigest, TFHE_VERSION};
+use crate::migrate::{self, ArtifactKind};
+use crate::redact::EvaluationKey;
+
+const MAGIC_PREFIX: &[u8; 3] = b"SKF";
+pub const SKF_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyComponent {
+    Shape,
+    Keyswitch,
+    Bootstrap,
+    PublicKeyswitch,
+    Compression,
+    Decompression,
+    NoiseSquashing,
+}
+
+impl KeyComponent {
+    // Whether screening evaluates with this component.
+    pub fn needed_for_screening(self) -> bool {
+        matches!(
+            self,
+            KeyComponent::Shape | KeyComponent::Keyswitch | KeyComponent::Bootstrap
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyShape {
+    message_modulus: MessageModulus,
+    carry_modulus: CarryModulus,
+    max_degree: MaxDegree,
+    max_noise_level: MaxNoiseLevel,
+    ciphertext_modulus: CiphertextModulus,
+    pbs_order: PBSOrder,
+    tag: Tag,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SkfHeader {
+    // Crate name and version that wrote the file.
+    producer: String,
+    tfhe_version: String,
+    parameters: Option<ParameterDigest>,
+    // Of the whole key, as `EvaluationKey::new` computes them.
+    fingerprint: String,
+    size: u64,
+    // Offset in the data section and length of each component, in file order.
+    components: Vec<(KeyComponent, u64, u64)>,
+}
+
+// A server key taken apart.
+struct KeyParts {
+    shape: KeyShape,
+    keyswitch: LweKeyswitchKeyOwned<u64>,
+    bootstrap: ShortintBootstrappingKey,
+    public_keyswitch: Option<KeySwitchingKeyMaterial>,
+    compression: Option<CompressionKey>,
+    decompression: Option<DecompressionKey>,
+    noise_squashing: Option<NoiseSquashingKey>,
+}
+
+trait Visit {
+    fn visit<T: Serialize>(
+        &mut self,
+        component: KeyComponent,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+// Records the range each component will take in the data section.
+struct Sizes(Vec<(KeyComponent, u64, u64)>);
+
+impl Visit for Sizes {
+    fn visit<T: Serialize>(
+        &mut self,
+        component: KeyComponent,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let offset = self.0.last().map_or(0, |&(_, offset, len)| offset + len);
+        self.0
+            .push((component, offset, bincode::serialized_size(value)?));
+        Ok(())
+    }
+}
+
+struct Writer<W>(W);
+
+impl<W: Write> Visit for Writer<W> {
+    fn visit<T: Serialize>(
+        &mut self,
+        _: KeyComponent,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(bincode::serialize_into(&mut self.0, value)?)
+    }
+}
+
+impl KeyParts {
+    fn new(key: ServerKey) -> Self {
+        let (integer, public_keyswitch, compression, decompression, noise_squashing, tag) =
+            key.into_raw_parts();
+        let (
+            keyswitch,
+            bootstrap,
+            message_modulus,
+            carry_modulus,
+            max_degree,
+            max_noise_level,
+            ciphertext_modulus,
+            pbs_order,
+        ) = integer.into_raw_parts().into_raw_parts();
+        Self {
+            shape: KeyShape {
+                message_modulus,
+                carry_modulus,
+                max_degree,
+                max_noise_level,
+                ciphertext_modulus,
+                pbs_order,
+                tag,
+            },
+            keyswitch,
+            bootstrap,
+            public_keyswitch,
+            compression,
+            decompression,
+            noise_squashing,
+        }
+    }
+
+    // Visits the components in file order.
+    fn each(&self, visit: &mut impl Visit) -> Result<(), Box<dyn std::error::Error>> {
+        visit.visit(KeyComponent::Shape, &self.shape)?;
+        visit.visit(KeyComponent::Keyswitch, &self.keyswitch)?;
+        visit.visit(KeyComponent::Bootstrap, &self.bootstrap)?;
+        if let Some(key) = &self.public_keyswitch {
+            visit.visit(KeyComponent::PublicKeyswitch, key)?;
+        }
+        if let Some(key) = &self.compression {
+            visit.visit(KeyComponent::Compression, key)?;
+        }
+        if let Some(key) = &self.decompression {
+            visit.visit(KeyComponent::Decompression, key)?;
+        }
+        if let Some(key) = &self.noise_squashing {
+            visit.visit(KeyComponent::NoiseSquashing, key)?;
+        }
+        Ok(())
+    }
+}
+
+fn producer() -> String {
+    format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+// Writes the server key artifact `server_key` (as sent by the owner) to `path` as a
+// split key file.
+pub fn write_split_key(
+    path: impl AsRef<Path>,
+    server_key: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let parameters = migrate::meta_of(ArtifactKind::ServerKey, server_key)?.parameters;
+    let key = EvaluationKey::new(migrate::decode(ArtifactKind::ServerKey, server_key)?)?;
+    let (fingerprint, size) = (key.fingerprint().to_string(), key.size() as u64);
+    let parts = KeyParts::new(key.into_inner());
+    let mut sizes = Sizes(Vec::new());
+    parts.each(&mut sizes)?;
+    let header = bincode::serialize(&SkfHeader {
+        producer: producer(),
+        tfhe_version: TFHE_VERSION.to_string(),
+        parameters,
+        fingerprint,
+        size,
+        components: sizes.0,
+    })?;
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC_PREFIX)?;
+    out.write_all(&[b'0' + SKF_VERSION])?;
+    out.write_all(&(header.len() as u64).to_le_bytes())?;
+    out.write_all(&header)?;
+    let mut writer = Writer(out);
+    parts.each(&mut writer)?;
+    writer.0.flush()?;
+    Ok(())
+}
+
+pub struct SplitKey {
+    mmap: Mmap,
+    header: SkfHeader,
+    data_start: usize,
+}
+
+impl SplitKey {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        // Safety: the file is only read, and it must not be modified while mapped; a
+        // concurrent writer would at worst make a component fail to deserialize.
+        let mmap = unsafe { Mmap::map(&file)? };
+        if mmap.len() < 12 || &mmap[..3] != MAGIC_PREFIX {
+            return Err("not a split key file".into());
+        }
+        let version = mmap[3].wrapping_sub(b'0');
+        if version == 0 || version > SKF_VERSION {
+            return Err(format!(
+                "split key format version {} is newer than this build supports ({})",
+                mmap[3] as char, SKF_VERSION
+            )
+            .into());
+        }
+        let header_len = u64::from_le_bytes(mmap[4..12].try_into()?) as usize;
+        let data_start = 12usize
+            .checked_add(header_len)
+            .filter(|&end| end <= mmap.len())
+            .ok_or("truncated split key header")?;
+        let header: SkfHeader = bincode::deserialize(&mmap[12..data_start])?;
+        compat::check(&ArtifactMeta {
+            kind: ArtifactKind::ServerKey,
+            format_version: ArtifactKind::ServerKey.current_version(),
+            tfhe_version: Some(header.tfhe_version.clone()),
+            parameters: header.parameters,
+        })?;
+        let data_len = (mmap.len() - data_start) as u64;
+        if header
+            .components
+            .iter()
+            .any(|&(_, o, l)| o.checked_add(l).is_none_or(|end| end > data_len))
+        {
+            return Err("split key component outside the file".into());
+        }
+        Ok(Self {
+            mmap,
+            header,
+            data_start,
+        })
+    }
+
+    pub fn producer(&self) -> &str {
+        &self.header.producer
+    }
+
+    pub fn tfhe_version(&self) -> &str {
+        &self.header.tfhe_version
+    }
+
+    pub fn parameters(&self) -> Option<ParameterDigest> {
+        self.header.parameters
+    }
+
+    // Fingerprint of the whole key, the same as the owner's `server_key_fingerprint`.
+    pub fn fingerprint(&self) -> &str {
+        &self.header.fingerprint
+    }
+
+    // Components in the file and their serialized sizes.
+    pub fn components(&self) -> Vec<(KeyComponent, u64)> {
+        self.header
+            .components
+            .iter()
+            .map(|&(component, _, len)| (component, len))
+            .collect()
+    }
+
+    // Serialized size of what `load` reads; the loaded key takes about as much memory.
+    pub fn screening_bytes(&self) -> u64 {
+        self.components()
+            .into_iter()
+            .filter(|(component, _)| component.needed_for_screening())
+            .map(|(_, len)| len)
+            .sum()
+    }
+
+    fn read<T: DeserializeOwned>(
+        &self,
+        component: KeyComponent,
+    ) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        let Some(&(_, offset, len)) = self
+            .header
+            .components
+            .iter()
+            .find(|&&(c, ..)| c == component)
+        else {
+            return Ok(None);
+        };
+        let start = self.data_start + offset as usize;
+        Ok(Some(bincode::deserialize(
+            &self.mmap[start..start + len as usize],
+        )?))
+    }
+
+    fn required<T: DeserializeOwned>(
+        &self,
+        component: KeyComponent,
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        self.read(component)?
+            .ok_or_else(|| format!("split key file has no {:?} component", component).into())
+    }
+
+    fn optional<T: DeserializeOwned>(
+        &self,
+        component: KeyComponent,
+        full: bool,
+    ) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        if full { self.read(component) } else { Ok(None) }
+    }
+
+    fn load_with(&self, full: bool) -> Result<EvaluationKey, Box<dyn std::error::Error>> {
+        let shape: KeyShape = self.required(KeyComponent::Shape)?;
+        let keyswitch: LweKeyswitchKeyOwned<u64> = self.required(KeyComponent::Keyswitch)?;
+        let bootstrap: ShortintBootstrappingKey = self.required(KeyComponent::Bootstrap)?;
+        // `from_raw_parts` panics on these.
+        if keyswitch.input_key_lwe_dimension() != bootstrap.output_lwe_dimension()
+            || keyswitch.output_key_lwe_dimension() != bootstrap.input_lwe_dimension()
+            || keyswitch.ciphertext_modulus() != shape.ciphertext_modulus
+        {
+            return Err("split key components don't fit together".into());
+        }
+        let shortint = tfhe::shortint::ServerKey::from_raw_parts(
+            keyswitch,
+            bootstrap,
+            shape.message_modulus,
+            shape.carry_modulus,
+            shape.max_degree,
+            shape.max_noise_level,
+            shape.ciphertext_modulus,
+            shape.pbs_order,
+        );
+        let key = ServerKey::from_raw_parts(
+            tfhe::integer::ServerKey::from_raw_parts(shortint),
+            self.optional(KeyComponent::PublicKeyswitch, full)?,
+            self.optional(KeyComponent::Compression, full)?,
+            self.optional(KeyComponent::Decompression, full)?,
+            self.optional(KeyComponent::NoiseSquashing, full)?,
+            shape.tag,
+        );
+        Ok(EvaluationKey::with_fingerprint(
+            key,
+            self.header.fingerprint.clone(),
+            self.header.size as usize,
+        ))
+    }
+
+    // The components screening evaluates with; the others stay on disk.
+    pub fn load(&self) -> Result<EvaluationKey, Box<dyn std::error::Error>> {
+        self.load_with(false)
+    }
+
+    // Every component in the file.
+    pub fn load_full(&self) -> Result<EvaluationKey, Box<dyn std::error::Error>> {
+        self.load_with(true)
+    }
+}
@@ -0,0 +1,128 @@
+// Known-answer steps that catch an evaluator skipping or botching work.
+//
+// The owner can't check flags at real steps, since it doesn't know the evaluator's
+// positions, but it knows what the evaluator screens at decoy steps (see `padding`): the
+// evaluator's plaintext there is `EVALUATOR_SENTINEL` on every axis. A decoy left at
+// `OWNER_SENTINEL` must come back false; `SpotChecks` moves a few secretly chosen decoys
+// onto `EVALUATOR_SENTINEL` instead, which must come back true. The ciphertexts look
+// alike, so an evaluator that returns made-up flags for the padded tail, or a faulty one,
+// fails `verify` with good probability.
+//
+// This relies on the evaluator's own trajectory ending where the owner's does, so that
+// its padding covers every decoy step.
+
+use std::fmt;
+
+use crate::common::SatelliteData;
+use crate::padding::EVALUATOR_SENTINEL;
+
+// A known-answer step came back wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpotCheckFailed {
+    // Absolute index of the step.
+    pub step: usize,
+    pub expected: bool,
+}
+
+impl fmt::Display for SpotCheckFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "spot check failed: step {} should have screened {}",
+            self.step,
+            if self.expected {
+                "positive"
+            } else {
+                "negative"
+            }
+        )
+    }
+}
+
+impl std::error::Error for SpotCheckFailed {}
+
+// The owner's secret choice of planted collisions. Debug output only shows how many.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SpotChecks {
+    // Absolute indices, sorted.
+    collisions: Vec<usize>,
+}
+
+impl fmt::Debug for SpotChecks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SpotChecks({})", self.collisions.len())
+    }
+}
+
+impl SpotChecks {
+    // `count` distinct decoy steps of a trajectory of `real_len` steps padded to
+    // `padded_len`, chosen at random.
+    pub fn choose(
+        real_len: usize,
+        padded_len: usize,
+        count: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut decoys: Vec<usize> = (real_len..padded_len).collect();
+        if count > decoys.len() {
+            return Err(format!(
+                "{} spot checks need as many decoy steps, there are {}",
+                count,
+                decoys.len()
+            )
+            .into());
+        }
+        // A partial Fisher-Yates shuffle puts `count` random decoys in front.
+        for i in 0..count {
+            let mut bytes = [0u8; 8];
+            getrandom::getrandom(&mut bytes)?;
+            let span = (decoys.len() - i) as u128;
+            let j = i + ((u64::from_le_bytes(bytes) as u128 * span) >> 64) as usize;
+            decoys.swap(i, j);
+        }
+        let mut collisions = decoys[..count].to_vec();
+        collisions.sort_unstable();
+        Ok(Self { collisions })
+    }
+
+    pub fn len(&self) -> usize {
+        self.collisions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collisions.is_empty()
+    }
+
+    // Moves the chosen steps of the owner's padded trajectory onto the evaluator's
+    // sentinel.
+    pub fn plant(&self, padded: &mut SatelliteData) {
+        for &step in &self.collisions {
+            if step < padded.x.len() {
+                padded.x[step] = EVALUATOR_SENTINEL;
+                padded.y[step] = EVALUATOR_SENTINEL;
+                padded.z[step] = EVALUATOR_SENTINEL;
+            }
+        }
+    }
+
+    // Checks every decoy step among `flags`, whose first is step `first_index`: planted
+    // collisions must be true, all other decoys false. Steps below `real_len` are real
+    // and not checked.
+    pub fn verify(
+        &self,
+        flags: &[bool],
+        first_index: usize,
+        real_len: usize,
+    ) -> Result<(), SpotCheckFailed> {
+        for (i, &flag) in flags.iter().enumerate() {
+            let step = first_index + i;
+            if step < real_len {
+                continue;
+            }
+            let expected = self.collisions.binary_search(&step).is_ok();
+            if flag != expected {
+                return Err(SpotCheckFailed { step, expected });
+            }
+        }
+        Ok(())
+    }
+}
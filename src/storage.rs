@@ -0,0 +1,234 @@
+// Owner-side SQLite record of screenings: who was screened against whom, with which
+// parameters and artifacts, and the decrypted outcome.
+//
+// Only public metadata and post-decryption results are stored; keys and trajectories
+// never are, artifacts appear by hash only.
+
+use std::path::Path;
+
+use rusqlite::{Connection, params};
+
+use crate::events::ConjunctionEvent;
+use crate::preset::ParameterPreset;
+use crate::protocol::{MessageKind, SessionNonce};
+use crate::transport::sha256_hex;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY,
+        nonce BLOB NOT NULL UNIQUE,
+        satellite TEXT NOT NULL,
+        peer TEXT NOT NULL,
+        preset TEXT NOT NULL,
+        started_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS artifacts (
+        session INTEGER NOT NULL REFERENCES sessions(id),
+        kind TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        sha256 TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS outcomes (
+        session INTEGER PRIMARY KEY REFERENCES sessions(id),
+        steps INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS conjunctions (
+        session INTEGER NOT NULL REFERENCES sessions(id),
+        start_index INTEGER NOT NULL,
+        start_epoch INTEGER NOT NULL,
+        end_epoch INTEGER NOT NULL,
+        n_steps INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS sessions_by_satellite ON sessions(satellite, started_at);
+";
+
+pub type SessionId = i64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub nonce: SessionNonce,
+    // Our satellite and the party it was screened against, as named by the caller.
+    pub satellite: String,
+    pub peer: String,
+    pub preset: ParameterPreset,
+    // Unix seconds.
+    pub started_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredConjunction {
+    pub session: SessionId,
+    pub peer: String,
+    pub started_at: u64,
+    pub event: ConjunctionEvent,
+}
+
+pub struct ScreeningStore {
+    conn: Connection,
+}
+
+fn preset_name(preset: ParameterPreset) -> &'static str {
+    match preset {
+        ParameterPreset::Default => "default",
+        ParameterPreset::Gaussian2m128 => "gaussian2m128",
+        ParameterPreset::Tuniform2m64 => "tuniform2m64",
+    }
+}
+
+fn parse_preset(name: &str) -> Result<ParameterPreset, Box<dyn std::error::Error>> {
+    [
+        ParameterPreset::Default,
+        ParameterPreset::Gaussian2m128,
+        ParameterPreset::Tuniform2m64,
+    ]
+    .into_iter()
+    .find(|&preset| preset_name(preset) == name)
+    .ok_or_else(|| format!("unknown parameter preset {:?}", name).into())
+}
+
+impl ScreeningStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn in_memory() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, Box<dyn std::error::Error>> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn record_session(
+        &self,
+        record: &SessionRecord,
+    ) -> Result<SessionId, Box<dyn std::error::Error>> {
+        self.conn.execute(
+            "INSERT INTO sessions (nonce, satellite, peer, preset, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                &record.nonce[..],
+                record.satellite,
+                record.peer,
+                preset_name(record.preset),
+                record.started_at as i64
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    // Records an artifact exchanged in `session` by size and SHA-256 only.
+    pub fn record_artifact(
+        &self,
+        session: SessionId,
+        kind: MessageKind,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute(
+            "INSERT INTO artifacts (session, kind, size, sha256) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                session,
+                format!("{:?}", kind),
+                bytes.len() as i64,
+                sha256_hex(bytes)
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Records the decrypted outcome of `session`: how many steps were screened and the
+    // conjunction events found (see `events::cluster`). A session has one outcome.
+    pub fn record_outcome(
+        &mut self,
+        session: SessionId,
+        steps: usize,
+        events: &[ConjunctionEvent],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO outcomes (session, steps) VALUES (?1, ?2)",
+            params![session, steps as i64],
+        )?;
+        for event in events {
+            tx.execute(
+                "INSERT INTO conjunctions (session, start_index, start_epoch, end_epoch, n_steps)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    session,
+                    event.start_index as i64,
+                    event.start_epoch as i64,
+                    event.end_epoch as i64,
+                    event.n_steps as i64
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn session(&self, id: SessionId) -> Result<SessionRecord, Box<dyn std::error::Error>> {
+        let (nonce, satellite, peer, preset, started_at) = self.conn.query_row(
+            "SELECT nonce, satellite, peer, preset, started_at FROM sessions WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok((
+                    row.get::<_, Vec<u8>>(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            },
+        )?;
+        Ok(SessionRecord {
+            nonce: nonce
+                .try_into()
+                .map_err(|_| "stored session nonce has the wrong length")?,
+            satellite,
+            peer,
+            preset: parse_preset(&preset)?,
+            started_at: started_at as u64,
+        })
+    }
+
+    // All conjunctions found for `satellite` in sessions started at or after `since`
+    // (unix seconds), oldest first.
+    pub fn conjunctions(
+        &self,
+        satellite: &str,
+        since: u64,
+    ) -> Result<Vec<StoredConjunction>, Box<dyn std::error::Error>> {
+        let mut statement = self.conn.prepare(
+            "SELECT s.id, s.peer, s.started_at, c.start_index, c.start_epoch, c.end_epoch,
+                    c.n_steps
+             FROM conjunctions c JOIN sessions s ON s.id = c.session
+             WHERE s.satellite = ?1 AND s.started_at >= ?2
+             ORDER BY s.started_at, s.id, c.start_index",
+        )?;
+        let rows = statement.query_map(params![satellite, since as i64], |row| {
+            Ok(StoredConjunction {
+                session: row.get(0)?,
+                peer: row.get(1)?,
+                started_at: row.get::<_, i64>(2)? as u64,
+                event: ConjunctionEvent {
+                    start_index: row.get::<_, i64>(3)? as usize,
+                    start_epoch: row.get::<_, i64>(4)? as u64,
+                    end_epoch: row.get::<_, i64>(5)? as u64,
+                    n_steps: row.get::<_, i64>(6)? as usize,
+                },
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // `conjunctions` over the `days` days before `now` (unix seconds).
+    pub fn recent_conjunctions(
+        &self,
+        satellite: &str,
+        days: u64,
+        now: u64,
+    ) -> Result<Vec<StoredConjunction>, Box<dyn std::error::Error>> {
+        self.conjunctions(satellite, now.saturating_sub(days * 86_400))
+    }
+}
@@ -0,0 +1,97 @@
+// Per-step results streamed back to the owner while screening is still running.
+//
+// A week of steps takes a while to screen, but a conjunction in the next few hours is worth
+// hearing about right away. In streaming mode the evaluator hands off the flags in
+// `ResultBatch`es of a few steps each as soon as they are computed, and the owner decrypts
+// them as they arrive (`OwnerParty::decrypt_unpadded` with the batch's `first_index`).
+// Batches go out in the order they are screened (see `schedule`). Only per-step flags can
+// be streamed: any other reveal policy (see `reveal`) needs all steps before it has
+// anything to release.
+
+use tfhe::FheBool;
+
+use crate::common::{SatelliteData, safe_deserialize_items, safe_serialize_item};
+use crate::depth::OpCounter;
+use crate::migrate::{self, ArtifactKind};
+use crate::schedule::{StepOrder, batch_order};
+use crate::screening::{ScreeningConfig, screen_kernel_each};
+use crate::trajectory::EncryptedTrajectory;
+
+// Flags of consecutive steps, the first of them at absolute step `first_index`.
+pub struct ResultBatch {
+    pub first_index: usize,
+    pub flags: Vec<FheBool>,
+}
+
+impl ResultBatch {
+    pub fn len(&self) -> usize {
+        self.flags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    // Absolute index one past the last step of the batch.
+    pub fn end_index(&self) -> usize {
+        self.first_index + self.flags.len()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let items = self
+            .flags
+            .iter()
+            .map(safe_serialize_item)
+            .collect::<Result<Vec<_>, _>>()?;
+        migrate::encode(ArtifactKind::Batch, &(self.first_index, items))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let (first_index, items): (usize, Vec<Vec<u8>>) =
+            migrate::decode(ArtifactKind::Batch, data)?;
+        Ok(Self {
+            first_index,
+            flags: safe_deserialize_items(&items)?,
+        })
+    }
+}
+
+// Screens `encrypted` against `plaintext` with the configured kernel and passes the flags
+// to `send` in batches of `batch_steps` consecutive steps (the last one may be shorter),
+// screening the batches in `order`. An error from `send`, e.g. the owner hanging up, stops
+// the screening.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_streaming(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    config: &ScreeningConfig,
+    batch_steps: usize,
+    order: &StepOrder,
+    mut send: impl FnMut(ResultBatch) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<OpCounter, Box<dyn std::error::Error>> {
+    let kernel = config.kernel.kernel()?;
+    let batches = batch_order(order, &encrypted.epochs, batch_steps)?;
+    let mut pending = batches.iter();
+    let mut current = pending.next();
+    let mut flags = Vec::new();
+    screen_kernel_each(
+        encrypted,
+        plaintext,
+        &*kernel,
+        config,
+        batches.iter().flat_map(|batch| batch.clone()),
+        |flag| {
+            flags.push(flag);
+            let batch = current.expect("steps come from the batches");
+            if flags.len() == batch.len() {
+                send(ResultBatch {
+                    first_index: encrypted.absolute_index(batch.start),
+                    flags: std::mem::take(&mut flags),
+                })?;
+                current = pending.next();
+            }
+            Ok(())
+        },
+    )
+}
@@ -0,0 +1,81 @@
+// Deterministic test vectors freezing the byte-level wire format.
+//
+// Everything here is built from fixed inputs, so the bytes only change when a format
+// does. The `sat-fhe-testdata` binary writes them to `tests/golden`, and the golden
+// tests compare them with what this build produces and check this build still reads the
+// committed files. Ciphertexts are randomized by encryption, so the vectors hold none:
+// they cover the framing (tags, headers, metadata, envelopes) around them.
+
+use crate::frame::Frame;
+use crate::kernel::KernelChoice;
+use crate::mask::StepMask;
+use crate::object_id::ObjectId;
+use crate::protocol::{Envelope, MessageKind, SessionMetadata, SessionNonce};
+use crate::regime::AltitudeBand;
+use crate::reveal::RevealPolicy;
+use crate::screening::results_to_bytes;
+use crate::session::Session;
+use crate::time_system::TimeSystem;
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::Units;
+
+pub const NONCE: SessionNonce = [0x5a; 16];
+
+// Session metadata with every field set.
+pub fn metadata() -> Result<SessionMetadata, Box<dyn std::error::Error>> {
+    Ok(SessionMetadata {
+        altitude_band: Some(AltitudeBand::new(400.0, 430.0)?),
+        server_key_fingerprint: Some("0123456789abcdef".to_string()),
+        frame: Some(Frame::Ecef),
+        units: Some(Units::Kilometers),
+        reveal: Some(RevealPolicy::Count),
+        padded_len: Some(64),
+        kernel: Some(KernelChoice::BoxThreshold { half_width: 5 }),
+        mask: Some(StepMask::new([10..20, 40..48])),
+        object_id: Some(ObjectId::Norad(25544)),
+        time_system: Some(TimeSystem::Gps),
+    })
+}
+
+// A trajectory with metadata but no steps.
+pub fn trajectory() -> EncryptedTrajectory {
+    EncryptedTrajectory {
+        x: Vec::new(),
+        y: Vec::new(),
+        z: Vec::new(),
+        epochs: Vec::new(),
+        first_index: 7,
+        frame: Frame::Ecef,
+        units: Units::Kilometers,
+    }
+}
+
+// File name and contents of a byte-exact vector.
+pub type Vector = (&'static str, Vec<u8>);
+
+pub fn vectors() -> Result<Vec<Vector>, Box<dyn std::error::Error>> {
+    let envelope = Envelope {
+        nonce: NONCE,
+        seq: 3,
+        kind: MessageKind::Results,
+        payload: b"golden".to_vec(),
+        checksum: [0xa5; 32],
+    };
+    let vectors = vec![
+        ("envelope.bin", envelope.to_bytes()?),
+        ("hello.bin", Session::join(NONCE).hello(&metadata()?)?),
+        ("trajectory.bin", trajectory().to_bytes()?),
+        ("results.bin", results_to_bytes(&[])?),
+    ];
+    #[cfg(feature = "proto")]
+    let vectors = {
+        use prost::Message;
+        let mut vectors = vectors;
+        vectors.push((
+            "screening_request.pb",
+            crate::proto::ScreeningRequest::from(&metadata()?).encode_to_vec(),
+        ));
+        vectors
+    };
+    Ok(vectors)
+}
@@ -0,0 +1,119 @@
+// Time systems of trajectory epochs.
+//
+// Exact-time matching pairs steps by equal epochs, so both parties have to count seconds
+// the same way. UTC, TAI and GPS time differ by whole seconds that change with every leap
+// second: since 2017 a GPS clock reads 18 s ahead of UTC and TAI 37 s. Epochs of every
+// system here are seconds since 1970-01-01 00:00:00 as read on that system's clock, so a
+// UTC epoch is a Unix time. Conversion happens on the plaintext epochs before they are
+// aligned with the peer's, like frame conversion (see `frame`); epochs still in different
+// systems are a hard protocol error rather than a silent offset.
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::ProtocolError;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeSystem {
+    // Coordinated Universal Time, with leap seconds.
+    #[default]
+    Utc,
+    // International Atomic Time.
+    Tai,
+    // GPS time, a constant 19 s behind TAI.
+    Gps,
+}
+
+const GPS_BEHIND_TAI_S: u64 = 19;
+
+// TAI - UTC in seconds from each UTC instant (Unix seconds) on, per IERS Bulletin C up to
+// the leap second of 2016-12-31. Extend this table when the IERS announces another.
+pub const LEAP_SECONDS: [(u64, u64); 28] = [
+    (63_072_000, 10),
+    (78_796_800, 11),
+    (94_694_400, 12),
+    (126_230_400, 13),
+    (157_766_400, 14),
+    (189_302_400, 15),
+    (220_924_800, 16),
+    (252_460_800, 17),
+    (283_996_800, 18),
+    (315_532_800, 19),
+    (362_793_600, 20),
+    (394_329_600, 21),
+    (425_865_600, 22),
+    (489_024_000, 23),
+    (567_993_600, 24),
+    (631_152_000, 25),
+    (662_688_000, 26),
+    (709_948_800, 27),
+    (741_484_800, 28),
+    (773_020_800, 29),
+    (820_454_400, 30),
+    (867_715_200, 31),
+    (915_148_800, 32),
+    (1_136_073_600, 33),
+    (1_230_768_000, 34),
+    (1_341_100_800, 35),
+    (1_435_708_800, 36),
+    (1_483_228_800, 37),
+];
+
+// TAI - UTC at the UTC epoch `unix_s`; before 1972, when the offset wasn't a whole number
+// of seconds, it is taken as the 10 s of 1972.
+pub fn tai_minus_utc(unix_s: u64) -> u64 {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|&&(from, _)| unix_s >= from)
+        .map_or(LEAP_SECONDS[0].1, |&(_, offset)| offset)
+}
+
+// `epoch` in `system` as a TAI epoch.
+pub fn to_tai(epoch: u64, system: TimeSystem) -> u64 {
+    match system {
+        TimeSystem::Utc => epoch + tai_minus_utc(epoch),
+        TimeSystem::Tai => epoch,
+        TimeSystem::Gps => epoch + GPS_BEHIND_TAI_S,
+    }
+}
+
+// The TAI epoch `tai` in `system`. A leap second has no UTC epoch of its own and maps to
+// the second after it; GPS epochs before the 19th TAI second clamp to zero.
+pub fn from_tai(tai: u64, system: TimeSystem) -> u64 {
+    match system {
+        TimeSystem::Utc => {
+            let offset = LEAP_SECONDS
+                .iter()
+                .rev()
+                .find(|&&(from, offset)| tai >= from + offset)
+                .map_or(LEAP_SECONDS[0].1, |&(_, offset)| offset);
+            tai.saturating_sub(offset)
+        }
+        TimeSystem::Tai => tai,
+        TimeSystem::Gps => tai.saturating_sub(GPS_BEHIND_TAI_S),
+    }
+}
+
+// Converts `epoch` from time system `from` to time system `to`.
+pub fn convert(epoch: u64, from: TimeSystem, to: TimeSystem) -> u64 {
+    if from == to {
+        epoch
+    } else {
+        from_tai(to_tai(epoch, from), to)
+    }
+}
+
+// `epochs` in `from` converted to `to`, for aligning one party's plaintext epochs with the
+// other's.
+pub fn align_epochs(epochs: &[u64], from: TimeSystem, to: TimeSystem) -> Vec<u64> {
+    epochs.iter().map(|&t| convert(t, from, to)).collect()
+}
+
+// Both parties' epochs must be in the same time system; whoever differs has to convert
+// before aligning.
+pub fn check_time_systems(owner: TimeSystem, evaluator: TimeSystem) -> Result<(), ProtocolError> {
+    if owner != evaluator {
+        return Err(ProtocolError::TimeSystemMismatch { owner, evaluator });
+    }
+    Ok(())
+}
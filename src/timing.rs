@@ -0,0 +1,163 @@
+// Encrypted time matching.
+//
+// Exact-match screening pairs step `i` of one trajectory with step `i` of the other,
+// which assumes both were sampled on the same public time grid. When the owner's epochs
+// are sensitive too, it can encrypt them as nanosecond `FheUint64`s and the evaluator
+// pairs steps by encrypted time instead: a pair of steps counts if `|t1 - t2| < delta`
+// and their positions match. Without a shared grid every owner step has to be compared
+// with every evaluator step, so the cost grows with the product of the two lengths.
+
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheBool, FheUint64};
+
+use crate::common::{SatelliteData, safe_deserialize_items, safe_serialize_item};
+use crate::depth::OpCounter;
+use crate::frame::check_frames;
+use crate::migrate::{self, ArtifactKind};
+use crate::reveal::{reduce_tree, tree_depth};
+use crate::screening::{ScreeningConfig, ScreeningOutput, exact_match_cost, exact_match_step};
+use crate::trajectory::EncryptedTrajectory;
+
+// Epochs of an encrypted trajectory, one ciphertext per step, in nanoseconds.
+pub struct EncryptedEpochs {
+    pub epochs: Vec<FheUint64>,
+}
+
+impl EncryptedEpochs {
+    pub fn encrypt(
+        epochs_ns: &[u64],
+        client_key: &ClientKey,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut epochs = Vec::with_capacity(epochs_ns.len());
+        for &t in epochs_ns {
+            epochs.push(FheUint64::try_encrypt(t, client_key)?);
+        }
+        Ok(Self { epochs })
+    }
+
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let items = self
+            .epochs
+            .iter()
+            .map(safe_serialize_item)
+            .collect::<Result<Vec<_>, _>>()?;
+        migrate::encode(ArtifactKind::Epochs, &items)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let items: Vec<Vec<u8>> = migrate::decode(ArtifactKind::Epochs, data)?;
+        Ok(Self {
+            epochs: safe_deserialize_items(&items)?,
+        })
+    }
+}
+
+// `|t - other| < delta` for a clear `other`, as `other - delta < t < other + delta`
+// with the bounds clamped to the `u64` range.
+pub fn within_window_clear(t: &FheUint64, other: u64, delta: u64) -> FheBool {
+    if delta == 0 {
+        return FheBool::encrypt_trivial(false);
+    }
+    t.ge(other.saturating_sub(delta - 1)) & t.lt(other.saturating_add(delta))
+}
+
+// `|a - b| < delta` with both epochs encrypted under the same key (symmetric mode).
+// Epochs must stay `delta` below `u64::MAX`, which nanoseconds since 1970 do for
+// centuries.
+pub fn within_window(a: &FheUint64, b: &FheUint64, delta: u64) -> FheBool {
+    a.lt(&(b + delta)) & b.lt(&(a + delta))
+}
+
+// Cost of screening one owner step against `evaluator_steps` steps: per pair a position
+// match and a window check ANDed together, then an OR tree over the pairs.
+pub fn time_matched_cost(evaluator_steps: usize) -> OpCounter {
+    let pairs = evaluator_steps as u64;
+    let position = exact_match_cost();
+    OpCounter {
+        comparisons: pairs * (position.comparisons + 2),
+        arithmetic: 0,
+        boolean: pairs * (position.boolean + 2) + pairs.saturating_sub(1),
+        // The window's two comparisons and AND run alongside the position match.
+        depth: position.depth + 1 + tree_depth(evaluator_steps),
+        depth_warning: None,
+    }
+}
+
+// Collision check paired by encrypted time: owner step `i` is flagged if any step `j` of
+// `plaintext` lies within `delta_ns` of it and sits at the same position.
+// `plaintext_epochs_ns` holds the epochs of `plaintext`'s steps.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn screen_time_matched(
+    encrypted: &EncryptedTrajectory,
+    epochs: &EncryptedEpochs,
+    plaintext: &SatelliteData,
+    plaintext_epochs_ns: &[u64],
+    delta_ns: u64,
+    config: &ScreeningConfig,
+) -> Result<ScreeningOutput, Box<dyn std::error::Error>> {
+    if epochs.len() != encrypted.len() {
+        return Err(format!(
+            "{} encrypted epochs for a trajectory of {} steps",
+            epochs.len(),
+            encrypted.len()
+        )
+        .into());
+    }
+    if plaintext_epochs_ns.len() != plaintext.x.len() {
+        return Err(format!(
+            "{} epochs for a plaintext trajectory of {} steps",
+            plaintext_epochs_ns.len(),
+            plaintext.x.len()
+        )
+        .into());
+    }
+    check_frames(encrypted.frame, plaintext.frame)?;
+    let rescaled;
+    let plaintext = if plaintext.units == encrypted.units {
+        plaintext
+    } else {
+        rescaled = plaintext.to_units(encrypted.units)?;
+        &rescaled
+    };
+    let mut step = time_matched_cost(plaintext.x.len());
+    config.check_depth(&mut step)?;
+
+    let mut results = Vec::with_capacity(encrypted.len());
+    for i in 0..encrypted.len() {
+        let pairs: Vec<FheBool> = (0..plaintext.x.len())
+            .map(|j| {
+                let axes = [
+                    (&encrypted.x[i], config.clear(plaintext.x[j])),
+                    (&encrypted.y[i], config.clear(plaintext.y[j])),
+                    (&encrypted.z[i], config.clear(plaintext.z[j])),
+                ];
+                let t = &epochs.epochs[i];
+                let window = if config.constant_shape {
+                    // The clamped bounds of `within_window_clear` hit TFHE's shortcuts for
+                    // comparisons with 0 near the start of the epoch range.
+                    let other = FheUint64::encrypt_trivial(plaintext_epochs_ns[j]);
+                    within_window(t, &other, delta_ns)
+                } else {
+                    within_window_clear(t, plaintext_epochs_ns[j], delta_ns)
+                };
+                exact_match_step(axes, config.parallel_axes) & window
+            })
+            .collect();
+        results.push(
+            reduce_tree(pairs, |a, b| a | b).unwrap_or_else(|| FheBool::encrypt_trivial(false)),
+        );
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&step, encrypted.len() as u64);
+    Ok(ScreeningOutput { results, ops })
+}
@@ -0,0 +1,271 @@
+// Mutually authenticated TLS between daemon clients and the daemon.
+//
+// The payloads are ciphertexts, but session metadata, epochs and server keys travel next
+// to them, and a daemon without authentication takes work from anyone who can reach it.
+// The two operators exchange (typically self-signed) certificates out of band and pin
+// each other's by SHA-256 fingerprint (`TlsConfig::pinned`): the daemon completes a
+// handshake only with a client presenting a pinned certificate, and the client only with
+// a daemon presenting one. There is no CA; like an SSH host key, a pinned certificate is
+// trusted whatever its names and validity period.
+//
+// `TlsTransport` and `TlsListener` wrap any `net::Transport` and `net::Listener`.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, DistinguishedName, ServerConfig,
+    SignatureScheme,
+};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::{TlsAcceptor, TlsConnector, client, server};
+
+use crate::net::{Listener, TcpTransport, Transport};
+use crate::service::ServiceError;
+use crate::transport::sha256_hex;
+
+// Name the client asks for; certificates are pinned, so it isn't checked.
+const SERVER_NAME: &str = "sat-fhe";
+
+// A client that hasn't finished its handshake by then is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    // PEM certificate chain presented to the counterpart, leaf first.
+    pub cert: PathBuf,
+    // PEM private key of the leaf certificate.
+    pub key: PathBuf,
+    // Fingerprints (see `fingerprint`) of the counterpart certificates accepted.
+    pub pinned: Vec<String>,
+}
+
+// Lowercase hex SHA-256 of a DER certificate, as listed in `TlsConfig::pinned`; the same
+// as `openssl x509 -in cert.pem -outform der | sha256sum`.
+pub fn fingerprint(cert: &CertificateDer<'_>) -> String {
+    sha256_hex(cert)
+}
+
+fn identity(
+    config: &TlsConfig,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ServiceError> {
+    let certs = CertificateDer::pem_file_iter(&config.cert)?.collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(format!("no certificate in {}", config.cert.display()).into());
+    }
+    Ok((certs, PrivateKeyDer::from_pem_file(&config.key)?))
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+// Accepts exactly the pinned end-entity certificates, in either role.
+#[derive(Debug)]
+struct PinnedVerifier {
+    pinned: Vec<String>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl PinnedVerifier {
+    fn new(config: &TlsConfig, provider: &CryptoProvider) -> Result<Self, ServiceError> {
+        if config.pinned.is_empty() {
+            return Err("TLS needs at least one pinned certificate".into());
+        }
+        Ok(Self {
+            pinned: config.pinned.iter().map(|p| p.to_lowercase()).collect(),
+            algorithms: provider.signature_verification_algorithms,
+        })
+    }
+
+    fn check(&self, end_entity: &CertificateDer<'_>) -> Result<(), rustls::Error> {
+        if self.pinned.contains(&fingerprint(end_entity)) {
+            Ok(())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.check(end_entity)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+impl ClientCertVerifier for PinnedVerifier {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        self.check(end_entity)?;
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+// Connects over `inner` and authenticates the daemon by its pinned certificate.
+pub struct TlsTransport<T = TcpTransport> {
+    inner: T,
+    connector: TlsConnector,
+    server_name: ServerName<'static>,
+}
+
+impl<T: Transport> TlsTransport<T> {
+    pub fn new(inner: T, config: &TlsConfig) -> Result<Self, ServiceError> {
+        let provider = provider();
+        let verifier = Arc::new(PinnedVerifier::new(config, &provider)?);
+        let (certs, key) = identity(config)?;
+        let client = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_client_auth_cert(certs, key)?;
+        Ok(Self {
+            inner,
+            connector: TlsConnector::from(Arc::new(client)),
+            server_name: ServerName::try_from(SERVER_NAME)?,
+        })
+    }
+}
+
+impl<T: Transport> Transport for TlsTransport<T> {
+    type Conn = client::TlsStream<T::Conn>;
+
+    async fn connect(&self) -> io::Result<Self::Conn> {
+        let stream = self.inner.connect().await?;
+        self.connector
+            .connect(self.server_name.clone(), stream)
+            .await
+    }
+}
+
+// Accepts connections from `inner` whose client presents a pinned certificate.
+// Handshakes run in their own tasks, so a stalling client doesn't hold up the others;
+// `inner.accept` must be cancel-safe, as the TCP and in-process listeners are.
+pub struct TlsListener<L: Listener = TcpListener> {
+    inner: L,
+    acceptor: TlsAcceptor,
+    handshakes: JoinSet<(io::Result<server::TlsStream<L::Conn>>, IpAddr)>,
+}
+
+impl<L: Listener> TlsListener<L> {
+    pub fn new(inner: L, config: &TlsConfig) -> Result<Self, ServiceError> {
+        let provider = provider();
+        let verifier = Arc::new(PinnedVerifier::new(config, &provider)?);
+        let (certs, key) = identity(config)?;
+        let server = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(certs, key)?;
+        Ok(Self {
+            inner,
+            acceptor: TlsAcceptor::from(Arc::new(server)),
+            handshakes: JoinSet::new(),
+        })
+    }
+}
+
+impl TlsListener {
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+impl<L: Listener> Listener for TlsListener<L> {
+    type Conn = server::TlsStream<L::Conn>;
+
+    async fn accept(&mut self) -> io::Result<(Self::Conn, IpAddr)> {
+        loop {
+            tokio::select! {
+                accepted = self.inner.accept() => {
+                    let (stream, peer) = accepted?;
+                    let handshake = self.acceptor.accept(stream);
+                    self.handshakes.spawn(async move {
+                        let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await {
+                            Ok(stream) => stream,
+                            Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "handshake timed out")),
+                        };
+                        (stream, peer)
+                    });
+                }
+                Some(done) = self.handshakes.join_next() => {
+                    match done.map_err(io::Error::other)? {
+                        (Ok(stream), peer) => return Ok((stream, peer)),
+                        (Err(err), peer) => eprintln!("TLS handshake with {}: {}", peer, err),
+                    }
+                }
+            }
+        }
+    }
+}
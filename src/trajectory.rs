@@ -0,0 +1,259 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tfhe::prelude::*;
+use tfhe::{ClientKey, FheUint32};
+
+use crate::common::{SatelliteData, safe_deserialize_items, safe_serialize_item};
+use crate::compat::{self, ParameterDigest};
+use crate::frame::Frame;
+use crate::migrate::{self, ArtifactKind};
+use crate::units::Units;
+
+// Wire layout of an encrypted trajectory: public time metadata plus per-axis lists of
+// individually serialized ciphertexts.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SerializedTrajectory {
+    pub(crate) frame: Frame,
+    pub(crate) units: Units,
+    pub(crate) first_index: usize,
+    pub(crate) epochs: Vec<u64>,
+    pub(crate) x: Vec<Vec<u8>>,
+    pub(crate) y: Vec<Vec<u8>>,
+    pub(crate) z: Vec<Vec<u8>>,
+}
+
+impl SerializedTrajectory {
+    // Parses the outer layout only; the ciphertexts stay serialized.
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let serialized: SerializedTrajectory = migrate::decode(ArtifactKind::Trajectory, data)?;
+        serialized.validate()?;
+        Ok(serialized)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let len = self.x.len();
+        if self.y.len() != len || self.z.len() != len {
+            return Err("encrypted trajectory axes have different lengths".into());
+        }
+        if self.epochs.len() != len {
+            return Err("encrypted trajectory epochs don't match its length".into());
+        }
+        // Absolute step indices run up to `first_index + len`, which must fit.
+        if self.first_index.checked_add(len).is_none() {
+            return Err(format!(
+                "encrypted trajectory of {} steps from step {} runs past the last index",
+                len, self.first_index
+            )
+            .into());
+        }
+        if self.epochs.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("encrypted trajectory epochs must be strictly increasing".into());
+        }
+        Ok(())
+    }
+}
+
+// Per-step content hashes of a plaintext trajectory, kept by the owner between
+// screenings. Ciphertexts are randomized, so re-encrypting an unchanged step gives
+// different bytes; changes are found by comparing these instead (see
+// `EncryptedTrajectory::diff_indices`). Frame and units are hashed into every step, so
+// re-expressing the trajectory changes all of them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrajectoryDigest {
+    steps: Vec<[u8; 32]>,
+}
+
+impl TrajectoryDigest {
+    pub fn of(data: &SatelliteData) -> Result<Self, Box<dyn std::error::Error>> {
+        if data.y.len() != data.x.len() || data.z.len() != data.x.len() {
+            return Err("trajectory axes have different lengths".into());
+        }
+        let header = bincode::serialize(&(data.frame, data.units))?;
+        let steps = (0..data.x.len())
+            .map(|i| {
+                let mut hasher = Sha256::new();
+                hasher.update(&header);
+                for v in [data.x[i], data.y[i], data.z[i]] {
+                    hasher.update(v.to_le_bytes());
+                }
+                hasher.finalize().into()
+            })
+            .collect();
+        Ok(Self { steps })
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+// A satellite trajectory encrypted under its owner's client key, one ciphertext per
+// coordinate per time step.
+//
+// `epochs` (seconds, strictly increasing) and `first_index` are public metadata. A
+// trajectory cut out of a longer one with `window` keeps the absolute index of its first
+// step, so results computed on the window map back to the original time steps.
+pub struct EncryptedTrajectory {
+    pub x: Vec<FheUint32>,
+    pub y: Vec<FheUint32>,
+    pub z: Vec<FheUint32>,
+    pub epochs: Vec<u64>,
+    pub first_index: usize,
+    pub frame: Frame,
+    pub units: Units,
+}
+
+impl EncryptedTrajectory {
+    // Encrypts `data`; epochs default to the step indices until set with `with_epochs`.
+    pub fn encrypt(
+        data: &SatelliteData,
+        client_key: &ClientKey,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if data.y.len() != data.x.len() || data.z.len() != data.x.len() {
+            return Err("trajectory axes have different lengths".into());
+        }
+        let encrypt_axis = |axis: &[u32]| -> Result<Vec<FheUint32>, Box<dyn std::error::Error>> {
+            let mut out = Vec::with_capacity(axis.len());
+            for &v in axis {
+                out.push(FheUint32::try_encrypt(v, client_key)?);
+            }
+            Ok(out)
+        };
+        Ok(Self {
+            x: encrypt_axis(&data.x)?,
+            y: encrypt_axis(&data.y)?,
+            z: encrypt_axis(&data.z)?,
+            epochs: (0..data.x.len() as u64).collect(),
+            first_index: 0,
+            frame: data.frame,
+            units: data.units,
+        })
+    }
+
+    // Inverse of `encrypt`, for the owner. Decoy steps of a padded trajectory are kept.
+    pub fn decrypt(&self, client_key: &ClientKey) -> SatelliteData {
+        let decrypt_axis = |axis: &[FheUint32]| -> Vec<u32> {
+            axis.iter().map(|v| v.decrypt(client_key)).collect()
+        };
+        SatelliteData {
+            x: decrypt_axis(&self.x),
+            y: decrypt_axis(&self.y),
+            z: decrypt_axis(&self.z),
+            frame: self.frame,
+            units: self.units,
+        }
+    }
+
+    pub fn with_epochs(mut self, epochs: Vec<u64>) -> Result<Self, Box<dyn std::error::Error>> {
+        if epochs.len() != self.len() {
+            return Err(format!(
+                "{} epochs given for a trajectory of {} steps",
+                epochs.len(),
+                self.len()
+            )
+            .into());
+        }
+        if epochs.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("epochs must be strictly increasing".into());
+        }
+        self.epochs = epochs;
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    // Index of step `i` of this trajectory within the trajectory it was cut from.
+    pub fn absolute_index(&self, i: usize) -> usize {
+        self.first_index + i
+    }
+
+    // Indices of the steps of `new` that differ from `old` or are past its end: the only
+    // ones an incremental screening needs to send and screen again.
+    pub fn diff_indices(old: &TrajectoryDigest, new: &TrajectoryDigest) -> Vec<usize> {
+        (0..new.len())
+            .filter(|&i| old.steps.get(i) != Some(&new.steps[i]))
+            .collect()
+    }
+
+    // Steps whose epoch lies in `[start, end)`, without decrypting anything: the
+    // ciphertexts are copied as-is.
+    pub fn window(&self, start: u64, end: u64) -> EncryptedTrajectory {
+        let from = self.epochs.partition_point(|&t| t < start);
+        let to = self.epochs.partition_point(|&t| t < end).max(from);
+        self.steps(from..to)
+    }
+
+    // Steps at positions `range` of this trajectory, copied like in `window`.
+    pub fn steps(&self, range: Range<usize>) -> EncryptedTrajectory {
+        EncryptedTrajectory {
+            x: self.x[range.clone()].to_vec(),
+            y: self.y[range.clone()].to_vec(),
+            z: self.z[range.clone()].to_vec(),
+            epochs: self.epochs[range.clone()].to_vec(),
+            first_index: self.first_index + range.start,
+            frame: self.frame,
+            units: self.units,
+        }
+    }
+
+    // Serialize every ciphertext individually with `safe_serialize_item` and pack them,
+    // together with the time metadata, into a single blob.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        migrate::encode_with(
+            ArtifactKind::Trajectory,
+            &self.to_serialized()?,
+            self.x.first().and_then(ParameterDigest::of_uint),
+        )
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_serialized(SerializedTrajectory::parse(data)?)
+            .map_err(|e| compat::explain(ArtifactKind::Trajectory, data, e))
+    }
+
+    pub(crate) fn to_serialized(&self) -> Result<SerializedTrajectory, Box<dyn std::error::Error>> {
+        let serialize_axis =
+            |axis: &[FheUint32]| -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+                axis.iter().map(safe_serialize_item).collect()
+            };
+        Ok(SerializedTrajectory {
+            frame: self.frame,
+            units: self.units,
+            first_index: self.first_index,
+            epochs: self.epochs.clone(),
+            x: serialize_axis(&self.x)?,
+            y: serialize_axis(&self.y)?,
+            z: serialize_axis(&self.z)?,
+        })
+    }
+
+    pub(crate) fn from_serialized(
+        serialized: SerializedTrajectory,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let deserialize_axis =
+            |axis: &[Vec<u8>]| -> Result<Vec<FheUint32>, Box<dyn std::error::Error>> {
+                safe_deserialize_items(axis)
+            };
+        Ok(Self {
+            x: deserialize_axis(&serialized.x)?,
+            y: deserialize_axis(&serialized.y)?,
+            z: deserialize_axis(&serialized.z)?,
+            epochs: serialized.epochs,
+            first_index: serialized.first_index,
+            frame: serialized.frame,
+            units: serialized.units,
+        })
+    }
+}
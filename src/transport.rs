@@ -0,0 +1,246 @@
+// Out-of-band transport for large artifacts.
+//
+// Server keys (hundreds of MB) and long encrypted trajectories often can't travel in a
+// request body. With an object store, the sender uploads the artifact and only sends an
+// `ArtifactRef` (object key, size, SHA-256) inside an `ArtifactRef` envelope; the
+// receiver downloads it and checks size and hash before using it, so a swapped or
+// truncated object is rejected rather than evaluated.
+//
+// The receiver only ever reads from its own configured store: the peer names an object
+// key, never a URL, so it can't point the receiver at a local file or another host.
+//
+// S3-compatible stores are driven through `curl --aws-sigv4` (curl >= 7.75), like the
+// catalog download; credentials are passed to curl on stdin, never on its command line.
+
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::protocol::MessageKind;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactRef {
+    // Key of the object in the store both parties are configured with.
+    pub key: String,
+    pub size: u64,
+    // Hex SHA-256 of the artifact bytes.
+    pub sha256: String,
+}
+
+// Payload of an `ArtifactRef` envelope: which artifact the reference stands for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactPointer {
+    pub kind: MessageKind,
+    pub artifact: ArtifactRef,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Object store section of a configuration file.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ObjectStoreConfig {
+    Local(LocalStore),
+    S3(S3Store),
+}
+
+impl ObjectStoreConfig {
+    pub fn store(&self) -> &(dyn ObjectStore + Send + Sync) {
+        match self {
+            ObjectStoreConfig::Local(store) => store,
+            ObjectStoreConfig::S3(store) => store,
+        }
+    }
+}
+
+pub trait ObjectStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>>;
+    fn get(&self, key: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
+}
+
+// Object keys are relative paths of `[A-Za-z0-9/_.-]` that don't climb out of the store
+// with a `..` component.
+pub fn check_key(key: &str) -> Result<(), String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "/_.-".contains(c);
+    if key.is_empty()
+        || key.starts_with('/')
+        || !key.chars().all(allowed)
+        || key.split('/').any(|part| part == "..")
+    {
+        return Err(format!("invalid object key: {:?}", key));
+    }
+    Ok(())
+}
+
+pub fn publish(
+    store: &dyn ObjectStore,
+    key: &str,
+    bytes: &[u8],
+) -> Result<ArtifactRef, Box<dyn std::error::Error>> {
+    store.put(key, bytes)?;
+    Ok(ArtifactRef {
+        key: key.to_string(),
+        size: bytes.len() as u64,
+        sha256: sha256_hex(bytes),
+    })
+}
+
+pub fn fetch_verified(
+    store: &dyn ObjectStore,
+    artifact: &ArtifactRef,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let bytes = store.get(&artifact.key)?;
+    if bytes.len() as u64 != artifact.size {
+        return Err(format!(
+            "artifact {} has {} bytes, expected {}",
+            artifact.key,
+            bytes.len(),
+            artifact.size
+        )
+        .into());
+    }
+    if sha256_hex(&bytes) != artifact.sha256 {
+        return Err(format!("artifact {} doesn't match its hash", artifact.key).into());
+    }
+    Ok(bytes)
+}
+
+// Objects as files in a directory (shared volume, tests), one file per key.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LocalStore {
+    pub dir: PathBuf,
+}
+
+impl ObjectStore for LocalStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        check_key(key)?;
+        let path = self.dir.join(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, bytes)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        check_key(key)?;
+        // A symlink in the directory could still lead out of it.
+        let path = self.dir.join(key).canonicalize()?;
+        if !path.starts_with(self.dir.canonicalize()?) {
+            return Err(format!("object {} is outside the store", key).into());
+        }
+        Ok(std::fs::read(path)?)
+    }
+}
+
+// S3-compatible bucket, path-style addressing (`{endpoint}/{bucket}/{key}`).
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct S3Store {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl fmt::Debug for S3Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "S3Store({}/{}, region={}, key={})",
+            self.endpoint, self.bucket, self.region, self.access_key
+        )
+    }
+}
+
+impl S3Store {
+    pub fn url(&self, key: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.endpoint.trim_end_matches('/'),
+            self.bucket,
+            key
+        )
+    }
+
+    // Runs curl with a config (credentials, signing, URL) written to its stdin, plus the
+    // `options` given as (name, value) pairs.
+    fn curl(&self, options: &[(&str, &str)]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut config = format!(
+            "silent\nshow-error\nfail\nuser = {}\naws-sigv4 = {}\n",
+            curl_value(&format!("{}:{}", self.access_key, self.secret_key))?,
+            curl_value(&format!("aws:amz:{}:s3", self.region))?
+        );
+        for (name, value) in options {
+            config.push_str(&format!("{} = {}\n", name, curl_value(value)?));
+        }
+        let mut child = Command::new("curl")
+            .args(["-K", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .ok_or("curl stdin unavailable")?
+            .write_all(config.as_bytes())?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "curl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl ObjectStore for S3Store {
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        check_key(key)?;
+        let url = self.url(key);
+        // curl signs the payload, so it needs it as a file rather than on stdin.
+        let staging = std::env::temp_dir().join(format!(
+            "sat-fhe-upload-{}-{}",
+            std::process::id(),
+            &sha256_hex(bytes)[..16]
+        ));
+        std::fs::write(&staging, bytes)?;
+        let result = self.curl(&[("upload-file", &staging.to_string_lossy()), ("url", &url)]);
+        std::fs::remove_file(&staging)?;
+        result?;
+        Ok(())
+    }
+
+    // Redirects aren't followed: the object is only ever read from the configured bucket.
+    fn get(&self, key: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        check_key(key)?;
+        self.curl(&[("url", &self.url(key))])
+    }
+}
+
+// Quoted string in curl's config syntax. Values that would need escaping are refused
+// rather than escaped, so nothing can end the string and start another option.
+fn curl_value(value: &str) -> Result<String, String> {
+    if value
+        .chars()
+        .any(|c| c.is_control() || c == '"' || c == '\\')
+    {
+        // Not echoed: the value may be a credential.
+        return Err(
+            "curl config values can't hold control characters, quotes or backslashes".into(),
+        );
+    }
+    Ok(format!("\"{}\"", value))
+}
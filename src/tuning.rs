@@ -0,0 +1,79 @@
+// Session parameters tuned to the link and the evaluator at hand.
+//
+// Fixed sizes suit no deployment well: 8 MB upload pieces waste most of a slow link's
+// time on the one piece a drop hits, and 16-step result batches either flood a fast
+// evaluator's owner with polls or keep a slow one's waiting for minutes. When opening a
+// session the client measures the round trip and throughput to the daemon with a couple
+// of probe requests, and the daemon reports how long one step took in its last screening
+// (`Request::Benchmark`). `tune` turns that into an upload piece size and a streaming
+// batch size for the session.
+
+use std::time::Duration;
+
+use crate::serve::STREAM_BATCH_STEPS;
+use crate::service::CHUNK_BYTES;
+
+// Bounds of the tuned upload piece size.
+pub const MIN_CHUNK_BYTES: usize = 256 << 10;
+pub const MAX_CHUNK_BYTES: usize = 64 << 20;
+
+// Bounds of the tuned streaming batch size, in steps.
+pub const MIN_BATCH_STEPS: usize = 1;
+pub const MAX_BATCH_STEPS: usize = 1024;
+
+// Each piece and each batch poll costs one round trip; sized to take this many round
+// trips' worth of time, that overhead stays around 2%.
+const ROUND_TRIPS_PER_UNIT: u32 = 50;
+
+// A batch takes at least this long to compute, so results don't arrive a step at a time.
+const MIN_BATCH_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkBenchmark {
+    // Round trip of an empty request.
+    pub rtt: Duration,
+    // Upload throughput measured with a probe payload.
+    pub bytes_per_second: f64,
+    // The daemon's time per screened step; `None` before its first screening.
+    pub step_time: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionTuning {
+    // Size of the pieces uploads are split into (see `Client::with_chunk_bytes`).
+    pub chunk_bytes: usize,
+    // Steps per streamed result batch.
+    pub batch_steps: usize,
+}
+
+impl Default for SessionTuning {
+    fn default() -> Self {
+        Self {
+            chunk_bytes: CHUNK_BYTES,
+            batch_steps: STREAM_BATCH_STEPS,
+        }
+    }
+}
+
+pub fn tune(benchmark: &LinkBenchmark) -> SessionTuning {
+    let unit = benchmark.rtt * ROUND_TRIPS_PER_UNIT;
+    let chunk_bytes = if benchmark.bytes_per_second.is_finite() && benchmark.bytes_per_second > 0.0
+    {
+        ((benchmark.bytes_per_second * unit.as_secs_f64()) as usize)
+            .clamp(MIN_CHUNK_BYTES, MAX_CHUNK_BYTES)
+    } else {
+        CHUNK_BYTES
+    };
+    let batch_steps = match benchmark.step_time {
+        Some(step) if !step.is_zero() => {
+            let batch_time = unit.max(MIN_BATCH_TIME);
+            (batch_time.as_secs_f64() / step.as_secs_f64()).ceil() as usize
+        }
+        _ => STREAM_BATCH_STEPS,
+    }
+    .clamp(MIN_BATCH_STEPS, MAX_BATCH_STEPS);
+    SessionTuning {
+        chunk_bytes,
+        batch_steps,
+    }
+}
@@ -0,0 +1,3 @@
+// Length units and the fixed-point encoding of positions, see `core::units`.
+
+pub use crate::core::units::{BIAS, CANONICAL_UNITS, Units, bias, decode, encode, rescale, unbias};
@@ -0,0 +1,104 @@
+// Follow-up on conjunction candidates: how fast the two objects pass each other.
+//
+// Once the owner has decrypted the collision flags, they ask the evaluator for the
+// relative velocity at the flagged steps. The evaluator computes, under encryption, the
+// squared change of the relative position between step `k` and `k + 1`:
+//
+//     |(A[k+1] - A[k]) - (B[k+1] - B[k])|^2
+//
+// which only the owner can decrypt and turn into a speed with the (public) epoch
+// spacing. The flagged step indices are revealed to the evaluator; nothing else is.
+
+use tfhe::prelude::*;
+use tfhe::{FheUint32, FheUint64};
+
+use crate::common::SatelliteData;
+use crate::depth::OpCounter;
+use crate::distance::DISTANCE_CAP;
+use crate::screening::align_plaintext;
+use crate::trajectory::EncryptedTrajectory;
+use crate::units::Units;
+
+pub struct RelativeVelocityOutput {
+    // Absolute step indices the velocities were computed at.
+    pub steps: Vec<usize>,
+    // Seconds between step `k` and `k + 1`, per requested step.
+    pub dt_s: Vec<u64>,
+    // Encrypted squared relative displacement over `dt_s`, in units of the encrypted
+    // trajectory squared. Per-axis components are clamped to `DISTANCE_CAP`.
+    pub squared_displacement: Vec<FheUint64>,
+    pub ops: OpCounter,
+}
+
+// Cost of one step: per axis two scalar additions, max, min, subtraction, clamp and
+// square, then two additions.
+pub fn relative_velocity_cost() -> OpCounter {
+    OpCounter {
+        comparisons: 9,
+        arithmetic: 14,
+        boolean: 0,
+        depth: 7,
+        depth_warning: None,
+    }
+}
+
+fn axis_component_squared(a0: &FheUint32, a1: &FheUint32, b0: u32, b1: u32) -> FheUint64 {
+    // (a1 - a0) - (b1 - b0) as the difference of two non-negative sums, so nothing wraps.
+    let a0: FheUint64 = a0.clone().cast_into();
+    let a1: FheUint64 = a1.clone().cast_into();
+    let p = a1 + b0 as u64;
+    let q = a0 + b1 as u64;
+    let diff = p.max(&q) - p.min(&q);
+    let diff = diff.min(DISTANCE_CAP as u64);
+    &diff * &diff
+}
+
+// Relative velocity at the absolute steps `steps` (typically the ones flagged by a
+// screening), each paired with the following step.
+//
+// Runs under the server key matching `encrypted`, e.g. inside `FheContext::evaluate_with`.
+pub fn relative_velocity(
+    encrypted: &EncryptedTrajectory,
+    plaintext: &SatelliteData,
+    steps: &[usize],
+) -> Result<RelativeVelocityOutput, Box<dyn std::error::Error>> {
+    let plaintext = align_plaintext(encrypted, plaintext)?;
+    let offset = encrypted.first_index;
+
+    let mut dt_s = Vec::with_capacity(steps.len());
+    let mut squared_displacement = Vec::with_capacity(steps.len());
+    for &k in steps {
+        if k < offset || k + 1 >= offset + encrypted.len() {
+            return Err(format!(
+                "step {} has no successor within steps {}..{}",
+                k,
+                offset,
+                offset + encrypted.len()
+            )
+            .into());
+        }
+        let i = k - offset;
+        dt_s.push(encrypted.epochs[i + 1] - encrypted.epochs[i]);
+        let component =
+            |a: &[FheUint32], b: &[u32]| axis_component_squared(&a[i], &a[i + 1], b[k], b[k + 1]);
+        squared_displacement.push(
+            component(&encrypted.x, &plaintext.x)
+                + component(&encrypted.y, &plaintext.y)
+                + component(&encrypted.z, &plaintext.z),
+        );
+    }
+
+    let mut ops = OpCounter::default();
+    ops.add_steps(&relative_velocity_cost(), steps.len() as u64);
+    Ok(RelativeVelocityOutput {
+        steps: steps.to_vec(),
+        dt_s,
+        squared_displacement,
+        ops,
+    })
+}
+
+// Owner side: relative speed in m/s from a decrypted squared displacement.
+pub fn relative_speed_m_s(squared_displacement: u64, dt_s: u64, units: Units) -> f64 {
+    (squared_displacement as f64).sqrt() * units.meters() / dt_s as f64
+}
@@ -0,0 +1,135 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use sat_trajectory_fhe::alerts::{AlertReport, AlertSink, WebhookAlert};
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::units::Units;
+
+#[derive(Debug, Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<AlertReport>>>);
+
+impl AlertSink for Recorder {
+    fn notify(&self, report: &AlertReport) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.lock().unwrap().push(report.clone());
+        Ok(())
+    }
+}
+
+fn report() -> AlertReport {
+    AlertReport {
+        steps: 10,
+        events: vec![ConjunctionEvent {
+            start_index: 3,
+            start_epoch: 180,
+            end_epoch: 240,
+            n_steps: 2,
+        }],
+    }
+}
+
+/// Decrypting a positive flag fires the configured sink once, with the clustered events;
+/// an all-negative result fires nothing.
+#[tokio::test]
+async fn test_owner_alerts_on_positive_flag() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![400, 101, 402],
+        ..sat1.clone()
+    };
+    let recorder = Recorder::default();
+    let owner = PartyBuilder::new(sat1.clone())
+        .owner()
+        .alert(recorder.clone())
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?.with_epochs(vec![0, 60, 120])?;
+
+    let evaluator = PartyBuilder::new(sat2)
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let output = evaluator.evaluate(&encrypted)?;
+    let events = owner.decrypt_events(&output.results, &encrypted.epochs, 0)?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].start_epoch, 60);
+
+    let far = PartyBuilder::new(SatelliteData {
+        x: vec![9, 9, 9],
+        ..sat1
+    })
+    .evaluator(owner.server_key_bytes()?)
+    .build()?;
+    let output = far.evaluate(&encrypted)?;
+    assert!(
+        owner
+            .decrypt_events(&output.results, &encrypted.epochs, 0)?
+            .is_empty()
+    );
+
+    let reports = recorder.0.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].steps, 3);
+    assert_eq!(reports[0].events, events);
+    Ok(())
+}
+
+/// The webhook POSTs the JSON report with the configured headers.
+#[test]
+fn test_webhook_posts_report() -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+    let server = std::thread::spawn(move || -> std::io::Result<(Vec<String>, String)> {
+        let (stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream);
+        let mut head = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            if line.trim_end().is_empty() {
+                break;
+            }
+            head.push(line.trim_end().to_string());
+        }
+        let length: usize = head
+            .iter()
+            .find_map(|h| {
+                h.to_ascii_lowercase()
+                    .strip_prefix("content-length: ")?
+                    .parse()
+                    .ok()
+            })
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")?;
+        Ok((head, String::from_utf8_lossy(&body).into_owned()))
+    });
+
+    let webhook = WebhookAlert {
+        url: format!("http://{}/hooks/conjunction", addr),
+        label: "SAT \"A\"".to_string(),
+        headers: vec!["Authorization: Bearer secret".to_string()],
+    };
+    webhook.notify(&report())?;
+
+    let (head, body) = server.join().unwrap()?;
+    assert_eq!(head[0], "POST /hooks/conjunction HTTP/1.1");
+    assert!(head.contains(&"Authorization: Bearer secret".to_string()));
+    assert_eq!(body, report().to_json("SAT \"A\""));
+    assert_eq!(
+        body,
+        "{\"label\":\"SAT \\\"A\\\"\",\"steps\":10,\"events\":[{\"start_index\":3,\
+         \"start_epoch\":180,\"end_epoch\":240,\"n_steps\":2}]}"
+    );
+    Ok(())
+}
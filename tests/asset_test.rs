@@ -0,0 +1,117 @@
+use std::f64::consts::TAU;
+
+use sat_trajectory_fhe::alerts::AlertReport;
+use sat_trajectory_fhe::asset::{ProtectedAsset, SafetyVolume};
+use sat_trajectory_fhe::core::canonical::{CANONICAL, CanonicalEncoding};
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::units::Units;
+
+// 2024-058T00:00:00Z, i.e. 2024-02-27.
+const START: u64 = 1_708_992_000;
+const RADIUS_M: f64 = 6_778_000.0;
+const PERIOD_S: f64 = 5_554.0;
+
+// A circular equatorial orbit standing in for the ISS.
+fn state(t_s: f64) -> ([f64; 3], [f64; 3]) {
+    let w = TAU / PERIOD_S;
+    let (sin, cos) = (w * t_s).sin_cos();
+    (
+        [RADIUS_M * cos, RADIUS_M * sin, 0.0],
+        [-RADIUS_M * w * sin, RADIUS_M * w * cos, 0.0],
+    )
+}
+
+// Four-minute samples over an hour, with day-of-year epochs like NASA's ISS ephemeris.
+fn oem() -> String {
+    let mut text = "CCSDS_OEM_VERS = 2.0\nCOMMENT synthetic\n\nMETA_START\n\
+                    OBJECT_NAME = ISS\nCENTER_NAME = EARTH\nREF_FRAME = EME2000\n\
+                    TIME_SYSTEM = UTC\nMETA_STOP\n\n"
+        .to_string();
+    for i in 0..16 {
+        let t = i as f64 * 240.0;
+        let (p, v) = state(t);
+        text.push_str(&format!(
+            "2024-058T00:{:02}:00.000Z {} {} {} {} {} {}\n",
+            i * 4,
+            p[0] / 1_000.0,
+            p[1] / 1_000.0,
+            p[2] / 1_000.0,
+            v[0] / 1_000.0,
+            v[1] / 1_000.0,
+            v[2] / 1_000.0
+        ));
+    }
+    text
+}
+
+/// Interpolation between published samples is accurate to well under a meter, and
+/// epochs outside the ephemeris are refused.
+#[test]
+fn test_ephemeris_interpolation() -> Result<(), Box<dyn std::error::Error>> {
+    let asset = ProtectedAsset::from_oem(&oem(), SafetyVolume::ISS)?;
+    assert_eq!(asset.name(), "ISS");
+    assert_eq!(asset.ephemeris.frame, Frame::Eci);
+    assert_eq!(asset.ephemeris.epochs_s[0], START as f64);
+    for t in [0, 100, 500, 1_999, 3_600] {
+        let position = asset.position_at(START + t)?;
+        let (expected, _) = state(t as f64);
+        let error = (0..3)
+            .map(|i| (position[i] - expected[i]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        assert!(error < 1.0, "{} m off at t = {} s", error, t);
+    }
+    assert!(asset.position_at(START - 1).is_err());
+    assert!(asset.position_at(START + 3_601).is_err());
+    Ok(())
+}
+
+/// An owner passing through the safety volume at one step is flagged at that step only,
+/// and the alert names the volume.
+#[test]
+fn test_screen_against_asset() -> Result<(), Box<dyn std::error::Error>> {
+    let asset = ProtectedAsset::from_oem(&oem(), SafetyVolume::ISS)?;
+    let epochs: Vec<u64> = (0..4).map(|i| START + 600 + i * 60).collect();
+    // 10 km radially at the second step, 100 km elsewhere.
+    let positions: Vec<[f64; 3]> = epochs
+        .iter()
+        .enumerate()
+        .map(|(i, &epoch)| {
+            let (p, _) = state((epoch - START) as f64);
+            let scale = if i == 1 { 10_000.0 } else { 100_000.0 } / RADIUS_M;
+            p.map(|c| c * (1.0 + scale))
+        })
+        .collect();
+    let owner_data = CanonicalEncoding {
+        units: Units::Kilometers,
+        ..CANONICAL
+    }
+    .encode_trajectory(&positions, Frame::Eci)?;
+    let owner = PartyBuilder::new(owner_data).owner().build()?;
+    let evaluator = asset.evaluator(owner.server_key_bytes()?, &epochs, Units::Kilometers)?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let output = evaluator.evaluate(&encrypted)?;
+    assert_eq!(
+        owner.decrypt_results(&output.results),
+        [false, true, false, false]
+    );
+
+    let report = AlertReport {
+        steps: 4,
+        events: vec![ConjunctionEvent {
+            start_index: 1,
+            start_epoch: epochs[1],
+            end_epoch: epochs[1],
+            n_steps: 1,
+        }],
+    };
+    assert_eq!(
+        asset.alert_text(&report),
+        "ISS safety volume (±2.0 km radial, ±25.0 km along-track, ±25.0 km cross-track): \
+         1 possible entry in 4 screened steps\n  \
+         2024-02-27T00:11:00.000 to 2024-02-27T00:11:00.000 UTC, 1 step\n"
+    );
+    Ok(())
+}
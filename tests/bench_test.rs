@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use sat_trajectory_fhe::bench::{
+    BenchKernel, Measurement, PerformanceProfile, Workload, run_profile, standard_workloads,
+};
+use sat_trajectory_fhe::depth::OpCounter;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::preset::ParameterPreset;
+
+/// The profile estimates from the longest workload of a kernel and round-trips through
+/// flat JSON.
+#[test]
+fn test_profile_estimates_and_json() -> Result<(), Box<dyn std::error::Error>> {
+    let workloads = standard_workloads(&BenchKernel::ALL, &[100, 1_000], 2);
+    assert_eq!(workloads.len(), 6);
+    assert!(workloads.iter().all(|w| w.pairs == 2));
+    assert_eq!(BenchKernel::parse("distance")?, BenchKernel::Distance);
+    assert!(BenchKernel::parse("fuzzy").is_err());
+
+    let measurement = |steps: usize, elapsed: Duration| Measurement {
+        workload: Workload {
+            kernel: BenchKernel::Box,
+            steps,
+            pairs: 2,
+        },
+        elapsed,
+        ops: OpCounter::default(),
+    };
+    let profile = PerformanceProfile {
+        preset: ParameterPreset::Tuniform2m64,
+        threads: 8,
+        measurements: vec![
+            measurement(100, Duration::from_secs(10)),
+            measurement(1_000, Duration::from_secs(40)),
+        ],
+    };
+    let boxed = KernelChoice::BoxThreshold { half_width: 5 };
+    assert_eq!(profile.step_time(boxed), Some(Duration::from_millis(20)));
+    assert_eq!(profile.estimate(boxed, 500), Some(Duration::from_secs(10)));
+    assert_eq!(profile.step_time(KernelChoice::ExactMatch), None);
+
+    let json = profile.to_json();
+    assert!(json.starts_with("{\"preset\":\"tuniform2m64\",\"threads\":8,\"measurements\":[{"));
+    assert!(json.contains(
+        "\"kernel\":\"box\",\"steps\":1000,\"pairs\":2,\"elapsed_ns\":40000000000,\"step_ns\":20000000"
+    ));
+    assert_eq!(PerformanceProfile::from_json(&json)?, profile);
+    assert!(PerformanceProfile::from_json("{\"preset\":\"default\"}").is_err());
+    Ok(())
+}
+
+/// A tiny real run times every workload and counts the kernel's work.
+#[test]
+fn test_run_profile() -> Result<(), Box<dyn std::error::Error>> {
+    let workloads = standard_workloads(&[BenchKernel::Eq], &[2], 2);
+    let profile = run_profile(ParameterPreset::Default, &workloads)?;
+    assert_eq!(profile.measurements.len(), 1);
+    let measurement = profile.measurements[0];
+    assert!(measurement.elapsed > Duration::ZERO);
+    assert_eq!(measurement.ops.comparisons, 2 * 3);
+    assert!(profile.estimate(KernelChoice::ExactMatch, 10).is_some());
+    Ok(())
+}
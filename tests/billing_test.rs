@@ -0,0 +1,49 @@
+use sat_trajectory_fhe::billing::{PriceList, UsageLog, UsageMeter, UsageRecord, cost_report_csv};
+use sat_trajectory_fhe::depth::OpCounter;
+
+fn record(session: &str, client: &str, steps: u64) -> UsageRecord {
+    UsageRecord {
+        session: session.to_string(),
+        client: client.to_string(),
+        steps,
+        ops: OpCounter {
+            comparisons: 3 * steps,
+            arithmetic: steps,
+            boolean: 2 * steps,
+            depth: 3,
+            depth_warning: None,
+        },
+        unix_s: 1_717_200_000,
+    }
+}
+
+/// Records add up per session into one priced row each, then a total.
+#[test]
+fn test_cost_report_csv() -> Result<(), Box<dyn std::error::Error>> {
+    let log = UsageLog::default();
+    log.record(&record("7", "10.0.0.2", 16))?;
+    log.record(&record("3", "10.0.0.1", 4))?;
+    log.record(&record("7", "10.0.0.2", 2))?;
+    let prices = PriceList {
+        comparison: 0.01,
+        arithmetic: 0.02,
+        boolean: 0.001,
+    };
+    assert_eq!(
+        cost_report_csv(&log.records(), &prices),
+        "session,client,steps,comparisons,arithmetic,boolean,cost\n\
+         3,10.0.0.1,4,12,4,8,0.208000\n\
+         7,10.0.0.2,18,54,18,36,0.936000\n\
+         total,,22,66,22,44,1.144000\n"
+    );
+    assert_eq!(log.drain().len(), 3);
+    assert!(log.records().is_empty());
+    Ok(())
+}
+
+/// Session names that would break the CSV are quoted.
+#[test]
+fn test_cost_report_quotes_fields() {
+    let report = cost_report_csv(&[record("a,\"b\"", "c", 1)], &PriceList::default());
+    assert!(report.contains("\"a,\"\"b\"\"\",c,1,"), "{}", report);
+}
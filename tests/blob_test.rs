@@ -0,0 +1,88 @@
+use std::io::ErrorKind;
+
+use sat_trajectory_fhe::blob::{BlobStore, DedupStore, FsStore, MemoryStore};
+use sat_trajectory_fhe::transport::sha256_hex;
+
+fn exercise(store: &dyn BlobStore) -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(store.get("job-1/results.bin")?, None);
+    assert!(!store.contains("job-1/results.bin")?);
+    assert_eq!(
+        store.read("job-1/results.bin").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    store.put("job-1/results.bin", b"first")?;
+    store.put("job-1/results.bin", b"second")?;
+    store.put("job-2/results.bin", b"other")?;
+    assert!(store.contains("job-1/results.bin")?);
+    assert_eq!(store.read("job-1/results.bin")?, b"second");
+    assert_eq!(store.get("job-2/results.bin")?, Some(b"other".to_vec()));
+
+    store.delete("job-1/results.bin")?;
+    store.delete("job-1/results.bin")?;
+    assert_eq!(store.get("job-1/results.bin")?, None);
+    assert_eq!(store.read("job-2/results.bin")?, b"other");
+    Ok(())
+}
+
+/// Both backends behave the same behind the trait.
+#[test]
+fn test_blob_stores() -> Result<(), Box<dyn std::error::Error>> {
+    exercise(&MemoryStore::default())?;
+    exercise(&DedupStore::new(Box::new(MemoryStore::default())))?;
+
+    let dir = std::env::temp_dir().join(format!("blob_test_{}", std::process::id()));
+    exercise(&FsStore::new(&dir)?)?;
+    assert_eq!(
+        std::fs::read(dir.join("job-2").join("results.bin"))?,
+        b"other"
+    );
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// File-backed keys can't reach outside the store's directory.
+#[test]
+fn test_fs_store_rejects_escaping_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("blob_keys_test_{}", std::process::id()));
+    let store = FsStore::new(&dir)?;
+    for key in ["", "../outside", "job-1/../../outside", "/etc/passwd"] {
+        assert_eq!(
+            store.put(key, b"x").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Identical blobs under different keys are stored once and removed with their last key;
+/// blobs stored before deduplication stay readable.
+#[test]
+fn test_dedup_store() -> Result<(), Box<dyn std::error::Error>> {
+    let inner = MemoryStore::default();
+    inner.put("job-0/trajectory.bin", b"legacy")?;
+    let store = DedupStore::new(Box::new(inner));
+    assert_eq!(store.read("job-0/trajectory.bin")?, b"legacy");
+
+    let catalog = vec![7u8; 4096];
+    let object = DedupStore::object_key(&sha256_hex(&catalog));
+    store.put("job-1/trajectory.bin", &catalog)?;
+    store.put("job-2/trajectory.bin", &catalog)?;
+    assert_eq!(store.read("job-2/trajectory.bin")?, catalog);
+    assert_eq!(store.inner().read(&object)?, catalog);
+    assert!(store.inner().read("job-1/trajectory.bin")?.len() < 100);
+
+    store.delete("job-1/trajectory.bin")?;
+    assert_eq!(store.get("job-1/trajectory.bin")?, None);
+    assert_eq!(store.read("job-2/trajectory.bin")?, catalog);
+
+    // Replacing the last reference drops the object.
+    store.put("job-2/trajectory.bin", b"refreshed")?;
+    assert!(!store.inner().contains(&object)?);
+    store.put("job-3/trajectory.bin", &catalog)?;
+    store.delete("job-3/trajectory.bin")?;
+    assert!(!store.inner().contains(&object)?);
+    assert_eq!(store.read("job-2/trajectory.bin")?, b"refreshed");
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "bundle")]
+
+use sat_trajectory_fhe::bundle::Bundle;
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::{MessageKind, SessionMetadata};
+use sat_trajectory_fhe::session::Session;
+
+// The owner's side of a session and every message exchanged in it.
+type Screening = (Session, Vec<Vec<u8>>);
+
+fn screening() -> Result<Screening, Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&SessionMetadata::default())?;
+    let (mut evaluator, _) = Session::accept(&hello)?;
+    let key = owner.send(MessageKind::ServerKey, b"server key".to_vec())?;
+    let trajectory = owner.send(MessageKind::EncryptedTrajectory, b"trajectory".to_vec())?;
+    evaluator.receive(&key)?;
+    evaluator.receive(&trajectory)?;
+    let results = evaluator.send(MessageKind::Results, b"results".to_vec())?;
+    owner.receive(&results)?;
+    Ok((owner, vec![hello, key, trajectory, results]))
+}
+
+fn events() -> Vec<ConjunctionEvent> {
+    vec![ConjunctionEvent {
+        start_index: 4,
+        start_epoch: 1040,
+        end_epoch: 1060,
+        n_steps: 3,
+    }]
+}
+
+/// A bundle reads back as written and reproduces the session transcript.
+#[test]
+fn test_bundle_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let (owner, messages) = screening()?;
+    let bundle = Bundle::new(ParameterPreset::Tuniform2m64, messages, 10, events())?;
+    assert_eq!(bundle.transcript, owner.transcript());
+    assert_eq!(bundle.nonce, owner.nonce());
+
+    let mut archive = Vec::new();
+    bundle.write(&mut archive)?;
+    let read = Bundle::read(archive.as_slice())?;
+    assert_eq!(read, bundle);
+    assert!(read.software.starts_with("sat-trajectory-fhe "));
+    Ok(())
+}
+
+/// Altered, missing or reordered messages are rejected.
+#[test]
+fn test_bundle_tampering() -> Result<(), Box<dyn std::error::Error>> {
+    let (_, messages) = screening()?;
+    let bundle = Bundle::new(ParameterPreset::Default, messages.clone(), 10, events())?;
+    let mut archive = Vec::new();
+    bundle.write(&mut archive)?;
+    let at = archive
+        .windows(7)
+        .position(|w| w == b"results")
+        .ok_or("results not in archive")?;
+    archive[at] ^= 1;
+    assert!(Bundle::read(archive.as_slice()).is_err());
+
+    for messages in [
+        messages[..3].to_vec(),
+        vec![
+            messages[0].clone(),
+            messages[2].clone(),
+            messages[1].clone(),
+            messages[3].clone(),
+        ],
+    ] {
+        let altered = Bundle {
+            messages,
+            ..bundle.clone()
+        };
+        let mut archive = Vec::new();
+        altered.write(&mut archive)?;
+        assert!(Bundle::read(archive.as_slice()).is_err());
+    }
+
+    let (_, other) = screening()?;
+    let mixed = vec![messages[0].clone(), other[1].clone()];
+    assert!(Bundle::new(ParameterPreset::Default, mixed, 10, events()).is_err());
+    Ok(())
+}
@@ -0,0 +1,147 @@
+#![cfg(feature = "serve")]
+
+use sat_trajectory_fhe::alerts::AlertReport;
+use sat_trajectory_fhe::campaign::{CampaignConfig, KernelConfig, PartyRole};
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::units::Units;
+
+const CAMPAIGN: &str = r#"
+name = "geo-75e"
+cadence_s = 21600
+
+[window]
+start = "2024-06-01T00:00:00Z"
+step_s = 1200
+steps = 3
+
+[[party]]
+name = "SAT-A"
+role = "owner"
+trajectory = "sat-a.bin"
+
+[[party]]
+name = "SAT-B"
+role = "evaluator"
+trajectory = "sat-b.bin"
+address = "screening.example.org:7878"
+
+[[screening]]
+owner = "SAT-A"
+evaluator = "SAT-B"
+kernel = { type = "box", half_width_m = 25000.0 }
+
+[[screening]]
+owner = "SAT-A"
+evaluator = "SAT-B"
+kernel = { type = "distance", threshold_m = 1500.0 }
+window = { start = "2024-06-02T00:00:00Z", step_s = 60, steps = 10 }
+
+[[sink]]
+type = "webhook"
+url = "https://ops.example.org/conjunctions"
+headers = ["Authorization: Bearer secret"]
+
+[daemon]
+listen = "0.0.0.0:7878"
+storage_dir = "/var/lib/sat-fhe"
+trajectory = "sat-b.bin"
+"#;
+
+/// A campaign parses into its parties, windows, kernels in each unit, and daemon.
+#[test]
+fn test_campaign_from_toml() -> Result<(), Box<dyn std::error::Error>> {
+    let campaign = CampaignConfig::from_toml(CAMPAIGN).map_err(|e| e.to_string())?;
+    assert_eq!(
+        campaign.window.epochs(),
+        [1_717_200_000, 1_717_201_200, 1_717_202_400]
+    );
+    let second = &campaign.screenings[1];
+    assert_eq!(campaign.window_of(second).start, 1_717_286_400);
+    assert_eq!(
+        campaign.screenings[0].kernel,
+        KernelConfig::Box {
+            half_width_m: 25_000.0
+        }
+    );
+    let kernel = |i: usize, units| campaign.screenings[i].kernel.choice(units).unwrap();
+    assert_eq!(
+        kernel(0, Units::Kilometers),
+        KernelChoice::BoxThreshold { half_width: 25 }
+    );
+    assert_eq!(
+        kernel(1, Units::Kilometers),
+        KernelChoice::SquaredDistanceThreshold { threshold: 2 }
+    );
+    assert_eq!(
+        kernel(1, Units::Meters),
+        KernelChoice::SquaredDistanceThreshold { threshold: 1_500 }
+    );
+    let evaluator = campaign
+        .party("SAT-B", PartyRole::Evaluator)
+        .map_err(|e| e.to_string())?;
+    assert_eq!(
+        evaluator.address.as_deref(),
+        Some("screening.example.org:7878")
+    );
+    assert_eq!(campaign.daemon.as_ref().map(|d| d.max_jobs), Some(1));
+    assert_eq!(campaign.sinks().len(), 1);
+
+    let summary = campaign.summary();
+    assert!(summary.contains("every 21600 s"), "{}", summary);
+    assert!(summary.contains("SAT-A -> SAT-B (screening.example.org:7878)"));
+    assert!(!summary.contains("secret"), "{}", summary);
+    Ok(())
+}
+
+/// Inconsistent campaigns are refused when loaded, not when they run.
+#[test]
+fn test_campaign_validation() {
+    let refused = |from: &str, to: &str| {
+        let text = CAMPAIGN.replacen(from, to, 1);
+        CampaignConfig::from_toml(&text)
+            .err()
+            .unwrap_or_else(|| panic!("{:?} accepted", to))
+            .to_string()
+    };
+    assert!(refused("evaluator = \"SAT-B\"", "evaluator = \"SAT-C\"").contains("SAT-C"));
+    assert!(refused("evaluator = \"SAT-B\"", "evaluator = \"SAT-A\"").contains("not an evaluator"));
+    assert!(refused("name = \"SAT-B\"", "name = \"SAT-A\"").contains("twice"));
+    refused("steps = 3", "steps = 0");
+    refused("cadence_s = 21600", "cadence_s = 0");
+    refused("half_width_m = 25000.0", "half_width_m = -1.0");
+    refused("2024-06-01T00:00:00Z", "June 1st");
+    refused("type = \"box\"", "type = \"sphere\"");
+    refused("address =", "adress =");
+    refused("[daemon]", "[daemon]\nmax_jobs = 0");
+}
+
+/// The JSON-lines sink appends one report per line.
+#[test]
+fn test_json_lines_sink() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("campaign_alerts_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let text = CAMPAIGN.replace(
+        "type = \"webhook\"\nurl = \"https://ops.example.org/conjunctions\"\nheaders = [\"Authorization: Bearer secret\"]",
+        &format!("type = \"json_lines\"\npath = {:?}", path.display().to_string()),
+    );
+    let campaign = CampaignConfig::from_toml(&text).map_err(|e| e.to_string())?;
+    let report = AlertReport {
+        steps: 3,
+        events: vec![ConjunctionEvent {
+            start_index: 1,
+            start_epoch: 1_717_201_200,
+            end_epoch: 1_717_201_200,
+            n_steps: 1,
+        }],
+    };
+    for sink in campaign.sinks() {
+        sink.notify(&report)?;
+        sink.notify(&report)?;
+    }
+    let written = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(written.lines().count(), 2);
+    assert!(written.starts_with("{\"label\":\"geo-75e\""), "{}", written);
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use sat_trajectory_fhe::core::SatelliteData;
+use sat_trajectory_fhe::core::canonical::{ByteOrder, CANONICAL, CanonicalEncoding};
+use sat_trajectory_fhe::core::frame::Frame;
+use sat_trajectory_fhe::core::units::Units;
+
+/// Fixed vectors any client must reproduce bit for bit: offset 2^31, halves rounded away
+/// from zero, x y z little-endian.
+#[test]
+fn test_canonical_known_answers() -> Result<(), Box<dyn std::error::Error>> {
+    let positions = [[1.0, -1.0, 0.4], [0.5, -0.5, -2_147_483_648.0]];
+    let data = CANONICAL.encode_trajectory(&positions, Frame::Eci)?;
+    assert_eq!(data.x, vec![0x8000_0001, 0x8000_0001]);
+    assert_eq!(data.y, vec![0x7fff_ffff, 0x7fff_ffff]);
+    assert_eq!(data.z, vec![0x8000_0000, 0]);
+    assert_eq!(
+        CANONICAL.to_bytes(&data)?,
+        [
+            0x01, 0x00, 0x00, 0x80, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x00, 0x80, //
+            0x01, 0x00, 0x00, 0x80, 0xff, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x00, 0x00,
+        ]
+    );
+
+    // The canonical spec is what the rest of the crate encodes with.
+    let ground = SatelliteData::encode(&positions, Units::Meters, Frame::Eci)?;
+    assert_eq!((ground.x, ground.y, ground.z), (data.x, data.y, data.z));
+    assert!(CANONICAL.encode([2_147_483_648.0, 0.0, 0.0]).is_err());
+    Ok(())
+}
+
+/// Encoding, serializing and reading back yields the same cells, whatever the layout.
+#[test]
+fn test_canonical_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let positions = [
+        [6_771_000.4, -12_345.6, 42.0],
+        [-6_771_000.0, 0.0, 1_000_000.5],
+    ];
+    let big_km = CanonicalEncoding {
+        units: Units::Kilometers,
+        byte_order: ByteOrder::BigEndian,
+        ..CANONICAL
+    };
+    for spec in [CANONICAL, big_km] {
+        let data = spec.encode_trajectory(&positions, Frame::Ecef)?;
+        let bytes = spec.to_bytes(&data)?;
+        assert_eq!(bytes.len(), 2 * 12);
+        let back = spec.from_bytes(&bytes, Frame::Ecef)?;
+        assert_eq!((&back.x, &back.y, &back.z), (&data.x, &data.y, &data.z));
+        assert_eq!(back.units, spec.units);
+        for (decoded, original) in spec.decode_trajectory(&data).iter().zip(&positions) {
+            for axis in 0..3 {
+                assert!((decoded[axis] - original[axis]).abs() <= spec.units.meters() / 2.0);
+            }
+        }
+    }
+    assert_eq!(
+        big_km.to_bytes(&big_km.encode_trajectory(&[[1000.0, 0.0, 0.0]], Frame::Ecef)?)?[..4],
+        [0x80, 0x00, 0x00, 0x01]
+    );
+
+    // Bytes from one layout are rejected where the units disagree or the length is off.
+    let data = CANONICAL.encode_trajectory(&positions, Frame::Ecef)?;
+    assert!(big_km.to_bytes(&data).is_err());
+    assert!(CANONICAL.from_bytes(&[0; 13], Frame::Ecef).is_err());
+    Ok(())
+}
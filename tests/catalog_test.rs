@@ -0,0 +1,88 @@
+#![cfg(feature = "catalog")]
+
+use sat_trajectory_fhe::catalog::{CatalogFilter, TimeGrid, parse_tle, screening_set};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::regime::AltitudeBand;
+use sat_trajectory_fhe::units::Units;
+
+const TLES: &str = "\
+ISS (ZARYA)
+1 25544U 98067A   24001.50000000  .00016717  00000-0  10270-3 0  9005
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391428520
+GOES 16
+1 41866U 16071A   24001.50000000 -.00000262  00000-0  00000+0 0  9991
+2 41866   0.0518 269.7286 0001140 222.4290 254.6117  1.00271068 26171
+";
+
+/// TLEs parse into sensible orbital elements.
+#[test]
+fn test_parse_tle() -> Result<(), Box<dyn std::error::Error>> {
+    let tles = parse_tle(TLES)?;
+    assert_eq!(tles.len(), 2);
+
+    let iss = &tles[0];
+    assert_eq!(iss.name, "ISS (ZARYA)");
+    assert_eq!(iss.norad_id, 25544);
+    assert!((iss.inclination_deg - 51.6416).abs() < 1e-9);
+    assert!((iss.eccentricity - 0.0006703).abs() < 1e-12);
+    // 2024-01-01T12:00:00Z
+    assert!((iss.epoch_unix_s - 1_704_110_400.0).abs() < 1e-3);
+
+    let (perigee, apogee) = iss.perigee_apogee_km();
+    assert!(perigee > 350.0 && apogee < 450.0, "{} {}", perigee, apogee);
+
+    let (perigee, apogee) = tles[1].perigee_apogee_km();
+    assert!((perigee - 35_786.0).abs() < 50.0 && (apogee - 35_786.0).abs() < 50.0);
+
+    Ok(())
+}
+
+/// Propagated positions stay on the orbit radius.
+#[test]
+fn test_propagation_radius() -> Result<(), Box<dyn std::error::Error>> {
+    let tles = parse_tle(TLES)?;
+    let iss = &tles[0];
+    let a = iss.semi_major_axis_km();
+    for k in 0..10 {
+        let [x, y, z] = iss.position_km(iss.epoch_unix_s + k as f64 * 600.0);
+        let r = (x * x + y * y + z * z).sqrt();
+        assert!((r - a).abs() < a * iss.eccentricity + 1.0);
+    }
+    Ok(())
+}
+
+/// Only objects overlapping the user's altitude band end up in the screening set.
+#[test]
+fn test_screening_set_filters_by_altitude() -> Result<(), Box<dyn std::error::Error>> {
+    let tles = parse_tle(TLES)?;
+    let filter = CatalogFilter {
+        band: AltitudeBand::new(300.0, 600.0)?,
+        inclination_deg: None,
+        inclination_tolerance_deg: 0.0,
+    };
+    let grid = TimeGrid {
+        start_unix_s: tles[0].epoch_unix_s,
+        step_s: 60.0,
+        steps: 5,
+    };
+
+    let set = screening_set(&tles, &filter, &grid, Units::Kilometers, Frame::Eci)?;
+    assert_eq!(set.len(), 1);
+    assert_eq!(set[0].norad_id, 25544);
+    assert_eq!(set[0].trajectory.x.len(), 5);
+    assert_eq!(set[0].trajectory.units, Units::Kilometers);
+
+    Ok(())
+}
+
+/// Non-ASCII data lines are rejected instead of panicking on a char boundary.
+#[test]
+fn test_parse_tle_rejects_non_ascii() {
+    let tle = "\
+ISS (ZARYA)
+1 2é544U 98067A   24001.50000000  .00016717  00000-0  10270-3 0  9005
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391428520
+";
+    let err = parse_tle(tle).unwrap_err();
+    assert_eq!(err.to_string(), "malformed TLE for ISS (ZARYA)");
+}
@@ -0,0 +1,56 @@
+use tfhe::prelude::*;
+use tfhe::{ConfigBuilder, FheBool};
+
+use sat_trajectory_fhe::certificate::WorkCertificate;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::units::Units;
+
+/// The owner accepts a certificate of the work done and rejects skipped, reordered or
+/// swapped chunks.
+#[tokio::test]
+async fn test_work_certificate() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = owner.encrypt(&SatelliteData {
+        x: vec![1, 2, 3, 4],
+        y: vec![5, 6, 7, 8],
+        z: vec![9, 10, 11, 12],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    })?;
+    let flags: Vec<FheBool> = owner.evaluate_with(|| {
+        Ok::<_, Box<dyn std::error::Error>>(
+            encrypted
+                .x
+                .iter()
+                .map(|x| x.eq(2u32) | x.eq(3u32))
+                .collect(),
+        )
+    })?;
+
+    let mut certificate = WorkCertificate::default();
+    certificate.record(&encrypted, 0..2, &flags[0..2])?;
+    certificate.record(&encrypted, 2..4, &flags[2..4])?;
+    certificate.verify(&encrypted, &flags)?;
+    let certificate = WorkCertificate::from_bytes(&certificate.to_bytes()?)?;
+    certificate.verify(&encrypted, &flags)?;
+
+    // A chunk missing, the chunks in the wrong order, or flags not the ones committed to.
+    let skipped = WorkCertificate {
+        chunks: certificate.chunks[..1].to_vec(),
+    };
+    assert!(skipped.verify(&encrypted, &flags).is_err());
+    let reordered = WorkCertificate {
+        chunks: certificate.chunks.iter().rev().cloned().collect(),
+    };
+    assert!(reordered.verify(&encrypted, &flags).is_err());
+    let mut swapped = flags.clone();
+    swapped.swap(0, 1);
+    assert!(certificate.verify(&encrypted, &swapped).is_err());
+    // Another encryption of the same value.
+    let mut replaced = flags.clone();
+    replaced[3] = flags[0].clone();
+    assert!(certificate.verify(&encrypted, &replaced).is_err());
+    Ok(())
+}
@@ -0,0 +1,608 @@
+#![cfg(feature = "serve")]
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use sat_trajectory_fhe::blob::BlobStoreConfig;
+use sat_trajectory_fhe::client::Client;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::net::in_process;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::{MessageKind, SessionMetadata};
+use sat_trajectory_fhe::quota::QuotaConfig;
+use sat_trajectory_fhe::serve::{Daemon, ServeConfig};
+use sat_trajectory_fhe::service::{JobStatus, Request, ServiceError};
+use sat_trajectory_fhe::shard::{Sharded, shard_ranges};
+use sat_trajectory_fhe::transport::{LocalStore, ObjectStoreConfig, publish};
+use sat_trajectory_fhe::units::Units;
+
+/// Owner-side round trip through the daemon using only the client SDK, with the server
+/// key sent by reference through an object store.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_client_screening_through_daemon() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let evaluator_trajectory = SatelliteData {
+        x: vec![400, 101, 402],
+        y: vec![500, 201, 502],
+        z: vec![600, 301, 602],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&evaluator_trajectory)?,
+    )?;
+    let store = LocalStore {
+        dir: dir.join("artifacts"),
+    };
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: Some(ObjectStoreConfig::Local(store.clone())),
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let owner = PartyBuilder::new(SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    })
+    .config(ParameterPreset::Default.config())
+    .owner()
+    .build()
+    .map_err(|e| e.to_string())?;
+
+    let mut client = Client::connect(addr).await?;
+    assert_eq!(client.info().await?.preset, ParameterPreset::Default);
+
+    let mut job = client.open_session(&SessionMetadata::default()).await?;
+    assert_eq!(client.status(&job).await?, JobStatus::AwaitingUploads);
+    assert!(client.results(&mut job).await.is_err());
+
+    // The server key goes through the object store, the trajectory inline.
+    let server_key = owner.server_key_bytes().map_err(|e| e.to_string())?;
+    let artifact = publish(&store, "server_key.bin", &server_key).map_err(|e| e.to_string())?;
+    client
+        .upload_artifact_ref(&mut job, MessageKind::ServerKey, artifact)
+        .await?;
+    let encrypted = owner.encrypt_trajectory().map_err(|e| e.to_string())?;
+    client.upload_trajectory(&mut job, &encrypted).await?;
+
+    client.wait(&job, Duration::from_millis(200)).await?;
+    let results = client.verified_results(&mut job, &encrypted).await?;
+    assert_eq!(owner.decrypt_results(&results), vec![false, true, false]);
+    let certificate = client.certificate(&job).await?;
+    assert_eq!(certificate.chunks.iter().map(|c| c.steps).sum::<usize>(), 3);
+
+    // Fetching again resends the envelope already sealed, which this session has accepted,
+    // instead of sealing a new one the session would take.
+    let Err(err) = client.results(&mut job).await else {
+        panic!("a resealed envelope was accepted");
+    };
+    assert!(
+        err.to_string().contains("unexpected sequence number"),
+        "{}",
+        err
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Two owners with different keys interleave their uploads on one daemon; both jobs run
+/// concurrently and each owner decrypts only its own, correct, results.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_interleaved_sessions_with_different_keys() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_multi_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let evaluator_trajectory = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&evaluator_trajectory)?,
+    )?;
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 2,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let owner = |x: Vec<u32>| {
+        PartyBuilder::new(SatelliteData {
+            x,
+            y: vec![200, 201, 202],
+            z: vec![300, 301, 302],
+            frame: Frame::Eci,
+            units: Units::Meters,
+        })
+        .owner()
+        .build()
+        .map_err(|e| e.to_string())
+    };
+    let owner_a = owner(vec![100, 0, 0])?;
+    let owner_b = owner(vec![0, 101, 102])?;
+
+    let mut client = Client::connect(addr).await?;
+    let mut job_a = client.open_session(&SessionMetadata::default()).await?;
+    let mut job_b = client.open_session(&SessionMetadata::default()).await?;
+
+    let key_a = owner_a.server_key_bytes().map_err(|e| e.to_string())?;
+    let key_b = owner_b.server_key_bytes().map_err(|e| e.to_string())?;
+    let trajectory_a = owner_a.encrypt_trajectory().map_err(|e| e.to_string())?;
+    let trajectory_b = owner_b.encrypt_trajectory().map_err(|e| e.to_string())?;
+    client.upload_server_key(&mut job_a, key_a).await?;
+    client.upload_server_key(&mut job_b, key_b).await?;
+    client.upload_trajectory(&mut job_a, &trajectory_a).await?;
+    client.upload_trajectory(&mut job_b, &trajectory_b).await?;
+
+    client.wait(&job_a, Duration::from_millis(200)).await?;
+    client.wait(&job_b, Duration::from_millis(200)).await?;
+    let results_a = client.results(&mut job_a).await?;
+    let results_b = client.results(&mut job_b).await?;
+    assert_eq!(
+        owner_a.decrypt_results(&results_a),
+        vec![true, false, false]
+    );
+    assert_eq!(owner_b.decrypt_results(&results_b), vec![false, true, true]);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Cancelled jobs stop without losing their work: a job cancelled before its uploads or
+/// while queued behind another reports how many steps it screened, and the flags of those
+/// steps come back certified.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cancel_and_partial_results() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_cancel_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&SatelliteData {
+            x: vec![400, 101, 402],
+            y: vec![500, 201, 502],
+            z: vec![600, 301, 602],
+            frame: Frame::Eci,
+            units: Units::Meters,
+        })?,
+    )?;
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: BlobStoreConfig::Memory,
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let owner = PartyBuilder::new(SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    })
+    .owner()
+    .build()
+    .map_err(|e| e.to_string())?;
+    let server_key = owner.server_key_bytes().map_err(|e| e.to_string())?;
+    let encrypted = owner.encrypt_trajectory().map_err(|e| e.to_string())?;
+    let interval = Duration::from_millis(200);
+
+    let mut client = Client::connect(addr).await?;
+    let mut idle = client.open_session(&SessionMetadata::default()).await?;
+    assert_eq!(
+        client.cancel(&idle, interval).await?,
+        JobStatus::Cancelled { steps: 0 }
+    );
+    assert!(
+        client
+            .upload_trajectory(&mut idle, &encrypted)
+            .await
+            .is_err()
+    );
+    assert!(client.wait(&idle, interval).await.is_err());
+    assert!(
+        client
+            .partial_results(&mut idle, &encrypted)
+            .await?
+            .is_empty()
+    );
+
+    // With a single evaluation thread, the second job waits for the first.
+    let mut first = client.open_session(&SessionMetadata::default()).await?;
+    let mut second = client.open_session(&SessionMetadata::default()).await?;
+    for job in [&mut first, &mut second] {
+        client.upload_server_key(job, server_key.clone()).await?;
+        client.upload_trajectory(job, &encrypted).await?;
+    }
+    let steps = match client.cancel(&second, interval).await? {
+        JobStatus::Cancelled { steps } => steps,
+        JobStatus::Done => encrypted.len(),
+        other => return Err(format!("unexpected status {:?}", other).into()),
+    };
+    let partial = client.partial_results(&mut second, &encrypted).await?;
+    assert_eq!(partial.len(), steps);
+    assert_eq!(
+        owner.decrypt_results(&partial.flags),
+        [false, true, false][..steps]
+    );
+    assert!(client.cancel(&second, interval).await.is_err());
+
+    client.wait(&first, interval).await?;
+    let partial = client.partial_results(&mut first, &encrypted).await?;
+    assert_eq!(
+        owner.decrypt_results(&partial.flags),
+        vec![false, true, false]
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// A recurring job is screened again once the daemon's counterpart ephemerides change,
+/// and each run's flags come back certified.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_recurring_screening() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_recur_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let counterpart = |x: Vec<u32>| SatelliteData {
+        x,
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&counterpart(vec![900, 101, 902]))?,
+    )?;
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let owner = PartyBuilder::new(counterpart(vec![100, 101, 102]))
+        .owner()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let encrypted = owner.encrypt_trajectory().map_err(|e| e.to_string())?;
+    let interval = Duration::from_millis(200);
+
+    let mut client = Client::connect(addr).await?;
+    let mut job = client.open_session(&SessionMetadata::default()).await?;
+    client
+        .upload_server_key(
+            &mut job,
+            owner.server_key_bytes().map_err(|e| e.to_string())?,
+        )
+        .await?;
+    client.upload_trajectory(&mut job, &encrypted).await?;
+    client.recur(&job, Duration::from_millis(100)).await?;
+
+    let first = client.next_run(&mut job, &encrypted, interval).await?;
+    assert_eq!(owner.decrypt_results(&first), vec![false, true, false]);
+
+    // New ephemerides of the counterpart arrive.
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&counterpart(vec![100, 901, 102]))?,
+    )?;
+    let second = client.next_run(&mut job, &encrypted, interval).await?;
+    assert_eq!(owner.decrypt_results(&second), vec![true, false, true]);
+
+    assert_eq!(client.cancel(&job, interval).await?, JobStatus::Done);
+    assert!(
+        client
+            .next_run(&mut job, &encrypted, interval)
+            .await
+            .is_err()
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// Forwards connections to `daemon`, letting `fault` tamper with client frames: it gets
+// the decoded request and the raw frame, and returns false to drop the connection instead
+// of forwarding it.
+async fn flaky_proxy(
+    daemon: SocketAddr,
+    fault: impl Fn(&Request, &mut Vec<u8>) -> bool + Send + Sync + 'static,
+) -> Result<SocketAddr, ServiceError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let fault = Arc::new(fault);
+    tokio::spawn(async move {
+        while let Ok((client, _)) = listener.accept().await {
+            let fault = fault.clone();
+            tokio::spawn(async move {
+                let server = TcpStream::connect(daemon).await?;
+                let (mut client_read, mut client_write) = client.into_split();
+                let (mut server_read, mut server_write) = server.into_split();
+                tokio::spawn(
+                    async move { tokio::io::copy(&mut server_read, &mut client_write).await },
+                );
+                loop {
+                    let len = client_read.read_u32().await?;
+                    let mut frame = vec![0u8; len as usize];
+                    client_read.read_exact(&mut frame).await?;
+                    let request: Request = bincode::deserialize(&frame)
+                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                    if !fault(&request, &mut frame) {
+                        return Ok::<_, std::io::Error>(());
+                    }
+                    server_write.write_u32(len).await?;
+                    server_write.write_all(&frame).await?;
+                }
+            });
+        }
+    });
+    Ok(addr)
+}
+
+/// Chunked uploads survive a corrupted piece and a dropped connection: the daemon
+/// rejects the corrupted piece, the client reconnects, re-sends what is missing and the
+/// daemon stores exactly the uploaded bytes.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_chunked_upload_over_flaky_link() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_chunk_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&SatelliteData {
+            x: vec![1],
+            y: vec![2],
+            z: vec![3],
+            frame: Frame::Eci,
+            units: Units::Meters,
+        })?,
+    )?;
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let daemon_addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    // Flip a byte of the first piece's data, then drop the connection carrying the third.
+    let chunks_seen = Arc::new(AtomicUsize::new(0));
+    let seen = chunks_seen.clone();
+    let proxy = flaky_proxy(daemon_addr, move |request, frame| {
+        if !matches!(request, Request::UploadChunk { .. }) {
+            return true;
+        }
+        match seen.fetch_add(1, Ordering::SeqCst) {
+            0 => {
+                *frame.last_mut().unwrap() ^= 0xff;
+                true
+            }
+            2 => false,
+            _ => true,
+        }
+    })
+    .await?;
+
+    let mut client = Client::connect(proxy).await?.with_chunk_bytes(100);
+    let mut job = client.open_session(&SessionMetadata::default()).await?;
+    // The daemon only parses the key when the job runs, which it won't without a
+    // trajectory.
+    let server_key: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+    client
+        .upload_server_key(&mut job, server_key.clone())
+        .await?;
+
+    assert_eq!(client.status(&job).await?, JobStatus::AwaitingUploads);
+    // 1000 bytes of key plus the envelope header: 11 pieces, two of them sent twice.
+    assert_eq!(chunks_seen.load(Ordering::SeqCst), 13);
+    let stored = std::fs::read(dir.join(format!("jobs/job-{}/server_key.bin", job.id)))?;
+    assert_eq!(stored, server_key);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// The client and daemon talk over an in-process transport without opening a socket.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_in_process_transport() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_test_net_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![400],
+        y: vec![500],
+        z: vec![600],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+    let (transport, listener) = in_process();
+    let daemon = Daemon::with_listener(
+        ServeConfig {
+            listen: String::new(),
+            storage_dir: dir.join("jobs"),
+            max_jobs: 1,
+            queue_depth: 16,
+            preset: ParameterPreset::Default,
+            trajectory: dir.join("trajectory.bin"),
+            object_store: None,
+            quotas: QuotaConfig::default(),
+            tls: None,
+            blob_store: Default::default(),
+            dedup_blobs: false,
+            health_listen: None,
+            prewarm_keys: Vec::new(),
+            drain_timeout_s: 30,
+        },
+        listener,
+    )?;
+    tokio::spawn(daemon.run());
+
+    let mut client = Client::over(transport.clone()).await?;
+    assert_eq!(client.info().await?.preset, ParameterPreset::Default);
+    let job = client.open_session(&SessionMetadata::default()).await?;
+    // A second connection sees the job the first opened.
+    let mut other = Client::over(transport).await?;
+    assert_eq!(other.status(&job).await?, JobStatus::AwaitingUploads);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Shards are as even as possible and cover every step once, in order.
+#[test]
+fn test_shard_ranges() {
+    assert_eq!(shard_ranges(10, 3), [0..4, 4..7, 7..10]);
+    assert_eq!(shard_ranges(2, 4), [0..1, 1..2]);
+    assert_eq!(shard_ranges(5, 0), shard_ranges(5, 1));
+    assert!(shard_ranges(0, 3).is_empty());
+}
+
+/// A trajectory split across two daemons comes back as the flags of one screening.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sharded_screening() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("client_test_shard_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let evaluator_trajectory = SatelliteData {
+        x: vec![100, 999, 102, 999, 104],
+        y: vec![200, 201, 202, 203, 204],
+        z: vec![300, 301, 302, 303, 304],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&evaluator_trajectory)?,
+    )?;
+    let mut evaluators = Vec::new();
+    for node in 0..2 {
+        let (transport, listener) = in_process();
+        let daemon = Daemon::with_listener(
+            ServeConfig {
+                listen: String::new(),
+                storage_dir: dir.join(format!("jobs{}", node)),
+                max_jobs: 1,
+                queue_depth: 16,
+                preset: ParameterPreset::Default,
+                trajectory: dir.join("trajectory.bin"),
+                object_store: None,
+                quotas: QuotaConfig::default(),
+                tls: None,
+                blob_store: Default::default(),
+                dedup_blobs: false,
+                health_listen: None,
+                prewarm_keys: Vec::new(),
+                drain_timeout_s: 30,
+            },
+            listener,
+        )?;
+        tokio::spawn(daemon.run());
+        evaluators.push(Client::over(transport).await?);
+    }
+
+    let owner = PartyBuilder::new(SatelliteData {
+        x: vec![100, 101, 102, 103, 104],
+        y: vec![200, 201, 202, 203, 204],
+        z: vec![300, 301, 302, 303, 304],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    })
+    .config(ParameterPreset::Default.config())
+    .owner()
+    .build()
+    .map_err(|e| e.to_string())?;
+    let server_key = owner.server_key_bytes().map_err(|e| e.to_string())?;
+    let encrypted = owner.encrypt_trajectory().map_err(|e| e.to_string())?;
+
+    let results = Sharded::new(evaluators)
+        .with_poll_interval(Duration::from_millis(200))
+        .screen(&SessionMetadata::default(), server_key, &encrypted)
+        .await?;
+    assert_eq!(
+        owner.decrypt_results(&results),
+        vec![true, false, true, false, true]
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
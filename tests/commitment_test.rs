@@ -0,0 +1,85 @@
+use sat_trajectory_fhe::commitment::TrajectoryCommitment;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::protocol::{MessageKind, SessionMetadata};
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::Units;
+
+fn trajectory(x: u32) -> SatelliteData {
+    SatelliteData {
+        x: vec![x, x + 1],
+        y: vec![10, 11],
+        z: vec![20, 21],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// Both sides commit before exchanging trajectories; openings check out for what was
+/// actually sent and expose a swapped trajectory.
+#[test]
+fn test_commitment_phase() -> Result<(), Box<dyn std::error::Error>> {
+    let mut a = Session::open()?;
+    let (mut b, _) = Session::accept(&a.hello(&SessionMetadata::default())?)?;
+    let (a_ciphertexts, b_ciphertexts) = (b"a's ciphertexts".to_vec(), b"b's ciphertexts".to_vec());
+    let (a_commitment, a_opening) =
+        TrajectoryCommitment::commit(&a.nonce(), &a_ciphertexts, &trajectory(1))?;
+    let (b_commitment, b_opening) =
+        TrajectoryCommitment::commit(&b.nonce(), &b_ciphertexts, &trajectory(2))?;
+
+    assert_eq!(
+        b.accept_commitment(&a.commit(&a_commitment)?)?,
+        a_commitment
+    );
+    assert_eq!(
+        a.accept_commitment(&b.commit(&b_commitment)?)?,
+        b_commitment
+    );
+    b.receive(&a.send(MessageKind::EncryptedTrajectory, a_ciphertexts.clone())?)?;
+    a.receive(&b.send(MessageKind::EncryptedTrajectory, b_ciphertexts.clone())?)?;
+
+    // Committing now would come too late.
+    assert!(a.commit(&a_commitment).is_err());
+
+    let opening = b.accept_opening(&a.open_commitment(&a_opening)?, &a_ciphertexts)?;
+    assert!(
+        a_commitment
+            .verify_plaintext(&a.nonce(), &opening, &trajectory(1))
+            .is_ok()
+    );
+    assert!(
+        a_commitment
+            .verify_plaintext(&a.nonce(), &opening, &trajectory(3))
+            .is_err()
+    );
+    // B swapped its ciphertexts after committing.
+    assert!(
+        a.accept_opening(&b.open_commitment(&b_opening)?, b"other ciphertexts")
+            .is_err()
+    );
+    assert_eq!(a.transcript(), b.transcript());
+    Ok(())
+}
+
+/// A commitment accepted after this side already sent its trajectory binds nothing.
+#[test]
+fn test_late_commitment_is_refused() -> Result<(), Box<dyn std::error::Error>> {
+    let mut a = Session::open()?;
+    let (mut b, _) = Session::accept(&a.hello(&SessionMetadata::default())?)?;
+    b.receive(&a.send(
+        MessageKind::EncryptedTrajectory,
+        b"a's ciphertexts".to_vec(),
+    )?)?;
+    let (commitment, _) = TrajectoryCommitment::commit(&b.nonce(), b"b's", &trajectory(2))?;
+    assert!(b.commit(&commitment).is_err());
+
+    let mut c = Session::open()?;
+    let (mut d, _) = Session::accept(&c.hello(&SessionMetadata::default())?)?;
+    let message = d.commit(&commitment)?;
+    c.send(
+        MessageKind::EncryptedTrajectory,
+        b"c's ciphertexts".to_vec(),
+    )?;
+    assert!(c.accept_commitment(&message).is_err());
+    Ok(())
+}
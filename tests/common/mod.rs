@@ -0,0 +1,29 @@
+// Fixtures shared by the integration tests. Each test binary uses only some of them.
+#![allow(dead_code)]
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::units::Units;
+
+// Trajectory moving along x only, at a fixed y and z.
+pub fn trajectory(x: Vec<u32>) -> SatelliteData {
+    let len = x.len();
+    SatelliteData {
+        x,
+        y: vec![5000; len],
+        z: vec![7000; len],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+// Trajectory moving along the diagonal: y and z follow x at fixed offsets.
+pub fn diagonal_trajectory(x: Vec<u32>) -> SatelliteData {
+    SatelliteData {
+        y: x.iter().map(|v| v + 100).collect(),
+        z: x.iter().map(|v| v + 200).collect(),
+        x,
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
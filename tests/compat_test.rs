@@ -0,0 +1,98 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::compat::{
+    ArtifactMeta, Mismatch, ParameterDigest, TFHE_VERSION, check, check_parameters,
+};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::migrate::{ArtifactKind, meta_of, upgrade};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::protocol::Envelope;
+use sat_trajectory_fhe::screening::results_from_bytes;
+use sat_trajectory_fhe::units::Units;
+
+// A version 3 envelope claiming to be written by TFHE-rs `tfhe_version`, with a payload
+// this build can't parse.
+fn envelope_from(tfhe_version: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut data = b"SFE\x03".to_vec();
+    data.extend(bincode::serialize(&(
+        Some(tfhe_version),
+        None::<ParameterDigest>,
+    ))?);
+    data.extend_from_slice(b"\x01\x02");
+    Ok(data)
+}
+
+/// Keys and trajectories record this build's TFHE-rs version and the same parameters.
+#[test]
+fn test_artifacts_record_version_and_parameters() -> Result<(), Box<dyn std::error::Error>> {
+    let data = SatelliteData {
+        x: vec![1, 2],
+        y: vec![3, 4],
+        z: vec![5, 6],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let owner = PartyBuilder::new(data).owner().build()?;
+    let key = meta_of(ArtifactKind::ServerKey, &owner.server_key_bytes()?)?;
+    let trajectory = meta_of(
+        ArtifactKind::Trajectory,
+        &owner.encrypt_trajectory()?.to_bytes()?,
+    )?;
+    assert_eq!(
+        key.format_version,
+        ArtifactKind::ServerKey.current_version()
+    );
+    assert_eq!(key.tfhe_version.as_deref(), Some(TFHE_VERSION));
+    assert!(key.parameters.is_some());
+    assert_eq!(trajectory.parameters, key.parameters);
+    assert!(check_parameters(&trajectory, key.parameters.unwrap()).is_ok());
+    Ok(())
+}
+
+/// Each mismatching component is named, and a payload that fails to parse says why.
+#[test]
+fn test_check_names_mismatches() -> Result<(), Box<dyn std::error::Error>> {
+    let meta = ArtifactMeta {
+        kind: ArtifactKind::Trajectory,
+        format_version: 3,
+        tfhe_version: Some("99.0.0".to_string()),
+        parameters: Some(ParameterDigest([1; 8])),
+    };
+    let err = check_parameters(&meta, ParameterDigest([2; 8])).unwrap_err();
+    assert_eq!(
+        err.mismatches,
+        [
+            Mismatch::TfheVersion {
+                found: "99.0.0".to_string(),
+                current: TFHE_VERSION.to_string(),
+            },
+            Mismatch::Parameters {
+                found: ParameterDigest([1; 8]),
+                expected: ParameterDigest([2; 8]),
+            },
+        ]
+    );
+    // Older releases are readable.
+    let older = ArtifactMeta {
+        tfhe_version: Some("0.1.0".to_string()),
+        ..meta
+    };
+    assert!(check(&older).is_ok());
+
+    let err = Envelope::from_bytes(&envelope_from("99.0.0")?).unwrap_err();
+    assert!(err.to_string().contains("TFHE-rs 99.0.0"), "{}", err);
+    let err = Envelope::from_bytes(&envelope_from(TFHE_VERSION)?).unwrap_err();
+    assert!(!err.to_string().contains("TFHE-rs"), "{}", err);
+    Ok(())
+}
+
+/// Upgrading a version 2 artifact adds a header that leaves its origin unknown.
+#[test]
+fn test_upgrade_leaves_origin_unknown() -> Result<(), Box<dyn std::error::Error>> {
+    let v2 = b"SFR\x02\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+    let upgraded = upgrade(ArtifactKind::Results, &v2)?.expect("upgraded");
+    let meta = meta_of(ArtifactKind::Results, &upgraded)?;
+    assert_eq!(meta.format_version, 3);
+    assert_eq!(meta.tfhe_version, None);
+    assert!(results_from_bytes(&upgraded)?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::padding::EVALUATOR_SENTINEL;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::units::Units;
+
+fn trajectory(x: Vec<u32>, y: Vec<u32>, z: Vec<u32>) -> SatelliteData {
+    SatelliteData {
+        x,
+        y,
+        z,
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// Constant-shape evaluation flags the same steps as the scalar path, also for clear
+/// values at the edges of the range.
+#[tokio::test]
+async fn test_constant_shape_matches_scalar() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(trajectory(vec![0, 3, 500], vec![0, 0, 7], vec![0, 1, 9]))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let plaintext = trajectory(vec![0, 0, u32::MAX], vec![0, 0, 7], vec![0, 1, 9]);
+
+    for kernel in [
+        KernelChoice::ExactMatch,
+        KernelChoice::BoxThreshold { half_width: 5 },
+    ] {
+        let mut flags = Vec::new();
+        for constant_shape in [true, false] {
+            let evaluator = PartyBuilder::new(plaintext.clone())
+                .screening(ScreeningConfig {
+                    kernel,
+                    constant_shape,
+                    ..Default::default()
+                })
+                .evaluator(owner.server_key_bytes()?)
+                .build()?;
+            let output = evaluator.evaluate(&encrypted)?;
+            flags.push(owner.decrypt_results(&output.results));
+        }
+        assert_eq!(flags[0], flags[1], "{:?}", kernel);
+    }
+    Ok(())
+}
+
+/// How long the evaluator takes doesn't depend on whether its trajectory collides with
+/// the owner's, nor on it being padding at `EVALUATOR_SENTINEL`.
+#[tokio::test]
+async fn test_time_independent_of_plaintext() -> Result<(), Box<dyn std::error::Error>> {
+    let steps = 4;
+    let owner_data = trajectory(
+        (1..=steps).map(|i| i * 1000).collect(),
+        (1..=steps).map(|i| i * 2000).collect(),
+        (1..=steps).map(|i| i * 3000).collect(),
+    );
+    let owner = PartyBuilder::new(owner_data.clone()).owner().build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+
+    let sentinel = vec![EVALUATOR_SENTINEL; steps as usize];
+    let plaintexts = [
+        // Collides at every step.
+        owner_data.clone(),
+        // Collides nowhere.
+        trajectory(
+            owner_data.x.iter().map(|v| v + 1).collect(),
+            owner_data.y.clone(),
+            owner_data.z.clone(),
+        ),
+        // Padding only.
+        trajectory(sentinel.clone(), sentinel.clone(), sentinel),
+    ];
+
+    let mut times = Vec::new();
+    for plaintext in plaintexts {
+        let evaluator = PartyBuilder::new(plaintext)
+            .evaluator(owner.server_key_bytes()?)
+            .build()?;
+        // Best of a few runs, to keep scheduling noise out of the comparison.
+        let mut best = Duration::MAX;
+        for _ in 0..3 {
+            let start = Instant::now();
+            evaluator.evaluate(&encrypted)?;
+            best = best.min(start.elapsed());
+        }
+        times.push(best);
+    }
+
+    let fastest = times.iter().min().unwrap().as_secs_f64();
+    let slowest = times.iter().max().unwrap().as_secs_f64();
+    assert!(
+        slowest / fastest < 1.5,
+        "evaluation times differ by plaintext: {:?}",
+        times
+    );
+    Ok(())
+}
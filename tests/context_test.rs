@@ -0,0 +1,119 @@
+use std::panic::AssertUnwindSafe;
+
+use tfhe::ConfigBuilder;
+use tfhe::prelude::*;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::screening::{
+    ScreeningConfig, results_from_bytes, results_to_bytes, screen_exact,
+};
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+/// A context built from a server key alone can evaluate but not encrypt or decrypt.
+#[tokio::test]
+async fn test_evaluator_context_has_no_client_key() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = FheContext::generate(ConfigBuilder::default().build())?;
+    let evaluator = FheContext::from_server_key((**owner.server_key()).clone())?;
+    assert_eq!(
+        evaluator.server_key().fingerprint(),
+        owner.server_key().fingerprint()
+    );
+
+    let sat = SatelliteData {
+        x: vec![1],
+        y: vec![2],
+        z: vec![3],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let encrypted = owner.encrypt(&sat)?;
+    assert!(evaluator.encrypt(&sat).is_err());
+    assert!(evaluator.decrypt::<_, u32>(&encrypted.x).is_err());
+    assert_eq!(owner.decrypt::<_, u32>(&encrypted.x)?, vec![1]);
+
+    // Debug output goes through the redacted key wrappers.
+    println!("{:?}", evaluator);
+
+    Ok(())
+}
+
+/// Two evaluators with different keys screening at the same time on different threads,
+/// with parallel axes, each get results their own owner can decrypt.
+#[tokio::test]
+async fn test_concurrent_contexts_are_isolated() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ScreeningConfig {
+        parallel_axes: true,
+        ..Default::default()
+    };
+    let track = |x: Vec<u32>| SatelliteData {
+        y: x.clone(),
+        z: x.clone(),
+        x,
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let plaintext = track(vec![10, 20, 30, 40]);
+
+    let owner_a = FheContext::generate(ConfigBuilder::default().build())?;
+    let owner_b = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted_a = owner_a.encrypt(&track(vec![10, 0, 30, 0]))?;
+    let encrypted_b = owner_b.encrypt(&track(vec![0, 20, 0, 40]))?;
+    let evaluator_a = FheContext::from_server_key((**owner_a.server_key()).clone())?;
+    let evaluator_b = FheContext::from_server_key((**owner_b.server_key()).clone())?;
+
+    let screen = |evaluator: &FheContext, encrypted: &EncryptedTrajectory| {
+        evaluator
+            .evaluate_with(|| screen_exact(encrypted, &plaintext, &config))
+            .and_then(|output| results_to_bytes(&output.results))
+            .map_err(|e| e.to_string())
+    };
+    let (bytes_a, bytes_b) = std::thread::scope(|s| {
+        let a = s.spawn(|| screen(&evaluator_a, &encrypted_a));
+        let b = s.spawn(|| screen(&evaluator_b, &encrypted_b));
+        (a.join().unwrap(), b.join().unwrap())
+    });
+
+    let flags_a: Vec<bool> = owner_a.decrypt(&results_from_bytes(&bytes_a?)?)?;
+    let flags_b: Vec<bool> = owner_b.decrypt(&results_from_bytes(&bytes_b?)?)?;
+    assert_eq!(flags_a, vec![true, false, true, false]);
+    assert_eq!(flags_b, vec![false, true, false, true]);
+
+    Ok(())
+}
+
+/// An evaluation inside another puts the outer context's key back when it ends, and one
+/// that panics leaves no key installed.
+#[test]
+fn test_evaluation_restores_previous_key() -> Result<(), Box<dyn std::error::Error>> {
+    let outer = FheContext::generate(ConfigBuilder::default().build())?;
+    let inner = FheContext::generate(ConfigBuilder::default().build())?;
+    let sat = SatelliteData {
+        x: (1..=8).collect(),
+        y: vec![0; 8],
+        z: vec![0; 8],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let (a, b) = (outer.encrypt(&sat)?, inner.encrypt(&sat)?);
+    let expected: Vec<bool> = sat.x.iter().map(|&x| x == 3).collect();
+
+    let (outer_flags, inner_flags) = outer.evaluate_with(|| {
+        let inner_flags =
+            inner.evaluate_with(|| Ok::<_, Box<dyn std::error::Error>>(b.x[2].eq(3u32)))?;
+        let outer_flags: Vec<_> = a.x.iter().map(|x| x.eq(3u32)).collect();
+        Ok::<_, Box<dyn std::error::Error>>((outer_flags, inner_flags))
+    })?;
+    assert_eq!(outer.decrypt::<_, bool>(&outer_flags)?, expected);
+    assert_eq!(inner.decrypt::<_, bool>(&[inner_flags])?, vec![true]);
+
+    let panicked = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        outer.evaluate_with(|| -> Result<(), Box<dyn std::error::Error>> { panic!("kernel bug") })
+    }));
+    assert!(panicked.is_err());
+    let leaked = std::panic::catch_unwind(AssertUnwindSafe(|| a.x[0].eq(1u32)));
+    assert!(leaked.is_err());
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use sat_trajectory_fhe::core::encoding::EncodingParams;
+use sat_trajectory_fhe::core::frame::{Frame, convert};
+use sat_trajectory_fhe::core::{SatelliteData, grid, units};
+use sat_trajectory_fhe::negotiation::negotiate;
+
+/// Data prepared with the std-free encoding is what the ground segment checks against
+/// the negotiated parameters.
+#[test]
+fn test_core_encoding_matches_ground() -> Result<(), Box<dyn std::error::Error>> {
+    let params = EncodingParams {
+        units: units::Units::Meters,
+        cell_size: 100,
+        time_step_s: 60,
+        window_start: 1_700_000_000,
+        window_end: 1_700_000_180,
+    };
+    let epochs = params.epochs();
+    let positions: Vec<[f64; 3]> = epochs
+        .iter()
+        .map(|&t| {
+            convert(
+                [6_771_000.0, 12_345.6, -42.4],
+                t as f64,
+                Frame::Ecef,
+                Frame::Eci,
+            )
+        })
+        .collect();
+    let onboard = params.quantize(&SatelliteData::encode(
+        &positions,
+        units::Units::Meters,
+        Frame::Eci,
+    )?);
+
+    params.check(&onboard, &epochs)?;
+    assert_eq!(negotiate(&params, &params)?, params);
+    let back = convert(
+        [
+            units::decode(onboard.x[0]),
+            units::decode(onboard.y[0]),
+            units::decode(onboard.z[0]),
+        ],
+        epochs[0] as f64,
+        Frame::Eci,
+        Frame::Ecef,
+    );
+    assert!((back[0] - 6_771_000.0).abs() < 100.0);
+    assert!((back[2] + 42.4).abs() < 100.0);
+    assert_eq!(grid::voxel(onboard.z[0], 100), Some(1 << 15));
+    Ok(())
+}
+
+/// Negative coordinates survive the bias, keep their order and quantize symmetrically.
+#[test]
+fn test_bias_handles_negative_coordinates() -> Result<(), Box<dyn std::error::Error>> {
+    for steps in [i32::MIN as i64, -6_771_000, -1, 0, 1, i32::MAX as i64] {
+        assert_eq!(units::bias(steps).map(units::unbias), Some(steps));
+    }
+    assert_eq!(units::bias(-(1 << 31) - 1), None);
+    assert_eq!(units::bias(1 << 31), None);
+
+    let data = SatelliteData::encode(
+        &[[-6_771_049.0, -1.0, 6_771_049.0]],
+        units::Units::Meters,
+        Frame::Eci,
+    )?;
+    assert!(data.x[0] < data.y[0] && data.y[0] < data.z[0]);
+    // The bias cancels in differences, so the distance math sees true separations.
+    assert_eq!(data.z[0] - data.x[0], 2 * 6_771_049);
+
+    let params = EncodingParams {
+        units: units::Units::Meters,
+        cell_size: 100,
+        time_step_s: 60,
+        window_start: 0,
+        window_end: 60,
+    };
+    let snapped = params.quantize(&data);
+    assert_eq!(units::unbias(snapped.x[0]), -6_771_000);
+    assert_eq!(units::unbias(snapped.z[0]), 6_771_000);
+    Ok(())
+}
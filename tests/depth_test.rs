@@ -0,0 +1,53 @@
+use sat_trajectory_fhe::depth::{DepthExceeded, DepthLimit, DepthPolicy, OpCounter};
+use sat_trajectory_fhe::screening::{ScreeningConfig, exact_match_cost};
+
+/// Per-step costs add up across steps while depth stays the per-step maximum.
+#[test]
+fn test_op_counter_accumulates() {
+    let mut total = OpCounter::default();
+    total.add_steps(&exact_match_cost(), 10);
+    assert_eq!(total.comparisons, 30);
+    assert_eq!(total.boolean, 20);
+    assert_eq!(total.total(), 50);
+    assert_eq!(total.depth, 3);
+}
+
+/// The limit is enforced before evaluation according to the configured policy; a
+/// warning is recorded on the cost and carried into the totals, not printed.
+#[test]
+fn test_depth_limit_policy() {
+    let mut step = exact_match_cost();
+
+    let strict = ScreeningConfig {
+        depth_limit: Some(DepthLimit {
+            max_depth: 2,
+            policy: DepthPolicy::Error,
+        }),
+        ..Default::default()
+    };
+    let err = strict.check_depth(&mut step).unwrap_err();
+    assert_eq!(err.depth, 3);
+    assert_eq!(err.max_depth, 2);
+
+    let lenient = ScreeningConfig {
+        depth_limit: Some(DepthLimit {
+            max_depth: 2,
+            policy: DepthPolicy::Warn,
+        }),
+        ..Default::default()
+    };
+    assert!(ScreeningConfig::default().check_depth(&mut step).is_ok());
+    assert_eq!(step.depth_warning, None);
+
+    assert!(lenient.check_depth(&mut step).is_ok());
+    assert_eq!(
+        step.depth_warning,
+        Some(DepthExceeded {
+            depth: 3,
+            max_depth: 2
+        })
+    );
+    let mut total = OpCounter::default();
+    total.add_steps(&step, 10);
+    assert_eq!(total.depth_warning, step.depth_warning);
+}
@@ -0,0 +1,144 @@
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::distance::{
+    DISTANCE_CAP, Tier, TieredThreshold, screen_thresholds, screen_tiered, squared_distances,
+    threshold_cost,
+};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::units::Units;
+
+/// Two thresholds evaluated on the same encrypted distances: 5 units apart at step 0,
+/// 1 unit apart at step 1.
+#[tokio::test]
+async fn test_multi_threshold_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101],
+        y: vec![200, 201],
+        z: vec![300, 301],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![103, 101],
+        y: vec![204, 201],
+        z: vec![300, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?;
+
+    let output = context.evaluate_with(|| {
+        screen_thresholds(&encrypted, &sat2, &[5, 1], &ScreeningConfig::default())
+    })?;
+    let flags = output
+        .results
+        .iter()
+        .map(|per_step| context.decrypt(per_step))
+        .collect::<Result<Vec<Vec<bool>>, _>>()?;
+    assert_eq!(flags, vec![vec![true, true], vec![false, true]]);
+
+    // The distance is paid once per step, each threshold adds a single comparison.
+    assert_eq!(output.ops.comparisons, 2 * threshold_cost(2).comparisons);
+    assert_eq!(
+        threshold_cost(2).comparisons,
+        threshold_cost(1).comparisons + 1
+    );
+
+    // Thresholds beyond the clamp can't be answered correctly.
+    let too_far = context.evaluate_with(|| {
+        screen_thresholds(
+            &encrypted,
+            &sat2,
+            &[DISTANCE_CAP],
+            &ScreeningConfig::default(),
+        )
+    });
+    assert!(too_far.is_err());
+
+    Ok(())
+}
+
+/// The squared distances themselves decrypt to the same values whether the plaintext
+/// enters as scalars or, by default, as trivial ciphertexts.
+#[tokio::test]
+async fn test_squared_distances() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101],
+        y: vec![200, 201],
+        z: vec![300, 301],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![103, 101],
+        y: vec![204, 201],
+        z: vec![300, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?;
+    for config in [
+        ScreeningConfig::default(),
+        ScreeningConfig {
+            constant_shape: false,
+            ..Default::default()
+        },
+    ] {
+        let distances = context.evaluate_with(|| squared_distances(&encrypted, &sat2, &config))?;
+        let distances: Vec<u64> = context.decrypt(&distances)?;
+        assert_eq!(distances, vec![25, 1]);
+    }
+    Ok(())
+}
+
+/// Alert and warning flags from one distance per step: 1 unit apart at step 0, 5 at step 1
+/// and 9 at step 2, against a 2-unit alert and a 6-unit warning radius.
+#[tokio::test]
+async fn test_tiered_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 100, 100],
+        y: vec![200, 200, 200],
+        z: vec![300, 300, 300],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![101, 103, 109],
+        y: vec![200, 204, 200],
+        z: vec![300, 300, 300],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?;
+    let tiers = TieredThreshold::new(2, 6)?;
+
+    let output = context
+        .evaluate_with(|| screen_tiered(&encrypted, &sat2, tiers, &ScreeningConfig::default()))?;
+    let alert = context.decrypt(&output.alert)?;
+    let warning = context.decrypt(&output.warning)?;
+    assert_eq!(alert, vec![true, false, false]);
+    assert_eq!(warning, vec![true, true, false]);
+    let classified = Tier::classify(&alert, &warning);
+    assert_eq!(classified, vec![Tier::Alert, Tier::Warning, Tier::Clear]);
+    for (i, tier) in classified.into_iter().enumerate() {
+        let a = [sat1.x[i], sat1.y[i], sat1.z[i]];
+        let b = [sat2.x[i], sat2.y[i], sat2.z[i]];
+        assert_eq!(tiers.tier_clear(a, b), tier);
+    }
+
+    // Both tiers share the distance: the same work as two thresholds, not two screenings.
+    assert_eq!(output.ops.comparisons, 3 * threshold_cost(2).comparisons);
+
+    assert!(TieredThreshold::new(6, 2).is_err());
+    assert!(TieredThreshold::new(2, DISTANCE_CAP).is_err());
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::depth::{DepthLimit, DepthPolicy};
+use sat_trajectory_fhe::dry_run::DryRunInput;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::protocol::{ProtocolError, SessionMetadata};
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::time_system::{TimeSystem, align_epochs};
+use sat_trajectory_fhe::units::Units;
+
+fn plaintext() -> SatelliteData {
+    SatelliteData {
+        x: vec![100, 101, 102, 103],
+        y: vec![200, 201, 202, 203],
+        z: vec![300, 301, 302, 303],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// A consistent setup passes and reports the planned work.
+#[test]
+fn test_dry_run_ok() -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::open()?;
+    let metadata = SessionMetadata {
+        server_key_fingerprint: Some("00112233".to_string()),
+        ..Default::default()
+    };
+    let plaintext = plaintext();
+    let report = session.dry_run(&DryRunInput {
+        metadata: &metadata,
+        server_key_fingerprint: "00112233",
+        first_index: 1,
+        epochs: &[60, 120],
+        frame: Frame::Eci,
+        units: Units::Meters,
+        plaintext: &plaintext,
+        plaintext_epochs: &[0, 60, 120, 180],
+        plaintext_time_system: TimeSystem::Utc,
+        config: &ScreeningConfig::default(),
+    });
+
+    println!("{}", report);
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.steps, 2);
+    assert_eq!(report.planned_ops.comparisons, 6);
+    Ok(())
+}
+
+/// Every configuration problem is collected in the report.
+#[test]
+fn test_dry_run_reports_issues() -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::open()?;
+    let metadata = SessionMetadata {
+        server_key_fingerprint: Some("00112233".to_string()),
+        ..Default::default()
+    };
+    let config = ScreeningConfig {
+        depth_limit: Some(DepthLimit {
+            max_depth: 1,
+            policy: DepthPolicy::Error,
+        }),
+        ..Default::default()
+    };
+    let plaintext = plaintext();
+
+    // Wrong key, misaligned epochs, too strict a depth limit.
+    let report = session.dry_run(&DryRunInput {
+        metadata: &metadata,
+        server_key_fingerprint: "deadbeef",
+        first_index: 0,
+        epochs: &[0, 61],
+        frame: Frame::Eci,
+        units: Units::Meters,
+        plaintext: &plaintext,
+        plaintext_epochs: &[0, 60, 120, 180],
+        plaintext_time_system: TimeSystem::Utc,
+        config: &config,
+    });
+    println!("{}", report);
+    assert_eq!(report.issues.len(), 3);
+
+    // Encrypted trajectory longer than the plaintext.
+    let report = session.dry_run(&DryRunInput {
+        metadata: &metadata,
+        server_key_fingerprint: "00112233",
+        first_index: 3,
+        epochs: &[180, 240],
+        frame: Frame::Eci,
+        units: Units::Meters,
+        plaintext: &plaintext,
+        plaintext_epochs: &[0, 60, 120, 180],
+        plaintext_time_system: TimeSystem::Utc,
+        config: &ScreeningConfig::default(),
+    });
+    assert_eq!(report.issues.len(), 1);
+    Ok(())
+}
+
+/// The planned work follows the configured kernel, which must be the one the owner asked for.
+#[test]
+fn test_dry_run_kernel() -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::open()?;
+    let metadata = SessionMetadata {
+        server_key_fingerprint: Some("00112233".to_string()),
+        kernel: Some(KernelChoice::BoxThreshold { half_width: 2 }),
+        ..Default::default()
+    };
+    let plaintext = plaintext();
+    let matching = ScreeningConfig {
+        kernel: KernelChoice::BoxThreshold { half_width: 2 },
+        ..Default::default()
+    };
+    let exact = ScreeningConfig::default();
+    let input = |config| DryRunInput {
+        metadata: &metadata,
+        server_key_fingerprint: "00112233",
+        first_index: 0,
+        epochs: &[0, 60],
+        frame: Frame::Eci,
+        units: Units::Meters,
+        plaintext: &plaintext,
+        plaintext_epochs: &[0, 60, 120, 180],
+        plaintext_time_system: TimeSystem::Utc,
+        config,
+    };
+
+    let report = session.dry_run(&input(&matching));
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.planned_ops.comparisons, 12);
+
+    let report = session.dry_run(&input(&exact));
+    assert_eq!(report.issues.len(), 1);
+    Ok(())
+}
+
+/// Epochs in another time system than the owner declared are reported, not compared with
+/// a silent offset; converted ones align.
+#[test]
+fn test_dry_run_time_system() -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::open()?;
+    let metadata = SessionMetadata {
+        server_key_fingerprint: Some("00112233".to_string()),
+        time_system: Some(TimeSystem::Gps),
+        ..Default::default()
+    };
+    let plaintext = plaintext();
+    // 2024-03-01 12:00 UTC, when GPS time is 18 s ahead.
+    let utc = [1_709_294_400, 1_709_294_460, 1_709_294_520, 1_709_294_580];
+    let gps = align_epochs(&utc, TimeSystem::Utc, TimeSystem::Gps);
+    let config = ScreeningConfig::default();
+    let input = |plaintext_epochs, plaintext_time_system| DryRunInput {
+        metadata: &metadata,
+        server_key_fingerprint: "00112233",
+        first_index: 0,
+        epochs: &gps[..2],
+        frame: Frame::Eci,
+        units: Units::Meters,
+        plaintext: &plaintext,
+        plaintext_epochs,
+        plaintext_time_system,
+        config: &config,
+    };
+
+    let report = session.dry_run(&input(&utc[..], TimeSystem::Utc));
+    println!("{}", report);
+    assert_eq!(report.issues.len(), 1);
+    assert!(report.issues[0].contains("time system"), "{}", report);
+
+    let report = session.dry_run(&input(&gps[..], TimeSystem::Gps));
+    assert!(report.is_ok(), "{}", report);
+    Ok(())
+}
+
+/// An encrypted trajectory in another frame, or in units the plaintext can't be rescaled
+/// to, is reported with the error the screening itself would fail with.
+#[test]
+fn test_dry_run_frame_and_units() -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::open()?;
+    let metadata = SessionMetadata {
+        server_key_fingerprint: Some("00112233".to_string()),
+        ..Default::default()
+    };
+    let config = ScreeningConfig::default();
+    let input = |plaintext, frame, units| DryRunInput {
+        metadata: &metadata,
+        server_key_fingerprint: "00112233",
+        first_index: 0,
+        epochs: &[0, 60],
+        frame,
+        units,
+        plaintext,
+        plaintext_epochs: &[0, 60, 120, 180],
+        plaintext_time_system: TimeSystem::Utc,
+        config: &config,
+    };
+
+    let plaintext = plaintext();
+    let report = session.dry_run(&input(&plaintext, Frame::Ecef, Units::Meters));
+    assert_eq!(
+        report.issues,
+        vec![
+            ProtocolError::FrameMismatch {
+                owner: Frame::Ecef,
+                evaluator: Frame::Eci,
+            }
+            .to_string()
+        ]
+    );
+
+    // Far from the origin, kilometres don't fit the fixed-point range in metres.
+    let kilometers = SatelliteData {
+        units: Units::Kilometers,
+        ..plaintext.clone()
+    };
+    let report = session.dry_run(&input(&kilometers, Frame::Eci, Units::Meters));
+    assert_eq!(report.issues.len(), 1);
+    assert!(report.issues[0].contains("fixed-point range"), "{}", report);
+
+    let report = session.dry_run(&input(&plaintext, Frame::Eci, Units::Meters));
+    assert!(report.is_ok(), "{}", report);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "mmap")]
+
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::eft::{EftReader, screen_exact_eft, write_eft};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::units::Units;
+
+/// A trajectory written to an `.eft` file is read back step by step through the mapping
+/// and screens like the in-memory one.
+#[tokio::test]
+async fn test_eft_roundtrip_and_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![400, 101, 402],
+        y: vec![500, 201, 502],
+        z: vec![600, 301, 602],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?.with_epochs(vec![0, 60, 120])?;
+
+    let path = std::env::temp_dir().join(format!("eft_test_{}.eft", std::process::id()));
+    write_eft(&path, &encrypted)?;
+    let reader = EftReader::open(&path)?;
+    assert_eq!(reader.len(), 3);
+    assert_eq!(reader.epochs(), &[0, 60, 120]);
+    assert_eq!(
+        context.decrypt::<_, u32>(&reader.step(2)?)?,
+        vec![102, 202, 302]
+    );
+    assert!(reader.step(3).is_err());
+
+    let output =
+        context.evaluate_with(|| screen_exact_eft(&reader, &sat2, &ScreeningConfig::default()))?;
+    let flags: Vec<bool> = context.decrypt(&output.results)?;
+    assert_eq!(flags, vec![false, true, false]);
+
+    drop(reader);
+    std::fs::write(&path, b"not an eft file")?;
+    assert!(EftReader::open(&path).is_err());
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
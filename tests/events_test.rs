@@ -0,0 +1,37 @@
+use sat_trajectory_fhe::events::{ConjunctionEvent, cluster};
+
+/// Adjacent positive steps merge into one event; isolated ones stay separate.
+#[test]
+fn test_cluster_events() -> Result<(), Box<dyn std::error::Error>> {
+    let flags = [true, true, false, false, true, false, true, true, true];
+    let epochs: Vec<u64> = (0..flags.len() as u64).map(|i| 1_000 + 60 * i).collect();
+
+    let events = cluster(&flags, &epochs, 10)?;
+    assert_eq!(
+        events,
+        vec![
+            ConjunctionEvent {
+                start_index: 10,
+                start_epoch: 1_000,
+                end_epoch: 1_060,
+                n_steps: 2,
+            },
+            ConjunctionEvent {
+                start_index: 14,
+                start_epoch: 1_240,
+                end_epoch: 1_240,
+                n_steps: 1,
+            },
+            ConjunctionEvent {
+                start_index: 16,
+                start_epoch: 1_360,
+                end_epoch: 1_480,
+                n_steps: 3,
+            },
+        ]
+    );
+
+    assert!(cluster(&[false, false], &[0, 1], 0)?.is_empty());
+    assert!(cluster(&[true], &[0, 1], 0).is_err());
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::core::canonical::{CANONICAL, CanonicalEncoding};
+use sat_trajectory_fhe::export::{
+    from_csv, quantization_tolerance, round_trip_error, to_csv, to_oem,
+};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::units::Units;
+
+const POSITIONS: [[f64; 3]; 3] = [
+    [6_771_000.4, -12_345.6, 42.25],
+    [6_770_950.7, 447_891.2, -1_000.5],
+    [6_755_123.9, 906_543.1, -2_049.49],
+];
+
+/// Encoding, encrypting, decrypting and exporting to CSV keeps every component within
+/// half a step of the units it was encoded in.
+#[tokio::test]
+async fn test_csv_round_trip_within_quantization() -> Result<(), Box<dyn std::error::Error>> {
+    let epochs = [1_709_294_400, 1_709_294_460, 1_709_294_520];
+    for units in [Units::Meters, Units::Kilometers] {
+        let data =
+            CanonicalEncoding { units, ..CANONICAL }.encode_trajectory(&POSITIONS, Frame::Eci)?;
+        let owner = PartyBuilder::new(data.clone()).owner().build()?;
+        let decrypted = owner.decrypt_trajectory(&owner.encrypt_trajectory()?)?;
+        assert_eq!(
+            (&decrypted.x, &decrypted.y, &decrypted.z),
+            (&data.x, &data.y, &data.z)
+        );
+        assert!(round_trip_error(&POSITIONS, &decrypted)? <= quantization_tolerance(units));
+
+        let (read_epochs, read_positions) = from_csv(&to_csv(&decrypted, &epochs)?)?;
+        assert_eq!(read_epochs, epochs);
+        for (read, original) in read_positions.iter().zip(&POSITIONS) {
+            for axis in 0..3 {
+                assert!((read[axis] - original[axis]).abs() <= quantization_tolerance(units));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The OEM carries the frame, UTC epochs and a state vector per step in km and km/s.
+#[test]
+fn test_oem_export() -> Result<(), Box<dyn std::error::Error>> {
+    let data = SatelliteData::encode(&POSITIONS, Units::Meters, Frame::Eci)?;
+    let oem = to_oem(
+        &data,
+        &[1_709_294_400, 1_709_294_460, 1_709_294_520],
+        "SAT-A",
+    )?;
+    assert!(oem.starts_with("CCSDS_OEM_VERS = 2.0\n"));
+    assert!(oem.contains("REF_FRAME = EME2000\n"));
+    assert!(oem.contains("START_TIME = 2024-03-01T12:00:00.000\n"));
+    assert!(oem.contains("STOP_TIME = 2024-03-01T12:02:00.000\n"));
+    let states: Vec<&str> = oem
+        .lines()
+        .skip_while(|l| *l != "META_STOP")
+        .skip(2)
+        .collect();
+    assert_eq!(states.len(), 3);
+    let fields: Vec<&str> = states[0].split_whitespace().collect();
+    assert_eq!(fields[..2], ["2024-03-01T12:00:00.000", "6771.000"]);
+    // Forward difference over the first minute: 460237 m in 60 s along y.
+    assert_eq!(fields[5], "7.670617");
+
+    assert!(to_oem(&data, &[0, 0, 60], "SAT-A").is_err());
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use tfhe::prelude::*;
+use tfhe::{ConfigBuilder, FheBool, generate_keys, set_server_key};
+
+use sat_trajectory_fhe::common::SerializationLimits;
+use sat_trajectory_fhe::fallback::{BundleFailure, Fallback, ResendRequest, open_bundle, rebundle};
+use sat_trajectory_fhe::protocol::MessageKind;
+use sat_trajectory_fhe::result_bundle::ResultBundle;
+use sat_trajectory_fhe::session::Session;
+
+/// An unreadable result message is answered with resend requests escalating from
+/// uncompressed to chunked bundles, after which both sides give up.
+#[test]
+fn test_session_escalates_resends() -> Result<(), Box<dyn std::error::Error>> {
+    let limits = SerializationLimits {
+        result_bundle: 1 << 12,
+        fhe_bool: 1 << 8,
+        ..Default::default()
+    };
+    let too_large = BundleFailure::TooLarge {
+        size: 1 << 13,
+        limit: 1 << 12,
+    };
+    assert_eq!(
+        Fallback::after(&too_large, None, &limits),
+        Some(Fallback::Chunked { chunk_steps: 16 })
+    );
+    let request = ResendRequest {
+        first_index: 10,
+        steps: 40,
+        fallback: Fallback::Chunked { chunk_steps: 16 },
+    };
+    assert_eq!(request.bundles(), 3);
+
+    let mut owner = Session::open()?;
+    let mut evaluator = Session::join(owner.nonce());
+    let results = evaluator.send(MessageKind::Results, b"not a bundle".to_vec())?;
+    let failure = match owner.accept_results(&results)? {
+        Err(failure @ BundleFailure::Malformed(_)) => failure,
+        other => panic!(
+            "expected a malformed bundle, got {:?}",
+            other.map(|b| b.len())
+        ),
+    };
+
+    let (request, message) = owner.request_resend(&failure, 0, 100)?;
+    assert_eq!(request.fallback, Fallback::Uncompressed);
+    assert_eq!(evaluator.accept_resend(&message)?, request);
+
+    let (request, message) = owner.request_resend(&failure, 0, 100)?;
+    assert!(matches!(request.fallback, Fallback::Chunked { .. }));
+    assert_eq!(evaluator.accept_resend(&message)?, request);
+
+    let err = owner.request_resend(&failure, 0, 100).unwrap_err();
+    assert!(err.to_string().contains("giving up"), "{}", err);
+
+    // A peer that keeps asking is refused.
+    let extra = owner.send(MessageKind::Resend, bincode::serialize(&request)?)?;
+    assert!(evaluator.accept_resend(&extra).is_err());
+    Ok(())
+}
+
+/// The evaluator answers a resend from the flags it kept, without evaluating again:
+/// chunked bundles carry their own unverified steps, and a compressed bundle opens to
+/// the same flags.
+#[test]
+fn test_rebundle_and_open() -> Result<(), Box<dyn std::error::Error>> {
+    let (client_key, server_key) = generate_keys(ConfigBuilder::default().build());
+    set_server_key(server_key);
+    let values = [true, false, false, true, true, false, true];
+    let flags: Vec<FheBool> = values
+        .iter()
+        .map(|&v| FheBool::encrypt(v, &client_key))
+        .collect();
+    let decrypt = |bundle: ResultBundle| -> Vec<bool> {
+        bundle
+            .into_flags()
+            .iter()
+            .map(|f| f.decrypt(&client_key))
+            .collect()
+    };
+
+    let compressed = ResultBundle::compressed(20, &flags).to_bytes()?;
+    let opened = open_bundle(&compressed)?;
+    assert!(!opened.is_compressed());
+    assert_eq!(opened.first_index, 20);
+    assert_eq!(decrypt(opened), values);
+
+    let request = ResendRequest {
+        first_index: 21,
+        steps: 5,
+        fallback: Fallback::Chunked { chunk_steps: 2 },
+    };
+    let bundles = rebundle(&request, 20, &flags, &[22, 25])?;
+    assert_eq!(bundles.len(), request.bundles());
+    let firsts: Vec<usize> = bundles.iter().map(|b| b.first_index).collect();
+    assert_eq!(firsts, [21, 23, 25]);
+    assert_eq!(bundles[0].failed(), [22]);
+    assert!(bundles[1].failed().is_empty());
+    assert_eq!(bundles[2].failed(), [25]);
+    let resent: Vec<bool> = bundles.into_iter().flat_map(decrypt).collect();
+    assert_eq!(resent, values[1..6]);
+
+    let outside = ResendRequest {
+        first_index: 25,
+        steps: 5,
+        fallback: Fallback::Uncompressed,
+    };
+    assert!(rebundle(&outside, 20, &flags, &[]).is_err());
+    Ok(())
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use sat_trajectory_fhe::fleet::{EncryptedFleet, Fleet, FleetResults};
+use sat_trajectory_fhe::object_id::ObjectId;
+use sat_trajectory_fhe::party::PartyBuilder;
+
+use common::trajectory;
+
+/// Satellite IDs are unique within a fleet.
+#[test]
+fn test_fleet_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let mut fleet = Fleet::new();
+    fleet.add(ObjectId::Norad(25544), trajectory(vec![1, 2]))?;
+    fleet.add("sat-b", trajectory(vec![3, 4]))?;
+    assert!(fleet.add("sat-b", trajectory(vec![5, 6])).is_err());
+    assert_eq!(
+        fleet.ids().cloned().collect::<Vec<_>>(),
+        [ObjectId::Norad(25544), ObjectId::from("sat-b")]
+    );
+    assert_eq!(
+        fleet.get(&"sat-b".into()).map(|t| t.x.clone()),
+        Some(vec![3, 4])
+    );
+    Ok(())
+}
+
+/// Every owned satellite is screened against every counterpart under one key, and each
+/// hit is attributed to the pair.
+#[tokio::test]
+async fn test_fleet_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(trajectory(vec![0, 0, 0]))
+        .owner()
+        .build()?;
+    let counterparts = [trajectory(vec![10, 20, 30]), trajectory(vec![11, 21, 31])];
+    let evaluator = PartyBuilder::new(counterparts[0].clone())
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+
+    let mut fleet = Fleet::new();
+    fleet.add("alpha", trajectory(vec![10, 99, 31]))?;
+    fleet.add("beta", trajectory(vec![99, 21, 99]))?;
+    let encrypted = EncryptedFleet::from_bytes(&owner.encrypt_fleet(&fleet)?.to_bytes()?)?;
+    assert_eq!(encrypted.len(), 2);
+
+    let output = evaluator.evaluate_fleet(&encrypted, &counterparts)?;
+    assert_eq!(output.ops.comparisons, 2 * 2 * 3 * 3);
+    let results = FleetResults::from_bytes(&output.results.to_bytes()?)?;
+    let flags = owner.decrypt_fleet(&results);
+    assert_eq!(flags.len(), 4);
+    assert_eq!(
+        flags[&(ObjectId::from("alpha"), 0)],
+        vec![true, false, false]
+    );
+    assert_eq!(
+        flags[&(ObjectId::from("alpha"), 1)],
+        vec![false, false, true]
+    );
+    assert_eq!(
+        flags[&(ObjectId::from("beta"), 0)],
+        vec![false, false, false]
+    );
+    assert_eq!(
+        flags[&(ObjectId::from("beta"), 1)],
+        vec![false, true, false]
+    );
+    Ok(())
+}
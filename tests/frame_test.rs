@@ -0,0 +1,42 @@
+use sat_trajectory_fhe::frame::{Frame, check_frames, convert, eci_to_ecef, gmst_rad};
+use sat_trajectory_fhe::protocol::ProtocolError;
+
+/// ECI -> ECEF -> ECI is the identity, and the rotation keeps the radius and z.
+#[test]
+fn test_eci_ecef_roundtrip() {
+    let t = 1_700_000_000.0;
+    let eci = [6778.0, 120.5, -35.25];
+
+    let ecef = eci_to_ecef(eci, t);
+    let back = convert(ecef, t, Frame::Ecef, Frame::Eci);
+    for k in 0..3 {
+        assert!((back[k] - eci[k]).abs() < 1e-9, "{:?} != {:?}", back, eci);
+    }
+
+    let norm = |p: [f64; 3]| (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+    assert!((norm(ecef) - norm(eci)).abs() < 1e-9);
+    assert_eq!(ecef[2], eci[2]);
+    assert_eq!(convert(eci, t, Frame::Eci, Frame::Eci), eci);
+}
+
+/// GMST at the J2000 epoch (2000-01-01 12:00 UT) is about 280.46 degrees.
+#[test]
+fn test_gmst_at_j2000() {
+    let j2000_unix_s = 946_728_000.0;
+    assert!((gmst_rad(j2000_unix_s).to_degrees() - 280.460_618_37).abs() < 1e-6);
+}
+
+/// Unconverted data in different frames is a hard protocol error.
+#[test]
+fn test_frame_mismatch() {
+    assert!(check_frames(Frame::Ecef, Frame::Ecef).is_ok());
+    let err = check_frames(Frame::Eci, Frame::Ecef).unwrap_err();
+    assert_eq!(
+        err,
+        ProtocolError::FrameMismatch {
+            owner: Frame::Eci,
+            evaluator: Frame::Ecef
+        }
+    );
+    println!("{}", err);
+}
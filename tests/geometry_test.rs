@@ -0,0 +1,73 @@
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::geometry::{decode, ric_basis, ric_separation};
+use sat_trajectory_fhe::units::{BIAS, Units};
+
+fn at(v: i64) -> u32 {
+    (BIAS + v) as u32
+}
+
+fn trajectory(points: &[[i64; 3]]) -> SatelliteData {
+    SatelliteData {
+        x: points.iter().map(|p| at(p[0])).collect(),
+        y: points.iter().map(|p| at(p[1])).collect(),
+        z: points.iter().map(|p| at(p[2])).collect(),
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+fn assert_close(a: [f64; 3], b: [f64; 3]) {
+    for (x, y) in a.iter().zip(&b) {
+        assert!((x - y).abs() < 1e-3, "{:?} != {:?}", a, b);
+    }
+}
+
+/// The basis follows the object's position and direction of motion, also at its last step.
+#[test]
+fn test_ric_basis() -> Result<(), Box<dyn std::error::Error>> {
+    // Along +y at x = 7000 km, then along -z at y = 7000 km.
+    let track = trajectory(&[[7_000_000, 0, 0], [7_000_000, 7_500, 0]]);
+    let [r, i, c] = ric_basis(&track, 0)?;
+    assert_close(r, [1.0, 0.0, 0.0]);
+    assert_close(i, [0.0, 1.0, 0.0]);
+    assert_close(c, [0.0, 0.0, 1.0]);
+
+    let track = trajectory(&[[0, 7_000_000, 7_500], [0, 7_000_000, 0]]);
+    let [r, i, c] = ric_basis(&track, 1)?;
+    assert_close(r, [0.0, 1.0, 0.0]);
+    assert_close(i, [0.0, 0.0, -1.0]);
+    assert_close(c, [-1.0, 0.0, 0.0]);
+
+    assert!(ric_basis(&trajectory(&[[7_000_000, 0, 0]]), 0).is_err());
+    Ok(())
+}
+
+/// The owner decrypts the separation from the evaluator's object in its RIC frame.
+#[tokio::test]
+async fn test_ric_separation() -> Result<(), Box<dyn std::error::Error>> {
+    let evaluator = trajectory(&[[7_000_000, 0, 0], [7_000_000, 7_500, 0]]);
+    let owner = trajectory(&[[7_000_100, -200, 50], [7_000_000, 7_500, 0]]);
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&owner)?;
+    let output = context.evaluate_with(|| ric_separation(&encrypted, &evaluator, &[0]))?;
+    assert_eq!(output.steps, vec![0]);
+    let raw: Vec<u64> = context.decrypt(&output.components[0])?;
+    let ric = [
+        decode(raw[0], Units::Meters),
+        decode(raw[1], Units::Meters),
+        decode(raw[2], Units::Meters),
+    ];
+    assert_close(ric, [100.0, -200.0, 50.0]);
+
+    assert!(
+        context
+            .evaluate_with(|| ric_separation(&encrypted, &evaluator, &[2]))
+            .is_err()
+    );
+    Ok(())
+}
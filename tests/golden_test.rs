@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use sat_trajectory_fhe::protocol::{Envelope, MessageKind};
+use sat_trajectory_fhe::screening::results_from_bytes;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::testdata;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+
+// Vectors in `tests/golden` are written by `cargo run --bin sat-fhe-testdata`. A failing
+// comparison means a wire format changed: bump its version (see `migrate`) and regenerate
+// them only if the change is intentional.
+fn golden(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+}
+
+/// This build writes exactly the committed bytes.
+#[test]
+fn test_golden_vectors_unchanged() -> Result<(), Box<dyn std::error::Error>> {
+    for (name, bytes) in testdata::vectors()? {
+        let committed = std::fs::read(golden(name))?;
+        assert_eq!(bytes, committed, "wire format of {} changed", name);
+    }
+    Ok(())
+}
+
+/// And reads them back to the values they were written from.
+#[test]
+fn test_golden_vectors_decode() -> Result<(), Box<dyn std::error::Error>> {
+    let envelope = Envelope::from_bytes(&std::fs::read(golden("envelope.bin"))?)?;
+    assert_eq!(envelope.nonce, testdata::NONCE);
+    assert_eq!(envelope.kind, MessageKind::Results);
+    assert_eq!(envelope.payload, b"golden");
+
+    let (session, metadata) = Session::accept(&std::fs::read(golden("hello.bin"))?)?;
+    assert_eq!(session.nonce(), testdata::NONCE);
+    assert_eq!(metadata, testdata::metadata()?);
+
+    let trajectory = EncryptedTrajectory::from_bytes(&std::fs::read(golden("trajectory.bin"))?)?;
+    let expected = testdata::trajectory();
+    assert_eq!(
+        (trajectory.first_index, trajectory.frame, trajectory.units),
+        (expected.first_index, expected.frame, expected.units)
+    );
+    assert!(results_from_bytes(&std::fs::read(golden("results.bin"))?)?.is_empty());
+    Ok(())
+}
+
+/// `.eft` headers carry the producing release, so the file is checked by reading it.
+#[cfg(feature = "mmap")]
+#[test]
+fn test_golden_eft() -> Result<(), Box<dyn std::error::Error>> {
+    use sat_trajectory_fhe::eft::{EFT_VERSION, EftReader};
+
+    let reader = EftReader::open(golden("trajectory.eft"))?;
+    let expected = testdata::trajectory();
+    assert_eq!(reader.version(), EFT_VERSION);
+    assert_eq!(reader.producer(), "sat-trajectory-fhe 0.1.0");
+    assert!(reader.is_empty());
+    assert_eq!(
+        (reader.first_index(), reader.frame(), reader.units()),
+        (expected.first_index, expected.frame, expected.units)
+    );
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::grid::{
+    EncryptedInput, EvaluatorResources, OUTSIDE, Resolution, ResolutionRequest, voxel,
+};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::protocol::SessionMetadata;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::{BIAS, Units};
+
+fn trajectory(x: Vec<i64>) -> SatelliteData {
+    let len = x.len();
+    SatelliteData {
+        x: x.into_iter().map(|v| (v + BIAS) as u32).collect(),
+        y: vec![BIAS as u32; len],
+        z: vec![(BIAS + 250) as u32; len],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// Voxels are counted from the encoding's zero and refuse what doesn't fit in 16 bits.
+#[test]
+fn test_voxel() {
+    let at = |offset: i64| voxel((BIAS + offset) as u32, 100);
+    assert_eq!(at(0), Some(1 << 15));
+    assert_eq!(at(99), Some(1 << 15));
+    assert_eq!(at(-1), Some((1 << 15) - 1));
+    assert_eq!(at(-(1 << 15) * 100), Some(0));
+    assert_eq!(at(-(1 << 15) * 100 - 1), None);
+    assert_eq!(at(((1 << 15) - 2) * 100), Some(OUTSIDE - 1));
+    assert_eq!(at(((1 << 15) - 1) * 100), None);
+}
+
+/// Constrained evaluators ask for the grid, and the request reaches the owner.
+#[test]
+fn test_resolution_request() -> Result<(), Box<dyn std::error::Error>> {
+    let small = EvaluatorResources {
+        threads: 2,
+        memory_bytes: Some(32 << 30),
+    };
+    let large = EvaluatorResources {
+        threads: 16,
+        memory_bytes: None,
+    };
+    assert_eq!(
+        ResolutionRequest::for_resources(small, 500).resolution,
+        Resolution::Grid16 { cell_size: 500 }
+    );
+    assert_eq!(
+        ResolutionRequest::for_resources(large, 500).resolution,
+        Resolution::Full
+    );
+
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&SessionMetadata::default())?;
+    let (mut evaluator, _) = Session::accept(&hello)?;
+    let request = ResolutionRequest::for_resources(small, 500);
+    let message = evaluator.request_resolution(&request)?;
+    assert_eq!(owner.accept_resolution(&message)?, request);
+    Ok(())
+}
+
+/// At grid resolution, positions in the same voxel match and others don't.
+#[tokio::test]
+async fn test_grid_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(trajectory(vec![0, 1_000, 5_000, -40]))
+        .owner()
+        .build()?;
+    let evaluator = PartyBuilder::new(trajectory(vec![60, 1_100, 5_099, -101]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+
+    let encrypted = owner.encrypt_at(Resolution::Grid16 { cell_size: 100 })?;
+    let received = EncryptedInput::from_bytes(&encrypted.to_bytes()?)?;
+    assert!(matches!(received, EncryptedInput::Grid(_)));
+    let output = evaluator.evaluate_input(&received)?;
+    assert_eq!(output.ops.comparisons, 3 * 4);
+    assert_eq!(
+        owner.decrypt_results(&output.results),
+        vec![true, false, true, false]
+    );
+
+    // Full resolution still goes through the regular path.
+    let full = owner.encrypt_at(Resolution::Full)?;
+    let output = evaluator.evaluate_input(&full)?;
+    assert_eq!(
+        owner.decrypt_results(&output.results),
+        vec![false, false, false, false]
+    );
+    Ok(())
+}
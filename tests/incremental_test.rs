@@ -0,0 +1,68 @@
+mod common;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::trajectory::{EncryptedTrajectory, TrajectoryDigest};
+use sat_trajectory_fhe::units::Units;
+
+use common::trajectory;
+
+/// Changed and appended steps are found by content hash; units change every step.
+#[test]
+fn test_diff_indices() -> Result<(), Box<dyn std::error::Error>> {
+    let old = trajectory(vec![10, 20, 30, 40, 50]);
+    let mut new = trajectory(vec![10, 21, 30, 40, 51, 60, 70]);
+    new.z[2] = 1;
+    let diff = |old: &SatelliteData, new: &SatelliteData| {
+        EncryptedTrajectory::diff_indices(
+            &TrajectoryDigest::of(old).unwrap(),
+            &TrajectoryDigest::of(new).unwrap(),
+        )
+    };
+    assert_eq!(diff(&old, &new), vec![1, 2, 4, 5, 6]);
+    assert!(diff(&old, &old).is_empty());
+    // Dropped steps have nothing to screen.
+    assert!(diff(&new, &trajectory(vec![10])).is_empty());
+    let kilometers = SatelliteData {
+        units: Units::Kilometers,
+        ..old.clone()
+    };
+    assert_eq!(diff(&old, &kilometers), vec![0, 1, 2, 3, 4]);
+
+    // Unequal axes are refused instead of indexed out of bounds.
+    let mut ragged = old.clone();
+    ragged.z.pop();
+    assert_eq!(
+        TrajectoryDigest::of(&ragged).unwrap_err().to_string(),
+        "trajectory axes have different lengths"
+    );
+    Ok(())
+}
+
+/// Only runs of changed steps are encrypted again, and screen against the right steps.
+#[tokio::test]
+async fn test_encrypt_changed() -> Result<(), Box<dyn std::error::Error>> {
+    let previous = TrajectoryDigest::of(&trajectory(vec![100, 200, 300, 400, 500]))?;
+    let owner = PartyBuilder::new(trajectory(vec![100, 201, 301, 400, 501, 600]))
+        .owner()
+        .build()?;
+    let evaluator = PartyBuilder::new(trajectory(vec![0, 201, 0, 0, 0, 600]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+
+    let runs = owner.encrypt_changed(&previous)?;
+    assert_eq!(
+        runs.iter()
+            .map(|run| (run.first_index, run.len()))
+            .collect::<Vec<_>>(),
+        vec![(1, 2), (4, 2)]
+    );
+    let flags: Vec<Vec<bool>> = runs
+        .iter()
+        .map(|run| Ok(owner.decrypt_results(&evaluator.evaluate(run)?.results)))
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+    assert_eq!(flags, vec![vec![true, false], vec![false, true]]);
+
+    assert!(owner.encrypt_changed(&owner.digest()?)?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::inspect::{inspect, inspect_file};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::MessageKind;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::testdata;
+use sat_trajectory_fhe::units::Units;
+
+fn golden(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+}
+
+/// The golden vectors are described from their framing alone.
+#[test]
+fn test_inspect_golden_vectors() -> Result<(), Box<dyn Error>> {
+    let hello = inspect_file(golden("hello.bin"))?;
+    assert_eq!((hello.kind, hello.version), ("envelope", 2));
+    assert_eq!(hello.get("message"), Some("Hello"));
+    assert_eq!(hello.get("key fingerprint"), Some("0123456789abcdef"));
+    assert_eq!(hello.get("padded length"), Some("64"));
+    assert_eq!(hello.get("units"), Some("Kilometers"));
+
+    let trajectory = inspect_file(golden("trajectory.bin"))?;
+    assert_eq!(trajectory.kind, "encrypted trajectory");
+    assert_eq!(trajectory.get("steps"), Some("0"));
+    assert_eq!(trajectory.get("epochs"), Some("none"));
+    assert_eq!(
+        trajectory.get("frame"),
+        Some(format!("{:?}", testdata::trajectory().frame).as_str())
+    );
+
+    // The golden envelope's payload is not an artifact itself.
+    let envelope = inspect_file(golden("envelope.bin"))?;
+    assert_eq!(envelope.get("payload bytes"), Some("6"));
+    assert!(envelope.payload.is_none());
+
+    assert_eq!(inspect_file(golden("results.bin"))?.get("flags"), Some("0"));
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_inspect_eft() -> Result<(), Box<dyn Error>> {
+    let info = inspect_file(golden("trajectory.eft"))?;
+    assert_eq!(info.kind, ".eft trajectory");
+    assert_eq!(info.get("steps"), Some("0"));
+    assert!(
+        info.to_string()
+            .starts_with(".eft trajectory (format version")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_inspect_rejects_unknown_bytes() {
+    assert!(inspect(b"").is_err());
+    assert!(inspect(b"definitely not an artifact").is_err());
+}
+
+/// A server key inside an envelope: fingerprint and preset without the client key.
+#[tokio::test]
+async fn test_inspect_server_key() -> Result<(), Box<dyn Error>> {
+    let owner = PartyBuilder::new(SatelliteData {
+        x: vec![1],
+        y: vec![2],
+        z: vec![3],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    })
+    .config(ParameterPreset::Tuniform2m64.config())
+    .owner()
+    .build()?;
+    let message = Session::open()?.send(MessageKind::ServerKey, owner.server_key_bytes()?)?;
+
+    let info = inspect(&message)?;
+    assert_eq!(info.get("message"), Some("ServerKey"));
+    let key = info.payload.as_ref().expect("server key payload");
+    assert_eq!(key.kind, "server key");
+    assert_eq!(key.get("fingerprint"), Some(owner.server_key_fingerprint()));
+    assert_eq!(key.get("preset"), Some("Tuniform2m64"));
+    assert!(
+        info.to_string()
+            .contains("    server key (format version 2")
+    );
+    Ok(())
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "interop")]
+
+use anise::constants::frames::{EARTH_ITRF93, EARTH_J2000, MOON_J2000};
+use anise::prelude::Orbit;
+use hifitime::{Epoch, Unit};
+use sat_trajectory_fhe::events::cluster;
+use sat_trajectory_fhe::export::quantization_tolerance;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::interop::{
+    epoch_from_unix, epoch_to_unix, event_epochs, event_from_epochs, from_states, to_states,
+};
+use sat_trajectory_fhe::units::Units;
+
+const START: u64 = 1_709_294_400;
+
+fn states(frame: anise::prelude::Frame) -> Vec<Orbit> {
+    (0..3)
+        .map(|i| {
+            let epoch = epoch_from_unix(START + 60 * i as u64);
+            Orbit::new(
+                6_771.000_4,
+                -12.3456 + 460.0 * i as f64,
+                0.04225,
+                0.0,
+                7.6,
+                0.0,
+                epoch,
+                frame,
+            )
+        })
+        .collect()
+}
+
+/// nyx-style states become a trajectory in the matching frame and come back within half a
+/// step of the units, with finite-difference velocities.
+#[test]
+fn test_states_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let original = states(EARTH_J2000);
+    let trajectory = from_states(&original, Units::Meters)?;
+    assert_eq!(trajectory.epochs, [START, START + 60, START + 120]);
+    assert_eq!(trajectory.data.frame, Frame::Eci);
+
+    let back = to_states(&trajectory)?;
+    assert_eq!(back.len(), 3);
+    let tolerance_km = quantization_tolerance(Units::Meters) / 1_000.0;
+    for (back, original) in back.iter().zip(&original) {
+        assert_eq!(back.epoch, original.epoch);
+        assert_eq!(back.frame, EARTH_J2000);
+        assert!((back.radius_km - original.radius_km).norm() <= tolerance_km * 3f64.sqrt());
+    }
+    // 460 km along y per minute.
+    assert!((back[1].velocity_km_s.y - 460.0 / 60.0).abs() < 1e-3);
+
+    let ecef = from_states(&states(EARTH_ITRF93), Units::Kilometers)?;
+    assert_eq!(ecef.data.frame, Frame::Ecef);
+    assert_eq!(to_states(&ecef)?[0].frame, EARTH_ITRF93);
+    Ok(())
+}
+
+/// States this crate can't represent are refused rather than approximated.
+#[test]
+fn test_unrepresentable_states_refused() {
+    assert!(from_states(&states(MOON_J2000), Units::Meters).is_err());
+
+    let mut mixed = states(EARTH_J2000);
+    mixed[2].frame = EARTH_ITRF93;
+    assert!(from_states(&mixed, Units::Meters).is_err());
+
+    let mut fractional = states(EARTH_J2000);
+    fractional[1].epoch += 0.5 * Unit::Second;
+    assert!(from_states(&fractional, Units::Meters).is_err());
+
+    let mut unordered = states(EARTH_J2000);
+    unordered.swap(0, 1);
+    assert!(from_states(&unordered, Units::Meters).is_err());
+}
+
+/// Conjunction events convert to and from `hifitime` epochs in UTC.
+#[test]
+fn test_event_epochs() -> Result<(), Box<dyn std::error::Error>> {
+    let epochs = [START, START + 60, START + 120, START + 180];
+    let events = cluster(&[false, true, true, false], &epochs, 0)?;
+    assert_eq!(events.len(), 1);
+
+    let (start, end) = event_epochs(&events[0]);
+    assert_eq!(start, Epoch::from_gregorian_utc_hms(2024, 3, 1, 12, 1, 0));
+    assert_eq!(end, Epoch::from_gregorian_utc_hms(2024, 3, 1, 12, 2, 0));
+    assert_eq!(event_from_epochs(1, 2, start, end)?, events[0]);
+
+    assert_eq!(epoch_to_unix(epoch_from_unix(START))?, START);
+    assert!(epoch_to_unix(Epoch::from_gregorian_utc_hms(1969, 12, 31, 0, 0, 0)).is_err());
+    assert!(event_from_epochs(1, 2, end, start).is_err());
+    Ok(())
+}
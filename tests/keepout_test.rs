@@ -0,0 +1,73 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::core::canonical::{CANONICAL, CanonicalEncoding};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::keepout::{KeepOutZone, screen_keep_out};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::testdata;
+use sat_trajectory_fhe::units::Units;
+
+fn zones() -> [KeepOutZone; 2] {
+    [
+        KeepOutZone::Box {
+            min_m: [90_000.0, -5_000.0, -5_000.0],
+            max_m: [110_000.0, 5_000.0, 5_000.0],
+        },
+        KeepOutZone::Sphere {
+            center_m: [205_000.0, 3_000.0, 0.0],
+            radius_m: 10_000.0,
+        },
+    ]
+}
+
+/// Steps inside either zone are flagged, steps outside both aren't.
+#[test]
+fn test_keep_out_zones() -> Result<(), Box<dyn std::error::Error>> {
+    let owner_data = CanonicalEncoding {
+        units: Units::Kilometers,
+        ..CANONICAL
+    }
+    .encode_trajectory(
+        &[
+            [100_000.0, 0.0, 0.0],
+            [200_000.0, 0.0, 0.0],
+            [300_000.0, 0.0, 0.0],
+            [115_000.0, 0.0, 0.0],
+        ],
+        Frame::Ecef,
+    )?;
+    let owner = PartyBuilder::new(owner_data).owner().build()?;
+    // The evaluator has zones, not a trajectory.
+    let no_trajectory = SatelliteData {
+        x: Vec::new(),
+        y: Vec::new(),
+        z: Vec::new(),
+        frame: Frame::Ecef,
+        units: Units::Kilometers,
+    };
+    let evaluator = PartyBuilder::new(no_trajectory)
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let output =
+        evaluator.evaluate_keep_out(&owner.encrypt_trajectory()?, &zones(), Frame::Ecef)?;
+    assert_eq!(
+        owner.decrypt_results(&output.results),
+        [true, true, false, false]
+    );
+    assert_eq!(output.ops.comparisons, 4 * (6 + 10));
+    Ok(())
+}
+
+/// Zones in another frame, no zones at all and inverted boxes are refused.
+#[test]
+fn test_keep_out_rejects_bad_zones() {
+    let encrypted = testdata::trajectory();
+    let config = ScreeningConfig::default();
+    assert!(screen_keep_out(&encrypted, &zones(), Frame::Eci, &config).is_err());
+    assert!(screen_keep_out(&encrypted, &[], Frame::Ecef, &config).is_err());
+    let inverted = KeepOutZone::Box {
+        min_m: [1_000.0, 0.0, 0.0],
+        max_m: [0.0, 0.0, 0.0],
+    };
+    assert!(screen_keep_out(&encrypted, &[inverted], Frame::Ecef, &config).is_err());
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::depth::OpCounter;
+use sat_trajectory_fhe::distance::DISTANCE_CAP;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::{CustomKernel, KernelChoice};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::regime::radial_profile;
+use sat_trajectory_fhe::reveal::{RevealPolicy, Revealed};
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::units::Units;
+use tfhe::prelude::*;
+
+use common::diagonal_trajectory;
+
+/// The same screening flags different steps depending on the session's kernel.
+#[tokio::test]
+async fn test_kernels() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![1000, 2000, 3000]))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    // Exact on step 0, 3 units off on x at step 1, 100 units off at step 2.
+    let plaintext = diagonal_trajectory(vec![1000, 2003, 3100]);
+
+    for (kernel, expected) in [
+        (KernelChoice::ExactMatch, vec![true, false, false]),
+        (
+            KernelChoice::BoxThreshold { half_width: 5 },
+            vec![true, true, false],
+        ),
+        (
+            KernelChoice::SquaredDistanceThreshold { threshold: 4 },
+            vec![true, true, false],
+        ),
+    ] {
+        let evaluator = PartyBuilder::new(plaintext.clone())
+            .screening(ScreeningConfig {
+                kernel,
+                ..Default::default()
+            })
+            .evaluator(owner.server_key_bytes()?)
+            .build()?;
+        let output = evaluator.evaluate(&encrypted)?;
+        assert_eq!(output.ops.depth, kernel.kernel()?.cost().depth);
+        assert_eq!(
+            owner.decrypt_results(&output.results),
+            expected,
+            "{:?}",
+            kernel
+        );
+    }
+    Ok(())
+}
+
+/// A closure kernel decides the flags, serially or with steps spread over the workers.
+#[tokio::test]
+async fn test_custom_kernel() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![1000, 2000, 3000, 4000]))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    // Only x is compared: step 1 is off on y and z, step 2 on x.
+    let plaintext = SatelliteData {
+        y: vec![1100, 0, 3100, 4100],
+        z: vec![1200, 0, 3200, 4200],
+        ..diagonal_trajectory(vec![1000, 2000, 3001, 4000])
+    };
+    let cost = OpCounter {
+        comparisons: 1,
+        depth: 1,
+        ..Default::default()
+    };
+    let kernel = CustomKernel::new(cost, |encrypted, clear| encrypted[0].eq(clear[0]));
+
+    for parallel_axes in [false, true] {
+        let evaluator = PartyBuilder::new(plaintext.clone())
+            .screening(ScreeningConfig {
+                parallel_axes,
+                ..Default::default()
+            })
+            .evaluator(owner.server_key_bytes()?)
+            .build()?;
+        let flags = evaluator.evaluate_custom(&encrypted, &kernel, RevealPolicy::PerIndex)?;
+        assert_eq!(flags.ops.comparisons, 4);
+        assert_eq!(flags.ops.depth, 1);
+        assert_eq!(
+            owner.decrypt_revealed(&flags.result)?,
+            Revealed::PerIndex(vec![true, true, false, true])
+        );
+        let count = evaluator.evaluate_custom(&encrypted, &kernel, RevealPolicy::Count)?;
+        assert_eq!(owner.decrypt_revealed(&count.result)?, Revealed::Count(3));
+    }
+    Ok(())
+}
+
+/// The altitude kernel flags steps at the same distance from the origin, even far apart.
+#[tokio::test]
+async fn test_altitude_band_kernel() -> Result<(), Box<dyn std::error::Error>> {
+    let positions =
+        |points: &[[f64; 3]]| SatelliteData::encode(points, Units::Kilometers, Frame::Eci);
+    let owner_track = positions(&[[6_771.0, 0.0, 0.0], [6_771.0, 0.0, 0.0]])?;
+    // Same altitude on the other side of the Earth, then 50 km higher.
+    let evaluator_track = positions(&[[-6_771.0, 0.0, 0.0], [6_821.0, 0.0, 0.0]])?;
+
+    let owner = PartyBuilder::new(radial_profile(&owner_track, 1_000))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let kernel = KernelChoice::AltitudeBand { tolerance: 1 };
+    let evaluator = PartyBuilder::new(radial_profile(&evaluator_track, 1_000))
+        .screening(ScreeningConfig {
+            kernel,
+            ..Default::default()
+        })
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let output = evaluator.evaluate(&encrypted)?;
+    assert_eq!(owner.decrypt_results(&output.results), vec![true, false]);
+    assert_eq!(output.ops.comparisons, 2 * 2);
+    Ok(())
+}
+
+/// Kernel parameters are checked before any homomorphic work.
+#[test]
+fn test_kernel_validation() {
+    assert!(KernelChoice::default().kernel().is_ok());
+    assert!(
+        KernelChoice::SquaredDistanceThreshold {
+            threshold: DISTANCE_CAP
+        }
+        .kernel()
+        .is_err()
+    );
+    let cost = KernelChoice::BoxThreshold { half_width: 1 }
+        .kernel()
+        .unwrap()
+        .cost();
+    assert_eq!((cost.comparisons, cost.depth), (6, 4));
+}
@@ -0,0 +1,34 @@
+use tfhe::prelude::*;
+use tfhe::{ConfigBuilder, FheBool, FheUint32, generate_keys};
+
+use sat_trajectory_fhe::common::{
+    SerializationLimits, safe_deserialize_item, safe_serialize_item, serialization_limits,
+    set_serialization_limits,
+};
+
+/// Each ciphertext type has its own limit, enforced when writing and when reading.
+#[tokio::test]
+async fn test_limits_per_artifact_type() -> Result<(), Box<dyn std::error::Error>> {
+    let (client_key, _) = generate_keys(ConfigBuilder::default().build());
+    let flag = FheBool::encrypt(true, &client_key);
+    let coordinate = FheUint32::encrypt(42u32, &client_key);
+    assert_eq!(serialization_limits(), SerializationLimits::default());
+    let flag_bytes = safe_serialize_item(&flag)?;
+    let coordinate_bytes = safe_serialize_item(&coordinate)?;
+
+    set_serialization_limits(SerializationLimits {
+        fhe_bool: flag_bytes.len() as u64 - 1,
+        ..Default::default()
+    });
+    assert!(safe_serialize_item(&flag).is_err());
+    assert!(safe_deserialize_item::<FheBool>(&flag_bytes).is_err());
+    // Other types keep their own limit.
+    let coordinate: FheUint32 = safe_deserialize_item(&coordinate_bytes)?;
+    let value: u32 = coordinate.decrypt(&client_key);
+    assert_eq!(value, 42);
+
+    set_serialization_limits(SerializationLimits::default());
+    let flag: FheBool = safe_deserialize_item(&flag_bytes)?;
+    assert!(flag.decrypt(&client_key));
+    Ok(())
+}
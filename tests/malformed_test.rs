@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use tfhe::{ConfigBuilder, FheBool, FheUint32};
+
+use sat_trajectory_fhe::common::{
+    ItemTypeMismatch, SatelliteData, item_type_name, safe_deserialize_items, safe_serialize_item,
+};
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::protocol::Envelope;
+use sat_trajectory_fhe::reveal::RevealedResult;
+use sat_trajectory_fhe::screening::results_from_bytes;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::stream::ResultBatch;
+use sat_trajectory_fhe::timing::EncryptedEpochs;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+// Every truncation of `data` and every single-byte corruption of it. The fuzz targets in
+// `fuzz/` explore far more; this keeps the cheap cases in the regular test run.
+fn mutations(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut out: Vec<Vec<u8>> = (0..data.len()).map(|len| data[..len].to_vec()).collect();
+    for i in 0..data.len() {
+        for flip in [0x01, 0x80, 0xff] {
+            let mut mutated = data.to_vec();
+            mutated[i] ^= flip;
+            out.push(mutated);
+        }
+    }
+    out
+}
+
+fn golden(name: &str) -> Vec<u8> {
+    std::fs::read(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name),
+    )
+    .expect("golden vector")
+}
+
+/// Corrupted or truncated wire messages are rejected with an error, never a panic.
+#[test]
+fn test_malformed_messages_dont_panic() {
+    for name in ["envelope.bin", "hello.bin", "trajectory.bin", "results.bin"] {
+        for data in mutations(&golden(name)) {
+            let _ = Envelope::from_bytes(&data);
+            let _ = Session::accept(&data);
+            let _ = EncryptedTrajectory::from_bytes(&data);
+            let _ = results_from_bytes(&data);
+            let _ = RevealedResult::from_bytes(&data);
+            let _ = EncryptedEpochs::from_bytes(&data);
+            let _ = ResultBatch::from_bytes(&data);
+        }
+    }
+    // A length prefix claiming far more than the input holds.
+    let mut huge = b"SFR\x02".to_vec();
+    huge.extend_from_slice(&u64::MAX.to_le_bytes());
+    assert!(results_from_bytes(&huge).is_err());
+}
+
+/// Same for `.eft` files.
+#[cfg(feature = "mmap")]
+#[test]
+fn test_malformed_eft_doesnt_panic() -> Result<(), Box<dyn std::error::Error>> {
+    use sat_trajectory_fhe::eft::EftReader;
+
+    let path = std::env::temp_dir().join(format!("malformed_test_{}.eft", std::process::id()));
+    for data in mutations(&golden("trajectory.eft")) {
+        std::fs::write(&path, &data)?;
+        if let Ok(reader) = EftReader::open(&path) {
+            for i in 0..reader.len() {
+                let _ = reader.step(i);
+            }
+        }
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Items of the wrong TFHE type are refused with both type names and their position.
+#[test]
+fn test_item_type_mismatch_names_types() -> Result<(), Box<dyn std::error::Error>> {
+    let items = results_from_bytes(&golden("results.bin"))?
+        .iter()
+        .map(safe_serialize_item)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(item_type_name(&items[0])?, "FheBool");
+    assert_eq!(
+        safe_deserialize_items::<FheBool>(&items)?.len(),
+        items.len()
+    );
+
+    let err = safe_deserialize_items::<FheUint32>(&items)
+        .map(drop)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ItemTypeMismatch>(),
+        Some(&ItemTypeMismatch {
+            expected: "FheUint32".to_string(),
+            found: "FheBool".to_string(),
+            index: Some(0),
+        })
+    );
+    assert_eq!(
+        err.to_string(),
+        "expected FheUint32, found FheBool at index 0"
+    );
+
+    let mut mixed = items.clone();
+    mixed.push(b"not a ciphertext".to_vec());
+    let err = safe_deserialize_items::<FheBool>(&mixed)
+        .map(drop)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with(&format!("item {}:", items.len()))
+    );
+    Ok(())
+}
+
+/// Trajectories whose absolute step indices would overflow, or whose epochs don't
+/// increase, are refused when parsed rather than panicking in screening.
+#[test]
+fn test_trajectory_metadata_is_checked() -> Result<(), Box<dyn std::error::Error>> {
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&SatelliteData {
+        x: vec![1, 2],
+        y: vec![3, 4],
+        z: vec![5, 6],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    })?;
+    let valid = encrypted.to_bytes()?;
+    assert!(EncryptedTrajectory::from_bytes(&valid).is_ok());
+
+    let mut overflowing = EncryptedTrajectory::from_bytes(&valid)?;
+    overflowing.first_index = usize::MAX - 1;
+    let err = EncryptedTrajectory::from_bytes(&overflowing.to_bytes()?)
+        .map(drop)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("runs past the last index"),
+        "{}",
+        err
+    );
+
+    for epochs in [vec![60, 60], vec![120, 60]] {
+        let mut unordered = EncryptedTrajectory::from_bytes(&valid)?;
+        unordered.epochs = epochs;
+        let err = EncryptedTrajectory::from_bytes(&unordered.to_bytes()?)
+            .map(drop)
+            .unwrap_err();
+        assert!(err.to_string().contains("strictly increasing"), "{}", err);
+    }
+    Ok(())
+}
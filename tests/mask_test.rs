@@ -0,0 +1,77 @@
+mod common;
+
+use sat_trajectory_fhe::mask::StepMask;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::protocol::SessionMetadata;
+use sat_trajectory_fhe::reveal::{RevealPolicy, Revealed, RevealedResult};
+use sat_trajectory_fhe::session::Session;
+
+use common::trajectory;
+
+/// Screened steps skip the masked ones, and their flags go back to the right indices.
+#[test]
+fn test_unmask() -> Result<(), Box<dyn std::error::Error>> {
+    let mask = StepMask::new([2..4, 6..6, 9..12]);
+    assert_eq!(mask.ranges(), [2..4, 9..12]);
+    assert!(mask.is_masked(3) && !mask.is_masked(4));
+
+    // A window starting at step 1, like a trajectory with `first_index` 1.
+    assert_eq!(mask.screened(1..10), vec![1, 4, 5, 6, 7, 8]);
+    let flags = [true, false, true, false, false, true];
+    assert_eq!(
+        mask.unmask(&flags, 1..10)?,
+        vec![true, false, false, false, true, false, false, false, true]
+    );
+    assert!(mask.unmask(&flags[1..], 1..10).is_err());
+    Ok(())
+}
+
+/// The evaluator runs no comparison at masked steps; the owner gets every step back.
+#[tokio::test]
+async fn test_masked_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let sat = trajectory(vec![100, 200, 300, 400, 500]);
+    let owner = PartyBuilder::new(sat.clone()).owner().build()?;
+    let evaluator = PartyBuilder::new(sat)
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    // Every step matches; steps 1, 2 and 5 are maneuver windows.
+    let mask = StepMask::new([1..3, 5..6]);
+    let encrypted = owner.encrypt_trajectory()?.steps(1..5);
+
+    let output = evaluator.evaluate_masked(&encrypted, &mask, RevealPolicy::PerIndex)?;
+    assert_eq!(output.ops.comparisons, 2 * 3);
+    let RevealedResult::PerIndex(flags) = RevealedResult::from_bytes(&output.result.to_bytes()?)?
+    else {
+        panic!("per-step results expected");
+    };
+    assert_eq!(flags.len(), 2);
+    assert_eq!(
+        owner.decrypt_masked(&flags, &mask, 1..5)?,
+        vec![false, false, true, true]
+    );
+
+    let count = evaluator.evaluate_masked(&encrypted, &mask, RevealPolicy::Count)?;
+    assert_eq!(owner.decrypt_revealed(&count.result)?, Revealed::Count(2));
+    Ok(())
+}
+
+/// The mask is part of the session transcript both sides compute.
+#[test]
+fn test_mask_in_transcript() -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = |mask| SessionMetadata {
+        mask: Some(mask),
+        ..Default::default()
+    };
+
+    let mut owner = Session::open()?;
+    let mask = StepMask::new([10..20, 30..40]);
+    let hello = owner.hello(&metadata(mask.clone()))?;
+    let (evaluator, received) = Session::accept(&hello)?;
+    assert_eq!(received.mask, Some(mask));
+    assert_eq!(owner.transcript(), evaluator.transcript());
+
+    let mut other = Session::join(owner.nonce());
+    other.hello(&metadata(StepMask::new([10..21, 30..40])))?;
+    assert_ne!(other.transcript(), owner.transcript());
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::grid::{GridTrajectory, voxel};
+use sat_trajectory_fhe::membership::{catalog_cells, membership_cost, screen_membership, voxel_id};
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::units::{BIAS, Units};
+
+fn trajectory(x: Vec<i64>) -> SatelliteData {
+    let len = x.len();
+    SatelliteData {
+        x: x.into_iter().map(|v| (v + BIAS) as u32).collect(),
+        y: vec![BIAS as u32; len],
+        z: vec![(BIAS + 250) as u32; len],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// Catalog voxels are packed per step, sorted and deduplicated, and off-grid objects
+/// dropped.
+#[test]
+fn test_catalog_cells() {
+    let a = trajectory(vec![0, 500, 900]);
+    let b = trajectory(vec![-100, 550, 900]);
+    let far = trajectory(vec![0, 1 << 30, 0]);
+    let cells = catalog_cells(&[&a, &b, &far], 0..3, 100);
+    let id = |x: i64| {
+        voxel_id(
+            voxel((x + BIAS) as u32, 100).unwrap(),
+            1 << 15,
+            (1 << 15) + 2,
+        )
+    };
+    assert_eq!(
+        cells,
+        vec![vec![id(-100), id(0)], vec![id(500)], vec![id(0), id(900)]]
+    );
+    assert_eq!(voxel_id(1, 2, 3), (1 << 32) | (2 << 16) | 3);
+    assert_eq!(membership_cost(8).comparisons, 8);
+    assert_eq!(membership_cost(8).boolean, 7);
+    assert_eq!(membership_cost(8).depth, 3 + 3);
+}
+
+/// The owner's voxel matches when any catalog object shares it at that step.
+#[test]
+fn test_membership_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let context = FheContext::generate(ParameterPreset::Default.config())?;
+    let owner = trajectory(vec![0, 1_000, 5_000]);
+    let catalog = [
+        trajectory(vec![-500, 1_050, 9_000]),
+        trajectory(vec![99, 2_000, 9_000]),
+        trajectory(vec![300, 3_000, 9_000]),
+    ];
+    let encrypted = GridTrajectory::encrypt(&owner, 100, context.client_key()?)?;
+    let output = context.evaluate_with(|| screen_membership(&encrypted, &catalog))?;
+    assert_eq!(context.decrypt(&output.results)?, vec![true, true, false]);
+    // Three distinct voxels at the first two steps, one shared at the last.
+    assert_eq!(output.ops.comparisons, 3 + 3 + 1);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::migrate::{ArtifactKind, upgrade, upgrade_file, version_of};
+use sat_trajectory_fhe::protocol::{Envelope, MessageKind};
+use sat_trajectory_fhe::screening::results_from_bytes;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+// Fixtures in `tests/fixtures/v1` are in the untagged layout written before format
+// versions existed.
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/v1")
+        .join(name)
+}
+
+fn scratch_copy(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("migrate_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(name);
+    std::fs::copy(fixture(name), &path)?;
+    Ok(path)
+}
+
+/// Version 1 artifacts are still read by the current readers.
+#[test]
+fn test_reads_v1_fixtures() -> Result<(), Box<dyn std::error::Error>> {
+    let trajectory = std::fs::read(fixture("trajectory.bin"))?;
+    assert_eq!(version_of(ArtifactKind::Trajectory, &trajectory)?, 1);
+    let trajectory = EncryptedTrajectory::from_bytes(&trajectory)?;
+    assert!(trajectory.is_empty());
+    assert_eq!(trajectory.first_index, 7);
+    assert_eq!(trajectory.frame, Frame::Ecef);
+    assert_eq!(trajectory.units, Units::Kilometers);
+
+    assert!(results_from_bytes(&std::fs::read(fixture("results.bin"))?)?.is_empty());
+
+    let envelope = Envelope::from_bytes(&std::fs::read(fixture("envelope.bin"))?)?;
+    assert_eq!(
+        envelope,
+        Envelope {
+            nonce: [0x2a; 16],
+            seq: 3,
+            kind: MessageKind::Results,
+            payload: b"v1".to_vec(),
+            checksum: [0; 32],
+        }
+    );
+    Ok(())
+}
+
+/// Upgrading rewrites old files in the current version, once, without changing what they
+/// decode to; the current writers already produce that version.
+#[test]
+fn test_upgrade_files() -> Result<(), Box<dyn std::error::Error>> {
+    for (name, kind) in [
+        ("trajectory.bin", ArtifactKind::Trajectory),
+        ("results.bin", ArtifactKind::Results),
+        ("envelope.bin", ArtifactKind::Envelope),
+    ] {
+        let path = scratch_copy(name)?;
+        assert!(upgrade_file(kind, &path)?);
+        assert!(!upgrade_file(kind, &path)?);
+        assert_eq!(
+            version_of(kind, &std::fs::read(&path)?)?,
+            kind.current_version()
+        );
+    }
+
+    let path = scratch_copy("envelope.bin")?;
+    upgrade_file(ArtifactKind::Envelope, &path)?;
+    let upgraded = std::fs::read(&path)?;
+    let envelope = Envelope::from_bytes(&upgraded)?;
+    assert_eq!(envelope.payload, b"v1");
+    assert_eq!(envelope.to_bytes()?, upgraded);
+    Ok(())
+}
+
+/// Artifacts of another kind or from a newer release are refused instead of misread.
+#[test]
+fn test_rejects_wrong_kind_and_newer_version() -> Result<(), Box<dyn std::error::Error>> {
+    let envelope = upgrade(
+        ArtifactKind::Envelope,
+        &std::fs::read(fixture("envelope.bin"))?,
+    )?
+    .expect("v1 fixture needs upgrading");
+    assert!(results_from_bytes(&envelope).is_err());
+    assert!(upgrade(ArtifactKind::Trajectory, &envelope).is_err());
+
+    let mut newer = envelope.clone();
+    newer[3] = ArtifactKind::Envelope.current_version() + 1;
+    let err = Envelope::from_bytes(&newer).unwrap_err();
+    assert!(err.to_string().contains("newer"), "{}", err);
+    Ok(())
+}
+
+/// Version 1 `.eft` files open, and upgrade to the current version with their ciphertexts
+/// copied unchanged.
+#[cfg(feature = "mmap")]
+#[test]
+fn test_upgrade_eft_v1() -> Result<(), Box<dyn std::error::Error>> {
+    use sat_trajectory_fhe::eft::{EFT_VERSION, EftReader};
+    use sat_trajectory_fhe::migrate::upgrade_eft;
+
+    let reader = EftReader::open(fixture("trajectory.eft"))?;
+    assert_eq!(reader.version(), 1);
+    assert_eq!(reader.epochs(), &[100, 160]);
+    assert_eq!(reader.first_index(), 4);
+    assert_eq!(reader.frame(), Frame::Ecef);
+    assert_eq!(reader.units(), Units::Meters);
+
+    let path = scratch_copy("trajectory.eft")?;
+    assert!(upgrade_eft(&path)?);
+    assert!(!upgrade_eft(&path)?);
+
+    let upgraded = EftReader::open(&path)?;
+    assert_eq!(upgraded.version(), EFT_VERSION);
+    assert!(upgraded.producer().starts_with("sat-trajectory-fhe "));
+    assert_eq!(upgraded.epochs(), reader.epochs());
+    assert_eq!(upgraded.first_index(), 4);
+    assert_eq!(upgraded.frame(), Frame::Ecef);
+    assert!(std::fs::read(&path)?.ends_with(b"x0y0z0x1y1z1"));
+    Ok(())
+}
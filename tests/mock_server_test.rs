@@ -0,0 +1,252 @@
+#![cfg(feature = "mock-server")]
+
+use tokio::net::TcpStream;
+
+use sat_trajectory_fhe::certificate::WorkCertificate;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::mock_server::{
+    MockServer, mock_server_key, mock_trajectory, read_batch, read_results,
+};
+use sat_trajectory_fhe::protocol::{MessageKind, SessionMetadata};
+use sat_trajectory_fhe::reveal::{RevealPolicy, Revealed};
+use sat_trajectory_fhe::service::{
+    JobHandle, JobStatus, Request, Response, ServiceError, read_frame, write_frame,
+};
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::transport::sha256_hex;
+use sat_trajectory_fhe::units::Units;
+
+fn data(x: Vec<u32>) -> SatelliteData {
+    SatelliteData {
+        y: vec![7; x.len()],
+        z: vec![9; x.len()],
+        x,
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+struct Connection(TcpStream);
+
+impl Connection {
+    async fn call(&mut self, request: Request) -> Result<Response, ServiceError> {
+        write_frame(&mut self.0, &request).await?;
+        read_frame(&mut self.0).await
+    }
+
+    // Opens a job for `metadata` and uploads a mock server key and `owner`'s trajectory.
+    async fn screen(
+        &mut self,
+        metadata: &SessionMetadata,
+        owner: &SatelliteData,
+    ) -> Result<(Session, JobHandle), ServiceError> {
+        let mut session = Session::open().map_err(|e| e.to_string())?;
+        let hello = session.hello(metadata).map_err(|e| e.to_string())?;
+        let Response::SessionOpened { job } = self.call(Request::OpenSession { hello }).await?
+        else {
+            panic!("expected a new job");
+        };
+        for (kind, payload) in [
+            (
+                MessageKind::ServerKey,
+                mock_server_key().map_err(|e| e.to_string())?,
+            ),
+            (
+                MessageKind::EncryptedTrajectory,
+                mock_trajectory(owner, &[0, 60, 120, 180], 0).map_err(|e| e.to_string())?,
+            ),
+        ] {
+            let envelope = session.send(kind, payload).map_err(|e| e.to_string())?;
+            assert_eq!(
+                self.call(Request::Upload { job, envelope }).await?,
+                Response::Uploaded
+            );
+        }
+        Ok((session, job))
+    }
+}
+
+async fn start(trajectory: SatelliteData) -> Result<Connection, ServiceError> {
+    let server = MockServer::bind("127.0.0.1:0", trajectory).await?;
+    let addr = server.local_addr()?;
+    tokio::spawn(server.run());
+    Ok(Connection(TcpStream::connect(addr).await?))
+}
+
+/// A per-step screening runs the whole protocol on mock ciphertexts: results, streamed
+/// batches and a certificate, all in session envelopes.
+#[tokio::test]
+async fn test_mock_per_step_screening() -> Result<(), ServiceError> {
+    let mut conn = start(data(vec![1, 2, 3, 4])).await?;
+    let (mut session, job) = conn
+        .screen(&SessionMetadata::default(), &data(vec![1, 5, 3, 6]))
+        .await?;
+    assert_eq!(
+        conn.call(Request::Status { job }).await?,
+        Response::Status(JobStatus::Done)
+    );
+
+    let Response::Results { envelope } = conn.call(Request::Results { job }).await? else {
+        panic!("expected results");
+    };
+    // A fetch retried after a lost reply gets the same envelope, not the next one.
+    assert_eq!(
+        conn.call(Request::Results { job }).await?,
+        Response::Results {
+            envelope: envelope.clone()
+        }
+    );
+    let envelope = session.receive(&envelope).map_err(|e| e.to_string())?;
+    assert_eq!(envelope.kind, MessageKind::Results);
+    let flags = vec![true, false, true, false];
+    assert_eq!(
+        read_results(&envelope.payload).map_err(|e| e.to_string())?,
+        Revealed::PerIndex(flags.clone())
+    );
+
+    let Response::ResultBatch {
+        envelope: Some(envelope),
+        ..
+    } = conn.call(Request::ResultBatch { job, batch: 0 }).await?
+    else {
+        panic!("expected a batch");
+    };
+    let envelope = session.receive(&envelope).map_err(|e| e.to_string())?;
+    assert_eq!(
+        read_batch(&envelope.payload).map_err(|e| e.to_string())?,
+        (0, flags)
+    );
+
+    let Response::Certificate { certificate } = conn.call(Request::Certificate { job }).await?
+    else {
+        panic!("expected a certificate");
+    };
+    let certificate = WorkCertificate::from_bytes(&certificate).map_err(|e| e.to_string())?;
+    assert_eq!(certificate.chunks.len(), 1);
+    assert_eq!(certificate.chunks[0].steps, 4);
+    Ok(())
+}
+
+/// Aggregates follow the kernel and reveal policy of the `Hello`, and chunked uploads are
+/// reassembled.
+#[tokio::test]
+async fn test_mock_aggregate_and_chunks() -> Result<(), ServiceError> {
+    let mut conn = start(data(vec![10, 20, 30, 40])).await?;
+    let metadata = SessionMetadata {
+        kernel: Some(KernelChoice::BoxThreshold { half_width: 2 }),
+        reveal: Some(RevealPolicy::Count),
+        ..Default::default()
+    };
+    let mut session = Session::open().map_err(|e| e.to_string())?;
+    let hello = session.hello(&metadata).map_err(|e| e.to_string())?;
+    let Response::SessionOpened { job } = conn.call(Request::OpenSession { hello }).await? else {
+        panic!("expected a new job");
+    };
+    let key = session
+        .send(
+            MessageKind::ServerKey,
+            mock_server_key().map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| e.to_string())?;
+    conn.call(Request::Upload { job, envelope: key }).await?;
+
+    let trajectory = mock_trajectory(&data(vec![11, 25, 28, 40]), &[0, 60, 120, 180], 0)
+        .map_err(|e| e.to_string())?;
+    let envelope = session
+        .send(MessageKind::EncryptedTrajectory, trajectory)
+        .map_err(|e| e.to_string())?;
+    let chunks: Vec<&[u8]> = envelope.chunks(envelope.len().div_ceil(2)).collect();
+    let hashes = chunks.iter().map(|chunk| sha256_hex(chunk)).collect();
+    assert_eq!(
+        conn.call(Request::BeginChunkedUpload {
+            job,
+            chunks: hashes
+        })
+        .await?,
+        Response::MissingChunks(vec![0, 1])
+    );
+    for (index, chunk) in chunks.iter().enumerate() {
+        let data = chunk.to_vec();
+        assert_eq!(
+            conn.call(Request::UploadChunk { job, index, data }).await?,
+            Response::ChunkReceived { valid: true }
+        );
+    }
+    assert_eq!(
+        conn.call(Request::FinishChunkedUpload { job }).await?,
+        Response::Uploaded
+    );
+
+    let Response::Results { envelope } = conn.call(Request::Results { job }).await? else {
+        panic!("expected results");
+    };
+    let envelope = session.receive(&envelope).map_err(|e| e.to_string())?;
+    assert_eq!(
+        read_results(&envelope.payload).map_err(|e| e.to_string())?,
+        Revealed::Count(3)
+    );
+    assert!(matches!(
+        conn.call(Request::ResultBatch { job, batch: 0 }).await?,
+        Response::Error(_)
+    ));
+    Ok(())
+}
+
+/// Malformed mock ciphertexts fail the job, and protocol errors are answered as the daemon
+/// answers them.
+#[tokio::test]
+async fn test_mock_rejects_bad_clients() -> Result<(), ServiceError> {
+    let mut conn = start(data(vec![1, 2])).await?;
+    let mut session = Session::open().map_err(|e| e.to_string())?;
+    let hello = session
+        .hello(&SessionMetadata::default())
+        .map_err(|e| e.to_string())?;
+    let Response::SessionOpened { job } = conn.call(Request::OpenSession { hello }).await? else {
+        panic!("expected a new job");
+    };
+
+    // An envelope skipping a sequence number is refused.
+    let mut ahead = Session::join(session.nonce());
+    for kind in [MessageKind::Hello, MessageKind::ServerKey] {
+        ahead.send(kind, Vec::new()).map_err(|e| e.to_string())?;
+    }
+    let skipped = ahead
+        .send(
+            MessageKind::ServerKey,
+            mock_server_key().map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| e.to_string())?;
+    assert!(matches!(
+        conn.call(Request::Upload {
+            job,
+            envelope: skipped
+        })
+        .await?,
+        Response::Error(_)
+    ));
+
+    let key = session
+        .send(
+            MessageKind::ServerKey,
+            mock_server_key().map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| e.to_string())?;
+    conn.call(Request::Upload { job, envelope: key }).await?;
+    let mut trajectory =
+        mock_trajectory(&data(vec![1, 2]), &[0, 60], 0).map_err(|e| e.to_string())?;
+    // The last coordinate item, an 8-byte length and 4 bytes of value, loses a byte.
+    let len = trajectory.len();
+    trajectory[len - 12] = 3;
+    trajectory.pop();
+    let envelope = session
+        .send(MessageKind::EncryptedTrajectory, trajectory)
+        .map_err(|e| e.to_string())?;
+    conn.call(Request::Upload { job, envelope }).await?;
+    assert!(matches!(
+        conn.call(Request::Status { job }).await?,
+        Response::Status(JobStatus::Failed(_))
+    ));
+    Ok(())
+}
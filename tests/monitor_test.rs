@@ -0,0 +1,53 @@
+#![cfg(feature = "monitor")]
+
+use std::time::Duration;
+
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+
+use sat_trajectory_fhe::monitor::{job_label, render, throughput};
+use sat_trajectory_fhe::service::{JobStatus, JobSummary, JobsSnapshot};
+
+/// Each job gets a labelled progress bar, running jobs add up to the throughput and the
+/// newest alert is listed.
+#[test]
+fn test_monitor_renders_jobs_and_alerts() -> Result<(), Box<dyn std::error::Error>> {
+    let running = JobSummary {
+        job: 7,
+        status: JobStatus::Running,
+        steps: 100,
+        screened: 48,
+        batches: 3,
+        batch_steps: 16,
+        elapsed: Duration::from_secs(4),
+    };
+    let failed = JobSummary {
+        job: 8,
+        status: JobStatus::Failed("bad key".to_string()),
+        ..running.clone()
+    };
+    let snapshot = JobsSnapshot {
+        jobs: vec![running.clone(), failed],
+        alerts: vec!["job 8 failed: bad key".to_string()],
+    };
+    assert_eq!(
+        job_label(&running),
+        "job 7  running  48/100 steps  batch 3/7  12.0 steps/s"
+    );
+    assert_eq!(throughput(&snapshot), 12.0);
+
+    let mut terminal = Terminal::new(TestBackend::new(90, 20))?;
+    terminal.draw(|frame| render(frame, "127.0.0.1:7878", &snapshot))?;
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(screen.contains("2 jobs, 1 active, 12.0 steps/s"));
+    assert!(screen.contains("job 7  running"));
+    assert!(screen.contains("job 8  failed"));
+    assert!(screen.contains("job 8 failed: bad key"));
+    Ok(())
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use sat_trajectory_fhe::multires::{CoarseToFine, assemble};
+use sat_trajectory_fhe::party::PartyBuilder;
+
+use common::trajectory;
+
+/// Plans cover the drift between samples and refine around positive samples only.
+#[test]
+fn test_plan() {
+    let plan = CoarseToFine::covering(4, 10, 5);
+    assert_eq!(plan.half_width, 30);
+    assert_eq!(CoarseToFine::covering(5, 10, 5).half_width, 45);
+
+    assert_eq!(plan.refine(&[false, true, false, false], 14), vec![1..8]);
+    // Neighbouring positives merge; the last range stops at the end of the trajectory.
+    assert_eq!(
+        plan.refine(&[true, false, true, true], 14),
+        vec![0..4, 5..14]
+    );
+    assert!(plan.refine(&[false; 4], 14).is_empty());
+
+    assert_eq!(
+        assemble(6, 10, [(11, vec![false, true]), (14, vec![true])]),
+        vec![false, false, true, false, true, false]
+    );
+}
+
+/// A crossing between two coarse samples is found and pinned to its step by the fine pass,
+/// which only screens the steps around it.
+#[tokio::test]
+async fn test_coarse_to_fine() -> Result<(), Box<dyn std::error::Error>> {
+    let steps = 24;
+    let owner = PartyBuilder::new(trajectory((0..steps).map(|i| 100 + 10 * i).collect()))
+        .owner()
+        .build()?;
+    let evaluator = PartyBuilder::new(trajectory((0..steps).map(|i| 340 - 10 * i).collect()))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let plan = CoarseToFine::covering(4, 10, 10);
+
+    let coarse = plan.coarse(&encrypted);
+    assert_eq!(coarse.len(), 6);
+    let coarse_output = evaluator.evaluate_coarse(&coarse, &plan)?;
+    let coarse_flags = owner.decrypt_results(&coarse_output.results);
+    assert_eq!(coarse_flags, vec![false, false, false, true, false, false]);
+
+    let ranges = plan.refine(&coarse_flags, encrypted.len());
+    assert_eq!(ranges, vec![9..16]);
+    let mut refined = Vec::new();
+    for range in ranges {
+        let part = encrypted.steps(range);
+        let output = evaluator.evaluate(&part)?;
+        refined.push((part.first_index, owner.decrypt_results(&output.results)));
+    }
+    let flags = assemble(encrypted.len(), encrypted.first_index, refined);
+    let expected: Vec<bool> = (0..steps as usize).map(|i| i == 12).collect();
+    assert_eq!(flags, expected);
+    Ok(())
+}
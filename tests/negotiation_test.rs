@@ -0,0 +1,117 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::negotiation::{EncodingParams, negotiate};
+use sat_trajectory_fhe::protocol::{ProtocolError, SessionMetadata};
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::{BIAS, Units};
+
+fn params() -> EncodingParams {
+    EncodingParams {
+        units: Units::Meters,
+        cell_size: 10,
+        time_step_s: 60,
+        window_start: 1_700_000_000,
+        window_end: 1_700_000_240,
+    }
+}
+
+/// Matching parameters are agreed on; the first differing one is named otherwise.
+#[test]
+fn test_negotiate() {
+    assert_eq!(negotiate(&params(), &params()), Ok(params()));
+    for (theirs, parameter) in [
+        (
+            EncodingParams {
+                units: Units::Kilometers,
+                ..params()
+            },
+            "units",
+        ),
+        (
+            EncodingParams {
+                cell_size: 5,
+                ..params()
+            },
+            "cell size",
+        ),
+        (
+            EncodingParams {
+                time_step_s: 30,
+                ..params()
+            },
+            "time step",
+        ),
+        (
+            EncodingParams {
+                window_end: 1_700_000_300,
+                ..params()
+            },
+            "window",
+        ),
+    ] {
+        match negotiate(&params(), &theirs) {
+            Err(ProtocolError::EncodingMismatch { parameter: p, .. }) => assert_eq!(p, parameter),
+            other => panic!("expected a {} mismatch, got {:?}", parameter, other),
+        }
+    }
+}
+
+/// The `Encoding` message goes through the session like any other and a mismatch stops
+/// the exchange.
+#[test]
+fn test_encoding_handshake() -> Result<(), Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&SessionMetadata::default())?;
+    let (mut evaluator, _) = Session::accept(&hello)?;
+
+    let message = owner.encoding(&params())?;
+    assert_eq!(evaluator.accept_encoding(&params(), &message)?, params());
+
+    let message = owner.encoding(&EncodingParams {
+        time_step_s: 10,
+        ..params()
+    })?;
+    let err = evaluator
+        .accept_encoding(&params(), &message)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("time step"), "{}", err);
+
+    // Anything else where the encoding is expected is refused.
+    let other = owner.send(
+        sat_trajectory_fhe::protocol::MessageKind::Results,
+        Vec::new(),
+    )?;
+    assert!(evaluator.accept_encoding(&params(), &other).is_err());
+    Ok(())
+}
+
+/// Trajectories are checked against the agreed steps and grid, and can be snapped to it.
+#[test]
+fn test_check_and_quantize() -> Result<(), Box<dyn std::error::Error>> {
+    let at = |v: i64| (BIAS + v) as u32;
+    let data = SatelliteData {
+        x: vec![at(0), at(14), at(-16), at(25)],
+        y: vec![at(10); 4],
+        z: vec![at(-20); 4],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let params = params();
+    assert_eq!(params.steps(), 4);
+    let epochs = params.epochs();
+    assert_eq!(epochs[3], 1_700_000_180);
+
+    assert!(params.check(&data, &epochs).is_err());
+    let quantized = params.quantize(&data);
+    assert_eq!(quantized.x, vec![at(0), at(10), at(-20), at(30)]);
+    params.check(&quantized, &epochs)?;
+
+    assert!(params.check(&quantized, &epochs[..3]).is_err());
+    let km = SatelliteData {
+        units: Units::Kilometers,
+        ..quantized.clone()
+    };
+    assert!(params.check(&km, &epochs).is_err());
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::fleet::Fleet;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::object_id::{Blinder, ObjectId};
+use sat_trajectory_fhe::protocol::SessionMetadata;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::Units;
+
+fn trajectory() -> SatelliteData {
+    SatelliteData {
+        x: vec![1, 2],
+        y: vec![3, 4],
+        z: vec![5, 6],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// Object IDs print and parse in their prefixed form.
+#[test]
+fn test_object_id_text() -> Result<(), Box<dyn std::error::Error>> {
+    let blinded = Blinder::from_secret([7; 32]).blind(&ObjectId::Norad(25544));
+    for id in [
+        ObjectId::Norad(25544),
+        ObjectId::from("cubesat:alpha"),
+        blinded,
+    ] {
+        assert_eq!(id.to_string().parse::<ObjectId>()?, id);
+    }
+    assert_eq!("norad:43013".parse::<ObjectId>()?, ObjectId::Norad(43013));
+    assert!("43013".parse::<ObjectId>().is_err());
+    assert!("norad:ISS".parse::<ObjectId>().is_err());
+    assert!("blinded:abcd".parse::<ObjectId>().is_err());
+    Ok(())
+}
+
+/// Blinded IDs are stable under one secret, differ between secrets, and resolve back.
+#[test]
+fn test_blinding() -> Result<(), Box<dyn std::error::Error>> {
+    let blinder = Blinder::from_secret([1; 32]);
+    let other = Blinder::generate()?;
+    let id = ObjectId::Norad(25544);
+    let blinded = blinder.blind(&id);
+    assert!(blinded.is_blinded());
+    assert_eq!(blinder.blind(&id), blinded);
+    assert_ne!(other.blind(&id), blinded);
+    assert_eq!(blinder.blind(&blinded), blinded);
+
+    let mut fleet = Fleet::new();
+    fleet.add(id.clone(), trajectory())?;
+    fleet.add("sat-b", trajectory())?;
+    let sent = fleet.blinded(&blinder);
+    assert!(sent.ids().all(ObjectId::is_blinded));
+    let resolved: Vec<_> = sent
+        .ids()
+        .map(|b| blinder.resolve(b, fleet.ids()).cloned())
+        .collect();
+    assert_eq!(resolved, [Some(id), Some(ObjectId::from("sat-b"))]);
+    assert_eq!(other.resolve(&blinded, fleet.ids()), None);
+    Ok(())
+}
+
+/// The Hello carries the owner's object ID to the evaluator.
+#[test]
+fn test_hello_object_id() -> Result<(), Box<dyn std::error::Error>> {
+    let blinder = Blinder::generate()?;
+    let metadata = SessionMetadata {
+        object_id: Some(blinder.blind(&ObjectId::Norad(25544))),
+        ..Default::default()
+    };
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&metadata)?;
+    let (_, received) = Session::accept(&hello)?;
+    assert_eq!(received.object_id, metadata.object_id);
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use tfhe::prelude::*;
+use tfhe::{ClientKey, ConfigBuilder, FheBool};
+
+use sat_trajectory_fhe::screening::{ScreeningOutcome, decrypt_results};
+
+/// The outcome summarises the decrypted flags.
+#[test]
+fn test_screening_outcome_summaries() {
+    let outcome = ScreeningOutcome::from_flags(vec![false, true, false, true]);
+    assert!(outcome.any);
+    assert_eq!(outcome.first_positive, Some(1));
+    assert_eq!(outcome.count, 2);
+    assert_eq!(outcome.flags, vec![false, true, false, true]);
+
+    assert_eq!(
+        ScreeningOutcome::from_flags(vec![false; 3]),
+        ScreeningOutcome {
+            flags: vec![false; 3],
+            ..Default::default()
+        }
+    );
+}
+
+/// Encrypted result flags decrypt into the same summary as their clear values.
+#[test]
+fn test_decrypt_results() -> Result<(), Box<dyn std::error::Error>> {
+    let client_key = ClientKey::generate(ConfigBuilder::default().build());
+    let flags = [false, false, true];
+    let encrypted = flags
+        .iter()
+        .map(|&flag| FheBool::try_encrypt(flag, &client_key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let outcome = decrypt_results(&encrypted, &client_key);
+    assert_eq!(outcome, ScreeningOutcome::from_flags(flags.to_vec()));
+    assert_eq!(outcome.first_positive, Some(2));
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use tfhe::prelude::*;
+use tfhe::{ConfigBuilder, generate_keys, set_server_key};
+
+use sat_trajectory_fhe::packing::{PackedCatalog, encrypt_replicated, pack, replicate, unpack};
+
+/// Plaintext packing helpers round-trip.
+#[test]
+fn test_pack_unpack() {
+    let word = pack(&[1, 2, 0xffff], 7).unwrap();
+    assert_eq!(unpack(word), [1, 2, 0xffff, 7]);
+    assert_eq!(
+        pack(&[1, 2, 3, 4, 5], 0).unwrap_err().to_string(),
+        "at most 4 cells per word, got 5"
+    );
+    assert_eq!(unpack(replicate(42)), [42; 4]);
+
+    let catalog = PackedCatalog::new(
+        &[vec![1, 2], vec![3, 4], vec![5, 6], vec![7, 8], vec![9, 10]],
+        0,
+    )
+    .unwrap();
+    assert_eq!(catalog.objects(), 5);
+    assert_eq!(catalog.steps(), 2);
+    assert_eq!(catalog.unpack_step(1), vec![2, 4, 6, 8, 10]);
+}
+
+/// One encrypted cell per step is screened against five catalog objects (two packed
+/// words); only the matching objects come back positive.
+#[tokio::test]
+async fn test_packed_catalog_screening() -> Result<(), Box<dyn std::error::Error>> {
+    // cells[object][step]
+    let catalog_cells = vec![
+        vec![10, 20],
+        vec![11, 21],
+        vec![12, 99],
+        vec![13, 23],
+        vec![99, 24],
+    ];
+    let owner_cells = [99u16, 99];
+
+    let config = ConfigBuilder::default().build();
+    let (client_key, server_key) = generate_keys(config);
+
+    let encrypted = encrypt_replicated(&owner_cells, &client_key)?;
+
+    set_server_key(server_key);
+    let catalog = PackedCatalog::new(&catalog_cells, 0)?;
+    let flags = catalog.screen(&encrypted)?;
+
+    let decrypted: Vec<Vec<bool>> = flags
+        .iter()
+        .map(|step| step.iter().map(|f| f.decrypt(&client_key)).collect())
+        .collect();
+    assert_eq!(
+        decrypted,
+        vec![
+            vec![false, false, false, false, true],
+            vec![false, false, true, false, false],
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use sat_trajectory_fhe::padding::{
+    EVALUATOR_SENTINEL, OWNER_SENTINEL, check_padded_len, pad, pad_epochs, strip,
+};
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::reveal::{RevealPolicy, Revealed};
+
+use common::diagonal_trajectory;
+
+/// Padding appends sentinel steps and continues the epoch cadence; stripping drops them.
+#[test]
+fn test_pad_and_strip() -> Result<(), Box<dyn std::error::Error>> {
+    let padded = pad(&diagonal_trajectory(vec![1, 2]), 4, OWNER_SENTINEL)?;
+    assert_eq!(padded.x, vec![1, 2, OWNER_SENTINEL, OWNER_SENTINEL]);
+    assert_eq!(padded.z, vec![201, 202, OWNER_SENTINEL, OWNER_SENTINEL]);
+    // Already long enough: unchanged.
+    assert_eq!(pad(&padded, 3, EVALUATOR_SENTINEL)?.x, padded.x);
+    // A length the allocator can't provide is an error, not an abort.
+    assert!(pad(&padded, usize::MAX, EVALUATOR_SENTINEL).is_err());
+    assert!(check_padded_len(4, 4).is_ok());
+    assert!(check_padded_len(5, 4).is_err());
+
+    assert_eq!(pad_epochs(&[100, 130], 4), vec![100, 130, 160, 190]);
+    assert_eq!(pad_epochs(&[], 2), vec![0, 1]);
+
+    let flags = [true, false, true, true];
+    assert_eq!(strip(&flags, 0, 2), &[true, false]);
+    // A window starting at step 1 keeps only its first step.
+    assert_eq!(strip(&flags[1..], 1, 2), &[false]);
+    assert!(strip(&flags, 5, 2).is_empty());
+    Ok(())
+}
+
+/// The evaluator sees a padded trajectory; the owner gets back flags for its real steps
+/// only, and decoys never count as collisions.
+#[tokio::test]
+async fn test_padded_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![10, 11, 12]))
+        .pad_to(6)
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    assert_eq!(encrypted.len(), 6);
+
+    let evaluator = PartyBuilder::new(diagonal_trajectory(vec![0, 11, 0, 13]))
+        .pad_to(6)
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let output = evaluator.evaluate(&encrypted)?;
+    assert_eq!(output.results.len(), 6);
+    assert_eq!(
+        owner.decrypt_unpadded(&output.results, 0),
+        vec![false, true, false]
+    );
+    let events = owner.decrypt_events(&output.results, &encrypted.epochs, 0)?;
+    assert_eq!(events.len(), 1);
+
+    let count = evaluator.evaluate_revealing(&encrypted, RevealPolicy::Count)?;
+    assert_eq!(owner.decrypt_revealed(&count.result)?, Revealed::Count(1));
+
+    // A trajectory longer than the agreed length can't be padded to it.
+    assert!(
+        PartyBuilder::new(diagonal_trajectory(vec![1; 7]))
+            .pad_to(6)
+            .owner()
+            .build()
+            .is_err()
+    );
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::time::Instant;
+
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::screening::{ScreeningConfig, screen_exact};
+use sat_trajectory_fhe::units::Units;
+
+/// Parallel per-axis evaluation gives the same flags as the sequential kernel.
+#[tokio::test]
+async fn test_parallel_axes_match_sequential() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![100, 101, 402],
+        y: vec![200, 501, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?;
+
+    let sequential = ScreeningConfig::default();
+    let parallel = ScreeningConfig {
+        parallel_axes: true,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let seq = context.evaluate_with(|| screen_exact(&encrypted, &sat2, &sequential))?;
+    println!("Sequential axes took: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let par = context.evaluate_with(|| screen_exact(&encrypted, &sat2, &parallel))?;
+    println!("Parallel axes took: {:?}", start.elapsed());
+
+    let seq: Vec<bool> = context.decrypt(&seq.results)?;
+    let par: Vec<bool> = context.decrypt(&par.results)?;
+    assert_eq!(seq, vec![true, false, false]);
+    assert_eq!(par, seq);
+
+    Ok(())
+}
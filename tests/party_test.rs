@@ -0,0 +1,49 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+/// Full owner -> evaluator -> owner round trip through the role-typed API, with a
+/// collision on index 1 only.
+#[tokio::test]
+async fn test_owner_evaluator_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![400, 101, 402],
+        y: vec![500, 201, 502],
+        z: vec![600, 301, 602],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    // Party A owns sat1.
+    let owner = PartyBuilder::new(sat1).owner().build()?;
+    let ser_trajectory = owner.encrypt_trajectory()?.to_bytes()?;
+    let ser_server_key = owner.server_key_bytes()?;
+
+    // Party B evaluates with A's server key against sat2.
+    let evaluator = PartyBuilder::new(sat2).evaluator(ser_server_key).build()?;
+    assert_eq!(
+        evaluator.server_key_fingerprint(),
+        owner.server_key_fingerprint()
+    );
+    println!("{:?}", evaluator);
+
+    let encrypted = EncryptedTrajectory::from_bytes(&ser_trajectory)?;
+    let output = evaluator.evaluate(&encrypted)?;
+    assert_eq!(output.ops.comparisons, 9);
+    assert_eq!(output.ops.depth, 3);
+
+    // Back at A.
+    let flags = owner.decrypt_results(&output.results);
+    assert_eq!(flags, vec![false, true, false]);
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::pipeline::screen_exact_pipelined;
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::units::Units;
+
+/// Screening straight from the serialized trajectory, with deserialization overlapped
+/// through a channel smaller than the trajectory.
+#[tokio::test]
+async fn test_pipelined_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102, 103],
+        y: vec![200, 201, 202, 203],
+        z: vec![300, 301, 302, 303],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![400, 101, 402, 103],
+        y: vec![500, 201, 502, 203],
+        z: vec![600, 301, 602, 303],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let bytes = context.encrypt(&sat1)?.to_bytes()?;
+
+    let output = context
+        .evaluate_with(|| screen_exact_pipelined(&bytes, &sat2, &ScreeningConfig::default(), 1))?;
+    let flags: Vec<bool> = context.decrypt(&output.results)?;
+    assert_eq!(flags, vec![false, true, false, true]);
+    assert_eq!(output.ops.comparisons, 12);
+
+    // Only exact match is pipelined; another kernel isn't silently swapped for it.
+    let box_threshold = ScreeningConfig {
+        kernel: KernelChoice::BoxThreshold { half_width: 1 },
+        ..Default::default()
+    };
+    assert!(
+        context
+            .evaluate_with(|| screen_exact_pipelined(&bytes, &sat2, &box_threshold, 1))
+            .is_err()
+    );
+
+    // A truncated blob is rejected before any work is done.
+    assert!(
+        screen_exact_pipelined(
+            &bytes[..bytes.len() / 2],
+            &sat2,
+            &ScreeningConfig::default(),
+            1
+        )
+        .is_err()
+    );
+
+    Ok(())
+}
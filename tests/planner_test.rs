@@ -0,0 +1,123 @@
+use std::time::Instant;
+
+use tfhe::prelude::*;
+use tfhe::{ConfigBuilder, generate_keys, set_server_key};
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::planner::{EvalPlan, Operand, plan, screen_planned};
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+/// Scalar and ciphertext plans agree on the result. Clear operands only take the scalar
+/// path with `constant_shape` turned off; the default config never picks it.
+#[tokio::test]
+async fn test_scalar_vs_ciphertext_plan() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![100, 401, 402],
+        y: vec![200, 501, 502],
+        z: vec![300, 601, 602],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let config = ConfigBuilder::default().build();
+    let (client_key, server_key) = generate_keys(config);
+    set_server_key(server_key);
+
+    let enc_sat1 = EncryptedTrajectory::encrypt(&sat1, &client_key)?;
+    let enc_sat2 = EncryptedTrajectory::encrypt(&sat2, &client_key)?;
+
+    let clear = Operand::Clear(&sat2);
+    let encrypted = Operand::Encrypted(&enc_sat2);
+    let scalar_config = ScreeningConfig {
+        constant_shape: false,
+        ..Default::default()
+    };
+    assert_eq!(plan(&clear, &scalar_config), EvalPlan::Scalar);
+    assert_eq!(
+        plan(&clear, &ScreeningConfig::default()),
+        EvalPlan::Ciphertext
+    );
+    assert_eq!(plan(&encrypted, &scalar_config), EvalPlan::Ciphertext);
+
+    let scalar = screen_planned(&enc_sat1, clear, &scalar_config)?;
+    let symmetric = screen_planned(&enc_sat1, encrypted, &scalar_config)?;
+
+    let decrypt = |flags: &[tfhe::FheBool]| -> Vec<bool> {
+        flags.iter().map(|f| f.decrypt(&client_key)).collect()
+    };
+    assert_eq!(decrypt(&scalar.results), vec![true, false, false]);
+    assert_eq!(decrypt(&symmetric.results), vec![true, false, false]);
+
+    Ok(())
+}
+
+/// Benchmark: the scalar plan screens a clear trajectory faster than comparing against
+/// trivially encrypted copies of it, which is what `constant_shape` (the default) does.
+/// Run with `cargo test --release --test planner_test -- --ignored --nocapture`.
+#[test]
+#[ignore = "benchmark"]
+fn bench_scalar_plan_speedup() -> Result<(), Box<dyn std::error::Error>> {
+    let steps = 32;
+    let owned = SatelliteData {
+        x: (0..steps).map(|i| 1_000 + i).collect(),
+        y: (0..steps).map(|i| 2_000 + i).collect(),
+        z: (0..steps).map(|i| 3_000 + i).collect(),
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let other = SatelliteData {
+        x: (0..steps).map(|i| 1_000 + 2 * i).collect(),
+        ..owned.clone()
+    };
+
+    let (client_key, server_key) = generate_keys(ConfigBuilder::default().build());
+    set_server_key(server_key);
+    let encrypted = EncryptedTrajectory::encrypt(&owned, &client_key)?;
+
+    let scalar_config = ScreeningConfig {
+        constant_shape: false,
+        ..Default::default()
+    };
+    let trivial_config = ScreeningConfig::default();
+    assert_eq!(
+        plan(&Operand::Clear(&other), &trivial_config),
+        EvalPlan::Ciphertext
+    );
+
+    let start = Instant::now();
+    let scalar = screen_planned(&encrypted, Operand::Clear(&other), &scalar_config)?;
+    let scalar_time = start.elapsed();
+
+    let start = Instant::now();
+    let trivial = screen_planned(&encrypted, Operand::Clear(&other), &trivial_config)?;
+    let trivial_time = start.elapsed();
+
+    println!(
+        "{} steps: scalar {:?}, trivially encrypted {:?} ({:.1}x)",
+        steps,
+        scalar_time,
+        trivial_time,
+        trivial_time.as_secs_f64() / scalar_time.as_secs_f64()
+    );
+    let decrypt = |flags: &[tfhe::FheBool]| -> Vec<bool> {
+        flags.iter().map(|f| f.decrypt(&client_key)).collect()
+    };
+    assert_eq!(decrypt(&scalar.results), decrypt(&trivial.results));
+    assert!(
+        scalar_time < trivial_time,
+        "scalar plan ({:?}) is not faster than the trivially encrypted one ({:?})",
+        scalar_time,
+        trivial_time
+    );
+    Ok(())
+}
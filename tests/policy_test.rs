@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::negotiation::EncodingParams;
+use sat_trajectory_fhe::policy::{KernelKind, Policies, PolicyProfile};
+use sat_trajectory_fhe::protocol::{ProtocolError, SessionMetadata};
+use sat_trajectory_fhe::reveal::RevealPolicy;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::Units;
+
+fn params() -> EncodingParams {
+    EncodingParams {
+        units: Units::Meters,
+        cell_size: 100,
+        time_step_s: 60,
+        window_start: 1_700_000_000,
+        window_end: 1_700_003_600,
+    }
+}
+
+fn guarded() -> PolicyProfile {
+    PolicyProfile {
+        kernels: vec![KernelKind::Exact, KernelKind::Box],
+        max_tolerance_m: Some(5_000.0),
+        reveals: vec![RevealPolicy::AnyFlag, RevealPolicy::Count],
+        min_resolution_m: Some(100.0),
+        min_time_step_s: Some(60),
+        max_horizon_s: Some(86_400),
+    }
+}
+
+fn rule(result: Result<(), ProtocolError>) -> &'static str {
+    match result {
+        Err(ProtocolError::PolicyViolation { rule, .. }) => rule,
+        other => panic!("expected a policy violation, got {:?}", other),
+    }
+}
+
+/// Each limit of a profile refuses the screenings past it and nothing else.
+#[test]
+fn test_profile_limits() {
+    let profile = guarded();
+    let allowed = SessionMetadata {
+        kernel: Some(KernelChoice::BoxThreshold { half_width: 5 }),
+        units: Some(Units::Kilometers),
+        reveal: Some(RevealPolicy::Count),
+        ..Default::default()
+    };
+    assert_eq!(profile.check_metadata(&allowed), Ok(()));
+    assert_eq!(profile.check_encoding(&params()), Ok(()));
+    // Unset limits allow anything.
+    let open = PolicyProfile::default();
+    assert_eq!(open.check_metadata(&SessionMetadata::default()), Ok(()));
+
+    let distance = SessionMetadata {
+        kernel: Some(KernelChoice::SquaredDistanceThreshold { threshold: 1 }),
+        ..allowed.clone()
+    };
+    assert_eq!(rule(profile.check_metadata(&distance)), "kernel");
+    let wide = SessionMetadata {
+        kernel: Some(KernelChoice::BoxThreshold { half_width: 6 }),
+        ..allowed.clone()
+    };
+    assert_eq!(rule(profile.check_metadata(&wide)), "tolerance");
+    // Per-step flags are the default reveal policy.
+    let per_index = SessionMetadata {
+        reveal: None,
+        ..allowed.clone()
+    };
+    assert_eq!(rule(profile.check_metadata(&per_index)), "reveal policy");
+
+    let fine = EncodingParams {
+        cell_size: 10,
+        ..params()
+    };
+    assert_eq!(rule(profile.check_encoding(&fine)), "resolution");
+    let fast = EncodingParams {
+        time_step_s: 30,
+        ..params()
+    };
+    assert_eq!(rule(profile.check_encoding(&fast)), "time step");
+    let long = EncodingParams {
+        window_end: 1_700_000_000 + 2 * 86_400,
+        ..params()
+    };
+    assert_eq!(rule(profile.check_encoding(&long)), "window");
+}
+
+/// Counterparts get their own profile, the default, or nothing.
+#[test]
+fn test_policies() {
+    let mut policies = Policies {
+        counterparts: BTreeMap::from([("Trusted Ops".to_string(), PolicyProfile::default())]),
+        default: None,
+    };
+    assert_eq!(
+        policies.profile("Trusted Ops"),
+        Ok(&PolicyProfile::default())
+    );
+    let err = policies.profile("Unknown Ops").unwrap_err();
+    assert!(err.to_string().contains("Unknown Ops"), "{}", err);
+
+    policies.default = Some(guarded());
+    assert_eq!(policies.profile("Unknown Ops"), Ok(&guarded()));
+}
+
+/// A session held to a profile refuses to announce or accept what it forbids.
+#[test]
+fn test_session_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?.with_policy(guarded());
+    let forbidden = SessionMetadata::default();
+    let err = owner.hello(&forbidden).unwrap_err().to_string();
+    assert!(err.contains("reveal policy"), "{}", err);
+
+    let allowed = SessionMetadata {
+        reveal: Some(RevealPolicy::AnyFlag),
+        ..Default::default()
+    };
+    let hello = owner.hello(&allowed)?;
+    let (mut evaluator, _) = Session::accept(&hello)?;
+
+    let fine = EncodingParams {
+        cell_size: 1,
+        ..params()
+    };
+    assert!(owner.encoding(&fine).is_err());
+    // An evaluator without a profile encodes as it likes, but the owner won't agree.
+    let message = evaluator.encoding(&fine)?;
+    let err = owner
+        .accept_encoding(&fine, &message)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("resolution"), "{}", err);
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use sat_trajectory_fhe::pool::{EvalPool, PoolFull};
+
+/// Work runs on the pool's own threads and its result comes back to the caller.
+#[tokio::test]
+async fn test_pool_runs_work_off_the_runtime() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = EvalPool::new(2, 4)?;
+    assert_eq!(pool.threads(), 2);
+    let caller = std::thread::current().id();
+    let outcome = pool.submit(move || (std::thread::current().id() != caller, 6 * 7))?;
+    assert_eq!(outcome.await?.unwrap(), (true, 42));
+    Ok(())
+}
+
+/// Once every thread is busy and the queue holds `queue` tasks, `submit` refuses more
+/// until a task is picked up.
+#[tokio::test]
+async fn test_pool_refuses_work_when_full() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = EvalPool::new(1, 1)?;
+    let (release, blocked) = mpsc::channel::<()>();
+    let (started, running) = mpsc::channel::<()>();
+    let first = pool.submit(move || {
+        started.send(()).unwrap();
+        blocked.recv().unwrap();
+        1
+    })?;
+    running.recv_timeout(Duration::from_secs(10))?;
+
+    let second = pool.submit(|| 2)?;
+    assert!(pool.is_full());
+    assert_eq!(pool.submit(|| 3).err(), Some(PoolFull { queued: 1 }));
+
+    release.send(())?;
+    assert_eq!(first.await?.unwrap(), 1);
+    assert_eq!(second.await?.unwrap(), 2);
+    assert!(!pool.is_full());
+    assert_eq!(pool.submit(|| 4)?.await?.unwrap(), 4);
+    Ok(())
+}
+
+/// A panicking task is reported to its caller and doesn't take its thread down.
+#[tokio::test]
+async fn test_pool_survives_panics() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = EvalPool::new(1, 1)?;
+    assert!(pool.submit(|| panic!("evaluation bug"))?.await?.is_err());
+    assert_eq!(
+        pool.submit(|| "still running")?.await?.unwrap(),
+        "still running"
+    );
+    Ok(())
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "catalog")]
+
+use sat_trajectory_fhe::catalog::{CatalogFilter, TimeGrid, parse_tle, screening_set};
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::precheck::{combined_report, precheck_catalog};
+use sat_trajectory_fhe::regime::AltitudeBand;
+use sat_trajectory_fhe::report::{SessionOutcome, Source};
+use sat_trajectory_fhe::units::Units;
+
+const TLES: &str = "\
+ISS (ZARYA)
+1 25544U 98067A   24001.50000000  .00016717  00000-0  10270-3 0  9005
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391428520
+GOES 16
+1 41866U 16071A   24001.50000000 -.00000262  00000-0  00000+0 0  9991
+2 41866   0.0518 269.7286 0001140 222.4290 254.6117  1.00271068 26171
+";
+
+/// Clear verdicts follow the encrypted kernels, including the distance cap.
+#[test]
+fn test_matches_clear() {
+    let a = [1_000, 1_000, 1_000];
+    assert!(KernelChoice::ExactMatch.matches_clear(a, a));
+    assert!(!KernelChoice::ExactMatch.matches_clear(a, [1_000, 1_000, 1_001]));
+
+    let boxed = KernelChoice::BoxThreshold { half_width: 5 };
+    assert!(boxed.matches_clear(a, [1_005, 995, 1_000]));
+    assert!(!boxed.matches_clear(a, [1_006, 1_000, 1_000]));
+
+    let distance = KernelChoice::SquaredDistanceThreshold { threshold: 5 };
+    assert!(distance.matches_clear(a, [1_003, 1_004, 1_000]));
+    assert!(!distance.matches_clear(a, [1_003, 1_004, 1_001]));
+    assert!(!distance.matches_clear([0; 3], [u32::MAX; 3]));
+
+    let band = KernelChoice::AltitudeBand { tolerance: 2 };
+    assert!(band.matches_clear(a, [1_002, 0, u32::MAX]));
+    assert!(!band.matches_clear(a, [997, 1_000, 1_000]));
+}
+
+/// The pre-check flags public objects in the clear and reports them with FHE outcomes.
+#[test]
+fn test_precheck_catalog() -> Result<(), Box<dyn std::error::Error>> {
+    let grid = TimeGrid {
+        start_unix_s: 1_704_110_400.0,
+        step_s: 60.0,
+        steps: 10,
+    };
+    let epochs: Vec<u64> = (0..grid.steps as u64)
+        .map(|i| grid.start_unix_s as u64 + i * 60)
+        .collect();
+    let catalog = screening_set(
+        &parse_tle(TLES)?,
+        &CatalogFilter {
+            band: AltitudeBand::new(0.0, 40_000.0)?,
+            inclination_deg: None,
+            inclination_tolerance_deg: 0.0,
+        },
+        &grid,
+        Units::Kilometers,
+        Frame::Eci,
+    )?;
+    assert_eq!(catalog.len(), 2);
+    // An owner flying right next to the ISS.
+    let mut owner = catalog[0].trajectory.clone();
+    owner.x.iter_mut().for_each(|x| *x += 3);
+
+    let kernel = KernelChoice::BoxThreshold { half_width: 5 };
+    let outcomes = precheck_catalog("SAT-A", &owner, &epochs, &catalog, kernel)?;
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].peer, "NORAD 25544 ISS (ZARYA)");
+    assert_eq!(outcomes[0].source, Source::Catalog);
+    assert_eq!(outcomes[0].events.len(), 1);
+    assert_eq!(outcomes[0].events[0].n_steps, 10);
+
+    let tight = KernelChoice::BoxThreshold { half_width: 2 };
+    assert!(precheck_catalog("SAT-A", &owner, &epochs, &catalog, tight)?.is_empty());
+    assert!(precheck_catalog("SAT-A", &owner, &epochs[1..], &catalog, kernel).is_err());
+    let metres = owner.to_units(Units::Meters)?;
+    assert!(precheck_catalog("SAT-A", &metres, &epochs, &catalog, kernel).is_err());
+
+    let private = SessionOutcome {
+        satellite: "SAT-A".to_string(),
+        peer: "op-1".to_string(),
+        events: vec![ConjunctionEvent {
+            start_index: 0,
+            start_epoch: epochs[0],
+            end_epoch: epochs[0],
+            n_steps: 1,
+        }],
+        source: Source::Encrypted,
+    };
+    let report = combined_report(&outcomes, &[private]);
+    println!("{}", report);
+    let events = &report.satellite("SAT-A").unwrap().events;
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].peer, "op-1");
+    assert_eq!(events[0].source, Source::Encrypted);
+    assert_eq!(events[1].source, Source::Catalog);
+    assert!(report.to_string().contains("[public catalog]"));
+    Ok(())
+}
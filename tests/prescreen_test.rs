@@ -0,0 +1,116 @@
+mod common;
+
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::prescreen::{BloomFilter, CellFilter, CellGrid, session_salt};
+use sat_trajectory_fhe::protocol::SessionMetadata;
+use sat_trajectory_fhe::screening::exact_match_cost;
+use sat_trajectory_fhe::session::Session;
+
+use common::trajectory;
+
+const GRID: CellGrid = CellGrid {
+    cell_size: 100,
+    steps_per_bucket: 2,
+    neighbors: false,
+};
+
+/// Inserted items are always found; unrelated ones rarely.
+#[test]
+fn test_bloom_filter() {
+    let mut filter = BloomFilter::with_capacity(100);
+    for i in 0..100u32 {
+        filter.insert(&i.to_le_bytes());
+    }
+    assert!((0..100u32).all(|i| filter.contains(&i.to_le_bytes())));
+    let false_positives = (1000..11000u32)
+        .filter(|i| filter.contains(&i.to_le_bytes()))
+        .count();
+    assert!(false_positives < 300, "{} false positives", false_positives);
+}
+
+/// Only steps sharing a (time bucket, voxel) cell with the other trajectory are
+/// candidates; neighbors catch positions across a cell boundary.
+#[test]
+fn test_candidates() -> Result<(), Box<dyn std::error::Error>> {
+    let salt = session_salt(&[7; 16]);
+    let owner = trajectory(vec![1010, 2020, 3030, 4040, 5050, 6060]);
+    let evaluator = trajectory(vec![1090, 9000, 3001, 9000, 9000, 5990]);
+
+    let filter = CellFilter::build(&owner, GRID, &salt);
+    assert_eq!(filter.candidates(&evaluator, &salt)?, vec![0, 2]);
+    // The same position two buckets later is a different cell.
+    assert!(
+        filter
+            .candidates(&trajectory(vec![9000, 9000, 9000, 9000, 1010, 9000]), &salt)?
+            .is_empty()
+    );
+
+    let wide = CellFilter::build(
+        &owner,
+        CellGrid {
+            neighbors: true,
+            ..GRID
+        },
+        &salt,
+    );
+    assert_eq!(wide.candidates(&evaluator, &salt)?, vec![0, 2, 5]);
+
+    // Filters from another session don't match.
+    let other = CellFilter::build(&owner, GRID, &session_salt(&[8; 16]));
+    assert!(other.candidates(&evaluator, &salt)?.len() < 2);
+
+    Ok(())
+}
+
+/// Filters travel in session envelopes, and both sides derive the same salt.
+#[test]
+fn test_filter_exchange() -> Result<(), Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&SessionMetadata::default())?;
+    let (mut evaluator, _) = Session::accept(&hello)?;
+    assert_eq!(owner.cell_salt(), evaluator.cell_salt());
+
+    let filter = CellFilter::build(&trajectory(vec![1, 2, 3]), GRID, &owner.cell_salt());
+    let message = owner.cell_filter(&filter)?;
+    assert_eq!(evaluator.accept_cell_filter(&message)?, filter);
+    assert_eq!(owner.transcript(), evaluator.transcript());
+
+    let mut truncated = filter.to_bytes()?;
+    truncated.truncate(truncated.len() - 9);
+    assert!(CellFilter::from_bytes(&truncated).is_err());
+    Ok(())
+}
+
+/// The pre-screened evaluation flags the same steps as a full one while running FHE on
+/// the candidate steps only.
+#[tokio::test]
+async fn test_evaluate_prescreened() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(trajectory(vec![1010, 2020, 3030, 4040, 5050, 6060]))
+        .owner()
+        .build()?;
+    let evaluator = PartyBuilder::new(trajectory(vec![1010, 9000, 3001, 9000, 9000, 6060]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let salt = session_salt(&[1; 16]);
+
+    let candidates = owner.prescreen_candidates(&evaluator.cell_filter(GRID, &salt), &salt)?;
+    assert_eq!(candidates, vec![0, 2, 5]);
+
+    let encrypted = owner.encrypt_trajectory()?;
+    let prescreened =
+        evaluator.evaluate_prescreened(&encrypted, &owner.cell_filter(GRID, &salt), &salt)?;
+    let full = evaluator.evaluate(&encrypted)?;
+    assert_eq!(
+        owner.decrypt_results(&prescreened.results),
+        owner.decrypt_results(&full.results)
+    );
+    assert_eq!(
+        owner.decrypt_results(&prescreened.results),
+        vec![true, false, false, false, false, true]
+    );
+    assert_eq!(
+        prescreened.ops.comparisons,
+        3 * exact_match_cost().comparisons
+    );
+    Ok(())
+}
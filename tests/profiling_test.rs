@@ -0,0 +1,42 @@
+#![cfg(feature = "profiling")]
+
+use std::time::Duration;
+
+use sat_trajectory_fhe::profiling::{record, span};
+
+fn busy(time: Duration) {
+    let started = std::time::Instant::now();
+    while started.elapsed() < time {}
+}
+
+/// Nested spans fold into one line per stack with their own time, children excluded,
+/// and nothing is recorded outside `record`.
+#[test]
+fn test_record_folds_nested_spans() {
+    span("outside", || busy(Duration::from_millis(1)));
+    let (value, profile) = record("screen", || {
+        for _ in 0..2 {
+            span("box", || {
+                span("ge", || busy(Duration::from_millis(5)));
+                span("and", || busy(Duration::from_millis(1)));
+            });
+        }
+        7
+    });
+    assert_eq!(value, 7);
+    let stacks: Vec<&str> = profile.stacks.keys().map(String::as_str).collect();
+    assert_eq!(
+        stacks,
+        ["screen", "screen;box", "screen;box;and", "screen;box;ge"]
+    );
+    assert!(profile.time_in("ge") >= Duration::from_millis(10));
+    assert!(profile.time_in("box") < Duration::from_millis(5));
+    assert!(profile.total() >= Duration::from_millis(12));
+
+    let folded = profile.to_folded();
+    assert_eq!(folded.lines().count(), 4);
+    assert!(folded.lines().all(|line| {
+        let (stack, nanos) = line.rsplit_once(' ').unwrap();
+        stack.starts_with("screen") && nanos.parse::<u64>().is_ok()
+    }));
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "progress")]
+
+use std::time::Duration;
+
+use sat_trajectory_fhe::progress::eta;
+
+/// The calibrated estimate drives the ETA before any step is done, the measured rate once
+/// most are, and a mix of both in between.
+#[test]
+fn test_eta_blends_estimate_with_measured_rate() {
+    let estimate = Some(Duration::from_secs(100));
+    assert_eq!(eta(estimate, 0, 100, Duration::ZERO), estimate);
+    assert_eq!(eta(None, 0, 100, Duration::from_secs(3)), None);
+    assert_eq!(
+        eta(None, 25, 100, Duration::from_secs(10)),
+        Some(Duration::from_secs(30))
+    );
+
+    // Half done in 100 s, twice as slow as estimated: 50 s estimated and 100 s measured
+    // left, weighted equally.
+    let left = eta(estimate, 50, 100, Duration::from_secs(100)).unwrap();
+    assert!((left.as_secs_f64() - 75.0).abs() < 1e-6);
+
+    assert_eq!(
+        eta(estimate, 100, 100, Duration::from_secs(7)),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(eta(None, 0, 0, Duration::ZERO), Some(Duration::ZERO));
+}
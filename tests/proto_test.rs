@@ -0,0 +1,129 @@
+#![cfg(feature = "proto")]
+
+use prost::Message;
+use tfhe::ConfigBuilder;
+use tfhe::prelude::*;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::KernelChoice;
+use sat_trajectory_fhe::mask::StepMask;
+use sat_trajectory_fhe::object_id::ObjectId;
+use sat_trajectory_fhe::proto;
+use sat_trajectory_fhe::protocol::{Envelope, MessageKind, SessionMetadata};
+use sat_trajectory_fhe::regime::AltitudeBand;
+use sat_trajectory_fhe::reveal::RevealPolicy;
+use sat_trajectory_fhe::time_system::TimeSystem;
+use sat_trajectory_fhe::units::Units;
+
+/// Envelopes encode exactly as `proto/sat_fhe.proto` prescribes and convert back.
+#[test]
+fn test_envelope_wire_format() -> Result<(), Box<dyn std::error::Error>> {
+    let envelope = Envelope {
+        nonce: [1; 16],
+        seq: 2,
+        kind: MessageKind::Results,
+        payload: b"ok".to_vec(),
+        checksum: [4; 32],
+    };
+    let bytes = proto::Envelope::from(&envelope).encode_to_vec();
+
+    let mut expected = vec![0x0a, 16];
+    expected.extend([1; 16]);
+    expected.extend([0x10, 2, 0x18, 3, 0x22, 2, b'o', b'k', 0x2a, 32]);
+    expected.extend([4; 32]);
+    assert_eq!(bytes, expected);
+
+    let decoded = Envelope::try_from(proto::Envelope::decode(bytes.as_slice())?)?;
+    assert_eq!(decoded, envelope);
+
+    // Malformed fields are rejected rather than guessed.
+    let short_nonce = proto::Envelope {
+        nonce: vec![1; 4],
+        ..proto::Envelope::from(&envelope)
+    };
+    assert!(Envelope::try_from(short_nonce).is_err());
+    let short_checksum = proto::Envelope {
+        checksum: vec![4; 31],
+        ..proto::Envelope::from(&envelope)
+    };
+    assert!(Envelope::try_from(short_checksum).is_err());
+    let unknown_kind = proto::Envelope {
+        kind: 42,
+        ..proto::Envelope::from(&envelope)
+    };
+    assert!(Envelope::try_from(unknown_kind).is_err());
+    Ok(())
+}
+
+/// Session metadata survives the protobuf round trip, unset fields included.
+#[test]
+fn test_screening_request_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    for metadata in [
+        SessionMetadata::default(),
+        SessionMetadata {
+            altitude_band: Some(AltitudeBand::new(400.0, 430.0)?),
+            server_key_fingerprint: Some("0123456789abcdef".to_string()),
+            frame: Some(Frame::Ecef),
+            units: Some(Units::Kilometers),
+            reveal: Some(RevealPolicy::Count),
+            padded_len: Some(1024),
+            kernel: Some(KernelChoice::BoxThreshold { half_width: 5 }),
+            mask: Some(StepMask::new([3..5, 100..200])),
+            object_id: Some(ObjectId::Label("sentinel-2b".to_string())),
+            time_system: Some(TimeSystem::Tai),
+        },
+    ] {
+        let bytes = proto::ScreeningRequest::from(&metadata).encode_to_vec();
+        let decoded = proto::ScreeningRequest::decode(bytes.as_slice())?;
+        assert_eq!(SessionMetadata::try_from(decoded)?, metadata);
+    }
+    Ok(())
+}
+
+/// Keys, trajectories and results go through protobuf and still evaluate and decrypt.
+#[tokio::test]
+async fn test_fhe_artifacts_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = FheContext::generate(ConfigBuilder::default().build())?;
+    let sat = SatelliteData {
+        x: vec![100, 101],
+        y: vec![200, 201],
+        z: vec![300, 301],
+        frame: Frame::Eci,
+        units: Units::Kilometers,
+    };
+    let encrypted = owner.encrypt(&sat)?.with_epochs(vec![10, 20])?;
+
+    let key = proto::ServerKey::from_key(owner.server_key())?;
+    let key = proto::ServerKey::decode(key.encode_to_vec().as_slice())?;
+    let evaluator = FheContext::from_server_key(key.to_key()?.into_inner())?;
+    let tampered = proto::ServerKey {
+        fingerprint: "0000000000000000".to_string(),
+        ..key
+    };
+    assert!(tampered.to_key().is_err());
+
+    let bundle = proto::TrajectoryBundle::from_trajectory(&encrypted)?;
+    let bundle = proto::TrajectoryBundle::decode(bundle.encode_to_vec().as_slice())?;
+    let received = bundle.to_trajectory()?;
+    assert_eq!(received.epochs, vec![10, 20]);
+    assert_eq!(received.units, Units::Kilometers);
+    assert_eq!(owner.decrypt::<_, u32>(&received.x)?, vec![100, 101]);
+
+    let flags = evaluator
+        .evaluate_with(|| Ok::<_, Box<dyn std::error::Error>>(received.x[0].eq(100u32)))?;
+    let results = proto::ScreeningResults::from_results(&[flags])?;
+    let results = proto::ScreeningResults::decode(results.encode_to_vec().as_slice())?;
+    assert_eq!(
+        owner.decrypt::<_, bool>(&results.to_results()?)?,
+        vec![true]
+    );
+
+    let truncated = proto::TrajectoryBundle {
+        epochs: vec![10],
+        ..bundle
+    };
+    assert!(truncated.to_trajectory().is_err());
+    Ok(())
+}
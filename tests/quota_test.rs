@@ -0,0 +1,60 @@
+#![cfg(feature = "serve")]
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use sat_trajectory_fhe::quota::{QuotaConfig, QuotaError, QuotaTracker};
+
+const DAY: u64 = 86_400;
+
+/// Without limits everything is accepted.
+#[test]
+fn test_unlimited_by_default() {
+    let tracker = QuotaTracker::new(QuotaConfig::default());
+    let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    assert_eq!(tracker.check_jobs(1000), Ok(()));
+    assert_eq!(tracker.charge_steps(client, 1_000_000, 0), Ok(()));
+}
+
+/// Length, job and daily limits reject with the numbers that were exceeded.
+#[test]
+fn test_limits_reject_informatively() {
+    let tracker = QuotaTracker::new(QuotaConfig {
+        max_trajectory_steps: Some(100),
+        max_concurrent_jobs: Some(2),
+        daily_step_budget: Some(150),
+    });
+    let alice = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let bob = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+    assert_eq!(tracker.check_jobs(1), Ok(()));
+    assert_eq!(
+        tracker.check_jobs(2),
+        Err(QuotaError::TooManyJobs { active: 2, max: 2 })
+    );
+
+    assert_eq!(
+        tracker.charge_steps(alice, 101, 0),
+        Err(QuotaError::TrajectoryTooLong {
+            steps: 101,
+            max: 100
+        })
+    );
+    // Checking the length alone charges nothing.
+    assert_eq!(tracker.check_steps(100), Ok(()));
+    assert!(tracker.check_steps(101).is_err());
+    assert_eq!(tracker.charge_steps(alice, 100, 0), Ok(()));
+    let err = tracker.charge_steps(alice, 60, 10).unwrap_err();
+    assert_eq!(
+        err,
+        QuotaError::DailyBudgetExceeded {
+            used: 100,
+            requested: 60,
+            budget: 150
+        }
+    );
+    assert!(err.to_string().contains("100 of 150"));
+
+    // Budgets are per client and reset at the next UTC day.
+    assert_eq!(tracker.charge_steps(bob, 100, 10), Ok(()));
+    assert_eq!(tracker.charge_steps(alice, 60, DAY), Ok(()));
+}
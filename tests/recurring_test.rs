@@ -0,0 +1,23 @@
+use std::time::{Duration, Instant};
+
+use sat_trajectory_fhe::recurring::Recurrence;
+
+/// A run is due once the cadence has elapsed and only if the ephemerides changed.
+#[test]
+fn test_recurrence_due() {
+    let start = Instant::now();
+    let hour = Duration::from_secs(3600);
+    let mut recurrence = Recurrence::new(8 * hour, start, "a".to_string());
+
+    assert!(!recurrence.due(start + hour, "b"));
+    assert!(!recurrence.due(start + 8 * hour, "a"));
+    assert!(recurrence.due(start + 8 * hour, "b"));
+
+    // Ephemerides that arrive late start a run as soon as they're seen.
+    assert!(!recurrence.due(start + 20 * hour, "a"));
+    assert!(recurrence.due(start + 20 * hour, "b"));
+    recurrence.started(start + 20 * hour, "b");
+    assert_eq!(recurrence.next_run, start + 28 * hour);
+    assert!(!recurrence.due(start + 28 * hour, "b"));
+    assert!(recurrence.due(start + 28 * hour, "c"));
+}
@@ -0,0 +1,34 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::redact::{PrivateTrajectory, fingerprint};
+use sat_trajectory_fhe::units::Units;
+
+/// Debug/Display of a private trajectory must never contain the coordinates.
+#[test]
+fn test_private_trajectory_debug_is_redacted() {
+    let sat = PrivateTrajectory::new(SatelliteData {
+        x: vec![123456, 101, 102],
+        y: vec![234567, 201, 202],
+        z: vec![345678, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    });
+
+    let debug = format!("{:?}", sat);
+    let display = format!("{}", sat);
+
+    for secret in ["123456", "234567", "345678"] {
+        assert!(!debug.contains(secret), "Debug leaked {}", secret);
+        assert!(!display.contains(secret), "Display leaked {}", secret);
+    }
+    assert!(debug.contains("3 steps"));
+    assert!(debug.contains(&sat.fingerprint()));
+}
+
+/// Fingerprints are stable and distinguish different inputs.
+#[test]
+fn test_fingerprint() {
+    assert_eq!(fingerprint(b"abc"), fingerprint(b"abc"));
+    assert_ne!(fingerprint(b"abc"), fingerprint(b"abd"));
+    assert_eq!(fingerprint(b"abc").len(), 16);
+}
@@ -0,0 +1,82 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::regime::{AltitudeBand, OrbitalRegime, prefilter, radial_profile};
+use sat_trajectory_fhe::units::Units;
+
+/// Objects whose altitude range can't reach the declared band are dropped.
+#[test]
+fn test_prefilter_by_band() -> Result<(), Box<dyn std::error::Error>> {
+    let declared = AltitudeBand::new(500.0, 550.0)?;
+    // (name, perigee, apogee)
+    let objects = [
+        ("leo-low", 300.0, 350.0),
+        ("leo-near", 555.0, 600.0),
+        ("leo-crossing", 200.0, 1_200.0),
+        ("geo", 35_780.0, 35_790.0),
+    ];
+
+    let kept = prefilter(&declared, 10.0, &objects, |o| {
+        AltitudeBand::of_orbit(o.1, o.2).unwrap()
+    })?;
+    let names: Vec<&str> = kept.iter().map(|o| o.0).collect();
+    assert_eq!(names, vec!["leo-near", "leo-crossing"]);
+
+    assert!(AltitudeBand::new(10.0, 5.0).is_err());
+    assert!(
+        prefilter(&declared, -100.0, &objects, |o| {
+            AltitudeBand::of_orbit(o.1, o.2).unwrap()
+        })
+        .is_err()
+    );
+    Ok(())
+}
+
+/// Non-finite and inverted bands are refused on construction and on deserialization.
+#[test]
+fn test_band_validation() -> Result<(), Box<dyn std::error::Error>> {
+    assert!(AltitudeBand::new(f64::NAN, 10.0).is_err());
+    assert!(AltitudeBand::of_orbit(400.0, f64::INFINITY).is_err());
+    assert_eq!(
+        AltitudeBand::of_orbit(430.0, 400.0)?,
+        AltitudeBand::new(400.0, 430.0)?
+    );
+
+    let band = AltitudeBand::new(400.0, 430.0)?;
+    let bytes = bincode::serialize(&band)?;
+    assert_eq!(bincode::deserialize::<AltitudeBand>(&bytes)?, band);
+
+    let inverted = bincode::serialize(&(430.0f64, 400.0f64))?;
+    assert!(bincode::deserialize::<AltitudeBand>(&inverted).is_err());
+    let nan = bincode::serialize(&(f64::NAN, 400.0f64))?;
+    assert!(bincode::deserialize::<AltitudeBand>(&nan).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_regime_classification() {
+    let classify = |p, a| OrbitalRegime::classify(&AltitudeBand::of_orbit(p, a).unwrap());
+    assert_eq!(classify(400.0, 420.0), OrbitalRegime::Leo);
+    assert_eq!(classify(20_100.0, 20_300.0), OrbitalRegime::Meo);
+    assert_eq!(classify(35_780.0, 35_795.0), OrbitalRegime::Geo);
+    assert_eq!(classify(250.0, 35_786.0), OrbitalRegime::Heo);
+}
+
+/// The radial profile buckets the distance from the origin, whatever the direction.
+#[test]
+fn test_radial_profile() -> Result<(), Box<dyn std::error::Error>> {
+    let data = SatelliteData::encode(
+        &[
+            [6_771.0, 0.0, 0.0],
+            [0.0, -6_771.4, 0.0],
+            [3_000.0, 4_000.0, 0.0],
+        ],
+        Units::Kilometers,
+        Frame::Eci,
+    )?;
+    let profile = radial_profile(&data, 10_000);
+    assert_eq!(profile.x, vec![677, 677, 500]);
+    assert_eq!(profile.y, vec![0; 3]);
+    assert_eq!(profile.z, vec![0; 3]);
+    assert_eq!(profile.units, data.units);
+    Ok(())
+}
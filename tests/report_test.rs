@@ -0,0 +1,61 @@
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::report::{GroupReport, SessionOutcome, Source};
+
+fn event(start_epoch: u64, end_epoch: u64) -> ConjunctionEvent {
+    ConjunctionEvent {
+        start_index: 0,
+        start_epoch,
+        end_epoch,
+        n_steps: 1,
+    }
+}
+
+fn outcome(satellite: &str, peer: &str, events: Vec<ConjunctionEvent>) -> SessionOutcome {
+    SessionOutcome {
+        satellite: satellite.to_string(),
+        peer: peer.to_string(),
+        events,
+        source: Source::Encrypted,
+    }
+}
+
+/// Outcomes of many sessions merge into one ranked, deduplicated list per satellite.
+#[test]
+fn test_group_report() {
+    let report = GroupReport::aggregate(&[
+        outcome("sat-a", "op-1", vec![event(600, 700), event(2000, 2000)]),
+        // Overlapping screening window against the same peer: same conjunction.
+        outcome("sat-a", "op-1", vec![event(650, 800)]),
+        // Same time, different peer: a different object.
+        outcome("sat-a", "op-2", vec![event(600, 700)]),
+        outcome("sat-a", "op-3", vec![event(100, 200)]),
+        outcome("sat-b", "op-1", vec![]),
+        outcome("sat-c", "op-2", vec![event(50, 60)]),
+    ]);
+    println!("{}", report);
+
+    assert_eq!(report.satellites.len(), 2);
+    assert!(report.satellite("sat-b").is_none());
+
+    let sat_a = report.satellite("sat-a").unwrap();
+    let ranked: Vec<_> = sat_a
+        .events
+        .iter()
+        .map(|e| (e.peer.as_str(), e.tca_epoch(), e.sessions))
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            ("op-3", 150, 1),
+            ("op-2", 650, 1),
+            ("op-1", 700, 2),
+            ("op-1", 2000, 1)
+        ]
+    );
+    assert_eq!(
+        (sat_a.events[2].start_epoch, sat_a.events[2].end_epoch),
+        (600, 800)
+    );
+
+    assert_eq!(GroupReport::aggregate(&[]).to_string(), "no conjunctions");
+}
@@ -0,0 +1,48 @@
+use tfhe::prelude::*;
+use tfhe::{ConfigBuilder, FheBool, generate_keys, set_server_key};
+
+use sat_trajectory_fhe::common::{SerializationLimits, set_serialization_limits};
+use sat_trajectory_fhe::inspect::inspect;
+use sat_trajectory_fhe::result_bundle::ResultBundle;
+use sat_trajectory_fhe::screening::results_to_bytes;
+
+/// A bundle round-trips with its first index, compressed or not, and frames the flags
+/// with less overhead than serializing them one by one.
+#[tokio::test]
+async fn test_result_bundle_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let (client_key, server_key) = generate_keys(ConfigBuilder::default().build());
+    set_server_key(server_key);
+    let values = [false, true, true, false, false];
+    let flags: Vec<FheBool> = values
+        .iter()
+        .map(|&v| FheBool::encrypt(v, &client_key))
+        .collect();
+    let decrypt = |flags: Vec<FheBool>| -> Vec<bool> {
+        flags.iter().map(|f| f.decrypt(&client_key)).collect()
+    };
+
+    let plain = ResultBundle::new(40, flags.clone()).to_bytes()?;
+    assert!(plain.len() < results_to_bytes(&flags)?.len());
+    let bundle = ResultBundle::from_bytes(&plain)?;
+    assert_eq!((bundle.first_index, bundle.len()), (40, 5));
+    assert!(!bundle.is_compressed());
+    assert_eq!(decrypt(bundle.into_flags()), values);
+
+    let compressed = ResultBundle::compressed(40, &flags).to_bytes()?;
+    assert!(compressed.len() < plain.len());
+    let bundle = ResultBundle::from_bytes(&compressed)?;
+    assert!(bundle.is_compressed());
+    assert_eq!(decrypt(bundle.into_flags()), values);
+
+    let info = inspect(&compressed)?;
+    assert_eq!(info.get("flags"), Some("5"));
+    assert_eq!(info.get("compressed"), Some("true"));
+
+    set_serialization_limits(SerializationLimits {
+        result_bundle: compressed.len() as u64 - 1,
+        ..Default::default()
+    });
+    assert!(ResultBundle::from_bytes(&compressed).is_err());
+    set_serialization_limits(SerializationLimits::default());
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::{MessageKind, SessionMetadata};
+use sat_trajectory_fhe::reveal::{
+    Aggregation, RevealPolicy, Revealed, RevealedResult, aggregation_bootstraps, reveal_cost,
+    reveal_cost_with,
+};
+use sat_trajectory_fhe::screening::ScreeningConfig;
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::units::Units;
+
+/// Each policy releases only its aggregate, and the owner decrypts the right value.
+#[tokio::test]
+async fn test_reveal_policies() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102, 103, 104],
+        y: vec![200, 201, 202, 203, 204],
+        z: vec![300, 301, 302, 303, 304],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![100, 0, 102, 0, 0],
+        ..sat1.clone()
+    };
+
+    let owner = PartyBuilder::new(sat1).owner().build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let evaluator = PartyBuilder::new(sat2)
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+
+    for (policy, expected) in [
+        (
+            RevealPolicy::PerIndex,
+            Revealed::PerIndex(vec![true, false, true, false, false]),
+        ),
+        (RevealPolicy::AnyFlag, Revealed::AnyFlag(true)),
+        (RevealPolicy::Count, Revealed::Count(2)),
+    ] {
+        let output = evaluator.evaluate_revealing(&encrypted, policy)?;
+        assert_eq!(output.result.policy(), policy);
+        assert_eq!(output.ops.depth, 3 + reveal_cost(policy, 5).depth);
+
+        // What goes over the wire decodes to the same aggregate.
+        let received = RevealedResult::from_bytes(&output.result.to_bytes()?)?;
+        assert_eq!(owner.decrypt_revealed(&received)?, expected);
+    }
+    Ok(())
+}
+
+/// Aggregation cost grows with a log-depth reduction tree.
+#[test]
+fn test_reveal_cost() {
+    assert_eq!(reveal_cost(RevealPolicy::PerIndex, 100).total(), 0);
+    let any = reveal_cost(RevealPolicy::AnyFlag, 5);
+    assert_eq!((any.boolean, any.depth), (4, 3));
+    let count = reveal_cost(RevealPolicy::Count, 8);
+    assert_eq!((count.arithmetic, count.depth), (7, 3));
+    assert_eq!(reveal_cost(RevealPolicy::Count, 1).depth, 0);
+}
+
+/// Short screenings are ORed, long ones summed, unless the config says otherwise.
+#[test]
+fn test_aggregation_selection() {
+    let preset = ParameterPreset::Default;
+    assert_eq!(Aggregation::Auto.select(5, preset), Aggregation::OrTree);
+    assert_eq!(Aggregation::Auto.select(1_000, preset), Aggregation::Sum);
+    assert_eq!(
+        Aggregation::OrTree.select(1_000, preset),
+        Aggregation::OrTree
+    );
+    assert_eq!(Aggregation::Sum.select(5, preset), Aggregation::Sum);
+
+    // 15 flags fit a block's carry space; a 16-bit sum has 8 blocks.
+    assert_eq!(
+        aggregation_bootstraps(Aggregation::OrTree, 1_000, preset),
+        999
+    );
+    assert_eq!(
+        aggregation_bootstraps(Aggregation::Sum, 1_000, preset),
+        67 * 8 + 8
+    );
+    assert_eq!(
+        aggregation_bootstraps(Aggregation::Auto, 1_000, preset),
+        aggregation_bootstraps(Aggregation::Sum, 1_000, preset)
+    );
+
+    let sum = reveal_cost_with(RevealPolicy::AnyFlag, 8, Aggregation::Sum);
+    assert_eq!((sum.arithmetic, sum.comparisons, sum.depth), (7, 1, 4));
+    assert_eq!(reveal_cost(RevealPolicy::AnyFlag, 1_000).arithmetic, 999);
+}
+
+/// Both aggregations release the same "any step matched" flag.
+#[tokio::test]
+async fn test_aggregations_agree() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let owner = PartyBuilder::new(sat1.clone()).owner().build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    for (other, expected) in [(vec![0, 101, 0], true), (vec![0, 0, 0], false)] {
+        for aggregation in [Aggregation::OrTree, Aggregation::Sum] {
+            let evaluator = PartyBuilder::new(SatelliteData {
+                x: other.clone(),
+                ..sat1.clone()
+            })
+            .screening(ScreeningConfig {
+                aggregation,
+                ..Default::default()
+            })
+            .evaluator(owner.server_key_bytes()?)
+            .build()?;
+            let output = evaluator.evaluate_revealing(&encrypted, RevealPolicy::AnyFlag)?;
+            assert_eq!(
+                owner.decrypt_revealed(&output.result)?,
+                Revealed::AnyFlag(expected)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The reveal policy is part of the session transcript both sides compute.
+#[test]
+fn test_policy_in_transcript() -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = |policy| SessionMetadata {
+        reveal: Some(policy),
+        ..Default::default()
+    };
+
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&metadata(RevealPolicy::AnyFlag))?;
+    let (mut evaluator, received) = Session::accept(&hello)?;
+    assert_eq!(received.reveal, Some(RevealPolicy::AnyFlag));
+
+    let results = evaluator.send(MessageKind::Results, b"aggregate".to_vec())?;
+    owner.receive(&results)?;
+    assert_eq!(owner.transcript(), evaluator.transcript());
+
+    // Same nonce, different declared policy: the transcripts diverge.
+    let mut other = Session::join(owner.nonce());
+    other.hello(&metadata(RevealPolicy::PerIndex))?;
+    let mut reference = Session::join(owner.nonce());
+    reference.hello(&metadata(RevealPolicy::AnyFlag))?;
+    assert_ne!(other.transcript(), reference.transcript());
+
+    Ok(())
+}
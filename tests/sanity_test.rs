@@ -0,0 +1,64 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::sanity::{SanityLimits, Severity, check_trajectory};
+use sat_trajectory_fhe::units::Units;
+
+// A circular low orbit sampled every 60 s.
+fn leo(steps: usize) -> Result<SatelliteData, Box<dyn std::error::Error>> {
+    let radius = 6_771_000.0;
+    let rate = 7_670.0 / radius;
+    let positions: Vec<[f64; 3]> = (0..steps)
+        .map(|i| {
+            let angle = rate * 60.0 * i as f64;
+            [radius * angle.cos(), radius * angle.sin(), 0.0]
+        })
+        .collect();
+    SatelliteData::encode(&positions, Units::Meters, Frame::Eci)
+}
+
+/// A real orbit passes; bad epochs, uneven steps and impossible jumps are reported at the
+/// step they happen.
+#[test]
+fn test_trajectory_sanity() -> Result<(), Box<dyn std::error::Error>> {
+    let limits = SanityLimits::default();
+    let data = leo(5)?;
+    let report = check_trajectory(&data, &[0, 60, 120, 180, 240], &limits);
+    assert!(report.issues.is_empty(), "{}", report);
+
+    let report = check_trajectory(&data, &[0, 60, 60, 180, 240], &limits);
+    assert!(!report.is_ok());
+    assert_eq!(report.issues[0].step, Some(2));
+    assert!(!check_trajectory(&data, &[0, 60, 120], &limits).is_ok());
+
+    let report = check_trajectory(&data, &[0, 60, 120, 190, 250], &limits);
+    assert!(report.is_ok());
+    let warnings: Vec<_> = report.warnings().collect();
+    assert_eq!((warnings.len(), warnings[0].step), (1, Some(3)));
+
+    let mut jumped = data.clone();
+    jumped.x[3] = jumped.x[3].wrapping_add(5_000_000);
+    let report = check_trajectory(&jumped, &[0, 60, 120, 180, 240], &limits);
+    let errors: Vec<_> = report.errors().collect();
+    assert_eq!(errors[0].step, Some(3));
+    assert_eq!(errors[0].severity, Severity::Error);
+    assert!(report.into_result().is_err());
+
+    // Positions inside the Earth are only suspicious.
+    let small = SatelliteData::encode(&[[0.0; 3], [1.0, 0.0, 0.0]], Units::Meters, Frame::Eci)?;
+    let report = check_trajectory(&small, &[0, 1], &limits);
+    assert!(report.is_ok() && report.warnings().count() == 1);
+    Ok(())
+}
+
+/// The owner only encrypts a trajectory that passes, with its epochs attached.
+#[tokio::test]
+async fn test_encrypt_validated() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(leo(3)?).owner().build()?;
+    let limits = SanityLimits::default();
+    assert!(owner.encrypt_validated(&[0, 0, 60], &limits).is_err());
+    let (encrypted, report) = owner.encrypt_validated(&[100, 160, 220], &limits)?;
+    assert!(report.issues.is_empty());
+    assert_eq!(encrypted.epochs, vec![100, 160, 220]);
+    Ok(())
+}
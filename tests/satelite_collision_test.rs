@@ -3,22 +3,28 @@ use tfhe::prelude::*;
 use tfhe::{ConfigBuilder, FheBool, FheUint32, generate_keys, set_server_key};
 
 use sat_trajectory_fhe::common::{SatelliteData, safe_deserialize_item, safe_serialize_item};
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::units::Units;
 
 /// This test uses two different satellite trajectories ensuring that no collision occurs.
 #[tokio::test]
 async fn test_satellite_no_collision() -> Result<(), Box<dyn std::error::Error>> {
     // Satellite trajectory data
     let sat1 = SatelliteData {
-        x: [100, 101, 102],
-        y: [200, 201, 202],
-        z: [300, 301, 302],
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
     };
 
     let sat2 = SatelliteData {
         // Not matching sat1 in every coordinate at any index.
-        x: [101, 401, 102],
-        y: [200, 201, 202],
-        z: [300, 601, 602],
+        x: vec![101, 401, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 601, 602],
+        frame: Frame::Eci,
+        units: Units::Meters,
     };
 
     // ======================================================
@@ -223,17 +229,21 @@ async fn test_satellite_collision() -> Result<(), Box<dyn std::error::Error>> {
     // Define satellite trajectory data.
     // For sat1, we use a reference trajectory.
     let sat1 = SatelliteData {
-        x: [100, 101, 102],
-        y: [200, 201, 202],
-        z: [300, 301, 302],
+        x: vec![100, 101, 102],
+        y: vec![200, 201, 202],
+        z: vec![300, 301, 302],
+        frame: Frame::Eci,
+        units: Units::Meters,
     };
 
     // For sat2, we intentionally set index 0 to be the same as sat1 (collision),
     // while keeping the other indexes different.
     let sat2 = SatelliteData {
-        x: [100, 401, 402],
-        y: [200, 501, 502],
-        z: [300, 601, 602],
+        x: vec![100, 401, 402],
+        y: vec![200, 501, 502],
+        z: vec![300, 601, 602],
+        frame: Frame::Eci,
+        units: Units::Meters,
     };
 
     // ======================================================
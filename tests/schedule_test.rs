@@ -0,0 +1,31 @@
+use sat_trajectory_fhe::schedule::{StepOrder, batch_order};
+
+/// Batches cover every step once and are ranked by their most urgent step.
+#[test]
+fn test_batch_order() -> Result<(), Box<dyn std::error::Error>> {
+    let epochs = [0, 60, 120, 180, 240, 300, 360];
+    assert_eq!(
+        batch_order(&StepOrder::Chronological, &epochs, 3)?,
+        vec![0..3, 3..6, 6..7]
+    );
+    assert_eq!(
+        batch_order(&StepOrder::NearestTo(250), &epochs, 3)?,
+        vec![3..6, 6..7, 0..3]
+    );
+    assert_eq!(
+        batch_order(&StepOrder::NearestTo(1000), &epochs, 2)?,
+        vec![6..7, 4..6, 2..4, 0..2]
+    );
+    assert_eq!(
+        batch_order(&StepOrder::Priority(vec![5, 5, 1, 9, 9, 9, 0]), &epochs, 2)?,
+        vec![6..7, 2..4, 0..2, 4..6]
+    );
+    // Ties keep chronological order.
+    assert_eq!(
+        batch_order(&StepOrder::Priority(vec![1, 1, 1, 1, 1, 1, 1]), &epochs, 4)?,
+        vec![0..4, 4..7]
+    );
+    assert!(batch_order(&StepOrder::Priority(vec![0; 3]), &epochs, 2).is_err());
+    assert!(batch_order(&StepOrder::Chronological, &[], 2)?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "deterministic-tests")]
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::units::Units;
+use tfhe::ConfigBuilder;
+
+fn trajectory() -> SatelliteData {
+    SatelliteData {
+        x: vec![1, 2],
+        y: vec![3, 4],
+        z: vec![5, 6],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    }
+}
+
+/// The same seed gives the same key pair on every run; another seed gives another one.
+#[test]
+fn test_seeded_keys_are_reproducible() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ConfigBuilder::default().build();
+    let a = FheContext::generate_seeded(config, 42)?;
+    let b = FheContext::generate_seeded(config, 42)?;
+    let c = FheContext::generate_seeded(config, 43)?;
+    assert_eq!(a.client_key()?.fingerprint(), b.client_key()?.fingerprint());
+    assert_eq!(a.server_key().fingerprint(), b.server_key().fingerprint());
+    assert_ne!(a.client_key()?.fingerprint(), c.client_key()?.fingerprint());
+    assert_ne!(a.server_key().fingerprint(), c.server_key().fingerprint());
+
+    let owner = PartyBuilder::new(trajectory()).owner().seed(42).build()?;
+    assert_eq!(owner.server_key_fingerprint(), a.server_key().fingerprint());
+    Ok(())
+}
+
+/// Seeding one context leaves key generation on the thread back on OS entropy.
+#[test]
+fn test_unseeded_keys_stay_random() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ConfigBuilder::default().build();
+    FheContext::generate_seeded(config, 42)?;
+    let a = FheContext::generate(config)?;
+    let b = FheContext::generate(config)?;
+    assert_ne!(a.server_key().fingerprint(), b.server_key().fingerprint());
+    Ok(())
+}
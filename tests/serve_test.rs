@@ -0,0 +1,472 @@
+#![cfg(feature = "serve")]
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use sat_trajectory_fhe::blob::BlobStoreConfig;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::health::probe_response;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::SessionMetadata;
+use sat_trajectory_fhe::quota::{QuotaConfig, QuotaError};
+use sat_trajectory_fhe::serve::{Daemon, ServeConfig};
+use sat_trajectory_fhe::service::{
+    JobHandle, JobStatus, JobSummary, Request, Response, ServiceError, read_frame, write_frame,
+};
+use sat_trajectory_fhe::session::Session;
+use sat_trajectory_fhe::transport::{LocalStore, ObjectStoreConfig};
+use sat_trajectory_fhe::units::Units;
+
+/// The TOML config fills in defaults and rejects unknown keys.
+#[test]
+fn test_serve_config_from_toml() -> Result<(), ServiceError> {
+    let config = ServeConfig::from_toml(
+        r#"
+        listen = "127.0.0.1:7878"
+        storage_dir = "/tmp/jobs"
+        trajectory = "/tmp/trajectory.bin"
+        preset = "tuniform2m64"
+        "#,
+    )?;
+    assert_eq!(config.max_jobs, 1);
+    assert_eq!(config.queue_depth, 16);
+    assert_eq!(config.preset, ParameterPreset::Tuniform2m64);
+    assert_eq!(config.blob_store, BlobStoreConfig::Filesystem);
+    assert_eq!(config.drain_timeout_s, 30);
+
+    let with_store = ServeConfig::from_toml(
+        r#"
+        listen = "127.0.0.1:7878"
+        storage_dir = "/tmp/jobs"
+        trajectory = "/tmp/trajectory.bin"
+
+        [object_store]
+        kind = "local"
+        dir = "/srv/artifacts"
+        "#,
+    )?;
+    assert_eq!(
+        with_store.object_store,
+        Some(ObjectStoreConfig::Local(LocalStore {
+            dir: "/srv/artifacts".into()
+        }))
+    );
+
+    let in_memory = ServeConfig::from_toml(
+        r#"
+        listen = "127.0.0.1:7878"
+        storage_dir = "/tmp/jobs"
+        trajectory = "/tmp/trajectory.bin"
+
+        [blob_store]
+        kind = "memory"
+        "#,
+    )?;
+    assert_eq!(in_memory.blob_store, BlobStoreConfig::Memory);
+
+    assert!(ServeConfig::from_toml("listen = 1").is_err());
+    assert!(
+        ServeConfig::from_toml(
+            "listen = \"a\"\nstorage_dir = \"b\"\ntrajectory = \"c\"\nqueue_depth = 0"
+        )
+        .is_err()
+    );
+    assert!(
+        ServeConfig::from_toml(
+            "listen = \"a\"\nstorage_dir = \"b\"\ntrajectory = \"c\"\nworkers = 4"
+        )
+        .is_err()
+    );
+    Ok(())
+}
+
+/// The daemon answers over TCP and turns bad requests into error responses.
+#[tokio::test]
+async fn test_daemon_info_and_sessions() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("serve_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![1, 2],
+        y: vec![3, 4],
+        z: vec![5, 6],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 2,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let mut stream = TcpStream::connect(addr).await?;
+    let mut call = async |request: Request| -> Result<Response, ServiceError> {
+        write_frame(&mut stream, &request).await?;
+        read_frame(&mut stream).await
+    };
+
+    assert_eq!(
+        call(Request::Info).await?,
+        Response::Info {
+            preset: ParameterPreset::Default,
+            max_jobs: 2
+        }
+    );
+
+    let mut owner = Session::open().map_err(|e| e.to_string())?;
+    let hello = owner
+        .hello(&SessionMetadata::default())
+        .map_err(|e| e.to_string())?;
+    let Response::SessionOpened { job } = call(Request::OpenSession { hello }).await? else {
+        panic!("expected a new job");
+    };
+    assert!(matches!(
+        call(Request::Results { job }).await?,
+        Response::Error(_)
+    ));
+    assert!(matches!(
+        call(Request::Status {
+            job: JobHandle {
+                id: job.id + 100,
+                ..job
+            }
+        })
+        .await?,
+        Response::Error(_)
+    ));
+
+    // Local clients see every job's progress for the monitor.
+    let Response::Jobs(snapshot) = call(Request::Jobs).await? else {
+        panic!("expected the job list");
+    };
+    assert_eq!(snapshot.jobs.len(), 1);
+    let summary = &snapshot.jobs[0];
+    assert_eq!(
+        (summary.job, &summary.status),
+        (job.id, &JobStatus::AwaitingUploads)
+    );
+    assert_eq!((summary.steps, summary.progress()), (0, 0.0));
+    assert!(snapshot.alerts.is_empty());
+    let running = JobSummary {
+        steps: 100,
+        screened: 40,
+        batches: 3,
+        elapsed: Duration::from_secs(4),
+        ..summary.clone()
+    };
+    assert_eq!(running.total_batches(), 7);
+    assert_eq!(
+        (running.progress(), running.steps_per_sec()),
+        (0.4, Some(10.0))
+    );
+
+    // A session declaring another frame than the daemon's trajectory is refused.
+    let mut ecef_owner = Session::open().map_err(|e| e.to_string())?;
+    let hello = ecef_owner
+        .hello(&SessionMetadata {
+            frame: Some(Frame::Ecef),
+            ..Default::default()
+        })
+        .map_err(|e| e.to_string())?;
+    assert!(matches!(
+        call(Request::OpenSession { hello }).await?,
+        Response::Error(_)
+    ));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// A client at its job limit, or declaring a padded length over the step limit, gets a
+/// typed quota rejection instead of a new job.
+#[tokio::test]
+async fn test_daemon_enforces_job_quota() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("serve_quota_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![1],
+        y: vec![2],
+        z: vec![3],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig {
+            max_concurrent_jobs: Some(1),
+            max_trajectory_steps: Some(100),
+            ..Default::default()
+        },
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let mut stream = TcpStream::connect(addr).await?;
+    let mut open = async |padded_len: Option<usize>| -> Result<Response, ServiceError> {
+        let mut owner = Session::open().map_err(|e| e.to_string())?;
+        let hello = owner
+            .hello(&SessionMetadata {
+                padded_len,
+                ..Default::default()
+            })
+            .map_err(|e| e.to_string())?;
+        write_frame(&mut stream, &Request::OpenSession { hello }).await?;
+        read_frame(&mut stream).await
+    };
+
+    // Decoys count against the step limit, and can't make the daemon's trajectory shorter.
+    assert_eq!(
+        open(Some(usize::MAX)).await?,
+        Response::QuotaExceeded(QuotaError::TrajectoryTooLong {
+            steps: usize::MAX,
+            max: 100
+        })
+    );
+    assert_eq!(
+        open(Some(0)).await?,
+        Response::Error("trajectory has 1 steps, more than the padded length 0".to_string())
+    );
+
+    assert!(matches!(
+        open(Some(100)).await?,
+        Response::SessionOpened { .. }
+    ));
+    assert_eq!(
+        open(None).await?,
+        Response::QuotaExceeded(QuotaError::TooManyJobs { active: 1, max: 1 })
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// A job only answers requests carrying the token it was opened with: another client
+/// on the same host that knows the job's ID, asking about it or trying to cancel it, is
+/// told the job is unknown.
+#[tokio::test]
+async fn test_daemon_refuses_jobs_without_their_token() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("serve_owner_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![1],
+        y: vec![2],
+        z: vec![3],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let mut owner = TcpStream::connect(addr).await?;
+    let hello = Session::open()
+        .map_err(|e| e.to_string())?
+        .hello(&SessionMetadata::default())
+        .map_err(|e| e.to_string())?;
+    write_frame(&mut owner, &Request::OpenSession { hello }).await?;
+    let Response::SessionOpened { job } = read_frame(&mut owner).await? else {
+        return Err("no session opened".into());
+    };
+
+    let mut other = TcpStream::connect(addr).await?;
+    let guess = JobHandle {
+        token: [0; 16],
+        ..job
+    };
+    for request in [
+        Request::Status { job: guess },
+        Request::Cancel { job: guess },
+    ] {
+        write_frame(&mut other, &request).await?;
+        assert_eq!(
+            read_frame::<_, Response>(&mut other).await?,
+            Response::Error("unknown job".to_string())
+        );
+    }
+
+    write_frame(&mut owner, &Request::Status { job }).await?;
+    assert_eq!(
+        read_frame::<_, Response>(&mut owner).await?,
+        Response::Status(JobStatus::AwaitingUploads)
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// The HTTP probes answer on their own address, and the daemon turns ready once its
+/// pre-warm is over, even if a listed key couldn't be read.
+#[tokio::test]
+async fn test_daemon_health_probes() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("serve_health_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![1],
+        y: vec![2],
+        z: vec![3],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: Some("127.0.0.1:0".to_string()),
+        prewarm_keys: vec![dir.join("missing_key.bin")],
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let health = daemon.health_addr()?.ok_or("no health address")?;
+    tokio::spawn(daemon.run());
+
+    let get = async |path: &str| -> Result<String, ServiceError> {
+        let mut stream = TcpStream::connect(health).await?;
+        let request = format!("GET {} HTTP/1.1\r\nhost: localhost\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    };
+
+    assert!(get("/healthz").await?.starts_with("HTTP/1.1 200"));
+    assert!(get("/metrics").await?.starts_with("HTTP/1.1 404"));
+    let mut ready = false;
+    for _ in 0..50 {
+        if get("/readyz").await?.starts_with("HTTP/1.1 200") {
+            ready = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(ready);
+    assert_eq!(
+        probe_response("GET /readyz HTTP/1.1", false).0,
+        "503 Service Unavailable"
+    );
+    assert_eq!(
+        probe_response("POST /healthz HTTP/1.1", true).0,
+        "405 Method Not Allowed"
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// A daemon told to shut down stops serving once no job holds it up, flushing its store
+/// and closing the connections still open.
+#[tokio::test]
+async fn test_daemon_graceful_shutdown() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("serve_shutdown_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![1],
+        y: vec![2],
+        z: vec![3],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let running = tokio::spawn(daemon.run_until(async {
+        let _ = stopped.await;
+    }));
+
+    let mut stream = TcpStream::connect(addr).await?;
+    let hello = Session::open()
+        .map_err(|e| e.to_string())?
+        .hello(&SessionMetadata::default())
+        .map_err(|e| e.to_string())?;
+    write_frame(&mut stream, &Request::OpenSession { hello }).await?;
+    assert!(matches!(
+        read_frame::<_, Response>(&mut stream).await?,
+        Response::SessionOpened { .. }
+    ));
+
+    // A job still waiting for uploads doesn't hold up the shutdown.
+    stop.send(()).map_err(|_| "daemon gone")?;
+    tokio::time::timeout(Duration::from_secs(5), running).await???;
+    assert!(read_frame::<_, Response>(&mut stream).await.is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use sat_trajectory_fhe::protocol::{Envelope, MessageKind, ProtocolError, SessionMetadata};
+use sat_trajectory_fhe::regime::AltitudeBand;
+use sat_trajectory_fhe::session::Session;
+
+fn protocol_error(err: Box<dyn std::error::Error>) -> ProtocolError {
+    err.downcast_ref::<ProtocolError>()
+        .expect("expected a ProtocolError")
+        .clone()
+}
+
+/// Messages flow in order within one session and are rejected when replayed.
+#[test]
+fn test_session_rejects_replay() -> Result<(), Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?;
+    let mut evaluator = Session::join(owner.nonce());
+
+    let key_msg = owner.send(MessageKind::ServerKey, b"server key".to_vec())?;
+    let traj_msg = owner.send(MessageKind::EncryptedTrajectory, b"trajectory".to_vec())?;
+
+    let received = evaluator.receive(&key_msg)?;
+    assert_eq!(received.kind, MessageKind::ServerKey);
+    assert_eq!(received.seq, 0);
+    evaluator.receive(&traj_msg)?;
+
+    // Replaying an already accepted message fails.
+    let err = protocol_error(evaluator.receive(&traj_msg).unwrap_err());
+    assert_eq!(
+        err,
+        ProtocolError::UnexpectedSequence {
+            expected: 2,
+            found: 1
+        }
+    );
+
+    // Results flow back with their own sequence numbers.
+    let results_msg = evaluator.send(MessageKind::Results, b"results".to_vec())?;
+    assert_eq!(owner.receive(&results_msg)?.kind, MessageKind::Results);
+
+    Ok(())
+}
+
+/// A result captured from an old screening can't be replayed into a new one.
+#[test]
+fn test_session_rejects_other_session() -> Result<(), Box<dyn std::error::Error>> {
+    let old_owner = Session::open()?;
+    let mut old_evaluator = Session::join(old_owner.nonce());
+    let old_result = old_evaluator.send(MessageKind::Results, b"no collision".to_vec())?;
+
+    let mut new_owner = Session::open()?;
+    assert_ne!(new_owner.nonce(), old_owner.nonce());
+
+    let err = protocol_error(new_owner.receive(&old_result).unwrap_err());
+    assert_eq!(err, ProtocolError::SessionMismatch);
+
+    Ok(())
+}
+
+/// A payload re-stamped with the next sequence number, or with another session's
+/// nonce, fails its checksum.
+#[test]
+fn test_session_rejects_restamped_replay() -> Result<(), Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&SessionMetadata::default())?;
+    let (mut evaluator, _) = Session::accept(&hello)?;
+
+    let trajectory = owner.send(MessageKind::EncryptedTrajectory, b"trajectory".to_vec())?;
+    evaluator.receive(&trajectory)?;
+
+    let mut replay = Envelope::from_bytes(&trajectory)?;
+    replay.seq += 1;
+    let err = protocol_error(evaluator.receive(&replay.to_bytes()?).unwrap_err());
+    assert_eq!(err, ProtocolError::ChecksumMismatch);
+
+    let mut other = Session::open()?;
+    let mut replay = Envelope::from_bytes(&trajectory)?;
+    replay.nonce = other.nonce();
+    replay.seq = 0;
+    let err = protocol_error(other.receive(&replay.to_bytes()?).unwrap_err());
+    assert_eq!(err, ProtocolError::ChecksumMismatch);
+
+    // The genuine next message is still accepted.
+    let key = owner.send(MessageKind::ServerKey, Vec::new())?;
+    assert_eq!(evaluator.receive(&key)?.seq, 2);
+    Ok(())
+}
+
+/// Skipped messages are detected too.
+#[test]
+fn test_session_rejects_gap() -> Result<(), Box<dyn std::error::Error>> {
+    let mut owner = Session::open()?;
+    let mut evaluator = Session::join(owner.nonce());
+
+    let _dropped = owner.send(MessageKind::ServerKey, Vec::new())?;
+    let second = owner.send(MessageKind::EncryptedTrajectory, Vec::new())?;
+
+    let err = protocol_error(evaluator.receive(&second).unwrap_err());
+    assert_eq!(
+        err,
+        ProtocolError::UnexpectedSequence {
+            expected: 0,
+            found: 1
+        }
+    );
+
+    Ok(())
+}
+
+/// The evaluator joins from the owner's `Hello` and learns the declared altitude band.
+#[test]
+fn test_session_hello_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = SessionMetadata {
+        altitude_band: Some(AltitudeBand::new(400.0, 430.0)?),
+        ..Default::default()
+    };
+
+    let mut owner = Session::open()?;
+    let hello = owner.hello(&metadata)?;
+
+    let (mut evaluator, received) = Session::accept(&hello)?;
+    assert_eq!(evaluator.nonce(), owner.nonce());
+    assert_eq!(received, metadata);
+
+    // The session continues with the next sequence number.
+    let key_msg = owner.send(MessageKind::ServerKey, Vec::new())?;
+    assert_eq!(evaluator.receive(&key_msg)?.seq, 1);
+
+    // Anything but a Hello can't open a session.
+    let mut other = Session::open()?;
+    let not_hello = other.send(MessageKind::Results, Vec::new())?;
+    assert!(Session::accept(&not_hello).is_err());
+
+    Ok(())
+}
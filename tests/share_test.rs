@@ -0,0 +1,63 @@
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::compat::ParameterDigest;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::migrate::{ArtifactKind, meta_of};
+use sat_trajectory_fhe::share::{decimate, redact, trim};
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+/// Trimmed, redacted and decimated copies decrypt to the steps they kept, with their
+/// absolute indices, epochs and the original's parameters.
+#[test]
+fn test_reduced_copies() -> Result<(), Box<dyn std::error::Error>> {
+    let data = SatelliteData {
+        x: vec![100, 101, 102, 103, 104, 105],
+        y: vec![200, 201, 202, 203, 204, 205],
+        z: vec![300, 301, 302, 303, 304, 305],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let epochs = vec![1_000, 1_060, 1_120, 1_180, 1_240, 1_300];
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&data)?.with_epochs(epochs)?;
+    let bytes = encrypted.to_bytes()?;
+    let parameters = meta_of(ArtifactKind::Trajectory, &bytes)?.parameters;
+    assert_eq!(parameters, ParameterDigest::of_uint(&encrypted.x[0]));
+
+    let check = |copy: &[u8], first_index: usize, x: &[u32], epochs: &[u64]| {
+        assert_eq!(
+            meta_of(ArtifactKind::Trajectory, copy).unwrap().parameters,
+            parameters
+        );
+        let copy = EncryptedTrajectory::from_bytes(copy).unwrap();
+        assert_eq!(copy.first_index, first_index);
+        assert_eq!(copy.epochs, epochs);
+        assert_eq!(copy.decrypt(context.client_key().unwrap()).x, x);
+    };
+
+    check(
+        &trim(&bytes, 1_100, 1_250)?,
+        2,
+        &[102, 103, 104],
+        &[1_120, 1_180, 1_240],
+    );
+    assert!(EncryptedTrajectory::from_bytes(&trim(&bytes, 5_000, 6_000)?)?.is_empty());
+
+    let runs = redact(&bytes, &[0, 3, 2])?;
+    assert_eq!(runs.len(), 2);
+    check(&runs[0], 1, &[101], &[1_060]);
+    check(&runs[1], 4, &[104, 105], &[1_240, 1_300]);
+    assert!(redact(&bytes, &[6]).is_err());
+
+    // Redacting a trimmed copy works in absolute steps too.
+    let runs = redact(&trim(&bytes, 1_100, 1_350)?, &[4])?;
+    check(&runs[0], 2, &[102, 103], &[1_120, 1_180]);
+    check(&runs[1], 5, &[105], &[1_300]);
+
+    check(&decimate(&bytes, 4)?, 0, &[100, 104], &[1_000, 1_240]);
+    assert!(decimate(&bytes, 0).is_err());
+    Ok(())
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::shuffle::{Shuffle, unshuffle};
+
+use common::diagonal_trajectory;
+
+/// Shuffling and unshuffling with the opened permutation are inverses, and the opening is
+/// bound to the commitment.
+#[test]
+fn test_shuffle_commitment() -> Result<(), Box<dyn std::error::Error>> {
+    let shuffle = Shuffle::random(20)?;
+    let items: Vec<usize> = (0..20).collect();
+    let shuffled = shuffle.apply(items.clone())?;
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    assert_eq!(sorted, items);
+
+    let commitment = shuffle.commitment();
+    let permutation = commitment.verify(&shuffle.opening())?;
+    assert_eq!(unshuffle(&shuffled, &permutation)?, items);
+
+    // A different permutation, or the right one with another salt, doesn't open it.
+    let mut forged = shuffle.opening();
+    forged.permutation.swap(0, 1);
+    assert!(commitment.verify(&forged).is_err());
+    let mut forged = shuffle.opening();
+    forged.salt[0] ^= 1;
+    assert!(commitment.verify(&forged).is_err());
+
+    assert!(shuffle.apply(vec![0; 3]).is_err());
+    Ok(())
+}
+
+/// The owner learns that some catalog object matched, and which one only after the
+/// evaluator opens its permutation.
+#[tokio::test]
+async fn test_shuffled_catalog_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![10, 11, 12]))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+
+    let catalog = vec![
+        diagonal_trajectory(vec![0, 0, 0]),
+        diagonal_trajectory(vec![0, 0, 12]),
+        diagonal_trajectory(vec![1, 1, 1]),
+    ];
+    let evaluator = PartyBuilder::new(diagonal_trajectory(vec![]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let shuffle = Shuffle::random(catalog.len())?;
+    let flags = evaluator.evaluate_objects_shuffled(&encrypted, &catalog, &shuffle)?;
+    let commitment = shuffle.commitment();
+
+    let shuffled = owner.decrypt_results(&flags);
+    assert_eq!(shuffled.iter().filter(|&&f| f).count(), 1);
+
+    // Both agree to reveal the mapping.
+    let permutation = commitment.verify(&shuffle.opening())?;
+    assert_eq!(
+        unshuffle(&shuffled, &permutation)?,
+        vec![false, true, false]
+    );
+    Ok(())
+}
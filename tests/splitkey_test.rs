@@ -0,0 +1,67 @@
+#![cfg(feature = "mmap")]
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::splitkey::{KeyComponent, SplitKey, write_split_key};
+use sat_trajectory_fhe::units::Units;
+
+/// A key loaded from a split key file keeps the owner's fingerprint and screens like the
+/// key it was split from.
+#[test]
+fn test_split_key_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let owner_data = SatelliteData {
+        x: vec![100, 101],
+        y: vec![200, 201],
+        z: vec![300, 301],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let evaluator_data = SatelliteData {
+        x: vec![100, 999],
+        ..owner_data.clone()
+    };
+    let owner = PartyBuilder::new(owner_data).owner().build()?;
+
+    let path = std::env::temp_dir().join(format!("splitkey_test_{}.skf", std::process::id()));
+    write_split_key(&path, &owner.server_key_bytes()?)?;
+    let split = SplitKey::open(&path)?;
+    assert_eq!(split.fingerprint(), owner.server_key_fingerprint());
+    let components: Vec<KeyComponent> = split.components().iter().map(|&(c, _)| c).collect();
+    assert_eq!(
+        components[..3],
+        [
+            KeyComponent::Shape,
+            KeyComponent::Keyswitch,
+            KeyComponent::Bootstrap
+        ]
+    );
+    assert!(split.screening_bytes() > 0);
+
+    let evaluator = PartyBuilder::new(evaluator_data)
+        .evaluator_with_key(split.load()?)
+        .build()?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(
+        evaluator.server_key_fingerprint(),
+        owner.server_key_fingerprint()
+    );
+    let output = evaluator.evaluate(&owner.encrypt_trajectory()?)?;
+    assert_eq!(owner.decrypt_results(&output.results), [true, false]);
+    Ok(())
+}
+
+/// Files that aren't split keys, or are cut short, are refused.
+#[test]
+fn test_split_key_rejects_bad_files() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("splitkey_bad_{}.skf", std::process::id()));
+    std::fs::write(&path, b"EFT2\x00\x00\x00\x00\x00\x00\x00\x00")?;
+    assert!(SplitKey::open(&path).is_err());
+    std::fs::write(&path, b"SKF1\xff\x00\x00\x00\x00\x00\x00\x00")?;
+    assert!(SplitKey::open(&path).is_err());
+    std::fs::write(&path, b"SKF9\x00\x00\x00\x00\x00\x00\x00\x00")?;
+    let err = SplitKey::open(&path).err().expect("newer format refused");
+    std::fs::remove_file(&path)?;
+    assert!(err.to_string().contains("newer"), "{}", err);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::spotcheck::{SpotCheckFailed, SpotChecks};
+
+use common::trajectory;
+
+/// Planted steps must come back positive and other decoys negative; real steps are free.
+#[test]
+fn test_verify() -> Result<(), Box<dyn std::error::Error>> {
+    assert!(SpotChecks::choose(3, 10, 8).is_err());
+    let none = SpotChecks::choose(3, 10, 0)?;
+    assert!(
+        none.verify(&[true, false, true, false, false], 0, 3)
+            .is_ok()
+    );
+    assert_eq!(
+        none.verify(&[false, false, true], 2, 3),
+        Err(SpotCheckFailed {
+            step: 4,
+            expected: false
+        })
+    );
+
+    // Every decoy planted.
+    let all = SpotChecks::choose(3, 10, 7)?;
+    assert_eq!(format!("{:?}", all), "SpotChecks(7)");
+    let mut flags = vec![false, true, false];
+    flags.extend([true; 7]);
+    assert!(all.verify(&flags, 0, 3).is_ok());
+    flags[6] = false;
+    assert_eq!(
+        all.verify(&flags, 0, 3),
+        Err(SpotCheckFailed {
+            step: 6,
+            expected: true
+        })
+    );
+
+    let mut padded = trajectory(vec![1; 10]);
+    all.plant(&mut padded);
+    assert_eq!(padded.x, [1, 1, 1, 0, 0, 0, 0, 0, 0, 0]);
+    Ok(())
+}
+
+/// An honest evaluator passes; one that makes up the decoys' flags is caught.
+#[tokio::test]
+async fn test_spot_checks_catch_lazy_evaluator() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(trajectory(vec![100, 200, 300]))
+        .pad_to(8)
+        .owner()
+        .spot_checks(2)
+        .build()?;
+    let evaluator = PartyBuilder::new(trajectory(vec![100, 0, 0]))
+        .pad_to(8)
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+
+    let honest = evaluator.evaluate(&encrypted)?.results;
+    assert_eq!(
+        owner.decrypt_verified(&honest, 0)?,
+        vec![true, false, false]
+    );
+
+    // Screens the real steps only and copies a real flag into every decoy slot.
+    let mut lazy = evaluator.evaluate(&encrypted.steps(0..3))?.results;
+    let filler = lazy[1].clone();
+    lazy.extend(std::iter::repeat_n(filler, 5));
+    let err = owner.decrypt_verified(&lazy, 0).unwrap_err();
+    assert!(
+        err.downcast_ref::<SpotCheckFailed>()
+            .is_some_and(|e| e.expected)
+    );
+    Ok(())
+}
+
+/// Spot checks are planted among decoys, so they need padding with room for them.
+#[test]
+fn test_spot_checks_need_padding() {
+    let build = |pad: Option<usize>| {
+        let builder = PartyBuilder::new(trajectory(vec![1, 2, 3]));
+        match pad {
+            Some(len) => builder.pad_to(len),
+            None => builder,
+        }
+        .owner()
+        .spot_checks(2)
+        .build()
+    };
+    assert!(build(None).is_err());
+    assert!(build(Some(4)).is_err());
+}
@@ -0,0 +1,73 @@
+use tfhe::{ConfigBuilder, FheBool, FheUint32};
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::depth::OpCounter;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::kernel::{ComparisonKernel, ExactMatch};
+use sat_trajectory_fhe::result_bundle::ResultBundle;
+use sat_trajectory_fhe::screening::{ClearCoord, ScreeningConfig, screen_kernel_checked};
+use sat_trajectory_fhe::units::Units;
+
+// Exact match that panics on steps where the clear x is `poison`.
+struct Flaky {
+    poison: u32,
+}
+
+impl ComparisonKernel for Flaky {
+    fn cost(&self) -> OpCounter {
+        ExactMatch.cost()
+    }
+
+    fn compare(
+        &self,
+        encrypted: [&FheUint32; 3],
+        clear: [ClearCoord; 3],
+        parallel: bool,
+    ) -> FheBool {
+        if clear[0].value == self.poison {
+            panic!("kernel failed at x = {}", self.poison);
+        }
+        ExactMatch.compare(encrypted, clear, parallel)
+    }
+}
+
+/// A panicking step is recorded and placeholder-flagged while the remaining steps are
+/// screened, and the bundle tells the owner which epochs are unverified.
+#[tokio::test]
+async fn test_failed_steps_are_reported() -> Result<(), Box<dyn std::error::Error>> {
+    let trajectory = |x: Vec<u32>| SatelliteData {
+        y: vec![0; x.len()],
+        z: vec![0; x.len()],
+        x,
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&trajectory(vec![10, 20, 30, 40]))?;
+    let counterpart = trajectory(vec![10, 99, 30, 40]);
+
+    let checked = context.evaluate_with(|| {
+        screen_kernel_checked(
+            &encrypted,
+            &counterpart,
+            &Flaky { poison: 30 },
+            &ScreeningConfig::default(),
+        )
+    })?;
+    assert_eq!(checked.failed_indices(), vec![2]);
+    assert!(checked.failures[0].reason.contains("x = 30"));
+    assert_eq!(
+        checked.output.ops.comparisons,
+        3 * ExactMatch.cost().comparisons
+    );
+    let flags: Vec<bool> = context.decrypt(&checked.output.results)?;
+    assert_eq!(flags, vec![true, false, false, true]);
+
+    let bundle = ResultBundle::new(0, checked.output.results).with_failed(vec![2]);
+    let bundle = ResultBundle::from_bytes(&bundle.to_bytes()?)?;
+    assert_eq!(bundle.failed(), [2]);
+    assert!(bundle.is_verified(1) && !bundle.is_verified(2));
+    assert_eq!(bundle.unverified_epochs(&[100, 200, 300, 400]), vec![300]);
+    Ok(())
+}
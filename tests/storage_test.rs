@@ -0,0 +1,86 @@
+#![cfg(feature = "storage")]
+
+use sat_trajectory_fhe::events::ConjunctionEvent;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::MessageKind;
+use sat_trajectory_fhe::storage::{ScreeningStore, SessionRecord};
+
+const DAY: u64 = 86_400;
+
+fn event(start_index: usize) -> ConjunctionEvent {
+    ConjunctionEvent {
+        start_index,
+        start_epoch: start_index as u64 * 60,
+        end_epoch: start_index as u64 * 60 + 120,
+        n_steps: 3,
+    }
+}
+
+/// Sessions round-trip, and conjunctions are queried per satellite and time range.
+#[test]
+fn test_store_and_query_conjunctions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut store = ScreeningStore::in_memory()?;
+    let now = 100 * DAY;
+    let session = |nonce: u8, satellite: &str, peer: &str, started_at| SessionRecord {
+        nonce: [nonce; 16],
+        satellite: satellite.to_string(),
+        peer: peer.to_string(),
+        preset: ParameterPreset::Tuniform2m64,
+        started_at,
+    };
+
+    let old = store.record_session(&session(1, "SAT-A", "operator-1", now - 40 * DAY))?;
+    let recent = store.record_session(&session(2, "SAT-A", "operator-2", now - 2 * DAY))?;
+    let other = store.record_session(&session(3, "SAT-B", "operator-1", now - DAY))?;
+    assert_eq!(
+        store.session(recent)?,
+        session(2, "SAT-A", "operator-2", now - 2 * DAY)
+    );
+
+    store.record_artifact(recent, MessageKind::ServerKey, b"server key")?;
+    store.record_outcome(old, 100, &[event(5)])?;
+    store.record_outcome(recent, 100, &[event(10), event(50)])?;
+    store.record_outcome(other, 100, &[event(7)])?;
+    // One outcome per session.
+    assert!(store.record_outcome(recent, 100, &[]).is_err());
+
+    let found = store.recent_conjunctions("SAT-A", 30, now)?;
+    assert_eq!(found.len(), 2);
+    assert!(
+        found
+            .iter()
+            .all(|c| c.session == recent && c.peer == "operator-2")
+    );
+    assert_eq!(found[0].event, event(10));
+    assert_eq!(found[1].event, event(50));
+
+    assert_eq!(store.conjunctions("SAT-A", 0)?.len(), 3);
+    assert!(store.conjunctions("SAT-C", 0)?.is_empty());
+
+    Ok(())
+}
+
+/// Records survive reopening the database file.
+#[test]
+fn test_store_persists_to_file() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("storage_test_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut store = ScreeningStore::open(&path)?;
+    let id = store.record_session(&SessionRecord {
+        nonce: [9; 16],
+        satellite: "SAT-A".to_string(),
+        peer: "operator-1".to_string(),
+        preset: ParameterPreset::Default,
+        started_at: 1_000,
+    })?;
+    store.record_outcome(id, 10, &[event(3)])?;
+    drop(store);
+
+    let store = ScreeningStore::open(&path)?;
+    assert_eq!(store.session(id)?.preset, ParameterPreset::Default);
+    assert_eq!(store.conjunctions("SAT-A", 1_000)?[0].event, event(3));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
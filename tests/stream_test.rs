@@ -0,0 +1,182 @@
+mod common;
+
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::schedule::StepOrder;
+use sat_trajectory_fhe::stream::ResultBatch;
+
+use common::diagonal_trajectory;
+
+/// Streamed batches cover the steps in order and decrypt to the same flags as a
+/// regular evaluation, also after a trip through their wire form.
+#[tokio::test]
+async fn test_streamed_batches() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![10, 20, 30, 40, 50]))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let evaluator = PartyBuilder::new(diagonal_trajectory(vec![10, 21, 30, 41, 50]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+
+    let mut batches = Vec::new();
+    let ops = evaluator.evaluate_streaming(&encrypted, 2, &StepOrder::Chronological, |batch| {
+        batches.push(ResultBatch::from_bytes(&batch.to_bytes()?)?);
+        Ok(())
+    })?;
+    assert_eq!(
+        batches
+            .iter()
+            .map(|batch| (batch.first_index, batch.len()))
+            .collect::<Vec<_>>(),
+        vec![(0, 2), (2, 2), (4, 1)]
+    );
+    let flags: Vec<bool> = batches
+        .iter()
+        .flat_map(|batch| owner.decrypt_unpadded(&batch.flags, batch.first_index))
+        .collect();
+    assert_eq!(flags, vec![true, false, true, false, true]);
+    assert_eq!(ops, evaluator.evaluate(&encrypted)?.ops);
+
+    // A failing sink stops the screening.
+    let mut sent = 0;
+    let stopped = evaluator.evaluate_streaming(&encrypted, 1, &StepOrder::Chronological, |_| {
+        sent += 1;
+        Err("owner hung up".into())
+    });
+    assert!(stopped.is_err());
+    assert_eq!(sent, 1);
+    Ok(())
+}
+
+/// Batches nearest the requested epoch arrive first and still decrypt to the right steps.
+#[tokio::test]
+async fn test_streaming_nearest_first() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![10, 20, 30, 40, 50]))
+        .owner()
+        .build()?;
+    let encrypted = owner
+        .encrypt_trajectory()?
+        .with_epochs(vec![1000, 1060, 1120, 1180, 1240])?;
+    let evaluator = PartyBuilder::new(diagonal_trajectory(vec![11, 20, 31, 40, 51]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+
+    let mut received = Vec::new();
+    evaluator.evaluate_streaming(&encrypted, 2, &StepOrder::NearestTo(1250), |batch| {
+        received.push((
+            batch.first_index,
+            owner.decrypt_unpadded(&batch.flags, batch.first_index),
+        ));
+        Ok(())
+    })?;
+    assert_eq!(
+        received,
+        vec![
+            (4, vec![false]),
+            (2, vec![false, true]),
+            (0, vec![false, true]),
+        ]
+    );
+    Ok(())
+}
+
+/// The daemon hands out batches while the job runs and the client reads them to the end.
+#[cfg(feature = "serve")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_daemon_streams_batches() -> Result<(), sat_trajectory_fhe::service::ServiceError> {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use sat_trajectory_fhe::billing::UsageLog;
+    use sat_trajectory_fhe::client::Client;
+    use sat_trajectory_fhe::preset::ParameterPreset;
+    use sat_trajectory_fhe::protocol::SessionMetadata;
+    use sat_trajectory_fhe::quota::QuotaConfig;
+    use sat_trajectory_fhe::screening::exact_match_cost;
+    use sat_trajectory_fhe::serve::{Daemon, STREAM_BATCH_STEPS, ServeConfig};
+
+    let dir = std::env::temp_dir().join(format!("stream_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let steps = STREAM_BATCH_STEPS + 2;
+    let mut evaluator_x: Vec<u32> = (0..steps as u32).map(|i| i * 10).collect();
+    // Only the second and the last step collide.
+    for (i, x) in evaluator_x.iter_mut().enumerate() {
+        if i != 1 && i != steps - 1 {
+            *x += 1;
+        }
+    }
+    std::fs::write(
+        dir.join("trajectory.bin"),
+        bincode::serialize(&diagonal_trajectory(evaluator_x))?,
+    )?;
+    let usage = Arc::new(UsageLog::default());
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?
+    .with_meter(usage.clone());
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let owner = PartyBuilder::new(diagonal_trajectory(
+        (0..steps as u32).map(|i| i * 10).collect(),
+    ))
+    .config(ParameterPreset::Default.config())
+    .owner()
+    .build()
+    .map_err(|e| e.to_string())?;
+    let mut client = Client::connect(addr).await?;
+    let mut job = client.open_session(&SessionMetadata::default()).await?;
+    client
+        .upload_server_key(
+            &mut job,
+            owner.server_key_bytes().map_err(|e| e.to_string())?,
+        )
+        .await?;
+    let encrypted = owner.encrypt_trajectory().map_err(|e| e.to_string())?;
+    client.upload_trajectory(&mut job, &encrypted).await?;
+
+    let mut flags = Vec::new();
+    let mut batches = 0;
+    while let Some(batch) = client
+        .next_batch(&mut job, Duration::from_millis(100))
+        .await?
+    {
+        assert_eq!(batch.first_index, flags.len());
+        flags.extend(owner.decrypt_unpadded(&batch.flags, batch.first_index));
+        batches += 1;
+    }
+    assert_eq!(batches, 2);
+    let expected: Vec<bool> = (0..steps).map(|i| i == 1 || i == steps - 1).collect();
+    assert_eq!(flags, expected);
+    // The complete result is still there once the job is done.
+    let results = client.results(&mut job).await?;
+    assert_eq!(owner.decrypt_results(&results), expected);
+    // Metered batch by batch, as the batches were screened.
+    let records = usage.records();
+    assert_eq!(
+        records.iter().map(|r| r.steps).collect::<Vec<_>>(),
+        [STREAM_BATCH_STEPS as u64, 2]
+    );
+    assert!(records.iter().all(|r| r.session == job.id.to_string()));
+    assert_eq!(
+        records[1].ops.comparisons,
+        2 * exact_match_cost().comparisons
+    );
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
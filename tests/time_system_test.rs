@@ -0,0 +1,65 @@
+use sat_trajectory_fhe::protocol::ProtocolError;
+use sat_trajectory_fhe::time_system::{
+    TimeSystem, align_epochs, check_time_systems, convert, tai_minus_utc,
+};
+
+// 2017-01-01 00:00:00 UTC, right after the last leap second.
+const NEW_YEAR_2017: u64 = 1_483_228_800;
+
+/// Known offsets: GPS is 18 s ahead of UTC since 2017, 13 s in 1999-2005, and TAI 19 s
+/// ahead of GPS throughout.
+#[test]
+fn test_known_offsets() {
+    let t = 1_709_294_400;
+    assert_eq!(convert(t, TimeSystem::Utc, TimeSystem::Tai), t + 37);
+    assert_eq!(convert(t, TimeSystem::Utc, TimeSystem::Gps), t + 18);
+    assert_eq!(convert(t + 18, TimeSystem::Gps, TimeSystem::Utc), t);
+    assert_eq!(convert(t, TimeSystem::Gps, TimeSystem::Tai), t + 19);
+
+    let y2k = 946_684_800;
+    assert_eq!(convert(y2k, TimeSystem::Utc, TimeSystem::Gps), y2k + 13);
+    assert_eq!(tai_minus_utc(0), 10);
+}
+
+/// Conversion steps over a leap second: the inserted second has no UTC epoch of its own.
+#[test]
+fn test_leap_second() {
+    assert_eq!(tai_minus_utc(NEW_YEAR_2017 - 1), 36);
+    assert_eq!(tai_minus_utc(NEW_YEAR_2017), 37);
+
+    let tai = align_epochs(
+        &[NEW_YEAR_2017 - 1, NEW_YEAR_2017],
+        TimeSystem::Utc,
+        TimeSystem::Tai,
+    );
+    assert_eq!(tai[1] - tai[0], 2);
+    let leap = tai[0] + 1;
+    assert_eq!(
+        convert(leap, TimeSystem::Tai, TimeSystem::Utc),
+        NEW_YEAR_2017
+    );
+
+    for t in [63_072_000, NEW_YEAR_2017 - 1, NEW_YEAR_2017, 1_709_294_400] {
+        for system in [TimeSystem::Tai, TimeSystem::Gps] {
+            assert_eq!(
+                convert(convert(t, TimeSystem::Utc, system), system, TimeSystem::Utc),
+                t
+            );
+        }
+    }
+}
+
+/// Unconverted epochs in different time systems are a hard protocol error.
+#[test]
+fn test_time_system_mismatch() {
+    assert!(check_time_systems(TimeSystem::Gps, TimeSystem::Gps).is_ok());
+    let err = check_time_systems(TimeSystem::Utc, TimeSystem::Gps).unwrap_err();
+    assert_eq!(
+        err,
+        ProtocolError::TimeSystemMismatch {
+            owner: TimeSystem::Utc,
+            evaluator: TimeSystem::Gps
+        }
+    );
+    println!("{}", err);
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use sat_trajectory_fhe::party::PartyBuilder;
+use sat_trajectory_fhe::timing::{EncryptedEpochs, time_matched_cost};
+
+use common::diagonal_trajectory;
+
+/// Steps are paired by encrypted epoch rather than by index: only a position match
+/// within the time window counts.
+#[tokio::test]
+async fn test_time_matched_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let owner = PartyBuilder::new(diagonal_trajectory(vec![10, 20]))
+        .owner()
+        .build()?;
+    let encrypted = owner.encrypt_trajectory()?;
+    let epochs = EncryptedEpochs::from_bytes(&owner.encrypt_epochs(&[1_000, 2_000])?.to_bytes()?)?;
+    assert_eq!(epochs.len(), 2);
+
+    // Step 0 matches owner step 0 500 ns later; owner step 1's position only shows up
+    // 7 us later.
+    let evaluator = PartyBuilder::new(diagonal_trajectory(vec![10, 30, 20]))
+        .evaluator(owner.server_key_bytes()?)
+        .build()?;
+    let output =
+        evaluator.evaluate_time_matched(&encrypted, &epochs, &[1_500, 2_050, 9_000], 600)?;
+    assert_eq!(owner.decrypt_results(&output.results), vec![true, false]);
+    assert_eq!(output.ops.comparisons, 2 * 3 * 5);
+
+    // Epochs must cover the evaluator's steps.
+    assert!(
+        evaluator
+            .evaluate_time_matched(&encrypted, &epochs, &[1_500], 600)
+            .is_err()
+    );
+    Ok(())
+}
+
+/// The OR over evaluator steps adds log depth on top of one pair's check.
+#[test]
+fn test_time_matched_cost() {
+    let cost = time_matched_cost(8);
+    assert_eq!(cost.comparisons, 40);
+    assert_eq!(cost.boolean, 8 * 4 + 7);
+    assert_eq!(cost.depth, 4 + 3);
+    assert_eq!(time_matched_cost(1).depth, 4);
+}
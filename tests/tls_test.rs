@@ -0,0 +1,93 @@
+#![cfg(feature = "serve")]
+
+use std::path::Path;
+
+use sat_trajectory_fhe::client::Client;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::quota::QuotaConfig;
+use sat_trajectory_fhe::serve::{Daemon, ServeConfig};
+use sat_trajectory_fhe::service::ServiceError;
+use sat_trajectory_fhe::tls::{TlsConfig, fingerprint};
+use sat_trajectory_fhe::units::Units;
+
+// A self-signed certificate and key written to `dir`; returns its fingerprint.
+fn certificate(dir: &Path, name: &str) -> Result<String, ServiceError> {
+    let certified = rcgen::generate_simple_self_signed(vec![name.to_string()])?;
+    std::fs::write(dir.join(format!("{}.pem", name)), certified.cert.pem())?;
+    std::fs::write(
+        dir.join(format!("{}.key", name)),
+        certified.key_pair.serialize_pem(),
+    )?;
+    Ok(fingerprint(certified.cert.der()))
+}
+
+fn tls_config(dir: &Path, name: &str, pinned: &str) -> TlsConfig {
+    TlsConfig {
+        cert: dir.join(format!("{}.pem", name)),
+        key: dir.join(format!("{}.key", name)),
+        pinned: vec![pinned.to_string()],
+    }
+}
+
+/// Only pinned operators get through the handshake, on either side.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mutual_tls_with_pinning() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("tls_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![400],
+        y: vec![500],
+        z: vec![600],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+    let daemon_print = certificate(&dir, "daemon")?;
+    let client_print = certificate(&dir, "client")?;
+    let stranger_print = certificate(&dir, "stranger")?;
+
+    let config = ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: Some(tls_config(&dir, "daemon", &client_print)),
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    };
+    assert!(Daemon::bind(config.clone()).await.is_err());
+    let daemon = Daemon::bind_tls(config).await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let mut client = Client::connect_tls(addr, &tls_config(&dir, "client", &daemon_print)).await?;
+    assert_eq!(client.info().await?.preset, ParameterPreset::Default);
+
+    // The daemon doesn't know this client.
+    let stranger = tls_config(&dir, "stranger", &daemon_print);
+    let refused = async {
+        let mut client = Client::connect_tls(addr, &stranger).await?;
+        client.info().await
+    };
+    assert!(refused.await.is_err());
+
+    // The client doesn't know this daemon.
+    let mispinned = tls_config(&dir, "client", &stranger_print);
+    assert!(Client::connect_tls(addr, &mispinned).await.is_err());
+
+    // The pinned client still gets through after the failed handshakes.
+    let mut client = Client::connect_tls(addr, &tls_config(&dir, "client", &daemon_print)).await?;
+    assert_eq!(client.info().await?.preset, ParameterPreset::Default);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
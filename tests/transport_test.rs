@@ -0,0 +1,77 @@
+use sat_trajectory_fhe::transport::{
+    LocalStore, ObjectStore, S3Store, check_key, fetch_verified, publish,
+};
+
+/// Artifacts round-trip through a store and are rejected if the object changed.
+#[test]
+fn test_publish_and_fetch_verified() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("transport_test_{}", std::process::id()));
+    let store = LocalStore { dir: dir.clone() };
+
+    let artifact = publish(&store, "job-1/server_key.bin", b"server key bytes")?;
+    assert_eq!(artifact.size, 16);
+    assert_eq!(fetch_verified(&store, &artifact)?, b"server key bytes");
+
+    // Same size, different content.
+    std::fs::write(dir.join("job-1/server_key.bin"), b"server key bytez")?;
+    assert!(fetch_verified(&store, &artifact).is_err());
+
+    // Truncated.
+    std::fs::write(dir.join("job-1/server_key.bin"), b"server")?;
+    assert!(fetch_verified(&store, &artifact).is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Peers name objects by key only: anything that could leave the store or the curl
+/// config string is refused, as is a symlink out of a local store.
+#[test]
+fn test_object_keys_stay_in_the_store() -> Result<(), Box<dyn std::error::Error>> {
+    assert!(check_key("job-1/server_key.bin").is_ok());
+    for key in [
+        "",
+        "/etc/passwd",
+        "../secrets",
+        "job-1/../../secrets",
+        "file:///etc/passwd",
+        "https://evil.example.com/key",
+        "key\"\nurl = \"http://169.254.169.254/",
+    ] {
+        assert!(check_key(key).is_err(), "{:?}", key);
+    }
+
+    let dir = std::env::temp_dir().join(format!("transport_keys_test_{}", std::process::id()));
+    let store = LocalStore {
+        dir: dir.join("store"),
+    };
+    store.put("inside.bin", b"inside")?;
+    std::fs::write(dir.join("outside.bin"), b"outside")?;
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(dir.join("outside.bin"), dir.join("store/link.bin"))?;
+        assert!(store.get("link.bin").is_err());
+    }
+    assert_eq!(store.get("inside.bin")?, b"inside");
+    assert!(store.get("../outside.bin").is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// S3 objects use path-style URLs and the secret never shows up in Debug output.
+#[test]
+fn test_s3_store_url_and_debug() {
+    let store = S3Store {
+        endpoint: "https://s3.example.com/".to_string(),
+        bucket: "artifacts".to_string(),
+        region: "eu-central-1".to_string(),
+        access_key: "AKIDEXAMPLE".to_string(),
+        secret_key: "very-secret".to_string(),
+    };
+    assert_eq!(
+        store.url("job-1/trajectory.bin"),
+        "https://s3.example.com/artifacts/job-1/trajectory.bin"
+    );
+    assert!(!format!("{:?}", store).contains("very-secret"));
+}
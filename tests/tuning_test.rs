@@ -0,0 +1,101 @@
+#![cfg(feature = "serve")]
+
+use std::time::Duration;
+
+use sat_trajectory_fhe::client::Client;
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::preset::ParameterPreset;
+use sat_trajectory_fhe::protocol::SessionMetadata;
+use sat_trajectory_fhe::quota::QuotaConfig;
+use sat_trajectory_fhe::serve::{Daemon, STREAM_BATCH_STEPS, ServeConfig};
+use sat_trajectory_fhe::service::{CHUNK_BYTES, ServiceError};
+use sat_trajectory_fhe::tuning::{
+    LinkBenchmark, MAX_CHUNK_BYTES, MIN_CHUNK_BYTES, SessionTuning, tune,
+};
+use sat_trajectory_fhe::units::Units;
+
+/// Pieces take about 50 round trips to send and batches about as long to compute, at
+/// least a second, within the bounds.
+#[test]
+fn test_tune() {
+    let tuned = |rtt_ms, bytes_per_second, step_ms: Option<u64>| {
+        tune(&LinkBenchmark {
+            rtt: Duration::from_millis(rtt_ms),
+            bytes_per_second,
+            step_time: step_ms.map(Duration::from_millis),
+        })
+    };
+    assert_eq!(
+        tuned(10, 10e6, Some(500)),
+        SessionTuning {
+            chunk_bytes: 5_000_000,
+            batch_steps: 2,
+        }
+    );
+    assert_eq!(
+        tuned(100, 100e6, Some(250)),
+        SessionTuning {
+            chunk_bytes: MAX_CHUNK_BYTES,
+            batch_steps: 20,
+        }
+    );
+    // A local link with an evaluator that hasn't screened anything yet.
+    assert_eq!(
+        tuned(0, 1e9, None),
+        SessionTuning {
+            chunk_bytes: MIN_CHUNK_BYTES,
+            batch_steps: STREAM_BATCH_STEPS,
+        }
+    );
+    assert_eq!(tuned(10, 1e6, Some(3_600_000)).batch_steps, 1);
+    assert_eq!(tuned(10, f64::INFINITY, None).chunk_bytes, CHUNK_BYTES);
+}
+
+/// Before its first screening the daemon has no step time, so batches keep their default.
+#[tokio::test]
+async fn test_open_tuned_session() -> Result<(), ServiceError> {
+    let dir = std::env::temp_dir().join(format!("tuning_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let trajectory = SatelliteData {
+        x: vec![1, 2],
+        y: vec![3, 4],
+        z: vec![5, 6],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    std::fs::write(dir.join("trajectory.bin"), bincode::serialize(&trajectory)?)?;
+    let daemon = Daemon::bind(ServeConfig {
+        listen: "127.0.0.1:0".to_string(),
+        storage_dir: dir.join("jobs"),
+        max_jobs: 1,
+        queue_depth: 16,
+        preset: ParameterPreset::Default,
+        trajectory: dir.join("trajectory.bin"),
+        object_store: None,
+        quotas: QuotaConfig::default(),
+        tls: None,
+        blob_store: Default::default(),
+        dedup_blobs: false,
+        health_listen: None,
+        prewarm_keys: Vec::new(),
+        drain_timeout_s: 30,
+    })
+    .await?;
+    let addr = daemon.local_addr()?;
+    tokio::spawn(daemon.run());
+
+    let mut client = Client::connect(addr).await?;
+    let benchmark = client.benchmark().await?;
+    assert_eq!(benchmark.step_time, None);
+    assert!(benchmark.bytes_per_second > 0.0);
+
+    let (_job, tuning) = client
+        .open_tuned_session(&SessionMetadata::default())
+        .await?;
+    assert_eq!(tuning.batch_steps, STREAM_BATCH_STEPS);
+    assert!((MIN_CHUNK_BYTES..=MAX_CHUNK_BYTES).contains(&tuning.chunk_bytes));
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
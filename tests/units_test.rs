@@ -0,0 +1,36 @@
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::units::{self, CANONICAL_UNITS, Units};
+
+/// Positions given in kilometers and in meters encode to the same canonical cells.
+#[test]
+fn test_encode_is_unit_aware() -> Result<(), Box<dyn std::error::Error>> {
+    let km = SatelliteData::encode(&[[6778.0, -12.5, 0.001]], Units::Kilometers, Frame::Eci)?;
+    let m = SatelliteData::encode(&[[6_778_000.0, -12_500.0, 1.0]], Units::Meters, Frame::Eci)?;
+    assert_eq!(km.units, CANONICAL_UNITS);
+    assert_eq!((km.x[0], km.y[0], km.z[0]), (m.x[0], m.y[0], m.z[0]));
+    assert_eq!(units::decode(km.y[0]), -12_500.0);
+    Ok(())
+}
+
+/// Rescaling keeps positions consistent and rounds when going to a coarser unit.
+#[test]
+fn test_rescale() -> Result<(), Box<dyn std::error::Error>> {
+    let m = SatelliteData::encode(&[[6_778_400.0, -1_600.0, 0.0]], Units::Meters, Frame::Eci)?;
+    let km = m.to_units(Units::Kilometers)?;
+    assert_eq!(km.units, Units::Kilometers);
+    assert_eq!(units::decode(km.x[0]), 6778.0);
+    assert_eq!(units::decode(km.y[0]), -2.0);
+
+    let back = km.to_units(Units::Meters)?;
+    assert_eq!(units::decode(back.x[0]), 6_778_000.0);
+    Ok(())
+}
+
+/// Values outside the fixed-point range are rejected instead of wrapping.
+#[test]
+fn test_encode_out_of_range() {
+    assert!(units::encode(3.0e6, Units::Kilometers).is_err());
+    let far = units::encode(3.0e6, Units::Meters).unwrap();
+    assert!(units::rescale(far, Units::Kilometers, Units::Meters).is_err());
+}
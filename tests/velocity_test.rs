@@ -0,0 +1,48 @@
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::units::Units;
+use sat_trajectory_fhe::velocity::{relative_speed_m_s, relative_velocity};
+
+/// Relative velocity at a flagged step: A moves +10 in x, B moves +4 in x and +8 in y, so
+/// the relative displacement over the 60 s step is (6, -8, 0), length 10.
+#[tokio::test]
+async fn test_relative_velocity_at_flagged_step() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 110, 120],
+        y: vec![200, 200, 200],
+        z: vec![300, 300, 300],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let sat2 = SatelliteData {
+        x: vec![100, 104, 500],
+        y: vec![200, 208, 500],
+        z: vec![300, 300, 500],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?.with_epochs(vec![0, 60, 120])?;
+
+    let output = context.evaluate_with(|| relative_velocity(&encrypted, &sat2, &[0]))?;
+    assert_eq!(output.steps, vec![0]);
+    assert_eq!(output.dt_s, vec![60]);
+
+    let squared: u64 = context.decrypt(&output.squared_displacement)?[0];
+    assert_eq!(squared, 100);
+    let speed = relative_speed_m_s(squared, output.dt_s[0], encrypted.units);
+    assert!((speed - 10.0 / 60.0).abs() < 1e-12);
+
+    // The last step has no successor to difference against.
+    assert!(
+        context
+            .evaluate_with(|| relative_velocity(&encrypted, &sat2, &[2]))
+            .is_err()
+    );
+
+    Ok(())
+}
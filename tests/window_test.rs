@@ -0,0 +1,60 @@
+use tfhe::ConfigBuilder;
+
+use sat_trajectory_fhe::common::SatelliteData;
+use sat_trajectory_fhe::context::FheContext;
+use sat_trajectory_fhe::frame::Frame;
+use sat_trajectory_fhe::screening::{ScreeningConfig, screen_exact};
+use sat_trajectory_fhe::trajectory::EncryptedTrajectory;
+use sat_trajectory_fhe::units::Units;
+
+/// Screening a time window of an encrypted trajectory only evaluates the steps inside it,
+/// and the results map back to the absolute steps and epochs.
+#[tokio::test]
+async fn test_window_screening() -> Result<(), Box<dyn std::error::Error>> {
+    let sat1 = SatelliteData {
+        x: vec![100, 101, 102, 103, 104],
+        y: vec![200, 201, 202, 203, 204],
+        z: vec![300, 301, 302, 303, 304],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    // Collides with sat1 at steps 0 and 3.
+    let sat2 = SatelliteData {
+        x: vec![100, 401, 402, 103, 404],
+        y: vec![200, 501, 502, 203, 504],
+        z: vec![300, 601, 602, 303, 604],
+        frame: Frame::Eci,
+        units: Units::Meters,
+    };
+    let epochs = vec![1_000, 1_060, 1_120, 1_180, 1_240];
+
+    let context = FheContext::generate(ConfigBuilder::default().build())?;
+    let encrypted = context.encrypt(&sat1)?.with_epochs(epochs)?;
+
+    // Steps 2 and 3 only.
+    let window = encrypted.window(1_100, 1_200);
+    assert_eq!(window.len(), 2);
+    assert_eq!(window.first_index, 2);
+    assert_eq!(window.epochs, vec![1_120, 1_180]);
+
+    // The window survives serialization with its bookkeeping.
+    let window = EncryptedTrajectory::from_bytes(&window.to_bytes()?)?;
+    assert_eq!(window.first_index, 2);
+
+    let output =
+        context.evaluate_with(|| screen_exact(&window, &sat2, &ScreeningConfig::default()))?;
+    assert_eq!(output.ops.comparisons, 6);
+
+    let flags: Vec<bool> = context.decrypt(&output.results)?;
+    let hits: Vec<(usize, u64)> = (0..flags.len())
+        .filter(|&i| flags[i])
+        .map(|i| (window.absolute_index(i), window.epochs[i]))
+        .collect();
+    assert_eq!(hits, vec![(3, 1_180)]);
+
+    // Empty and out-of-range windows are fine.
+    assert!(encrypted.window(5_000, 6_000).is_empty());
+    assert!(encrypted.window(1_200, 1_100).is_empty());
+
+    Ok(())
+}